@@ -0,0 +1,87 @@
+/**
+ * A2Aメッセージバス
+ * Rust側の a2aMessages（受信箱・返信・返信期限・履歴）を操作する
+ * 送信とタイムアウトは Tauri イベント `a2a-message` で通知される
+ */
+
+import type { A2AMessage, A2AMessageType } from './types';
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Rust側に保存されたA2Aメッセージ
+ */
+export interface StoredA2AMessage extends A2AMessage {
+  responseStatus?: 'pending' | 'answered' | 'timed_out';
+  responseDeadline?: string;
+  readAt?: string;
+  createdAt: string;
+}
+
+/**
+ * 送信するメッセージ
+ */
+export interface SendA2AMessageInput {
+  id?: string;
+  from: string;
+  to: string;
+  type: A2AMessageType;
+  taskId?: string;
+  payload: any;
+  responseTo?: string;
+  requiresResponse?: boolean;
+  responseTimeoutMs?: number;
+}
+
+/**
+ * メッセージを送信（送信先Agentの受信箱に入る）
+ */
+export async function sendA2AMessage(message: SendA2AMessageInput): Promise<StoredA2AMessage> {
+  return await invoke('send_a2a_message_command', { message });
+}
+
+/**
+ * メッセージに返信（送信先・taskId は元のメッセージから引き継ぐ）
+ */
+export async function replyA2AMessage(messageId: string, from: string, payload: any): Promise<StoredA2AMessage> {
+  return await invoke('reply_a2a_message_command', { messageId, from, payload });
+}
+
+/**
+ * メッセージを取得
+ */
+export async function getA2AMessage(messageId: string): Promise<StoredA2AMessage | null> {
+  return await invoke('get_a2a_message_command', { messageId });
+}
+
+/**
+ * 受信箱を取得（既読にはしない）
+ */
+export async function getA2AInbox(agentId: string, unreadOnly = false, limit?: number): Promise<StoredA2AMessage[]> {
+  return await invoke('get_a2a_inbox_command', { agentId, unreadOnly, limit });
+}
+
+/**
+ * 未読のメッセージを受信（取得したメッセージは既読になる）
+ */
+export async function receiveA2AMessages(agentId: string, limit?: number): Promise<StoredA2AMessage[]> {
+  return await invoke('receive_a2a_messages_command', { agentId, limit });
+}
+
+/**
+ * 返信を待つ（期限までに返信がなければ null）
+ */
+export async function waitForA2AResponse(messageId: string, timeoutMs?: number): Promise<StoredA2AMessage | null> {
+  return await invoke('wait_for_a2a_response_command', { messageId, timeoutMs });
+}
+
+/**
+ * 履歴を検索（古い順）
+ */
+export async function getA2AHistory(query: {
+  taskId?: string;
+  agentId?: string;
+  type?: A2AMessageType;
+  limit?: number;
+}): Promise<StoredA2AMessage[]> {
+  return await invoke('get_a2a_history_command', { query });
+}
@@ -63,11 +63,12 @@ export async function loadAgent(agentId: string): Promise<Agent | null> {
       selectedModel: agentData.selectedModel || undefined,
       systemPrompt: agentData.systemPrompt,
       config: agentData.config ? JSON.parse(agentData.config) : {},
+      promptVersion: agentData.promptVersion ?? undefined,
       createdAt: typeof agentData.createdAt === 'string' 
-        ? parseInt(agentData.createdAt, 10) * 1000 
+        ? new Date(agentData.createdAt).getTime() 
         : agentData.createdAt,
       updatedAt: typeof agentData.updatedAt === 'string' 
-        ? parseInt(agentData.updatedAt, 10) * 1000 
+        ? new Date(agentData.updatedAt).getTime() 
         : agentData.updatedAt,
     };
 
@@ -99,11 +100,12 @@ export async function loadAllAgents(): Promise<Agent[]> {
         selectedModel: agentData.selectedModel || undefined,
         systemPrompt: agentData.systemPrompt,
         config: agentData.config ? JSON.parse(agentData.config) : {},
+        promptVersion: agentData.promptVersion ?? undefined,
         createdAt: typeof agentData.createdAt === 'string' 
-          ? parseInt(agentData.createdAt, 10) * 1000 
+          ? new Date(agentData.createdAt).getTime() 
           : agentData.createdAt,
         updatedAt: typeof agentData.updatedAt === 'string' 
-          ? parseInt(agentData.updatedAt, 10) * 1000 
+          ? new Date(agentData.updatedAt).getTime() 
           : agentData.updatedAt,
       };
     });
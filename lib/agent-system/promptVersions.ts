@@ -0,0 +1,73 @@
+/**
+ * Agentのシステムプロンプトのバージョン管理
+ * Agentの保存時にシステムプロンプトが変わると新しいバージョンが作成される（Rust側の agent_prompt_versions）
+ * バージョン一覧・差分・ロールバックと、タスクのバージョン固定を行う
+ */
+
+import type { Agent, Task } from './types';
+import { loadAgent } from './agentStorage';
+import { getTask } from './taskManager';
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * システムプロンプトのバージョン
+ */
+export interface AgentPromptVersion {
+  id: string;
+  agentId: string;
+  version: number;
+  systemPrompt: string;
+  /** 変更内容のメモ（ロールバック時は「v2 に戻しました」など） */
+  note?: string;
+  createdBy?: string;
+  createdAt: string;
+}
+
+/**
+ * 2つのバージョンの差分（行単位）
+ */
+export interface PromptDiff {
+  agentId: string;
+  fromVersion: number;
+  toVersion: number;
+  lines: { op: 'equal' | 'insert' | 'delete'; text: string }[];
+  added: number;
+  removed: number;
+}
+
+/**
+ * バージョン一覧を取得（新しい順）
+ */
+export async function getAgentPromptVersions(agentId: string): Promise<AgentPromptVersion[]> {
+  return await invoke('get_agent_prompt_versions_command', { agentId });
+}
+
+/**
+ * バージョンを取得
+ */
+export async function getAgentPromptVersion(agentId: string, version: number): Promise<AgentPromptVersion | null> {
+  return await invoke('get_agent_prompt_version_command', { agentId, version });
+}
+
+/**
+ * 2つのバージョンの差分を取得
+ */
+export async function diffAgentPromptVersions(agentId: string, fromVersion: number, toVersion: number): Promise<PromptDiff> {
+  return await invoke('diff_agent_prompt_versions_command', { agentId, fromVersion, toVersion });
+}
+
+/**
+ * システムプロンプトを指定したバージョンに戻す（新しいバージョンとして保存される）
+ */
+export async function rollbackAgentPrompt(agentId: string, version: number): Promise<Agent | null> {
+  await invoke('rollback_agent_prompt_command', { agentId, version });
+  return await loadAgent(agentId);
+}
+
+/**
+ * タスクをバージョンに固定する（version を省略すると固定を解除し、Agentの現在のバージョンで実行する）
+ */
+export async function setTaskPromptVersion(taskId: string, version?: number): Promise<Task | null> {
+  await invoke('set_task_prompt_version_command', { taskId, version: version ?? null });
+  return await getTask(taskId);
+}
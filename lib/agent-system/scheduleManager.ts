@@ -0,0 +1,109 @@
+/**
+ * スケジュール管理機能
+ * タスク・タスクチェーンの自動実行（cron式・指定日時・データの変更）のCRUD操作と実行記録の取得
+ * 実行はRust側のスケジューラーが行い、Tauri イベント `schedule-triggered` で通知される
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * スケジュール
+ */
+export interface Schedule {
+  id: string;
+  name: string;
+  description?: string;
+  targetType: 'task' | 'chain';
+  targetId: string;
+  triggerType: 'cron' | 'once' | 'event';
+  /** 分 時 日 月 曜日（ローカルタイムゾーン）。例: "0 2 * * *"（毎日2時） */
+  cronExpression?: string;
+  /** 指定日時（RFC 3339） */
+  runAt?: string;
+  /** 変更を監視するテーブル。例: "meetingNotes" */
+  eventTable?: string;
+  /** create / update / delete / restore / purge（省略時はすべて） */
+  eventOperation?: string;
+  /** 変更後の行と比較する値。例: { organizationId: 'org-1' } */
+  eventFilter?: Record<string, any>;
+  catchUpPolicy: 'skip' | 'run_once' | 'run_all';
+  enabled: boolean;
+  nextRunAt?: string;
+  lastRunAt?: string;
+  lastError?: string;
+  createdAt: string;
+  updatedAt: string;
+}
+
+/**
+ * スケジュールの実行記録
+ */
+export interface ScheduleRun {
+  id: string;
+  scheduleId: string;
+  reason: 'schedule' | 'catch_up' | 'event' | 'manual';
+  status: 'triggered' | 'skipped' | 'failed';
+  scheduledFor?: string;
+  executionId?: string;
+  chainRunId?: string;
+  eventSeq?: number;
+  error?: string;
+  createdAt: string;
+}
+
+function fromRust(schedule: any): Schedule {
+  return {
+    ...schedule,
+    eventFilter: schedule.eventFilter ? JSON.parse(schedule.eventFilter) : undefined,
+  };
+}
+
+/**
+ * スケジュールを保存（次回の実行時刻はRust側で計算される）
+ */
+export async function saveSchedule(schedule: Schedule): Promise<Schedule> {
+  const saved = await invoke('save_schedule_command', {
+    schedule: {
+      ...schedule,
+      eventFilter: schedule.eventFilter ? JSON.stringify(schedule.eventFilter) : undefined,
+    },
+  });
+  return fromRust(saved);
+}
+
+/**
+ * スケジュールを取得
+ */
+export async function getSchedule(scheduleId: string): Promise<Schedule | null> {
+  const schedule = await invoke('get_schedule_command', { scheduleId });
+  return schedule ? fromRust(schedule) : null;
+}
+
+/**
+ * すべてのスケジュールを取得
+ */
+export async function getAllSchedules(): Promise<Schedule[]> {
+  const schedules: any[] = await invoke('get_all_schedules_command');
+  return schedules.map(fromRust);
+}
+
+/**
+ * スケジュールを削除
+ */
+export async function deleteSchedule(scheduleId: string): Promise<void> {
+  await invoke('delete_schedule_command', { scheduleId });
+}
+
+/**
+ * スケジュールの実行記録を取得（新しい順）
+ */
+export async function getScheduleRuns(scheduleId: string, limit?: number): Promise<ScheduleRun[]> {
+  return await invoke('get_schedule_runs_command', { scheduleId, limit });
+}
+
+/**
+ * スケジュールを今すぐ実行
+ */
+export async function runScheduleNow(scheduleId: string): Promise<ScheduleRun> {
+  return await invoke('run_schedule_now_command', { scheduleId });
+}
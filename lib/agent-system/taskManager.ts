@@ -11,12 +11,12 @@ import { invoke } from '@tauri-apps/api/core';
  * タスクを保存
  */
 export async function saveTask(task: Task): Promise<void> {
-  // ミリ秒のタイムスタンプを秒単位の文字列に変換
+  // ミリ秒のタイムスタンプを RFC 3339 形式の文字列に変換
   const createdAtStr = typeof task.createdAt === 'number' 
-    ? Math.floor(task.createdAt / 1000).toString() 
+    ? new Date(task.createdAt).toISOString() 
     : task.createdAt.toString();
   const updatedAtStr = typeof task.updatedAt === 'number' 
-    ? Math.floor(task.updatedAt / 1000).toString() 
+    ? new Date(task.updatedAt).toISOString() 
     : task.updatedAt.toString();
 
   // typeフィールドを文字列に変換（enumの場合）
@@ -50,12 +50,12 @@ export async function getTask(taskId: string): Promise<Task | null> {
   const task: any = await invoke('get_task_command', { taskId });
   if (!task) return null;
 
-  // 秒単位の文字列タイムスタンプをミリ秒の数値に変換
+  // RFC 3339 形式の文字列タイムスタンプをミリ秒の数値に変換
   const createdAt = typeof task.createdAt === 'string' 
-    ? parseInt(task.createdAt, 10) * 1000 
+    ? new Date(task.createdAt).getTime() 
     : task.createdAt;
   const updatedAt = typeof task.updatedAt === 'string' 
-    ? parseInt(task.updatedAt, 10) * 1000 
+    ? new Date(task.updatedAt).getTime() 
     : task.updatedAt;
 
   return {
@@ -74,12 +74,12 @@ export async function getTask(taskId: string): Promise<Task | null> {
 export async function getAllTasks(): Promise<Task[]> {
   const tasks: any[] = await invoke('get_all_tasks_command');
   return tasks.map(task => {
-    // 秒単位の文字列タイムスタンプをミリ秒の数値に変換
+    // RFC 3339 形式の文字列タイムスタンプをミリ秒の数値に変換
     const createdAt = typeof task.createdAt === 'string' 
-      ? parseInt(task.createdAt, 10) * 1000 
+      ? new Date(task.createdAt).getTime() 
       : task.createdAt;
     const updatedAt = typeof task.updatedAt === 'string' 
-      ? parseInt(task.updatedAt, 10) * 1000 
+      ? new Date(task.updatedAt).getTime() 
       : task.updatedAt;
 
     return {
@@ -151,6 +151,26 @@ export async function getAllTaskExecutions(): Promise<TaskExecution[]> {
   }));
 }
 
+/**
+ * タスクをRust側の実行エンジンで実行（ウィンドウを閉じても実行は続く）
+ * 進捗は Tauri イベント `task-execution-progress` で通知される
+ */
+export async function enqueueTaskExecution(taskId: string): Promise<TaskExecution> {
+  const execution: any = await invoke('enqueue_task_execution_command', { taskId });
+  return {
+    ...execution,
+    result: execution.result ? JSON.parse(execution.result) : undefined,
+    logs: execution.logs ? JSON.parse(execution.logs) : [],
+  };
+}
+
+/**
+ * Rust側の実行エンジンで実行中・実行待ちのタスク実行をキャンセル
+ */
+export async function cancelTaskExecution(executionId: string): Promise<void> {
+  await invoke('cancel_task_execution_command', { executionId });
+}
+
 /**
  * タスクチェーンを保存
  */
@@ -194,8 +214,9 @@ export async function getTaskChain(chainId: string): Promise<TaskChain | null> {
     description: chain.description,
     startNodeId: chain.startNodeId,
     nodes,
-    createdAt: chain.createdAt,
-    updatedAt: chain.updatedAt,
+    // RFC 3339 形式の文字列タイムスタンプをミリ秒の数値に変換
+    createdAt: new Date(chain.createdAt).getTime(),
+    updatedAt: new Date(chain.updatedAt).getTime(),
   };
 }
 
@@ -218,8 +239,8 @@ export async function getAllTaskChains(): Promise<TaskChain[]> {
       description: chain.description,
       startNodeId: chain.startNodeId,
       nodes,
-      createdAt: chain.createdAt,
-      updatedAt: chain.updatedAt,
+      createdAt: new Date(chain.createdAt).getTime(),
+      updatedAt: new Date(chain.updatedAt).getTime(),
     };
   });
 }
@@ -231,3 +252,104 @@ export async function deleteTaskChain(chainId: string): Promise<void> {
   await invoke('delete_task_chain_command', { chainId });
 }
 
+
+/**
+ * タスクチェーンの検証結果
+ */
+export interface TaskChainValidation {
+  valid: boolean;
+  errors: { nodeId?: string; message: string }[];
+  warnings: { nodeId?: string; message: string }[];
+}
+
+/**
+ * Rust側で実行したタスクチェーンの実行
+ */
+export interface TaskChainRun {
+  id: string;
+  chainId: string;
+  status: 'running' | 'completed' | 'failed' | 'cancelled';
+  error?: string;
+  startedAt: string;
+  completedAt?: string;
+  createdAt: string;
+  updatedAt: string;
+}
+
+/**
+ * タスクチェーンの実行で通過したノード
+ */
+export interface TaskChainRunStep {
+  id: string;
+  runId: string;
+  nodeId: string;
+  nodeType: string;
+  cursorId: string;
+  executionId?: string;
+  status: string;
+  result?: any;
+  startedAt: string;
+  completedAt?: string;
+}
+
+/**
+ * タスクチェーンを検証（保存前のチェーンも検証できる）
+ */
+export async function validateTaskChain(chain: TaskChain): Promise<TaskChainValidation> {
+  const nodesObj: Record<string, any> = {};
+  chain.nodes.forEach((node, key) => {
+    nodesObj[key] = node;
+  });
+
+  return await invoke('validate_task_chain_command', {
+    chain: {
+      id: chain.id,
+      name: chain.name,
+      description: chain.description,
+      startNodeId: chain.startNodeId,
+      nodes: JSON.stringify(nodesObj),
+      createdAt: chain.createdAt,
+      updatedAt: chain.updatedAt,
+    },
+  });
+}
+
+/**
+ * 保存済みのタスクチェーンをRust側で実行（ウィンドウを閉じても実行は続き、再起動後は続きから再開する）
+ * 進捗は Tauri イベント `task-chain-progress` で通知される
+ */
+export async function runTaskChain(chainId: string): Promise<TaskChainRun> {
+  return await invoke('run_task_chain_command', { chainId });
+}
+
+/**
+ * タスクチェーンの実行をキャンセル
+ */
+export async function cancelTaskChainRun(runId: string): Promise<void> {
+  await invoke('cancel_task_chain_run_command', { runId });
+}
+
+/**
+ * タスクチェーンの実行を取得
+ */
+export async function getTaskChainRun(runId: string): Promise<TaskChainRun | null> {
+  return await invoke('get_task_chain_run_command', { runId });
+}
+
+/**
+ * タスクチェーンの実行履歴を取得（新しい順）
+ */
+export async function getTaskChainRuns(chainId: string): Promise<TaskChainRun[]> {
+  return await invoke('get_task_chain_runs_command', { chainId });
+}
+
+/**
+ * タスクチェーンの実行で通過したノードを取得
+ */
+export async function getTaskChainRunSteps(runId: string): Promise<TaskChainRunStep[]> {
+  const steps: any[] = await invoke('get_task_chain_run_steps_command', { runId });
+  return steps.map((step) => ({
+    ...step,
+    result: step.result ? JSON.parse(step.result) : undefined,
+  }));
+}
@@ -30,6 +30,7 @@ export interface Task {
   retryCount?: number;           // リトライ回数
   modelType?: 'gpt' | 'local' | 'cursor'; // 使用するLLMタイプ（オプション、AgentのmodelTypeを優先）
   selectedModel?: string;        // 選択されたモデル名（オプション、AgentのmodelTypeを優先）
+  promptVersion?: number;        // 固定したシステムプロンプトのバージョン（未指定時はAgentの現在のバージョン）
   createdAt: number;             // 作成日時
   updatedAt: number;             // 更新日時
 }
@@ -68,6 +69,7 @@ export interface TaskExecution {
   result?: any;                  // 実行結果
   error?: string;                // エラーメッセージ
   logs: ExecutionLog[];          // 実行ログ
+  promptVersion?: number;        // 実行に使用したシステムプロンプトのバージョン
 }
 
 /**
@@ -114,6 +116,7 @@ export interface Agent {
   selectedModel?: string;        // 選択されたLLMモデル（gpt-4.1-mini, qwen:7bなど）
   systemPrompt: string;           // システムプロンプト
   config: AgentConfig;           // Agent設定
+  promptVersion?: number;        // 現在のシステムプロンプトのバージョン（保存時に設定される）
   createdAt: number;             // 作成日時
   updatedAt: number;             // 更新日時
 }
@@ -0,0 +1,289 @@
+/**
+ * REST APIの認証・認可
+ * `Authorization: Bearer <token>` ヘッダーのトークンを検証し、スコープを確認する
+ *
+ * - `mai_` で始まるトークンはAPIトークン（apiTokensテーブル、スコープはトークンごと）
+ *   - 持ち主が存在しない・承認されていないトークンは無効とし、スコープは持ち主の現在のロールの範囲に制限する
+ * - それ以外はセッショントークン（sessionsテーブル、ユーザーのロールからスコープを決定）
+ *   - viewer: read / editor: read, write / admin: read, write, admin
+ * - 認証されていない場合は 401、スコープが不足している場合は 403 をJSONで返す
+ */
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{header::AUTHORIZATION, request::Parts, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::{json, Value};
+
+use crate::database::{
+    get_approved_user_by_id, get_user_role, validate_api_token, validate_session, with_user,
+    ApiScope, Role, User, API_TOKEN_PREFIX,
+};
+
+/// 認証不要のパス
+const PUBLIC_PATHS: &[&str] = &["/health", "/api/auth/sign-in"];
+
+/// POSTでもデータを変更しないパス（readスコープで実行可能）
+const READ_ONLY_POST_PATHS: &[&str] = &["/api/search"];
+
+/// adminスコープが必要なパスのプレフィックス
+const ADMIN_PATH_PREFIXES: &[&str] = &["/api/api-tokens", "/api/admin"];
+
+/// GETでもwriteスコープが必要なパスのプレフィックス（削除済みデータを扱うため）
+const WRITE_PATH_PREFIXES: &[&str] = &["/api/trash"];
+
+/// 認証済みの呼び出し元
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// 呼び出し元のユーザー（APIトークンの場合はトークンの持ち主）
+    pub user: User,
+    pub scopes: Vec<ApiScope>,
+    /// APIトークンで認証した場合のトークンID
+    pub api_token_id: Option<String>,
+}
+
+impl Principal {
+    pub fn has_scope(&self, required: ApiScope) -> bool {
+        self.scopes.iter().any(|s| s.grants(required))
+    }
+}
+
+/// 認証済みユーザー（APIトークンの場合はトークンの持ち主）
+#[derive(Debug, Clone)]
+pub struct AuthUser(pub User);
+
+/// Authorizationヘッダーからベアラートークンを取得
+pub fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer ").or_else(|| v.strip_prefix("bearer ")))
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message, "status": status.as_u16() }))).into_response()
+}
+
+/// リクエストに必要なスコープを決定
+pub fn required_scope(method: &Method, path: &str) -> ApiScope {
+    if ADMIN_PATH_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+        return ApiScope::Admin;
+    }
+    if WRITE_PATH_PREFIXES.iter().any(|prefix| path.starts_with(prefix)) {
+        return ApiScope::Write;
+    }
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => ApiScope::Read,
+        Method::POST if READ_ONLY_POST_PATHS.contains(&path) => ApiScope::Read,
+        _ => ApiScope::Write,
+    }
+}
+
+/// APIトークンのスコープを持ち主のロールで許可される範囲に制限
+/// （発行後に持ち主のロールが変更された場合でも、そのロールを超える操作はできない）
+pub fn effective_scopes(token_scopes: &[ApiScope], owner_role: Role) -> Vec<ApiScope> {
+    let allowed = owner_role.api_scopes();
+    token_scopes.iter().copied().filter(|s| allowed.contains(s)).collect()
+}
+
+/// トークンから呼び出し元を解決（無効なトークンの場合は None）
+fn resolve_principal(token: &str) -> Result<Option<Principal>, String> {
+    if token.starts_with(API_TOKEN_PREFIX) {
+        let api_token = match validate_api_token(token).map_err(|e| e.to_string())? {
+            Some(api_token) => api_token,
+            None => return Ok(None),
+        };
+        // 持ち主のいないトークン、持ち主が削除された・承認されていないトークンは無効とする
+        let Some(user_id) = api_token.user_id.as_deref() else {
+            eprintln!("⚠️ [require_auth] 持ち主のいないAPIトークンを拒否しました: id={}", api_token.id);
+            return Ok(None);
+        };
+        let user = match get_approved_user_by_id(user_id).map_err(|e| e.to_string())? {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+        let role = get_user_role(&user.uid).map_err(|e| e.to_string())?;
+        return Ok(Some(Principal {
+            user,
+            scopes: effective_scopes(&api_token.scopes, role),
+            api_token_id: Some(api_token.id),
+        }));
+    }
+
+    let user = match validate_session(token).map_err(|e| e.to_string())? {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+    let scopes = get_user_role(&user.uid).map_err(|e| e.to_string())?.api_scopes();
+    Ok(Some(Principal {
+        user,
+        scopes,
+        api_token_id: None,
+    }))
+}
+
+/// 認証・認可ミドルウェア
+/// 公開パス以外はトークンを必須とし、メソッドとパスから決まるスコープを確認する
+pub async fn require_auth(mut request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    if request.method() == Method::OPTIONS || PUBLIC_PATHS.contains(&path.as_str()) {
+        return next.run(request).await;
+    }
+
+    let token = match bearer_token(request.headers()) {
+        Some(token) => token,
+        None => {
+            return error_response(
+                StatusCode::UNAUTHORIZED,
+                "認証が必要です。Authorization: Bearer <token> を指定してください",
+            );
+        }
+    };
+
+    let principal = match resolve_principal(&token) {
+        Ok(Some(principal)) => principal,
+        Ok(None) => {
+            return error_response(StatusCode::UNAUTHORIZED, "トークンが無効か、有効期限が切れています");
+        }
+        Err(e) => {
+            eprintln!("❌ [require_auth] トークンの検証に失敗しました: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "トークンの検証に失敗しました");
+        }
+    };
+
+    let required = required_scope(request.method(), &path);
+    if !principal.has_scope(required) {
+        eprintln!(
+            "⚠️ [require_auth] スコープ不足: {} {} (必要: {})",
+            request.method(), path, required.as_str()
+        );
+        return error_response(
+            StatusCode::FORBIDDEN,
+            &format!("この操作には {} スコープが必要です", required.as_str()),
+        );
+    }
+
+    let user = principal.user.clone();
+    request.extensions_mut().insert(principal);
+    request.extensions_mut().insert(AuthUser(user.clone()));
+    with_user(user, next.run(request)).await
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthUser
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or_else(|| (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "error": "認証が必要です", "status": 401 }))
+            ))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Principal
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Principal>()
+            .cloned()
+            .ok_or_else(|| (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "error": "認証が必要です", "status": 401 }))
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{create_api_token, create_session, init_test_database, insert_test_user};
+
+    #[test]
+    fn required_scope_depends_on_method_and_path() {
+        assert_eq!(required_scope(&Method::GET, "/api/organizations"), ApiScope::Read);
+        assert_eq!(required_scope(&Method::POST, "/api/organizations"), ApiScope::Write);
+        assert_eq!(required_scope(&Method::DELETE, "/api/organizations/1"), ApiScope::Write);
+        assert_eq!(required_scope(&Method::POST, "/api/search"), ApiScope::Read);
+        // 埋め込み生成はプロバイダーのAPIを呼び出すため、POSTでもwriteスコープが必要
+        assert_eq!(required_scope(&Method::POST, "/api/embeddings"), ApiScope::Write);
+        assert_eq!(required_scope(&Method::GET, "/api/trash"), ApiScope::Write);
+        assert_eq!(required_scope(&Method::GET, "/api/api-tokens"), ApiScope::Admin);
+        assert_eq!(required_scope(&Method::POST, "/api/admin/users/1/role"), ApiScope::Admin);
+    }
+
+    #[test]
+    fn effective_scopes_are_capped_by_the_owner_role() {
+        let all = [ApiScope::Read, ApiScope::Write, ApiScope::Admin];
+        assert_eq!(effective_scopes(&all, Role::Viewer), vec![ApiScope::Read]);
+        assert_eq!(effective_scopes(&all, Role::Editor), vec![ApiScope::Read, ApiScope::Write]);
+        assert_eq!(effective_scopes(&all, Role::Admin), all.to_vec());
+        assert_eq!(effective_scopes(&[ApiScope::Read], Role::Admin), vec![ApiScope::Read]);
+    }
+
+    #[test]
+    fn api_token_scopes_follow_the_owner_role() {
+        let owner = insert_test_user(Role::Admin, true);
+        let issued = create_api_token("test", &[ApiScope::Admin], &owner.uid, None).unwrap();
+
+        let principal = resolve_principal(&issued.token).unwrap().unwrap();
+        assert_eq!(principal.user.uid, owner.uid);
+        assert!(principal.has_scope(ApiScope::Admin));
+
+        // 持ち主が降格されると、トークンのスコープもロールの範囲に制限される
+        let conn = init_test_database().get_connection().unwrap();
+        conn.execute("UPDATE users SET role = 'viewer' WHERE id = ?1", [&owner.uid]).unwrap();
+        let principal = resolve_principal(&issued.token).unwrap().unwrap();
+        assert!(principal.has_scope(ApiScope::Read));
+        assert!(!principal.has_scope(ApiScope::Write));
+    }
+
+    #[test]
+    fn rejects_api_tokens_without_an_approved_owner() {
+        let conn = init_test_database().get_connection().unwrap();
+
+        let deleted = insert_test_user(Role::Admin, true);
+        let issued = create_api_token("deleted owner", &[ApiScope::Admin], &deleted.uid, None).unwrap();
+        conn.execute("DELETE FROM users WHERE id = ?1", [&deleted.uid]).unwrap();
+        assert!(resolve_principal(&issued.token).unwrap().is_none());
+
+        let unapproved = insert_test_user(Role::Editor, false);
+        let issued = create_api_token("unapproved owner", &[ApiScope::Write], &unapproved.uid, None).unwrap();
+        assert!(resolve_principal(&issued.token).unwrap().is_none());
+
+        // 持ち主のいない（userId が NULL の）トークンも無効
+        let issued = create_api_token("ownerless", &[ApiScope::Admin], &deleted.uid, None).unwrap();
+        conn.execute("UPDATE apiTokens SET userId = NULL WHERE id = ?1", [&issued.info.id]).unwrap();
+        assert!(resolve_principal(&issued.token).unwrap().is_none());
+    }
+
+    #[test]
+    fn session_tokens_get_the_scopes_of_the_user_role() {
+        let editor = insert_test_user(Role::Editor, true);
+        let session = create_session(&editor).unwrap();
+        let principal = resolve_principal(&session.token).unwrap().unwrap();
+        assert_eq!(principal.scopes, vec![ApiScope::Read, ApiScope::Write]);
+        assert!(principal.api_token_id.is_none());
+
+        assert!(resolve_principal("not-a-session-token").unwrap().is_none());
+        assert!(resolve_principal("mai_not-an-api-token").unwrap().is_none());
+    }
+}
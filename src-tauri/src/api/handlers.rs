@@ -1,21 +1,54 @@
 use axum::{
     extract::{Path, Query, Json as AxumJson},
     response::Json,
+    response::sse::{Event, KeepAlive, Sse},
     http::StatusCode,
 };
 use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::convert::Infallible;
+use tokio_stream::wrappers::ReceiverStream;
 
+use crate::db::{count_dead_letter_jobs, write_queue, write_queue_metrics, WriteJob};
 use crate::database::{
     get_organization_by_id, create_organization as db_create_organization, 
     update_organization as db_update_organization, delete_organization as db_delete_organization,
     get_organizations_by_parent_id, get_organization_tree as db_get_organization_tree, 
     search_organizations_by_name,
     get_members_by_organization_id, add_member, update_member, delete_member,
-    get_all_themes, get_theme_by_id, save_theme as db_save_theme, create_theme as db_create_theme, delete_theme as db_delete_theme,
+    get_all_themes, get_theme_by_id, new_theme, get_current_user,
     Theme as DbTheme,
-    get_doc, set_doc, update_doc, delete_doc, get_collection,
+    get_doc, set_doc, update_doc, delete_doc, get_collection, query_collection, CollectionQuery,
+    hybrid_search as db_hybrid_search, SearchTarget,
+    get_chroma_sync_progress, trigger_chroma_sync,
+    subscribe_changes, list_changes, latest_change_seq, parse_resume_token, CHANGE_PAGE_SIZE,
+    generate_embedding, resolve_embedding, check_embedding_model,
+    get_all_companies, get_company_by_id, get_company_by_code as db_get_company_by_code,
+    get_companies_by_organization_id, create_company as db_create_company,
+    update_company as db_update_company, update_company_positions as db_update_company_positions,
+    delete_company as db_delete_company, export_companies_to_csv,
+    query_audit_log, export_audit_log_to_csv, AuditLogQuery,
+    list_trash, restore_from_trash, purge_trash_item as db_purge_trash_item, remove_purged_embeddings,
+    create_organization_company_display as db_create_organization_company_display,
+    get_all_organization_company_displays as db_get_all_organization_company_displays,
+    get_companies_by_organization_display as db_get_companies_by_organization_display,
+    get_organizations_by_company_display as db_get_organizations_by_company_display,
+    update_organization_company_display_order as db_update_organization_company_display_order,
+    delete_organization_company_display as db_delete_organization_company_display,
+    delete_organization_company_display_by_ids as db_delete_organization_company_display_by_ids,
+    send_a2a_message, reply_a2a_message, get_a2a_message, get_a2a_inbox, receive_a2a_messages,
+    get_a2a_history, wait_for_a2a_response, SendA2AMessage, A2AHistoryQuery, DEFAULT_RESPONSE_TIMEOUT_MS,
+    store_business_plan_file, get_business_plan_file, get_business_plan_files,
+    delete_business_plan_file, permanently_delete_business_plan_file, get_creation_history, PlanType,
 };
+use crate::database::vector_store::get_vector_store;
+use crate::database::{
+    sign_in as db_sign_in, sign_out as db_sign_out,
+    create_api_token as db_create_api_token, list_api_tokens, revoke_api_token as db_revoke_api_token,
+    ApiScope,
+    get_user_role, list_approval_requests, approve_user, reject_user, list_users, set_user_role, Role,
+};
+use crate::api::auth::{bearer_token, AuthUser};
 
 // ヘルスチェック
 pub async fn health_check() -> Json<Value> {
@@ -25,6 +58,338 @@ pub async fn health_check() -> Json<Value> {
     }))
 }
 
+// 認証関連ハンドラー
+pub async fn sign_in(
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let email = payload.get("email")
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "email is required" }))
+        ))?;
+    let password = payload.get("password")
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "password is required" }))
+        ))?;
+
+    match db_sign_in(email, password) {
+        Ok(result) => Ok(Json(json!({
+            "user": result.user,
+            "token": result.session.token,
+            "expiresAt": result.session.expires_at,
+        }))),
+        Err(rusqlite::Error::QueryReturnedNoRows) | Err(rusqlite::Error::InvalidQuery) => Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "メールアドレスまたはパスワードが正しくありません" }))
+        )),
+        Err(rusqlite::Error::SqliteFailure(err, _))
+            if err.code == rusqlite::ErrorCode::AuthorizationForStatementDenied => Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "このアカウントは管理者の承認待ちか、登録が却下されています" }))
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("ログインに失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn sign_out(
+    headers: axum::http::HeaderMap,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let token = bearer_token(&headers)
+        .ok_or_else(|| (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "認証が必要です" }))
+        ))?;
+
+    match db_sign_out(&token) {
+        Ok(_) => Ok(Json(json!({ "message": "サインアウトしました" }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("サインアウトに失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn get_me(
+    AuthUser(user): AuthUser,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let role = get_user_role(&user.uid).map_err(|e| (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("ユーザーロールの取得に失敗しました: {}", e) }))
+    ))?;
+    let mut value = serde_json::to_value(user).unwrap();
+    value["role"] = json!(role);
+    Ok(Json(value))
+}
+
+// APIトークン管理ハンドラー
+pub async fn get_api_tokens() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match list_api_tokens() {
+        Ok(tokens) => Ok(Json(serde_json::to_value(tokens).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("APIトークン一覧の取得に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn create_api_token(
+    AuthUser(user): AuthUser,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let name = payload.get("name")
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "name is required" }))
+        ))?;
+    let scopes = payload.get("scopes")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "scopes is required (read / write / admin)" }))
+        ))?
+        .iter()
+        .map(|v| v.as_str().and_then(ApiScope::from_str))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "scopes must be read / write / admin" }))
+        ))?;
+    let expires_in_days = payload.get("expiresInDays").and_then(|v| v.as_i64());
+
+    match db_create_api_token(&name, &scopes, &user.uid, expires_in_days) {
+        Ok(issued) => Ok(Json(serde_json::to_value(issued).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("APIトークンの発行に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn revoke_api_token(
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match db_revoke_api_token(&id) {
+        Ok(_) => Ok(Json(json!({ "message": "APIトークンを失効しました" }))),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "APIトークンが見つからないか、既に失効しています" }))
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("APIトークンの失効に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+// 利用者の承認・ロール管理ハンドラー（adminスコープが必要）
+fn parse_role_param(payload: &HashMap<String, Value>) -> Result<Option<Role>, (StatusCode, Json<Value>)> {
+    match payload.get("role").and_then(|v| v.as_str()) {
+        None => Ok(None),
+        Some(role) => Role::from_str(role)
+            .filter(|_| role.trim() != "user")
+            .map(Some)
+            .ok_or_else(|| (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "role must be admin / editor / viewer" }))
+            )),
+    }
+}
+
+fn approval_error(e: rusqlite::Error, action: &str) -> (StatusCode, Json<Value>) {
+    match e {
+        rusqlite::Error::QueryReturnedNoRows => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "承認リクエストが見つかりません" }))
+        ),
+        rusqlite::Error::SqliteFailure(err, Some(message))
+            if err.code == rusqlite::ErrorCode::ConstraintViolation => (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": message }))
+        ),
+        e => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("{}に失敗しました: {}", action, e) }))
+        ),
+    }
+}
+
+pub async fn get_approval_requests(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let status = params.get("status").map(|s| s.as_str()).unwrap_or("pending");
+    let status = if status == "all" { None } else { Some(status) };
+
+    match list_approval_requests(status) {
+        Ok(requests) => Ok(Json(serde_json::to_value(requests).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("承認リクエスト一覧の取得に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn approve_approval_request(
+    AuthUser(admin): AuthUser,
+    Path(id): Path<String>,
+    payload: Option<AxumJson<HashMap<String, Value>>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let payload = payload.map(|AxumJson(p)| p).unwrap_or_default();
+    let role = parse_role_param(&payload)?;
+
+    match approve_user(&admin, &id, role) {
+        Ok(request) => Ok(Json(serde_json::to_value(request).unwrap())),
+        Err(e) => Err(approval_error(e, "利用者の承認")),
+    }
+}
+
+pub async fn reject_approval_request(
+    AuthUser(admin): AuthUser,
+    Path(id): Path<String>,
+    payload: Option<AxumJson<HashMap<String, Value>>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let payload = payload.map(|AxumJson(p)| p).unwrap_or_default();
+    let reason = payload.get("reason").and_then(|v| v.as_str());
+
+    match reject_user(&admin, &id, reason) {
+        Ok(request) => Ok(Json(serde_json::to_value(request).unwrap())),
+        Err(e) => Err(approval_error(e, "利用者の却下")),
+    }
+}
+
+pub async fn get_users() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match list_users() {
+        Ok(users) => Ok(Json(serde_json::to_value(users).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("利用者一覧の取得に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn update_user_role(
+    Path(id): Path<String>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let role = parse_role_param(&payload)?
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "role is required (admin / editor / viewer)" }))
+        ))?;
+
+    match set_user_role(&id, role) {
+        Ok(user) => Ok(Json(serde_json::to_value(user).unwrap())),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "利用者が見つかりません" }))
+        )),
+        Err(e) => Err(approval_error(e, "ロールの変更")),
+    }
+}
+
+// 書き込みキューのメトリクスハンドラー（adminスコープが必要）
+pub async fn get_write_queue_metrics() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match count_dead_letter_jobs() {
+        Ok(dead_letters) => {
+            let mut metrics = serde_json::to_value(write_queue_metrics().snapshot()).unwrap();
+            metrics["deadLetters"] = json!(dead_letters);
+            Ok(Json(metrics))
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("デッドレター件数の取得に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+// 監査ログハンドラー（adminスコープが必要）
+pub async fn get_audit_log(
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match query_audit_log(&query) {
+        Ok(entries) => Ok(Json(serde_json::to_value(entries).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("監査ログの取得に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn export_audit_log_csv(
+    Query(query): Query<AuditLogQuery>,
+) -> Result<([(axum::http::header::HeaderName, &'static str); 2], String), (StatusCode, Json<Value>)> {
+    match export_audit_log_to_csv(&query) {
+        Ok(csv) => Ok((
+            [
+                (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (axum::http::header::CONTENT_DISPOSITION, "attachment; filename=\"audit-log.csv\""),
+            ],
+            csv,
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("監査ログのエクスポートに失敗しました: {}", e) }))
+        ))
+    }
+}
+
+// ゴミ箱ハンドラー
+fn trash_error(e: rusqlite::Error, action: &str) -> (StatusCode, Json<Value>) {
+    match e {
+        rusqlite::Error::QueryReturnedNoRows => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "ゴミ箱の項目が見つかりません" }))
+        ),
+        rusqlite::Error::SqliteFailure(err, Some(message))
+            if err.code == rusqlite::ErrorCode::ConstraintViolation => (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": message }))
+        ),
+        e => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("{}に失敗しました: {}", action, e) }))
+        ),
+    }
+}
+
+pub async fn get_trash(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let item_type = params.get("itemType").map(|s| s.as_str());
+
+    match list_trash(item_type) {
+        Ok(items) => Ok(Json(serde_json::to_value(items).unwrap())),
+        Err(e) => Err(trash_error(e, "ゴミ箱の取得")),
+    }
+}
+
+pub async fn restore_trash_item(
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match restore_from_trash(&id) {
+        Ok(item) => Ok(Json(serde_json::to_value(item).unwrap())),
+        Err(e) => Err(trash_error(e, "ゴミ箱からの復元")),
+    }
+}
+
+pub async fn purge_trash_item(
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match db_purge_trash_item(&id) {
+        Ok(purged) => {
+            remove_purged_embeddings(purged).await;
+            Ok(Json(json!({ "message": "ゴミ箱の項目を完全削除しました" })))
+        }
+        Err(e) => Err(trash_error(e, "ゴミ箱の項目の完全削除")),
+    }
+}
+
 // 組織関連ハンドラー
 pub async fn get_organizations(
     Query(params): Query<HashMap<String, String>>,
@@ -111,7 +476,7 @@ pub async fn delete_organization(
     Path(id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     match db_delete_organization(&id) {
-        Ok(_) => Ok(Json(json!({ "message": "組織を削除しました" }))),
+        Ok(_) => Ok(Json(json!({ "message": "組織をゴミ箱に移動しました" }))),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({ "error": format!("組織の削除に失敗しました: {}", e) }))
@@ -268,16 +633,10 @@ pub async fn delete_organization_member(
     }
 }
 
-// 事業会社関連ハンドラー（Companiesテーブル削除のため無効化）
+// 事業会社関連ハンドラー（organizationsテーブルのtype='company'）
 pub async fn get_companies(
     Query(_params): Query<HashMap<String, String>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // Companiesテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "Companiesテーブルは削除されました。このAPIは使用できません。" }))
-    ))
-    /* 以下は無効化されたコード
     match get_all_companies() {
         Ok(companies) => {
             let companies_json: Vec<Value> = companies.into_iter()
@@ -290,48 +649,47 @@ pub async fn get_companies(
             Json(json!({ "error": format!("事業会社の取得に失敗しました: {}", e) }))
         ))
     }
-    */
 }
 
 pub async fn get_company(
-    Path(_id): Path<String>,
+    Path(id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // Companiesテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "Companiesテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match get_company_by_id(&id) {
+        Ok(company) => Ok(Json(serde_json::to_value(company).unwrap())),
+        Err(e) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("事業会社の取得に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn get_company_by_code(
-    Path(_code): Path<String>,
+    Path(code): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // Companiesテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "Companiesテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match db_get_company_by_code(&code) {
+        Ok(company) => Ok(Json(serde_json::to_value(company).unwrap())),
+        Err(e) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("事業会社の取得に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn get_companies_by_organization(
-    Path(_org_id): Path<String>,
+    Path(org_id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // Companiesテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "Companiesテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match get_companies_by_organization_id(&org_id) {
+        Ok(companies) => Ok(Json(serde_json::to_value(companies).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業会社の取得に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn create_company(
-    AxumJson(_payload): AxumJson<HashMap<String, Value>>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // Companiesテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "Companiesテーブルは削除されました。このAPIは使用できません。" }))
-    ))
-    /* 以下は無効化されたコード
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     let code = payload.get("code")
         .and_then(|v| v.as_str().map(|s| s.to_string()))
         .ok_or_else(|| (
@@ -362,11 +720,11 @@ pub async fn create_company(
     let department = payload.get("department").and_then(|v| v.as_str().map(|s| s.to_string()));
     let region = payload.get("region")
         .and_then(|v| v.as_str().map(|s| s.to_string()))
-        .unwrap_or_else(|| "JP".to_string());
+        .unwrap_or_else(|| "国内".to_string());
     let position = payload.get("position")
         .and_then(|v| v.as_i64().map(|i| i as i32))
         .unwrap_or(0);
-    
+
     match db_create_company(code, name, name_short, category, organization_id, company, division, department, region, position) {
         Ok(company) => Ok(Json(serde_json::to_value(company).unwrap())),
         Err(e) => Err((
@@ -374,19 +732,12 @@ pub async fn create_company(
             Json(json!({ "error": format!("事業会社の作成に失敗しました: {}", e) }))
         ))
     }
-    */
 }
 
 pub async fn update_company(
-    Path(_id): Path<String>,
-    AxumJson(_payload): AxumJson<HashMap<String, Value>>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // Companiesテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "Companiesテーブルは削除されました。このAPIは使用できません。" }))
-    ))
-    /* 以下は無効化されたコード
+    Path(id): Path<String>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     let code = payload.get("code").and_then(|v| v.as_str().map(|s| s.to_string()));
     let name = payload.get("name").and_then(|v| v.as_str().map(|s| s.to_string()));
     let name_short = payload.get("name_short").and_then(|v| v.as_str().map(|s| s.to_string()));
@@ -397,7 +748,7 @@ pub async fn update_company(
     let department = payload.get("department").and_then(|v| v.as_str().map(|s| s.to_string()));
     let region = payload.get("region").and_then(|v| v.as_str().map(|s| s.to_string()));
     let position = payload.get("position").and_then(|v| v.as_i64().map(|i| i as i32));
-    
+
     match db_update_company(&id, code, name, name_short, category, organization_id, company, division, department, region, position) {
         Ok(company) => Ok(Json(serde_json::to_value(company).unwrap())),
         Err(e) => Err((
@@ -405,21 +756,65 @@ pub async fn update_company(
             Json(json!({ "error": format!("事業会社の更新に失敗しました: {}", e) }))
         ))
     }
-    */
+}
+
+// [{ "id": "...", "position": 1 }, ...] の形式で表示順序を一括更新
+pub async fn update_company_positions(
+    AxumJson(payload): AxumJson<Vec<HashMap<String, Value>>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let updates: Vec<(String, i32)> = payload.iter()
+        .filter_map(|item| {
+            let id = item.get("id").and_then(|v| v.as_str())?;
+            let position = item.get("position").and_then(|v| v.as_i64())?;
+            Some((id.to_string(), position as i32))
+        })
+        .collect();
+
+    match db_update_company_positions(&updates) {
+        Ok(_) => Ok(Json(json!({ "message": "事業会社の表示順序を更新しました", "updated": updates.len() }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業会社の表示順序の更新に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn export_companies_csv() -> Result<([(axum::http::header::HeaderName, &'static str); 2], String), (StatusCode, Json<Value>)> {
+    match export_companies_to_csv() {
+        Ok(csv) => Ok((
+            [
+                (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (axum::http::header::CONTENT_DISPOSITION, "attachment; filename=\"companies.csv\""),
+            ],
+            csv,
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業会社のエクスポートに失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn delete_company(
-    Path(_id): Path<String>,
+    Path(id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // Companiesテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "Companiesテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match db_delete_company(&id) {
+        Ok(_) => Ok(Json(json!({ "message": "事業会社を削除しました" }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業会社の削除に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 // リレーション関連ハンドラー
-pub async fn get_relations() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+pub async fn get_relations(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    // クエリパラメータがある場合は構造化クエリでページ単位に返す
+    if !params.is_empty() {
+        return query_collection_page("relations", &params, "リレーションの取得");
+    }
     match get_collection("relations", None) {
         Ok(relations) => {
             // 各リレーションのデータを整形
@@ -439,6 +834,31 @@ pub async fn get_relations() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     }
 }
 
+/// クエリパラメータ（where, orderBy, limit, cursor, select, カラム名=値）でコレクションを検索し { items, nextCursor } を返す
+fn query_collection_page(
+    collection_name: &str,
+    params: &HashMap<String, String>,
+    action: &str,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let query = CollectionQuery::from_params(params).map_err(|e| (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": e }))
+    ))?;
+    match query_collection(collection_name, &query) {
+        Ok(page) => Ok(Json(json!({ "items": page.items, "nextCursor": page.next_cursor }))),
+        // 無効なフィールド名・演算子・値などはリクエストの誤り
+        Err(rusqlite::Error::SqliteFailure(err, Some(message)))
+            if matches!(err.code, rusqlite::ErrorCode::ApiMisuse | rusqlite::ErrorCode::TypeMismatch) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": message }))
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("{}に失敗しました: {}", action, e) }))
+        ))
+    }
+}
+
 pub async fn get_relation(
     Path(id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
@@ -537,33 +957,17 @@ pub async fn delete_relation(
 }
 
 // エンティティ関連ハンドラー
-pub async fn get_entities() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+pub async fn get_entities(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    // クエリパラメータがある場合は構造化クエリでページ単位に返す
+    if !params.is_empty() {
+        return query_collection_page("entities", &params, "エンティティの取得");
+    }
     match get_collection("entities", None) {
         Ok(entities) => {
-            // 各エンティティのデータを整形
-            let formatted: Vec<Value> = entities.into_iter().map(|mut item| {
-                // idフィールドを追加
-                if let Some(id) = item.remove("id") {
-                    item.insert("id".to_string(), id);
-                }
-                // aliasesとmetadataをJSON文字列からオブジェクトに変換
-                if let Some(aliases) = item.get("aliases") {
-                    if let Some(aliases_str) = aliases.as_str() {
-                        if let Ok(parsed) = serde_json::from_str::<Value>(aliases_str) {
-                            item.insert("aliases".to_string(), parsed);
-                        }
-                    }
-                }
-                if let Some(metadata) = item.get("metadata") {
-                    if let Some(metadata_str) = metadata.as_str() {
-                        if let Ok(parsed) = serde_json::from_str::<Value>(metadata_str) {
-                            item.insert("metadata".to_string(), parsed);
-                        }
-                    }
-                }
-                json!(item)
-            }).collect();
-            Ok(Json(json!(formatted)))
+            // aliasesとmetadataはカラム型に従ってオブジェクトに変換済み
+            Ok(Json(json!(entities)))
         },
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -578,21 +982,6 @@ pub async fn get_entity(
     match get_doc("entities", &id) {
         Ok(mut entity) => {
             entity.insert("id".to_string(), json!(id));
-            // aliasesとmetadataをJSON文字列からオブジェクトに変換
-            if let Some(aliases) = entity.get("aliases") {
-                if let Some(aliases_str) = aliases.as_str() {
-                    if let Ok(parsed) = serde_json::from_str::<Value>(aliases_str) {
-                        entity.insert("aliases".to_string(), parsed);
-                    }
-                }
-            }
-            if let Some(metadata) = entity.get("metadata") {
-                if let Some(metadata_str) = metadata.as_str() {
-                    if let Ok(parsed) = serde_json::from_str::<Value>(metadata_str) {
-                        entity.insert("metadata".to_string(), parsed);
-                    }
-                }
-            }
             Ok(Json(json!(entity)))
         },
         Err(e) => {
@@ -613,7 +1002,7 @@ pub async fn get_entity(
 }
 
 pub async fn create_entity(
-    AxumJson(mut payload): AxumJson<HashMap<String, Value>>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     // IDを生成（payloadにidがない場合）
     let id = payload.get("id")
@@ -626,43 +1015,12 @@ pub async fn create_entity(
             uuid::Uuid::new_v4().to_string().chars().take(8).collect::<String>()
         ));
     
-    // aliasesとmetadataをJSON文字列に変換（データベースではTEXT型として保存）
-    if let Some(aliases) = payload.get("aliases") {
-        if aliases.is_array() || aliases.is_object() {
-            if let Ok(json_str) = serde_json::to_string(aliases) {
-                payload.insert("aliases".to_string(), json!(json_str));
-            }
-        }
-    }
-    if let Some(metadata) = payload.get("metadata") {
-        if metadata.is_object() {
-            if let Ok(json_str) = serde_json::to_string(metadata) {
-                payload.insert("metadata".to_string(), json!(json_str));
-            }
-        }
-    }
-    
     match set_doc("entities", &id, payload) {
         Ok(_) => {
             // 作成したエンティティを取得して返す
             match get_doc("entities", &id) {
                 Ok(mut entity) => {
                     entity.insert("id".to_string(), json!(id));
-                    // aliasesとmetadataをJSON文字列からオブジェクトに変換
-                    if let Some(aliases) = entity.get("aliases") {
-                        if let Some(aliases_str) = aliases.as_str() {
-                            if let Ok(parsed) = serde_json::from_str::<Value>(aliases_str) {
-                                entity.insert("aliases".to_string(), parsed);
-                            }
-                        }
-                    }
-                    if let Some(metadata) = entity.get("metadata") {
-                        if let Some(metadata_str) = metadata.as_str() {
-                            if let Ok(parsed) = serde_json::from_str::<Value>(metadata_str) {
-                                entity.insert("metadata".to_string(), parsed);
-                            }
-                        }
-                    }
                     Ok(Json(json!(entity)))
                 },
                 Err(e) => Err((
@@ -680,45 +1038,14 @@ pub async fn create_entity(
 
 pub async fn update_entity(
     Path(id): Path<String>,
-    AxumJson(mut payload): AxumJson<HashMap<String, Value>>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // aliasesとmetadataをJSON文字列に変換（データベースではTEXT型として保存）
-    if let Some(aliases) = payload.get("aliases") {
-        if aliases.is_array() || aliases.is_object() {
-            if let Ok(json_str) = serde_json::to_string(aliases) {
-                payload.insert("aliases".to_string(), json!(json_str));
-            }
-        }
-    }
-    if let Some(metadata) = payload.get("metadata") {
-        if metadata.is_object() {
-            if let Ok(json_str) = serde_json::to_string(metadata) {
-                payload.insert("metadata".to_string(), json!(json_str));
-            }
-        }
-    }
-    
     match update_doc("entities", &id, payload) {
         Ok(_) => {
             // 更新したエンティティを取得して返す
             match get_doc("entities", &id) {
                 Ok(mut entity) => {
                     entity.insert("id".to_string(), json!(id));
-                    // aliasesとmetadataをJSON文字列からオブジェクトに変換
-                    if let Some(aliases) = entity.get("aliases") {
-                        if let Some(aliases_str) = aliases.as_str() {
-                            if let Ok(parsed) = serde_json::from_str::<Value>(aliases_str) {
-                                entity.insert("aliases".to_string(), parsed);
-                            }
-                        }
-                    }
-                    if let Some(metadata) = entity.get("metadata") {
-                        if let Some(metadata_str) = metadata.as_str() {
-                            if let Ok(parsed) = serde_json::from_str::<Value>(metadata_str) {
-                                entity.insert("metadata".to_string(), parsed);
-                            }
-                        }
-                    }
                     Ok(Json(json!(entity)))
                 },
                 Err(e) => Err((
@@ -749,72 +1076,107 @@ pub async fn delete_entity(
 // 組織と事業会社の表示関係ハンドラー
 
 pub async fn create_organization_company_display(
-    AxumJson(_payload): AxumJson<HashMap<String, Value>>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // organizationCompanyDisplayテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "organizationCompanyDisplayテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    let organization_id = payload.get("organization_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "organization_id is required" }))
+        ))?;
+    let company_id = payload.get("company_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "company_id is required" }))
+        ))?;
+    let display_order = payload.get("display_order").and_then(|v| v.as_i64().map(|i| i as i32));
+
+    match db_create_organization_company_display(organization_id, company_id, display_order) {
+        Ok(display) => Ok(Json(serde_json::to_value(display).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("表示関係の作成に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn get_all_organization_company_displays() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // organizationCompanyDisplayテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "organizationCompanyDisplayテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match db_get_all_organization_company_displays() {
+        Ok(displays) => Ok(Json(serde_json::to_value(displays).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("表示関係の取得に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn get_companies_by_organization_display(
-    Path(_org_id): Path<String>,
+    Path(org_id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // organizationCompanyDisplayテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "organizationCompanyDisplayテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match db_get_companies_by_organization_display(&org_id) {
+        Ok(displays) => Ok(Json(serde_json::to_value(displays).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("表示関係の取得に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn get_organizations_by_company_display(
-    Path(_company_id): Path<String>,
+    Path(company_id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // organizationCompanyDisplayテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "organizationCompanyDisplayテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match db_get_organizations_by_company_display(&company_id) {
+        Ok(displays) => Ok(Json(serde_json::to_value(displays).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("表示関係の取得に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn update_organization_company_display_order(
-    Path(_id): Path<String>,
-    AxumJson(_payload): AxumJson<HashMap<String, Value>>,
+    Path(id): Path<String>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // organizationCompanyDisplayテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "organizationCompanyDisplayテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    let display_order = payload.get("display_order")
+        .and_then(|v| v.as_i64().map(|i| i as i32))
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "display_order is required" }))
+        ))?;
+
+    match db_update_organization_company_display_order(&id, display_order) {
+        Ok(_) => Ok(Json(json!({ "message": "表示順序を更新しました" }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("表示順序の更新に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn delete_organization_company_display(
-    Path(_id): Path<String>,
+    Path(id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // organizationCompanyDisplayテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "organizationCompanyDisplayテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match db_delete_organization_company_display(&id) {
+        Ok(_) => Ok(Json(json!({ "message": "表示関係を削除しました" }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("表示関係の削除に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 pub async fn delete_organization_company_display_by_ids(
-    Path((_org_id, _company_id)): Path<(String, String)>,
+    Path((org_id, company_id)): Path<(String, String)>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    // organizationCompanyDisplayテーブル削除のため、エラーを返す
-    Err((
-        StatusCode::GONE,
-        Json(json!({ "error": "organizationCompanyDisplayテーブルは削除されました。このAPIは使用できません。" }))
-    ))
+    match db_delete_organization_company_display_by_ids(&org_id, &company_id) {
+        Ok(_) => Ok(Json(json!({ "message": "表示関係を削除しました" }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("表示関係の削除に失敗しました: {}", e) }))
+        ))
+    }
 }
 
 // テーマ関連ハンドラー
@@ -862,11 +1224,32 @@ pub async fn create_theme(
     let description = payload.get("description")
         .and_then(|v| v.as_str().map(|s| s.to_string()));
 
-    match db_create_theme(title, description) {
-        Ok(theme) => Ok(Json(serde_json::to_value(theme).unwrap())),
-        Err(e) => Err((
+    let theme = new_theme(title, description);
+    let theme_id = theme.id.clone();
+    save_theme_via_queue(theme).await
+        .map_err(|e| (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({ "error": format!("テーマの作成に失敗しました: {}", e) }))
+        ))?;
+    get_saved_theme(&theme_id)
+}
+
+/// テーマを書き込みキュー経由で保存（実行者はリクエストのユーザー）
+async fn save_theme_via_queue(theme: DbTheme) -> Result<(), String> {
+    write_queue()?.send(WriteJob::SaveTheme { theme }, get_current_user()).await
+}
+
+/// 保存したテーマを取得して返す
+fn get_saved_theme(theme_id: &str) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match get_theme_by_id(theme_id) {
+        Ok(Some(theme)) => Ok(Json(serde_json::to_value(theme).unwrap())),
+        Ok(None) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "テーマの保存後に取得に失敗しました" }))
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("テーマの取得に失敗しました: {}", e) }))
         ))
     }
 }
@@ -904,19 +1287,22 @@ pub async fn update_theme(
         updated_at: None,
     };
 
-    match db_save_theme(&theme) {
-        Ok(updated_theme) => Ok(Json(serde_json::to_value(updated_theme).unwrap())),
-        Err(e) => Err((
+    save_theme_via_queue(theme).await
+        .map_err(|e| (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({ "error": format!("テーマの更新に失敗しました: {}", e) }))
-        ))
-    }
+        ))?;
+    get_saved_theme(&id)
 }
 
 pub async fn delete_theme_handler(
     Path(id): Path<String>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    match db_delete_theme(&id) {
+    let result = match write_queue() {
+        Ok(queue) => queue.send(WriteJob::DeleteTheme { theme_id: id }, get_current_user()).await,
+        Err(e) => Err(e),
+    };
+    match result {
         Ok(_) => Ok(Json(json!({ "message": "テーマを削除しました" }))),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -924,3 +1310,450 @@ pub async fn delete_theme_handler(
         ))
     }
 }
+
+// ハイブリッド検索ハンドラー
+pub async fn hybrid_search(
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let target = payload.get("type")
+        .and_then(|v| v.as_str())
+        .and_then(SearchTarget::from_str)
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "type is required (entity / relation / topic)" }))
+        ))?;
+    let query = payload.get("query")
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "query is required" }))
+        ))?;
+    let query_embedding = payload.get("queryEmbedding")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|item| item.as_f64().map(|f| f as f32)).collect::<Vec<f32>>());
+    let limit = payload.get("limit")
+        .and_then(|v| v.as_u64().map(|n| n as usize))
+        .unwrap_or(10);
+    let organization_id = payload.get("organizationId")
+        .and_then(|v| v.as_str().map(|s| s.to_string()));
+
+    match db_hybrid_search(target, query, query_embedding, limit, organization_id).await {
+        Ok(results) => Ok(Json(serde_json::to_value(results).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("ハイブリッド検索に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+// ベクトルストア再同期関連ハンドラー
+pub async fn get_chroma_sync_status() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    Ok(Json(serde_json::to_value(get_chroma_sync_progress()).unwrap()))
+}
+
+pub async fn run_chroma_sync() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    trigger_chroma_sync();
+    Ok(Json(json!({ "success": true })))
+}
+
+// 変更フィード
+/// データの変更を Server-Sent Events で配信
+/// collections（カンマ区切り）で対象テーブルを絞り込む。Last-Event-ID ヘッダーまたは since の再開トークンを指定した場合は、
+/// その後の変更（切断中の変更を含む）から送る。指定しない場合は接続後の変更のみ送る
+pub async fn stream_changes(
+    headers: axum::http::HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Sse<ReceiverStream<Result<Event, Infallible>>>, (StatusCode, Json<Value>)> {
+    let collections: Option<Vec<String>> = params.get("collections").map(|value| {
+        value.split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    });
+    let token = headers.get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .or_else(|| params.get("since").cloned());
+
+    let mut last_seq = match token {
+        Some(token) => parse_resume_token(&token).ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("無効な再開トークンです: {}", token) }))
+        ))?,
+        None => latest_change_seq().map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("変更フィードの取得に失敗しました: {}", e) }))
+        ))?,
+    };
+
+    let mut latest = subscribe_changes();
+    let (tx, rx) = tokio::sync::mpsc::channel(CHANGE_PAGE_SIZE as usize);
+    tokio::spawn(async move {
+        loop {
+            // 最後に送った変更より後の変更をすべて送る
+            let changes = match list_changes(last_seq, collections.as_deref(), CHANGE_PAGE_SIZE) {
+                Ok(changes) => changes,
+                Err(e) => {
+                    let event = Event::default().event("error").data(format!("変更の取得に失敗しました: {}", e));
+                    let _ = tx.send(Ok(event)).await;
+                    return;
+                }
+            };
+            let count = changes.len() as i64;
+            for change in changes {
+                last_seq = change.seq;
+                let event = Event::default()
+                    .id(change.seq.to_string())
+                    .event("change")
+                    .data(serde_json::to_string(&change).unwrap_or_default());
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+            if count == CHANGE_PAGE_SIZE {
+                continue;
+            }
+
+            // 新しい変更が通知されるか、クライアントが切断するまで待つ
+            tokio::select! {
+                changed = latest.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                }
+                _ = tx.closed() => return,
+            }
+        }
+    });
+
+    Ok(Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default()))
+}
+
+// 埋め込み関連ハンドラー
+pub async fn create_embedding(
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let text = payload.get("text")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "text is required" }))
+        ))?;
+
+    match generate_embedding(text).await {
+        Ok(embedding) => Ok(Json(json!({
+            "embedding": embedding,
+            "dimension": embedding.len(),
+        }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("埋め込みの生成に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn get_embedding_model() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match check_embedding_model().await {
+        Ok(info) => Ok(Json(serde_json::to_value(info).unwrap())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("埋め込みモデルの確認に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+// テキスト（または埋め込みベクトル）を受け取り、ベクトルストアへ保存する
+pub async fn index_embedding(
+    Path((target, id)): Path<(String, String)>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let target = SearchTarget::from_str(&target)
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "type must be one of entity / relation / topic" }))
+        ))?;
+    let organization_id = payload.get("organizationId")
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "organizationId is required" }))
+        ))?;
+    let text = payload.get("text")
+        .and_then(|v| v.as_str().map(|s| s.to_string()));
+    let embedding = payload.get("embedding")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|item| item.as_f64().map(|f| f as f32)).collect::<Vec<f32>>());
+    let metadata: HashMap<String, Value> = payload.get("metadata")
+        .and_then(|v| v.as_object())
+        .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+
+    let embedding = resolve_embedding(embedding, text).await
+        .map_err(|e| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": e }))
+        ))?;
+
+    let store = get_vector_store()
+        .map_err(|e| (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": e }))
+        ))?;
+    let result = match target {
+        SearchTarget::Entity => store.save_entity_embedding(id.clone(), organization_id, embedding, metadata).await,
+        SearchTarget::Relation => store.save_relation_embedding(id.clone(), organization_id, embedding, metadata).await,
+        SearchTarget::Topic => {
+            let meeting_note_id = payload.get("meetingNoteId")
+                .and_then(|v| v.as_str().map(|s| s.to_string()))
+                .ok_or_else(|| (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": "meetingNoteId is required for topic" }))
+                ))?;
+            store.save_topic_embedding(id.clone(), meeting_note_id, organization_id, embedding, metadata).await
+        }
+    };
+
+    match result {
+        Ok(_) => Ok(Json(json!({ "success": true, "id": id }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("埋め込みの保存に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+// A2A（Agent間）メッセージハンドラー
+fn a2a_error(e: String, action: &str) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": format!("{}に失敗しました: {}", action, e) }))
+    )
+}
+
+fn a2a_query_error(e: rusqlite::Error, action: &str) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": format!("{}に失敗しました: {}", action, e) }))
+    )
+}
+
+pub async fn send_a2a_message_handler(
+    AxumJson(message): AxumJson<SendA2AMessage>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match send_a2a_message(message) {
+        Ok(message) => Ok(Json(serde_json::to_value(message).unwrap())),
+        Err(e) => Err(a2a_error(e, "A2Aメッセージの送信")),
+    }
+}
+
+pub async fn get_a2a_message_handler(
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match get_a2a_message(&id) {
+        Ok(Some(message)) => Ok(Json(serde_json::to_value(message).unwrap())),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "A2Aメッセージが見つかりませんでした" }))
+        )),
+        Err(e) => Err(a2a_query_error(e, "A2Aメッセージの取得")),
+    }
+}
+
+// body: { "from": 返信するAgent ID, "payload": ... }
+pub async fn reply_a2a_message_handler(
+    Path(id): Path<String>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let from = payload.get("from")
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "from is required" }))
+        ))?;
+    let body = payload.get("payload").cloned().unwrap_or(Value::Null);
+
+    match reply_a2a_message(&id, &from, body) {
+        Ok(message) => Ok(Json(serde_json::to_value(message).unwrap())),
+        Err(e) => Err(a2a_error(e, "A2Aメッセージの返信")),
+    }
+}
+
+// ?timeoutMs= まで返信を待つ（返信がなければ response は null）
+pub async fn wait_for_a2a_response_handler(
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let timeout_ms = params.get("timeoutMs")
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_RESPONSE_TIMEOUT_MS);
+
+    match wait_for_a2a_response(&id, std::time::Duration::from_millis(timeout_ms)).await {
+        Ok(response) => Ok(Json(json!({ "response": response }))),
+        Err(e) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("A2Aメッセージの返信の待機に失敗しました: {}", e) }))
+        )),
+    }
+}
+
+pub async fn get_a2a_inbox_handler(
+    Path(agent_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let unread_only = params.get("unreadOnly").map(|s| s == "true").unwrap_or(false);
+    let limit = params.get("limit").and_then(|s| s.parse::<i64>().ok()).unwrap_or(100);
+
+    match get_a2a_inbox(&agent_id, unread_only, limit) {
+        Ok(messages) => Ok(Json(serde_json::to_value(messages).unwrap())),
+        Err(e) => Err(a2a_query_error(e, "受信箱の取得")),
+    }
+}
+
+pub async fn receive_a2a_messages_handler(
+    Path(agent_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let limit = params.get("limit").and_then(|s| s.parse::<i64>().ok()).unwrap_or(100);
+
+    match receive_a2a_messages(&agent_id, limit) {
+        Ok(messages) => Ok(Json(serde_json::to_value(messages).unwrap())),
+        Err(e) => Err(a2a_query_error(e, "A2Aメッセージの受信")),
+    }
+}
+
+// ?taskId=&agentId=&type=&limit=
+pub async fn get_a2a_history_handler(
+    Query(query): Query<A2AHistoryQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match get_a2a_history(&query) {
+        Ok(messages) => Ok(Json(serde_json::to_value(messages).unwrap())),
+        Err(e) => Err(a2a_query_error(e, "A2Aメッセージ履歴の取得")),
+    }
+}
+
+// 事業計画ファイル関連ハンドラー
+pub async fn get_business_plan_files_handler(
+    Path(plan_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let include_deleted = params.get("includeDeleted").map(|v| v == "true").unwrap_or(false);
+
+    match get_business_plan_files(&plan_id, include_deleted) {
+        Ok(files) => Ok(Json(json!(files))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業計画ファイル一覧の取得に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+// アップロードされたファイル（fileData: バイト列）をアプリの保存ディレクトリにコピーして事業計画に添付する
+// 同一内容のファイルがあれば既存のファイルを返す
+pub async fn attach_business_plan_file_handler(
+    AuthUser(user): AuthUser,
+    Path(plan_id): Path<String>,
+    AxumJson(payload): AxumJson<HashMap<String, Value>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let plan_type = payload.get("planType")
+        .and_then(|v| v.as_str())
+        .filter(|s| PlanType::from_str(s).is_some())
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "planType is required (businessProject / servicePlan / concept)" }))
+        ))?;
+    let original_file_name = payload.get("originalFileName")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "originalFileName is required" }))
+        ))?;
+    let file_data: Vec<u8> = payload.get("fileData")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .ok_or_else(|| (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "fileData is required (array of bytes)" }))
+        ))?;
+    let mime_type = payload.get("mimeType").and_then(|v| v.as_str());
+    let description = payload.get("description").and_then(|v| v.as_str());
+    let category = payload.get("category").and_then(|v| v.as_str());
+
+    let (file_id, duplicate) = store_business_plan_file(
+        &user,
+        &plan_id,
+        plan_type,
+        original_file_name,
+        &file_data,
+        mime_type,
+        description,
+        category,
+    ).map_err(|e| (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({ "error": e }))
+    ))?;
+
+    match get_business_plan_file(&file_id) {
+        Ok(file) => Ok(Json(json!({ "duplicate": duplicate, "file": file }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業計画ファイルの取得に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn get_business_plan_file_handler(
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match get_business_plan_file(&id) {
+        Ok(file) => Ok(Json(json!(file))),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "事業計画ファイルが見つかりませんでした" }))
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業計画ファイルの取得に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+// ?permanent=true の場合は完全削除、それ以外は論理削除
+pub async fn delete_business_plan_file_handler(
+    AuthUser(user): AuthUser,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let permanent = params.get("permanent").map(|v| v == "true").unwrap_or(false);
+    let result = if permanent {
+        permanently_delete_business_plan_file(&user, &id)
+    } else {
+        delete_business_plan_file(&user, &id)
+    };
+
+    match result {
+        Ok(_) => Ok(Json(json!({ "message": "事業計画ファイルを削除しました", "permanent": permanent }))),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "事業計画ファイルが見つかりませんでした" }))
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業計画ファイルの削除に失敗しました: {}", e) }))
+        ))
+    }
+}
+
+pub async fn get_business_plan_history_handler(
+    Path(plan_id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let limit = params.get("limit").and_then(|v| v.parse::<i64>().ok());
+
+    match get_creation_history(&plan_id, limit) {
+        Ok(history) => Ok(Json(json!(history))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("事業計画作成履歴の取得に失敗しました: {}", e) }))
+        ))
+    }
+}
@@ -1,3 +1,4 @@
 pub mod server;
 pub mod handlers;
 pub mod routes;
+pub mod auth;
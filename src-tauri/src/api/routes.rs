@@ -10,6 +10,35 @@ pub fn create_routes() -> Router {
         // ヘルスチェック
         .route("/health", get(handlers::health_check))
         
+        // 認証API（セッショントークンの発行・失効）
+        .route("/api/auth/sign-in", post(handlers::sign_in))
+        .route("/api/auth/sign-out", post(handlers::sign_out))
+        .route("/api/auth/me", get(handlers::get_me))
+        
+        // APIトークン管理API（adminスコープが必要）
+        .route("/api/api-tokens", get(handlers::get_api_tokens))
+        .route("/api/api-tokens", post(handlers::create_api_token))
+        .route("/api/api-tokens/:id", delete(handlers::revoke_api_token))
+        
+        // 利用者の承認・ロール管理API（adminスコープが必要）
+        .route("/api/admin/approval-requests", get(handlers::get_approval_requests))
+        .route("/api/admin/approval-requests/:id/approve", post(handlers::approve_approval_request))
+        .route("/api/admin/approval-requests/:id/reject", post(handlers::reject_approval_request))
+        .route("/api/admin/users", get(handlers::get_users))
+        .route("/api/admin/users/:id/role", put(handlers::update_user_role))
+        
+        // 監査ログAPI（adminスコープが必要）
+        .route("/api/admin/audit-log", get(handlers::get_audit_log))
+        .route("/api/admin/audit-log/export/csv", get(handlers::export_audit_log_csv))
+        
+        // 書き込みキューのメトリクスAPI（adminスコープが必要）
+        .route("/api/admin/write-queue/metrics", get(handlers::get_write_queue_metrics))
+        
+        // ゴミ箱API（一覧・復元はwriteスコープ、完全削除はadminスコープが必要）
+        .route("/api/trash", get(handlers::get_trash))
+        .route("/api/trash/:id/restore", post(handlers::restore_trash_item))
+        .route("/api/admin/trash/:id", delete(handlers::purge_trash_item))
+        
         // 組織関連API
         .route("/api/organizations", get(handlers::get_organizations))
         .route("/api/organizations", post(handlers::create_organization))
@@ -26,6 +55,8 @@ pub fn create_routes() -> Router {
         // 事業会社関連API
         .route("/api/companies", get(handlers::get_companies))
         .route("/api/companies", post(handlers::create_company))
+        .route("/api/companies/positions", put(handlers::update_company_positions))
+        .route("/api/companies/export/csv", get(handlers::export_companies_csv))
         .route("/api/companies/:id", get(handlers::get_company))
         .route("/api/companies/:id", put(handlers::update_company))
         .route("/api/companies/:id", delete(handlers::delete_company))
@@ -55,6 +86,37 @@ pub fn create_routes() -> Router {
         .route("/api/entities/:id", put(handlers::update_entity))
         .route("/api/entities/:id", delete(handlers::delete_entity))
         
+        // ハイブリッド検索API
+        .route("/api/search", post(handlers::hybrid_search))
+        
+        // 埋め込み関連API（Rust側で埋め込みを生成）
+        .route("/api/embeddings", post(handlers::create_embedding))
+        .route("/api/embeddings/model", get(handlers::get_embedding_model))
+        .route("/api/embeddings/:type/:id", post(handlers::index_embedding))
+        
+        // ベクトルストア再同期API
+        .route("/api/chroma-sync/status", get(handlers::get_chroma_sync_status))
+        .route("/api/chroma-sync/run", post(handlers::run_chroma_sync))
+        
+        // 変更フィードAPI（Server-Sent Events）
+        .route("/api/changes", get(handlers::stream_changes))
+        
+        // A2A（Agent間）メッセージAPI
+        .route("/api/a2a/messages", post(handlers::send_a2a_message_handler))
+        .route("/api/a2a/messages/:id", get(handlers::get_a2a_message_handler))
+        .route("/api/a2a/messages/:id/reply", post(handlers::reply_a2a_message_handler))
+        .route("/api/a2a/messages/:id/response", get(handlers::wait_for_a2a_response_handler))
+        .route("/api/a2a/agents/:agent_id/inbox", get(handlers::get_a2a_inbox_handler))
+        .route("/api/a2a/agents/:agent_id/receive", post(handlers::receive_a2a_messages_handler))
+        .route("/api/a2a/history", get(handlers::get_a2a_history_handler))
+        
+        // 事業計画ファイル関連API
+        .route("/api/business-plans/:plan_id/files", get(handlers::get_business_plan_files_handler))
+        .route("/api/business-plans/:plan_id/files", post(handlers::attach_business_plan_file_handler))
+        .route("/api/business-plans/:plan_id/history", get(handlers::get_business_plan_history_handler))
+        .route("/api/business-plan-files/:id", get(handlers::get_business_plan_file_handler))
+        .route("/api/business-plan-files/:id", delete(handlers::delete_business_plan_file_handler))
+        
         // テーマ関連API
         .route("/api/themes", get(handlers::get_themes))
         .route("/api/themes", post(handlers::create_theme))
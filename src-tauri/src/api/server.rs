@@ -1,10 +1,43 @@
 use axum::Router;
 use std::net::SocketAddr;
-use tower_http::cors::{CorsLayer, Any};
+use axum::http::{header, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower::ServiceBuilder;
 
 use crate::database::get_db;
 
+/// デフォルトで許可するオリジン（Tauriのカスタムプロトコルと開発用のNext.jsサーバー）
+const DEFAULT_ALLOWED_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+    "http://localhost:3010",
+    "http://127.0.0.1:3010",
+];
+
+/// 許可するオリジンの一覧（環境変数 API_CORS_ALLOWED_ORIGINS でカンマ区切りで指定可能）
+fn allowed_origins() -> Vec<HeaderValue> {
+    let origins: Vec<String> = match std::env::var("API_CORS_ALLOWED_ORIGINS") {
+        Ok(value) if !value.trim().is_empty() => value
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        _ => DEFAULT_ALLOWED_ORIGINS.iter().map(|s| s.to_string()).collect(),
+    };
+
+    origins
+        .iter()
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                eprintln!("⚠️ 無効なCORSオリジンを無視します: {}", origin);
+                None
+            }
+        })
+        .collect()
+}
+
 pub async fn start_api_server(addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
     eprintln!("🚀 APIサーバーを起動中: http://{}", addr);
     
@@ -14,16 +47,20 @@ pub async fn start_api_server(addr: SocketAddr) -> Result<(), Box<dyn std::error
         return Err("Database not initialized".into());
     }
     
-    // CORS設定（プリフライトリクエストを適切に処理）
+    // CORS設定（許可リストに含まれるオリジンのみ、プリフライトリクエストを適切に処理）
+    let origins = allowed_origins();
+    eprintln!("🔒 CORS許可オリジン: {:?}", origins);
     let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any)
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT, header::HeaderName::from_static("last-event-id")])
         .allow_credentials(false)
         .max_age(std::time::Duration::from_secs(3600));
     
     // ルーターの作成
+    // 認証ミドルウェアはCORSの内側で実行する（プリフライトリクエストには認証不要）
     let app: Router = crate::api::routes::create_routes()
+        .layer(axum::middleware::from_fn(crate::api::auth::require_auth))
         .layer(ServiceBuilder::new().layer(cors));
     
     // サーバーの起動
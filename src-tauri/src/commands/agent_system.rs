@@ -1,17 +1,39 @@
 use crate::database::{
-    save_task, get_task, get_all_tasks, delete_task,
-    save_task_execution, get_task_execution, get_task_executions, get_all_task_executions,
+    get_task, get_all_tasks,
+    get_task_execution, get_task_executions, get_all_task_executions,
     save_task_chain, get_task_chain, get_all_task_chains, delete_task_chain,
-    save_agent, get_agent, get_all_agents, delete_agent,
+    get_agent, get_all_agents,
     save_mcp_tool, get_mcp_tool_by_name, get_all_mcp_tools, get_enabled_mcp_tools, delete_mcp_tool,
-    update_mcp_tool_enabled,
-    Task, TaskExecution, TaskChain, Agent, MCPTool,
+    update_mcp_tool_enabled, enqueue_task_execution, cancel_task_execution,
+    validate_task_chain, start_task_chain_run, cancel_task_chain_run,
+    get_task_chain_run, get_task_chain_runs, get_task_chain_run_steps,
+    validate_schedule, save_schedule, get_schedule, get_all_schedules, delete_schedule,
+    get_schedule_runs, run_schedule_now, Schedule, ScheduleRun,
+    send_a2a_message, reply_a2a_message, get_a2a_message, get_a2a_inbox, receive_a2a_messages,
+    get_a2a_history, wait_for_a2a_response, A2AMessage, SendA2AMessage, A2AHistoryQuery,
+    DEFAULT_RESPONSE_TIMEOUT_MS,
+    get_agent_prompt_versions, get_agent_prompt_version, diff_agent_prompt_versions,
+    check_agent_prompt_rollback, check_task_prompt_version, AgentPromptVersion, PromptDiff,
+    Task, TaskExecution, TaskChain, ChainValidation, TaskChainRun, TaskChainRunStep,
+    Agent, MCPTool, Permission, SessionState,
 };
+use crate::db::{WriteJob, WriteQueueState};
+use tauri::{AppHandle, State};
 
 /// タスクを保存
 #[tauri::command]
-pub async fn save_task_command(task: Task) -> Result<Task, String> {
-    save_task(&task).map_err(|e| format!("タスクの保存に失敗しました: {}", e))
+pub async fn save_task_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    task: Task,
+) -> Result<Task, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    let task_id = task.id.clone();
+    state.send(WriteJob::SaveTask { task }, Some(user)).await
+        .map_err(|e| format!("タスクの保存に失敗しました: {}", e))?;
+    get_task(&task_id)
+        .map_err(|e| format!("タスクの取得に失敗しました: {}", e))?
+        .ok_or_else(|| "タスクの保存後に取得に失敗しました".to_string())
 }
 
 /// タスクを取得
@@ -28,14 +50,30 @@ pub async fn get_all_tasks_command() -> Result<Vec<Task>, String> {
 
 /// タスクを削除
 #[tauri::command]
-pub async fn delete_task_command(task_id: String) -> Result<(), String> {
-    delete_task(&task_id).map_err(|e| format!("タスクの削除に失敗しました: {}", e))
+pub async fn delete_task_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    task_id: String,
+) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Delete)?;
+    state.send(WriteJob::DeleteTask { task_id }, Some(user)).await
+        .map_err(|e| format!("タスクの削除に失敗しました: {}", e))
 }
 
 /// タスク実行を保存
 #[tauri::command]
-pub async fn save_task_execution_command(execution: TaskExecution) -> Result<TaskExecution, String> {
-    save_task_execution(&execution).map_err(|e| format!("タスク実行の保存に失敗しました: {}", e))
+pub async fn save_task_execution_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    execution: TaskExecution,
+) -> Result<TaskExecution, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    let execution_id = execution.id.clone();
+    state.send(WriteJob::SaveTaskExecution { execution }, Some(user)).await
+        .map_err(|e| format!("タスク実行の保存に失敗しました: {}", e))?;
+    get_task_execution(&execution_id)
+        .map_err(|e| format!("タスク実行の取得に失敗しました: {}", e))?
+        .ok_or_else(|| "タスク実行の保存後に取得に失敗しました".to_string())
 }
 
 /// タスク実行を取得
@@ -56,9 +94,34 @@ pub async fn get_all_task_executions_command() -> Result<Vec<TaskExecution>, Str
     get_all_task_executions().map_err(|e| format!("実行履歴一覧の取得に失敗しました: {}", e))
 }
 
+/// タスクをRust側の実行エンジンの実行キューに追加（進捗は task-execution-progress イベントで通知される）
+#[tauri::command]
+pub async fn enqueue_task_execution_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    task_id: String,
+) -> Result<TaskExecution, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    enqueue_task_execution(&state, &task_id, Some(user)).await
+        .map_err(|e| format!("タスクの実行登録に失敗しました: {}", e))
+}
+
+/// タスク実行をキャンセル
+#[tauri::command]
+pub async fn cancel_task_execution_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    execution_id: String,
+) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    cancel_task_execution(&state, &execution_id, Some(user)).await
+        .map_err(|e| format!("タスク実行のキャンセルに失敗しました: {}", e))
+}
+
 /// タスクチェーンを保存
 #[tauri::command]
-pub async fn save_task_chain_command(chain: TaskChain) -> Result<TaskChain, String> {
+pub async fn save_task_chain_command(session_state: State<'_, SessionState>, chain: TaskChain) -> Result<TaskChain, String> {
+    session_state.require_permission(Permission::Write)?;
     save_task_chain(&chain).map_err(|e| format!("タスクチェーンの保存に失敗しました: {}", e))
 }
 
@@ -76,14 +139,185 @@ pub async fn get_all_task_chains_command() -> Result<Vec<TaskChain>, String> {
 
 /// タスクチェーンを削除
 #[tauri::command]
-pub async fn delete_task_chain_command(chain_id: String) -> Result<(), String> {
+pub async fn delete_task_chain_command(session_state: State<'_, SessionState>, chain_id: String) -> Result<(), String> {
+    session_state.require_permission(Permission::Delete)?;
     delete_task_chain(&chain_id).map_err(|e| format!("タスクチェーンの削除に失敗しました: {}", e))
 }
 
+/// タスクチェーンを検証（保存前のチェーンも検証できる）
+#[tauri::command]
+pub async fn validate_task_chain_command(chain: TaskChain) -> Result<ChainValidation, String> {
+    validate_task_chain(&chain).map_err(|e| format!("タスクチェーンの検証に失敗しました: {}", e))
+}
+
+/// タスクチェーンをRust側で実行（進捗は task-chain-progress イベントで通知される）
+#[tauri::command]
+pub async fn run_task_chain_command(
+    session_state: State<'_, SessionState>,
+    chain_id: String,
+) -> Result<TaskChainRun, String> {
+    session_state.require_permission(Permission::Write)?;
+    start_task_chain_run(&chain_id).await
+}
+
+/// タスクチェーンの実行をキャンセル
+#[tauri::command]
+pub async fn cancel_task_chain_run_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    run_id: String,
+) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    cancel_task_chain_run(&state, &run_id, Some(user)).await
+        .map_err(|e| format!("タスクチェーンの実行のキャンセルに失敗しました: {}", e))
+}
+
+/// タスクチェーンの実行を取得
+#[tauri::command]
+pub async fn get_task_chain_run_command(run_id: String) -> Result<Option<TaskChainRun>, String> {
+    get_task_chain_run(&run_id).map_err(|e| format!("タスクチェーンの実行の取得に失敗しました: {}", e))
+}
+
+/// タスクチェーンの実行履歴を取得
+#[tauri::command]
+pub async fn get_task_chain_runs_command(chain_id: String) -> Result<Vec<TaskChainRun>, String> {
+    get_task_chain_runs(&chain_id).map_err(|e| format!("タスクチェーンの実行履歴の取得に失敗しました: {}", e))
+}
+
+/// タスクチェーンの実行で通過したノードを取得
+#[tauri::command]
+pub async fn get_task_chain_run_steps_command(run_id: String) -> Result<Vec<TaskChainRunStep>, String> {
+    get_task_chain_run_steps(&run_id).map_err(|e| format!("実行ステップの取得に失敗しました: {}", e))
+}
+
+/// スケジュールを保存（保存前に検証し、次回の実行時刻を計算する）
+#[tauri::command]
+pub async fn save_schedule_command(session_state: State<'_, SessionState>, schedule: Schedule) -> Result<Schedule, String> {
+    session_state.require_permission(Permission::Write)?;
+    validate_schedule(&schedule)?;
+    save_schedule(&schedule).map_err(|e| format!("スケジュールの保存に失敗しました: {}", e))
+}
+
+/// スケジュールを取得
+#[tauri::command]
+pub async fn get_schedule_command(schedule_id: String) -> Result<Option<Schedule>, String> {
+    get_schedule(&schedule_id).map_err(|e| format!("スケジュールの取得に失敗しました: {}", e))
+}
+
+/// すべてのスケジュールを取得
+#[tauri::command]
+pub async fn get_all_schedules_command() -> Result<Vec<Schedule>, String> {
+    get_all_schedules().map_err(|e| format!("スケジュール一覧の取得に失敗しました: {}", e))
+}
+
+/// スケジュールを削除
+#[tauri::command]
+pub async fn delete_schedule_command(session_state: State<'_, SessionState>, schedule_id: String) -> Result<(), String> {
+    session_state.require_permission(Permission::Delete)?;
+    delete_schedule(&schedule_id).map_err(|e| format!("スケジュールの削除に失敗しました: {}", e))
+}
+
+/// スケジュールの実行記録を取得（新しい順、デフォルト50件）
+#[tauri::command]
+pub async fn get_schedule_runs_command(schedule_id: String, limit: Option<i64>) -> Result<Vec<ScheduleRun>, String> {
+    get_schedule_runs(&schedule_id, limit.unwrap_or(50))
+        .map_err(|e| format!("スケジュールの実行記録の取得に失敗しました: {}", e))
+}
+
+/// スケジュールを今すぐ実行
+#[tauri::command]
+pub async fn run_schedule_now_command(
+    app: AppHandle,
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    schedule_id: String,
+) -> Result<ScheduleRun, String> {
+    session_state.require_permission(Permission::Write)?;
+    run_schedule_now(&app, &state, &schedule_id).await
+}
+
+/// A2Aメッセージを送信（送信先Agentの受信箱に入る）
+#[tauri::command]
+pub async fn send_a2a_message_command(
+    session_state: State<'_, SessionState>,
+    message: SendA2AMessage,
+) -> Result<A2AMessage, String> {
+    session_state.require_permission(Permission::Write)?;
+    send_a2a_message(message).map_err(|e| format!("A2Aメッセージの送信に失敗しました: {}", e))
+}
+
+/// A2Aメッセージに返信（送信先・taskId は元のメッセージから引き継ぐ）
+#[tauri::command]
+pub async fn reply_a2a_message_command(
+    session_state: State<'_, SessionState>,
+    message_id: String,
+    from: String,
+    payload: serde_json::Value,
+) -> Result<A2AMessage, String> {
+    session_state.require_permission(Permission::Write)?;
+    reply_a2a_message(&message_id, &from, payload).map_err(|e| format!("A2Aメッセージの返信に失敗しました: {}", e))
+}
+
+/// A2Aメッセージを取得
+#[tauri::command]
+pub async fn get_a2a_message_command(message_id: String) -> Result<Option<A2AMessage>, String> {
+    get_a2a_message(&message_id).map_err(|e| format!("A2Aメッセージの取得に失敗しました: {}", e))
+}
+
+/// Agentの受信箱を取得（既読にはしない）
+#[tauri::command]
+pub async fn get_a2a_inbox_command(
+    agent_id: String,
+    unread_only: Option<bool>,
+    limit: Option<i64>,
+) -> Result<Vec<A2AMessage>, String> {
+    get_a2a_inbox(&agent_id, unread_only.unwrap_or(false), limit.unwrap_or(100))
+        .map_err(|e| format!("受信箱の取得に失敗しました: {}", e))
+}
+
+/// Agentの未読メッセージを受信（取得したメッセージは既読になる）
+#[tauri::command]
+pub async fn receive_a2a_messages_command(
+    session_state: State<'_, SessionState>,
+    agent_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<A2AMessage>, String> {
+    session_state.require_permission(Permission::Write)?;
+    receive_a2a_messages(&agent_id, limit.unwrap_or(100))
+        .map_err(|e| format!("A2Aメッセージの受信に失敗しました: {}", e))
+}
+
+/// A2Aメッセージへの返信を待つ（期限までに返信がなければ None）
+#[tauri::command]
+pub async fn wait_for_a2a_response_command(
+    message_id: String,
+    timeout_ms: Option<u64>,
+) -> Result<Option<A2AMessage>, String> {
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_RESPONSE_TIMEOUT_MS));
+    wait_for_a2a_response(&message_id, timeout).await
+        .map_err(|e| format!("A2Aメッセージの返信の待機に失敗しました: {}", e))
+}
+
+/// A2Aメッセージの履歴を検索（タスク・Agentごと）
+#[tauri::command]
+pub async fn get_a2a_history_command(query: A2AHistoryQuery) -> Result<Vec<A2AMessage>, String> {
+    get_a2a_history(&query).map_err(|e| format!("A2Aメッセージ履歴の取得に失敗しました: {}", e))
+}
+
 /// Agent定義を保存
 #[tauri::command]
-pub async fn save_agent_command(agent: Agent) -> Result<Agent, String> {
-    save_agent(&agent).map_err(|e| format!("Agent定義の保存に失敗しました: {}", e))
+pub async fn save_agent_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    agent: Agent,
+) -> Result<Agent, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    let agent_id = agent.id.clone();
+    state.send(WriteJob::SaveAgent { agent }, Some(user)).await
+        .map_err(|e| format!("Agent定義の保存に失敗しました: {}", e))?;
+    get_agent(&agent_id)
+        .map_err(|e| format!("Agent定義の取得に失敗しました: {}", e))?
+        .ok_or_else(|| "Agent定義の保存後に取得に失敗しました".to_string())
 }
 
 /// Agent定義を取得
@@ -100,13 +334,72 @@ pub async fn get_all_agents_command() -> Result<Vec<Agent>, String> {
 
 /// Agent定義を削除
 #[tauri::command]
-pub async fn delete_agent_command(agent_id: String) -> Result<(), String> {
-    delete_agent(&agent_id).map_err(|e| format!("Agent定義の削除に失敗しました: {}", e))
+pub async fn delete_agent_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    agent_id: String,
+) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Delete)?;
+    state.send(WriteJob::DeleteAgent { agent_id }, Some(user)).await
+        .map_err(|e| format!("Agent定義の削除に失敗しました: {}", e))
+}
+
+/// Agentのシステムプロンプトのバージョン一覧を取得（新しい順）
+#[tauri::command]
+pub async fn get_agent_prompt_versions_command(agent_id: String) -> Result<Vec<AgentPromptVersion>, String> {
+    get_agent_prompt_versions(&agent_id).map_err(|e| format!("プロンプトのバージョン一覧の取得に失敗しました: {}", e))
+}
+
+/// Agentのシステムプロンプトのバージョンを取得
+#[tauri::command]
+pub async fn get_agent_prompt_version_command(agent_id: String, version: i64) -> Result<Option<AgentPromptVersion>, String> {
+    get_agent_prompt_version(&agent_id, version).map_err(|e| format!("プロンプトのバージョンの取得に失敗しました: {}", e))
+}
+
+/// 2つのバージョンの差分を取得
+#[tauri::command]
+pub async fn diff_agent_prompt_versions_command(agent_id: String, from_version: i64, to_version: i64) -> Result<PromptDiff, String> {
+    diff_agent_prompt_versions(&agent_id, from_version, to_version)
+}
+
+/// システムプロンプトを指定したバージョンに戻す
+#[tauri::command]
+pub async fn rollback_agent_prompt_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    agent_id: String,
+    version: i64,
+) -> Result<Agent, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    check_agent_prompt_rollback(&agent_id, version)?;
+    state.send(WriteJob::RollbackAgentPrompt { agent_id: agent_id.clone(), version }, Some(user)).await
+        .map_err(|e| format!("Agent定義の保存に失敗しました: {}", e))?;
+    get_agent(&agent_id)
+        .map_err(|e| format!("Agent定義の取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("Agentが見つかりません: {}", agent_id))
+}
+
+/// タスクをシステムプロンプトのバージョンに固定する（version を省略すると固定を解除）
+#[tauri::command]
+pub async fn set_task_prompt_version_command(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    task_id: String,
+    version: Option<i64>,
+) -> Result<Task, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    check_task_prompt_version(&task_id, version)?;
+    state.send(WriteJob::SetTaskPromptVersion { task_id: task_id.clone(), version }, Some(user)).await
+        .map_err(|e| format!("タスクの保存に失敗しました: {}", e))?;
+    get_task(&task_id)
+        .map_err(|e| format!("タスクの取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("タスクが見つかりません: {}", task_id))
 }
 
 /// MCPツールを保存
 #[tauri::command]
-pub async fn save_mcp_tool_command(tool: MCPTool) -> Result<MCPTool, String> {
+pub async fn save_mcp_tool_command(session_state: State<'_, SessionState>, tool: MCPTool) -> Result<MCPTool, String> {
+    session_state.require_permission(Permission::Write)?;
     save_mcp_tool(&tool).map_err(|e| format!("MCPツールの保存に失敗しました: {}", e))
 }
 
@@ -130,13 +423,15 @@ pub async fn get_enabled_mcp_tools_command() -> Result<Vec<MCPTool>, String> {
 
 /// MCPツールを削除
 #[tauri::command]
-pub async fn delete_mcp_tool_command(name: String) -> Result<(), String> {
+pub async fn delete_mcp_tool_command(session_state: State<'_, SessionState>, name: String) -> Result<(), String> {
+    session_state.require_permission(Permission::Delete)?;
     delete_mcp_tool(&name).map_err(|e| format!("MCPツールの削除に失敗しました: {}", e))
 }
 
 /// MCPツールの有効/無効を切り替え
 #[tauri::command]
-pub async fn update_mcp_tool_enabled_command(name: String, enabled: bool) -> Result<(), String> {
+pub async fn update_mcp_tool_enabled_command(session_state: State<'_, SessionState>, name: String, enabled: bool) -> Result<(), String> {
+    session_state.require_permission(Permission::Write)?;
     update_mcp_tool_enabled(&name, enabled).map_err(|e| format!("MCPツールの有効/無効切り替えに失敗しました: {}", e))
 }
 
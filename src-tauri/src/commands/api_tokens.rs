@@ -0,0 +1,48 @@
+/**
+ * REST API用のAPIトークン管理のTauriコマンド
+ * トークンの発行・失効は管理者（users.role = 'admin'）のみ実行できる
+ */
+
+use crate::database::{
+    create_api_token, list_api_tokens, revoke_api_token,
+    ApiScope, Permission, SessionState,
+};
+use tauri::State;
+
+/// APIトークンを発行（トークンはこのレスポンスでのみ返される）
+#[tauri::command]
+pub fn create_api_token_cmd(
+    session_state: State<'_, SessionState>,
+    name: String,
+    scopes: Vec<String>,
+    expires_in_days: Option<i64>,
+) -> Result<serde_json::Value, String> {
+    let user = session_state.require_permission(Permission::Admin)?;
+    let scopes = scopes
+        .iter()
+        .map(|s| ApiScope::from_str(s).ok_or_else(|| format!("無効なスコープです: {}", s)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match create_api_token(&name, &scopes, &user.uid, expires_in_days) {
+        Ok(issued) => Ok(serde_json::to_value(issued).unwrap()),
+        Err(e) => Err(format!("APIトークンの発行に失敗しました: {}", e)),
+    }
+}
+
+/// APIトークン一覧を取得
+#[tauri::command]
+pub fn list_api_tokens_cmd(session_state: State<'_, SessionState>) -> Result<Vec<serde_json::Value>, String> {
+    session_state.require_permission(Permission::Admin)?;
+    match list_api_tokens() {
+        Ok(tokens) => Ok(tokens.into_iter().map(|t| serde_json::to_value(t).unwrap()).collect()),
+        Err(e) => Err(format!("APIトークン一覧の取得に失敗しました: {}", e)),
+    }
+}
+
+/// APIトークンを失効
+#[tauri::command]
+pub fn revoke_api_token_cmd(session_state: State<'_, SessionState>, id: String) -> Result<(), String> {
+    session_state.require_permission(Permission::Admin)?;
+    revoke_api_token(&id)
+        .map_err(|e| format!("APIトークンの失効に失敗しました: {}", e))
+}
@@ -1,6 +1,7 @@
 use tauri::{AppHandle, Manager, State};
 use std::collections::HashMap;
 use std::fs;
+use crate::database::{Permission, SessionState};
 use crate::db::{WriteJob, WriteQueueState};
 
 #[tauri::command]
@@ -75,8 +76,12 @@ pub async fn check_database_status() -> Result<HashMap<String, String>, String>
 }
 
 #[tauri::command]
-pub async fn reinitialize_database(app: AppHandle) -> Result<HashMap<String, String>, String> {
+pub async fn reinitialize_database(session_state: State<'_, SessionState>, app: AppHandle) -> Result<HashMap<String, String>, String> {
     use crate::database::{init_database, get_db};
+    // データベースが利用できない場合は権限を確認できないため、復旧のために再初期化を許可する
+    if get_db().is_some() {
+        session_state.require_permission(Permission::Admin)?;
+    }
     
     let mut result = HashMap::new();
     
@@ -129,6 +134,13 @@ pub async fn list_tables() -> Result<Vec<String>, String> {
     tables.map_err(|e| format!("テーブル一覧取得エラー: {}", e))
 }
 
+/// 現在のスキーマバージョンと適用済みマイグレーションを取得
+#[tauri::command]
+pub async fn get_schema_version() -> Result<crate::database::SchemaVersion, String> {
+    crate::database::get_schema_version()
+        .map_err(|e| format!("スキーマバージョンの取得に失敗しました: {}", e))
+}
+
 #[tauri::command]
 pub async fn diagnose_database(app: AppHandle) -> Result<HashMap<String, String>, String> {
     
@@ -233,19 +245,20 @@ pub async fn diagnose_database(app: AppHandle) -> Result<HashMap<String, String>
 
 #[tauri::command]
 pub async fn update_chroma_sync_status(
+    session_state: State<'_, SessionState>,
     state: State<'_, WriteQueueState>,
     entity_type: String,
     entity_id: String,
     synced: bool,
     error: Option<String>,
 ) -> Result<(), String> {
-    state.tx.send(WriteJob::UpdateChromaSyncStatus {
+    let user = session_state.require_permission(Permission::Write)?;
+    state.send(WriteJob::UpdateChromaSyncStatus {
         entity_type,
         entity_id,
         synced,
         error,
-    }).await
-    .map_err(|e| format!("書き込みキューへの送信に失敗しました: {}", e))?;
+    }, Some(user)).await?;
     
     Ok(())
 }
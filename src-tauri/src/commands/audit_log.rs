@@ -0,0 +1,42 @@
+/**
+ * 監査ログのTauriコマンド
+ * 監査ログの検索・エクスポートは管理者（admin）のみ実行できる
+ */
+
+use crate::database::{query_audit_log, export_audit_log_to_csv, AuditLogQuery, Permission, SessionState};
+use serde_json::Value;
+use std::fs;
+use tauri::State;
+
+/// 監査ログを検索（新しい順）
+#[tauri::command]
+pub fn query_audit_log_cmd(
+    session_state: State<'_, SessionState>,
+    query: Option<AuditLogQuery>,
+) -> Result<Vec<Value>, String> {
+    session_state.require_permission(Permission::Admin)?;
+    match query_audit_log(&query.unwrap_or_default()) {
+        Ok(entries) => Ok(entries.into_iter().map(|e| serde_json::to_value(e).unwrap()).collect()),
+        Err(e) => Err(format!("監査ログの取得に失敗しました: {}", e)),
+    }
+}
+
+/// 監査ログをCSV形式でエクスポート（export_pathを指定した場合はファイルにも保存）
+#[tauri::command]
+pub fn export_audit_log_csv(
+    session_state: State<'_, SessionState>,
+    query: Option<AuditLogQuery>,
+    export_path: Option<String>,
+) -> Result<String, String> {
+    session_state.require_permission(Permission::Admin)?;
+    let csv = export_audit_log_to_csv(&query.unwrap_or_default())
+        .map_err(|e| format!("監査ログのエクスポートに失敗しました: {}", e))?;
+
+    if let Some(path) = export_path {
+        fs::write(&path, &csv)
+            .map_err(|e| format!("ファイル保存エラー: {}", e))?;
+        eprintln!("✅ [export_audit_log_csv] エクスポート成功: {}", path);
+    }
+
+    Ok(csv)
+}
@@ -0,0 +1,149 @@
+/**
+ * 事業計画（添付ファイル・ID登録・作成履歴）関連のTauriコマンド
+ * JavaScript側から事業計画の添付ファイルを管理するためのAPI
+ */
+
+use crate::database::{
+    store_business_plan_file, get_business_plan_file, get_business_plan_files,
+    delete_business_plan_file, permanently_delete_business_plan_file,
+    register_business_plan_id, get_business_plan_id_info,
+    record_creation_history, get_creation_history, PlanType, Permission, SessionState,
+};
+use serde_json::{json, Value};
+use tauri::State;
+
+/// 事業計画にファイルを添付（同一内容のファイルが既にあれば既存のファイルを返す）
+/// ファイルはアプリのデータディレクトリ内の business_plan_files に保存する
+#[tauri::command]
+pub fn attach_business_plan_file_cmd(
+    session_state: State<'_, SessionState>,
+    plan_id: String,
+    plan_type: String,
+    original_file_name: String,
+    file_data: Vec<u8>,
+    mime_type: Option<String>,
+    description: Option<String>,
+    category: Option<String>,
+) -> Result<Value, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    let (file_id, duplicate) = store_business_plan_file(
+        &user,
+        &plan_id,
+        &plan_type,
+        &original_file_name,
+        &file_data,
+        mime_type.as_deref(),
+        description.as_deref(),
+        category.as_deref(),
+    )?;
+
+    match get_business_plan_file(&file_id) {
+        Ok(file) => Ok(json!({ "duplicate": duplicate, "file": file })),
+        Err(e) => Err(format!("事業計画ファイルの取得に失敗しました: {}", e)),
+    }
+}
+
+/// 事業計画ファイルを取得
+#[tauri::command]
+pub fn get_business_plan_file_cmd(file_id: String) -> Result<Value, String> {
+    match get_business_plan_file(&file_id) {
+        Ok(file) => Ok(serde_json::to_value(file).unwrap()),
+        Err(e) => Err(format!("事業計画ファイルの取得に失敗しました: {}", e)),
+    }
+}
+
+/// 事業計画のファイル一覧を取得
+#[tauri::command]
+pub fn get_business_plan_files_cmd(plan_id: String, include_deleted: Option<bool>) -> Result<Vec<Value>, String> {
+    match get_business_plan_files(&plan_id, include_deleted.unwrap_or(false)) {
+        Ok(files) => Ok(files.into_iter().map(|f| serde_json::to_value(f).unwrap()).collect()),
+        Err(e) => Err(format!("事業計画ファイル一覧の取得に失敗しました: {}", e)),
+    }
+}
+
+/// 事業計画ファイルを削除（論理削除）
+#[tauri::command]
+pub fn delete_business_plan_file_cmd(session_state: State<'_, SessionState>, file_id: String) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Delete)?;
+    delete_business_plan_file(&user, &file_id)
+        .map_err(|e| format!("事業計画ファイルの削除に失敗しました: {}", e))
+}
+
+/// 事業計画ファイルを完全削除（ファイルシステムからも削除）
+#[tauri::command]
+pub fn permanently_delete_business_plan_file_cmd(session_state: State<'_, SessionState>, file_id: String) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Delete)?;
+    permanently_delete_business_plan_file(&user, &file_id)
+        .map_err(|e| format!("事業計画ファイルの完全削除に失敗しました: {}", e))
+}
+
+/// 事業計画IDを登録
+#[tauri::command]
+pub fn register_business_plan_id_cmd(
+    session_state: State<'_, SessionState>,
+    plan_id: String,
+    plan_type: String,
+    display_id: Option<String>,
+    custom_prefix: Option<String>,
+    metadata: Option<Value>,
+) -> Result<String, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    if PlanType::from_str(&plan_type).is_none() {
+        return Err(format!("無効な事業計画の種類です: {}", plan_type));
+    }
+    let metadata = metadata.map(|m| m.to_string());
+    register_business_plan_id(
+        &user,
+        &plan_id,
+        &plan_type,
+        display_id.as_deref(),
+        custom_prefix.as_deref(),
+        metadata.as_deref(),
+    )
+    .map_err(|e| format!("事業計画IDの登録に失敗しました: {}", e))
+}
+
+/// 事業計画ID情報を取得
+#[tauri::command]
+pub fn get_business_plan_id_info_cmd(plan_id: String) -> Result<Value, String> {
+    match get_business_plan_id_info(&plan_id) {
+        Ok(info) => Ok(serde_json::to_value(info).unwrap()),
+        Err(e) => Err(format!("事業計画ID情報の取得に失敗しました: {}", e)),
+    }
+}
+
+/// 事業計画作成履歴を記録
+#[tauri::command]
+pub fn record_business_plan_history_cmd(
+    session_state: State<'_, SessionState>,
+    plan_id: String,
+    plan_type: String,
+    action: String,
+    previous_state: Option<Value>,
+    new_state: Option<Value>,
+    metadata: Option<Value>,
+) -> Result<String, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    let previous_state = previous_state.map(|v| v.to_string());
+    let new_state = new_state.map(|v| v.to_string());
+    let metadata = metadata.map(|v| v.to_string());
+    record_creation_history(
+        &user,
+        &plan_id,
+        &plan_type,
+        &action,
+        previous_state.as_deref(),
+        new_state.as_deref(),
+        metadata.as_deref(),
+    )
+    .map_err(|e| format!("事業計画作成履歴の記録に失敗しました: {}", e))
+}
+
+/// 事業計画の作成履歴を取得
+#[tauri::command]
+pub fn get_business_plan_history_cmd(plan_id: String, limit: Option<i64>) -> Result<Vec<Value>, String> {
+    match get_creation_history(&plan_id, limit) {
+        Ok(history) => Ok(history.into_iter().map(|h| serde_json::to_value(h).unwrap()).collect()),
+        Err(e) => Err(format!("事業計画作成履歴の取得に失敗しました: {}", e)),
+    }
+}
@@ -1,21 +1,29 @@
 /**
  * ChromaDB関連のTauriコマンド
  * JavaScript側からChromaDBを使用するためのAPI
+ * 実際の保存先はVectorStoreトレイト経由で選択されたバックエンド（VECTOR_BACKEND）
+ * 埋め込みベクトルの代わりにテキストを渡した場合はRust側で埋め込みを生成する
  */
 
-use crate::database::chromadb;
+use crate::database::vector_store::{get_vector_store, TopicSearchResult};
+use crate::database::{get_chroma_sync_progress, trigger_chroma_sync, resolve_embedding, ChromaSyncProgress, Permission, SessionState};
+use tauri::State;
 use serde_json::Value;
 use std::collections::HashMap;
 
 /// エンティティ埋め込みを保存
 #[tauri::command]
 pub async fn chromadb_save_entity_embedding(
+    session_state: State<'_, SessionState>,
     entityId: String,
     organizationId: String,
-    combinedEmbedding: Vec<f32>,
+    combinedEmbedding: Option<Vec<f32>>,
+    text: Option<String>,
     metadata: HashMap<String, Value>,
 ) -> Result<(), String> {
-    chromadb::save_entity_embedding(
+    session_state.require_permission(Permission::Write)?;
+    let combinedEmbedding = resolve_embedding(combinedEmbedding, text).await?;
+    get_vector_store()?.save_entity_embedding(
         entityId,
         organizationId,
         combinedEmbedding,
@@ -29,17 +37,19 @@ pub async fn chromadb_get_entity_embedding(
     entityId: String,
     organizationId: String,
 ) -> Result<Option<HashMap<String, Value>>, String> {
-    chromadb::get_entity_embedding(entityId, organizationId).await
+    get_vector_store()?.get_entity_embedding(entityId, organizationId).await
 }
 
 /// 類似エンティティを検索
 #[tauri::command]
 pub async fn chromadb_find_similar_entities(
-    queryEmbedding: Vec<f32>,
+    queryEmbedding: Option<Vec<f32>>,
+    queryText: Option<String>,
     limit: usize,
     organizationId: Option<String>,
 ) -> Result<Vec<(String, f32)>, String> {
-    chromadb::find_similar_entities(queryEmbedding, limit, organizationId).await
+    let queryEmbedding = resolve_embedding(queryEmbedding, queryText).await?;
+    get_vector_store()?.find_similar_entities(queryEmbedding, limit, organizationId).await
 }
 
 /// エンティティコレクションの件数を取得
@@ -47,18 +57,22 @@ pub async fn chromadb_find_similar_entities(
 pub async fn chromadb_count_entities(
     organizationId: Option<String>,
 ) -> Result<usize, String> {
-    chromadb::count_entities(organizationId).await
+    get_vector_store()?.count_entities(organizationId).await
 }
 
 /// リレーション埋め込みを保存
 #[tauri::command]
 pub async fn chromadb_save_relation_embedding(
+    session_state: State<'_, SessionState>,
     relationId: String,
     organizationId: String,
-    combinedEmbedding: Vec<f32>,
+    combinedEmbedding: Option<Vec<f32>>,
+    text: Option<String>,
     metadata: HashMap<String, Value>,
 ) -> Result<(), String> {
-    chromadb::save_relation_embedding(
+    session_state.require_permission(Permission::Write)?;
+    let combinedEmbedding = resolve_embedding(combinedEmbedding, text).await?;
+    get_vector_store()?.save_relation_embedding(
         relationId,
         organizationId,
         combinedEmbedding,
@@ -72,29 +86,35 @@ pub async fn chromadb_get_relation_embedding(
     relationId: String,
     organizationId: String,
 ) -> Result<Option<HashMap<String, Value>>, String> {
-    chromadb::get_relation_embedding(relationId, organizationId).await
+    get_vector_store()?.get_relation_embedding(relationId, organizationId).await
 }
 
 /// 類似リレーションを検索
 #[tauri::command]
 pub async fn chromadb_find_similar_relations(
-    queryEmbedding: Vec<f32>,
+    queryEmbedding: Option<Vec<f32>>,
+    queryText: Option<String>,
     limit: usize,
     organizationId: Option<String>,
 ) -> Result<Vec<(String, f32)>, String> {
-    chromadb::find_similar_relations(queryEmbedding, limit, organizationId).await
+    let queryEmbedding = resolve_embedding(queryEmbedding, queryText).await?;
+    get_vector_store()?.find_similar_relations(queryEmbedding, limit, organizationId).await
 }
 
 /// トピック埋め込みを保存
 #[tauri::command]
 pub async fn chromadb_save_topic_embedding(
+    session_state: State<'_, SessionState>,
     topicId: String,
     meetingNoteId: String,
     organizationId: String,
-    combinedEmbedding: Vec<f32>,
+    combinedEmbedding: Option<Vec<f32>>,
+    text: Option<String>,
     metadata: HashMap<String, Value>,
 ) -> Result<(), String> {
-    chromadb::save_topic_embedding(
+    session_state.require_permission(Permission::Write)?;
+    let combinedEmbedding = resolve_embedding(combinedEmbedding, text).await?;
+    get_vector_store()?.save_topic_embedding(
         topicId,
         meetingNoteId,
         organizationId,
@@ -109,38 +129,46 @@ pub async fn chromadb_get_topic_embedding(
     topicId: String,
     organizationId: String,
 ) -> Result<Option<HashMap<String, Value>>, String> {
-    chromadb::get_topic_embedding(topicId, organizationId).await
+    get_vector_store()?.get_topic_embedding(topicId, organizationId).await
 }
 
 /// 類似トピックを検索
 #[tauri::command]
 pub async fn chromadb_find_similar_topics(
-    queryEmbedding: Vec<f32>,
+    queryEmbedding: Option<Vec<f32>>,
+    queryText: Option<String>,
     limit: usize,
     organizationId: Option<String>,
-) -> Result<Vec<chromadb::TopicSearchResult>, String> {
-    chromadb::find_similar_topics(queryEmbedding, limit, organizationId).await
+) -> Result<Vec<TopicSearchResult>, String> {
+    let queryEmbedding = resolve_embedding(queryEmbedding, queryText).await?;
+    get_vector_store()?.find_similar_topics(queryEmbedding, limit, organizationId).await
 }
 
 /// システム設計ドキュメント埋め込みを保存
 #[tauri::command]
 pub async fn chromadb_save_design_doc_embedding(
+    session_state: State<'_, SessionState>,
     sectionId: String,
-    combinedEmbedding: Vec<f32>,
+    combinedEmbedding: Option<Vec<f32>>,
+    text: Option<String>,
     metadata: HashMap<String, Value>,
 ) -> Result<(), String> {
-    chromadb::save_design_doc_embedding(sectionId, combinedEmbedding, metadata).await
+    session_state.require_permission(Permission::Write)?;
+    let combinedEmbedding = resolve_embedding(combinedEmbedding, text).await?;
+    get_vector_store()?.save_design_doc_embedding(sectionId, combinedEmbedding, metadata).await
 }
 
 /// 類似システム設計ドキュメントを検索
 #[tauri::command]
 pub async fn chromadb_find_similar_design_docs(
-    queryEmbedding: Vec<f32>,
+    queryEmbedding: Option<Vec<f32>>,
+    queryText: Option<String>,
     limit: usize,
     sectionId: Option<String>,
     tags: Option<Vec<String>>,
 ) -> Result<Vec<(String, f32)>, String> {
-    chromadb::find_similar_design_docs(queryEmbedding, limit, sectionId, tags).await
+    let queryEmbedding = resolve_embedding(queryEmbedding, queryText).await?;
+    get_vector_store()?.find_similar_design_docs(queryEmbedding, limit, sectionId, tags).await
 }
 
 /// システム設計ドキュメントのメタデータを取得
@@ -148,52 +176,75 @@ pub async fn chromadb_find_similar_design_docs(
 pub async fn chromadb_get_design_doc_metadata(
     sectionId: String,
 ) -> Result<HashMap<String, Value>, String> {
-    chromadb::get_design_doc_metadata(sectionId).await
+    get_vector_store()?.get_design_doc_metadata(sectionId).await
 }
 
 /// システム設計ドキュメントコレクション内の全セクションIDを取得（デバッグ用）
 #[tauri::command]
 pub async fn chromadb_list_design_doc_section_ids() -> Result<Vec<String>, String> {
-    chromadb::list_design_doc_section_ids().await
+    get_vector_store()?.list_design_doc_section_ids().await
 }
 
 /// トピック埋め込みを削除
 #[tauri::command]
 pub async fn chromadb_delete_topic_embedding(
+    session_state: State<'_, SessionState>,
     topicId: String,
     organizationId: String,
 ) -> Result<(), String> {
-    chromadb::delete_topic_embedding(topicId, organizationId).await
+    session_state.require_permission(Permission::Delete)?;
+    get_vector_store()?.delete_topic_embedding(topicId, organizationId).await
 }
 
 /// エンティティ埋め込みを削除
 #[tauri::command]
 pub async fn chromadb_delete_entity_embedding(
+    session_state: State<'_, SessionState>,
     entityId: String,
     organizationId: String,
 ) -> Result<(), String> {
-    chromadb::delete_entity_embedding(entityId, organizationId).await
+    session_state.require_permission(Permission::Delete)?;
+    get_vector_store()?.delete_entity_embedding(entityId, organizationId).await
 }
 
 /// リレーション埋め込みを削除
 #[tauri::command]
 pub async fn chromadb_delete_relation_embedding(
+    session_state: State<'_, SessionState>,
     relationId: String,
     organizationId: String,
 ) -> Result<(), String> {
-    chromadb::delete_relation_embedding(relationId, organizationId).await
+    session_state.require_permission(Permission::Delete)?;
+    get_vector_store()?.delete_relation_embedding(relationId, organizationId).await
 }
 
 /// ChromaDBのデータディレクトリをクリア（破損したデータベースを修復するため）
 #[tauri::command]
-pub async fn chromadb_clear_data_dir() -> Result<(), String> {
-    chromadb::clear_chromadb_data_dir().await
+pub async fn chromadb_clear_data_dir(session_state: State<'_, SessionState>) -> Result<(), String> {
+    session_state.require_permission(Permission::Admin)?;
+    get_vector_store()?.clear_data_dir().await
 }
 
 /// 組織に関連するChromaDBコレクションを削除
 #[tauri::command]
 pub async fn chromadb_delete_organization_collections(
+    session_state: State<'_, SessionState>,
     organizationId: String,
 ) -> Result<(), String> {
-    chromadb::delete_organization_collections(organizationId).await
+    session_state.require_permission(Permission::Delete)?;
+    get_vector_store()?.delete_organization_collections(organizationId).await
+}
+
+/// ベクトルストア再同期ワーカーの進捗を取得
+#[tauri::command]
+pub async fn chromadb_get_sync_status() -> Result<ChromaSyncProgress, String> {
+    Ok(get_chroma_sync_progress())
+}
+
+/// ベクトルストア再同期を即時実行（待機中のワーカーを起こす）
+#[tauri::command]
+pub async fn chromadb_trigger_sync(session_state: State<'_, SessionState>) -> Result<(), String> {
+    session_state.require_permission(Permission::Write)?;
+    trigger_chroma_sync();
+    Ok(())
 }
@@ -0,0 +1,141 @@
+/**
+ * 事業会社関連のTauriコマンド
+ * 事業会社は organizations テーブルの type = 'company' の行として管理される
+ */
+
+use crate::database::{
+    create_company, update_company, get_company_by_id, get_company_by_code,
+    get_companies_by_organization_id, get_all_companies, update_company_positions,
+    delete_company, export_companies_to_csv, with_user_sync, Permission, SessionState,
+};
+use tauri::State;
+use std::fs;
+
+#[tauri::command]
+pub fn create_company_cmd(
+    session_state: State<'_, SessionState>,
+    code: String,
+    name: String,
+    name_short: Option<String>,
+    category: String,
+    organization_id: String,
+    company: Option<String>,
+    division: Option<String>,
+    department: Option<String>,
+    region: String,
+    position: Option<i32>,
+) -> Result<serde_json::Value, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    match with_user_sync(user, || create_company(
+        code,
+        name,
+        name_short,
+        category,
+        organization_id,
+        company,
+        division,
+        department,
+        region,
+        position.unwrap_or(0),
+    )) {
+        Ok(company) => Ok(serde_json::to_value(company).unwrap()),
+        Err(e) => Err(format!("事業会社の作成に失敗しました: {}", e)),
+    }
+}
+
+#[tauri::command]
+pub fn update_company_cmd(
+    session_state: State<'_, SessionState>,
+    id: String,
+    code: Option<String>,
+    name: Option<String>,
+    name_short: Option<String>,
+    category: Option<String>,
+    organization_id: Option<String>,
+    company: Option<String>,
+    division: Option<String>,
+    department: Option<String>,
+    region: Option<String>,
+    position: Option<i32>,
+) -> Result<serde_json::Value, String> {
+    let user = session_state.require_permission(Permission::Write)?;
+    match with_user_sync(user, || update_company(
+        &id,
+        code,
+        name,
+        name_short,
+        category,
+        organization_id,
+        company,
+        division,
+        department,
+        region,
+        position,
+    )) {
+        Ok(company) => Ok(serde_json::to_value(company).unwrap()),
+        Err(e) => Err(format!("事業会社の更新に失敗しました: {}", e)),
+    }
+}
+
+#[tauri::command]
+pub fn get_company(id: String) -> Result<serde_json::Value, String> {
+    match get_company_by_id(&id) {
+        Ok(company) => Ok(serde_json::to_value(company).unwrap()),
+        Err(e) => Err(format!("事業会社の取得に失敗しました: {}", e)),
+    }
+}
+
+#[tauri::command]
+pub fn get_company_by_code_cmd(code: String) -> Result<serde_json::Value, String> {
+    match get_company_by_code(&code) {
+        Ok(company) => Ok(serde_json::to_value(company).unwrap()),
+        Err(e) => Err(format!("事業会社の取得に失敗しました: {}", e)),
+    }
+}
+
+#[tauri::command]
+pub fn get_companies_by_org(organization_id: String) -> Result<Vec<serde_json::Value>, String> {
+    match get_companies_by_organization_id(&organization_id) {
+        Ok(companies) => Ok(companies.into_iter().map(|c| serde_json::to_value(c).unwrap()).collect()),
+        Err(e) => Err(format!("事業会社の取得に失敗しました: {}", e)),
+    }
+}
+
+#[tauri::command]
+pub fn get_all_companies_cmd() -> Result<Vec<serde_json::Value>, String> {
+    match get_all_companies() {
+        Ok(companies) => Ok(companies.into_iter().map(|c| serde_json::to_value(c).unwrap()).collect()),
+        Err(e) => Err(format!("事業会社一覧の取得に失敗しました: {}", e)),
+    }
+}
+
+/// 複数の事業会社のpositionを一括更新
+#[tauri::command]
+pub fn update_company_positions_cmd(session_state: State<'_, SessionState>, updates: Vec<(String, i32)>) -> Result<(), String> {
+    session_state.require_permission(Permission::Write)?;
+    update_company_positions(&updates)
+        .map_err(|e| format!("事業会社の表示順序の更新に失敗しました: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_company_cmd(session_state: State<'_, SessionState>, id: String) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Delete)?;
+    with_user_sync(user, || delete_company(&id))
+        .map_err(|e| format!("事業会社の削除に失敗しました: {}", e))
+}
+
+/// 事業会社をCSV形式でエクスポート（export_pathを指定した場合はファイルにも保存）
+#[tauri::command]
+pub fn export_companies_csv(session_state: State<'_, SessionState>, export_path: Option<String>) -> Result<String, String> {
+    session_state.require_permission(Permission::Read)?;
+    let csv = export_companies_to_csv()
+        .map_err(|e| format!("事業会社のエクスポートに失敗しました: {}", e))?;
+
+    if let Some(path) = export_path {
+        fs::write(&path, &csv)
+            .map_err(|e| format!("ファイル保存エラー: {}", e))?;
+        eprintln!("✅ [export_companies_csv] エクスポート成功: {}", path);
+    }
+
+    Ok(csv)
+}
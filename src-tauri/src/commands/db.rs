@@ -1,16 +1,61 @@
 use crate::database::{sign_in as db_sign_in, sign_up as db_sign_up, sign_out as db_sign_out, 
-                      get_current_user as db_get_current_user, get_doc, set_doc, update_doc, delete_doc, add_doc, get_collection,
-                      export_to_file, import_from_file, export_organizations_and_members_to_file,
-                      delete_meeting_note_with_relations as db_delete_meeting_note_with_relations};
-use serde_json::Value;
+                      with_user_sync, Permission, SessionState, get_doc, set_doc, update_doc, delete_doc, add_doc, get_collection,
+                      query_collection, CollectionQuery, list_changes, latest_change_seq, parse_resume_token, CHANGE_PAGE_SIZE,
+                      export_to_file, import_from_file, export_organizations_and_members_to_file};
+use crate::db::{WriteJob, WriteQueueState};
+use serde_json::{json, Value};
 use std::collections::HashMap;
+use tauri::State;
+
+/// 書き込みキュー経由で書き込むコレクション（書き込みワーカーとのロック競合を避ける）
+const QUEUED_COLLECTIONS: &[&str] = &["meetingNotes", "focusInitiatives"];
+
+fn is_queued_collection(collection_name: &str) -> bool {
+    QUEUED_COLLECTIONS.contains(&collection_name)
+}
+
+/// 議事録・注力施策の保存ジョブを作成（partial: 指定したフィールドのみ更新）
+fn upsert_job(collection_name: &str, doc_id: String, payload: HashMap<String, Value>, partial: bool) -> WriteJob {
+    match (collection_name, partial) {
+        ("meetingNotes", false) => WriteJob::UpsertMeetingNote { meeting_note_id: doc_id, payload },
+        ("meetingNotes", true) => WriteJob::UpdateMeetingNote { meeting_note_id: doc_id, payload },
+        (_, false) => WriteJob::UpsertFocusInitiative { focus_initiative_id: doc_id, payload },
+        (_, true) => WriteJob::UpdateFocusInitiative { focus_initiative_id: doc_id, payload },
+    }
+}
+
+/// 議事録の削除ジョブを作成（埋め込みの削除に使う組織IDを議事録から取得）
+fn delete_meeting_note_job(note_id: String) -> WriteJob {
+    let organization_id = get_doc("meetingNotes", &note_id)
+        .ok()
+        .and_then(|doc| {
+            doc.get("organizationId")
+                .or_else(|| doc.get("companyId"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_default();
+    WriteJob::DeleteMeetingNote { meeting_note_id: note_id, organization_id }
+}
 
 #[tauri::command]
-pub async fn sign_in(email: String, password: String) -> Result<HashMap<String, Value>, String> {
+pub async fn sign_in(
+    session_state: State<'_, SessionState>,
+    email: String,
+    password: String,
+) -> Result<HashMap<String, Value>, String> {
     match db_sign_in(email.clone(), password) {
         Ok(result) => {
+            // 以前のセッションが残っていれば失効させてから新しいトークンを保持
+            if let Some(previous) = session_state.token() {
+                let _ = db_sign_out(&previous);
+            }
+            session_state.set_token(Some(result.session.token.clone()));
+            
             let mut map = HashMap::new();
             map.insert("user".to_string(), serde_json::to_value(result.user).unwrap());
+            map.insert("sessionToken".to_string(), Value::String(result.session.token));
+            map.insert("expiresAt".to_string(), Value::String(result.session.expires_at.to_rfc3339()));
             Ok(map)
         }
         Err(e) => {
@@ -35,17 +80,23 @@ pub async fn sign_in(email: String, password: String) -> Result<HashMap<String,
                        - パスワード: admin123",
                     email
                 )
+            } else if error_msg.contains("承認待ち") {
+                format!(
+                    "このアカウントはまだ利用できません。\n\n\
+                    入力されたメールアドレス: {}\n\n\
+                    新規登録したアカウントは、管理者の承認後にログインできます。\n\
+                    管理者に承認を依頼してください。",
+                    email
+                )
             } else if error_msg.contains("InvalidQuery") {
                 format!(
                     "ログインに失敗しました。\n\n\
                     入力されたメールアドレス: {}\n\n\
                     考えられる原因:\n\
-                    1. パスワードが間違っている\n\
-                    2. ユーザーが承認されていない\n\n\
+                    1. パスワードが間違っている\n\n\
                     対処法:\n\
                     1. パスワードを確認してください\n\
-                    2. 新規登録の場合は、開発環境では自動承認されます\n\
-                    3. デフォルトユーザーでログインする場合:\n\
+                    2. デフォルトユーザーでログインする場合:\n\
                        - メールアドレス: admin@example.com\n\
                        - パスワード: admin123",
                     email
@@ -73,6 +124,8 @@ pub async fn sign_up(email: String, password: String) -> Result<HashMap<String,
         Ok(result) => {
             let mut map = HashMap::new();
             map.insert("user".to_string(), serde_json::to_value(result.user).unwrap());
+            map.insert("approved".to_string(), Value::Bool(result.approved));
+            map.insert("role".to_string(), Value::String(result.role.as_str().to_string()));
             Ok(map)
         }
         Err(e) => Err(format!("登録エラー: {}", e)),
@@ -80,17 +133,20 @@ pub async fn sign_up(email: String, password: String) -> Result<HashMap<String,
 }
 
 #[tauri::command]
-pub async fn sign_out() -> Result<HashMap<String, Value>, String> {
-    db_sign_out();
+pub async fn sign_out(session_state: State<'_, SessionState>) -> Result<HashMap<String, Value>, String> {
+    if let Some(token) = session_state.token() {
+        db_sign_out(&token).map_err(|e| format!("サインアウトエラー: {}", e))?;
+    }
+    session_state.set_token(None);
     Ok(HashMap::new())
 }
 
 #[tauri::command]
-pub async fn get_current_user() -> Result<Option<HashMap<String, Value>>, String> {
+pub async fn get_current_user(session_state: State<'_, SessionState>) -> Result<Option<HashMap<String, Value>>, String> {
     // デバッグ用ログ（呼び出し回数が多い場合はコメントアウト）
     // eprintln!("🔍 get_current_user called");
     
-    match db_get_current_user() {
+    match session_state.current_user() {
         Some(user) => {
             let mut map = HashMap::new();
             map.insert("uid".to_string(), Value::String(user.uid));
@@ -123,10 +179,22 @@ pub async fn doc_get(collection_name: String, doc_id: String) -> Result<HashMap<
 }
 
 #[tauri::command]
-pub async fn doc_set(collection_name: String, doc_id: String, data: HashMap<String, Value>) -> Result<HashMap<String, Value>, String> {
+pub async fn doc_set(
+    session_state: State<'_, SessionState>,
+    write_state: State<'_, WriteQueueState>,
+    collection_name: String,
+    doc_id: String,
+    data: HashMap<String, Value>,
+) -> Result<HashMap<String, Value>, String> {
+    let user = session_state.require_permission(Permission::Write)?;
     eprintln!("📝 [doc_set] コマンドが呼び出されました: collection_name={}, doc_id={}", collection_name, doc_id);
     
-    match set_doc(&collection_name, &doc_id, data) {
+    let result = if is_queued_collection(&collection_name) {
+        write_state.send(upsert_job(&collection_name, doc_id.clone(), data, false), Some(user)).await
+    } else {
+        with_user_sync(user, || set_doc(&collection_name, &doc_id, data)).map_err(|e| e.to_string())
+    };
+    match result {
         Ok(_) => {
             eprintln!("✅ [doc_set] 成功: doc_id={}", doc_id);
             let mut result = HashMap::new();
@@ -168,11 +236,23 @@ pub async fn doc_set(collection_name: String, doc_id: String, data: HashMap<Stri
 }
 
 #[tauri::command]
-pub async fn doc_update(collection_name: String, doc_id: String, data: HashMap<String, Value>) -> Result<HashMap<String, Value>, String> {
+pub async fn doc_update(
+    session_state: State<'_, SessionState>,
+    write_state: State<'_, WriteQueueState>,
+    collection_name: String,
+    doc_id: String,
+    data: HashMap<String, Value>,
+) -> Result<HashMap<String, Value>, String> {
+    let user = session_state.require_permission(Permission::Write)?;
     eprintln!("📝 [doc_update] コマンドが呼び出されました: collection_name={}, doc_id={}", collection_name, doc_id);
     eprintln!("📝 [doc_update] データキー: {:?}", data.keys().collect::<Vec<_>>());
     
-    match update_doc(&collection_name, &doc_id, data) {
+    let result = if is_queued_collection(&collection_name) {
+        write_state.send(upsert_job(&collection_name, doc_id.clone(), data, true), Some(user)).await
+    } else {
+        with_user_sync(user, || update_doc(&collection_name, &doc_id, data)).map_err(|e| e.to_string())
+    };
+    match result {
         Ok(_) => {
             eprintln!("✅ [doc_update] 成功: doc_id={}", doc_id);
             let mut result = HashMap::new();
@@ -188,10 +268,23 @@ pub async fn doc_update(collection_name: String, doc_id: String, data: HashMap<S
 }
 
 #[tauri::command]
-pub async fn doc_delete(collection_name: String, doc_id: String) -> Result<HashMap<String, Value>, String> {
+pub async fn doc_delete(
+    session_state: State<'_, SessionState>,
+    write_state: State<'_, WriteQueueState>,
+    collection_name: String,
+    doc_id: String,
+) -> Result<HashMap<String, Value>, String> {
+    let user = session_state.require_permission(Permission::Delete)?;
     eprintln!("🗑️ [doc_delete] コマンドが呼び出されました: collection_name={}, doc_id={}", collection_name, doc_id);
     
-    match delete_doc(&collection_name, &doc_id) {
+    let result = match collection_name.as_str() {
+        "meetingNotes" => write_state.send(delete_meeting_note_job(doc_id.clone()), Some(user)).await,
+        "focusInitiatives" => {
+            write_state.send(WriteJob::DeleteFocusInitiative { focus_initiative_id: doc_id.clone() }, Some(user)).await
+        }
+        _ => with_user_sync(user, || delete_doc(&collection_name, &doc_id)).map_err(|e| e.to_string()),
+    };
+    match result {
         Ok(_) => {
             eprintln!("✅ [doc_delete] 削除成功: collection_name={}, doc_id={}", collection_name, doc_id);
             let mut result = HashMap::new();
@@ -207,10 +300,15 @@ pub async fn doc_delete(collection_name: String, doc_id: String) -> Result<HashM
 }
 
 #[tauri::command]
-pub async fn delete_meeting_note_with_relations(note_id: String) -> Result<HashMap<String, Value>, String> {
+pub async fn delete_meeting_note_with_relations(
+    session_state: State<'_, SessionState>,
+    write_state: State<'_, WriteQueueState>,
+    note_id: String,
+) -> Result<HashMap<String, Value>, String> {
+    let user = session_state.require_permission(Permission::Delete)?;
     eprintln!("🗑️ [delete_meeting_note_with_relations] コマンド呼び出し: note_id={}", note_id);
     
-    match db_delete_meeting_note_with_relations(&note_id) {
+    match write_state.send(delete_meeting_note_job(note_id.clone()), Some(user)).await {
         Ok(_) => {
             eprintln!("✅ [delete_meeting_note_with_relations] 成功: note_id={}", note_id);
             let mut result = HashMap::new();
@@ -237,7 +335,13 @@ pub async fn delete_meeting_note_with_relations(note_id: String) -> Result<HashM
 }
 
 #[tauri::command]
-pub async fn collection_add(collection_name: String, data: HashMap<String, Value>) -> Result<HashMap<String, Value>, String> {
+pub async fn collection_add(
+    session_state: State<'_, SessionState>,
+    write_state: State<'_, WriteQueueState>,
+    collection_name: String,
+    data: HashMap<String, Value>,
+) -> Result<HashMap<String, Value>, String> {
+    let user = session_state.require_permission(Permission::Write)?;
     eprintln!("📝 [collection_add] コマンドが呼び出されました: collection_name={}", collection_name);
     eprintln!("📝 [collection_add] データサイズ: {} bytes", serde_json::to_string(&data).unwrap_or_default().len());
     eprintln!("📝 [collection_add] データキー: {:?}", data.keys().collect::<Vec<_>>());
@@ -260,7 +364,14 @@ pub async fn collection_add(collection_name: String, data: HashMap<String, Value
     }
     
     eprintln!("📝 [collection_add] add_docを呼び出します...");
-    match add_doc(&collection_name, data) {
+    let result = if is_queued_collection(&collection_name) {
+        let doc_id = uuid::Uuid::new_v4().to_string();
+        write_state.send(upsert_job(&collection_name, doc_id.clone(), data, false), Some(user)).await
+            .map(|_| doc_id)
+    } else {
+        with_user_sync(user, || add_doc(&collection_name, data)).map_err(|e| e.to_string())
+    };
+    match result {
         Ok(doc_id) => {
             eprintln!("✅ [collection_add] 成功: doc_id={}", doc_id);
             let mut result = HashMap::new();
@@ -338,24 +449,66 @@ pub async fn collection_get(collection_name: String) -> Result<Vec<HashMap<Strin
     }
 }
 
+fn to_query_result(mut row: HashMap<String, Value>) -> HashMap<String, Value> {
+    let id = row.remove("id").and_then(|v| v.as_str().map(|s| s.to_string())).unwrap_or_default();
+    let mut result = HashMap::new();
+    result.insert("id".to_string(), Value::String(id));
+    result.insert("data".to_string(), serde_json::to_value(row).unwrap());
+    result
+}
+
+/// コレクションを検索（query を指定した場合は構造化クエリで { items, nextCursor } を返す）
 #[tauri::command]
-pub async fn query_get(collection_name: String, conditions: Option<HashMap<String, Value>>) -> Result<Vec<HashMap<String, Value>>, String> {
+pub async fn query_get(
+    collection_name: String,
+    conditions: Option<HashMap<String, Value>>,
+    query: Option<CollectionQuery>,
+) -> Result<Value, String> {
+    if let Some(query) = query {
+        let page = query_collection(&collection_name, &query)
+            .map_err(|e| format!("クエリ取得エラー: {}", e))?;
+        let items: Vec<HashMap<String, Value>> = page.items.into_iter().map(to_query_result).collect();
+        return Ok(json!({ "items": items, "nextCursor": page.next_cursor }));
+    }
+
     match get_collection(&collection_name, conditions) {
         Ok(results) => {
-            Ok(results.into_iter().map(|mut row| {
-                let id = row.remove("id").and_then(|v| v.as_str().map(|s| s.to_string())).unwrap_or_default();
-                let mut result = HashMap::new();
-                result.insert("id".to_string(), Value::String(id));
-                result.insert("data".to_string(), serde_json::to_value(row).unwrap());
-                result
-            }).collect())
+            let results: Vec<HashMap<String, Value>> = results.into_iter().map(to_query_result).collect();
+            Ok(json!(results))
         }
         Err(e) => Err(format!("クエリ取得エラー: {}", e)),
     }
 }
 
+/// 再開トークンより後の変更を取得（db-change イベントを受け取れなかった間の変更の取得に使う）
+/// since を省略した場合は変更を返さず、現在の再開トークンのみ返す
+#[tauri::command]
+pub async fn changes_since(
+    since: Option<String>,
+    collections: Option<Vec<String>>,
+    limit: Option<i64>,
+) -> Result<Value, String> {
+    let since = match since {
+        Some(token) => parse_resume_token(&token)
+            .ok_or_else(|| format!("無効な再開トークンです: {}", token))?,
+        None => {
+            let seq = latest_change_seq().map_err(|e| format!("変更の取得に失敗しました: {}", e))?;
+            return Ok(json!({ "changes": [], "resumeToken": seq.to_string(), "hasMore": false }));
+        }
+    };
+
+    let limit = limit.unwrap_or(CHANGE_PAGE_SIZE);
+    let changes = list_changes(since, collections.as_deref(), limit)
+        .map_err(|e| format!("変更の取得に失敗しました: {}", e))?;
+    let resume_token = changes.last().map(|c| c.seq).unwrap_or(since);
+    let has_more = changes.len() as i64 >= limit.clamp(1, CHANGE_PAGE_SIZE);
+    Ok(json!({ "changes": changes, "resumeToken": resume_token.to_string(), "hasMore": has_more }))
+}
+
 #[tauri::command]
-pub async fn export_database_data(export_path: String) -> Result<HashMap<String, Value>, String> {
+pub async fn export_database_data(session_state: State<'_, SessionState>, export_path: String) -> Result<HashMap<String, Value>, String> {
+    // 利用者のパスワードハッシュを含むため管理者のみ
+    session_state.require_permission(Permission::Admin)?;
     eprintln!("📤 [export_database_data] データベースのエクスポートを開始します: {}", export_path);
     
     match export_to_file(&export_path) {
@@ -375,7 +528,8 @@ pub async fn export_database_data(export_path: String) -> Result<HashMap<String,
 }
 
 #[tauri::command]
-pub async fn import_database_data(import_path: String) -> Result<HashMap<String, Value>, String> {
+pub async fn import_database_data(session_state: State<'_, SessionState>, import_path: String) -> Result<HashMap<String, Value>, String> {
+    session_state.require_permission(Permission::Admin)?;
     eprintln!("📥 [import_database_data] データベースのインポートを開始します: {}", import_path);
     
     match import_from_file(&import_path) {
@@ -395,7 +549,8 @@ pub async fn import_database_data(import_path: String) -> Result<HashMap<String,
 }
 
 #[tauri::command]
-pub async fn export_organizations_and_members(export_path: String) -> Result<HashMap<String, Value>, String> {
+pub async fn export_organizations_and_members(session_state: State<'_, SessionState>, export_path: String) -> Result<HashMap<String, Value>, String> {
+    session_state.require_permission(Permission::Read)?;
     eprintln!("📤 [export_organizations_and_members] 組織とメンバーのエクスポートを開始します: {}", export_path);
     
     match export_organizations_and_members_to_file(&export_path) {
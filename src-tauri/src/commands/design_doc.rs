@@ -9,11 +9,14 @@ use crate::database::{
     create_design_doc_section_relation, update_design_doc_section_relation,
     get_design_doc_section_relation_by_id, get_design_doc_section_relations_by_section_id,
     get_all_design_doc_section_relations, delete_design_doc_section_relation,
+    Permission, SessionState,
 };
+use tauri::State;
 
 /// セクションを作成
 #[tauri::command]
 pub fn create_design_doc_section_cmd(
+    session_state: State<'_, SessionState>,
     title: String,
     description: Option<String>,
     content: String,
@@ -26,6 +29,7 @@ pub fn create_design_doc_section_cmd(
     keywords: Option<Vec<String>>,
     summary: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    session_state.require_permission(Permission::Write)?;
     match create_design_doc_section(
         title,
         description,
@@ -47,6 +51,7 @@ pub fn create_design_doc_section_cmd(
 /// セクションを更新
 #[tauri::command]
 pub fn update_design_doc_section_cmd(
+    session_state: State<'_, SessionState>,
     id: String,
     title: Option<String>,
     description: Option<String>,
@@ -60,6 +65,7 @@ pub fn update_design_doc_section_cmd(
     keywords: Option<Vec<String>>,
     summary: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    session_state.require_permission(Permission::Write)?;
     match update_design_doc_section(
         &id,
         title,
@@ -108,7 +114,8 @@ pub fn get_all_design_doc_sections_lightweight_cmd() -> Result<Vec<serde_json::V
 
 /// セクションを削除
 #[tauri::command]
-pub fn delete_design_doc_section_cmd(id: String) -> Result<(), String> {
+pub fn delete_design_doc_section_cmd(session_state: State<'_, SessionState>, id: String) -> Result<(), String> {
+    session_state.require_permission(Permission::Delete)?;
     match delete_design_doc_section(&id) {
         Ok(_) => Ok(()),
         Err(e) => Err(format!("セクションの削除に失敗しました: {}", e)),
@@ -118,11 +125,13 @@ pub fn delete_design_doc_section_cmd(id: String) -> Result<(), String> {
 /// セクション関係を作成
 #[tauri::command]
 pub fn create_design_doc_section_relation_cmd(
+    session_state: State<'_, SessionState>,
     source_section_id: String,
     target_section_id: String,
     relation_type: String,
     description: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    session_state.require_permission(Permission::Write)?;
     match create_design_doc_section_relation(
         source_section_id,
         target_section_id,
@@ -137,10 +146,12 @@ pub fn create_design_doc_section_relation_cmd(
 /// セクション関係を更新
 #[tauri::command]
 pub fn update_design_doc_section_relation_cmd(
+    session_state: State<'_, SessionState>,
     id: String,
     relation_type: Option<String>,
     description: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    session_state.require_permission(Permission::Write)?;
     match update_design_doc_section_relation(&id, relation_type, description) {
         Ok(relation) => Ok(serde_json::to_value(relation).unwrap()),
         Err(e) => Err(format!("セクション関係の更新に失敗しました: {}", e)),
@@ -176,7 +187,8 @@ pub fn get_all_design_doc_section_relations_cmd() -> Result<Vec<serde_json::Valu
 
 /// セクション関係を削除
 #[tauri::command]
-pub fn delete_design_doc_section_relation_cmd(id: String) -> Result<(), String> {
+pub fn delete_design_doc_section_relation_cmd(session_state: State<'_, SessionState>, id: String) -> Result<(), String> {
+    session_state.require_permission(Permission::Delete)?;
     match delete_design_doc_section_relation(&id) {
         Ok(_) => Ok(()),
         Err(e) => Err(format!("セクション関係の削除に失敗しました: {}", e)),
@@ -0,0 +1,16 @@
+use crate::database::{
+    check_embedding_model as db_check_embedding_model, generate_embedding as db_generate_embedding,
+    EmbeddingModelInfo,
+};
+
+/// テキストから埋め込みベクトルを生成（AI設定のプロバイダーを使用）
+#[tauri::command]
+pub async fn generate_embedding(text: String) -> Result<Vec<f32>, String> {
+    db_generate_embedding(&text).await
+}
+
+/// 設定中の埋め込みモデルの次元数が EMBEDDING_DIMENSION と一致するか確認
+#[tauri::command]
+pub async fn check_embedding_model() -> Result<EmbeddingModelInfo, String> {
+    db_check_embedding_model().await
+}
@@ -2,7 +2,8 @@ use std::fs;
 use std::path::Path;
 use std::collections::HashMap;
 use serde_json::Value;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, State};
+use crate::database::{Permission, SessionState};
 
 #[tauri::command]
 pub async fn read_file(file_path: String) -> Result<HashMap<String, Value>, String> {
@@ -23,7 +24,8 @@ pub async fn read_file(file_path: String) -> Result<HashMap<String, Value>, Stri
 }
 
 #[tauri::command]
-pub async fn write_file(file_path: String, data: String) -> Result<HashMap<String, Value>, String> {
+pub async fn write_file(session_state: State<'_, SessionState>, file_path: String, data: String) -> Result<HashMap<String, Value>, String> {
+    session_state.require_permission(Permission::Write)?;
     // 親ディレクトリが存在しない場合は作成
     if let Some(parent) = Path::new(&file_path).parent() {
         if let Err(e) = fs::create_dir_all(parent) {
@@ -60,12 +62,14 @@ pub async fn file_exists(file_path: String) -> Result<HashMap<String, Value>, St
 /// 画像ファイルをローカルに保存
 #[tauri::command]
 pub async fn save_image_file(
+    session_state: State<'_, SessionState>,
     app: AppHandle,
     plan_id: String,
     plan_type: String,
     file_name: String,
     image_data: Vec<u8>,
 ) -> Result<HashMap<String, Value>, String> {
+    session_state.require_permission(Permission::Write)?;
     // アプリデータディレクトリを取得
     let app_data_dir = app.path().app_data_dir()
         .map_err(|e| format!("アプリデータディレクトリ取得エラー: {}", e))?;
@@ -2,11 +2,19 @@ pub mod db;
 pub mod app;
 pub mod dialog;
 pub mod organization;
-// pub mod companies; // 削除（事業会社ページ削除のため）
-// pub mod organization_company_display; // 削除（事業会社ページ削除のため）
+pub mod companies;
+pub mod organization_company_display;
 pub mod fs;
 pub mod chromadb;
+pub mod search;
+pub mod embedding;
 pub mod design_doc;
+pub mod business_plan;
+pub mod api_tokens;
+pub mod users;
+pub mod audit_log;
+pub mod trash;
+pub mod write_queue;
 pub mod plantuml;
 pub mod agent_system;
 pub mod system;
@@ -2,10 +2,10 @@ use crate::database::{
     search_organizations_by_name, get_organizations_by_parent_id, get_organization_tree,
     add_member, update_member, get_member_by_id, get_members_by_organization_id, delete_member,
     get_organization_by_id,
-    update_theme_positions,
     get_all_themes,
     delete_organization,
     get_deletion_targets,
+    with_user_sync, Permission, SessionState,
 };
 use crate::db::{WriteJob, WriteQueueState};
 use serde_json::json;
@@ -14,6 +14,7 @@ use tauri::State;
 
 #[tauri::command]
 pub async fn create_org(
+    session_state: State<'_, SessionState>,
     state: State<'_, WriteQueueState>,
     parent_id: Option<String>,
     name: String,
@@ -24,6 +25,7 @@ pub async fn create_org(
     position: i32,
     org_type: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    let user = session_state.require_permission(Permission::Write)?;
     // UUIDを生成（組織ID）
     let organization_id = uuid::Uuid::new_v4().to_string();
     
@@ -57,11 +59,10 @@ pub async fn create_org(
     }
     
     // 書き込みキューに送信
-    state.tx.send(WriteJob::UpsertOrganization {
+    state.send(WriteJob::UpsertOrganization {
         organization_id: organization_id.clone(),
         payload,
-    }).await
-    .map_err(|e| format!("書き込みキューへの送信に失敗しました: {}", e))?;
+    }, Some(user)).await?;
     
     // 作成された組織の情報を返す（IDと基本情報のみ）
     Ok(json!({
@@ -79,6 +80,7 @@ pub async fn create_org(
 
 #[tauri::command]
 pub async fn update_org(
+    session_state: State<'_, SessionState>,
     state: State<'_, WriteQueueState>,
     id: String,
     name: Option<String>,
@@ -86,6 +88,7 @@ pub async fn update_org(
     description: Option<String>,
     position: Option<i32>,
 ) -> Result<serde_json::Value, String> {
+    let user = session_state.require_permission(Permission::Write)?;
     // 現在の組織情報を取得
     let current_org = get_organization_by_id(&id)
         .map_err(|e| format!("組織の取得に失敗しました: {}", e))?;
@@ -115,11 +118,10 @@ pub async fn update_org(
     }
     
     // 書き込みキューに送信
-    state.tx.send(WriteJob::UpsertOrganization {
+    state.send(WriteJob::UpsertOrganization {
         organization_id: id.clone(),
         payload,
-    }).await
-    .map_err(|e| format!("書き込みキューへの送信に失敗しました: {}", e))?;
+    }, Some(user)).await?;
     
     // 更新後の組織情報を返す
     Ok(json!({
@@ -136,10 +138,12 @@ pub async fn update_org(
 
 #[tauri::command]
 pub async fn update_org_parent(
+    session_state: State<'_, SessionState>,
     state: State<'_, WriteQueueState>,
     id: String,
     parent_id: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    let user = session_state.require_permission(Permission::Write)?;
     // 現在の組織情報を取得
     let current_org = get_organization_by_id(&id)
         .map_err(|e| format!("組織の取得に失敗しました: {}", e))?;
@@ -169,11 +173,10 @@ pub async fn update_org_parent(
     }
     
     // 書き込みキューに送信
-    state.tx.send(WriteJob::UpsertOrganization {
+    state.send(WriteJob::UpsertOrganization {
         organization_id: id.clone(),
         payload,
-    }).await
-    .map_err(|e| format!("書き込みキューへの送信に失敗しました: {}", e))?;
+    }, Some(user)).await?;
     
     // 更新後の組織情報を返す
     Ok(json!({
@@ -222,13 +225,15 @@ pub fn get_org_tree(root_id: Option<String>) -> Result<Vec<serde_json::Value>, S
 
 #[tauri::command]
 pub fn delete_org(
+    session_state: State<'_, SessionState>,
     id: String,
 ) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Delete)?;
     println!("🗑️ [delete_org] Tauriコマンド呼び出し: id={}", id);
     
     // 削除処理を同期的に実行（書き込みキューを使わない）
     // 削除処理は重要な操作なので、完了を確認する必要がある
-    match delete_organization(&id) {
+    match with_user_sync(user, || delete_organization(&id)) {
         Ok(_) => {
             println!("✅ [delete_org] 削除成功: id={}", id);
             Ok(())
@@ -242,6 +247,7 @@ pub fn delete_org(
 
 #[tauri::command]
 pub fn add_org_member(
+    session_state: State<'_, SessionState>,
     organization_id: String,
     name: String,
     position: Option<String>,
@@ -260,11 +266,12 @@ pub fn add_org_member(
     floor_door_no: Option<String>,
     previous_name: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    match add_member(
+    let user = session_state.require_permission(Permission::Write)?;
+    match with_user_sync(user, || add_member(
         organization_id, name, position, name_romaji, department, extension,
         company_phone, mobile_phone, email, itochu_email, teams, employee_type,
         role_name, indicator, location, floor_door_no, previous_name
-    ) {
+    )) {
         Ok(member) => Ok(serde_json::to_value(member).unwrap()),
         Err(e) => Err(format!("メンバーの追加に失敗しました: {}", e)),
     }
@@ -272,6 +279,7 @@ pub fn add_org_member(
 
 #[tauri::command]
 pub fn update_org_member(
+    session_state: State<'_, SessionState>,
     id: String,
     name: Option<String>,
     position: Option<String>,
@@ -290,11 +298,12 @@ pub fn update_org_member(
     floor_door_no: Option<String>,
     previous_name: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    match update_member(
+    let user = session_state.require_permission(Permission::Write)?;
+    match with_user_sync(user, || update_member(
         &id, name, position, name_romaji, department, extension,
         company_phone, mobile_phone, email, itochu_email, teams, employee_type,
         role_name, indicator, location, floor_door_no, previous_name
-    ) {
+    )) {
         Ok(member) => Ok(serde_json::to_value(member).unwrap()),
         Err(e) => Err(format!("メンバーの更新に失敗しました: {}", e)),
     }
@@ -324,8 +333,9 @@ pub fn get_org_members(organization_id: String) -> Result<Vec<serde_json::Value>
 }
 
 #[tauri::command]
-pub fn delete_org_member(id: String) -> Result<(), String> {
-    match delete_member(&id) {
+pub fn delete_org_member(session_state: State<'_, SessionState>, id: String) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Delete)?;
+    match with_user_sync(user, || delete_member(&id)) {
         Ok(_) => Ok(()),
         Err(e) => Err(format!("メンバーの削除に失敗しました: {}", e)),
     }
@@ -336,9 +346,12 @@ pub fn delete_org_member(id: String) -> Result<(), String> {
 /// 複数のテーマのpositionを一括更新
 #[tauri::command]
 pub async fn update_theme_positions_cmd(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
     updates: Vec<(String, i32)>,
 ) -> Result<(), String> {
-    update_theme_positions(&updates)
+    let user = session_state.require_permission(Permission::Write)?;
+    state.send(WriteJob::UpdateThemePositions { updates }, Some(user)).await
         .map_err(|e| format!("テーマ順序の更新に失敗しました: {}", e))?;
     Ok(())
 }
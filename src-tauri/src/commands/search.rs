@@ -0,0 +1,26 @@
+use crate::database::{
+    hybrid_search as db_hybrid_search, rebuild_search_index as db_rebuild_search_index,
+    HybridSearchResult, SearchTarget, Permission, SessionState,
+};
+use tauri::State;
+
+/// ハイブリッド検索（BM25 + ベクトル類似度をRRFで統合）
+#[tauri::command]
+pub async fn hybrid_search(
+    target: String,
+    query: String,
+    query_embedding: Option<Vec<f32>>,
+    limit: Option<usize>,
+    organization_id: Option<String>,
+) -> Result<Vec<HybridSearchResult>, String> {
+    let target = SearchTarget::from_str(&target)
+        .ok_or_else(|| format!("検索対象が不正です: {}（entity / relation / topic のいずれかを指定してください）", target))?;
+    db_hybrid_search(target, query, query_embedding, limit.unwrap_or(10), organization_id).await
+}
+
+/// 全文検索インデックスを再構築
+#[tauri::command]
+pub async fn rebuild_search_index(session_state: State<'_, SessionState>) -> Result<(), String> {
+    session_state.require_permission(Permission::Write)?;
+    db_rebuild_search_index().map_err(|e| format!("全文検索インデックスの再構築に失敗しました: {}", e))
+}
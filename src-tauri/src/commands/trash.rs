@@ -0,0 +1,50 @@
+/**
+ * ゴミ箱のTauriコマンド
+ * 一覧・復元は削除権限（editor 以上）、完全削除は管理者（admin）のみ実行できる
+ */
+
+use crate::database::{
+    list_trash, restore_from_trash, purge_trash_item, remove_purged_embeddings, with_user_sync,
+    Permission, SessionState,
+};
+use serde_json::Value;
+use tauri::State;
+
+/// ゴミ箱の項目一覧を取得（item_type: organization / meetingNote / テーブル名）
+#[tauri::command]
+pub fn list_trash_cmd(
+    session_state: State<'_, SessionState>,
+    item_type: Option<String>,
+) -> Result<Vec<Value>, String> {
+    session_state.require_permission(Permission::Delete)?;
+    match list_trash(item_type.as_deref()) {
+        Ok(items) => Ok(items.into_iter().map(|i| serde_json::to_value(i).unwrap()).collect()),
+        Err(e) => Err(format!("ゴミ箱の取得に失敗しました: {}", e)),
+    }
+}
+
+/// ゴミ箱の項目を復元
+#[tauri::command]
+pub fn restore_trash_item_cmd(
+    session_state: State<'_, SessionState>,
+    trash_id: String,
+) -> Result<Value, String> {
+    let user = session_state.require_permission(Permission::Delete)?;
+    match with_user_sync(user, || restore_from_trash(&trash_id)) {
+        Ok(item) => Ok(serde_json::to_value(item).unwrap()),
+        Err(e) => Err(format!("ゴミ箱からの復元に失敗しました: {}", e)),
+    }
+}
+
+/// ゴミ箱の項目を完全削除（ベクトルストアの埋め込みも削除）
+#[tauri::command]
+pub async fn purge_trash_item_cmd(
+    session_state: State<'_, SessionState>,
+    trash_id: String,
+) -> Result<(), String> {
+    let user = session_state.require_permission(Permission::Admin)?;
+    let purged = with_user_sync(user, || purge_trash_item(&trash_id))
+        .map_err(|e| format!("ゴミ箱の項目の完全削除に失敗しました: {}", e))?;
+    remove_purged_embeddings(purged).await;
+    Ok(())
+}
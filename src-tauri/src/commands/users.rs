@@ -0,0 +1,101 @@
+/**
+ * 利用者の承認・ロール管理のTauriコマンド
+ * 承認リクエストの一覧・承認・却下、ロールの変更は管理者（admin）のみ実行できる
+ */
+
+use crate::database::{
+    list_approval_requests, approve_user, reject_user, list_users, set_user_role, get_user_role,
+    Permission, Role, SessionState,
+};
+use serde_json::{json, Value};
+use tauri::State;
+
+/// ロール名を解析（旧ロール 'user' は指定できない）
+fn parse_role(role: &str) -> Result<Role, String> {
+    Role::from_str(role)
+        .filter(|_| role.trim() != "user")
+        .ok_or_else(|| format!("無効なロールです: {}（admin / editor / viewer）", role))
+}
+
+/// サインイン中のユーザーのロールを取得
+#[tauri::command]
+pub fn get_current_user_role(session_state: State<'_, SessionState>) -> Result<Value, String> {
+    let user = session_state.require_user()?;
+    let role = get_user_role(&user.uid)
+        .map_err(|e| format!("ユーザーロールの取得に失敗しました: {}", e))?;
+    Ok(json!({
+        "role": role,
+        "canWrite": role.allows(Permission::Write),
+        "canDelete": role.allows(Permission::Delete),
+        "isAdmin": role.allows(Permission::Admin),
+    }))
+}
+
+/// 承認リクエスト一覧を取得（status を省略した場合は承認待ちのみ）
+#[tauri::command]
+pub fn list_approval_requests_cmd(
+    session_state: State<'_, SessionState>,
+    status: Option<String>,
+) -> Result<Vec<Value>, String> {
+    session_state.require_permission(Permission::Admin)?;
+    let status = status.unwrap_or_else(|| "pending".to_string());
+    let status = if status == "all" { None } else { Some(status.as_str()) };
+    match list_approval_requests(status) {
+        Ok(requests) => Ok(requests.into_iter().map(|r| serde_json::to_value(r).unwrap()).collect()),
+        Err(e) => Err(format!("承認リクエスト一覧の取得に失敗しました: {}", e)),
+    }
+}
+
+/// 承認リクエストを承認（role を省略した場合は viewer）
+#[tauri::command]
+pub fn approve_user_cmd(
+    session_state: State<'_, SessionState>,
+    request_id: String,
+    role: Option<String>,
+) -> Result<Value, String> {
+    let admin = session_state.require_permission(Permission::Admin)?;
+    let role = role.as_deref().map(parse_role).transpose()?;
+    match approve_user(&admin, &request_id, role) {
+        Ok(request) => Ok(serde_json::to_value(request).unwrap()),
+        Err(e) => Err(format!("利用者の承認に失敗しました: {}", e)),
+    }
+}
+
+/// 承認リクエストを却下
+#[tauri::command]
+pub fn reject_user_cmd(
+    session_state: State<'_, SessionState>,
+    request_id: String,
+    reason: Option<String>,
+) -> Result<Value, String> {
+    let admin = session_state.require_permission(Permission::Admin)?;
+    match reject_user(&admin, &request_id, reason.as_deref()) {
+        Ok(request) => Ok(serde_json::to_value(request).unwrap()),
+        Err(e) => Err(format!("利用者の却下に失敗しました: {}", e)),
+    }
+}
+
+/// 利用者一覧を取得
+#[tauri::command]
+pub fn list_users_cmd(session_state: State<'_, SessionState>) -> Result<Vec<Value>, String> {
+    session_state.require_permission(Permission::Admin)?;
+    match list_users() {
+        Ok(users) => Ok(users.into_iter().map(|u| serde_json::to_value(u).unwrap()).collect()),
+        Err(e) => Err(format!("利用者一覧の取得に失敗しました: {}", e)),
+    }
+}
+
+/// 利用者のロールを変更
+#[tauri::command]
+pub fn update_user_role_cmd(
+    session_state: State<'_, SessionState>,
+    user_id: String,
+    role: String,
+) -> Result<Value, String> {
+    session_state.require_permission(Permission::Admin)?;
+    let role = parse_role(&role)?;
+    match set_user_role(&user_id, role) {
+        Ok(user) => Ok(serde_json::to_value(user).unwrap()),
+        Err(e) => Err(format!("ロールの変更に失敗しました: {}", e)),
+    }
+}
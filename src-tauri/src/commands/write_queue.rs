@@ -0,0 +1,75 @@
+/**
+ * 書き込みキューのTauriコマンド
+ * ジョブの一括送信と、メトリクス・リトライしても失敗した書き込みジョブ（デッドレター）の確認・再実行・破棄（管理者のみ）を行う
+ */
+
+use crate::database::{Permission, SessionState};
+use crate::db::{
+    count_dead_letter_jobs, discard_dead_letter_job, list_dead_letter_jobs, write_queue_metrics,
+    WriteJob, WriteQueueState,
+};
+use serde_json::{json, Value};
+use tauri::State;
+
+/// 書き込みジョブをまとめて送信し、すべての処理結果を待つ（ナレッジグラフの一括取り込みなど）
+/// 削除系のジョブを含む場合は削除権限が必要
+#[tauri::command]
+pub async fn submit_write_jobs_cmd(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    jobs: Vec<WriteJob>,
+) -> Result<(), String> {
+    let permission = if jobs.iter().any(|job| job.is_delete()) { Permission::Delete } else { Permission::Write };
+    let user = session_state.require_permission(permission)?;
+    state.send_all(jobs, Some(user)).await
+}
+
+/// 書き込みキューのメトリクスを取得（滞留件数・スループット・デッドレター件数など）
+#[tauri::command]
+pub fn get_write_queue_metrics_cmd(session_state: State<'_, SessionState>) -> Result<Value, String> {
+    session_state.require_permission(Permission::Admin)?;
+    let dead_letters = count_dead_letter_jobs()
+        .map_err(|e| format!("デッドレター件数の取得に失敗しました: {}", e))?;
+    let mut metrics = serde_json::to_value(write_queue_metrics().snapshot()).unwrap();
+    metrics["deadLetters"] = json!(dead_letters);
+    Ok(metrics)
+}
+
+/// デッドレターの一覧を取得（新しい順）
+#[tauri::command]
+pub fn list_dead_letter_jobs_cmd(
+    session_state: State<'_, SessionState>,
+    limit: Option<i64>,
+) -> Result<Vec<Value>, String> {
+    session_state.require_permission(Permission::Admin)?;
+    match list_dead_letter_jobs(limit) {
+        Ok(jobs) => Ok(jobs.into_iter().map(|j| serde_json::to_value(j).unwrap()).collect()),
+        Err(e) => Err(format!("デッドレターの取得に失敗しました: {}", e)),
+    }
+}
+
+/// デッドレターのジョブを再実行（結果を待つ。再び失敗した場合はデッドレターに戻る）
+#[tauri::command]
+pub async fn retry_dead_letter_job_cmd(
+    session_state: State<'_, SessionState>,
+    state: State<'_, WriteQueueState>,
+    id: String,
+) -> Result<(), String> {
+    session_state.require_permission(Permission::Admin)?;
+    state.retry_dead_letter(&id).await
+        .map_err(|e| format!("デッドレターの再実行に失敗しました: {}", e))
+}
+
+/// デッドレターを破棄
+#[tauri::command]
+pub fn discard_dead_letter_job_cmd(
+    session_state: State<'_, SessionState>,
+    id: String,
+) -> Result<(), String> {
+    session_state.require_permission(Permission::Admin)?;
+    match discard_dead_letter_job(&id) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("デッドレターが見つかりません: {}", id)),
+        Err(e) => Err(format!("デッドレターの破棄に失敗しました: {}", e)),
+    }
+}
@@ -0,0 +1,438 @@
+/**
+ * A2A（Agent間）メッセージバス
+ * a2aMessages テーブルを使って、Agent間のメッセージの送信・受信・返信を管理する
+ *
+ * - 送信したメッセージは送信先Agentの受信箱（toAgent = Agent ID の未読メッセージ）に入る。受信すると readAt を設定する
+ * - 返信は responseTo に元のメッセージIDを指定して送る（reply_a2a_message は送信先・taskId を元のメッセージから引き継ぐ）
+ * - requiresResponse のメッセージは responseStatus = "pending" とし、期限（responseDeadline）までに返信がなければ "timed_out" にする
+ *   期限までに返信があれば "answered" にする（期限後の返信も履歴には残す）
+ * - 送信・返信・タイムアウトは Tauriイベント "a2a-message" で通知する
+ * - 履歴は taskId・Agent ID で検索でき、responseTo で会話をたどれる
+ */
+
+use crate::database::{get_db, Timestamp};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::watch;
+
+/// 通知用のTauriイベント名
+pub const A2A_MESSAGE_EVENT: &str = "a2a-message";
+
+/// メッセージの種類（フロントエンドの A2AMessageType と同じ）
+pub const A2A_MESSAGE_TYPES: &[&str] = &["request", "response", "notification", "confirmation", "status_update"];
+/// 返信の期限のデフォルト（ミリ秒）
+pub const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 60_000;
+/// 返信の期限の上限（ミリ秒）
+const MAX_RESPONSE_TIMEOUT_MS: u64 = 24 * 60 * 60 * 1000;
+/// 期限切れを確認する間隔
+const TIMEOUT_CHECK_INTERVAL_SECS: u64 = 5;
+/// 1回に取得する最大件数
+const MAX_MESSAGES: i64 = 1000;
+
+/// A2Aメッセージ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct A2AMessage {
+    pub id: String,
+    /// 送信元Agent ID
+    pub from: String,
+    /// 送信先Agent ID
+    pub to: String,
+    #[serde(rename = "type")]
+    pub message_type: String,
+    #[serde(rename = "taskId", skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    pub payload: Value,
+    /// 送信日時（Unixミリ秒、フロントエンドの A2AMessage と同じ）
+    pub timestamp: i64,
+    #[serde(rename = "responseTo", skip_serializing_if = "Option::is_none")]
+    pub response_to: Option<String>,
+    #[serde(rename = "requiresResponse")]
+    pub requires_response: bool,
+    /// pending / answered / timed_out（返信が不要な場合は None）
+    #[serde(rename = "responseStatus", skip_serializing_if = "Option::is_none")]
+    pub response_status: Option<String>,
+    #[serde(rename = "responseDeadline", skip_serializing_if = "Option::is_none")]
+    pub response_deadline: Option<Timestamp>,
+    #[serde(rename = "readAt", skip_serializing_if = "Option::is_none")]
+    pub read_at: Option<Timestamp>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Timestamp,
+}
+
+/// 送信するメッセージ
+#[derive(Debug, Clone, Deserialize)]
+pub struct SendA2AMessage {
+    /// 省略した場合は生成する
+    #[serde(default)]
+    pub id: Option<String>,
+    pub from: String,
+    pub to: String,
+    #[serde(rename = "type")]
+    pub message_type: String,
+    #[serde(rename = "taskId", default)]
+    pub task_id: Option<String>,
+    #[serde(default)]
+    pub payload: Value,
+    #[serde(rename = "responseTo", default)]
+    pub response_to: Option<String>,
+    #[serde(rename = "requiresResponse", default)]
+    pub requires_response: bool,
+    /// 返信の期限（ミリ秒、デフォルト60秒）
+    #[serde(rename = "responseTimeoutMs", default)]
+    pub response_timeout_ms: Option<u64>,
+}
+
+/// 履歴の検索条件
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct A2AHistoryQuery {
+    #[serde(rename = "taskId", default)]
+    pub task_id: Option<String>,
+    /// 送信元または送信先
+    #[serde(rename = "agentId", default)]
+    pub agent_id: Option<String>,
+    #[serde(rename = "type", default)]
+    pub message_type: Option<String>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+/// 通知（Tauriイベントで送る）
+#[derive(Debug, Clone, Serialize)]
+pub struct A2AMessageEvent {
+    /// sent / timed_out
+    pub kind: String,
+    pub message: A2AMessage,
+}
+
+static LATEST_MESSAGE: OnceLock<watch::Sender<u64>> = OnceLock::new();
+/// イベント送信用（ワーカーの起動時に設定する。REST API から送信した場合も通知するため）
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// 送信・タイムアウトのたびに値を進める（返信を待っている呼び出し側を起こす）
+fn latest_message() -> &'static watch::Sender<u64> {
+    LATEST_MESSAGE.get_or_init(|| watch::channel(0).0)
+}
+
+fn notify_waiters() {
+    latest_message().send_modify(|n| *n = n.wrapping_add(1));
+}
+
+fn not_initialized() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    )
+}
+
+const MESSAGE_COLUMNS: &str = "id, fromAgent, toAgent, type, taskId, payload, timestamp, responseTo, requiresResponse,
+    responseStatus, responseDeadline, readAt, createdAt";
+
+fn row_to_message(row: &rusqlite::Row) -> SqlResult<A2AMessage> {
+    let payload: String = row.get(5)?;
+    let requires_response: Option<i32> = row.get(8)?;
+    Ok(A2AMessage {
+        id: row.get(0)?,
+        from: row.get(1)?,
+        to: row.get(2)?,
+        message_type: row.get(3)?,
+        task_id: row.get(4)?,
+        payload: serde_json::from_str(&payload).unwrap_or(Value::String(payload)),
+        timestamp: row.get(6)?,
+        response_to: row.get(7)?,
+        requires_response: requires_response.unwrap_or(0) != 0,
+        response_status: row.get(9)?,
+        response_deadline: row.get(10)?,
+        read_at: row.get(11)?,
+        created_at: row.get(12)?,
+    })
+}
+
+/// メッセージを送信（受信箱に入れる）
+pub fn send_a2a_message(input: SendA2AMessage) -> Result<A2AMessage, String> {
+    if input.from.trim().is_empty() || input.to.trim().is_empty() {
+        return Err("from と to を指定してください".to_string());
+    }
+    if !A2A_MESSAGE_TYPES.contains(&input.message_type.as_str()) {
+        return Err(format!("type は {} のいずれかを指定してください", A2A_MESSAGE_TYPES.join(" / ")));
+    }
+
+    let db = get_db().ok_or_else(|| "データベースが初期化されていません".to_string())?;
+    let mut conn = db.get_connection().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let now = Timestamp::now();
+    let mut task_id = input.task_id.clone();
+    if let Some(response_to) = &input.response_to {
+        let original = tx.query_row(
+            &format!("SELECT {} FROM a2aMessages WHERE id = ?1", MESSAGE_COLUMNS),
+            params![response_to],
+            row_to_message,
+        ).optional().map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("返信先のメッセージが見つかりません: {}", response_to))?;
+        if original.from != input.to {
+            return Err(format!("返信は元のメッセージの送信元（{}）に送ってください", original.from));
+        }
+        // 会話は同じタスクの履歴に残す
+        task_id = task_id.or(original.task_id);
+        let in_time = original.response_deadline.map_or(true, |deadline| now <= deadline);
+        if original.response_status.as_deref() == Some("pending") && in_time {
+            tx.execute(
+                "UPDATE a2aMessages SET responseStatus = 'answered' WHERE id = ?1",
+                params![response_to],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let (response_status, response_deadline) = if input.requires_response {
+        let timeout_ms = input.response_timeout_ms.unwrap_or(DEFAULT_RESPONSE_TIMEOUT_MS).min(MAX_RESPONSE_TIMEOUT_MS);
+        let deadline = now.unix_millis().checked_add(timeout_ms as i64)
+            .and_then(Timestamp::from_unix_millis)
+            .ok_or_else(|| format!("応答期限を計算できません: responseTimeoutMs={}", timeout_ms))?;
+        (Some("pending"), Some(deadline))
+    } else {
+        (None, None)
+    };
+
+    let id = input.id.filter(|id| !id.is_empty()).unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    tx.execute(
+        "INSERT INTO a2aMessages (id, fromAgent, toAgent, type, taskId, payload, timestamp, responseTo, requiresResponse,
+            responseStatus, responseDeadline, readAt, createdAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, NULL, ?12)",
+        params![
+            id,
+            input.from,
+            input.to,
+            input.message_type,
+            task_id,
+            input.payload.to_string(),
+            now.unix_millis(),
+            input.response_to,
+            if input.requires_response { 1 } else { 0 },
+            response_status,
+            response_deadline,
+            now,
+        ],
+    ).map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("同じIDのメッセージが既にあります: {}", id)
+        }
+        e => e.to_string(),
+    })?;
+    tx.commit().map_err(|e| e.to_string())?;
+    notify_waiters();
+
+    let message = get_a2a_message(&id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "メッセージの保存後に取得に失敗しました".to_string())?;
+    emit_a2a_message("sent", &message);
+    Ok(message)
+}
+
+/// メッセージに返信（送信先・taskId は元のメッセージから引き継ぐ）
+pub fn reply_a2a_message(message_id: &str, from: &str, payload: Value) -> Result<A2AMessage, String> {
+    let original = get_a2a_message(message_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("メッセージが見つかりません: {}", message_id))?;
+    if original.to != from {
+        return Err(format!("メッセージの送信先（{}）以外は返信できません", original.to));
+    }
+    send_a2a_message(SendA2AMessage {
+        id: None,
+        from: from.to_string(),
+        to: original.from,
+        message_type: "response".to_string(),
+        task_id: original.task_id,
+        payload,
+        response_to: Some(original.id),
+        requires_response: false,
+        response_timeout_ms: None,
+    })
+}
+
+/// メッセージを取得
+pub fn get_a2a_message(id: &str) -> SqlResult<Option<A2AMessage>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.query_row(
+        &format!("SELECT {} FROM a2aMessages WHERE id = ?1", MESSAGE_COLUMNS),
+        params![id],
+        row_to_message,
+    ).optional()
+}
+
+/// 受信箱を取得（古い順、unread_only の場合は未読のみ。既読にはしない）
+pub fn get_a2a_inbox(agent_id: &str, unread_only: bool, limit: i64) -> SqlResult<Vec<A2AMessage>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM a2aMessages WHERE toAgent = ?1{} ORDER BY timestamp ASC, rowid ASC LIMIT ?2",
+        MESSAGE_COLUMNS,
+        if unread_only { " AND readAt IS NULL" } else { "" }
+    ))?;
+    let messages = stmt.query_map(params![agent_id, limit.clamp(1, MAX_MESSAGES)], row_to_message)?;
+    messages.collect()
+}
+
+/// 未読のメッセージを受信（古い順に取得して既読にする）
+pub fn receive_a2a_messages(agent_id: &str, limit: i64) -> SqlResult<Vec<A2AMessage>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let mut conn = db.get_connection()?;
+    let tx = conn.transaction()?;
+
+    let mut messages = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT {} FROM a2aMessages WHERE toAgent = ?1 AND readAt IS NULL
+             ORDER BY timestamp ASC, rowid ASC LIMIT ?2",
+            MESSAGE_COLUMNS
+        ))?;
+        let messages = stmt.query_map(params![agent_id, limit.clamp(1, MAX_MESSAGES)], row_to_message)?;
+        messages.collect::<SqlResult<Vec<_>>>()?
+    };
+
+    let now = Timestamp::now();
+    for message in &mut messages {
+        tx.execute("UPDATE a2aMessages SET readAt = ?1 WHERE id = ?2", params![now, message.id])?;
+        message.read_at = Some(now);
+    }
+    tx.commit()?;
+    Ok(messages)
+}
+
+/// 履歴を検索（古い順）
+pub fn get_a2a_history(query: &A2AHistoryQuery) -> SqlResult<Vec<A2AMessage>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut clauses = Vec::new();
+    let mut params = Vec::new();
+    if let Some(task_id) = &query.task_id {
+        clauses.push("taskId = ?");
+        params.push(SqlValue::Text(task_id.clone()));
+    }
+    if let Some(agent_id) = &query.agent_id {
+        clauses.push("(fromAgent = ? OR toAgent = ?)");
+        params.push(SqlValue::Text(agent_id.clone()));
+        params.push(SqlValue::Text(agent_id.clone()));
+    }
+    if let Some(message_type) = &query.message_type {
+        clauses.push("type = ?");
+        params.push(SqlValue::Text(message_type.clone()));
+    }
+    let where_clause = if clauses.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", clauses.join(" AND "))
+    };
+    params.push(SqlValue::Integer(query.limit.unwrap_or(MAX_MESSAGES).clamp(1, MAX_MESSAGES)));
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM a2aMessages{} ORDER BY timestamp ASC, rowid ASC LIMIT ?",
+        MESSAGE_COLUMNS, where_clause
+    ))?;
+    let messages = stmt.query_map(params_from_iter(params), row_to_message)?;
+    messages.collect()
+}
+
+/// 最初の返信を取得
+fn get_first_response(message_id: &str) -> SqlResult<Option<A2AMessage>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM a2aMessages WHERE responseTo = ?1 ORDER BY timestamp ASC, rowid ASC LIMIT 1",
+            MESSAGE_COLUMNS
+        ),
+        params![message_id],
+        row_to_message,
+    ).optional()
+}
+
+/// 返信を待つ（返信があれば返し、timeout またはメッセージの期限を過ぎた場合は None）
+pub async fn wait_for_a2a_response(message_id: &str, timeout: Duration) -> Result<Option<A2AMessage>, String> {
+    let message = get_a2a_message(message_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("メッセージが見つかりません: {}", message_id))?;
+
+    let mut deadline = tokio::time::Instant::now() + timeout;
+    if let Some(response_deadline) = message.response_deadline {
+        let remaining = (response_deadline.unix_millis() - Timestamp::now().unix_millis()).max(0) as u64;
+        deadline = deadline.min(tokio::time::Instant::now() + Duration::from_millis(remaining));
+    }
+
+    let mut latest = latest_message().subscribe();
+    loop {
+        if let Some(response) = get_first_response(message_id).map_err(|e| e.to_string())? {
+            return Ok(Some(response));
+        }
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => return Ok(None),
+            changed = latest.changed() => {
+                if changed.is_err() {
+                    tokio::time::sleep_until(deadline).await;
+                    return get_first_response(message_id).map_err(|e| e.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn emit_a2a_message(kind: &str, message: &A2AMessage) {
+    let Some(app) = APP_HANDLE.get() else { return };
+    let event = A2AMessageEvent { kind: kind.to_string(), message: message.clone() };
+    if let Err(e) = app.emit(A2A_MESSAGE_EVENT, &event) {
+        eprintln!("[A2A] ⚠️ メッセージイベントの送信に失敗しました: {}", e);
+    }
+}
+
+/// 返信の期限を過ぎたメッセージを timed_out にするワーカーを起動
+pub fn start_a2a_timeout_worker(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match expire_pending_responses() {
+                Ok(expired) => {
+                    if !expired.is_empty() {
+                        notify_waiters();
+                    }
+                    for message in &expired {
+                        emit_a2a_message("timed_out", message);
+                    }
+                }
+                Err(e) => eprintln!("[A2A] ⚠️ 返信期限の確認に失敗しました: {}", e),
+            }
+            tokio::time::sleep(Duration::from_secs(TIMEOUT_CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+/// 期限を過ぎた pending のメッセージを timed_out にして返す
+fn expire_pending_responses() -> SqlResult<Vec<A2AMessage>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let mut conn = db.get_connection()?;
+    let tx = conn.transaction()?;
+
+    let now = Timestamp::now();
+    let mut expired = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT {} FROM a2aMessages WHERE responseStatus = 'pending' AND responseDeadline <= ?1",
+            MESSAGE_COLUMNS
+        ))?;
+        let messages = stmt.query_map(params![now], row_to_message)?;
+        messages.collect::<SqlResult<Vec<_>>>()?
+    };
+    for message in &mut expired {
+        tx.execute(
+            "UPDATE a2aMessages SET responseStatus = 'timed_out' WHERE id = ?1 AND responseStatus = 'pending'",
+            params![message.id],
+        )?;
+        message.response_status = Some("timed_out".to_string());
+    }
+    tx.commit()?;
+    Ok(expired)
+}
@@ -1,6 +1,7 @@
-use rusqlite::{params, Result as SqlResult};
+use rusqlite::{params, Connection, Result as SqlResult};
 use serde::{Deserialize, Serialize};
-use crate::database::{get_db, get_timestamp};
+use crate::database::{get_db, get_timestamp, snapshot_row, audit_change, AuditAction, Timestamp};
+use crate::database::prompt_versions::record_prompt_version;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -30,6 +31,11 @@ pub struct Task {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "selectedModel")]
     pub selected_model: Option<String>,
+    /// 固定したシステムプロンプトのバージョン（None の場合はAgentの現在のバージョン）
+    /// save_task では変更せず、SetTaskPromptVersion ジョブで変更する
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "promptVersion")]
+    pub prompt_version: Option<i64>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
     #[serde(rename = "updatedAt")]
@@ -54,6 +60,10 @@ pub struct TaskExecution {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     pub logs: String, // JSON文字列
+    /// 実行に使用したシステムプロンプトのバージョン
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "promptVersion")]
+    pub prompt_version: Option<i64>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
     #[serde(rename = "updatedAt")]
@@ -70,6 +80,23 @@ pub fn save_task(task: &Task) -> SqlResult<Task> {
     })?;
 
     let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    save_task_in(&tx, task)?;
+    tx.commit()?;
+
+    // 更新後のタスクを取得
+    get_task(&task.id)
+        .and_then(|opt| opt.ok_or_else(|| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+                Some("タスクの保存後に取得に失敗しました".to_string()),
+            )
+        }))
+}
+
+/// タスクを呼び出し側のトランザクション内で保存（書き込みワーカー用）
+pub(crate) fn save_task_in(conn: &Connection, task: &Task) -> SqlResult<()> {
+    let before = snapshot_row(conn, "tasks", &task.id)?;
     let now = get_timestamp();
 
     // 既存のタスクを確認
@@ -123,14 +150,10 @@ pub fn save_task(task: &Task) -> SqlResult<Task> {
         )?;
     }
 
-    // 更新後のタスクを取得
-    get_task(&task.id)
-        .and_then(|opt| opt.ok_or_else(|| {
-            rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                Some("タスクの保存後に取得に失敗しました".to_string()),
-            )
-        }))
+    let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+    audit_change(conn, action, "tasks", &task.id, before)?;
+
+    Ok(())
 }
 
 /// タスクを取得
@@ -145,7 +168,7 @@ pub fn get_task(id: &str) -> SqlResult<Option<Task>> {
     let conn = db.get_connection()?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, name, description, type, agentId, requiredAgents, dependencies, parameters, priority, timeout, retryCount, modelType, selectedModel, createdAt, updatedAt
+        "SELECT id, name, description, type, agentId, requiredAgents, dependencies, parameters, priority, timeout, retryCount, modelType, selectedModel, promptVersion, createdAt, updatedAt
          FROM tasks WHERE id = ?1"
     )?;
 
@@ -164,8 +187,9 @@ pub fn get_task(id: &str) -> SqlResult<Option<Task>> {
             retry_count: row.get(10)?,
             model_type: row.get(11)?,
             selected_model: row.get(12)?,
-            created_at: row.get(13)?,
-            updated_at: row.get(14)?,
+            prompt_version: row.get(13)?,
+            created_at: row.get(14)?,
+            updated_at: row.get(15)?,
         })
     });
 
@@ -188,7 +212,7 @@ pub fn get_all_tasks() -> SqlResult<Vec<Task>> {
     let conn = db.get_connection()?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, name, description, type, agentId, requiredAgents, dependencies, parameters, priority, timeout, retryCount, modelType, selectedModel, createdAt, updatedAt
+        "SELECT id, name, description, type, agentId, requiredAgents, dependencies, parameters, priority, timeout, retryCount, modelType, selectedModel, promptVersion, createdAt, updatedAt
          FROM tasks ORDER BY createdAt DESC"
     )?;
 
@@ -207,8 +231,9 @@ pub fn get_all_tasks() -> SqlResult<Vec<Task>> {
             retry_count: row.get(10)?,
             model_type: row.get(11)?,
             selected_model: row.get(12)?,
-            created_at: row.get(13)?,
-            updated_at: row.get(14)?,
+            prompt_version: row.get(13)?,
+            created_at: row.get(14)?,
+            updated_at: row.get(15)?,
         })
     })?;
 
@@ -230,12 +255,24 @@ pub fn delete_task(id: &str) -> SqlResult<()> {
     })?;
 
     let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    delete_task_in(&tx, id)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// タスクを呼び出し側のトランザクション内で削除（書き込みワーカー用）
+pub(crate) fn delete_task_in(conn: &Connection, id: &str) -> SqlResult<()> {
+    let before = snapshot_row(conn, "tasks", id)?;
 
     conn.execute(
         "DELETE FROM tasks WHERE id = ?1",
         params![id],
     )?;
 
+    audit_change(conn, AuditAction::Delete, "tasks", id, before)?;
+
     Ok(())
 }
 
@@ -249,6 +286,23 @@ pub fn save_task_execution(execution: &TaskExecution) -> SqlResult<TaskExecution
     })?;
 
     let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    save_task_execution_in(&tx, execution)?;
+    tx.commit()?;
+
+    // 更新後の実行を取得
+    get_task_execution(&execution.id)
+        .and_then(|opt| opt.ok_or_else(|| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+                Some("タスク実行の保存後に取得に失敗しました".to_string()),
+            )
+        }))
+}
+
+/// タスク実行を呼び出し側のトランザクション内で保存（書き込みワーカー用）
+pub(crate) fn save_task_execution_in(conn: &Connection, execution: &TaskExecution) -> SqlResult<()> {
+    let before = snapshot_row(conn, "taskExecutions", &execution.id)?;
     let now = get_timestamp();
 
     // 既存の実行を確認
@@ -258,8 +312,8 @@ pub fn save_task_execution(execution: &TaskExecution) -> SqlResult<TaskExecution
     if is_new {
         // 新規作成
         conn.execute(
-            "INSERT INTO taskExecutions (id, taskId, agentId, status, startedAt, completedAt, result, error, logs, createdAt, updatedAt)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            "INSERT INTO taskExecutions (id, taskId, agentId, status, startedAt, completedAt, result, error, logs, promptVersion, createdAt, updatedAt)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             params![
                 execution.id,
                 execution.task_id,
@@ -270,6 +324,7 @@ pub fn save_task_execution(execution: &TaskExecution) -> SqlResult<TaskExecution
                 execution.result,
                 execution.error,
                 execution.logs,
+                execution.prompt_version,
                 now,
                 now,
             ],
@@ -277,8 +332,8 @@ pub fn save_task_execution(execution: &TaskExecution) -> SqlResult<TaskExecution
     } else {
         // 更新
         conn.execute(
-            "UPDATE taskExecutions SET taskId = ?1, agentId = ?2, status = ?3, startedAt = ?4, completedAt = ?5, result = ?6, error = ?7, logs = ?8, updatedAt = ?9
-             WHERE id = ?10",
+            "UPDATE taskExecutions SET taskId = ?1, agentId = ?2, status = ?3, startedAt = ?4, completedAt = ?5, result = ?6, error = ?7, logs = ?8, promptVersion = ?9, updatedAt = ?10
+             WHERE id = ?11",
             params![
                 execution.task_id,
                 execution.agent_id,
@@ -288,20 +343,17 @@ pub fn save_task_execution(execution: &TaskExecution) -> SqlResult<TaskExecution
                 execution.result,
                 execution.error,
                 execution.logs,
+                execution.prompt_version,
                 now,
                 execution.id,
             ],
         )?;
     }
 
-    // 更新後の実行を取得
-    get_task_execution(&execution.id)
-        .and_then(|opt| opt.ok_or_else(|| {
-            rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                Some("タスク実行の保存後に取得に失敗しました".to_string()),
-            )
-        }))
+    let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+    audit_change(conn, action, "taskExecutions", &execution.id, before)?;
+
+    Ok(())
 }
 
 /// タスク実行を取得
@@ -316,7 +368,7 @@ pub fn get_task_execution(id: &str) -> SqlResult<Option<TaskExecution>> {
     let conn = db.get_connection()?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, taskId, agentId, status, startedAt, completedAt, result, error, logs, createdAt, updatedAt
+        "SELECT id, taskId, agentId, status, startedAt, completedAt, result, error, logs, promptVersion, createdAt, updatedAt
          FROM taskExecutions WHERE id = ?1"
     )?;
 
@@ -331,8 +383,9 @@ pub fn get_task_execution(id: &str) -> SqlResult<Option<TaskExecution>> {
             result: row.get(6)?,
             error: row.get(7)?,
             logs: row.get(8)?,
-            created_at: row.get(9)?,
-            updated_at: row.get(10)?,
+            prompt_version: row.get(9)?,
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
         })
     });
 
@@ -355,7 +408,7 @@ pub fn get_task_executions(task_id: &str) -> SqlResult<Vec<TaskExecution>> {
     let conn = db.get_connection()?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, taskId, agentId, status, startedAt, completedAt, result, error, logs, createdAt, updatedAt
+        "SELECT id, taskId, agentId, status, startedAt, completedAt, result, error, logs, promptVersion, createdAt, updatedAt
          FROM taskExecutions WHERE taskId = ?1 ORDER BY createdAt DESC"
     )?;
 
@@ -370,8 +423,9 @@ pub fn get_task_executions(task_id: &str) -> SqlResult<Vec<TaskExecution>> {
             result: row.get(6)?,
             error: row.get(7)?,
             logs: row.get(8)?,
-            created_at: row.get(9)?,
-            updated_at: row.get(10)?,
+            prompt_version: row.get(9)?,
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
         })
     })?;
 
@@ -395,7 +449,7 @@ pub fn get_all_task_executions() -> SqlResult<Vec<TaskExecution>> {
     let conn = db.get_connection()?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, taskId, agentId, status, startedAt, completedAt, result, error, logs, createdAt, updatedAt
+        "SELECT id, taskId, agentId, status, startedAt, completedAt, result, error, logs, promptVersion, createdAt, updatedAt
          FROM taskExecutions ORDER BY createdAt DESC"
     )?;
 
@@ -410,8 +464,9 @@ pub fn get_all_task_executions() -> SqlResult<Vec<TaskExecution>> {
             result: row.get(6)?,
             error: row.get(7)?,
             logs: row.get(8)?,
-            created_at: row.get(9)?,
-            updated_at: row.get(10)?,
+            prompt_version: row.get(9)?,
+            created_at: row.get(10)?,
+            updated_at: row.get(11)?,
         })
     })?;
 
@@ -432,9 +487,9 @@ pub struct TaskChain {
     pub start_node_id: String,
     pub nodes: String, // JSON文字列（Map<string, ChainNode>）
     #[serde(rename = "createdAt")]
-    pub created_at: i64,
+    pub created_at: Timestamp,
     #[serde(rename = "updatedAt")]
-    pub updated_at: i64,
+    pub updated_at: Timestamp,
 }
 
 /// タスクチェーンを保存
@@ -599,6 +654,10 @@ pub struct Agent {
     #[serde(rename = "systemPrompt")]
     pub system_prompt: String,
     pub config: String, // JSON文字列
+    /// 現在のシステムプロンプトのバージョン（保存時に設定される）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "promptVersion")]
+    pub prompt_version: Option<i64>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
     #[serde(rename = "updatedAt")]
@@ -615,6 +674,22 @@ pub fn save_agent(agent: &Agent) -> SqlResult<Agent> {
     })?;
 
     let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    save_agent_in(&tx, agent, None)?;
+    tx.commit()?;
+
+    // 保存したAgentを取得して返す
+    get_agent(&agent.id)?.ok_or_else(|| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTFOUND),
+            Some("保存したAgentの取得に失敗しました".to_string()),
+        )
+    })
+}
+
+/// Agent定義を呼び出し側のトランザクション内で保存（書き込みワーカー用）
+pub(crate) fn save_agent_in(conn: &Connection, agent: &Agent, note: Option<&str>) -> SqlResult<()> {
+    let before = snapshot_row(conn, "agents", &agent.id)?;
     let now = get_timestamp();
 
     // 既存のAgentを確認
@@ -669,38 +744,6 @@ pub fn save_agent(agent: &Agent) -> SqlResult<Agent> {
             }
         }
     } else {
-        // 更新 - システムプロンプトが変更された場合はバージョン履歴を保存
-        if let Some(existing) = existing_agent {
-            if existing.system_prompt != agent.system_prompt {
-                // 現在の最大バージョン番号を取得
-                let max_version: i32 = conn.query_row(
-                    "SELECT COALESCE(MAX(version), 0) FROM agent_prompt_versions WHERE agentId = ?1",
-                    params![agent.id],
-                    |row| row.get(0),
-                ).unwrap_or(0);
-
-                // 新しいバージョンとして保存
-                let version_id = format!("{}-v{}", agent.id, max_version + 1);
-                use std::time::{SystemTime, UNIX_EPOCH};
-                let now_timestamp = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64;
-                conn.execute(
-                    "INSERT INTO agent_prompt_versions (id, agentId, version, systemPrompt, createdAt, updatedAt)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                    params![
-                        version_id,
-                        agent.id,
-                        max_version + 1,
-                        existing.system_prompt, // 変更前のプロンプトを保存
-                        now_timestamp,
-                        now_timestamp,
-                    ],
-                )?;
-            }
-        }
-
         // Agentを更新
         conn.execute(
             "UPDATE agents SET name = ?2, description = ?3, role = ?4, capabilities = ?5, tools = ?6, modelType = ?7, selectedModel = ?8, systemPrompt = ?9, config = ?10, updatedAt = ?11
@@ -721,13 +764,17 @@ pub fn save_agent(agent: &Agent) -> SqlResult<Agent> {
         )?;
     }
 
-    // 保存したAgentを取得して返す
-    get_agent(&agent.id)?.ok_or_else(|| {
-        rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTFOUND),
-            Some("保存したAgentの取得に失敗しました".to_string()),
-        )
-    })
+    // システムプロンプトが最新のバージョンと異なる場合は新しいバージョンとして保存
+    let version = record_prompt_version(conn, &agent.id, &agent.system_prompt, note)?;
+    conn.execute(
+        "UPDATE agents SET promptVersion = ?1 WHERE id = ?2",
+        params![version, agent.id],
+    )?;
+
+    let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+    audit_change(conn, action, "agents", &agent.id, before)?;
+
+    Ok(())
 }
 
 /// Agent定義を取得
@@ -742,7 +789,7 @@ pub fn get_agent(agent_id: &str) -> SqlResult<Option<Agent>> {
     let conn = db.get_connection()?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, name, description, role, capabilities, tools, modelType, selectedModel, systemPrompt, config, createdAt, updatedAt
+        "SELECT id, name, description, role, capabilities, tools, modelType, selectedModel, systemPrompt, config, promptVersion, createdAt, updatedAt
          FROM agents WHERE id = ?1"
     )?;
 
@@ -758,8 +805,9 @@ pub fn get_agent(agent_id: &str) -> SqlResult<Option<Agent>> {
             selected_model: row.get(7)?,
             system_prompt: row.get(8)?,
             config: row.get(9)?,
-            created_at: row.get(10)?,
-            updated_at: row.get(11)?,
+            prompt_version: row.get(10)?,
+            created_at: row.get(11)?,
+            updated_at: row.get(12)?,
         })
     });
 
@@ -782,7 +830,7 @@ pub fn get_all_agents() -> SqlResult<Vec<Agent>> {
     let conn = db.get_connection()?;
 
     let mut stmt = conn.prepare(
-        "SELECT id, name, description, role, capabilities, tools, modelType, selectedModel, systemPrompt, config, createdAt, updatedAt
+        "SELECT id, name, description, role, capabilities, tools, modelType, selectedModel, systemPrompt, config, promptVersion, createdAt, updatedAt
          FROM agents ORDER BY createdAt DESC"
     )?;
 
@@ -798,8 +846,9 @@ pub fn get_all_agents() -> SqlResult<Vec<Agent>> {
             selected_model: row.get(7)?,
             system_prompt: row.get(8)?,
             config: row.get(9)?,
-            created_at: row.get(10)?,
-            updated_at: row.get(11)?,
+            prompt_version: row.get(10)?,
+            created_at: row.get(11)?,
+            updated_at: row.get(12)?,
         })
     })?;
 
@@ -821,7 +870,18 @@ pub fn delete_agent(agent_id: &str) -> SqlResult<()> {
     })?;
 
     let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    delete_agent_in(&tx, agent_id)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Agent定義を呼び出し側のトランザクション内で削除（書き込みワーカー用）
+pub(crate) fn delete_agent_in(conn: &Connection, agent_id: &str) -> SqlResult<()> {
+    let before = snapshot_row(conn, "agents", agent_id)?;
     conn.execute("DELETE FROM agents WHERE id = ?1", params![agent_id])?;
+    audit_change(conn, AuditAction::Delete, "agents", agent_id, before)?;
 
     Ok(())
 }
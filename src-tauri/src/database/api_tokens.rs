@@ -0,0 +1,234 @@
+/**
+ * REST API用のAPIトークン管理
+ * スクリプトなどから REST API を呼び出すためのトークンを発行する
+ *
+ * - トークンそのものは発行時にのみ返し、SQLiteにはSHA-256ハッシュのみを保存する
+ * - スコープは read / write / admin（admin ⊃ write ⊃ read）
+ * - トークンは発行したユーザーに紐づき、利用時のスコープは持ち主の現在のロールの範囲に制限される
+ */
+
+use crate::database::{get_db, get_timestamp, Timestamp};
+use crate::database::session::hash_token;
+use rusqlite::{params, OptionalExtension, Result as SqlResult, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// APIトークンのプレフィックス（セッショントークンと区別するため）
+pub const API_TOKEN_PREFIX: &str = "mai_";
+
+/// APIトークンのスコープ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::Write => "write",
+            ApiScope::Admin => "admin",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim() {
+            "read" => Some(ApiScope::Read),
+            "write" => Some(ApiScope::Write),
+            "admin" => Some(ApiScope::Admin),
+            _ => None,
+        }
+    }
+
+    /// このスコープで required の操作が許可されるか（上位スコープは下位スコープを含む）
+    pub fn grants(&self, required: ApiScope) -> bool {
+        *self >= required
+    }
+}
+
+/// APIトークン情報（ハッシュは含まない）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<String>,
+    #[serde(rename = "revokedAt")]
+    pub revoked_at: Option<String>,
+}
+
+impl ApiToken {
+    /// required の操作が許可されているか
+    pub fn has_scope(&self, required: ApiScope) -> bool {
+        self.scopes.iter().any(|s| s.grants(required))
+    }
+}
+
+/// 発行したAPIトークン（token は発行時にのみ返す）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedApiToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiToken,
+}
+
+fn parse_scopes(value: &str) -> Vec<ApiScope> {
+    value.split(',').filter_map(ApiScope::from_str).collect()
+}
+
+fn row_to_api_token(row: &Row) -> SqlResult<ApiToken> {
+    let scopes: String = row.get(2)?;
+    Ok(ApiToken {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        scopes: parse_scopes(&scopes),
+        user_id: row.get(3)?,
+        created_at: row.get(4)?,
+        last_used_at: row.get(5)?,
+        expires_at: row.get(6)?,
+        revoked_at: row.get(7)?,
+    })
+}
+
+const API_TOKEN_COLUMNS: &str = "id, name, scopes, userId, createdAt, lastUsedAt, expiresAt, revokedAt";
+
+/// APIトークンを発行（user_id はトークンの持ち主）
+pub fn create_api_token(
+    name: &str,
+    scopes: &[ApiScope],
+    user_id: &str,
+    expires_in_days: Option<i64>,
+) -> SqlResult<IssuedApiToken> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+
+    if scopes.is_empty() {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+            Some("スコープを1つ以上指定してください（read / write / admin）".to_string()),
+        ));
+    }
+
+    let conn = db.get_connection()?;
+    let id = Uuid::new_v4().to_string();
+    let token = format!("{}{}{}", API_TOKEN_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let now = Timestamp::now();
+    let expires_at = expires_in_days
+        .filter(|d| *d > 0)
+        .map(|d| now.add_seconds(d * 24 * 60 * 60).to_rfc3339());
+    let now = now.to_rfc3339();
+
+    let mut scopes = scopes.to_vec();
+    scopes.sort();
+    scopes.dedup();
+    let scopes_str = scopes.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(",");
+
+    conn.execute(
+        "INSERT INTO apiTokens (id, name, tokenHash, scopes, userId, createdAt, expiresAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![id, name, hash_token(&token), scopes_str, user_id, now, expires_at],
+    )?;
+
+    Ok(IssuedApiToken {
+        token,
+        info: ApiToken {
+            id,
+            name: name.to_string(),
+            scopes,
+            user_id: Some(user_id.to_string()),
+            created_at: now,
+            last_used_at: None,
+            expires_at,
+            revoked_at: None,
+        },
+    })
+}
+
+/// APIトークン一覧を取得（失効済みを含む）
+pub fn list_api_tokens() -> SqlResult<Vec<ApiToken>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM apiTokens ORDER BY createdAt DESC",
+        API_TOKEN_COLUMNS
+    ))?;
+    let tokens = stmt.query_map([], row_to_api_token)?;
+    tokens.collect::<Result<Vec<_>, _>>()
+}
+
+/// APIトークンを失効
+pub fn revoke_api_token(id: &str) -> SqlResult<()> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let updated = conn.execute(
+        "UPDATE apiTokens SET revokedAt = ?1 WHERE id = ?2 AND revokedAt IS NULL",
+        params![get_timestamp(), id],
+    )?;
+    if updated == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    Ok(())
+}
+
+/// ユーザーに紐づくすべてのトークンを失効（却下されたユーザーなど）
+pub fn revoke_user_api_tokens(user_id: &str) -> SqlResult<usize> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    conn.execute(
+        "UPDATE apiTokens SET revokedAt = ?1 WHERE userId = ?2 AND revokedAt IS NULL",
+        params![get_timestamp(), user_id],
+    )
+}
+
+/// トークンを検証してAPIトークン情報を取得（失効済み・期限切れの場合は None）
+pub fn validate_api_token(token: &str) -> SqlResult<Option<ApiToken>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    let now = get_timestamp();
+
+    let api_token = conn.query_row(
+        &format!(
+            "SELECT {} FROM apiTokens
+             WHERE tokenHash = ?1 AND revokedAt IS NULL
+               AND (expiresAt IS NULL OR expiresAt > ?2)",
+            API_TOKEN_COLUMNS
+        ),
+        params![hash_token(token), now],
+        row_to_api_token,
+    ).optional()?;
+
+    if let Some(api_token) = &api_token {
+        conn.execute(
+            "UPDATE apiTokens SET lastUsedAt = ?1 WHERE id = ?2",
+            params![now, api_token.id],
+        )?;
+    }
+
+    Ok(api_token)
+}
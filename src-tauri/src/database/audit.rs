@@ -0,0 +1,276 @@
+/**
+ * 監査ログ
+ * データの作成・更新・削除を、実行者と変更前後のデータとともに auditLog テーブルに記録する
+ *
+ * - 記録は変更と同じトランザクション（同じ接続）で行う。変更がロールバックされた場合は監査ログも残らない
+ * - 実行者はリクエスト処理中のユーザー（get_current_user）。ユーザーがいない場合は system として記録する
+ * - パスワードハッシュなどの機密カラムは記録しない
+ * - 記録した変更は変更フィード（change_feed.rs）でも通知する
+ */
+
+use crate::database::{get_current_user, get_db, wake_change_feed, Timestamp};
+use rusqlite::types::ValueRef;
+use rusqlite::{params_from_iter, Connection, OptionalExtension, Result as SqlResult, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+/// 監査ログに記録しないカラム
+const REDACTED_COLUMNS: &[&str] = &["passwordHash", "tokenHash"];
+
+/// クエリで返す件数のデフォルト・上限
+const DEFAULT_QUERY_LIMIT: i64 = 100;
+const MAX_QUERY_LIMIT: i64 = 1000;
+
+/// 監査ログの操作種別
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    /// ゴミ箱からの復元
+    Restore,
+    /// ゴミ箱からの完全削除
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+        }
+    }
+}
+
+/// 監査ログエントリ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: String,
+    #[serde(rename = "actorId")]
+    pub actor_id: Option<String>,
+    #[serde(rename = "actorEmail")]
+    pub actor_email: Option<String>,
+    pub action: String,
+    #[serde(rename = "tableName")]
+    pub table_name: String,
+    #[serde(rename = "recordId")]
+    pub record_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Timestamp,
+}
+
+/// 監査ログの検索条件（from / to は RFC 3339 またはUnix秒、いずれも省略可）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditLogQuery {
+    #[serde(rename = "tableName")]
+    pub table_name: Option<String>,
+    #[serde(rename = "recordId")]
+    pub record_id: Option<String>,
+    #[serde(rename = "actorId")]
+    pub actor_id: Option<String>,
+    pub action: Option<String>,
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// 行のJSON表現を取得（行が存在しない場合は None）
+/// table は呼び出し側で検証済みのテーブル名であること
+pub(crate) fn snapshot_row(conn: &Connection, table: &str, id: &str) -> SqlResult<Option<Value>> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE id = ?1", table))?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+
+    stmt.query_row([id], |row| {
+        let mut map = Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            if REDACTED_COLUMNS.contains(&column.as_str()) {
+                continue;
+            }
+            let value = match row.get_ref(i)? {
+                ValueRef::Null => Value::Null,
+                ValueRef::Integer(n) => json!(n),
+                ValueRef::Real(f) => json!(f),
+                ValueRef::Text(t) => json!(String::from_utf8_lossy(t)),
+                ValueRef::Blob(b) => json!(format!("<blob {} bytes>", b.len())),
+            };
+            map.insert(column.clone(), value);
+        }
+        Ok(Value::Object(map))
+    }).optional()
+}
+
+/// 監査ログを記録（変更と同じ接続・トランザクションで呼び出す）
+pub(crate) fn record_audit(
+    conn: &Connection,
+    action: AuditAction,
+    table: &str,
+    record_id: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> SqlResult<()> {
+    // 値が変わっていない更新は記録しない
+    if action == AuditAction::Update && before.is_some() && before == after {
+        return Ok(());
+    }
+
+    let actor = get_current_user();
+    conn.execute(
+        "INSERT INTO auditLog (id, actorId, actorEmail, action, tableName, recordId, beforeData, afterData, createdAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            Uuid::new_v4().to_string(),
+            actor.as_ref().map(|u| u.uid.as_str()),
+            actor.as_ref().map(|u| u.email.as_str()),
+            action.as_str(),
+            table,
+            record_id,
+            before.map(|v| v.to_string()),
+            after.map(|v| v.to_string()),
+            Timestamp::now(),
+        ],
+    )?;
+    // 変更フィードはコミット後に監査ログから読み取る
+    wake_change_feed();
+    Ok(())
+}
+
+/// 変更後の行を取得して監査ログを記録（before は変更前に snapshot_row で取得したもの）
+pub(crate) fn audit_change(
+    conn: &Connection,
+    action: AuditAction,
+    table: &str,
+    record_id: &str,
+    before: Option<Value>,
+) -> SqlResult<()> {
+    let after = match action {
+        AuditAction::Delete | AuditAction::Purge => None,
+        AuditAction::Create | AuditAction::Update | AuditAction::Restore => snapshot_row(conn, table, record_id)?,
+    };
+    record_audit(conn, action, table, record_id, before.as_ref(), after.as_ref())
+}
+
+fn row_to_audit_log_entry(row: &Row) -> SqlResult<AuditLogEntry> {
+    let before: Option<String> = row.get(6)?;
+    let after: Option<String> = row.get(7)?;
+    Ok(AuditLogEntry {
+        id: row.get(0)?,
+        actor_id: row.get(1)?,
+        actor_email: row.get(2)?,
+        action: row.get(3)?,
+        table_name: row.get(4)?,
+        record_id: row.get(5)?,
+        before: before.and_then(|s| serde_json::from_str(&s).ok()),
+        after: after.and_then(|s| serde_json::from_str(&s).ok()),
+        created_at: row.get(8)?,
+    })
+}
+
+/// 検索条件からWHERE句とパラメータを作成
+fn build_where_clause(query: &AuditLogQuery) -> (String, Vec<rusqlite::types::Value>) {
+    use rusqlite::types::Value as SqlValue;
+
+    let mut clauses = Vec::new();
+    let mut params = Vec::new();
+    if let Some(table_name) = &query.table_name {
+        clauses.push("tableName = ?");
+        params.push(SqlValue::Text(table_name.clone()));
+    }
+    if let Some(record_id) = &query.record_id {
+        clauses.push("recordId = ?");
+        params.push(SqlValue::Text(record_id.clone()));
+    }
+    if let Some(actor_id) = &query.actor_id {
+        clauses.push("actorId = ?");
+        params.push(SqlValue::Text(actor_id.clone()));
+    }
+    if let Some(action) = &query.action {
+        clauses.push("action = ?");
+        params.push(SqlValue::Text(action.clone()));
+    }
+    if let Some(from) = query.from {
+        clauses.push("createdAt >= ?");
+        params.push(SqlValue::Text(from.to_rfc3339()));
+    }
+    if let Some(to) = query.to {
+        clauses.push("createdAt <= ?");
+        params.push(SqlValue::Text(to.to_rfc3339()));
+    }
+
+    if clauses.is_empty() {
+        (String::new(), params)
+    } else {
+        (format!(" WHERE {}", clauses.join(" AND ")), params)
+    }
+}
+
+fn select_audit_log(query: &AuditLogQuery, limit: Option<i64>) -> SqlResult<Vec<AuditLogEntry>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let (where_clause, params) = build_where_clause(query);
+    let mut sql = format!(
+        "SELECT id, actorId, actorEmail, action, tableName, recordId, beforeData, afterData, createdAt
+         FROM auditLog{} ORDER BY createdAt DESC, seq DESC",
+        where_clause
+    );
+    if let Some(limit) = limit {
+        sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, query.offset.unwrap_or(0).max(0)));
+    }
+
+    let mut stmt = conn.prepare(&sql)?;
+    let entries = stmt.query_map(params_from_iter(params), row_to_audit_log_entry)?;
+    entries.collect::<Result<Vec<_>, _>>()
+}
+
+/// 監査ログを検索（新しい順、limit のデフォルトは100件・上限1000件）
+pub fn query_audit_log(query: &AuditLogQuery) -> SqlResult<Vec<AuditLogEntry>> {
+    let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT).clamp(1, MAX_QUERY_LIMIT);
+    select_audit_log(query, Some(limit))
+}
+
+/// CSVフィールドをエスケープ
+fn escape_csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace("\"", "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 監査ログをCSV形式でエクスポート（limit を省略した場合は条件に一致するすべて）
+pub fn export_audit_log_to_csv(query: &AuditLogQuery) -> SqlResult<String> {
+    let entries = select_audit_log(query, query.limit.map(|l| l.max(1)))?;
+
+    let mut csv_lines = Vec::new();
+
+    // BOMを追加（Excelで正しく表示されるように）
+    csv_lines.push("\u{FEFF}ID,日時,実行者ID,実行者メールアドレス,操作,テーブル,レコードID,変更前,変更後".to_string());
+
+    for entry in &entries {
+        let line = format!(
+            "{},{},{},{},{},{},{},{},{}",
+            escape_csv_field(&entry.id),
+            entry.created_at,
+            entry.actor_id.as_ref().map(|s| escape_csv_field(s)).unwrap_or_else(|| "system".to_string()),
+            entry.actor_email.as_ref().map(|s| escape_csv_field(s)).unwrap_or_default(),
+            escape_csv_field(&entry.action),
+            escape_csv_field(&entry.table_name),
+            escape_csv_field(&entry.record_id),
+            entry.before.as_ref().map(|v| escape_csv_field(&v.to_string())).unwrap_or_default(),
+            entry.after.as_ref().map(|v| escape_csv_field(&v.to_string())).unwrap_or_default(),
+        );
+        csv_lines.push(line);
+    }
+
+    Ok(csv_lines.join("\n"))
+}
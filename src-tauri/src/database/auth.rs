@@ -1,5 +1,6 @@
-use crate::database::{get_db, set_current_user, get_timestamp, User};
-use rusqlite::Result as SqlResult;
+use crate::database::{get_db, get_timestamp, create_session, revoke_session, Session, User};
+use crate::database::rbac::{has_admin, Role};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult, Transaction, TransactionBehavior};
 use bcrypt::{hash, verify, DEFAULT_COST};
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
@@ -7,11 +8,15 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignUpResult {
     pub user: User,
+    /// 承認済みか（false の場合は管理者の承認待ち）
+    pub approved: bool,
+    pub role: Role,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignInResult {
     pub user: User,
+    pub session: Session,
 }
 
 pub fn sign_up(email: String, password: String) -> SqlResult<SignUpResult> {
@@ -20,28 +25,39 @@ pub fn sign_up(email: String, password: String) -> SqlResult<SignUpResult> {
         Some("データベースが初期化されていません".to_string())
     ))?;
     let conn = db.get_connection()?;
-    
+    sign_up_in(&conn, email, password)
+}
+
+/// 利用者を登録（管理者の有無の確認と登録を1つのトランザクションで行う）
+pub(crate) fn sign_up_in(conn: &Connection, email: String, password: String) -> SqlResult<SignUpResult> {
     let user_id = Uuid::new_v4().to_string();
     let password_hash = hash(password, DEFAULT_COST).unwrap_or_default();
     let now = get_timestamp();
     
-    // ローカル特化型では自動承認
-    let approved = 1;
+    // 書き込みロックを先に取得し、同時に登録した利用者が両方とも管理者として承認されないようにする
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
     
-    conn.execute(
-        "INSERT INTO users (id, email, passwordHash, approved, createdAt, updatedAt)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        [&user_id, &email, &password_hash, &approved.to_string(), &now, &now],
+    // 新規登録は管理者の承認待ち（viewer）とする
+    // 承認済みの管理者が1人もいない場合のみ、最初の利用者を管理者として自動承認する
+    let bootstrap_admin = !has_admin(&tx)?;
+    let (approved, role) = if bootstrap_admin { (1, Role::Admin) } else { (0, Role::Viewer) };
+    let approved_at = if bootstrap_admin { Some(now.clone()) } else { None };
+    
+    tx.execute(
+        "INSERT INTO users (id, email, passwordHash, approved, approvedAt, role, createdAt, updatedAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![user_id, email, password_hash, approved, approved_at, role.as_str(), now, now],
     )?;
     
     // 承認リクエストを作成
     let status = if approved == 1 { "approved" } else { "pending" };
     let request_id = Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT INTO approvalRequests (id, userId, email, status, requestedAt)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-        [&request_id, &user_id, &email, status, &now],
+    tx.execute(
+        "INSERT INTO approvalRequests (id, userId, email, status, requestedAt, reviewedAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![request_id, user_id, email, status, now, approved_at],
     )?;
+    tx.commit()?;
     
     let user = User {
         uid: user_id,
@@ -49,7 +65,7 @@ pub fn sign_up(email: String, password: String) -> SqlResult<SignUpResult> {
         email_verified: false,
     };
     
-    Ok(SignUpResult { user })
+    Ok(SignUpResult { user, approved: approved == 1, role })
 }
 
 pub fn sign_in(email: String, password: String) -> SqlResult<SignInResult> {
@@ -82,7 +98,10 @@ pub fn sign_in(email: String, password: String) -> SqlResult<SignInResult> {
     
     // 承認チェック
     if approved == 0 {
-        return Err(rusqlite::Error::InvalidQuery);
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_AUTH),
+            Some("このアカウントは管理者の承認待ちか、登録が却下されています".to_string()),
+        ));
     }
     
     let user = User {
@@ -91,12 +110,119 @@ pub fn sign_in(email: String, password: String) -> SqlResult<SignInResult> {
         email_verified: true,
     };
     
-    set_current_user(Some(user.clone()));
+    // セッションを発行（ユーザーごとに複数のセッションを同時に保持できる）
+    let session = create_session(&user)?;
     
-    Ok(SignInResult { user })
+    Ok(SignInResult { user, session })
 }
 
-pub fn sign_out() {
-    set_current_user(None);
+/// セッションを失効させてサインアウト
+pub fn sign_out(token: &str) -> SqlResult<()> {
+    revoke_session(token)
+}
+
+/// 承認済みのユーザーを取得（存在しない・未承認の場合は None）
+pub fn get_approved_user_by_id(user_id: &str) -> SqlResult<Option<User>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    conn.query_row(
+        "SELECT id, email FROM users WHERE id = ?1 AND approved = 1",
+        [user_id],
+        |row| Ok(User {
+            uid: row.get(0)?,
+            email: row.get(1)?,
+            email_verified: true,
+        }),
+    ).optional()
 }
 
+/// IDでユーザーを取得
+pub fn get_user_by_id(user_id: &str) -> SqlResult<User> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    conn.query_row(
+        "SELECT id, email FROM users WHERE id = ?1",
+        [user_id],
+        |row| Ok(User {
+            uid: row.get(0)?,
+            email: row.get(1)?,
+            email_verified: true,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{insert_test_user, migrations::run_migrations, validate_session};
+
+    fn open() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn approval_status(conn: &Connection, user_id: &str) -> String {
+        conn.query_row(
+            "SELECT status FROM approvalRequests WHERE userId = ?1",
+            [user_id],
+            |row| row.get(0),
+        ).unwrap()
+    }
+
+    #[test]
+    fn first_sign_up_becomes_the_admin_and_later_ones_wait_for_approval() {
+        let conn = open();
+
+        let first = sign_up_in(&conn, "first@example.com".to_string(), "password".to_string()).unwrap();
+        assert!(first.approved);
+        assert_eq!(first.role, Role::Admin);
+        assert_eq!(approval_status(&conn, &first.user.uid), "approved");
+
+        let second = sign_up_in(&conn, "second@example.com".to_string(), "password".to_string()).unwrap();
+        assert!(!second.approved);
+        assert_eq!(second.role, Role::Viewer);
+        assert_eq!(approval_status(&conn, &second.user.uid), "pending");
+    }
+
+    #[test]
+    fn failed_sign_up_leaves_no_partial_rows() {
+        let conn = open();
+        sign_up_in(&conn, "taken@example.com".to_string(), "password".to_string()).unwrap();
+        assert!(sign_up_in(&conn, "taken@example.com".to_string(), "password".to_string()).is_err());
+
+        let users: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0)).unwrap();
+        let requests: i64 = conn.query_row("SELECT COUNT(*) FROM approvalRequests", [], |row| row.get(0)).unwrap();
+        assert_eq!((users, requests), (1, 1));
+    }
+
+    #[test]
+    fn sign_in_issues_a_session_for_approved_users() {
+        let user = insert_test_user(Role::Editor, true);
+        let result = sign_in(user.email.clone(), "password".to_string()).unwrap();
+        assert_eq!(result.user.uid, user.uid);
+        assert_eq!(validate_session(&result.session.token).unwrap().unwrap().uid, user.uid);
+
+        sign_out(&result.session.token).unwrap();
+        assert!(validate_session(&result.session.token).unwrap().is_none());
+    }
+
+    #[test]
+    fn sign_in_rejects_wrong_passwords_unknown_and_unapproved_users() {
+        let user = insert_test_user(Role::Editor, true);
+        assert!(sign_in(user.email.clone(), "wrong".to_string()).is_err());
+        assert!(sign_in("nobody@example.com".to_string(), "password".to_string()).is_err());
+
+        let pending = insert_test_user(Role::Viewer, false);
+        match sign_in(pending.email, "password".to_string()) {
+            Err(rusqlite::Error::SqliteFailure(e, _)) => assert_eq!(e.code, rusqlite::ErrorCode::AuthorizationForStatementDenied),
+            other => panic!("unexpected result: {:?}", other.map(|r| r.user)),
+        }
+    }
+}
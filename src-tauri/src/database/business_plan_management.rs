@@ -1,10 +1,59 @@
-use crate::database::{get_db, get_timestamp, get_current_user};
-use rusqlite::Result as SqlResult;
+use crate::database::{get_db, get_timestamp, User};
+use rusqlite::{Connection, Result as SqlResult};
 use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use uuid::Uuid;
 use std::fs;
 
+/// 添付ファイルの保存ディレクトリ（init_database で設定する）
+/// 事業計画ファイルの filePath はこのディレクトリ内のパスのみ保存・削除する
+static FILES_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// 添付ファイルの保存ディレクトリを設定
+pub fn set_business_plan_files_root(dir: PathBuf) {
+    let _ = FILES_ROOT.set(dir);
+}
+
+/// ファイル名からパス区切り文字を除去（ディレクトリトラバーサル対策）
+pub fn sanitize_file_name(file_name: &str) -> String {
+    let name: String = file_name
+        .chars()
+        .map(|c| if c == '/' || c == '\\' || c == ':' { '_' } else { c })
+        .collect();
+    let name = name.trim_start_matches('.').to_string();
+    if name.is_empty() { "file".to_string() } else { name }
+}
+
+/// 保存ディレクトリを正規化したパス（なければ作成する）
+fn files_root() -> Result<PathBuf, String> {
+    let root = FILES_ROOT.get()
+        .ok_or_else(|| "添付ファイルの保存ディレクトリが設定されていません".to_string())?;
+    fs::create_dir_all(root)
+        .map_err(|e| format!("ディレクトリ作成エラー: {}", e))?;
+    root.canonicalize()
+        .map_err(|e| format!("添付ファイルの保存ディレクトリを解決できません: {}", e))
+}
+
+/// パスを正規化し、保存ディレクトリ内のファイルであることを確認（シンボリックリンクも解決する）
+fn resolve_in_files_root(path: &Path) -> Result<PathBuf, String> {
+    let root = files_root()?;
+    let canonical = path.canonicalize()
+        .map_err(|e| format!("ファイルが見つかりません: {} - {}", path.display(), e))?;
+    if canonical == root || !canonical.starts_with(&root) {
+        return Err(format!("添付ファイルの保存ディレクトリ外のパスは使用できません: {}", path.display()));
+    }
+    Ok(canonical)
+}
+
+fn permission_denied(message: String) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_PERM),
+        Some(message),
+    )
+}
+
 /// 事業計画の種類
 #[derive(Debug, Clone)]
 pub enum PlanType {
@@ -70,6 +119,7 @@ impl FileType {
 
 /// 事業計画ファイルを追加
 pub fn add_business_plan_file(
+    user: &User,
     plan_id: &str,
     plan_type: &str,
     file_name: &str,
@@ -85,10 +135,10 @@ pub fn add_business_plan_file(
         Some("データベースが初期化されていません".to_string())
     ))?;
 
-    let user = get_current_user().ok_or_else(|| rusqlite::Error::SqliteFailure(
-        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
-        Some("ユーザーがログインしていません".to_string())
-    ))?;
+    // 保存ディレクトリ外のファイルは登録しない（任意のパスの読み取り・削除を防ぐ）
+    let file_path = resolve_in_files_root(Path::new(file_path)).map_err(permission_denied)?;
+    let file_path = file_path.to_string_lossy().to_string();
+    let file_path = file_path.as_str();
 
     let conn = db.get_connection()?;
     let file_id = Uuid::new_v4().to_string();
@@ -104,6 +154,13 @@ pub fn add_business_plan_file(
     // ファイルハッシュを計算（オプション）
     let file_hash = calculate_file_hash(file_path).ok();
 
+    // 同じ事業計画に同一内容のファイルが既にある場合は既存のIDを返す
+    if let Some(hash) = file_hash.as_deref() {
+        if let Some(existing_id) = find_business_plan_file_by_hash(plan_id, hash)? {
+            return Ok(existing_id);
+        }
+    }
+
     conn.execute(
         "INSERT INTO businessPlanFiles (
             id, planId, planType, fileName, originalFileName, filePath,
@@ -129,9 +186,89 @@ pub fn add_business_plan_file(
         ],
     )?;
 
+    record_history_quietly(user, plan_id, plan_type, "fileAdded", &json!({
+        "fileId": file_id,
+        "originalFileName": original_file_name,
+    }));
+
     Ok(file_id)
 }
 
+/// アップロードされたファイルを保存ディレクトリに書き込み、事業計画に添付する
+/// 同一内容のファイルが既にあれば書き込まずに既存のIDを返す（戻り値の2番目が true）
+pub fn store_business_plan_file(
+    user: &User,
+    plan_id: &str,
+    plan_type: &str,
+    original_file_name: &str,
+    file_data: &[u8],
+    mime_type: Option<&str>,
+    description: Option<&str>,
+    category: Option<&str>,
+) -> Result<(String, bool), String> {
+    if PlanType::from_str(plan_type).is_none() {
+        return Err(format!("無効な事業計画の種類です: {}", plan_type));
+    }
+
+    // ディスクに書き込む前にハッシュで重複を確認
+    let file_hash = calculate_data_hash(file_data);
+    if let Some(existing_id) = find_business_plan_file_by_hash(plan_id, &file_hash)
+        .map_err(|e| format!("事業計画ファイルの重複確認に失敗しました: {}", e))?
+    {
+        return Ok((existing_id, true));
+    }
+
+    let files_dir = files_root()?.join(plan_type).join(sanitize_file_name(plan_id));
+    fs::create_dir_all(&files_dir)
+        .map_err(|e| format!("ディレクトリ作成エラー: {}", e))?;
+
+    let file_name = format!("{}_{}", Uuid::new_v4(), sanitize_file_name(original_file_name));
+    let file_path = files_dir.join(&file_name);
+    fs::write(&file_path, file_data)
+        .map_err(|e| format!("ファイル保存エラー: {}", e))?;
+
+    match add_business_plan_file(
+        user,
+        plan_id,
+        plan_type,
+        &file_name,
+        original_file_name,
+        &file_path.to_string_lossy(),
+        file_data.len() as i64,
+        mime_type,
+        description,
+        category,
+    ) {
+        Ok(id) => Ok((id, false)),
+        Err(e) => {
+            // 登録に失敗した場合は保存したファイルを削除
+            let _ = fs::remove_file(&file_path);
+            Err(format!("事業計画ファイルの追加に失敗しました: {}", e))
+        }
+    }
+}
+
+/// 同じ事業計画内で同一ハッシュのファイル（削除されていないもの）を検索
+pub fn find_business_plan_file_by_hash(plan_id: &str, file_hash: &str) -> SqlResult<Option<String>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+
+    let conn = db.get_connection()?;
+    let result = conn.query_row(
+        "SELECT id FROM businessPlanFiles WHERE planId = ?1 AND fileHash = ?2 AND isDeleted = 0 LIMIT 1",
+        [plan_id, file_hash],
+        |row| row.get::<_, String>(0),
+    );
+
+    match result {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
 /// 事業計画ファイルを取得
 pub fn get_business_plan_file(file_id: &str) -> SqlResult<HashMap<String, Value>> {
     let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
@@ -225,7 +362,7 @@ pub fn get_business_plan_files(plan_id: &str, include_deleted: bool) -> SqlResul
 }
 
 /// 事業計画ファイルを削除（論理削除）
-pub fn delete_business_plan_file(file_id: &str) -> SqlResult<()> {
+pub fn delete_business_plan_file(user: &User, file_id: &str) -> SqlResult<()> {
     let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
         rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
         Some("データベースが初期化されていません".to_string())
@@ -234,31 +371,68 @@ pub fn delete_business_plan_file(file_id: &str) -> SqlResult<()> {
     let conn = db.get_connection()?;
     let now = get_timestamp();
 
+    let (plan_id, plan_type): (String, String) = conn.query_row(
+        "SELECT planId, planType FROM businessPlanFiles WHERE id = ?1",
+        [file_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
     conn.execute(
-        "UPDATE businessPlanFiles SET isDeleted = 1, deletedAt = ?1 WHERE id = ?2",
+        "UPDATE businessPlanFiles SET isDeleted = 1, deletedAt = ?1, updatedAt = ?1 WHERE id = ?2",
         [&now, file_id],
     )?;
 
+    record_history_quietly(user, &plan_id, &plan_type, "fileDeleted", &json!({ "fileId": file_id }));
+
     Ok(())
 }
 
 /// 事業計画ファイルを完全削除
-pub fn permanently_delete_business_plan_file(file_id: &str) -> SqlResult<()> {
+pub fn permanently_delete_business_plan_file(user: &User, file_id: &str) -> SqlResult<()> {
     let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
         rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
         Some("データベースが初期化されていません".to_string())
     ))?;
 
-    // ファイル情報を取得してから削除
-    let file_info = get_business_plan_file(file_id)?;
-    
+    // ファイル情報を取得してから削除（論理削除済みのファイルも対象）
+    // 行の削除・残りの参照の確認・履歴の記録は1つのトランザクションで行う
     let conn = db.get_connection()?;
-    conn.execute("DELETE FROM businessPlanFiles WHERE id = ?1", [file_id])?;
+    let tx = conn.unchecked_transaction()?;
+    let (plan_id, plan_type, file_path): (String, String, String) = tx.query_row(
+        "SELECT planId, planType, filePath FROM businessPlanFiles WHERE id = ?1",
+        [file_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    tx.execute("DELETE FROM businessPlanFiles WHERE id = ?1", [file_id])?;
+
+    let remaining: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM businessPlanFiles WHERE filePath = ?1",
+        [&file_path],
+        |row| row.get(0),
+    )?;
 
-    // ファイルシステムからも削除
-    if let Some(file_path) = file_info.get("filePath").and_then(|v| v.as_str()) {
-        if let Err(e) = fs::remove_file(file_path) {
-            eprintln!("警告: ファイルの削除に失敗しました: {} - {}", file_path, e);
+    insert_creation_history(
+        &tx,
+        user,
+        &plan_id,
+        &plan_type,
+        "filePermanentlyDeleted",
+        None,
+        None,
+        Some(&json!({ "fileId": file_id }).to_string()),
+    )?;
+    tx.commit()?;
+
+    // 同じファイルを参照する行が残っていなければファイルシステムからも削除（保存ディレクトリ内のファイルのみ）
+    if remaining == 0 {
+        match resolve_in_files_root(Path::new(&file_path)) {
+            Ok(path) => {
+                if let Err(e) = fs::remove_file(&path) {
+                    eprintln!("警告: ファイルの削除に失敗しました: {} - {}", file_path, e);
+                }
+            }
+            Err(e) => eprintln!("警告: ファイルを削除しませんでした: {}", e),
         }
     }
 
@@ -267,6 +441,7 @@ pub fn permanently_delete_business_plan_file(file_id: &str) -> SqlResult<()> {
 
 /// 事業計画IDを登録
 pub fn register_business_plan_id(
+    user: &User,
     plan_id: &str,
     plan_type: &str,
     display_id: Option<&str>,
@@ -278,11 +453,6 @@ pub fn register_business_plan_id(
         Some("データベースが初期化されていません".to_string())
     ))?;
 
-    let user = get_current_user().ok_or_else(|| rusqlite::Error::SqliteFailure(
-        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
-        Some("ユーザーがログインしていません".to_string())
-    ))?;
-
     let conn = db.get_connection()?;
     let registry_id = Uuid::new_v4().to_string();
     let now = get_timestamp();
@@ -358,6 +528,7 @@ pub fn get_business_plan_id_info(plan_id: &str) -> SqlResult<HashMap<String, Val
 
 /// 事業計画作成履歴を記録
 pub fn record_creation_history(
+    user: &User,
     plan_id: &str,
     plan_type: &str,
     action: &str,
@@ -370,12 +541,21 @@ pub fn record_creation_history(
         Some("データベースが初期化されていません".to_string())
     ))?;
 
-    let user = get_current_user().ok_or_else(|| rusqlite::Error::SqliteFailure(
-        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
-        Some("ユーザーがログインしていません".to_string())
-    ))?;
-
     let conn = db.get_connection()?;
+    insert_creation_history(&conn, user, plan_id, plan_type, action, previous_state, new_state, metadata)
+}
+
+/// 作成履歴を1件追加（呼び出し元のトランザクション内で使用できる）
+fn insert_creation_history(
+    conn: &Connection,
+    user: &User,
+    plan_id: &str,
+    plan_type: &str,
+    action: &str,
+    previous_state: Option<&str>,
+    new_state: Option<&str>,
+    metadata: Option<&str>,
+) -> SqlResult<String> {
     let history_id = Uuid::new_v4().to_string();
     let now = get_timestamp();
 
@@ -458,14 +638,26 @@ pub fn get_creation_history(plan_id: &str, limit: Option<i64>) -> SqlResult<Vec<
     Ok(results)
 }
 
-/// ファイルハッシュを計算（SHA-256）
-fn calculate_file_hash(file_path: &str) -> Result<String, std::io::Error> {
+/// ファイル操作の履歴を記録（履歴の記録に失敗してもファイル操作自体は成功扱い）
+fn record_history_quietly(user: &User, plan_id: &str, plan_type: &str, action: &str, metadata: &Value) {
+    if let Err(e) = record_creation_history(user, plan_id, plan_type, action, None, None, Some(&metadata.to_string())) {
+        eprintln!("警告: 事業計画の履歴記録に失敗しました: {} - {}", action, e);
+    }
+}
+
+/// データのハッシュを計算（SHA-256）
+pub fn calculate_data_hash(data: &[u8]) -> String {
     use sha2::{Sha256, Digest};
-    
-    let contents = fs::read(file_path)?;
+
     let mut hasher = Sha256::new();
-    hasher.update(&contents);
+    hasher.update(data);
     let hash = hasher.finalize();
-    Ok(format!("{:x}", hash))
+    format!("{:x}", hash)
+}
+
+/// ファイルハッシュを計算（SHA-256）
+fn calculate_file_hash(file_path: &str) -> Result<String, std::io::Error> {
+    let contents = fs::read(file_path)?;
+    Ok(calculate_data_hash(&contents))
 }
 
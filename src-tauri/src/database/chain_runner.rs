@@ -0,0 +1,748 @@
+/**
+ * タスクチェーンの実行
+ * 検証済みのチェーンをRust側で実行し、各ステップを保存する。アプリが終了しても次回起動時に続きから再開する
+ *
+ * - 実行（taskChainRuns）は開始時のノード定義を保持するため、実行中にチェーンを編集しても影響しない
+ * - 実行中の経路（カーソル）・ループ回数・合流待ちの状態を state（JSON）に保存し、進むたびに更新する
+ * - task ノードはタスク実行エンジン（task_executor.rs）に登録し、taskExecutions の完了を待つ
+ * - 通過したノードを taskChainRunSteps に記録する（task ノードは実行IDと結果を含む）
+ * - タスクが failed / cancelled になった場合はチェーン全体を失敗にし、他の分岐で実行中のタスクをキャンセルする
+ * - 変更フィードでタスク実行の更新を受け取って進める（取りこぼしと wait ノードに備えて定期的にも確認する）
+ */
+
+use crate::database::task_chain::{parse_chain_nodes, validate_task_chain, ChainNode, ChainNodeKind};
+use crate::database::{
+    cancel_task_execution, enqueue_task_execution, get_db, get_task_chain, get_task_execution,
+    get_timestamp, subscribe_changes, TaskExecution, Timestamp, User,
+};
+use crate::db::WriteQueueState;
+use rusqlite::{params, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{Mutex, Notify};
+
+/// 進捗通知用のTauriイベント名
+pub const TASK_CHAIN_EVENT: &str = "task-chain-progress";
+
+/// 1回の実行で通過できる最大ノード数（無限ループ防止）
+const MAX_CHAIN_STEPS: u32 = 1_000;
+/// loopCount を指定していないループの最大回数
+const MAX_LOOP_ITERATIONS: u32 = 100;
+/// 変更がない場合に実行中のチェーンを確認する間隔
+const POLL_INTERVAL_SECS: u64 = 2;
+
+/// チェーンの実行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskChainRun {
+    pub id: String,
+    #[serde(rename = "chainId")]
+    pub chain_id: String,
+    /// running / completed / failed / cancelled
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(rename = "startedAt")]
+    pub started_at: Timestamp,
+    #[serde(rename = "completedAt", skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<Timestamp>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Timestamp,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Timestamp,
+}
+
+/// チェーンの実行で通過したノード
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskChainRunStep {
+    pub id: String,
+    #[serde(rename = "runId")]
+    pub run_id: String,
+    #[serde(rename = "nodeId")]
+    pub node_id: String,
+    #[serde(rename = "nodeType")]
+    pub node_type: String,
+    /// 並列実行の分岐を区別するID
+    #[serde(rename = "cursorId")]
+    pub cursor_id: String,
+    #[serde(rename = "executionId", skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>, // JSON文字列
+    #[serde(rename = "startedAt")]
+    pub started_at: Timestamp,
+    #[serde(rename = "completedAt", skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<Timestamp>,
+}
+
+/// 進捗（Tauriイベントで送る）
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskChainProgress {
+    #[serde(rename = "runId")]
+    pub run_id: String,
+    #[serde(rename = "chainId")]
+    pub chain_id: String,
+    pub status: String,
+    /// 実行中・待機中のノード
+    #[serde(rename = "activeNodeIds")]
+    pub active_node_ids: Vec<String>,
+    pub error: Option<String>,
+    pub timestamp: Timestamp,
+}
+
+/// 実行中の経路
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cursor {
+    id: String,
+    #[serde(rename = "nodeId")]
+    node_id: String,
+    /// 直前のタスクの実行結果（{ status, result, error }。条件の評価に使う）
+    #[serde(default)]
+    last: Option<Value>,
+    /// 完了を待っているタスク実行
+    #[serde(rename = "executionId", default)]
+    execution_id: Option<String>,
+    #[serde(rename = "stepId", default)]
+    step_id: Option<String>,
+    /// wait ノードで待つ期限
+    #[serde(rename = "waitUntil", default)]
+    wait_until: Option<Timestamp>,
+}
+
+/// 合流待ちの状態
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JoinState {
+    expected: usize,
+    arrived: Vec<Value>,
+}
+
+/// 実行状態（taskChainRuns.state に保存する）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RunState {
+    cursors: Vec<Cursor>,
+    #[serde(rename = "loopCounters", default)]
+    loop_counters: HashMap<String, u32>,
+    #[serde(default)]
+    joins: HashMap<String, JoinState>,
+    #[serde(rename = "nextCursorId", default)]
+    next_cursor_id: u32,
+    #[serde(default)]
+    steps: u32,
+}
+
+impl RunState {
+    fn new_cursor(&mut self, node_id: &str, last: Option<Value>) -> Cursor {
+        self.next_cursor_id += 1;
+        Cursor {
+            id: format!("c{}", self.next_cursor_id),
+            node_id: node_id.to_string(),
+            last,
+            execution_id: None,
+            step_id: None,
+            wait_until: None,
+        }
+    }
+}
+
+/// カーソルを1ノード分進めた結果
+enum Advance {
+    /// 次のノードへ進んだ（空の場合はその経路が終了した）
+    Moved(Vec<Cursor>),
+    /// タスクの完了や待ち時間を待っている（期限があれば返す）
+    Blocked(Cursor, Option<Timestamp>),
+    Failed(String),
+}
+
+static RUN_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+static TRIGGER: OnceLock<Notify> = OnceLock::new();
+
+/// 実行状態の更新（実行エンジンとキャンセル）を直列化するロック
+fn run_lock() -> &'static Mutex<()> {
+    RUN_LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn trigger() -> &'static Notify {
+    TRIGGER.get_or_init(Notify::new)
+}
+
+fn not_initialized() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    )
+}
+
+fn row_to_run(row: &rusqlite::Row) -> SqlResult<TaskChainRun> {
+    Ok(TaskChainRun {
+        id: row.get(0)?,
+        chain_id: row.get(1)?,
+        status: row.get(2)?,
+        error: row.get(3)?,
+        started_at: row.get(4)?,
+        completed_at: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// チェーンの実行を取得
+pub fn get_task_chain_run(run_id: &str) -> SqlResult<Option<TaskChainRun>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let result = conn.query_row(
+        "SELECT id, chainId, status, error, startedAt, completedAt, createdAt, updatedAt
+         FROM taskChainRuns WHERE id = ?1",
+        params![run_id],
+        row_to_run,
+    );
+    match result {
+        Ok(run) => Ok(Some(run)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// チェーンの実行履歴を取得（新しい順）
+pub fn get_task_chain_runs(chain_id: &str) -> SqlResult<Vec<TaskChainRun>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, chainId, status, error, startedAt, completedAt, createdAt, updatedAt
+         FROM taskChainRuns WHERE chainId = ?1 ORDER BY createdAt DESC",
+    )?;
+    let runs = stmt.query_map(params![chain_id], row_to_run)?;
+    runs.collect()
+}
+
+/// チェーンの実行で通過したノードを取得（通過した順）
+pub fn get_task_chain_run_steps(run_id: &str) -> SqlResult<Vec<TaskChainRunStep>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, runId, nodeId, nodeType, cursorId, executionId, status, result, startedAt, completedAt
+         FROM taskChainRunSteps WHERE runId = ?1 ORDER BY rowid ASC",
+    )?;
+    let steps = stmt.query_map(params![run_id], |row| {
+        Ok(TaskChainRunStep {
+            id: row.get(0)?,
+            run_id: row.get(1)?,
+            node_id: row.get(2)?,
+            node_type: row.get(3)?,
+            cursor_id: row.get(4)?,
+            execution_id: row.get(5)?,
+            status: row.get(6)?,
+            result: row.get(7)?,
+            started_at: row.get(8)?,
+            completed_at: row.get(9)?,
+        })
+    })?;
+    steps.collect()
+}
+
+/// チェーンを検証して実行を開始（タスクは実行エンジンで順に実行される）
+pub async fn start_task_chain_run(chain_id: &str) -> Result<TaskChainRun, String> {
+    let chain = get_task_chain(chain_id)
+        .map_err(|e| format!("タスクチェーンの取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("タスクチェーンが見つかりません: {}", chain_id))?;
+
+    let validation = validate_task_chain(&chain)
+        .map_err(|e| format!("タスクチェーンの検証に失敗しました: {}", e))?;
+    if !validation.valid {
+        let errors: Vec<String> = validation.errors.iter()
+            .map(|issue| match &issue.node_id {
+                Some(node_id) => format!("{}: {}", node_id, issue.message),
+                None => issue.message.clone(),
+            })
+            .collect();
+        return Err(format!("タスクチェーンが不正です: {}", errors.join(" / ")));
+    }
+
+    let mut state = RunState::default();
+    let cursor = state.new_cursor(&chain.start_node_id, None);
+    state.cursors.push(cursor);
+
+    let _guard = run_lock().lock().await;
+    let db = get_db().ok_or_else(|| "データベースが初期化されていません".to_string())?;
+    let conn = db.get_connection().map_err(|e| e.to_string())?;
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let now = Timestamp::now();
+    conn.execute(
+        "INSERT INTO taskChainRuns (id, chainId, status, startNodeId, nodes, state, error, startedAt, completedAt, createdAt, updatedAt)
+         VALUES (?1, ?2, 'running', ?3, ?4, ?5, NULL, ?6, NULL, ?6, ?6)",
+        params![
+            run_id,
+            chain.id,
+            chain.start_node_id,
+            chain.nodes,
+            serde_json::to_string(&state).map_err(|e| e.to_string())?,
+            now,
+        ],
+    ).map_err(|e| format!("タスクチェーンの実行の保存に失敗しました: {}", e))?;
+    trigger().notify_one();
+
+    get_task_chain_run(&run_id)
+        .map_err(|e| format!("タスクチェーンの実行の取得に失敗しました: {}", e))?
+        .ok_or_else(|| "タスクチェーンの実行の保存後に取得に失敗しました".to_string())
+}
+
+/// チェーンの実行をキャンセル（実行中のタスクもキャンセルする）
+pub async fn cancel_task_chain_run(
+    write_queue: &WriteQueueState,
+    run_id: &str,
+    actor: Option<User>,
+) -> Result<(), String> {
+    let _guard = run_lock().lock().await;
+    let (status, state) = load_run_state(run_id)
+        .map_err(|e| format!("タスクチェーンの実行の取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("タスクチェーンの実行が見つかりません: {}", run_id))?;
+    if status != "running" {
+        return Err(format!("タスクチェーンの実行は既に終了しています: status={}", status));
+    }
+
+    let mut state: RunState = serde_json::from_str(&state).unwrap_or_default();
+    cancel_cursors(write_queue, &state.cursors, actor).await;
+    state.cursors.clear();
+    finish_run(run_id, "cancelled", &state, Some("タスクチェーンがキャンセルされました"))
+        .map_err(|e| format!("タスクチェーンの実行の更新に失敗しました: {}", e))?;
+    trigger().notify_one();
+    Ok(())
+}
+
+/// タスクチェーンの実行エンジンを起動（running の実行は前回の続きから再開する）
+pub fn start_task_chain_runner(app: AppHandle, write_queue: WriteQueueState) {
+    tauri::async_runtime::spawn(async move {
+        let mut changes = subscribe_changes();
+        loop {
+            let next_wake = {
+                let _guard = run_lock().lock().await;
+                match run_pass(&app, &write_queue).await {
+                    Ok(next_wake) => next_wake,
+                    Err(e) => {
+                        eprintln!("[TaskChain] ⚠️ 実行中のタスクチェーンの取得に失敗しました: {}", e);
+                        None
+                    }
+                }
+            };
+
+            let mut wait = Duration::from_secs(POLL_INTERVAL_SECS);
+            if let Some(next_wake) = next_wake {
+                let millis = next_wake.unix_millis() - Timestamp::now().unix_millis();
+                wait = wait.min(Duration::from_millis(millis.max(0) as u64));
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = trigger().notified() => {}
+                // タスク実行の更新は監査ログ経由で変更フィードに流れる
+                changed = changes.changed() => {
+                    if changed.is_err() {
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// 実行中のすべてのチェーンを進める（戻り値は最も早い wait ノードの期限）
+async fn run_pass(app: &AppHandle, write_queue: &WriteQueueState) -> Result<Option<Timestamp>, String> {
+    let run_ids = load_running_run_ids().map_err(|e| e.to_string())?;
+    let mut next_wake: Option<Timestamp> = None;
+    for run_id in run_ids {
+        match advance_run(app, write_queue, &run_id).await {
+            Ok(Some(wake)) => next_wake = Some(next_wake.map_or(wake, |current| current.min(wake))),
+            Ok(None) => {}
+            Err(e) => eprintln!("[TaskChain] ⚠️ タスクチェーンの実行に失敗しました: run={}, error={}", run_id, e),
+        }
+    }
+    Ok(next_wake)
+}
+
+/// 1件の実行を進められるところまで進める
+async fn advance_run(app: &AppHandle, write_queue: &WriteQueueState, run_id: &str) -> Result<Option<Timestamp>, String> {
+    let (chain_id, nodes_json, state_json) = load_run_definition(run_id).map_err(|e| e.to_string())?;
+    let nodes = match parse_chain_nodes(&nodes_json) {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            finish_run(run_id, "failed", &RunState::default(), Some(&e)).map_err(|e| e.to_string())?;
+            emit_progress(app, run_id, &chain_id, "failed", &RunState::default(), Some(e));
+            return Ok(None);
+        }
+    };
+    let mut state: RunState = serde_json::from_str(&state_json)
+        .map_err(|e| format!("実行状態を解析できません: {}", e))?;
+    let before = state_json;
+
+    let mut queue: VecDeque<Cursor> = std::mem::take(&mut state.cursors).into();
+    let mut blocked = Vec::new();
+    let mut next_wake: Option<Timestamp> = None;
+    let mut failure: Option<String> = None;
+
+    while let Some(cursor) = queue.pop_front() {
+        if state.steps >= MAX_CHAIN_STEPS {
+            failure = Some(format!("通過したノードが {} を超えました（無限ループの可能性）", MAX_CHAIN_STEPS));
+            blocked.push(cursor);
+            blocked.extend(queue.drain(..));
+            break;
+        }
+        match advance_cursor(write_queue, run_id, &nodes, &mut state, cursor).await {
+            Advance::Moved(cursors) => queue.extend(cursors),
+            Advance::Blocked(cursor, wake) => {
+                if let Some(wake) = wake {
+                    next_wake = Some(next_wake.map_or(wake, |current| current.min(wake)));
+                }
+                blocked.push(cursor);
+            }
+            Advance::Failed(reason) => {
+                failure = Some(reason);
+                blocked.extend(queue.drain(..));
+                break;
+            }
+        }
+    }
+    state.cursors = blocked;
+
+    if let Some(reason) = failure {
+        // 他の分岐で実行中・実行待ちのタスクを止める
+        cancel_cursors(write_queue, &state.cursors, None).await;
+        state.cursors.clear();
+        finish_run(run_id, "failed", &state, Some(&reason)).map_err(|e| e.to_string())?;
+        emit_progress(app, run_id, &chain_id, "failed", &state, Some(reason));
+        return Ok(None);
+    }
+    if state.cursors.is_empty() {
+        finish_run(run_id, "completed", &state, None).map_err(|e| e.to_string())?;
+        emit_progress(app, run_id, &chain_id, "completed", &state, None);
+        return Ok(None);
+    }
+
+    let state_json = serde_json::to_string(&state).map_err(|e| e.to_string())?;
+    if state_json != before {
+        save_run_state(run_id, &state_json).map_err(|e| e.to_string())?;
+        emit_progress(app, run_id, &chain_id, "running", &state, None);
+    }
+    Ok(next_wake)
+}
+
+/// カーソルを1ノード分進める
+async fn advance_cursor(
+    write_queue: &WriteQueueState,
+    run_id: &str,
+    nodes: &BTreeMap<String, ChainNode>,
+    state: &mut RunState,
+    mut cursor: Cursor,
+) -> Advance {
+    let Some(node) = nodes.get(&cursor.node_id) else {
+        return Advance::Failed(format!("ノード {} が見つかりません", cursor.node_id));
+    };
+
+    // タスクの完了待ち（task ノードと、タスクを本体とする loop ノード）
+    if let Some(execution_id) = cursor.execution_id.clone() {
+        let execution = match get_task_execution(&execution_id) {
+            Ok(Some(execution)) => execution,
+            Ok(None) => return Advance::Failed(format!("ノード {} のタスク実行 {} が見つかりません", node.id, execution_id)),
+            Err(e) => return Advance::Failed(format!("タスク実行の取得に失敗しました: {}", e)),
+        };
+        match execution.status.as_str() {
+            "pending" | "running" => return Advance::Blocked(cursor, None),
+            "completed" => {
+                let context = execution_context(&execution);
+                if let Some(step_id) = cursor.step_id.take() {
+                    let _ = complete_step(&step_id, "completed", execution.result.as_deref());
+                }
+                cursor.execution_id = None;
+                cursor.last = Some(context);
+                state.steps += 1;
+                return match &node.kind {
+                    // ループは同じノードで次の繰り返しを判定する
+                    ChainNodeKind::Loop { .. } => Advance::Moved(vec![cursor]),
+                    _ => Advance::Moved(move_to(cursor, node_next(node))),
+                };
+            }
+            status => {
+                if let Some(step_id) = cursor.step_id.take() {
+                    let _ = complete_step(&step_id, status, None);
+                }
+                let error = execution.error.unwrap_or_default();
+                return Advance::Failed(format!("ノード {} のタスクが {} になりました: {}", node.id, status, error));
+            }
+        }
+    }
+
+    state.steps += 1;
+    match &node.kind {
+        ChainNodeKind::Task { next_node_id, .. } => match node.task_id() {
+            Some(task_id) => enqueue_step(write_queue, run_id, node, task_id, cursor).await,
+            None => {
+                // 開始・終了の目印として置かれたノード
+                let _ = record_step(run_id, node, &cursor.id, "completed", None, None);
+                Advance::Moved(move_to(cursor, next_node_id.as_deref()))
+            }
+        },
+        ChainNodeKind::Condition { condition, true_branch, false_branch } => {
+            let matched = condition.as_ref().is_some_and(|c| c.evaluate(cursor.last.as_ref()));
+            let result = json!({ "matched": matched }).to_string();
+            let _ = record_step(run_id, node, &cursor.id, "completed", None, Some(&result));
+            let next = if matched { true_branch } else { false_branch };
+            Advance::Moved(move_to(cursor, next.as_deref()))
+        }
+        ChainNodeKind::Parallel { branches, join_node_id } => {
+            let Some(join_node_id) = join_node_id else {
+                return Advance::Failed(format!("ノード {} の joinNodeId が指定されていません", node.id));
+            };
+            state.joins.insert(join_node_id.clone(), JoinState { expected: branches.len(), arrived: Vec::new() });
+            let _ = record_step(run_id, node, &cursor.id, "completed", None, None);
+            let cursors = branches.iter()
+                .map(|branch| state.new_cursor(branch, cursor.last.clone()))
+                .collect();
+            Advance::Moved(cursors)
+        }
+        ChainNodeKind::Join { next_node_id } => {
+            let _ = record_step(run_id, node, &cursor.id, "completed", None, None);
+            let Some(join) = state.joins.get_mut(&node.id) else {
+                // parallel を経由しない場合は通過するだけ
+                return Advance::Moved(move_to(cursor, next_node_id.as_deref()));
+            };
+            join.arrived.push(cursor.last.clone().unwrap_or(Value::Null));
+            if join.arrived.len() < join.expected {
+                return Advance::Moved(Vec::new());
+            }
+            let arrived = state.joins.remove(&node.id).map(|j| j.arrived).unwrap_or_default();
+            let last = json!({ "status": "completed", "result": { "branches": arrived } });
+            match next_node_id {
+                Some(next) => Advance::Moved(vec![state.new_cursor(next, Some(last))]),
+                None => Advance::Moved(Vec::new()),
+            }
+        }
+        ChainNodeKind::Loop { loop_count, loop_condition, body_node_id, next_node_id, .. } => {
+            let count = state.loop_counters.get(&node.id).copied().unwrap_or(0);
+            let limit = loop_count.unwrap_or(MAX_LOOP_ITERATIONS).min(MAX_LOOP_ITERATIONS);
+            // 1回目は必ず実行し、2回目以降は loopCondition を満たす間だけ繰り返す
+            let proceed = count < limit
+                && (count == 0 || loop_condition.as_ref().map_or(true, |c| c.evaluate(cursor.last.as_ref())));
+            if !proceed {
+                state.loop_counters.remove(&node.id);
+                let result = json!({ "iterations": count }).to_string();
+                let _ = record_step(run_id, node, &cursor.id, "completed", None, Some(&result));
+                return Advance::Moved(move_to(cursor, next_node_id.as_deref()));
+            }
+
+            state.loop_counters.insert(node.id.clone(), count + 1);
+            match (node.task_id(), body_node_id) {
+                (Some(task_id), _) => enqueue_step(write_queue, run_id, node, task_id, cursor).await,
+                (None, Some(body)) => {
+                    let result = json!({ "iteration": count + 1 }).to_string();
+                    let _ = record_step(run_id, node, &cursor.id, "completed", None, Some(&result));
+                    Advance::Moved(move_to(cursor, Some(body)))
+                }
+                (None, None) => Advance::Failed(format!("ノード {} のループ本体が指定されていません", node.id)),
+            }
+        }
+        ChainNodeKind::Wait { duration_ms, next_node_id } => {
+            let now = Timestamp::now();
+            match cursor.wait_until {
+                None => {
+                    let until = i64::try_from(duration_ms.unwrap_or(0)).ok()
+                        .and_then(|ms| now.unix_millis().checked_add(ms))
+                        .and_then(Timestamp::from_unix_millis);
+                    let Some(until) = until else {
+                        return Advance::Failed(format!("ノード {} の待機時間が長すぎます: {}ms", node.id, duration_ms.unwrap_or(0)));
+                    };
+                    let _ = record_step(run_id, node, &cursor.id, "completed", None, None);
+                    cursor.wait_until = Some(until);
+                    Advance::Blocked(cursor, Some(until))
+                }
+                Some(until) if now < until => {
+                    // 待っている間はステップ数に数えない
+                    state.steps -= 1;
+                    Advance::Blocked(cursor, Some(until))
+                }
+                Some(_) => {
+                    cursor.wait_until = None;
+                    Advance::Moved(move_to(cursor, next_node_id.as_deref()))
+                }
+            }
+        }
+    }
+}
+
+/// タスクを実行エンジンに登録し、完了を待つ状態にする
+async fn enqueue_step(
+    write_queue: &WriteQueueState,
+    run_id: &str,
+    node: &ChainNode,
+    task_id: &str,
+    mut cursor: Cursor,
+) -> Advance {
+    // 実行者は system（actor: None）
+    let execution = match enqueue_task_execution(write_queue, task_id, None).await {
+        Ok(execution) => execution,
+        Err(e) => return Advance::Failed(format!("ノード {} のタスクを登録できませんでした: {}", node.id, e)),
+    };
+    match record_step(run_id, node, &cursor.id, "running", Some(&execution.id), None) {
+        Ok(step_id) => cursor.step_id = Some(step_id),
+        Err(e) => eprintln!("[TaskChain] ⚠️ ステップの保存に失敗しました: {}", e),
+    }
+    cursor.execution_id = Some(execution.id);
+    Advance::Blocked(cursor, None)
+}
+
+/// 次のノードへ移動（次がなければ経路を終了）
+fn move_to(mut cursor: Cursor, next: Option<&str>) -> Vec<Cursor> {
+    match next {
+        Some(next) => {
+            cursor.node_id = next.to_string();
+            vec![cursor]
+        }
+        None => Vec::new(),
+    }
+}
+
+/// task ノードの次のノード
+fn node_next(node: &ChainNode) -> Option<&str> {
+    match &node.kind {
+        ChainNodeKind::Task { next_node_id, .. } => next_node_id.as_deref(),
+        _ => None,
+    }
+}
+
+/// 条件の評価に使う実行結果（output がJSONの場合は解析しておく）
+fn execution_context(execution: &TaskExecution) -> Value {
+    let mut result = execution.result.as_deref()
+        .and_then(|r| serde_json::from_str::<Value>(r).ok())
+        .unwrap_or(Value::Null);
+    let parsed_output = result.get("output")
+        .and_then(|o| o.as_str())
+        .and_then(|o| serde_json::from_str::<Value>(o.trim()).ok())
+        .filter(|o| o.is_object() || o.is_array());
+    if let Some(output) = parsed_output {
+        result["output"] = output;
+    }
+    json!({
+        "status": execution.status,
+        "result": result,
+        "error": execution.error,
+    })
+}
+
+/// カーソルが待っているタスク実行をキャンセル
+async fn cancel_cursors(write_queue: &WriteQueueState, cursors: &[Cursor], actor: Option<User>) {
+    for cursor in cursors {
+        if let Some(execution_id) = &cursor.execution_id {
+            if let Err(e) = cancel_task_execution(write_queue, execution_id, actor.clone()).await {
+                eprintln!("[TaskChain] ⚠️ タスク実行のキャンセルに失敗しました: id={}, error={}", execution_id, e);
+            }
+        }
+        if let Some(step_id) = &cursor.step_id {
+            let _ = complete_step(step_id, "cancelled", None);
+        }
+    }
+}
+
+fn emit_progress(app: &AppHandle, run_id: &str, chain_id: &str, status: &str, state: &RunState, error: Option<String>) {
+    let progress = TaskChainProgress {
+        run_id: run_id.to_string(),
+        chain_id: chain_id.to_string(),
+        status: status.to_string(),
+        active_node_ids: state.cursors.iter().map(|c| c.node_id.clone()).collect(),
+        error,
+        timestamp: Timestamp::now(),
+    };
+    if let Err(e) = app.emit(TASK_CHAIN_EVENT, &progress) {
+        eprintln!("[TaskChain] ⚠️ 進捗イベントの送信に失敗しました: {}", e);
+    }
+}
+
+fn load_running_run_ids() -> SqlResult<Vec<String>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let mut stmt = conn.prepare("SELECT id FROM taskChainRuns WHERE status = 'running' ORDER BY createdAt ASC")?;
+    let ids = stmt.query_map([], |row| row.get(0))?;
+    ids.collect()
+}
+
+/// 実行の chainId・ノード定義・実行状態
+fn load_run_definition(run_id: &str) -> SqlResult<(String, String, String)> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.query_row(
+        "SELECT chainId, nodes, state FROM taskChainRuns WHERE id = ?1",
+        params![run_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+}
+
+/// 実行の状態と実行状態（存在しない場合は None）
+fn load_run_state(run_id: &str) -> SqlResult<Option<(String, String)>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let result = conn.query_row(
+        "SELECT status, state FROM taskChainRuns WHERE id = ?1",
+        params![run_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    );
+    match result {
+        Ok(row) => Ok(Some(row)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn save_run_state(run_id: &str, state: &str) -> SqlResult<()> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.execute(
+        "UPDATE taskChainRuns SET state = ?1, updatedAt = ?2 WHERE id = ?3",
+        params![state, get_timestamp(), run_id],
+    )?;
+    Ok(())
+}
+
+fn finish_run(run_id: &str, status: &str, state: &RunState, error: Option<&str>) -> SqlResult<()> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let now = get_timestamp();
+    conn.execute(
+        "UPDATE taskChainRuns SET status = ?1, state = ?2, error = ?3, completedAt = ?4, updatedAt = ?4 WHERE id = ?5",
+        params![status, serde_json::to_string(state).unwrap_or_default(), error, now, run_id],
+    )?;
+    Ok(())
+}
+
+/// 通過したノードを記録（戻り値はステップID）
+fn record_step(
+    run_id: &str,
+    node: &ChainNode,
+    cursor_id: &str,
+    status: &str,
+    execution_id: Option<&str>,
+    result: Option<&str>,
+) -> SqlResult<String> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = get_timestamp();
+    let completed_at = if status == "running" { None } else { Some(now.clone()) };
+    conn.execute(
+        "INSERT INTO taskChainRunSteps (id, runId, nodeId, nodeType, cursorId, executionId, status, result, startedAt, completedAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![id, run_id, node.id, node.type_name(), cursor_id, execution_id, status, result, now, completed_at],
+    )?;
+    Ok(id)
+}
+
+fn complete_step(step_id: &str, status: &str, result: Option<&str>) -> SqlResult<()> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.execute(
+        "UPDATE taskChainRunSteps SET status = ?1, result = COALESCE(?2, result), completedAt = ?3 WHERE id = ?4",
+        params![status, result, get_timestamp(), step_id],
+    )?;
+    Ok(())
+}
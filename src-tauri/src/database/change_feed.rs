@@ -0,0 +1,190 @@
+/**
+ * 変更フィード
+ * データの作成・更新・削除を、コミット後にTauriイベントとREST API（Server-Sent Events）で通知する
+ *
+ * - 変更は監査ログ（auditLog）から読み取る。監査ログは変更と同じトランザクションで記録されるため、コミットされた変更だけが通知される
+ * - 各変更には監査ログの連番（auditLog.seq、AUTOINCREMENT で再利用されない）をつける。seq は再開トークンとして使え、アプリを再起動しても有効
+ * - 監査ログへの記録時にワーカーを起こし、少し待ってから新しい行を読み取る（取りこぼしに備えて定期的にも確認する）
+ * - Tauri側には "db-change" イベントで送る。REST API側は subscribe_changes で最新の seq を監視し、list_changes で差分を取得する
+ * - 通知するのはテーブル・ID・操作のみ（変更内容は含めない）。必要な場合は受け取った側で取得し直す
+ */
+
+use crate::database::{get_db, Timestamp};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{watch, Notify};
+
+/// 変更通知用のTauriイベント名
+pub const CHANGE_EVENT: &str = "db-change";
+
+/// 1回の読み取りで取得する最大件数
+pub const CHANGE_PAGE_SIZE: i64 = 500;
+/// ワーカーを起こしてから読み取るまでの待ち時間（記録したトランザクションのコミットを待つ）
+const WAKE_DELAY_MS: u64 = 50;
+/// 起こされなかった場合の確認間隔
+const POLL_INTERVAL_SECS: u64 = 2;
+
+/// 変更通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// 連番（再開トークン）
+    pub seq: i64,
+    pub table: String,
+    pub id: String,
+    /// create / update / delete / restore / purge
+    pub operation: String,
+    pub timestamp: Timestamp,
+}
+
+static WAKE: OnceLock<Notify> = OnceLock::new();
+static LATEST_SEQ: OnceLock<watch::Sender<i64>> = OnceLock::new();
+
+fn wake() -> &'static Notify {
+    WAKE.get_or_init(Notify::new)
+}
+
+fn latest_seq() -> &'static watch::Sender<i64> {
+    LATEST_SEQ.get_or_init(|| watch::channel(0).0)
+}
+
+/// 変更を記録したことをワーカーに知らせる（監査ログへの記録時に呼ぶ）
+pub(crate) fn wake_change_feed() {
+    wake().notify_one();
+}
+
+/// 通知済みの最新の seq を監視（変更があるたびに値が更新される）
+pub fn subscribe_changes() -> watch::Receiver<i64> {
+    latest_seq().subscribe()
+}
+
+/// 再開トークンをパース（数字以外は None）
+pub fn parse_resume_token(token: &str) -> Option<i64> {
+    token.trim().parse::<i64>().ok().filter(|seq| *seq >= 0)
+}
+
+fn not_initialized() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    )
+}
+
+/// 現在の最新の seq を取得（変更がない場合は 0）
+pub fn latest_change_seq() -> SqlResult<i64> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.query_row("SELECT COALESCE(MAX(seq), 0) FROM auditLog", [], |row| row.get(0))
+}
+
+/// after より後の変更を古い順に取得（tables を指定した場合はそのテーブルのみ）
+pub fn list_changes(after: i64, tables: Option<&[String]>, limit: i64) -> SqlResult<Vec<ChangeEvent>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut sql = "SELECT seq, tableName, recordId, action, createdAt FROM auditLog WHERE seq > ?".to_string();
+    let mut params = vec![SqlValue::Integer(after)];
+    if let Some(tables) = tables {
+        if tables.is_empty() {
+            return Ok(Vec::new());
+        }
+        sql.push_str(&format!(" AND tableName IN ({})", vec!["?"; tables.len()].join(", ")));
+        params.extend(tables.iter().map(|t| SqlValue::Text(t.clone())));
+    }
+    sql.push_str(" ORDER BY seq ASC LIMIT ?");
+    params.push(SqlValue::Integer(limit.clamp(1, CHANGE_PAGE_SIZE)));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let changes = stmt.query_map(params_from_iter(params), |row| {
+        Ok(ChangeEvent {
+            seq: row.get(0)?,
+            table: row.get(1)?,
+            id: row.get(2)?,
+            operation: row.get(3)?,
+            timestamp: row.get(4)?,
+        })
+    })?;
+    changes.collect()
+}
+
+/// 変更フィードのワーカーを起動（新しい変更をTauriイベントで送り、最新の seq を更新する）
+pub fn start_change_feed_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // 起動前の変更は通知しない
+        let mut last_seq = match latest_change_seq() {
+            Ok(seq) => seq,
+            Err(e) => {
+                eprintln!("[ChangeFeed] ❌ 変更フィードを開始できませんでした: {}", e);
+                return;
+            }
+        };
+        latest_seq().send_replace(last_seq);
+
+        loop {
+            tokio::select! {
+                _ = wake().notified() => {
+                    tokio::time::sleep(Duration::from_millis(WAKE_DELAY_MS)).await;
+                }
+                _ = tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+            }
+
+            loop {
+                let changes = match list_changes(last_seq, None, CHANGE_PAGE_SIZE) {
+                    Ok(changes) => changes,
+                    Err(e) => {
+                        eprintln!("[ChangeFeed] ⚠️ 変更の取得に失敗しました: {}", e);
+                        break;
+                    }
+                };
+                let count = changes.len() as i64;
+                for change in changes {
+                    if let Err(e) = app.emit(CHANGE_EVENT, &change) {
+                        eprintln!("[ChangeFeed] ⚠️ 変更イベントの送信に失敗しました: {}", e);
+                    }
+                    last_seq = change.seq;
+                }
+                if count > 0 {
+                    latest_seq().send_replace(last_seq);
+                }
+                if count < CHANGE_PAGE_SIZE {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{init_test_database, record_audit, AuditAction};
+    use uuid::Uuid;
+
+    #[test]
+    fn lists_changes_after_a_seq_in_order() {
+        let conn = init_test_database().get_connection().unwrap();
+        // 他のテストの監査ログと混ざらないよう、テストごとのテーブル名で絞り込む
+        let table = format!("feed_{}", Uuid::new_v4().simple());
+        let tables = [table.clone()];
+        let start = latest_change_seq().unwrap();
+
+        record_audit(&conn, AuditAction::Create, &table, "r1", None, None).unwrap();
+        record_audit(&conn, AuditAction::Delete, &table, "r1", None, None).unwrap();
+        let changes = list_changes(start, Some(&tables), CHANGE_PAGE_SIZE).unwrap();
+        assert_eq!(
+            changes.iter().map(|c| c.operation.as_str()).collect::<Vec<_>>(),
+            vec!["create", "delete"]
+        );
+        assert!(changes[0].seq > start && changes[1].seq > changes[0].seq);
+        assert!(latest_change_seq().unwrap() >= changes[1].seq);
+
+        // 再開トークン以降の変更だけを返す
+        let resumed = list_changes(changes[0].seq, Some(&tables), CHANGE_PAGE_SIZE).unwrap();
+        assert_eq!(resumed.len(), 1);
+        assert_eq!(resumed[0].seq, changes[1].seq);
+        assert!(list_changes(start, Some(&[]), CHANGE_PAGE_SIZE).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,472 @@
+/**
+ * ベクトルストア再同期ワーカー
+ * chromaSynced = 0 の行（未同期・同期失敗）を定期的に走査し、
+ * Rust側で埋め込みを再生成してベクトルストアへ保存する
+ * 失敗時は指数バックオフで再試行し、進捗をTauriイベントとREST APIで公開する
+ */
+
+use crate::database::embedding::generate_embedding;
+use crate::database::search::SearchTarget;
+use crate::database::vector_store::get_vector_store;
+use crate::database::{get_db, get_timestamp, table_schema, Timestamp};
+use crate::db::{WriteJob, WriteQueueState};
+use rusqlite::{params, Result as SqlResult};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+/// 進捗通知用のTauriイベント名
+pub const CHROMA_SYNC_EVENT: &str = "chroma-sync-progress";
+
+/// 1回の走査でテーブルごとに処理する最大件数
+const BATCH_SIZE: usize = 50;
+/// 起動直後の待機時間（ChromaDB Serverの起動を待つ）
+const STARTUP_DELAY_SECS: u64 = 30;
+/// 未同期データがない場合の走査間隔
+const IDLE_INTERVAL_SECS: u64 = 60;
+/// 未同期データが残っている場合の走査間隔
+const BUSY_INTERVAL_SECS: u64 = 1;
+/// 失敗時のバックオフ初期値
+const INITIAL_BACKOFF_SECS: u64 = 5;
+/// 失敗時のバックオフ上限
+const MAX_BACKOFF_SECS: u64 = 600;
+
+/// 再同期の進捗状態
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChromaSyncProgress {
+    pub running: bool,
+    pub pending: usize,
+    pub processed: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    #[serde(rename = "currentType")]
+    pub current_type: Option<String>,
+    #[serde(rename = "lastRunAt")]
+    pub last_run_at: Option<String>,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "backoffSecs")]
+    pub backoff_secs: u64,
+    #[serde(rename = "nextRunAt")]
+    pub next_run_at: Option<String>,
+}
+
+/// 再同期対象の行
+struct PendingItem {
+    target: SearchTarget,
+    id: String,
+    organization_id: String,
+    meeting_note_id: String,
+    /// 埋め込むテキスト（行のJSONカラムを読み込めない場合はエラー）
+    text: Result<String, String>,
+    metadata: HashMap<String, Value>,
+}
+
+static PROGRESS: OnceLock<Mutex<ChromaSyncProgress>> = OnceLock::new();
+static TRIGGER: OnceLock<Notify> = OnceLock::new();
+
+fn progress_state() -> &'static Mutex<ChromaSyncProgress> {
+    PROGRESS.get_or_init(|| Mutex::new(ChromaSyncProgress::default()))
+}
+
+fn trigger() -> &'static Notify {
+    TRIGGER.get_or_init(Notify::new)
+}
+
+/// 現在の再同期進捗を取得
+pub fn get_chroma_sync_progress() -> ChromaSyncProgress {
+    progress_state().lock().map(|p| p.clone()).unwrap_or_default()
+}
+
+/// 待機中の再同期ワーカーを起こして即時に走査させる
+pub fn trigger_chroma_sync() {
+    trigger().notify_one();
+}
+
+/// 進捗を更新してTauriイベントで通知
+fn update_progress<F: FnOnce(&mut ChromaSyncProgress)>(app: &AppHandle, f: F) {
+    let snapshot = match progress_state().lock() {
+        Ok(mut progress) => {
+            f(&mut progress);
+            progress.clone()
+        }
+        Err(_) => return,
+    };
+    if let Err(e) = app.emit(CHROMA_SYNC_EVENT, &snapshot) {
+        eprintln!("[ChromaSync] ⚠️ 進捗イベントの送信に失敗しました: {}", e);
+    }
+}
+
+/// 再同期ワーカーを起動
+pub fn start_chroma_sync_worker(app: AppHandle, write_queue: WriteQueueState) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(STARTUP_DELAY_SECS)).await;
+
+        let mut backoff_secs = INITIAL_BACKOFF_SECS;
+        loop {
+            let wait_secs = match run_sync_pass(&app, &write_queue).await {
+                Ok(remaining) => {
+                    backoff_secs = INITIAL_BACKOFF_SECS;
+                    if remaining > 0 { BUSY_INTERVAL_SECS } else { IDLE_INTERVAL_SECS }
+                }
+                Err(e) => {
+                    eprintln!("[ChromaSync] ⚠️ 再同期に失敗しました（{}秒後に再試行）: {}", backoff_secs, e);
+                    let wait = backoff_secs;
+                    backoff_secs = (backoff_secs * 2).min(MAX_BACKOFF_SECS);
+                    update_progress(&app, |p| p.last_error = Some(e));
+                    wait
+                }
+            };
+
+            let next_run_at = Some(Timestamp::now().add_seconds(wait_secs as i64).to_rfc3339());
+            update_progress(&app, |p| {
+                p.running = false;
+                p.current_type = None;
+                p.backoff_secs = wait_secs;
+                p.next_run_at = next_run_at;
+            });
+
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(wait_secs)) => {}
+                _ = trigger().notified() => {}
+            }
+        }
+    });
+}
+
+/// 1回分の再同期を実行（戻り値は残りの未同期件数）
+async fn run_sync_pass(app: &AppHandle, write_queue: &WriteQueueState) -> Result<usize, String> {
+    let pending = count_pending().map_err(|e| format!("未同期件数の取得に失敗しました: {}", e))?;
+    update_progress(app, |p| {
+        p.pending = pending;
+        p.last_run_at = Some(get_timestamp());
+    });
+    if pending == 0 {
+        return Ok(0);
+    }
+
+    let store = get_vector_store()?;
+    update_progress(app, |p| p.running = true);
+
+    for target in SearchTarget::all() {
+        let items = load_pending(target, BATCH_SIZE)
+            .map_err(|e| format!("未同期データの取得に失敗しました: {}", e))?;
+        update_progress(app, |p| p.current_type = Some(target.as_str().to_string()));
+
+        for item in items {
+            let result = sync_item(store.as_ref(), &item).await;
+            let (synced, error) = match &result {
+                Ok(()) => (true, None),
+                Err(e) => (false, Some(e.clone())),
+            };
+
+            let job = WriteJob::UpdateChromaSyncStatus {
+                entity_type: item.target.as_str().to_string(),
+                entity_id: item.id.clone(),
+                synced,
+                error,
+            };
+            // バックグラウンドの再同期のため実行者は system（actor: None）。反映されてから次の行に進む
+            write_queue.send(job, None).await?;
+
+            update_progress(app, |p| {
+                p.processed += 1;
+                if synced {
+                    p.succeeded += 1;
+                    p.pending = p.pending.saturating_sub(1);
+                } else {
+                    p.failed += 1;
+                }
+            });
+
+            // 埋め込みAPIやベクトルストアの障害は後続の行にも影響するため、この走査を中断してバックオフする
+            // （行のデータを読み込めない場合はその行だけ失敗として記録し、次の行に進む）
+            if let (Err(e), Ok(_)) = (result, &item.text) {
+                return Err(format!("{}の同期に失敗しました: id={}, error={}", item.target.as_str(), item.id, e));
+            }
+        }
+    }
+
+    Ok(count_pending().unwrap_or(0))
+}
+
+/// 1行分の埋め込みを生成してベクトルストアへ保存
+async fn sync_item(store: &dyn crate::database::vector_store::VectorStore, item: &PendingItem) -> Result<(), String> {
+    let text = item.text.as_ref().map_err(|e| e.clone())?;
+    let embedding = generate_embedding(text).await?;
+    match item.target {
+        SearchTarget::Entity => {
+            store.save_entity_embedding(item.id.clone(), item.organization_id.clone(), embedding, item.metadata.clone()).await
+        }
+        SearchTarget::Relation => {
+            store.save_relation_embedding(item.id.clone(), item.organization_id.clone(), embedding, item.metadata.clone()).await
+        }
+        SearchTarget::Topic => {
+            store.save_topic_embedding(
+                item.id.clone(),
+                item.meeting_note_id.clone(),
+                item.organization_id.clone(),
+                embedding,
+                item.metadata.clone(),
+            ).await
+        }
+    }
+}
+
+/// 未同期の行数を取得（組織に属する、ゴミ箱にない行のみ対象）
+fn count_pending() -> SqlResult<usize> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let mut total = 0usize;
+    for target in SearchTarget::all() {
+        let count: i64 = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {} WHERE COALESCE(chromaSynced, 0) = 0 AND organizationId IS NOT NULL AND deletedAt IS NULL",
+                target.table()
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+        total += count as usize;
+    }
+    Ok(total)
+}
+
+/// 未同期の行を取得（最終試行日時が古い順）
+fn load_pending(target: SearchTarget, limit: usize) -> SqlResult<Vec<PendingItem>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    let now = get_timestamp();
+
+    match target {
+        SearchTarget::Entity => {
+            let schema = table_schema(&conn, "entities")?;
+            let mut stmt = conn.prepare(
+                "SELECT id, name, type, aliases, metadata, organizationId, companyId
+                 FROM entities
+                 WHERE COALESCE(chromaSynced, 0) = 0 AND organizationId IS NOT NULL AND deletedAt IS NULL
+                 ORDER BY lastChromaSyncAttempt ASC
+                 LIMIT ?1",
+            )?;
+            let rows = stmt.query_map(params![limit as i64], |row| {
+                let id: String = row.get(0)?;
+                let name: String = row.get(1)?;
+                let entity_type: String = row.get(2)?;
+                let aliases: Option<String> = row.get(3)?;
+                let metadata: Option<String> = row.get(4)?;
+                let organization_id: String = row.get(5)?;
+                let company_id: Option<String> = row.get(6)?;
+
+                // フロントエンドと同じく 名前 + 別名 + メタデータ を結合して埋め込む
+                // 別名はドキュメントAPIと同じくカラム型レジストリで読み込む（壊れたJSONはこの行の同期エラーにする）
+                let text = schema.read_value(3, "aliases", row.get_ref(3)?)
+                    .map_err(|e| e.to_string())
+                    .map(|aliases| {
+                        let mut parts = vec![name.clone()];
+                        match aliases {
+                            Value::Null => {}
+                            Value::Array(list) => parts.extend(
+                                list.into_iter().map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string())),
+                            ),
+                            Value::String(alias) => parts.push(alias),
+                            other => parts.push(other.to_string()),
+                        }
+                        if let Some(metadata) = metadata.as_deref().filter(|s| !s.is_empty() && *s != "{}") {
+                            parts.push(metadata.to_string());
+                        }
+                        parts.join("\n\n")
+                    });
+
+                let mut meta = HashMap::new();
+                meta.insert("entityId".to_string(), Value::String(id.clone()));
+                meta.insert("organizationId".to_string(), Value::String(organization_id.clone()));
+                meta.insert("companyId".to_string(), Value::String(company_id.unwrap_or_default()));
+                meta.insert("name".to_string(), Value::String(name));
+                meta.insert("type".to_string(), Value::String(entity_type));
+                meta.insert("aliases".to_string(), Value::String(aliases.unwrap_or_default()));
+                meta.insert("metadata".to_string(), Value::String(metadata.unwrap_or_default()));
+                meta.insert("createdAt".to_string(), Value::String(now.clone()));
+                meta.insert("updatedAt".to_string(), Value::String(now.clone()));
+
+                Ok(PendingItem {
+                    target: SearchTarget::Entity,
+                    id,
+                    organization_id,
+                    meeting_note_id: String::new(),
+                    text,
+                    metadata: meta,
+                })
+            })?;
+            rows.collect()
+        }
+        SearchTarget::Relation => {
+            let mut stmt = conn.prepare(
+                "SELECT r.id, r.topicId, r.relationType, r.description, r.metadata, r.organizationId, r.companyId,
+                        r.sourceEntityId, r.targetEntityId, s.name, t.name
+                 FROM relations r
+                 LEFT JOIN entities s ON s.id = r.sourceEntityId
+                 LEFT JOIN entities t ON t.id = r.targetEntityId
+                 WHERE COALESCE(r.chromaSynced, 0) = 0 AND r.organizationId IS NOT NULL AND r.deletedAt IS NULL
+                 ORDER BY r.lastChromaSyncAttempt ASC
+                 LIMIT ?1",
+            )?;
+            let rows = stmt.query_map(params![limit as i64], |row| {
+                let id: String = row.get(0)?;
+                let topic_id: String = row.get(1)?;
+                let relation_type: String = row.get(2)?;
+                let description: Option<String> = row.get(3)?;
+                let metadata: Option<String> = row.get(4)?;
+                let organization_id: String = row.get(5)?;
+                let company_id: Option<String> = row.get(6)?;
+                let source_entity_id: Option<String> = row.get(7)?;
+                let target_entity_id: Option<String> = row.get(8)?;
+                let source_name: String = row.get::<_, Option<String>>(9)?.unwrap_or_default();
+                let target_name: String = row.get::<_, Option<String>>(10)?.unwrap_or_default();
+
+                // フロントエンドと同じく関係タイプを強調し、エンティティ名・説明・メタデータを結合して埋め込む
+                let mut parts = vec![relation_type.clone(); 3];
+                match (source_name.is_empty(), target_name.is_empty()) {
+                    (false, false) => parts.push(format!("{} と {} の関係", source_name, target_name)),
+                    (false, true) => parts.push(format!("{} に関連", source_name)),
+                    (true, false) => parts.push(format!("{} に関連", target_name)),
+                    (true, true) => {}
+                }
+                if let Some(description) = description.as_deref().filter(|s| !s.is_empty()) {
+                    parts.push(description.to_string());
+                }
+                if let Some(metadata) = metadata.as_deref().filter(|s| !s.is_empty() && *s != "{}") {
+                    parts.push(metadata.to_string());
+                }
+
+                let mut meta = HashMap::new();
+                meta.insert("relationId".to_string(), Value::String(id.clone()));
+                meta.insert("topicId".to_string(), Value::String(topic_id));
+                meta.insert("organizationId".to_string(), Value::String(organization_id.clone()));
+                meta.insert("companyId".to_string(), Value::String(company_id.unwrap_or_default()));
+                meta.insert("relationType".to_string(), Value::String(relation_type));
+                meta.insert("sourceEntityId".to_string(), Value::String(source_entity_id.unwrap_or_default()));
+                meta.insert("targetEntityId".to_string(), Value::String(target_entity_id.unwrap_or_default()));
+                meta.insert("sourceEntityName".to_string(), Value::String(source_name));
+                meta.insert("targetEntityName".to_string(), Value::String(target_name));
+                meta.insert("description".to_string(), Value::String(description.unwrap_or_default()));
+                meta.insert("metadata".to_string(), Value::String(metadata.unwrap_or_default()));
+                meta.insert("createdAt".to_string(), Value::String(now.clone()));
+                meta.insert("updatedAt".to_string(), Value::String(now.clone()));
+
+                Ok(PendingItem {
+                    target: SearchTarget::Relation,
+                    id,
+                    organization_id,
+                    meeting_note_id: String::new(),
+                    text: Ok(parts.join("\n\n")),
+                    metadata: meta,
+                })
+            })?;
+            rows.collect()
+        }
+        SearchTarget::Topic => {
+            let mut stmt = conn.prepare(
+                "SELECT tp.id, tp.meetingNoteId, tp.organizationId, tp.title, tp.content,
+                        tp.semanticCategory, tp.keywords, tp.tags, m.title
+                 FROM topics tp
+                 LEFT JOIN meetingNotes m ON m.id = tp.meetingNoteId
+                 WHERE COALESCE(tp.chromaSynced, 0) = 0 AND tp.organizationId IS NOT NULL AND tp.deletedAt IS NULL
+                 ORDER BY tp.lastChromaSyncAttempt ASC
+                 LIMIT ?1",
+            )?;
+            let rows = stmt.query_map(params![limit as i64], |row| {
+                let id: String = row.get(0)?;
+                let meeting_note_id: String = row.get(1)?;
+                let organization_id: String = row.get(2)?;
+                let title: String = row.get(3)?;
+                let content: String = row.get::<_, Option<String>>(4)?.unwrap_or_default();
+                let semantic_category: String = row.get::<_, Option<String>>(5)?.unwrap_or_default();
+                let keywords: String = row.get::<_, Option<String>>(6)?.unwrap_or_default();
+                let tags: String = row.get::<_, Option<String>>(7)?.unwrap_or_default();
+                let meeting_note_title: String = row.get::<_, Option<String>>(8)?.unwrap_or_default();
+
+                // タイトル + 内容 + メタデータ（カテゴリ・キーワード）を結合して埋め込む
+                let mut parts = vec![title.clone()];
+                if !content.is_empty() {
+                    parts.push(content.clone());
+                }
+                if !semantic_category.is_empty() {
+                    parts.push(semantic_category.clone());
+                }
+                if !keywords.is_empty() {
+                    parts.push(keywords.clone());
+                }
+
+                let mut meta = HashMap::new();
+                meta.insert("topicId".to_string(), Value::String(id.clone()));
+                meta.insert("meetingNoteId".to_string(), Value::String(meeting_note_id.clone()));
+                meta.insert("organizationId".to_string(), Value::String(organization_id.clone()));
+                meta.insert("title".to_string(), Value::String(title));
+                meta.insert("contentSummary".to_string(), Value::String(content.chars().take(200).collect()));
+                meta.insert("semanticCategory".to_string(), Value::String(semantic_category));
+                meta.insert("keywords".to_string(), Value::String(keywords));
+                meta.insert("tags".to_string(), Value::String(tags));
+                meta.insert("meetingNoteTitle".to_string(), Value::String(meeting_note_title));
+                meta.insert("createdAt".to_string(), Value::String(now.clone()));
+                meta.insert("updatedAt".to_string(), Value::String(now.clone()));
+
+                Ok(PendingItem {
+                    target: SearchTarget::Topic,
+                    id,
+                    organization_id,
+                    meeting_note_id,
+                    text: Ok(parts.join("\n\n")),
+                    metadata: meta,
+                })
+            })?;
+            rows.collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::init_test_database;
+    use uuid::Uuid;
+
+    fn insert_entity(aliases: &str) -> String {
+        let conn = init_test_database().get_connection().unwrap();
+        let (id, org_id) = (Uuid::new_v4().to_string(), Uuid::new_v4().to_string());
+        conn.execute(
+            "INSERT INTO organizations (id, name, level, levelName, createdAt, updatedAt)
+             VALUES (?1, 'テスト組織', 0, '本部', ?2, ?2)",
+            params![org_id, get_timestamp()],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO entities (id, name, type, aliases, organizationId, chromaSynced, createdAt, updatedAt)
+             VALUES (?1, '山田', 'person', ?2, ?3, 0, ?4, ?4)",
+            params![id, aliases, org_id, get_timestamp()],
+        ).unwrap();
+        id
+    }
+
+    #[test]
+    fn reads_aliases_with_the_column_type_policy() {
+        let valid = insert_entity(r#"["やまだ", "Yamada"]"#);
+        let malformed = insert_entity("やまだ, Yamada");
+
+        let items = load_pending(SearchTarget::Entity, 100_000).unwrap();
+        let text = |id: &str| items.iter().find(|item| item.id == id).map(|item| item.text.clone()).unwrap();
+
+        assert_eq!(text(&valid), Ok("山田\n\nやまだ\n\nYamada".to_string()));
+        let error = text(&malformed).unwrap_err();
+        assert!(error.contains("entities.aliases"), "{}", error);
+    }
+}
@@ -0,0 +1,248 @@
+/**
+ * テーブルごとのカラム型レジストリ
+ *
+ * - カラムの型はスキーマ（PRAGMA table_info の宣言型）から生成し、テーブルごとにキャッシュする
+ * - SQLiteの宣言型だけでは区別できない JSON・真偽値のカラムは、そのカラムを作成したマイグレーションの定義から取得する
+ * - createdAt / updatedAt はすべてのテーブルでタイムスタンプとして扱う（RFC 3339 で保存し、Firestore形式で返す）
+ * - 読み込み（SQLite → JSON）と書き込み（JSON → SQLite）の変換はこのレジストリを通す
+ * - 変換できない値（壊れたJSON、数値でない文字列など）はエラーにする（再同期ワーカーが埋め込むテキストも同じ規則で読み込む）
+ */
+
+use crate::database::migrations::annotated_column_type;
+use crate::database::Timestamp;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, Result as SqlResult, Row};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// カラムの型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Integer,
+    Real,
+    Boolean,
+    Json,
+    Timestamp,
+}
+
+/// タイムスタンプとして扱うカラム
+const TIMESTAMP_COLUMNS: &[&str] = &["createdAt", "updatedAt"];
+
+/// テーブルのカラム型
+#[derive(Debug, Clone)]
+pub struct TableSchema {
+    pub table: String,
+    pub columns: Vec<(String, ColumnType)>,
+}
+
+fn schema_cache() -> &'static RwLock<HashMap<String, Arc<TableSchema>>> {
+    static CACHE: OnceLock<RwLock<HashMap<String, Arc<TableSchema>>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn conversion_error(message: String) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISMATCH),
+        Some(message),
+    )
+}
+
+/// SQLiteの型親和性の規則で宣言型を判定
+fn affinity_type(declared: &str) -> ColumnType {
+    let declared = declared.to_uppercase();
+    if declared.contains("INT") {
+        ColumnType::Integer
+    } else if declared.contains("REAL") || declared.contains("FLOA") || declared.contains("DOUB") {
+        ColumnType::Real
+    } else {
+        ColumnType::Text
+    }
+}
+
+/// テーブルのカラム型を取得（初回はスキーマから生成してキャッシュする）
+/// table は呼び出し側で検証済みのテーブル名であること
+pub fn table_schema(conn: &Connection, table: &str) -> SqlResult<Arc<TableSchema>> {
+    if let Some(schema) = schema_cache().read().ok().and_then(|cache| cache.get(table).cloned()) {
+        return Ok(schema);
+    }
+
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+    })?.collect::<SqlResult<Vec<_>>>()?;
+    if columns.is_empty() {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+            Some(format!("テーブル '{}' が存在しません", table))
+        ));
+    }
+
+    let columns = columns.into_iter().map(|(name, declared)| {
+        let column_type = if TIMESTAMP_COLUMNS.contains(&name.as_str()) {
+            ColumnType::Timestamp
+        } else {
+            annotated_column_type(table, &name).unwrap_or_else(|| affinity_type(&declared))
+        };
+        (name, column_type)
+    }).collect();
+
+    let schema = Arc::new(TableSchema { table: table.to_string(), columns });
+    if let Ok(mut cache) = schema_cache().write() {
+        cache.insert(table.to_string(), schema.clone());
+    }
+    Ok(schema)
+}
+
+/// キャッシュしたカラム型を破棄（マイグレーションでスキーマが変わった後に呼ぶ）
+pub(crate) fn clear_table_schemas() {
+    if let Ok(mut cache) = schema_cache().write() {
+        cache.clear();
+    }
+}
+
+impl TableSchema {
+    pub fn column_type(&self, column: &str) -> Option<ColumnType> {
+        self.columns.iter().find(|(name, _)| name == column).map(|(_, t)| *t)
+    }
+
+    pub fn contains(&self, column: &str) -> bool {
+        self.column_type(column).is_some()
+    }
+
+    /// カラム名を検証（テーブルに存在するカラムのみ許可）
+    pub fn validate_column(&self, column: &str) -> SqlResult<()> {
+        if self.contains(column) {
+            Ok(())
+        } else {
+            Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+                Some(format!("無効なフィールド名: {}", column))
+            ))
+        }
+    }
+
+    /// SQLiteの値をカラムの型に従ってJSONの値に変換（index は結果セット内のカラム位置）
+    pub fn read_value(&self, index: usize, column: &str, value: ValueRef) -> SqlResult<Value> {
+        let column_type = self.column_type(column).unwrap_or(ColumnType::Text);
+        let invalid = |kind: &str, raw: &str| rusqlite::Error::FromSqlConversionFailure(
+            index,
+            value.data_type(),
+            format!(
+                "{}.{} の値を{}として読み込めません: {}",
+                self.table, column, kind, raw.chars().take(100).collect::<String>()
+            ).into(),
+        );
+
+        let text = match value {
+            ValueRef::Null => return Ok(Value::Null),
+            ValueRef::Integer(i) => {
+                return Ok(match column_type {
+                    ColumnType::Boolean => json!(i != 0),
+                    ColumnType::Timestamp => match Timestamp::from_unix_number(i) {
+                        Some(ts) => json!(ts.to_firestore()),
+                        None => return Err(invalid("タイムスタンプ", &i.to_string())),
+                    },
+                    ColumnType::Text => json!(i.to_string()),
+                    _ => json!(i),
+                });
+            }
+            ValueRef::Real(f) => {
+                return Ok(match column_type {
+                    ColumnType::Text => json!(f.to_string()),
+                    _ => json!(f),
+                });
+            }
+            ValueRef::Text(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+            ValueRef::Blob(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        };
+        // 空文字列は値なしとして扱う
+        if text.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        match column_type {
+            ColumnType::Text => Ok(Value::String(text)),
+            ColumnType::Json => serde_json::from_str(&text).map_err(|_| invalid("JSON", &text)),
+            ColumnType::Integer => text.trim().parse::<i64>().map(|i| json!(i)).map_err(|_| invalid("整数", &text)),
+            ColumnType::Real => text.trim().parse::<f64>().map(|f| json!(f)).map_err(|_| invalid("数値", &text)),
+            ColumnType::Boolean => match text.trim() {
+                "1" | "true" => Ok(Value::Bool(true)),
+                "0" | "false" => Ok(Value::Bool(false)),
+                _ => Err(invalid("真偽値", &text)),
+            },
+            ColumnType::Timestamp => Timestamp::parse(&text)
+                .map(|ts| json!(ts.to_firestore()))
+                .ok_or_else(|| invalid("タイムスタンプ", &text)),
+        }
+    }
+
+    /// 行をカラムの型に従ってマップに変換
+    pub fn row_to_map(&self, row: &Row) -> SqlResult<HashMap<String, Value>> {
+        let statement = row.as_ref();
+        let mut map = HashMap::new();
+        for i in 0..statement.column_count() {
+            let column = statement.column_name(i)?;
+            map.insert(column.to_string(), self.read_value(i, column, row.get_ref(i)?)?);
+        }
+        Ok(map)
+    }
+
+    /// JSONの値をカラムの型に従ってSQLiteの値に変換
+    pub fn to_sql_value(&self, column: &str, value: &Value) -> SqlResult<SqlValue> {
+        let column_type = self.column_type(column).unwrap_or(ColumnType::Text);
+        let invalid = || conversion_error(format!(
+            "{}.{} に書き込めない値です: {}",
+            self.table, column, value.to_string().chars().take(100).collect::<String>()
+        ));
+
+        if value.is_null() {
+            return Ok(SqlValue::Null);
+        }
+        Ok(match column_type {
+            ColumnType::Text => match value {
+                Value::String(s) => SqlValue::Text(s.clone()),
+                Value::Number(n) => SqlValue::Text(n.to_string()),
+                Value::Bool(b) => SqlValue::Text(b.to_string()),
+                _ => SqlValue::Text(value.to_string()),
+            },
+            ColumnType::Json => match value {
+                // 既にJSON文字列の場合はそのまま保存する
+                Value::String(s) if serde_json::from_str::<Value>(s).is_ok() => SqlValue::Text(s.clone()),
+                _ => SqlValue::Text(value.to_string()),
+            },
+            ColumnType::Integer => match value {
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => SqlValue::Integer(i),
+                    None => SqlValue::Integer(n.as_f64().ok_or_else(invalid)? as i64),
+                },
+                Value::Bool(b) => SqlValue::Integer(*b as i64),
+                Value::String(s) if s.is_empty() => SqlValue::Null,
+                Value::String(s) => SqlValue::Integer(s.trim().parse().map_err(|_| invalid())?),
+                _ => return Err(invalid()),
+            },
+            ColumnType::Real => match value {
+                Value::Number(n) => SqlValue::Real(n.as_f64().ok_or_else(invalid)?),
+                Value::String(s) if s.is_empty() => SqlValue::Null,
+                Value::String(s) => SqlValue::Real(s.trim().parse().map_err(|_| invalid())?),
+                _ => return Err(invalid()),
+            },
+            ColumnType::Boolean => match value {
+                Value::Bool(b) => SqlValue::Integer(*b as i64),
+                Value::Number(n) => SqlValue::Integer((n.as_f64() != Some(0.0)) as i64),
+                Value::String(s) => match s.trim() {
+                    "1" | "true" => SqlValue::Integer(1),
+                    "0" | "false" => SqlValue::Integer(0),
+                    _ => return Err(invalid()),
+                },
+                _ => return Err(invalid()),
+            },
+            // Firestore形式（{ seconds, nanoseconds }）・数値・文字列を受け付け、RFC 3339 に正規化する
+            ColumnType::Timestamp => match value {
+                Value::String(s) if s.is_empty() => SqlValue::Null,
+                _ => SqlValue::Text(Timestamp::from_json(value).ok_or_else(invalid)?.to_rfc3339()),
+            },
+        })
+    }
+}
@@ -1,8 +1,22 @@
-use rusqlite::{params, Result as SqlResult};
+/**
+ * 事業会社管理
+ * 事業会社は organizations テーブルの type = 'company' の行として管理する
+ * - 所属組織（旧 companies.organizationId）は parentId に保持する
+ * - コード・略称・カテゴリ・主管部署・地域は organizations の追加カラムに保持する
+ */
+
+use rusqlite::{params, OptionalExtension, Result as SqlResult, Row};
 use serde::{Deserialize, Serialize};
-use crate::database::{get_db, get_timestamp};
+use crate::database::{get_db, get_timestamp, get_organization_by_id, delete_organization, snapshot_row, audit_change, AuditAction};
 use uuid::Uuid;
 
+/// 事業会社の階層名
+const COMPANY_LEVEL_NAME: &str = "事業会社";
+
+const COMPANY_COLUMNS: &str =
+    "id, COALESCE(code, ''), name, nameShort, COALESCE(category, ''), COALESCE(parentId, ''),
+     company, division, department, COALESCE(region, ''), COALESCE(position, 0), createdAt, updatedAt";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Company {
     pub id: String,
@@ -24,6 +38,41 @@ pub struct Company {
     pub updated_at: String,
 }
 
+fn row_to_company(row: &Row) -> SqlResult<Company> {
+    Ok(Company {
+        id: row.get(0)?,
+        code: row.get(1)?,
+        name: row.get(2)?,
+        name_short: row.get(3)?,
+        category: row.get(4)?,
+        organization_id: row.get(5)?,
+        company: row.get(6)?,
+        division: row.get(7)?,
+        department: row.get(8)?,
+        region: row.get(9)?,
+        position: row.get(10)?,
+        created_at: row.get(11)?,
+        updated_at: row.get(12)?,
+    })
+}
+
+/// 同じコードの事業会社が既に存在する場合はエラー
+fn ensure_code_available(conn: &rusqlite::Connection, code: &str, exclude_id: Option<&str>) -> SqlResult<()> {
+    let existing: Option<String> = conn.query_row(
+        "SELECT id FROM organizations WHERE type = 'company' AND code = ?1",
+        params![code],
+        |row| row.get(0),
+    ).optional()?;
+
+    match existing {
+        Some(id) if Some(id.as_str()) != exclude_id => Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+            Some(format!("事業会社コード {} は既に使用されています（ゴミ箱にある事業会社を含む）", code)),
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// 事業会社を作成
 pub fn create_company(
     code: String,
@@ -44,34 +93,44 @@ pub fn create_company(
         )
     })?;
 
+    // 所属組織の階層の1つ下に配置する
+    let parent = get_organization_by_id(&organization_id)?;
+
     let conn = db.get_connection()?;
+    ensure_code_available(&conn, &code, None)?;
+
     let id = Uuid::new_v4().to_string();
     let now = get_timestamp();
-    let now_clone = now.clone();
 
     // トランザクションを開始（データベースロックを最小化）
     let tx = conn.unchecked_transaction()?;
-    
+
     tx.execute(
-        "INSERT INTO companies (id, code, name, nameShort, category, organizationId, company, division, department, region, position, createdAt, updatedAt)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        "INSERT INTO organizations (
+            id, parentId, name, level, levelName, position, type,
+            code, nameShort, category, company, division, department, region,
+            createdAt, updatedAt
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'company', ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
         params![
-            id.clone(),
-            code.clone(),
-            name.clone(),
-            name_short.clone(),
-            category.clone(),
-            organization_id.clone(),
-            company.clone(),
-            division.clone(),
-            department.clone(),
-            region.clone(),
+            id,
+            organization_id,
+            name,
+            parent.level + 1,
+            COMPANY_LEVEL_NAME,
             position,
+            code,
+            name_short,
+            category,
+            company,
+            division,
+            department,
+            region,
             now,
-            now_clone
+            now
         ],
     )?;
-    
+    audit_change(&tx, AuditAction::Create, "organizations", &id, None)?;
+
     tx.commit()?;
 
     Ok(Company {
@@ -86,8 +145,8 @@ pub fn create_company(
         department,
         region,
         position,
-        created_at: get_timestamp(),
-        updated_at: get_timestamp(),
+        created_at: now.clone(),
+        updated_at: now,
     })
 }
 
@@ -119,6 +178,9 @@ pub fn update_company(
     let mut comp = get_company_by_id(id)?;
 
     if let Some(code) = code {
+        if code != comp.code {
+            ensure_code_available(&conn, &code, Some(id))?;
+        }
         comp.code = code;
     }
     if let Some(name) = name {
@@ -130,7 +192,12 @@ pub fn update_company(
     if let Some(category) = category {
         comp.category = category;
     }
+    // 所属組織が変わる場合は階層も付け替える
+    let mut level: Option<i32> = None;
     if let Some(organization_id) = organization_id {
+        if organization_id != comp.organization_id {
+            level = Some(get_organization_by_id(&organization_id)?.level + 1);
+        }
         comp.organization_id = organization_id;
     }
     if company.is_some() {
@@ -152,12 +219,19 @@ pub fn update_company(
 
     // トランザクションを開始（データベースロックを最小化）
     let tx = conn.unchecked_transaction()?;
-    
+    let before = snapshot_row(&tx, "organizations", id)?;
+
     tx.execute(
-        "UPDATE companies SET code = ?1, name = ?2, nameShort = ?3, category = ?4, organizationId = ?5, company = ?6, division = ?7, department = ?8, region = ?9, position = ?10, updatedAt = ?11 WHERE id = ?12",
-        params![comp.code, comp.name, comp.name_short, comp.category, comp.organization_id, comp.company, comp.division, comp.department, comp.region, comp.position, now, id],
+        "UPDATE organizations SET code = ?1, name = ?2, nameShort = ?3, category = ?4, parentId = ?5, company = ?6,
+            division = ?7, department = ?8, region = ?9, position = ?10, level = COALESCE(?11, level), updatedAt = ?12
+         WHERE id = ?13 AND type = 'company'",
+        params![
+            comp.code, comp.name, comp.name_short, comp.category, comp.organization_id, comp.company,
+            comp.division, comp.department, comp.region, comp.position, level, now, id
+        ],
     )?;
-    
+    audit_change(&tx, AuditAction::Update, "organizations", id, before)?;
+
     tx.commit()?;
 
     Ok(comp)
@@ -175,26 +249,9 @@ pub fn get_company_by_id(id: &str) -> SqlResult<Company> {
     let conn = db.get_connection()?;
 
     conn.query_row(
-        "SELECT id, code, name, nameShort, category, organizationId, company, division, department, region, position, createdAt, updatedAt
-         FROM companies WHERE id = ?1",
+        &format!("SELECT {} FROM organizations WHERE id = ?1 AND type = 'company' AND deletedAt IS NULL", COMPANY_COLUMNS),
         params![id],
-        |row| {
-            Ok(Company {
-                id: row.get(0)?,
-                code: row.get(1)?,
-                name: row.get(2)?,
-                name_short: row.get(3)?,
-                category: row.get(4)?,
-                organization_id: row.get(5)?,
-                company: row.get(6)?,
-                division: row.get(7)?,
-                department: row.get(8)?,
-                region: row.get(9)?,
-                position: row.get(10)?,
-                created_at: row.get(11)?,
-                updated_at: row.get(12)?,
-            })
-        },
+        row_to_company,
     )
 }
 
@@ -210,26 +267,9 @@ pub fn get_company_by_code(code: &str) -> SqlResult<Company> {
     let conn = db.get_connection()?;
 
     conn.query_row(
-        "SELECT id, code, name, nameShort, category, organizationId, company, division, department, region, position, createdAt, updatedAt
-         FROM companies WHERE code = ?1",
+        &format!("SELECT {} FROM organizations WHERE code = ?1 AND type = 'company' AND deletedAt IS NULL", COMPANY_COLUMNS),
         params![code],
-        |row| {
-            Ok(Company {
-                id: row.get(0)?,
-                code: row.get(1)?,
-                name: row.get(2)?,
-                name_short: row.get(3)?,
-                category: row.get(4)?,
-                organization_id: row.get(5)?,
-                company: row.get(6)?,
-                division: row.get(7)?,
-                department: row.get(8)?,
-                region: row.get(9)?,
-                position: row.get(10)?,
-                created_at: row.get(11)?,
-                updated_at: row.get(12)?,
-            })
-        },
+        row_to_company,
     )
 }
 
@@ -244,28 +284,12 @@ pub fn get_companies_by_organization_id(organization_id: &str) -> SqlResult<Vec<
 
     let conn = db.get_connection()?;
 
-    let mut stmt = conn.prepare(
-        "SELECT id, code, name, nameShort, category, organizationId, company, division, department, region, position, createdAt, updatedAt
-         FROM companies WHERE organizationId = ?1 ORDER BY position ASC, code ASC",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM organizations WHERE type = 'company' AND parentId = ?1 AND deletedAt IS NULL ORDER BY position ASC, code ASC",
+        COMPANY_COLUMNS
+    ))?;
 
-    let companies = stmt.query_map(params![organization_id], |row| {
-        Ok(Company {
-            id: row.get(0)?,
-            code: row.get(1)?,
-            name: row.get(2)?,
-            name_short: row.get(3)?,
-            category: row.get(4)?,
-            organization_id: row.get(5)?,
-            company: row.get(6)?,
-            division: row.get(7)?,
-            department: row.get(8)?,
-            region: row.get(9)?,
-            position: row.get(10)?,
-            created_at: row.get(11)?,
-            updated_at: row.get(12)?,
-        })
-    })?;
+    let companies = stmt.query_map(params![organization_id], row_to_company)?;
 
     companies.collect::<Result<Vec<_>, _>>()
 }
@@ -281,34 +305,18 @@ pub fn get_all_companies() -> SqlResult<Vec<Company>> {
 
     let conn = db.get_connection()?;
 
-    let mut stmt = conn.prepare(
-        "SELECT id, code, name, nameShort, category, organizationId, company, division, department, region, position, createdAt, updatedAt
-         FROM companies ORDER BY organizationId ASC, position ASC, code ASC",
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM organizations WHERE type = 'company' AND deletedAt IS NULL ORDER BY parentId ASC, position ASC, code ASC",
+        COMPANY_COLUMNS
+    ))?;
 
-    let companies = stmt.query_map([], |row| {
-        Ok(Company {
-            id: row.get(0)?,
-            code: row.get(1)?,
-            name: row.get(2)?,
-            name_short: row.get(3)?,
-            category: row.get(4)?,
-            organization_id: row.get(5)?,
-            company: row.get(6)?,
-            division: row.get(7)?,
-            department: row.get(8)?,
-            region: row.get(9)?,
-            position: row.get(10)?,
-            created_at: row.get(11)?,
-            updated_at: row.get(12)?,
-        })
-    })?;
+    let companies = stmt.query_map([], row_to_company)?;
 
     companies.collect::<Result<Vec<_>, _>>()
 }
 
-/// 事業会社を削除
-pub fn delete_company(id: &str) -> SqlResult<()> {
+/// 複数の事業会社の表示順序（position）を一括更新
+pub fn update_company_positions(updates: &[(String, i32)]) -> SqlResult<()> {
     let db = get_db().ok_or_else(|| {
         rusqlite::Error::SqliteFailure(
             rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
@@ -317,17 +325,28 @@ pub fn delete_company(id: &str) -> SqlResult<()> {
     })?;
 
     let conn = db.get_connection()?;
-    
-    // トランザクションを開始（データベースロックを最小化）
     let tx = conn.unchecked_transaction()?;
-    
-    tx.execute("DELETE FROM companies WHERE id = ?1", params![id])?;
-    
+    let now = get_timestamp();
+
+    for (company_id, position) in updates {
+        tx.execute(
+            "UPDATE organizations SET position = ?1, updatedAt = ?2 WHERE id = ?3 AND type = 'company'",
+            params![position, now, company_id],
+        )?;
+    }
+
     tx.commit()?;
 
     Ok(())
 }
 
+/// 事業会社を削除（関連する表示関係・事業会社コンテンツとまとめてゴミ箱に移動）
+pub fn delete_company(id: &str) -> SqlResult<()> {
+    // 通常の組織を誤って削除しないよう、事業会社であることを確認する
+    get_company_by_id(id)?;
+    delete_organization(id)
+}
+
 /// CSVフィールドをエスケープ
 fn escape_csv_field(field: &str) -> String {
     if field.contains(',') || field.contains('"') || field.contains('\n') {
@@ -340,7 +359,7 @@ fn escape_csv_field(field: &str) -> String {
 /// 事業会社をCSV形式でエクスポート
 pub fn export_companies_to_csv() -> SqlResult<String> {
     let companies = get_all_companies()?;
-    
+
     // 組織名のマップを作成（事業会社のCSVに組織名を含めるため）
     use crate::database::get_all_organizations;
     let organizations = get_all_organizations()?;
@@ -348,16 +367,16 @@ pub fn export_companies_to_csv() -> SqlResult<String> {
         .iter()
         .map(|org| (org.id.clone(), org.name.clone()))
         .collect();
-    
+
     let mut csv_lines = Vec::new();
-    
+
     // BOMを追加（Excelで正しく表示されるように）
     csv_lines.push("\u{FEFF}".to_string());
-    
+
     // === 事業会社データ ===
     csv_lines.push("=== 事業会社データ ===".to_string());
     csv_lines.push("ID,コード,会社名,略称,カテゴリ,組織ID,組織名,主管カンパニー,主管部門,主管部,地域,表示順序,作成日時,更新日時".to_string());
-    
+
     for company in &companies {
         let org_name = org_map.get(&company.organization_id).cloned().unwrap_or_default();
         let line = format!(
@@ -379,6 +398,6 @@ pub fn export_companies_to_csv() -> SqlResult<String> {
         );
         csv_lines.push(line);
     }
-    
+
     Ok(csv_lines.join("\n"))
 }
@@ -0,0 +1,301 @@
+/**
+ * cron式
+ * スケジュール（scheduler.rs）の実行時刻を計算する
+ *
+ * - 5フィールド（分 時 日 月 曜日）の標準的な形式。`*`、`,`（列挙）、`-`（範囲）、`/`（間隔）を使用できる
+ * - 曜日は 0〜7（0 と 7 は日曜日）。`@hourly` / `@daily` / `@weekly` / `@monthly` / `@yearly` も使用できる
+ * - 日と曜日の両方を指定した場合は、どちらかに一致すれば実行する（標準のcronと同じ）
+ * - 時刻はローカルタイムゾーンで評価する
+ */
+
+use crate::database::Timestamp;
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone, Timelike};
+
+/// 次の実行時刻を探す最大日数（2月29日のみの指定などに対応するため4年以上にする）
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// 解析済みのcron式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    /// 日が `*` 以外で指定されている
+    day_of_month_restricted: bool,
+    /// 曜日が `*` 以外で指定されている
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    /// cron式を解析
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let expanded = match expression {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("cron式は「分 時 日 月 曜日」の5フィールドで指定してください: {}", expression));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7, "曜日")?;
+        // 7 は日曜日（0）として扱う
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+        days_of_week.truncate(7);
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, "分")?,
+            hours: parse_field(fields[1], 0, 23, "時")?,
+            days_of_month: parse_field(fields[2], 1, 31, "日")?,
+            months: parse_field(fields[3], 1, 12, "月")?,
+            days_of_week,
+            day_of_month_restricted: !fields[2].starts_with('*'),
+            day_of_week_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    /// after より後の最初の実行時刻（見つからない場合は None）
+    pub fn next_after(&self, after: Timestamp) -> Option<Timestamp> {
+        self.next_after_in(&Local, after)
+    }
+
+    /// タイムゾーンを指定して次の実行時刻を計算
+    fn next_after_in<Tz: TimeZone>(&self, tz: &Tz, after: Timestamp) -> Option<Timestamp> {
+        let after = tz.timestamp_millis_opt(after.unix_millis()).single()?.naive_local();
+        // 次の分の0秒から探す
+        let mut candidate = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = candidate + Duration::days(MAX_SEARCH_DAYS);
+
+        while candidate < limit {
+            if !self.months[candidate.month() as usize] {
+                candidate = first_day_of_next_month(candidate.date())?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.matches_day(candidate.date()) {
+                candidate = candidate.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.hours[candidate.hour() as usize] {
+                candidate = candidate.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !self.minutes[candidate.minute() as usize] {
+                candidate += Duration::minutes(1);
+                continue;
+            }
+            // 夏時間の切り替えで存在しない時刻は飛ばす
+            match tz.from_local_datetime(&candidate).earliest() {
+                Some(local) => return Timestamp::from_unix_millis(local.timestamp_millis()),
+                None => candidate += Duration::minutes(1),
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day_of_month = self.days_of_month[date.day() as usize];
+        let day_of_week = self.days_of_week[date.weekday().num_days_from_sunday() as usize];
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            (true, false) => day_of_month,
+            (false, true) => day_of_week,
+            (false, false) => true,
+        }
+    }
+}
+
+/// 1フィールドを解析（インデックスが値の配列を返す）
+fn parse_field(field: &str, min: u32, max: u32, label: &str) -> Result<Vec<bool>, String> {
+    let mut values = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse()
+                    .map_err(|_| format!("{}の間隔が不正です: {}", label, part))?;
+                if step == 0 {
+                    return Err(format!("{}の間隔に0は指定できません: {}", label, part));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start, label)?, parse_value(end, label)?)
+        } else {
+            let value = parse_value(range, label)?;
+            // "5/15" は5から最大値まで15おき
+            (value, if step > 1 { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!("{}は{}〜{}の範囲で指定してください: {}", label, min, max, part));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            values[value as usize] = true;
+        }
+    }
+    Ok(values)
+}
+
+fn parse_value(value: &str, label: &str) -> Result<u32, String> {
+    value.parse().map_err(|_| format!("{}の値が不正です: {}", label, value))
+}
+
+fn first_day_of_next_month(date: NaiveDate) -> Option<NaiveDate> {
+    if date.month() == 12 {
+        NaiveDate::from_ymd_opt(date.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult, NaiveDateTime, Utc};
+
+    /// 2024年の米国東部時間（3/10 2:00 に夏時間開始、11/3 2:00 に終了）
+    #[derive(Debug, Clone, Copy)]
+    struct Eastern2024;
+
+    impl Eastern2024 {
+        fn est() -> FixedOffset {
+            FixedOffset::west_opt(5 * 3600).unwrap()
+        }
+
+        fn edt() -> FixedOffset {
+            FixedOffset::west_opt(4 * 3600).unwrap()
+        }
+
+        fn at(month: u32, day: u32, hour: u32) -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2024, month, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+        }
+    }
+
+    impl TimeZone for Eastern2024 {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            Eastern2024
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            if *local < Self::at(3, 10, 2) {
+                LocalResult::Single(Self::est())
+            } else if *local < Self::at(3, 10, 3) {
+                LocalResult::None
+            } else if *local < Self::at(11, 3, 1) {
+                LocalResult::Single(Self::edt())
+            } else if *local < Self::at(11, 3, 2) {
+                LocalResult::Ambiguous(Self::edt(), Self::est())
+            } else {
+                LocalResult::Single(Self::est())
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc >= Self::at(3, 10, 7) && *utc < Self::at(11, 3, 6) {
+                Self::edt()
+            } else {
+                Self::est()
+            }
+        }
+    }
+
+    fn ts(value: &str) -> Timestamp {
+        Timestamp::parse(value).unwrap()
+    }
+
+    fn next_utc(expression: &str, after: &str) -> Option<String> {
+        CronSchedule::parse(expression).unwrap()
+            .next_after_in(&Utc, ts(after))
+            .map(|t| t.to_rfc3339())
+    }
+
+    #[test]
+    fn parses_fields_and_aliases() {
+        let schedule = CronSchedule::parse("5/15 9-17/4 * * 1,3,7").unwrap();
+        let minutes: Vec<usize> = (0..60).filter(|m| schedule.minutes[*m]).collect();
+        assert_eq!(minutes, vec![5, 20, 35, 50]);
+        let hours: Vec<usize> = (0..24).filter(|h| schedule.hours[*h]).collect();
+        assert_eq!(hours, vec![9, 13, 17]);
+        let days: Vec<usize> = (0..7).filter(|d| schedule.days_of_week[*d]).collect();
+        assert_eq!(days, vec![0, 1, 3]);
+        assert!(!schedule.day_of_month_restricted);
+        assert!(schedule.day_of_week_restricted);
+
+        assert_eq!(CronSchedule::parse("@daily").unwrap(), CronSchedule::parse("0 0 * * *").unwrap());
+        assert_eq!(CronSchedule::parse(" @weekly ").unwrap(), CronSchedule::parse("0 0 * * 7").unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in ["* * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "* * * 13 *", "* * * * 8",
+                           "*/0 * * * *", "5-1 * * * *", "a * * * *", "@reboot"] {
+            assert!(CronSchedule::parse(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn finds_the_next_matching_minute() {
+        assert_eq!(next_utc("*/15 * * * *", "2024-01-01T10:07:30Z").as_deref(), Some("2024-01-01T10:15:00.000Z"));
+        // ちょうど実行時刻の場合は次の回
+        assert_eq!(next_utc("0 * * * *", "2024-01-01T10:00:00Z").as_deref(), Some("2024-01-01T11:00:00.000Z"));
+        assert_eq!(next_utc("@yearly", "2024-06-01T00:00:00Z").as_deref(), Some("2025-01-01T00:00:00.000Z"));
+    }
+
+    #[test]
+    fn matches_either_day_of_month_or_day_of_week() {
+        // 2024-01-02 は火曜日。次の 15日 か 金曜日（1/5）
+        assert_eq!(next_utc("0 9 15 * 5", "2024-01-02T00:00:00Z").as_deref(), Some("2024-01-05T09:00:00.000Z"));
+        // 曜日が * の場合は日のみ
+        assert_eq!(next_utc("0 9 15 * *", "2024-01-02T00:00:00Z").as_deref(), Some("2024-01-15T09:00:00.000Z"));
+    }
+
+    #[test]
+    fn finds_february_29() {
+        assert_eq!(next_utc("0 0 29 2 *", "2024-03-01T00:00:00Z").as_deref(), Some("2028-02-29T00:00:00.000Z"));
+        assert_eq!(next_utc("0 0 29 2 *", "2024-01-31T12:00:00Z").as_deref(), Some("2024-02-29T00:00:00.000Z"));
+        // 存在しない日は見つからない
+        assert_eq!(next_utc("0 0 31 2 *", "2024-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn skips_times_in_the_spring_forward_gap() {
+        let schedule = CronSchedule::parse("30 2 * * *").unwrap();
+        // 3/10 は 2:30 が存在しないため翌日の 2:30（EDT）
+        let next = schedule.next_after_in(&Eastern2024, ts("2024-03-10T06:00:00Z")).unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-03-11T06:30:00.000Z");
+    }
+
+    #[test]
+    fn runs_once_during_the_fall_back_overlap() {
+        let schedule = CronSchedule::parse("30 1 * * *").unwrap();
+        // 1:30 が2回ある日は最初の 1:30（EDT）に実行し、2回目は実行しない
+        let first = schedule.next_after_in(&Eastern2024, ts("2024-11-03T04:00:00Z")).unwrap();
+        assert_eq!(first.to_rfc3339(), "2024-11-03T05:30:00.000Z");
+        let second = schedule.next_after_in(&Eastern2024, first).unwrap();
+        assert_eq!(second.to_rfc3339(), "2024-11-04T06:30:00.000Z");
+    }
+}
@@ -0,0 +1,278 @@
+/**
+ * 埋め込みベクトル生成クライアント
+ * AI設定（aiSettingsテーブル / 環境変数）の ProviderConfig を使用して
+ * Rust側でテキストから埋め込みベクトルを生成する
+ *
+ * - OpenAI / LM Studio: OpenAI互換の `/embeddings` エンドポイント
+ * - Ollama: `/api/embed` エンドポイント
+ * - 生成したベクトルの次元数は EMBEDDING_DIMENSION と一致するか検証する
+ */
+
+use crate::database::ai_settings::{get_ai_setting, AIProvider, ProviderConfig};
+use crate::database::vector_search::EMBEDDING_DIMENSION;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// デフォルトの埋め込みプロバイダー
+const DEFAULT_EMBEDDING_PROVIDER: &str = "openai";
+/// デフォルトのOpenAI APIベースURL
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+/// デフォルトのLM Studio APIベースURL
+const DEFAULT_LMSTUDIO_BASE_URL: &str = "http://localhost:1234/v1";
+/// デフォルトのOllama APIベースURL
+const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+/// リクエストタイムアウト（秒）
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// 埋め込みモデルの情報（次元数の検証結果）
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingModelInfo {
+    pub provider: String,
+    pub model: String,
+    pub dimension: usize,
+    #[serde(rename = "expectedDimension")]
+    pub expected_dimension: usize,
+    pub valid: bool,
+}
+
+/// 埋め込みクライアント
+pub struct EmbeddingClient {
+    config: ProviderConfig,
+    model: String,
+}
+
+/// プロバイダーごとのデフォルト埋め込みモデル（フロントエンドと同じモデル）
+fn get_default_embedding_model(provider: &AIProvider) -> &'static str {
+    match provider {
+        AIProvider::Ollama => "nomic-embed-text",
+        AIProvider::LMStudio => "text-embedding-nomic-embed-text-v1.5",
+        _ => "text-embedding-3-small",
+    }
+}
+
+impl EmbeddingClient {
+    /// ProviderConfigから作成（モデル未指定時はプロバイダーのデフォルト埋め込みモデル）
+    pub fn new(config: ProviderConfig, model: Option<String>) -> Self {
+        let model = model
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| get_default_embedding_model(&config.provider).to_string());
+        Self { config, model }
+    }
+
+    /// 環境変数 EMBEDDING_PROVIDER / EMBEDDING_MODEL とAI設定から作成
+    pub fn from_settings() -> Result<Self, String> {
+        let provider = std::env::var("EMBEDDING_PROVIDER")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| DEFAULT_EMBEDDING_PROVIDER.to_string());
+        let provider_kind = AIProvider::from_str(&provider)
+            .ok_or_else(|| format!("未対応の埋め込みプロバイダーです: {}", provider))?;
+
+        // Ollama / LM Studio はローカルで動作するため、AI設定がなくてもデフォルト設定で使用できる
+        let config = get_ai_setting(&provider)
+            .map_err(|e| format!("AI設定の取得に失敗しました: {}", e))?
+            .unwrap_or(ProviderConfig {
+                provider: provider_kind,
+                api_key: None,
+                base_url: None,
+                model: String::new(),
+            });
+
+        Ok(Self::new(config, std::env::var("EMBEDDING_MODEL").ok()))
+    }
+
+    pub fn provider(&self) -> &str {
+        self.config.provider.as_str()
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// テキストから埋め込みベクトルを生成
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        let mut embeddings = self.embed_batch(&[text.to_string()]).await?;
+        embeddings
+            .pop()
+            .ok_or_else(|| "埋め込みAPIのレスポンスにembeddingが含まれていません".to_string())
+    }
+
+    /// 複数のテキストから埋め込みベクトルを生成（次元数を検証）
+    pub async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let embeddings = match self.config.provider {
+            AIProvider::OpenAI | AIProvider::LMStudio => self.request_openai_compatible(texts).await?,
+            AIProvider::Ollama => self.request_ollama(texts).await?,
+            AIProvider::Anthropic => {
+                return Err("Anthropicは埋め込みAPIを提供していません。openai / ollama / lmstudio を指定してください".to_string());
+            }
+        };
+
+        if embeddings.len() != texts.len() {
+            return Err(format!(
+                "埋め込みAPIのレスポンス件数が一致しません。期待値: {}, 実際: {}",
+                texts.len(),
+                embeddings.len()
+            ));
+        }
+        for embedding in &embeddings {
+            validate_dimension(embedding, &self.model)?;
+        }
+        Ok(embeddings)
+    }
+
+    /// 埋め込みモデルの次元数を確認
+    pub async fn check_model(&self) -> Result<EmbeddingModelInfo, String> {
+        let embeddings = match self.config.provider {
+            AIProvider::OpenAI | AIProvider::LMStudio => self.request_openai_compatible(&["dimension check".to_string()]).await?,
+            AIProvider::Ollama => self.request_ollama(&["dimension check".to_string()]).await?,
+            AIProvider::Anthropic => {
+                return Err("Anthropicは埋め込みAPIを提供していません".to_string());
+            }
+        };
+        let dimension = embeddings.first().map(|e| e.len()).unwrap_or(0);
+
+        Ok(EmbeddingModelInfo {
+            provider: self.provider().to_string(),
+            model: self.model.clone(),
+            dimension,
+            expected_dimension: EMBEDDING_DIMENSION,
+            valid: dimension == EMBEDDING_DIMENSION,
+        })
+    }
+
+    fn http_client() -> Result<reqwest::Client, String> {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| format!("HTTPクライアントの作成に失敗しました: {}", e))
+    }
+
+    /// OpenAI互換の /embeddings エンドポイントを呼び出す
+    async fn request_openai_compatible(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let default_base_url = match self.config.provider {
+            AIProvider::LMStudio => DEFAULT_LMSTUDIO_BASE_URL,
+            _ => DEFAULT_OPENAI_BASE_URL,
+        };
+        let base_url = self.config.base_url.as_deref().unwrap_or(default_base_url);
+        let url = format!("{}/embeddings", base_url.trim_end_matches('/'));
+
+        if matches!(self.config.provider, AIProvider::OpenAI) && self.config.api_key.is_none() {
+            return Err("OpenAIのAPIキーが設定されていません".to_string());
+        }
+
+        let mut request = Self::http_client()?
+            .post(&url)
+            .json(&json!({
+                "model": self.model,
+                "input": texts,
+            }));
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let body = send_request(request).await?;
+        let data = body
+            .get("data")
+            .and_then(|d| d.as_array())
+            .ok_or_else(|| "埋め込みAPIのレスポンスにdataが含まれていません".to_string())?;
+
+        // indexの順に並べ替える（OpenAIは入力順を保証しないため）
+        let mut items: Vec<(u64, Vec<f32>)> = data
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let index = item.get("index").and_then(|v| v.as_u64()).unwrap_or(i as u64);
+                (index, parse_vector(item.get("embedding")))
+            })
+            .collect();
+        items.sort_by_key(|(index, _)| *index);
+        Ok(items.into_iter().map(|(_, embedding)| embedding).collect())
+    }
+
+    /// Ollamaの /api/embed エンドポイントを呼び出す
+    async fn request_ollama(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let base_url = self.config.base_url.as_deref().unwrap_or(DEFAULT_OLLAMA_BASE_URL);
+        // フロントエンドの設定（/api/embeddings まで含むURL）にも対応
+        let base_url = base_url
+            .trim_end_matches('/')
+            .trim_end_matches("/api/embeddings")
+            .trim_end_matches("/api");
+        let url = format!("{}/api/embed", base_url);
+
+        let request = Self::http_client()?
+            .post(&url)
+            .json(&json!({
+                "model": self.model,
+                "input": texts,
+            }));
+
+        let body = send_request(request).await?;
+        let embeddings = body
+            .get("embeddings")
+            .and_then(|e| e.as_array())
+            .ok_or_else(|| "OllamaのレスポンスにEmbeddingsが含まれていません".to_string())?;
+        Ok(embeddings.iter().map(|e| parse_vector(Some(e))).collect())
+    }
+}
+
+/// リクエストを送信してJSONレスポンスを取得
+async fn send_request(request: reqwest::RequestBuilder) -> Result<Value, String> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("埋め込みAPIへのリクエストに失敗しました: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("埋め込みAPIがエラーを返しました: status={}, body={}", status, body));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("埋め込みAPIのレスポンス解析に失敗しました: {}", e))
+}
+
+fn parse_vector(value: Option<&Value>) -> Vec<f32> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_f64().map(|f| f as f32)).collect())
+        .unwrap_or_default()
+}
+
+/// 埋め込みベクトルの次元数を検証
+pub fn validate_dimension(embedding: &[f32], model: &str) -> Result<(), String> {
+    if embedding.len() != EMBEDDING_DIMENSION {
+        return Err(format!(
+            "埋め込みベクトルの次元数が一致しません。期待値: {}, 実際: {}（モデル: {}）",
+            EMBEDDING_DIMENSION,
+            embedding.len(),
+            model
+        ));
+    }
+    Ok(())
+}
+
+/// テキストから埋め込みベクトルを生成（AI設定のプロバイダーを使用）
+pub async fn generate_embedding(text: &str) -> Result<Vec<f32>, String> {
+    EmbeddingClient::from_settings()?.embed(text).await
+}
+
+/// 埋め込みベクトルが指定されていればそれを、なければテキストから生成したものを返す
+pub async fn resolve_embedding(
+    embedding: Option<Vec<f32>>,
+    text: Option<String>,
+) -> Result<Vec<f32>, String> {
+    match (embedding, text) {
+        (Some(embedding), _) => Ok(embedding),
+        (None, Some(text)) if !text.trim().is_empty() => generate_embedding(&text).await,
+        _ => Err("埋め込みベクトルまたはテキストを指定してください".to_string()),
+    }
+}
+
+/// 設定中の埋め込みモデルの次元数を確認
+pub async fn check_embedding_model() -> Result<EmbeddingModelInfo, String> {
+    EmbeddingClient::from_settings()?.check_model().await
+}
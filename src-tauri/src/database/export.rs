@@ -27,6 +27,7 @@ pub fn export_all_data() -> Result<ExportData, Box<dyn std::error::Error>> {
         "backupHistory",
         "organizations",
         "organizationMembers",
+        "trash", // ゴミ箱にある組織・メンバーをインポート後も復元できるように含める
     ];
     
     for table_name in &table_names {
@@ -123,12 +124,17 @@ pub fn import_from_file(import_path: &str) -> Result<(), Box<dyn std::error::Err
                 }
             }
             
-            // INSERT OR REPLACEを使用して既存データを上書き
+            // UPSERTで既存データを上書き（INSERT OR REPLACE は削除トリガーが実行されず、全文検索インデックスがずれる）
+            let assignments = columns.iter()
+                .map(|c| format!("{} = excluded.{}", c, c))
+                .collect::<Vec<_>>()
+                .join(", ");
             let query = format!(
-                "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+                "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT DO UPDATE SET {}",
                 table_name,
                 columns.join(", "),
-                placeholders
+                placeholders,
+                assignments
             );
             
             let mut params: Vec<&dyn rusqlite::ToSql> = Vec::new();
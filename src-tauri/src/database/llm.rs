@@ -0,0 +1,218 @@
+/**
+ * LLMクライアント
+ * AI設定（aiSettingsテーブル / 環境変数）の ProviderConfig を使用して
+ * Rust側でチャット形式のテキスト生成を行う（バックグラウンドのタスク実行で使用）
+ *
+ * - OpenAI / LM Studio: OpenAI互換の `/chat/completions` エンドポイント
+ * - Ollama: `/api/chat` エンドポイント
+ * - Anthropic: `/v1/messages` エンドポイント
+ * - Agent・タスクの modelType（gpt / local / cursor）は、フロントエンドの llmHelper と同じ規則でプロバイダーに対応づける
+ */
+
+use crate::database::ai_settings::{get_ai_setting, AIProvider, ProviderConfig};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// デフォルトのOpenAI APIベースURL
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+/// デフォルトのLM Studio APIベースURL
+const DEFAULT_LMSTUDIO_BASE_URL: &str = "http://localhost:1234/v1";
+/// デフォルトのOllama APIベースURL
+const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+/// デフォルトのAnthropic APIベースURL
+const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+/// Anthropic APIのバージョン
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// Anthropic APIの最大出力トークン数
+const ANTHROPIC_MAX_TOKENS: u32 = 4096;
+/// デフォルトのモデルタイプ（フロントエンドの DEFAULT_MODEL_TYPE と同じ）
+pub const DEFAULT_MODEL_TYPE: &str = "gpt";
+/// デフォルトのモデル（フロントエンドの DEFAULT_MODEL と同じ）
+pub const DEFAULT_MODEL: &str = "gpt-4.1-mini";
+
+/// LLMクライアント
+pub struct LlmClient {
+    config: ProviderConfig,
+    model: String,
+}
+
+/// ローカルモデル名かどうか（フロントエンドの llmHelper と同じ判定）
+fn is_local_model(model: &str) -> bool {
+    model.starts_with("qwen")
+        || model.starts_with("llama")
+        || model.starts_with("mistral")
+        || model.contains(":latest")
+        || model.contains(":instruct")
+}
+
+impl LlmClient {
+    pub fn new(config: ProviderConfig, model: String) -> Self {
+        Self { config, model }
+    }
+
+    /// modelType（gpt / local / cursor）とモデル名から作成
+    pub fn for_model(model_type: &str, model: &str) -> Result<Self, String> {
+        let provider = if model.starts_with("claude") {
+            AIProvider::Anthropic
+        } else if model_type == "local" || is_local_model(model) {
+            AIProvider::Ollama
+        } else if model_type == "gpt" {
+            AIProvider::OpenAI
+        } else {
+            return Err(format!("modelType {} はRust側の実行に対応していません（gpt / local を指定してください）", model_type));
+        };
+
+        // Ollama はローカルで動作するため、AI設定がなくてもデフォルト設定で使用できる
+        let config = get_ai_setting(provider.as_str())
+            .map_err(|e| format!("AI設定の取得に失敗しました: {}", e))?
+            .unwrap_or(ProviderConfig {
+                provider,
+                api_key: None,
+                base_url: None,
+                model: String::new(),
+            });
+
+        Ok(Self::new(config, model.to_string()))
+    }
+
+    pub fn provider(&self) -> &str {
+        self.config.provider.as_str()
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// システムプロンプトとユーザープロンプトからテキストを生成
+    pub async fn complete(&self, system_prompt: &str, prompt: &str) -> Result<String, String> {
+        match self.config.provider {
+            AIProvider::OpenAI | AIProvider::LMStudio => self.request_openai_compatible(system_prompt, prompt).await,
+            AIProvider::Ollama => self.request_ollama(system_prompt, prompt).await,
+            AIProvider::Anthropic => self.request_anthropic(system_prompt, prompt).await,
+        }
+    }
+
+    fn http_client() -> Result<reqwest::Client, String> {
+        // タイムアウトはタスクごとに呼び出し側で管理する
+        reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("HTTPクライアントの作成に失敗しました: {}", e))
+    }
+
+    /// OpenAI互換の /chat/completions エンドポイントを呼び出す
+    async fn request_openai_compatible(&self, system_prompt: &str, prompt: &str) -> Result<String, String> {
+        let default_base_url = match self.config.provider {
+            AIProvider::LMStudio => DEFAULT_LMSTUDIO_BASE_URL,
+            _ => DEFAULT_OPENAI_BASE_URL,
+        };
+        let base_url = self.config.base_url.as_deref().unwrap_or(default_base_url);
+        let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+
+        if matches!(self.config.provider, AIProvider::OpenAI) && self.config.api_key.is_none() {
+            return Err("OpenAIのAPIキーが設定されていません".to_string());
+        }
+
+        let mut request = Self::http_client()?
+            .post(&url)
+            .json(&json!({
+                "model": self.model,
+                "messages": [
+                    { "role": "system", "content": system_prompt },
+                    { "role": "user", "content": prompt },
+                ],
+            }));
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let body = send_request(request).await?;
+        body.pointer("/choices/0/message/content")
+            .and_then(|c| c.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| "LLM APIのレスポンスにcontentが含まれていません".to_string())
+    }
+
+    /// Ollamaの /api/chat エンドポイントを呼び出す
+    async fn request_ollama(&self, system_prompt: &str, prompt: &str) -> Result<String, String> {
+        let base_url = self.config.base_url.as_deref().unwrap_or(DEFAULT_OLLAMA_BASE_URL);
+        // フロントエンドの設定（/api/chat まで含むURL）にも対応
+        let base_url = base_url
+            .trim_end_matches('/')
+            .trim_end_matches("/api/chat")
+            .trim_end_matches("/api");
+        let url = format!("{}/api/chat", base_url);
+
+        let request = Self::http_client()?
+            .post(&url)
+            .json(&json!({
+                "model": self.model,
+                "messages": [
+                    { "role": "system", "content": system_prompt },
+                    { "role": "user", "content": prompt },
+                ],
+                "stream": false,
+            }));
+
+        let body = send_request(request).await?;
+        body.pointer("/message/content")
+            .and_then(|c| c.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| "Ollamaのレスポンスにcontentが含まれていません".to_string())
+    }
+
+    /// Anthropicの /v1/messages エンドポイントを呼び出す
+    async fn request_anthropic(&self, system_prompt: &str, prompt: &str) -> Result<String, String> {
+        let api_key = self.config.api_key.as_deref()
+            .ok_or_else(|| "AnthropicのAPIキーが設定されていません".to_string())?;
+        let base_url = self.config.base_url.as_deref().unwrap_or(DEFAULT_ANTHROPIC_BASE_URL);
+        let base_url = base_url.trim_end_matches('/').trim_end_matches("/v1");
+        let url = format!("{}/v1/messages", base_url);
+
+        let request = Self::http_client()?
+            .post(&url)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&json!({
+                "model": self.model,
+                "max_tokens": ANTHROPIC_MAX_TOKENS,
+                "system": system_prompt,
+                "messages": [
+                    { "role": "user", "content": prompt },
+                ],
+            }));
+
+        let body = send_request(request).await?;
+        let text: String = body.get("content")
+            .and_then(|c| c.as_array())
+            .map(|blocks| {
+                blocks.iter()
+                    .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if text.is_empty() {
+            return Err("Anthropicのレスポンスにtextが含まれていません".to_string());
+        }
+        Ok(text)
+    }
+}
+
+/// リクエストを送信してJSONレスポンスを取得
+async fn send_request(request: reqwest::RequestBuilder) -> Result<Value, String> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("LLM APIへのリクエストに失敗しました: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("LLM APIがエラーを返しました: status={}, body={}", status, body));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("LLM APIのレスポンス解析に失敗しました: {}", e))
+}
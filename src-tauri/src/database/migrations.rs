@@ -0,0 +1,1894 @@
+/**
+ * バージョン管理されたスキーママイグレーション
+ *
+ * - 適用済みのマイグレーションは schema_migrations テーブルに記録する
+ * - 各マイグレーションは1つのトランザクション内で実行し、失敗した場合はロールバックして以降を実行しない
+ * - 各マイグレーションは冪等に書く（記録がない既存データベースに対しても安全に再実行できる）
+ * - 新しいスキーマ変更は MIGRATIONS の末尾に追加する（既存のマイグレーションは変更しない）
+ * - 宣言型だけでは判別できないカラム（JSON・真偽値）は、そのカラムを作成したマイグレーションの column_types に記述する
+ *   （カラム型レジストリはここから型を取得する）
+ */
+
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+
+use super::{get_db, get_timestamp, search, ColumnType, Timestamp};
+use ColumnType::{Boolean, Json};
+
+/// マイグレーション定義
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    /// テーブル再作成のために外部キー制約を無効化して実行するか（PRAGMAはトランザクション外でのみ有効）
+    disable_foreign_keys: bool,
+    up: fn(&Connection) -> SqlResult<()>,
+    /// このマイグレーションで作成したカラムのうち、宣言型だけでは判別できないもの（テーブル名, カラム名, 型）
+    column_types: &'static [(&'static str, &'static str, ColumnType)],
+}
+
+/// 適用済みマイグレーション
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    #[serde(rename = "appliedAt")]
+    pub applied_at: String,
+}
+
+/// スキーマバージョン情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaVersion {
+    #[serde(rename = "currentVersion")]
+    pub current_version: i64,
+    #[serde(rename = "latestVersion")]
+    pub latest_version: i64,
+    #[serde(rename = "pendingVersions")]
+    pub pending_versions: Vec<i64>,
+    pub applied: Vec<AppliedMigration>,
+}
+
+/// マイグレーション一覧（バージョン順）
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "initial_schema", disable_foreign_keys: false, up: m0001_initial_schema, column_types: M0001_COLUMN_TYPES },
+    Migration { version: 2, name: "add_missing_columns", disable_foreign_keys: false, up: m0002_add_missing_columns, column_types: &[] },
+    Migration { version: 3, name: "nullable_organization_id", disable_foreign_keys: true, up: m0003_nullable_organization_id, column_types: &[] },
+    Migration { version: 4, name: "knowledge_tables_company_id", disable_foreign_keys: true, up: m0004_knowledge_tables_company_id, column_types: &[] },
+    Migration { version: 5, name: "rag_search_columns", disable_foreign_keys: false, up: m0005_rag_search_columns, column_types: &[] },
+    Migration { version: 6, name: "indexes_and_triggers", disable_foreign_keys: false, up: m0006_indexes_and_triggers, column_types: &[] },
+    Migration { version: 7, name: "fts_search_index", disable_foreign_keys: false, up: m0007_fts_search_index, column_types: &[] },
+    Migration { version: 8, name: "business_plan_tables", disable_foreign_keys: false, up: m0008_business_plan_tables, column_types: &[] },
+    Migration { version: 9, name: "company_organizations", disable_foreign_keys: true, up: m0009_company_organizations, column_types: &[] },
+    Migration { version: 10, name: "sessions", disable_foreign_keys: false, up: m0010_sessions, column_types: &[] },
+    Migration { version: 11, name: "api_tokens", disable_foreign_keys: false, up: m0011_api_tokens, column_types: &[] },
+    Migration { version: 12, name: "rbac_roles", disable_foreign_keys: false, up: m0012_rbac_roles, column_types: &[] },
+    Migration { version: 13, name: "audit_log", disable_foreign_keys: false, up: m0013_audit_log, column_types: &[] },
+    Migration { version: 14, name: "soft_delete", disable_foreign_keys: false, up: m0014_soft_delete, column_types: &[] },
+    Migration { version: 15, name: "write_outbox", disable_foreign_keys: false, up: m0015_write_outbox, column_types: &[] },
+    Migration { version: 16, name: "normalize_timestamps", disable_foreign_keys: false, up: m0016_normalize_timestamps, column_types: &[] },
+    Migration { version: 17, name: "task_chain_runs", disable_foreign_keys: false, up: m0017_task_chain_runs, column_types: &[] },
+    Migration { version: 18, name: "schedules", disable_foreign_keys: false, up: m0018_schedules, column_types: &[] },
+    Migration { version: 19, name: "a2a_message_bus", disable_foreign_keys: false, up: m0019_a2a_message_bus, column_types: &[] },
+    Migration { version: 20, name: "agent_prompt_versioning", disable_foreign_keys: false, up: m0020_agent_prompt_versioning, column_types: &[] },
+    Migration { version: 21, name: "fts_entity_ids", disable_foreign_keys: false, up: m0021_fts_entity_ids, column_types: &[] },
+    Migration { version: 22, name: "audit_log_seq", disable_foreign_keys: false, up: m0022_audit_log_seq, column_types: &[] },
+];
+
+/// 宣言型だけでは判別できないカラムの型（後のマイグレーションの定義を優先する）
+pub(crate) fn annotated_column_type(table: &str, column: &str) -> Option<ColumnType> {
+    MIGRATIONS.iter().rev()
+        .flat_map(|m| m.column_types)
+        .find(|(t, c, _)| *t == table && *c == column)
+        .map(|(_, _, column_type)| *column_type)
+}
+
+/// 最新のスキーマバージョン
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn ensure_migrations_table(conn: &Connection) -> SqlResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            appliedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn current_version(conn: &Connection) -> SqlResult<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// 未適用のマイグレーションを順番に実行
+pub fn run_migrations(conn: &Connection) -> SqlResult<()> {
+    ensure_migrations_table(conn)?;
+    let current = current_version(conn)?;
+    init_log!("📊 スキーマバージョン: 現在={}, 最新={}", current, latest_version());
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        init_log_always!("📝 マイグレーションを適用します: {:04}_{}", migration.version, migration.name);
+
+        if migration.disable_foreign_keys {
+            conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+        }
+        let result = apply_migration(conn, migration);
+        if migration.disable_foreign_keys {
+            if let Err(e) = conn.execute_batch("PRAGMA foreign_keys = ON") {
+                init_log_always!("❌ 外部キー制約の再有効化に失敗しました: {}", e);
+            }
+        }
+
+        if let Err(e) = result {
+            init_log_always!(
+                "❌ マイグレーション {:04}_{} に失敗しました（ロールバックし、以降のマイグレーションは実行しません）: {}",
+                migration.version, migration.name, e
+            );
+            return Err(e);
+        }
+        init_log_always!("✅ マイグレーションを適用しました: {:04}_{}", migration.version, migration.name);
+    }
+
+    Ok(())
+}
+
+fn apply_migration(conn: &Connection, migration: &Migration) -> SqlResult<()> {
+    // コミットせずにスコープを抜けた場合はロールバックされる
+    let tx = conn.unchecked_transaction()?;
+    (migration.up)(&tx)?;
+    tx.execute(
+        "INSERT INTO schema_migrations (version, name, appliedAt) VALUES (?1, ?2, ?3)",
+        params![migration.version, migration.name, get_timestamp()],
+    )?;
+    tx.commit()
+}
+
+/// 現在のスキーマバージョンを取得
+pub fn get_schema_version() -> SqlResult<SchemaVersion> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    ensure_migrations_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT version, name, appliedAt FROM schema_migrations ORDER BY version")?;
+    let applied = stmt.query_map([], |row| {
+        Ok(AppliedMigration {
+            version: row.get(0)?,
+            name: row.get(1)?,
+            applied_at: row.get(2)?,
+        })
+    })?.collect::<SqlResult<Vec<_>>>()?;
+
+    let current_version = applied.iter().map(|m| m.version).max().unwrap_or(0);
+    let pending_versions = MIGRATIONS
+        .iter()
+        .filter(|m| m.version > current_version)
+        .map(|m| m.version)
+        .collect();
+
+    Ok(SchemaVersion {
+        current_version,
+        latest_version: latest_version(),
+        pending_versions,
+        applied,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// ヘルパー
+// ---------------------------------------------------------------------------
+
+fn table_exists(conn: &Connection, table: &str) -> SqlResult<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        params![table],
+        |row| Ok(row.get::<_, i64>(0)? > 0),
+    )
+}
+
+fn table_columns(conn: &Connection, table: &str) -> SqlResult<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqlResult<Vec<_>>>()?;
+    Ok(columns)
+}
+
+fn column_is_not_null(conn: &Connection, table: &str, column: &str) -> SqlResult<bool> {
+    let not_null: Option<i64> = conn.query_row(
+        &format!("SELECT \"notnull\" FROM pragma_table_info('{}') WHERE name = ?1", table),
+        params![column],
+        |row| row.get(0),
+    ).optional()?;
+    Ok(not_null.unwrap_or(0) != 0)
+}
+
+/// カラムが存在しない場合のみ追加（追加した場合は true）
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> SqlResult<bool> {
+    if table_columns(conn, table)?.iter().any(|c| c == column) {
+        return Ok(false);
+    }
+    init_log!("📝 {}テーブルにカラムを追加: {}", table, column);
+    conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    Ok(true)
+}
+
+/// テーブルを再作成（共通するカラムのデータをコピー）
+/// 外部キー制約を無効化したマイグレーション内で使用する
+fn rebuild_table(conn: &Connection, table: &str, create_sql: &str) -> SqlResult<()> {
+    let backup = format!("{}_backup", table);
+    init_log_always!("📝 {}テーブルを再作成します", table);
+
+    conn.execute(&format!("DROP TABLE IF EXISTS {}", backup), [])?;
+    conn.execute(&format!("CREATE TABLE {} AS SELECT * FROM {}", backup, table), [])?;
+    conn.execute(&format!("DROP TABLE {}", table), [])?;
+    conn.execute(create_sql, [])?;
+
+    let backup_columns = table_columns(conn, &backup)?;
+    let columns: Vec<String> = table_columns(conn, table)?
+        .into_iter()
+        .filter(|c| backup_columns.contains(c))
+        .collect();
+    let column_list = columns.join(", ");
+    conn.execute(
+        &format!("INSERT INTO {} ({}) SELECT {} FROM {}", table, column_list, column_list, backup),
+        [],
+    )?;
+
+    conn.execute(&format!("DROP TABLE {}", backup), [])?;
+    init_log_always!("✅ {}テーブルの再作成が完了しました", table);
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// テーブル定義（再作成時にも使用）
+// ---------------------------------------------------------------------------
+
+const CREATE_FOCUS_INITIATIVES: &str = "CREATE TABLE IF NOT EXISTS focusInitiatives (
+    id TEXT PRIMARY KEY,
+    organizationId TEXT,
+    companyId TEXT,
+    title TEXT NOT NULL,
+    description TEXT,
+    content TEXT,
+    themeIds TEXT,
+    topicIds TEXT,
+    createdAt TEXT,
+    updatedAt TEXT,
+    FOREIGN KEY (organizationId) REFERENCES organizations(id),
+    CHECK ((organizationId IS NOT NULL AND companyId IS NULL) OR
+           (organizationId IS NULL AND companyId IS NOT NULL))
+)";
+
+const CREATE_MEETING_NOTES: &str = "CREATE TABLE IF NOT EXISTS meetingNotes (
+    id TEXT PRIMARY KEY,
+    organizationId TEXT,
+    companyId TEXT,
+    title TEXT NOT NULL,
+    description TEXT,
+    content TEXT,
+    chromaSynced INTEGER DEFAULT 0,
+    chromaSyncError TEXT,
+    lastChromaSyncAttempt TEXT,
+    createdAt TEXT,
+    updatedAt TEXT,
+    FOREIGN KEY (organizationId) REFERENCES organizations(id),
+    CHECK ((organizationId IS NOT NULL AND companyId IS NULL) OR
+           (organizationId IS NULL AND companyId IS NOT NULL))
+)";
+
+const CREATE_ENTITIES: &str = "CREATE TABLE IF NOT EXISTS entities (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    type TEXT NOT NULL,
+    aliases TEXT,
+    metadata TEXT,
+    organizationId TEXT,
+    companyId TEXT,
+    searchableText TEXT,
+    displayName TEXT,
+    chromaSynced INTEGER DEFAULT 0,
+    chromaSyncError TEXT,
+    lastChromaSyncAttempt TEXT,
+    lastSearchDate TEXT,
+    searchCount INTEGER DEFAULT 0,
+    createdAt TEXT NOT NULL,
+    updatedAt TEXT NOT NULL,
+    FOREIGN KEY (organizationId) REFERENCES organizations(id),
+    CHECK ((organizationId IS NOT NULL AND companyId IS NULL) OR
+           (organizationId IS NULL AND companyId IS NOT NULL))
+)";
+
+const CREATE_RELATIONS: &str = "CREATE TABLE IF NOT EXISTS relations (
+    id TEXT PRIMARY KEY,
+    topicId TEXT NOT NULL,
+    sourceEntityId TEXT,
+    targetEntityId TEXT,
+    relationType TEXT NOT NULL,
+    description TEXT,
+    confidence REAL,
+    metadata TEXT,
+    organizationId TEXT,
+    companyId TEXT,
+    searchableText TEXT,
+    chromaSynced INTEGER DEFAULT 0,
+    chromaSyncError TEXT,
+    lastChromaSyncAttempt TEXT,
+    lastSearchDate TEXT,
+    searchCount INTEGER DEFAULT 0,
+    createdAt TEXT NOT NULL,
+    updatedAt TEXT NOT NULL,
+    FOREIGN KEY (sourceEntityId) REFERENCES entities(id),
+    FOREIGN KEY (targetEntityId) REFERENCES entities(id),
+    FOREIGN KEY (organizationId) REFERENCES organizations(id),
+    CHECK ((organizationId IS NOT NULL AND companyId IS NULL) OR
+           (organizationId IS NULL AND companyId IS NOT NULL))
+)";
+
+const CREATE_TOPICS: &str = "CREATE TABLE IF NOT EXISTS topics (
+    id TEXT PRIMARY KEY,
+    topicId TEXT NOT NULL,
+    meetingNoteId TEXT NOT NULL,
+    organizationId TEXT,
+    companyId TEXT,
+    title TEXT NOT NULL,
+    description TEXT,
+    content TEXT,
+    semanticCategory TEXT,
+    keywords TEXT,
+    tags TEXT,
+    contentSummary TEXT,
+    searchableText TEXT,
+    chromaSynced INTEGER DEFAULT 0,
+    chromaSyncError TEXT,
+    lastChromaSyncAttempt TEXT,
+    lastSearchDate TEXT,
+    searchCount INTEGER DEFAULT 0,
+    createdAt TEXT NOT NULL,
+    updatedAt TEXT NOT NULL,
+    FOREIGN KEY (meetingNoteId) REFERENCES meetingNotes(id),
+    FOREIGN KEY (organizationId) REFERENCES organizations(id),
+    CHECK ((organizationId IS NOT NULL AND companyId IS NULL) OR
+           (organizationId IS NULL AND companyId IS NOT NULL))
+)";
+
+// ---------------------------------------------------------------------------
+// マイグレーション
+// ---------------------------------------------------------------------------
+
+/// 0001 で作成するJSON・真偽値のカラム
+const M0001_COLUMN_TYPES: &[(&str, &str, ColumnType)] = &[
+    ("users", "approved", Boolean),
+    ("focusInitiatives", "themeIds", Json),
+    ("focusInitiatives", "topicIds", Json),
+    ("meetingNotes", "chromaSynced", Boolean),
+    ("themes", "initiativeIds", Json),
+    ("themeHierarchyConfigs", "levels", Json),
+    ("topics", "keywords", Json),
+    ("topics", "tags", Json),
+    ("topics", "chromaSynced", Boolean),
+    ("entities", "aliases", Json),
+    ("entities", "metadata", Json),
+    ("entities", "chromaSynced", Boolean),
+    ("relations", "metadata", Json),
+    ("relations", "chromaSynced", Boolean),
+    ("designDocSections", "tags", Json),
+    ("designDocSections", "hierarchy", Json),
+    ("designDocSections", "relatedSections", Json),
+    ("designDocSections", "keywords", Json),
+];
+
+/// 0001: 全テーブルを作成（新規データベース用、既存テーブルはそのまま）
+fn m0001_initial_schema(conn: &Connection) -> SqlResult<()> {
+    // ユーザーテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS users (
+            id TEXT PRIMARY KEY,
+            email TEXT UNIQUE NOT NULL,
+            passwordHash TEXT NOT NULL,
+            approved INTEGER DEFAULT 0,
+            approvedBy TEXT,
+            approvedAt TEXT,
+            role TEXT DEFAULT 'user',
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // 承認リクエストテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS approvalRequests (
+            id TEXT PRIMARY KEY,
+            userId TEXT NOT NULL,
+            email TEXT NOT NULL,
+            status TEXT DEFAULT 'pending',
+            requestedAt TEXT NOT NULL,
+            FOREIGN KEY (userId) REFERENCES users(id)
+        )",
+        [],
+    )?;
+
+    // AI設定テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS aiSettings (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            apiKey TEXT,
+            baseUrl TEXT,
+            defaultModel TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // バックアップ履歴テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS backupHistory (
+            id TEXT PRIMARY KEY,
+            backupPath TEXT NOT NULL,
+            backupSize INTEGER,
+            createdAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // 組織テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS organizations (
+            id TEXT PRIMARY KEY,
+            parentId TEXT,
+            name TEXT NOT NULL,
+            title TEXT,
+            description TEXT,
+            level INTEGER NOT NULL,
+            levelName TEXT NOT NULL,
+            position INTEGER DEFAULT 0,
+            type TEXT DEFAULT 'organization',
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL,
+            FOREIGN KEY (parentId) REFERENCES organizations(id)
+        )",
+        [],
+    )?;
+
+    // 組織メンバーテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS organizationMembers (
+            id TEXT PRIMARY KEY,
+            organizationId TEXT NOT NULL,
+            name TEXT NOT NULL,
+            position TEXT,
+            nameRomaji TEXT,
+            department TEXT,
+            extension TEXT,
+            companyPhone TEXT,
+            mobilePhone TEXT,
+            email TEXT,
+            itochuEmail TEXT,
+            teams TEXT,
+            employeeType TEXT,
+            roleName TEXT,
+            indicator TEXT,
+            location TEXT,
+            floorDoorNo TEXT,
+            previousName TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL,
+            FOREIGN KEY (organizationId) REFERENCES organizations(id)
+        )",
+        [],
+    )?;
+
+    // 組織コンテンツテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS organizationContents (
+            id TEXT PRIMARY KEY,
+            organizationId TEXT NOT NULL,
+            introduction TEXT,
+            focusAreas TEXT,
+            meetingNotes TEXT,
+            createdAt TEXT,
+            updatedAt TEXT,
+            FOREIGN KEY (organizationId) REFERENCES organizations(id)
+        )",
+        [],
+    )?;
+
+    // 注力施策テーブル
+    conn.execute(CREATE_FOCUS_INITIATIVES, [])?;
+
+    // 議事録テーブル（ChromaDB同期状態カラムを含む）
+    conn.execute(CREATE_MEETING_NOTES, [])?;
+
+    // 事業会社コンテンツテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS companyContents (
+            id TEXT PRIMARY KEY,
+            companyId TEXT NOT NULL,
+            introduction TEXT,
+            focusBusinesses TEXT,
+            capitalStructure TEXT,
+            capitalStructureDiagram TEXT,
+            createdAt TEXT,
+            updatedAt TEXT
+        )",
+        [],
+    )?;
+
+    // テーマテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS themes (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            description TEXT,
+            initiativeIds TEXT,
+            position INTEGER,
+            createdAt TEXT,
+            updatedAt TEXT
+        )",
+        [],
+    )?;
+
+    // テーマ階層設定テーブル（A2C100用）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS themeHierarchyConfigs (
+            id TEXT PRIMARY KEY,
+            maxLevels INTEGER NOT NULL,
+            levels TEXT NOT NULL,
+            createdAt TEXT,
+            updatedAt TEXT
+        )",
+        [],
+    )?;
+
+    // ナレッジグラフ用テーブル（ChromaDB同期状態カラム、RAG検索最適化カラムを含む）
+    conn.execute(CREATE_ENTITIES, [])?;
+    conn.execute(CREATE_RELATIONS, [])?;
+    conn.execute(CREATE_TOPICS, [])?;
+
+    // 注意: entityEmbeddings、relationEmbeddingsテーブルは廃止されました（ChromaDBに統一）
+    // 注意: companiesテーブルは削除されました（organizationsテーブルに type = 'company' として統合済み、0009を参照）
+
+    // システム設計ドキュメントセクションテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS designDocSections (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            description TEXT,
+            content TEXT NOT NULL,
+            tags TEXT,
+            order_index INTEGER DEFAULT 0,
+            pageUrl TEXT DEFAULT '/design',
+            hierarchy TEXT,
+            relatedSections TEXT,
+            semanticCategory TEXT,
+            keywords TEXT,
+            summary TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // システム設計ドキュメントセクション関係テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS designDocSectionRelations (
+            id TEXT PRIMARY KEY,
+            sourceSectionId TEXT NOT NULL,
+            targetSectionId TEXT NOT NULL,
+            relationType TEXT NOT NULL,
+            description TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL,
+            FOREIGN KEY (sourceSectionId) REFERENCES designDocSections(id) ON DELETE CASCADE,
+            FOREIGN KEY (targetSectionId) REFERENCES designDocSections(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Agentシステム用テーブル
+    // タスクテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tasks (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            type TEXT NOT NULL,
+            agentId TEXT,
+            requiredAgents TEXT,
+            dependencies TEXT,
+            parameters TEXT NOT NULL,
+            priority INTEGER DEFAULT 5,
+            timeout INTEGER,
+            retryCount INTEGER DEFAULT 0,
+            modelType TEXT,
+            selectedModel TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // タスク実行テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS taskExecutions (
+            id TEXT PRIMARY KEY,
+            taskId TEXT NOT NULL,
+            agentId TEXT NOT NULL,
+            status TEXT NOT NULL,
+            startedAt TEXT NOT NULL,
+            completedAt TEXT,
+            result TEXT,
+            error TEXT,
+            logs TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL,
+            FOREIGN KEY (taskId) REFERENCES tasks(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Agent定義テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agents (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            role TEXT NOT NULL,
+            capabilities TEXT NOT NULL,
+            tools TEXT NOT NULL,
+            modelType TEXT NOT NULL,
+            selectedModel TEXT,
+            systemPrompt TEXT NOT NULL,
+            config TEXT NOT NULL,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // A2Aメッセージ履歴テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS a2aMessages (
+            id TEXT PRIMARY KEY,
+            fromAgent TEXT NOT NULL,
+            toAgent TEXT NOT NULL,
+            type TEXT NOT NULL,
+            taskId TEXT,
+            payload TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            responseTo TEXT,
+            requiresResponse INTEGER DEFAULT 0,
+            createdAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Agentプロンプトバージョン履歴テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS agent_prompt_versions (
+            id TEXT PRIMARY KEY,
+            agentId TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            systemPrompt TEXT NOT NULL,
+            createdAt INTEGER NOT NULL,
+            updatedAt INTEGER NOT NULL,
+            FOREIGN KEY (agentId) REFERENCES agents(id) ON DELETE CASCADE,
+            UNIQUE(agentId, version)
+        )",
+        [],
+    )?;
+
+    // タスクチェーンテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS taskChains (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            startNodeId TEXT NOT NULL,
+            nodes TEXT NOT NULL,
+            createdAt INTEGER NOT NULL,
+            updatedAt INTEGER NOT NULL
+        )",
+        [],
+    )?;
+
+    // MCPツールテーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS mcp_tools (
+            id TEXT PRIMARY KEY,
+            name TEXT UNIQUE NOT NULL,
+            description TEXT NOT NULL,
+            arguments TEXT NOT NULL,
+            returns TEXT,
+            implementationType TEXT NOT NULL,
+            enabled INTEGER DEFAULT 1,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// 0002: 旧バージョンのテーブルに後から追加されたカラムを追加
+fn m0002_add_missing_columns(conn: &Connection) -> SqlResult<()> {
+    add_column_if_missing(conn, "users", "role", "TEXT DEFAULT 'user'")?;
+    add_column_if_missing(conn, "organizations", "type", "TEXT DEFAULT 'organization'")?;
+
+    for column in [
+        "nameRomaji", "department", "extension", "companyPhone", "mobilePhone", "email",
+        "itochuEmail", "teams", "employeeType", "roleName", "indicator", "location",
+        "floorDoorNo", "previousName",
+    ] {
+        add_column_if_missing(conn, "organizationMembers", column, "TEXT")?;
+    }
+
+    for column in ["themeIds", "topicIds", "companyId"] {
+        add_column_if_missing(conn, "focusInitiatives", column, "TEXT")?;
+    }
+    add_column_if_missing(conn, "meetingNotes", "companyId", "TEXT")?;
+    add_column_if_missing(conn, "companyContents", "capitalStructure", "TEXT")?;
+    add_column_if_missing(conn, "companyContents", "capitalStructureDiagram", "TEXT")?;
+    add_column_if_missing(conn, "tasks", "modelType", "TEXT")?;
+    add_column_if_missing(conn, "tasks", "selectedModel", "TEXT")?;
+    add_column_if_missing(conn, "agents", "selectedModel", "TEXT")?;
+
+    // themes.position は追加時に既存データへ連番を設定（createdAt順）
+    if add_column_if_missing(conn, "themes", "position", "INTEGER")? {
+        conn.execute(
+            "UPDATE themes SET position = (
+                SELECT COUNT(*) + 1 FROM themes t2
+                WHERE (t2.createdAt < themes.createdAt)
+                OR (t2.createdAt = themes.createdAt AND t2.title < themes.title)
+                OR (t2.createdAt = themes.createdAt AND t2.title = themes.title AND t2.id < themes.id)
+            )",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// 0003: meetingNotes / focusInitiatives の organizationId をNULL可能に（テーブル再作成）
+fn m0003_nullable_organization_id(conn: &Connection) -> SqlResult<()> {
+    if table_exists(conn, "meetingNotes")? && column_is_not_null(conn, "meetingNotes", "organizationId")? {
+        rebuild_table(conn, "meetingNotes", CREATE_MEETING_NOTES)?;
+    }
+    if table_exists(conn, "focusInitiatives")? && column_is_not_null(conn, "focusInitiatives", "organizationId")? {
+        rebuild_table(conn, "focusInitiatives", CREATE_FOCUS_INITIATIVES)?;
+    }
+    Ok(())
+}
+
+/// 0004: entities / relations / topics に companyId カラムとCHECK制約を追加（テーブル再作成）
+fn m0004_knowledge_tables_company_id(conn: &Connection) -> SqlResult<()> {
+    for (table, create_sql) in [
+        ("entities", CREATE_ENTITIES),
+        ("relations", CREATE_RELATIONS),
+        ("topics", CREATE_TOPICS),
+    ] {
+        if table_exists(conn, table)? && !table_columns(conn, table)?.iter().any(|c| c == "companyId") {
+            rebuild_table(conn, table, create_sql)?;
+        }
+    }
+    Ok(())
+}
+
+/// 0005: RAG検索最適化カラム（searchableText、検索頻度追跡など）を追加
+fn m0005_rag_search_columns(conn: &Connection) -> SqlResult<()> {
+    for table in ["entities", "relations", "topics"] {
+        add_column_if_missing(conn, table, "searchableText", "TEXT")?;
+        add_column_if_missing(conn, table, "lastSearchDate", "TEXT")?;
+        add_column_if_missing(conn, table, "searchCount", "INTEGER DEFAULT 0")?;
+    }
+    add_column_if_missing(conn, "entities", "displayName", "TEXT")?;
+    add_column_if_missing(conn, "topics", "contentSummary", "TEXT")?;
+    Ok(())
+}
+
+/// 0006: インデックスとsearchableText自動生成トリガーを作成
+fn m0006_indexes_and_triggers(conn: &Connection) -> SqlResult<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
+        CREATE INDEX IF NOT EXISTS idx_organizationContents_organizationId ON organizationContents(organizationId);
+        CREATE INDEX IF NOT EXISTS idx_focusInitiatives_organizationId ON focusInitiatives(organizationId);
+        CREATE INDEX IF NOT EXISTS idx_focusInitiatives_companyId ON focusInitiatives(companyId);
+        CREATE INDEX IF NOT EXISTS idx_meetingNotes_organizationId ON meetingNotes(organizationId);
+        CREATE INDEX IF NOT EXISTS idx_meetingNotes_companyId ON meetingNotes(companyId);
+        CREATE INDEX IF NOT EXISTS idx_companyContents_companyId ON companyContents(companyId);
+        CREATE INDEX IF NOT EXISTS idx_themes_id ON themes(id);
+        CREATE INDEX IF NOT EXISTS idx_themes_position ON themes(position);
+        CREATE INDEX IF NOT EXISTS idx_entities_organizationId ON entities(organizationId);
+        CREATE INDEX IF NOT EXISTS idx_entities_companyId ON entities(companyId);
+        CREATE INDEX IF NOT EXISTS idx_entities_type ON entities(type);
+        CREATE INDEX IF NOT EXISTS idx_entities_name ON entities(name);
+        CREATE INDEX IF NOT EXISTS idx_entities_chromaSynced ON entities(chromaSynced);
+        CREATE INDEX IF NOT EXISTS idx_entities_searchable_text ON entities(searchableText);
+        CREATE INDEX IF NOT EXISTS idx_entities_org_chroma ON entities(organizationId, chromaSynced);
+        CREATE INDEX IF NOT EXISTS idx_relations_topicId ON relations(topicId);
+        CREATE INDEX IF NOT EXISTS idx_relations_sourceEntityId ON relations(sourceEntityId);
+        CREATE INDEX IF NOT EXISTS idx_relations_targetEntityId ON relations(targetEntityId);
+        CREATE INDEX IF NOT EXISTS idx_relations_companyId ON relations(companyId);
+        CREATE INDEX IF NOT EXISTS idx_relations_relationType ON relations(relationType);
+        CREATE INDEX IF NOT EXISTS idx_relations_organizationId ON relations(organizationId);
+        CREATE INDEX IF NOT EXISTS idx_relations_chromaSynced ON relations(chromaSynced);
+        CREATE INDEX IF NOT EXISTS idx_relations_searchable_text ON relations(searchableText);
+        CREATE INDEX IF NOT EXISTS idx_relations_org_chroma ON relations(organizationId, chromaSynced);
+        CREATE INDEX IF NOT EXISTS idx_topics_meetingNoteId ON topics(meetingNoteId);
+        CREATE INDEX IF NOT EXISTS idx_topics_organizationId ON topics(organizationId);
+        CREATE INDEX IF NOT EXISTS idx_topics_companyId ON topics(companyId);
+        CREATE INDEX IF NOT EXISTS idx_topics_chromaSynced ON topics(chromaSynced);
+        CREATE INDEX IF NOT EXISTS idx_topics_searchable_text ON topics(searchableText);
+        CREATE INDEX IF NOT EXISTS idx_topics_semanticCategory ON topics(semanticCategory);
+        CREATE INDEX IF NOT EXISTS idx_topics_org_chroma ON topics(organizationId, chromaSynced);
+        CREATE INDEX IF NOT EXISTS idx_meetingNotes_chromaSynced ON meetingNotes(chromaSynced);
+        CREATE INDEX IF NOT EXISTS idx_meetingNotes_org_chroma ON meetingNotes(organizationId, chromaSynced);
+        CREATE INDEX IF NOT EXISTS idx_organizations_parentId ON organizations(parentId);
+        CREATE INDEX IF NOT EXISTS idx_organizations_level ON organizations(level);
+        CREATE INDEX IF NOT EXISTS idx_organizations_levelName ON organizations(levelName);
+        CREATE INDEX IF NOT EXISTS idx_organizationMembers_organizationId ON organizationMembers(organizationId);
+        CREATE INDEX IF NOT EXISTS idx_designDocSections_order ON designDocSections(order_index);
+        CREATE INDEX IF NOT EXISTS idx_designDocSections_semanticCategory ON designDocSections(semanticCategory);
+        CREATE INDEX IF NOT EXISTS idx_designDocSectionRelations_source ON designDocSectionRelations(sourceSectionId);
+        CREATE INDEX IF NOT EXISTS idx_designDocSectionRelations_target ON designDocSectionRelations(targetSectionId);
+        CREATE INDEX IF NOT EXISTS idx_designDocSectionRelations_type ON designDocSectionRelations(relationType);
+        CREATE INDEX IF NOT EXISTS idx_tasks_type ON tasks(type);
+        CREATE INDEX IF NOT EXISTS idx_tasks_agentId ON tasks(agentId);
+        CREATE INDEX IF NOT EXISTS idx_taskExecutions_taskId ON taskExecutions(taskId);
+        CREATE INDEX IF NOT EXISTS idx_taskExecutions_agentId ON taskExecutions(agentId);
+        CREATE INDEX IF NOT EXISTS idx_taskExecutions_status ON taskExecutions(status);
+        CREATE INDEX IF NOT EXISTS idx_agents_role ON agents(role);
+        CREATE INDEX IF NOT EXISTS idx_a2aMessages_fromAgent ON a2aMessages(fromAgent);
+        CREATE INDEX IF NOT EXISTS idx_a2aMessages_toAgent ON a2aMessages(toAgent);
+        CREATE INDEX IF NOT EXISTS idx_a2aMessages_taskId ON a2aMessages(taskId);
+        CREATE INDEX IF NOT EXISTS idx_taskChains_name ON taskChains(name);
+        CREATE INDEX IF NOT EXISTS idx_agent_prompt_versions_agentId ON agent_prompt_versions(agentId);
+        CREATE INDEX IF NOT EXISTS idx_agent_prompt_versions_version ON agent_prompt_versions(agentId, version);
+        CREATE INDEX IF NOT EXISTS idx_mcp_tools_name ON mcp_tools(name);
+        CREATE INDEX IF NOT EXISTS idx_mcp_tools_enabled ON mcp_tools(enabled);",
+    )?;
+
+    // RAG検索最適化: 自動更新トリガーを作成
+    // topicsテーブルのcontentSummaryとsearchableTextを自動生成
+    conn.execute(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS update_topics_searchable_fields
+        AFTER INSERT ON topics
+        BEGIN
+            UPDATE topics SET
+                contentSummary = CASE
+                    WHEN content IS NOT NULL AND LENGTH(content) > 0
+                    THEN SUBSTR(content, 1, 200)
+                    ELSE NULL
+                END,
+                searchableText = TRIM(
+                    COALESCE(title, '') || ' ' ||
+                    COALESCE(description, '') || ' ' ||
+                    COALESCE(SUBSTR(content, 1, 200), '')
+                )
+            WHERE id = NEW.id;
+        END
+        "#,
+        [],
+    )?;
+
+    conn.execute(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS update_topics_searchable_fields_on_update
+        AFTER UPDATE ON topics
+        BEGIN
+            UPDATE topics SET
+                contentSummary = CASE
+                    WHEN NEW.content IS NOT NULL AND LENGTH(NEW.content) > 0
+                    THEN SUBSTR(NEW.content, 1, 200)
+                    ELSE NULL
+                END,
+                searchableText = TRIM(
+                    COALESCE(NEW.title, '') || ' ' ||
+                    COALESCE(NEW.description, '') || ' ' ||
+                    COALESCE(SUBSTR(NEW.content, 1, 200), '')
+                )
+            WHERE id = NEW.id;
+        END
+        "#,
+        [],
+    )?;
+
+    // entitiesテーブルのsearchableTextとdisplayNameを自動生成
+    conn.execute(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS update_entities_searchable_fields
+        AFTER INSERT ON entities
+        BEGIN
+            UPDATE entities SET
+                searchableText = TRIM(
+                    COALESCE(name, '') || ' ' ||
+                    COALESCE(aliases, '') || ' ' ||
+                    CASE
+                        WHEN metadata IS NOT NULL AND json_extract(metadata, '$.role') IS NOT NULL
+                        THEN json_extract(metadata, '$.role') || ' '
+                        ELSE ''
+                    END ||
+                    CASE
+                        WHEN metadata IS NOT NULL AND json_extract(metadata, '$.department') IS NOT NULL
+                        THEN json_extract(metadata, '$.department')
+                        ELSE ''
+                    END
+                ),
+                displayName = name ||
+                    CASE
+                        WHEN metadata IS NOT NULL AND json_extract(metadata, '$.role') IS NOT NULL
+                        THEN ' (' || json_extract(metadata, '$.role') || ')'
+                        ELSE ''
+                    END
+            WHERE id = NEW.id;
+        END
+        "#,
+        [],
+    )?;
+
+    conn.execute(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS update_entities_searchable_fields_on_update
+        AFTER UPDATE ON entities
+        BEGIN
+            UPDATE entities SET
+                searchableText = TRIM(
+                    COALESCE(NEW.name, '') || ' ' ||
+                    COALESCE(NEW.aliases, '') || ' ' ||
+                    CASE
+                        WHEN NEW.metadata IS NOT NULL AND json_extract(NEW.metadata, '$.role') IS NOT NULL
+                        THEN json_extract(NEW.metadata, '$.role') || ' '
+                        ELSE ''
+                    END ||
+                    CASE
+                        WHEN NEW.metadata IS NOT NULL AND json_extract(NEW.metadata, '$.department') IS NOT NULL
+                        THEN json_extract(NEW.metadata, '$.department')
+                        ELSE ''
+                    END
+                ),
+                displayName = NEW.name ||
+                    CASE
+                        WHEN NEW.metadata IS NOT NULL AND json_extract(NEW.metadata, '$.role') IS NOT NULL
+                        THEN ' (' || json_extract(NEW.metadata, '$.role') || ')'
+                        ELSE ''
+                    END
+            WHERE id = NEW.id;
+        END
+        "#,
+        [],
+    )?;
+
+    // relationsテーブルのsearchableTextを自動生成
+    conn.execute(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS update_relations_searchable_fields
+        AFTER INSERT ON relations
+        BEGIN
+            UPDATE relations SET
+                searchableText = TRIM(
+                    COALESCE(relationType, '') || ' ' ||
+                    COALESCE(description, '')
+                )
+            WHERE id = NEW.id;
+        END
+        "#,
+        [],
+    )?;
+
+    conn.execute(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS update_relations_searchable_fields_on_update
+        AFTER UPDATE ON relations
+        BEGIN
+            UPDATE relations SET
+                searchableText = TRIM(
+                    COALESCE(NEW.relationType, '') || ' ' ||
+                    COALESCE(NEW.description, '')
+                )
+            WHERE id = NEW.id;
+        END
+        "#,
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// 0007: ハイブリッド検索用のFTS5インデックス（同期トリガーを含む）
+fn m0007_fts_search_index(conn: &Connection) -> SqlResult<()> {
+    search::init_fts_tables(conn)
+}
+
+/// 0008: 事業計画の添付ファイル・ID登録・作成履歴テーブル
+fn m0008_business_plan_tables(conn: &Connection) -> SqlResult<()> {
+    // 事業計画ファイルテーブル（isDeletedによる論理削除、fileHashによる重複排除）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS businessPlanFiles (
+            id TEXT PRIMARY KEY,
+            planId TEXT NOT NULL,
+            planType TEXT NOT NULL,
+            fileName TEXT NOT NULL,
+            originalFileName TEXT NOT NULL,
+            filePath TEXT NOT NULL,
+            fileSize INTEGER NOT NULL DEFAULT 0,
+            fileType TEXT NOT NULL,
+            mimeType TEXT,
+            fileHash TEXT,
+            description TEXT,
+            category TEXT,
+            userId TEXT,
+            isDeleted INTEGER NOT NULL DEFAULT 0,
+            deletedAt TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // 事業計画ID登録テーブル（planTypeごとの連番）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS businessPlanIdRegistry (
+            id TEXT PRIMARY KEY,
+            planId TEXT UNIQUE NOT NULL,
+            planType TEXT NOT NULL,
+            userId TEXT,
+            displayId TEXT,
+            customPrefix TEXT,
+            sequenceNumber INTEGER NOT NULL,
+            metadata TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // 事業計画作成履歴テーブル
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS businessPlanCreationHistory (
+            id TEXT PRIMARY KEY,
+            planId TEXT NOT NULL,
+            planType TEXT NOT NULL,
+            userId TEXT,
+            action TEXT NOT NULL,
+            previousState TEXT,
+            newState TEXT,
+            metadata TEXT,
+            createdAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_businessPlanFiles_planId ON businessPlanFiles(planId, isDeleted);
+        CREATE INDEX IF NOT EXISTS idx_businessPlanFiles_fileHash ON businessPlanFiles(planId, fileHash);
+        CREATE INDEX IF NOT EXISTS idx_businessPlanIdRegistry_planType ON businessPlanIdRegistry(planType, sequenceNumber);
+        CREATE INDEX IF NOT EXISTS idx_businessPlanCreationHistory_planId ON businessPlanCreationHistory(planId, createdAt);",
+    )?;
+
+    Ok(())
+}
+
+/// 0009: 事業会社（organizations.type = 'company'）の属性カラムと組織との表示関係テーブル
+/// 旧 companies / organizationCompanyDisplay テーブルが残っている場合はデータを移行する
+fn m0009_company_organizations(conn: &Connection) -> SqlResult<()> {
+    for column in ["code", "nameShort", "category", "company", "division", "department", "region"] {
+        add_column_if_missing(conn, "organizations", column, "TEXT")?;
+    }
+
+    // 旧 companies テーブルのデータを organizations（type = 'company'）へ移行
+    // 旧テーブルの organizationId は所属組織のIDなので parentId として引き継ぐ
+    if table_exists(conn, "companies")? {
+        let migrated = conn.execute(
+            "INSERT OR IGNORE INTO organizations (
+                id, parentId, name, level, levelName, position, type,
+                code, nameShort, category, company, division, department, region,
+                createdAt, updatedAt
+            )
+            SELECT c.id, c.organizationId, c.name,
+                   COALESCE((SELECT o.level + 1 FROM organizations o WHERE o.id = c.organizationId), 1),
+                   '事業会社', COALESCE(c.position, 0), 'company',
+                   c.code, c.nameShort, c.category, c.company, c.division, c.department, c.region,
+                   c.createdAt, c.updatedAt
+            FROM companies c",
+            [],
+        )?;
+        init_log_always!("📝 companiesテーブルから事業会社を移行しました: {}件", migrated);
+    }
+
+    // 旧テーブルは companyId が companies.id を参照していたため、作り直してからデータを移す
+    let legacy_displays = table_exists(conn, "organizationCompanyDisplay")?;
+    if legacy_displays {
+        conn.execute("ALTER TABLE organizationCompanyDisplay RENAME TO organizationCompanyDisplay_legacy", [])?;
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS organizationCompanyDisplay (
+            id TEXT PRIMARY KEY,
+            organizationId TEXT NOT NULL,
+            companyId TEXT NOT NULL,
+            displayOrder INTEGER NOT NULL DEFAULT 0,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL,
+            UNIQUE(organizationId, companyId),
+            FOREIGN KEY (organizationId) REFERENCES organizations(id),
+            FOREIGN KEY (companyId) REFERENCES organizations(id)
+        )",
+        [],
+    )?;
+
+    if legacy_displays {
+        conn.execute(
+            "INSERT OR IGNORE INTO organizationCompanyDisplay (id, organizationId, companyId, displayOrder, createdAt, updatedAt)
+             SELECT d.id, d.organizationId, d.companyId, COALESCE(d.displayOrder, 0), d.createdAt, d.updatedAt
+             FROM organizationCompanyDisplay_legacy d
+             WHERE EXISTS (SELECT 1 FROM organizations o WHERE o.id = d.companyId AND o.type = 'company')",
+            [],
+        )?;
+        conn.execute("DROP TABLE organizationCompanyDisplay_legacy", [])?;
+    }
+
+    conn.execute_batch(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_organizations_company_code ON organizations(code) WHERE type = 'company' AND code IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_organizations_type ON organizations(type, parentId, position);
+        CREATE INDEX IF NOT EXISTS idx_organizationCompanyDisplay_organizationId ON organizationCompanyDisplay(organizationId, displayOrder);
+        CREATE INDEX IF NOT EXISTS idx_organizationCompanyDisplay_companyId ON organizationCompanyDisplay(companyId);",
+    )?;
+
+    Ok(())
+}
+
+/// 0010: セッションテーブル（トークンはSHA-256ハッシュのみ保存）
+fn m0010_sessions(conn: &Connection) -> SqlResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS sessions (
+            id TEXT PRIMARY KEY,
+            tokenHash TEXT UNIQUE NOT NULL,
+            userId TEXT NOT NULL,
+            createdAt TEXT NOT NULL,
+            lastUsedAt TEXT NOT NULL,
+            expiresAt TEXT NOT NULL,
+            FOREIGN KEY (userId) REFERENCES users(id)
+        )",
+        [],
+    )?;
+
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_sessions_userId ON sessions(userId);
+        CREATE INDEX IF NOT EXISTS idx_sessions_expiresAt ON sessions(expiresAt);",
+    )?;
+
+    Ok(())
+}
+
+/// 0011: REST API用のAPIトークンテーブル（トークンはSHA-256ハッシュのみ保存）
+fn m0011_api_tokens(conn: &Connection) -> SqlResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS apiTokens (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            tokenHash TEXT UNIQUE NOT NULL,
+            scopes TEXT NOT NULL,
+            userId TEXT,
+            createdAt TEXT NOT NULL,
+            lastUsedAt TEXT,
+            expiresAt TEXT,
+            revokedAt TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// ロールを admin / editor / viewer に統一し、承認リクエストに審査結果のカラムを追加
+fn m0012_rbac_roles(conn: &Connection) -> SqlResult<()> {
+    // 旧ロール 'user'（または未設定）は editor として扱う
+    conn.execute(
+        "UPDATE users SET role = 'editor' WHERE role IS NULL OR role NOT IN ('admin', 'editor', 'viewer')",
+        [],
+    )?;
+
+    // 管理者が存在しない場合は、デフォルトユーザー（なければ最初に作成された承認済みユーザー）を管理者にする
+    let admin_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM users WHERE role = 'admin'",
+        [],
+        |row| row.get(0),
+    )?;
+    if admin_count == 0 {
+        conn.execute(
+            "UPDATE users SET role = 'admin' WHERE id = (
+                SELECT id FROM users WHERE approved = 1
+                ORDER BY CASE WHEN email = 'admin@example.com' THEN 0 ELSE 1 END, createdAt ASC
+                LIMIT 1
+            )",
+            [],
+        )?;
+    }
+
+    add_column_if_missing(conn, "approvalRequests", "reviewedBy", "TEXT")?;
+    add_column_if_missing(conn, "approvalRequests", "reviewedAt", "TEXT")?;
+    add_column_if_missing(conn, "approvalRequests", "reason", "TEXT")?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_approvalRequests_status ON approvalRequests(status)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// データ変更の監査ログテーブル
+fn m0013_audit_log(conn: &Connection) -> SqlResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS auditLog (
+            id TEXT PRIMARY KEY,
+            actorId TEXT,
+            actorEmail TEXT,
+            action TEXT NOT NULL,
+            tableName TEXT NOT NULL,
+            recordId TEXT NOT NULL,
+            beforeData TEXT,
+            afterData TEXT,
+            createdAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_auditLog_record ON auditLog(tableName, recordId)",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_auditLog_actorId ON auditLog(actorId)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_auditLog_createdAt ON auditLog(createdAt)", [])?;
+    Ok(())
+}
+
+/// 論理削除用の deletedAt / deletionId カラムとゴミ箱テーブル
+fn m0014_soft_delete(conn: &Connection) -> SqlResult<()> {
+    for table in [
+        "organizations",
+        "organizationMembers",
+        "organizationContents",
+        "companyContents",
+        "focusInitiatives",
+        "meetingNotes",
+        "entities",
+        "relations",
+        "topics",
+        "organizationCompanyDisplay",
+    ] {
+        if !table_exists(conn, table)? {
+            continue;
+        }
+        add_column_if_missing(conn, table, "deletedAt", "TEXT")?;
+        add_column_if_missing(conn, table, "deletionId", "TEXT")?;
+        conn.execute(
+            &format!("CREATE INDEX IF NOT EXISTS idx_{table}_deletionId ON {table}(deletionId)"),
+            [],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS trash (
+            id TEXT PRIMARY KEY,
+            itemType TEXT NOT NULL,
+            itemId TEXT NOT NULL,
+            name TEXT,
+            organizationId TEXT,
+            deletedBy TEXT,
+            deletedByEmail TEXT,
+            deletedAt TEXT NOT NULL,
+            itemCount INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_trash_deletedAt ON trash(deletedAt)", [])?;
+    Ok(())
+}
+
+fn m0015_write_outbox(conn: &Connection) -> SqlResult<()> {
+    // 未処理の書き込みジョブ（処理が完了したら削除する）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS writeOutbox (
+            id TEXT PRIMARY KEY,
+            jobType TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            actorId TEXT,
+            actorEmail TEXT,
+            enqueuedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_writeOutbox_enqueuedAt ON writeOutbox(enqueuedAt)", [])?;
+
+    // リトライしても失敗した書き込みジョブ
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS writeDeadLetters (
+            id TEXT PRIMARY KEY,
+            jobType TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            actorId TEXT,
+            actorEmail TEXT,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            error TEXT NOT NULL,
+            enqueuedAt TEXT NOT NULL,
+            failedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_writeDeadLetters_failedAt ON writeDeadLetters(failedAt)", [])?;
+    Ok(())
+}
+
+/// タイムスタンプとして保存しているカラム名（0016で RFC 3339 に正規化する）
+const TIMESTAMP_COLUMN_NAMES: &[&str] = &[
+    "createdAt",
+    "updatedAt",
+    "deletedAt",
+    "approvedAt",
+    "requestedAt",
+    "reviewedAt",
+    "startedAt",
+    "completedAt",
+    "lastChromaSyncAttempt",
+    "lastSearchDate",
+    "lastUsedAt",
+    "expiresAt",
+    "revokedAt",
+    "enqueuedAt",
+    "failedAt",
+    "appliedAt",
+];
+
+/// 0016: タイムスタンプを RFC 3339（UTC・ミリ秒精度）に統一
+/// Unix秒（TEXT / INTEGER）・Unixミリ秒・"YYYY-MM-DD HH:MM:SS" などの旧形式を変換する
+/// 解釈できない値は変更せず、件数をログに残す
+fn m0016_normalize_timestamps(conn: &Connection) -> SqlResult<()> {
+    use rusqlite::types::{FromSql, Value as SqlValue, ValueRef};
+
+    let tables: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND sql NOT LIKE 'CREATE VIRTUAL%'",
+        )?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.collect::<SqlResult<Vec<_>>>()?
+    };
+
+    for table in &tables {
+        for column in table_columns(conn, table)? {
+            if !TIMESTAMP_COLUMN_NAMES.contains(&column.as_str()) {
+                continue;
+            }
+
+            let rows: Vec<(i64, SqlValue)> = {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT rowid, {column} FROM {table} WHERE {column} IS NOT NULL AND {column} != ''"
+                ))?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<SqlResult<Vec<_>>>()?
+            };
+
+            let mut updated = 0;
+            let mut invalid = 0;
+            for (rowid, value) in rows {
+                let normalized = match Timestamp::column_result(ValueRef::from(&value)) {
+                    Ok(ts) => ts.to_rfc3339(),
+                    Err(_) => {
+                        invalid += 1;
+                        continue;
+                    }
+                };
+                if value == SqlValue::Text(normalized.clone()) {
+                    continue;
+                }
+                conn.execute(
+                    &format!("UPDATE {table} SET {column} = ?1 WHERE rowid = ?2"),
+                    params![normalized, rowid],
+                )?;
+                updated += 1;
+            }
+
+            if updated > 0 {
+                init_log!("📝 {}.{} のタイムスタンプを {} 件正規化しました", table, column, updated);
+            }
+            if invalid > 0 {
+                init_log_always!("⚠️ {}.{} にタイムスタンプとして解釈できない値が {} 件あります（変更していません）", table, column, invalid);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn m0017_task_chain_runs(conn: &Connection) -> SqlResult<()> {
+    // タスクチェーンの実行（開始時のノード定義と、再開用の実行状態を保持する）
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS taskChainRuns (
+            id TEXT PRIMARY KEY,
+            chainId TEXT NOT NULL,
+            status TEXT NOT NULL,
+            startNodeId TEXT NOT NULL,
+            nodes TEXT NOT NULL,
+            state TEXT NOT NULL,
+            error TEXT,
+            startedAt TEXT NOT NULL,
+            completedAt TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL,
+            FOREIGN KEY (chainId) REFERENCES taskChains(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_taskChainRuns_chainId ON taskChainRuns(chainId)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_taskChainRuns_status ON taskChainRuns(status)", [])?;
+
+    // 実行で通過したノード
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS taskChainRunSteps (
+            id TEXT PRIMARY KEY,
+            runId TEXT NOT NULL,
+            nodeId TEXT NOT NULL,
+            nodeType TEXT NOT NULL,
+            cursorId TEXT NOT NULL,
+            executionId TEXT,
+            status TEXT NOT NULL,
+            result TEXT,
+            startedAt TEXT NOT NULL,
+            completedAt TEXT,
+            FOREIGN KEY (runId) REFERENCES taskChainRuns(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_taskChainRunSteps_runId ON taskChainRunSteps(runId)", [])?;
+    Ok(())
+}
+
+fn m0018_schedules(conn: &Connection) -> SqlResult<()> {
+    // タスク・タスクチェーンの自動実行のスケジュール
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schedules (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            targetType TEXT NOT NULL,
+            targetId TEXT NOT NULL,
+            triggerType TEXT NOT NULL,
+            cronExpression TEXT,
+            runAt TEXT,
+            eventTable TEXT,
+            eventOperation TEXT,
+            eventFilter TEXT,
+            catchUpPolicy TEXT NOT NULL DEFAULT 'run_once',
+            enabled INTEGER NOT NULL DEFAULT 1,
+            nextRunAt TEXT,
+            lastRunAt TEXT,
+            lastError TEXT,
+            lastEventSeq INTEGER NOT NULL DEFAULT 0,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_schedules_nextRunAt ON schedules(enabled, nextRunAt)", [])?;
+
+    // スケジュールの実行記録
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scheduleRuns (
+            id TEXT PRIMARY KEY,
+            scheduleId TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            status TEXT NOT NULL,
+            scheduledFor TEXT,
+            executionId TEXT,
+            chainRunId TEXT,
+            eventSeq INTEGER,
+            error TEXT,
+            createdAt TEXT NOT NULL,
+            FOREIGN KEY (scheduleId) REFERENCES schedules(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_scheduleRuns_scheduleId ON scheduleRuns(scheduleId, createdAt)", [])?;
+    Ok(())
+}
+
+fn m0019_a2a_message_bus(conn: &Connection) -> SqlResult<()> {
+    // 受信箱（未読）と返信の待ち状態
+    add_column_if_missing(conn, "a2aMessages", "readAt", "TEXT")?;
+    add_column_if_missing(conn, "a2aMessages", "responseStatus", "TEXT")?;
+    add_column_if_missing(conn, "a2aMessages", "responseDeadline", "TEXT")?;
+    // 既存の返信が必要なメッセージは、返信があれば answered、なければ timed_out にする
+    conn.execute(
+        "UPDATE a2aMessages SET responseStatus = CASE
+            WHEN EXISTS (SELECT 1 FROM a2aMessages r WHERE r.responseTo = a2aMessages.id) THEN 'answered'
+            ELSE 'timed_out'
+         END
+         WHERE requiresResponse = 1 AND responseStatus IS NULL",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_a2aMessages_inbox ON a2aMessages(toAgent, readAt)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_a2aMessages_responseTo ON a2aMessages(responseTo)", [])?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_a2aMessages_responseStatus ON a2aMessages(responseStatus, responseDeadline)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn m0020_agent_prompt_versioning(conn: &Connection) -> SqlResult<()> {
+    add_column_if_missing(conn, "agents", "promptVersion", "INTEGER")?;
+    add_column_if_missing(conn, "tasks", "promptVersion", "INTEGER")?;
+    add_column_if_missing(conn, "taskExecutions", "promptVersion", "INTEGER")?;
+    add_column_if_missing(conn, "agent_prompt_versions", "note", "TEXT")?;
+    add_column_if_missing(conn, "agent_prompt_versions", "createdBy", "TEXT")?;
+
+    // これまでは変更前のプロンプトだけを保存していたため、現在のプロンプトを最新のバージョンとして追加する
+    let now = Timestamp::now();
+    conn.execute(
+        "INSERT INTO agent_prompt_versions (id, agentId, version, systemPrompt, createdAt, updatedAt)
+         SELECT a.id || '-v' || (COALESCE(l.version, 0) + 1), a.id, COALESCE(l.version, 0) + 1, a.systemPrompt, ?1, ?1
+         FROM agents a
+         LEFT JOIN agent_prompt_versions l ON l.agentId = a.id
+            AND l.version = (SELECT MAX(version) FROM agent_prompt_versions WHERE agentId = a.id)
+         WHERE l.id IS NULL OR l.systemPrompt IS NOT a.systemPrompt",
+        params![now],
+    )?;
+    conn.execute(
+        "UPDATE agents SET promptVersion = (SELECT MAX(version) FROM agent_prompt_versions WHERE agentId = agents.id)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 0021: FTS5インデックスを rowid ではなく元テーブルのID（UNINDEXED の id カラム）で対応付ける
+fn m0021_fts_entity_ids(conn: &Connection) -> SqlResult<()> {
+    search::migrate_fts_to_entity_ids(conn)
+}
+
+/// 0022: 監査ログに連番（seq INTEGER PRIMARY KEY AUTOINCREMENT）を追加
+/// 変更フィードとスケジューラーの再開位置は rowid で記録していたため、既存の行は rowid を seq として引き継ぐ
+/// （rowid は VACUUM で振り直され、末尾の行を削除すると再利用されるため、再開位置には使えない）
+fn m0022_audit_log_seq(conn: &Connection) -> SqlResult<()> {
+    if table_columns(conn, "auditLog")?.iter().any(|c| c == "seq") {
+        return Ok(());
+    }
+    conn.execute("ALTER TABLE auditLog RENAME TO auditLog_legacy", [])?;
+    conn.execute(
+        "CREATE TABLE auditLog (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            id TEXT NOT NULL UNIQUE,
+            actorId TEXT,
+            actorEmail TEXT,
+            action TEXT NOT NULL,
+            tableName TEXT NOT NULL,
+            recordId TEXT NOT NULL,
+            beforeData TEXT,
+            afterData TEXT,
+            createdAt TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT INTO auditLog (seq, id, actorId, actorEmail, action, tableName, recordId, beforeData, afterData, createdAt)
+         SELECT rowid, id, actorId, actorEmail, action, tableName, recordId, beforeData, afterData, createdAt
+         FROM auditLog_legacy ORDER BY rowid",
+        [],
+    )?;
+    conn.execute("DROP TABLE auditLog_legacy", [])?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_auditLog_record ON auditLog(tableName, recordId)",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_auditLog_actorId ON auditLog(actorId)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_auditLog_createdAt ON auditLog(createdAt)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_auditLog_table_seq ON auditLog(tableName, seq)", [])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// マイグレーション導入前のアプリが作成していた形のデータベース（後から追加されたカラムがない古い形を含む）
+    const BASELINE_SCHEMA: &str = "
+        CREATE TABLE users (
+            id TEXT PRIMARY KEY,
+            email TEXT UNIQUE NOT NULL,
+            passwordHash TEXT NOT NULL,
+            approved INTEGER DEFAULT 0,
+            approvedBy TEXT,
+            approvedAt TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        );
+        CREATE TABLE organizations (
+            id TEXT PRIMARY KEY,
+            parentId TEXT,
+            name TEXT NOT NULL,
+            title TEXT,
+            description TEXT,
+            level INTEGER NOT NULL,
+            levelName TEXT NOT NULL,
+            position INTEGER DEFAULT 0,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL,
+            FOREIGN KEY (parentId) REFERENCES organizations(id)
+        );
+        CREATE TABLE meetingNotes (
+            id TEXT PRIMARY KEY,
+            organizationId TEXT NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT,
+            content TEXT,
+            chromaSynced INTEGER DEFAULT 0,
+            chromaSyncError TEXT,
+            lastChromaSyncAttempt TEXT,
+            createdAt TEXT,
+            updatedAt TEXT,
+            FOREIGN KEY (organizationId) REFERENCES organizations(id)
+        );
+        CREATE TABLE themes (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            description TEXT,
+            initiativeIds TEXT,
+            createdAt TEXT,
+            updatedAt TEXT
+        );
+        CREATE TABLE entities (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            type TEXT NOT NULL,
+            aliases TEXT,
+            metadata TEXT,
+            organizationId TEXT NOT NULL,
+            chromaSynced INTEGER DEFAULT 0,
+            chromaSyncError TEXT,
+            lastChromaSyncAttempt TEXT,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL,
+            FOREIGN KEY (organizationId) REFERENCES organizations(id)
+        );
+        CREATE TABLE agents (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            role TEXT NOT NULL,
+            capabilities TEXT NOT NULL,
+            tools TEXT NOT NULL,
+            modelType TEXT NOT NULL,
+            systemPrompt TEXT NOT NULL,
+            config TEXT NOT NULL,
+            createdAt TEXT NOT NULL,
+            updatedAt TEXT NOT NULL
+        );
+        CREATE TABLE agent_prompt_versions (
+            id TEXT PRIMARY KEY,
+            agentId TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            systemPrompt TEXT NOT NULL,
+            createdAt INTEGER NOT NULL,
+            updatedAt INTEGER NOT NULL,
+            FOREIGN KEY (agentId) REFERENCES agents(id) ON DELETE CASCADE,
+            UNIQUE(agentId, version)
+        );
+        CREATE TABLE taskChains (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            startNodeId TEXT NOT NULL,
+            nodes TEXT NOT NULL,
+            createdAt INTEGER NOT NULL,
+            updatedAt INTEGER NOT NULL
+        );
+
+        INSERT INTO users (id, email, passwordHash, approved, createdAt, updatedAt) VALUES
+            ('u1', 'owner@example.com', 'hash', 1, '1700000000', '2023-11-14 22:13:20'),
+            ('u2', 'member@example.com', 'hash', 0, '1700000001000', '1700000001000');
+        INSERT INTO organizations (id, name, level, levelName, createdAt, updatedAt) VALUES
+            ('org1', '本部', 1, '本部', '1700000000', '1700000000');
+        INSERT INTO meetingNotes (id, organizationId, title, content, createdAt, updatedAt) VALUES
+            ('note1', 'org1', '定例会議', '議事内容', '1700000000', '1700000000');
+        INSERT INTO themes (id, title, createdAt, updatedAt) VALUES
+            ('theme1', 'テーマ', '1700000000', '1700000000');
+        INSERT INTO entities (id, name, type, organizationId, createdAt, updatedAt) VALUES
+            ('e1', '製品アルファ', 'product', 'org1', '1700000000', '1700000000');
+        INSERT INTO agents (id, name, description, role, capabilities, tools, modelType, systemPrompt, config, createdAt, updatedAt) VALUES
+            ('agent1', 'Agent', '', 'analyst', '[]', '[]', 'gpt', 'current prompt', '{}', '1700000000', '1700000000');
+        INSERT INTO agent_prompt_versions (id, agentId, version, systemPrompt, createdAt, updatedAt) VALUES
+            ('agent1-v1', 'agent1', 1, 'old prompt', 1700000000, 1700000000);
+        INSERT INTO taskChains (id, name, description, startNodeId, nodes, createdAt, updatedAt) VALUES
+            ('chain1', 'チェーン', '', 'a', '{}', 1700000000, 1700000000);
+    ";
+
+    fn open() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+        conn
+    }
+
+    fn query_string(conn: &Connection, sql: &str) -> String {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn declared_type(conn: &Connection, table: &str, column: &str) -> String {
+        conn.query_row(
+            &format!("SELECT type FROM pragma_table_info('{}') WHERE name = ?1", table),
+            params![column],
+            |row| row.get(0),
+        ).unwrap()
+    }
+
+    fn assert_latest(conn: &Connection) {
+        assert_eq!(current_version(conn).unwrap(), latest_version());
+        let applied: i64 = conn.query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0)).unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+
+        let violations: i64 = conn.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0)).unwrap();
+        assert_eq!(violations, 0);
+        for table in ["sessions", "auditLog", "trash", "writeOutbox", "writeDeadLetters"] {
+            let time_column = match table {
+                "sessions" => "expiresAt",
+                "auditLog" => "createdAt",
+                "trash" => "deletedAt",
+                "writeOutbox" => "enqueuedAt",
+                _ => "failedAt",
+            };
+            assert_eq!(declared_type(conn, table, time_column), "TEXT", "{}.{}", table, time_column);
+        }
+    }
+
+    #[test]
+    fn versions_are_sequential() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as i64 + 1, "{}", migration.name);
+        }
+    }
+
+    #[test]
+    fn column_types_refer_to_migrated_columns() {
+        let conn = open();
+        run_migrations(&conn).unwrap();
+
+        for migration in MIGRATIONS {
+            for (table, column, column_type) in migration.column_types {
+                let declared: Option<String> = conn.query_row(
+                    "SELECT type FROM pragma_table_info(?1) WHERE name = ?2",
+                    params![table, column],
+                    |row| row.get(0),
+                ).optional().unwrap();
+                let declared = declared.unwrap_or_else(|| panic!("{}.{} がスキーマにありません", table, column));
+                // JSON はテキスト、真偽値は整数で保存する
+                let expected = match column_type {
+                    Json => "TEXT",
+                    Boolean => "INTEGER",
+                    _ => panic!("{}.{} は宣言型で判別できます", table, column),
+                };
+                assert_eq!(declared.to_uppercase(), expected, "{}.{}", table, column);
+            }
+        }
+        assert_eq!(annotated_column_type("entities", "aliases"), Some(Json));
+        assert_eq!(annotated_column_type("entities", "name"), None);
+    }
+
+    #[test]
+    fn migrates_an_empty_database_to_the_latest_version() {
+        let conn = open();
+        run_migrations(&conn).unwrap();
+        assert_latest(&conn);
+
+        // 2回目は何も適用しない
+        run_migrations(&conn).unwrap();
+        assert_latest(&conn);
+    }
+
+    #[test]
+    fn migrates_a_baseline_database_and_keeps_its_data() {
+        let conn = open();
+        conn.execute_batch(BASELINE_SCHEMA).unwrap();
+
+        run_migrations(&conn).unwrap();
+        assert_latest(&conn);
+
+        // 0002: 後から追加されたカラム、0012: ロール
+        assert_eq!(query_string(&conn, "SELECT role FROM users WHERE id = 'u1'"), "admin");
+        assert_eq!(query_string(&conn, "SELECT role FROM users WHERE id = 'u2'"), "editor");
+        let position: i64 = conn.query_row("SELECT position FROM themes WHERE id = 'theme1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(position, 1);
+
+        // 0003 / 0004: テーブルを再作成してもデータは残る
+        assert!(!column_is_not_null(&conn, "meetingNotes", "organizationId").unwrap());
+        assert_eq!(query_string(&conn, "SELECT title FROM meetingNotes WHERE id = 'note1'"), "定例会議");
+        assert!(table_columns(&conn, "entities").unwrap().contains(&"companyId".to_string()));
+        assert_eq!(query_string(&conn, "SELECT name FROM entities WHERE id = 'e1'"), "製品アルファ");
+
+        // 0007 / 0021: 既存の行もエンティティIDでFTSに登録される
+        let fts_rows: i64 = conn.query_row("SELECT COUNT(*) FROM entities_fts", [], |row| row.get(0)).unwrap();
+        assert_eq!(fts_rows, 1);
+        assert_eq!(query_string(&conn, "SELECT id FROM entities_fts"), "e1");
+
+        // 0016: 旧形式のタイムスタンプ（Unix秒・Unixミリ秒・空白区切り）を RFC 3339 に統一
+        assert_eq!(query_string(&conn, "SELECT createdAt FROM users WHERE id = 'u1'"), "2023-11-14T22:13:20.000Z");
+        assert_eq!(query_string(&conn, "SELECT updatedAt FROM users WHERE id = 'u1'"), "2023-11-14T22:13:20.000Z");
+        assert_eq!(query_string(&conn, "SELECT createdAt FROM users WHERE id = 'u2'"), "2023-11-14T22:13:21.000Z");
+        assert_eq!(query_string(&conn, "SELECT createdAt FROM entities WHERE id = 'e1'"), "2023-11-14T22:13:20.000Z");
+        assert_eq!(
+            query_string(&conn, "SELECT typeof(createdAt) || ':' || createdAt FROM taskChains WHERE id = 'chain1'"),
+            "text:2023-11-14T22:13:20.000Z"
+        );
+
+        // 0020: 現在のプロンプトを最新のバージョンとして追加
+        assert_eq!(
+            query_string(&conn, "SELECT systemPrompt FROM agent_prompt_versions WHERE agentId = 'agent1' AND version = 2"),
+            "current prompt"
+        );
+        let prompt_version: i64 = conn.query_row("SELECT promptVersion FROM agents WHERE id = 'agent1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(prompt_version, 2);
+
+        run_migrations(&conn).unwrap();
+        assert_latest(&conn);
+    }
+
+    #[test]
+    fn audit_log_seq_keeps_rowids_and_is_never_reused() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_migrations_table(&conn).unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version < 22) {
+            apply_migration(&conn, migration).unwrap();
+        }
+        conn.execute_batch(
+            "INSERT INTO auditLog (id, action, tableName, recordId, createdAt) VALUES
+                ('a1', 'create', 'entities', 'e1', '2024-01-01T00:00:00.000Z'),
+                ('a2', 'update', 'entities', 'e1', '2024-01-01T00:00:01.000Z'),
+                ('a3', 'delete', 'entities', 'e1', '2024-01-01T00:00:02.000Z');
+             DELETE FROM auditLog WHERE id = 'a2';",
+        ).unwrap();
+
+        run_migrations(&conn).unwrap();
+        assert_latest(&conn);
+        let seqs: Vec<(String, i64)> = conn
+            .prepare("SELECT id, seq FROM auditLog ORDER BY seq").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .collect::<SqlResult<_>>().unwrap();
+        assert_eq!(seqs, vec![("a1".to_string(), 1), ("a3".to_string(), 3)]);
+
+        // 末尾の行を削除しても seq は再利用されない
+        conn.execute("DELETE FROM auditLog WHERE id = 'a3'", []).unwrap();
+        conn.execute(
+            "INSERT INTO auditLog (id, action, tableName, recordId, createdAt)
+             VALUES ('a4', 'create', 'entities', 'e2', '2024-01-01T00:00:03.000Z')",
+            [],
+        ).unwrap();
+        let seq: i64 = conn.query_row("SELECT seq FROM auditLog WHERE id = 'a4'", [], |row| row.get(0)).unwrap();
+        assert_eq!(seq, 4);
+    }
+
+    #[test]
+    fn rolls_back_a_failed_migration() {
+        let conn = open();
+        ensure_migrations_table(&conn).unwrap();
+        let failing = Migration {
+            version: 1,
+            name: "failing",
+            disable_foreign_keys: false,
+            up: |conn| {
+                conn.execute("CREATE TABLE partial (id TEXT)", [])?;
+                conn.execute("INSERT INTO missing_table VALUES (1)", [])?;
+                Ok(())
+            },
+            column_types: &[],
+        };
+
+        assert!(apply_migration(&conn, &failing).is_err());
+        assert!(!table_exists(&conn, "partial").unwrap());
+        assert_eq!(current_version(&conn).unwrap(), 0);
+    }
+}
@@ -1,6 +1,6 @@
 use rusqlite::{params, Result as SqlResult};
 use serde::{Deserialize, Serialize};
-use crate::database::{get_db, get_timestamp};
+use crate::database::{get_db, get_timestamp, snapshot_row, audit_change, trash_organization, trash_item, AuditAction};
 use uuid::Uuid;
 use std::collections::HashMap;
 
@@ -113,6 +113,7 @@ pub fn create_organization(
             now_clone
         ],
     )?;
+    audit_change(&tx, AuditAction::Create, "organizations", &id, None)?;
     
     tx.commit()?;
 
@@ -169,11 +170,13 @@ pub fn update_organization(
 
     // トランザクションを開始（データベースロックを最小化）
     let tx = conn.unchecked_transaction()?;
+    let before = snapshot_row(&tx, "organizations", id)?;
     
     tx.execute(
         "UPDATE organizations SET name = ?1, title = ?2, description = ?3, position = ?4, updatedAt = ?5 WHERE id = ?6",
         params![org.name, org.title, org.description, org.position, now, id],
     )?;
+    audit_change(&tx, AuditAction::Update, "organizations", id, before)?;
     
     tx.commit()?;
 
@@ -204,11 +207,13 @@ pub fn update_organization_parent_id(
 
     // トランザクションを開始（データベースロックを最小化）
     let tx = conn.unchecked_transaction()?;
+    let before = snapshot_row(&tx, "organizations", id)?;
     
     tx.execute(
         "UPDATE organizations SET parentId = ?1, updatedAt = ?2 WHERE id = ?3",
         params![parent_id, now, id],
     )?;
+    audit_change(&tx, AuditAction::Update, "organizations", id, before)?;
     
     tx.commit()?;
 
@@ -228,7 +233,7 @@ pub fn get_organization_by_id(id: &str) -> SqlResult<Organization> {
 
     conn.query_row(
         "SELECT id, parentId, name, title, description, level, levelName, position, type, createdAt, updatedAt
-         FROM organizations WHERE id = ?1",
+         FROM organizations WHERE id = ?1 AND deletedAt IS NULL",
         params![id],
         |row| {
             Ok(Organization {
@@ -262,7 +267,7 @@ pub fn search_organizations_by_name(name_pattern: &str) -> SqlResult<Vec<Organiz
 
     let mut stmt = conn.prepare(
         "SELECT id, parentId, name, title, description, level, levelName, position, type, createdAt, updatedAt
-         FROM organizations WHERE name LIKE ?1 ORDER BY name ASC",
+         FROM organizations WHERE name LIKE ?1 AND deletedAt IS NULL ORDER BY name ASC",
     )?;
     let rows = stmt.query_map(params![pattern], |row| {
         Ok(Organization {
@@ -299,7 +304,7 @@ pub fn get_organizations_by_parent_id(parent_id: Option<&str>) -> SqlResult<Vec<
         println!("🔍 [get_organizations_by_parent_id] 親IDで検索: parentId={}", parent_id);
         let mut stmt = conn.prepare(
             "SELECT id, parentId, name, title, description, level, levelName, position, type, createdAt, updatedAt
-             FROM organizations WHERE parentId = ?1 ORDER BY position ASC, name ASC",
+             FROM organizations WHERE parentId = ?1 AND deletedAt IS NULL ORDER BY position ASC, name ASC",
         )?;
         let rows = stmt.query_map(params![parent_id], |row| {
             Ok(Organization {
@@ -323,7 +328,7 @@ pub fn get_organizations_by_parent_id(parent_id: Option<&str>) -> SqlResult<Vec<
         println!("🔍 [get_organizations_by_parent_id] parentId IS NULLで検索");
         let mut stmt = conn.prepare(
             "SELECT id, parentId, name, title, description, level, levelName, position, type, createdAt, updatedAt
-             FROM organizations WHERE parentId IS NULL ORDER BY position ASC, name ASC",
+             FROM organizations WHERE parentId IS NULL AND deletedAt IS NULL ORDER BY position ASC, name ASC",
         )?;
         let rows = stmt.query_map([], |row| {
             Ok(Organization {
@@ -430,132 +435,22 @@ pub fn get_deletion_targets(organization_id: &str) -> SqlResult<(Vec<Organizatio
     Ok((child_orgs, all_members))
 }
 
-/// 組織を削除
+/// 組織を削除（子組織・メンバー・関連データとまとめてゴミ箱に移動し、restore_from_trash で復元できる）
 pub fn delete_organization(id: &str) -> SqlResult<()> {
     println!("🗑️ [delete_organization] 削除開始: id={}", id);
-    
-    let db = get_db().ok_or_else(|| {
-        rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-            Some("データベースが初期化されていません".to_string()),
-        )
-    })?;
-
-    let conn = db.get_connection()?;
-    
-    // 削除前に組織が存在するか確認
-    let org_exists: bool = {
-        let mut stmt = conn.prepare("SELECT COUNT(*) FROM organizations WHERE id = ?1")?;
-        let count: i64 = stmt.query_row(params![id], |row| Ok(row.get(0)?))?;
-        count > 0
-    };
-    
-    if !org_exists {
-        println!("⚠️ [delete_organization] 組織が存在しません: id={}", id);
-        return Ok(()); // 既に削除されている場合は成功として扱う
-    }
-    
-    println!("✅ [delete_organization] 組織が存在することを確認: id={}", id);
-    
-    // ロックを解放
-    drop(conn);
 
-    // 子組織のIDを取得（ロックを取得する前に）
-    let child_ids: Vec<String> = {
-        let conn = db.get_connection()?;
-        let mut stmt = conn.prepare(
-            "SELECT id FROM organizations WHERE parentId = ?1",
-        )?;
-        let rows = stmt.query_map(params![id], |row| {
-            Ok(row.get::<_, String>(0)?)
-        })?;
-        rows.collect::<Result<Vec<_>, _>>()?
-    };
-
-    println!("🔍 [delete_organization] 子組織数: {}件", child_ids.len());
-
-    // 子組織を再帰的に削除（ロックを解放した後）
-    for child_id in child_ids {
-        println!("🗑️ [delete_organization] 子組織を削除: id={}", child_id);
-        delete_organization(&child_id)?;
-    }
-
-    // メンバーと組織を削除
-    let conn = db.get_connection()?;
-    
-    // 外部キー制約を一時的に無効化（古い外部キー制約が残っている可能性があるため）
-    conn.execute("PRAGMA foreign_keys = OFF", [])?;
-    
-    // トランザクションを開始（すべての削除操作を1つのトランザクションにまとめる）
-    let tx = conn.unchecked_transaction()?;
-    
-    // 関連データを削除（外部キー制約があるため）
-    println!("🗑️ [delete_organization] 関連データを削除開始: id={}", id);
-    
-    // メンバーを削除
-    let deleted_members = tx.execute("DELETE FROM organizationMembers WHERE organizationId = ?1", params![id])?;
-    println!("✅ [delete_organization] メンバー削除: {}件", deleted_members);
-    
-    // 組織コンテンツを削除
-    let deleted_contents = tx.execute("DELETE FROM organizationContents WHERE organizationId = ?1", params![id])?;
-    println!("✅ [delete_organization] 組織コンテンツ削除: {}件", deleted_contents);
-    
-    // 事業会社コンテンツを削除（companyIdがこの組織のIDと一致する場合）
-    // 注意: companyContentsテーブルのcompanyIdは、organizationsテーブルのidを参照します
-    let deleted_company_contents = tx.execute("DELETE FROM companyContents WHERE companyId = ?1", params![id])?;
-    println!("✅ [delete_organization] 事業会社コンテンツ削除: {}件", deleted_company_contents);
-    
-    // 注力施策を削除
-    let deleted_initiatives = tx.execute("DELETE FROM focusInitiatives WHERE organizationId = ?1", params![id])?;
-    println!("✅ [delete_organization] 注力施策削除: {}件", deleted_initiatives);
-    
-    // 議事録を削除
-    let deleted_notes = tx.execute("DELETE FROM meetingNotes WHERE organizationId = ?1", params![id])?;
-    println!("✅ [delete_organization] 議事録削除: {}件", deleted_notes);
-    
-    // エンティティを削除（organizationIdが設定されている場合）
-    let deleted_entities = tx.execute("DELETE FROM entities WHERE organizationId = ?1", params![id])?;
-    println!("✅ [delete_organization] エンティティ削除: {}件", deleted_entities);
-    
-    // リレーションを削除（organizationIdが設定されている場合）
-    let deleted_relations = tx.execute("DELETE FROM relations WHERE organizationId = ?1", params![id])?;
-    println!("✅ [delete_organization] リレーション削除: {}件", deleted_relations);
-    
-    // 注意: companiesテーブルは削除されました（organizationsテーブルに統合済み）
-    // 事業会社はorganizationsテーブルでtype='company'として管理されるため、削除処理は不要
-    
-    // エンティティ埋め込みを削除
-    // entityEmbeddingsテーブルは廃止済み（entitiesテーブルに統合）
-    // 削除は不要（entitiesテーブルから削除される）
-    
-    // リレーション埋め込みを削除
-    // relationEmbeddingsテーブルは廃止済み（relationsテーブルに統合）
-    // 削除は不要（relationsテーブルから削除される）
-    
-    // トピック埋め込みを削除（organizationIdが設定されている場合）
-    // topicEmbeddingsテーブルは廃止済み（topicsテーブルに統合）
-    let deleted_topics = tx.execute("DELETE FROM topics WHERE organizationId = ?1", params![id])?;
-    println!("✅ [delete_organization] トピック削除: {}件", deleted_topics);
-
-    // 組織を削除
-    let deleted_orgs = tx.execute("DELETE FROM organizations WHERE id = ?1", params![id])?;
-    println!("✅ [delete_organization] 組織削除: {}件 (id={})", deleted_orgs, id);
-    
-    // トランザクションをコミット
-    tx.commit()?;
-    
-    // 外部キー制約を再度有効化
-    conn.execute("PRAGMA foreign_keys = ON", [])?;
-    
-    if deleted_orgs == 0 {
-        println!("⚠️ [delete_organization] 組織が削除されませんでした: id={}", id);
-        return Err(rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
-            Some(format!("組織の削除に失敗しました。組織ID {} は存在しないか、既に削除されています。", id)),
-        ));
+    match trash_organization(id)? {
+        Some(item) => {
+            println!(
+                "✅ [delete_organization] ゴミ箱に移動しました: id={}, trashId={}, 対象: {}件",
+                id, item.id, item.item_count
+            );
+        }
+        None => {
+            println!("⚠️ [delete_organization] 組織が存在しません: id={}", id);
+            // 既に削除されている場合は成功として扱う
+        }
     }
-    
-    println!("✅ [delete_organization] 削除完了: id={}", id);
     Ok(())
 }
 
@@ -610,6 +505,7 @@ pub fn add_member(
             floor_door_no.clone(), previous_name.clone(), now, now_clone
         ],
     )?;
+    audit_change(&tx, AuditAction::Create, "organizationMembers", &id, None)?;
     
     tx.commit()?;
 
@@ -730,6 +626,7 @@ pub fn update_member(
 
     // トランザクションを開始（データベースロックを最小化）
     let tx = conn.unchecked_transaction()?;
+    let before = snapshot_row(&tx, "organizationMembers", id)?;
     
     tx.execute(
         "UPDATE organizationMembers SET 
@@ -745,6 +642,7 @@ pub fn update_member(
             member.floor_door_no, member.previous_name, now, id
         ],
     )?;
+    audit_change(&tx, AuditAction::Update, "organizationMembers", id, before)?;
     
     tx.commit()?;
 
@@ -766,7 +664,7 @@ pub fn get_member_by_id(id: &str) -> SqlResult<OrganizationMember> {
         "SELECT id, organizationId, name, position, nameRomaji, department, extension,
                 companyPhone, mobilePhone, email, itochuEmail, teams, employeeType,
                 roleName, indicator, location, floorDoorNo, previousName, createdAt, updatedAt
-         FROM organizationMembers WHERE id = ?1",
+         FROM organizationMembers WHERE id = ?1 AND deletedAt IS NULL",
         params![id],
         |row| {
             Ok(OrganizationMember {
@@ -810,14 +708,14 @@ pub fn get_members_by_organization_id(organization_id: &str) -> SqlResult<Vec<Or
 
     // デバッグ: 該当するorganizationIdのメンバー数を確認
     let count: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM organizationMembers WHERE organizationId = ?1",
+        "SELECT COUNT(*) FROM organizationMembers WHERE organizationId = ?1 AND deletedAt IS NULL",
         params![organization_id],
         |row| Ok(row.get(0)?)
     ).unwrap_or(0);
     println!("📊 [get_members_by_organization_id] データベース内のメンバー数: {}", count);
     
     // デバッグ: 該当するorganizationIdのメンバーIDを確認
-    let mut debug_stmt = conn.prepare("SELECT id, name FROM organizationMembers WHERE organizationId = ?1 LIMIT 5").unwrap();
+    let mut debug_stmt = conn.prepare("SELECT id, name FROM organizationMembers WHERE organizationId = ?1 AND deletedAt IS NULL LIMIT 5").unwrap();
     let debug_members: Vec<(String, String)> = debug_stmt.query_map(params![organization_id], |row| {
         Ok((row.get(0)?, row.get(1)?))
     }).unwrap().collect::<Result<Vec<_>, _>>().unwrap_or_default();
@@ -827,7 +725,7 @@ pub fn get_members_by_organization_id(organization_id: &str) -> SqlResult<Vec<Or
         "SELECT id, organizationId, name, position, nameRomaji, department, extension,
                 companyPhone, mobilePhone, email, itochuEmail, teams, employeeType,
                 roleName, indicator, location, floorDoorNo, previousName, createdAt, updatedAt
-         FROM organizationMembers WHERE organizationId = ?1 ORDER BY position ASC, name ASC",
+         FROM organizationMembers WHERE organizationId = ?1 AND deletedAt IS NULL ORDER BY position ASC, name ASC",
     )?;
 
     let members = stmt.query_map(params![organization_id], |row| {
@@ -867,24 +765,9 @@ pub fn get_members_by_organization_id(organization_id: &str) -> SqlResult<Vec<Or
     result
 }
 
-/// メンバーを削除
+/// メンバーを削除（ゴミ箱に移動）
 pub fn delete_member(id: &str) -> SqlResult<()> {
-    let db = get_db().ok_or_else(|| {
-        rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-            Some("データベースが初期化されていません".to_string()),
-        )
-    })?;
-
-    let conn = db.get_connection()?;
-    
-    // トランザクションを開始（データベースロックを最小化）
-    let tx = conn.unchecked_transaction()?;
-    
-    tx.execute("DELETE FROM organizationMembers WHERE id = ?1", params![id])?;
-    
-    tx.commit()?;
-
+    trash_item("organizationMembers", id)?;
     Ok(())
 }
 
@@ -901,7 +784,7 @@ pub fn get_all_organizations() -> SqlResult<Vec<Organization>> {
 
     let mut stmt = conn.prepare(
         "SELECT id, parentId, name, title, description, level, levelName, position, type, createdAt, updatedAt
-         FROM organizations ORDER BY level ASC, position ASC, name ASC",
+         FROM organizations WHERE deletedAt IS NULL ORDER BY level ASC, position ASC, name ASC",
     )?;
     let rows = stmt.query_map([], |row| {
         Ok(Organization {
@@ -938,7 +821,7 @@ pub fn get_all_members() -> SqlResult<Vec<OrganizationMember>> {
         "SELECT id, organizationId, name, position, nameRomaji, department, extension,
                 companyPhone, mobilePhone, email, itochuEmail, teams, employeeType,
                 roleName, indicator, location, floorDoorNo, previousName, createdAt, updatedAt
-         FROM organizationMembers ORDER BY organizationId ASC, position ASC, name ASC",
+         FROM organizationMembers WHERE deletedAt IS NULL ORDER BY organizationId ASC, position ASC, name ASC",
     )?;
 
     let members = stmt.query_map([], |row| {
@@ -1085,7 +968,7 @@ pub fn check_duplicate_organizations() -> SqlResult<Vec<DuplicateOrgInfo>> {
     
     // 重複している組織名を取得
     let mut stmt = conn.prepare(
-        "SELECT name FROM organizations 
+        "SELECT name FROM organizations WHERE deletedAt IS NULL
          GROUP BY name HAVING COUNT(*) > 1
          ORDER BY COUNT(*) DESC, name ASC"
     )?;
@@ -1108,9 +991,9 @@ pub fn check_duplicate_organizations() -> SqlResult<Vec<DuplicateOrgInfo>> {
                 COUNT(DISTINCT m.id) as member_count,
                 COUNT(DISTINCT c.id) as child_count
              FROM organizations o
-             LEFT JOIN organizationMembers m ON o.id = m.organizationId
-             LEFT JOIN organizations c ON c.parentId = o.id
-             WHERE o.name = ?1
+             LEFT JOIN organizationMembers m ON o.id = m.organizationId AND m.deletedAt IS NULL
+             LEFT JOIN organizations c ON c.parentId = o.id AND c.deletedAt IS NULL
+             WHERE o.name = ?1 AND o.deletedAt IS NULL
              GROUP BY o.id, o.parentId, o.name, o.title, o.createdAt
              ORDER BY member_count DESC, child_count DESC, o.createdAt ASC"
         )?;
@@ -1212,7 +1095,7 @@ pub fn import_members_from_csv(csv_path: &str) -> SqlResult<usize> {
     let mut org_name_to_uuid: HashMap<String, String> = HashMap::new();
     {
         let mut stmt = conn.prepare(
-            "SELECT id, name FROM organizations"
+            "SELECT id, name FROM organizations WHERE deletedAt IS NULL"
         )?;
         let rows = stmt.query_map([], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
@@ -1,7 +1,11 @@
-use crate::database::{get_db, SqlResult};
+/**
+ * 組織と事業会社の表示関係
+ * companyId は organizations テーブルの type = 'company' の行のIDを参照する
+ */
+
+use crate::database::{get_db, get_timestamp, get_company_by_id, SqlResult};
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrganizationCompanyDisplay {
@@ -31,14 +35,13 @@ pub fn create_organization_company_display(
         )
     })?;
 
+    // 事業会社（type = 'company'）以外は表示対象にできない
+    get_company_by_id(company_id)?;
+
     let conn = db.get_connection()?;
     
     let id = uuid::Uuid::new_v4().to_string();
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
-        .to_string();
+    let now = get_timestamp();
     let display_order = display_order.unwrap_or(0);
 
     conn.execute(
@@ -71,7 +74,7 @@ pub fn get_companies_by_organization_display(organization_id: &str) -> SqlResult
     let mut stmt = conn.prepare(
         "SELECT id, organizationId, companyId, displayOrder, createdAt, updatedAt
          FROM organizationCompanyDisplay
-         WHERE organizationId = ?1
+         WHERE organizationId = ?1 AND deletedAt IS NULL
          ORDER BY displayOrder ASC, createdAt ASC",
     )?;
 
@@ -103,7 +106,7 @@ pub fn get_organizations_by_company_display(company_id: &str) -> SqlResult<Vec<O
     let mut stmt = conn.prepare(
         "SELECT id, organizationId, companyId, displayOrder, createdAt, updatedAt
          FROM organizationCompanyDisplay
-         WHERE companyId = ?1
+         WHERE companyId = ?1 AND deletedAt IS NULL
          ORDER BY displayOrder ASC, createdAt ASC",
     )?;
 
@@ -135,6 +138,7 @@ pub fn get_all_organization_company_displays() -> SqlResult<Vec<OrganizationComp
     let mut stmt = conn.prepare(
         "SELECT id, organizationId, companyId, displayOrder, createdAt, updatedAt
          FROM organizationCompanyDisplay
+         WHERE deletedAt IS NULL
          ORDER BY organizationId ASC, displayOrder ASC, createdAt ASC",
     )?;
 
@@ -165,11 +169,7 @@ pub fn update_organization_company_display_order(
     })?;
 
     let conn = db.get_connection()?;
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
-        .to_string();
+    let now = get_timestamp();
 
     conn.execute(
         "UPDATE organizationCompanyDisplay SET displayOrder = ?1, updatedAt = ?2 WHERE id = ?3",
@@ -0,0 +1,334 @@
+/**
+ * Agentのシステムプロンプトのバージョン管理
+ * agent_prompt_versions テーブルにシステムプロンプトの履歴を保存する
+ *
+ * - バージョン k は k 番目のシステムプロンプト。Agentの保存時にプロンプトが最新のバージョンと異なれば新しいバージョンを作成する
+ *   （agents.promptVersion は現在のプロンプトのバージョン）
+ * - ロールバックは古いバージョンのプロンプトを新しいバージョンとして保存する（履歴は書き換えない）
+ * - タスクは tasks.promptVersion で特定のバージョンに固定できる。固定していない場合はAgentの現在のバージョンで実行する
+ * - 実行に使ったバージョンは taskExecutions.promptVersion に記録する
+ */
+
+use crate::database::{get_db, get_timestamp, get_current_user, snapshot_row, audit_change, AuditAction, Timestamp};
+use crate::database::agent_system::{get_agent, get_task, save_agent_in};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+
+/// システムプロンプトのバージョン
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentPromptVersion {
+    pub id: String,
+    #[serde(rename = "agentId")]
+    pub agent_id: String,
+    pub version: i64,
+    #[serde(rename = "systemPrompt")]
+    pub system_prompt: String,
+    /// 変更内容のメモ（ロールバック時は「v2 に戻しました」など）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// 作成したユーザーのID（マイグレーションで作成したものなどは None）
+    #[serde(rename = "createdBy", skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Timestamp,
+}
+
+/// 差分の1行
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptDiffLine {
+    /// equal / insert / delete
+    pub op: &'static str,
+    pub text: String,
+}
+
+/// 2つのバージョンの差分（行単位）
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptDiff {
+    #[serde(rename = "agentId")]
+    pub agent_id: String,
+    #[serde(rename = "fromVersion")]
+    pub from_version: i64,
+    #[serde(rename = "toVersion")]
+    pub to_version: i64,
+    pub lines: Vec<PromptDiffLine>,
+    pub added: usize,
+    pub removed: usize,
+}
+
+fn not_initialized() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    )
+}
+
+fn not_found(message: String) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTFOUND),
+        Some(message),
+    )
+}
+
+const VERSION_COLUMNS: &str = "id, agentId, version, systemPrompt, note, createdBy, createdAt";
+
+fn row_to_version(row: &rusqlite::Row) -> SqlResult<AgentPromptVersion> {
+    Ok(AgentPromptVersion {
+        id: row.get(0)?,
+        agent_id: row.get(1)?,
+        version: row.get(2)?,
+        system_prompt: row.get(3)?,
+        note: row.get(4)?,
+        created_by: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+/// システムプロンプトが最新のバージョンと異なる場合は新しいバージョンを作成し、現在のバージョン番号を返す
+/// （save_agent のトランザクション内で呼ぶ）
+pub(crate) fn record_prompt_version(
+    conn: &Connection,
+    agent_id: &str,
+    system_prompt: &str,
+    note: Option<&str>,
+) -> SqlResult<i64> {
+    let latest: Option<(i64, String)> = conn.query_row(
+        "SELECT version, systemPrompt FROM agent_prompt_versions WHERE agentId = ?1 ORDER BY version DESC LIMIT 1",
+        params![agent_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional()?;
+
+    let version = match latest {
+        Some((version, prompt)) if prompt == system_prompt => return Ok(version),
+        Some((version, _)) => version + 1,
+        None => 1,
+    };
+
+    let now = Timestamp::now();
+    conn.execute(
+        "INSERT INTO agent_prompt_versions (id, agentId, version, systemPrompt, note, createdBy, createdAt, updatedAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            format!("{}-v{}", agent_id, version),
+            agent_id,
+            version,
+            system_prompt,
+            note,
+            get_current_user().map(|u| u.uid),
+            now,
+            now,
+        ],
+    )?;
+    Ok(version)
+}
+
+/// Agentのシステムプロンプトのバージョン一覧を取得（新しい順）
+pub fn get_agent_prompt_versions(agent_id: &str) -> SqlResult<Vec<AgentPromptVersion>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM agent_prompt_versions WHERE agentId = ?1 ORDER BY version DESC",
+        VERSION_COLUMNS
+    ))?;
+    let versions = stmt.query_map(params![agent_id], row_to_version)?
+        .collect::<SqlResult<Vec<_>>>()?;
+    Ok(versions)
+}
+
+/// 指定したバージョンを取得
+pub fn get_agent_prompt_version(agent_id: &str, version: i64) -> SqlResult<Option<AgentPromptVersion>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM agent_prompt_versions WHERE agentId = ?1 AND version = ?2",
+            VERSION_COLUMNS
+        ),
+        params![agent_id, version],
+        row_to_version,
+    ).optional()
+}
+
+fn require_version(agent_id: &str, version: i64) -> Result<AgentPromptVersion, String> {
+    get_agent_prompt_version(agent_id, version)
+        .map_err(|e| format!("プロンプトのバージョンの取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("Agent {} のプロンプトのバージョン {} が見つかりません", agent_id, version))
+}
+
+/// 2つのバージョンの差分を取得
+pub fn diff_agent_prompt_versions(agent_id: &str, from_version: i64, to_version: i64) -> Result<PromptDiff, String> {
+    let from = require_version(agent_id, from_version)?;
+    let to = require_version(agent_id, to_version)?;
+    let lines = diff_lines(&from.system_prompt, &to.system_prompt);
+    Ok(PromptDiff {
+        agent_id: agent_id.to_string(),
+        from_version,
+        to_version,
+        added: lines.iter().filter(|l| l.op == "insert").count(),
+        removed: lines.iter().filter(|l| l.op == "delete").count(),
+        lines,
+    })
+}
+
+/// 行単位の差分（最長共通部分列）
+fn diff_lines(from: &str, to: &str) -> Vec<PromptDiffLine> {
+    let a: Vec<&str> = from.lines().collect();
+    let b: Vec<&str> = to.lines().collect();
+    let mut lines = Vec::with_capacity(a.len().max(b.len()));
+    diff_range(&a, &b, &mut lines);
+    lines
+}
+
+fn push_lines(lines: &mut Vec<PromptDiffLine>, op: &'static str, texts: &[&str]) {
+    lines.extend(texts.iter().map(|text| PromptDiffLine { op, text: text.to_string() }));
+}
+
+/// a と b の差分を lines に追加
+/// Hirschberg 法（a を半分に分けて b の分割位置を求め、再帰する）でメモリを O(n + m) に抑える
+fn diff_range(a: &[&str], b: &[&str], lines: &mut Vec<PromptDiffLine>) {
+    // 共通の先頭・末尾はそのまま出力する
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    push_lines(lines, "equal", &a[..prefix]);
+
+    if a_mid.is_empty() || b_mid.is_empty() {
+        push_lines(lines, "delete", a_mid);
+        push_lines(lines, "insert", b_mid);
+    } else if a_mid.len() == 1 {
+        match b_mid.iter().position(|text| *text == a_mid[0]) {
+            Some(k) => {
+                push_lines(lines, "insert", &b_mid[..k]);
+                push_lines(lines, "equal", a_mid);
+                push_lines(lines, "insert", &b_mid[k + 1..]);
+            }
+            None => {
+                push_lines(lines, "delete", a_mid);
+                push_lines(lines, "insert", b_mid);
+            }
+        }
+    } else {
+        let half = a_mid.len() / 2;
+        let forward = lcs_lengths(a_mid[..half].iter().copied(), b_mid.iter().copied());
+        let backward = lcs_lengths(a_mid[half..].iter().rev().copied(), b_mid.iter().rev().copied());
+        // a_mid[..half] と b_mid[..j]、a_mid[half..] と b_mid[j..] の共通部分列の合計が最大になる位置で分割する
+        let split = (0..=b_mid.len())
+            .max_by_key(|&j| (forward[j] + backward[b_mid.len() - j], std::cmp::Reverse(j)))
+            .unwrap_or(0);
+        diff_range(&a_mid[..half], &b_mid[..split], lines);
+        diff_range(&a_mid[half..], &b_mid[split..], lines);
+    }
+
+    push_lines(lines, "equal", &a[a.len() - suffix..]);
+}
+
+/// lengths[j] = a と b[..j] の最長共通部分列の長さ（1行分の表だけを保持する）
+fn lcs_lengths<'a>(a: impl Iterator<Item = &'a str>, b: impl Iterator<Item = &'a str> + Clone) -> Vec<usize> {
+    let mut lengths = vec![0usize; b.clone().count() + 1];
+    for x in a {
+        let mut diagonal = 0;
+        for (j, y) in b.clone().enumerate() {
+            let above = lengths[j + 1];
+            lengths[j + 1] = if x == y { diagonal + 1 } else { above.max(lengths[j]) };
+            diagonal = above;
+        }
+    }
+    lengths
+}
+
+/// システムプロンプトを戻す前に、Agentと戻し先のバージョンが存在することを確認
+pub fn check_agent_prompt_rollback(agent_id: &str, version: i64) -> Result<(), String> {
+    require_version(agent_id, version)?;
+    get_agent(agent_id)
+        .map_err(|e| format!("Agent定義の取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("Agentが見つかりません: {}", agent_id))?;
+    Ok(())
+}
+
+/// システムプロンプトを呼び出し側のトランザクション内で指定したバージョンに戻す（書き込みワーカー用）
+/// 新しいバージョンとして保存する。現在のプロンプトと同じ場合は何もしない
+pub(crate) fn rollback_agent_prompt_in(conn: &Connection, agent_id: &str, version: i64) -> SqlResult<()> {
+    let target: String = conn.query_row(
+        "SELECT systemPrompt FROM agent_prompt_versions WHERE agentId = ?1 AND version = ?2",
+        params![agent_id, version],
+        |row| row.get(0),
+    ).optional()?.ok_or_else(|| not_found(format!("Agent {} のプロンプトのバージョン {} が見つかりません", agent_id, version)))?;
+    let mut agent = get_agent(agent_id)?
+        .ok_or_else(|| not_found(format!("Agentが見つかりません: {}", agent_id)))?;
+    if agent.system_prompt == target {
+        return Ok(());
+    }
+
+    agent.system_prompt = target;
+    save_agent_in(conn, &agent, Some(&format!("v{} に戻しました", version)))
+}
+
+/// タスクをバージョンに固定する前に、タスクと固定先のバージョンが存在することを確認
+pub fn check_task_prompt_version(task_id: &str, version: Option<i64>) -> Result<(), String> {
+    let task = get_task(task_id)
+        .map_err(|e| format!("タスクの取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("タスクが見つかりません: {}", task_id))?;
+    if let Some(version) = version {
+        let agent_id = task.agent_id.as_deref()
+            .ok_or_else(|| "Agentが指定されていないタスクはバージョンを固定できません".to_string())?;
+        require_version(agent_id, version)?;
+    }
+    Ok(())
+}
+
+/// タスクを呼び出し側のトランザクション内で特定のバージョンに固定する（None の場合は固定を解除、書き込みワーカー用）
+pub(crate) fn set_task_prompt_version_in(conn: &Connection, task_id: &str, version: Option<i64>) -> SqlResult<()> {
+    let before = snapshot_row(conn, "tasks", task_id)?;
+    if before.is_none() {
+        return Err(not_found(format!("タスクが見つかりません: {}", task_id)));
+    }
+    conn.execute(
+        "UPDATE tasks SET promptVersion = ?1, updatedAt = ?2 WHERE id = ?3",
+        params![version, get_timestamp(), task_id],
+    )?;
+    audit_change(conn, AuditAction::Update, "tasks", task_id, before)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(from: &str, to: &str) -> Vec<String> {
+        diff_lines(from, to)
+            .into_iter()
+            .map(|line| format!("{} {}", line.op, line.text))
+            .collect()
+    }
+
+    #[test]
+    fn diffs_lines_by_longest_common_subsequence() {
+        assert_eq!(
+            render("a\nb\nc\nd", "a\nc\nx\nd"),
+            vec!["equal a", "delete b", "equal c", "insert x", "equal d"]
+        );
+        assert_eq!(render("", "a\nb"), vec!["insert a", "insert b"]);
+        assert_eq!(render("a\nb", ""), vec!["delete a", "delete b"]);
+        assert_eq!(render("a\nb", "c"), vec!["delete a", "delete b", "insert c"]);
+        assert_eq!(render("x\na\ny\nb\nz", "a\nb"), vec!["delete x", "equal a", "delete y", "equal b", "delete z"]);
+    }
+
+    #[test]
+    fn diffs_long_prompts_minimally() {
+        let from: Vec<String> = (0..3000).map(|i| format!("line {}", i)).collect();
+        let to: Vec<String> = from.iter().enumerate()
+            .filter(|(i, _)| i % 3 != 0)
+            .map(|(_, line)| line.clone())
+            .chain(std::iter::once("tail".to_string()))
+            .collect();
+        let lines = diff_lines(&from.join("\n"), &to.join("\n"));
+
+        assert_eq!(lines.iter().filter(|l| l.op == "delete").count(), 1000);
+        assert_eq!(lines.iter().filter(|l| l.op == "insert").count(), 1);
+        assert_eq!(lines.iter().filter(|l| l.op == "equal").count(), 2000);
+        // 差分を適用すると変更後のプロンプトになる
+        let applied: Vec<&str> = lines.iter().filter(|l| l.op != "delete").map(|l| l.text.as_str()).collect();
+        assert_eq!(applied, to.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,530 @@
+/**
+ * コレクションの構造化クエリ
+ *
+ * - where: and / or で組み合わせた条件（eq, ne, lt, lte, gt, gte, in, notIn, like, isNull, isNotNull）
+ * - path: metadata カラムに対する JSON パス条件（json_extract）
+ * - orderBy: テーブルに存在するカラムのみ許可（最後に id を付けて順序を安定させる）
+ * - limit / cursor: ページング（cursor は前回の結果の nextCursor をそのまま渡す）
+ * - select: 返すカラムの指定（id は常に含む）
+ *
+ * フィールド名はすべてテーブルのカラムと照合し、値はすべてパラメータとしてバインドする
+ */
+
+use crate::database::{get_db, is_soft_delete_table, table_schema, TableSchema};
+use crate::database::store::{strip_hidden_columns, validate_table_name, validate_visible_column};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Result as SqlResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// 1ページで返す件数のデフォルト・上限
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+/// 条件のネストの上限
+const MAX_FILTER_DEPTH: usize = 8;
+/// JSON パス条件を使えるカラム
+const JSON_PATH_COLUMNS: &[&str] = &["metadata"];
+
+/// コレクションの構造化クエリ
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionQuery {
+    #[serde(rename = "where")]
+    pub filter: Option<Filter>,
+    #[serde(default)]
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub select: Option<Vec<String>>,
+}
+
+/// 条件（and / or の入れ子、または単一の条件）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Filter {
+    And { and: Vec<Filter> },
+    Or { or: Vec<Filter> },
+    Condition(Condition),
+}
+
+/// 単一の条件
+#[derive(Debug, Clone, Deserialize)]
+pub struct Condition {
+    pub field: String,
+    pub op: FilterOp,
+    #[serde(default)]
+    pub value: Value,
+    /// JSON パス（例: "$.source"。metadata カラムのみ）
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterOp {
+    #[serde(alias = "==")]
+    Eq,
+    #[serde(alias = "!=")]
+    Ne,
+    #[serde(alias = "<")]
+    Lt,
+    #[serde(alias = "<=")]
+    Lte,
+    #[serde(alias = ">")]
+    Gt,
+    #[serde(alias = ">=")]
+    Gte,
+    In,
+    NotIn,
+    Like,
+    IsNull,
+    IsNotNull,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderBy {
+    pub field: String,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// クエリ結果の1ページ
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionPage {
+    pub items: Vec<HashMap<String, Value>>,
+    /// 次のページのカーソル（最後のページの場合は None）
+    pub next_cursor: Option<String>,
+}
+
+fn invalid_query(message: String) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some(message),
+    )
+}
+
+impl CollectionQuery {
+    /// RESTのクエリパラメータからクエリを組み立てる
+    /// - where: 条件のJSON、orderBy: "name:asc,createdAt:desc"、limit、cursor、select: "id,name"
+    /// - それ以外のパラメータはカラムの等価条件として扱う（例: ?organizationId=xxx）
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self, String> {
+        let mut query = CollectionQuery::default();
+        let mut conditions = Vec::new();
+
+        for (key, value) in params {
+            match key.as_str() {
+                "where" => {
+                    let filter = serde_json::from_str::<Filter>(value)
+                        .map_err(|e| format!("whereの形式が不正です: {}", e))?;
+                    conditions.push(filter);
+                }
+                "orderBy" => {
+                    for item in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                        let (field, direction) = match item.split_once(':') {
+                            Some((field, "asc")) => (field, SortDirection::Asc),
+                            Some((field, "desc")) => (field, SortDirection::Desc),
+                            Some((_, direction)) => return Err(format!("無効な並び順です: {}", direction)),
+                            None => (item, SortDirection::Asc),
+                        };
+                        query.order_by.push(OrderBy { field: field.to_string(), direction });
+                    }
+                }
+                "limit" => {
+                    query.limit = Some(value.parse().map_err(|_| format!("limitが不正です: {}", value))?);
+                }
+                "cursor" => query.cursor = Some(value.clone()),
+                "select" => {
+                    query.select = Some(
+                        value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect()
+                    );
+                }
+                _ => conditions.push(Filter::Condition(Condition {
+                    field: key.clone(),
+                    op: FilterOp::Eq,
+                    value: Value::String(value.clone()),
+                    path: None,
+                })),
+            }
+        }
+
+        query.filter = match conditions.len() {
+            0 => None,
+            1 => conditions.pop(),
+            _ => Some(Filter::And { and: conditions }),
+        };
+        Ok(query)
+    }
+}
+
+/// JSONパス条件の値をバインド用の値に変換（json_extract は真偽値を 1/0 で返す）
+fn json_path_value(value: &Value) -> SqlResult<SqlValue> {
+    Ok(match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Array(_) | Value::Object(_) => {
+            return Err(invalid_query(format!("比較できない値です: {}", value)));
+        }
+    })
+}
+
+fn is_valid_json_path(path: &str) -> bool {
+    path.starts_with('$')
+        && path.chars().all(|c| c.is_alphanumeric() || matches!(c, '$' | '.' | '_' | '-' | '[' | ']'))
+}
+
+struct QueryBuilder<'a> {
+    schema: &'a TableSchema,
+    params: Vec<SqlValue>,
+}
+
+impl QueryBuilder<'_> {
+    fn build_filter(&mut self, filter: &Filter, depth: usize) -> SqlResult<String> {
+        if depth > MAX_FILTER_DEPTH {
+            return Err(invalid_query(format!("条件のネストが深すぎます（上限: {}）", MAX_FILTER_DEPTH)));
+        }
+        match filter {
+            Filter::And { and } => self.build_group(and, " AND ", "1", depth),
+            Filter::Or { or } => self.build_group(or, " OR ", "0", depth),
+            Filter::Condition(condition) => self.build_condition(condition),
+        }
+    }
+
+    fn build_group(&mut self, filters: &[Filter], separator: &str, empty: &str, depth: usize) -> SqlResult<String> {
+        if filters.is_empty() {
+            return Ok(empty.to_string());
+        }
+        let clauses = filters.iter()
+            .map(|f| self.build_filter(f, depth + 1))
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(format!("({})", clauses.join(separator)))
+    }
+
+    /// 条件の値をバインド用の値に変換（カラムの値はカラムの型に従って変換する）
+    fn bind(&self, condition: &Condition, value: &Value, is_json_path: bool) -> SqlResult<SqlValue> {
+        if is_json_path {
+            json_path_value(value)
+        } else if condition.op == FilterOp::Like {
+            Ok(SqlValue::Text(value.as_str().unwrap_or_default().to_string()))
+        } else {
+            self.schema.to_sql_value(&condition.field, value)
+        }
+    }
+
+    fn build_condition(&mut self, condition: &Condition) -> SqlResult<String> {
+        self.schema.validate_column(&condition.field)?;
+        validate_visible_column(&self.schema.table, &condition.field)?;
+
+        let is_json_path = condition.path.is_some();
+        let target = match &condition.path {
+            Some(path) => {
+                if !JSON_PATH_COLUMNS.contains(&condition.field.as_str()) {
+                    return Err(invalid_query(format!("JSONパス条件を使えないフィールドです: {}", condition.field)));
+                }
+                if !is_valid_json_path(path) {
+                    return Err(invalid_query(format!("無効なJSONパスです: {}", path)));
+                }
+                self.params.push(SqlValue::Text(path.clone()));
+                format!("json_extract(\"{}\", ?)", condition.field)
+            }
+            None => format!("\"{}\"", condition.field),
+        };
+
+        let comparison = |op: &str, builder: &mut Self| -> SqlResult<String> {
+            let value = builder.bind(condition, &condition.value, is_json_path)?;
+            builder.params.push(value);
+            Ok(format!("{} {} ?", target, op))
+        };
+
+        match condition.op {
+            FilterOp::Eq if condition.value.is_null() => Ok(format!("{} IS NULL", target)),
+            FilterOp::Ne if condition.value.is_null() => Ok(format!("{} IS NOT NULL", target)),
+            FilterOp::Eq => comparison("=", self),
+            FilterOp::Ne => comparison("!=", self),
+            FilterOp::Lt => comparison("<", self),
+            FilterOp::Lte => comparison("<=", self),
+            FilterOp::Gt => comparison(">", self),
+            FilterOp::Gte => comparison(">=", self),
+            FilterOp::Like => {
+                if !condition.value.is_string() {
+                    return Err(invalid_query(format!("likeの値は文字列で指定してください: {}", condition.field)));
+                }
+                comparison("LIKE", self)
+            }
+            FilterOp::In | FilterOp::NotIn => {
+                let values = condition.value.as_array().ok_or_else(|| {
+                    invalid_query(format!("in/notInの値は配列で指定してください: {}", condition.field))
+                })?;
+                let negate = condition.op == FilterOp::NotIn;
+                if values.is_empty() {
+                    return Ok(if negate { "1" } else { "0" }.to_string());
+                }
+                for value in values {
+                    let value = self.bind(condition, value, is_json_path)?;
+                    self.params.push(value);
+                }
+                let placeholders = vec!["?"; values.len()].join(", ");
+                Ok(format!("{} {}IN ({})", target, if negate { "NOT " } else { "" }, placeholders))
+            }
+            FilterOp::IsNull => Ok(format!("{} IS NULL", target)),
+            FilterOp::IsNotNull => Ok(format!("{} IS NOT NULL", target)),
+        }
+    }
+}
+
+/// カーソルを読み取る（カーソルは次のページの開始位置）
+fn parse_cursor(cursor: Option<&str>) -> SqlResult<i64> {
+    match cursor {
+        None | Some("") => Ok(0),
+        Some(cursor) => cursor.parse::<i64>().ok()
+            .filter(|offset| *offset >= 0)
+            .ok_or_else(|| invalid_query(format!("無効なカーソルです: {}", cursor))),
+    }
+}
+
+/// 構造化クエリでコレクションを取得
+pub fn query_collection(collection_name: &str, query: &CollectionQuery) -> SqlResult<CollectionPage> {
+    // テーブル名の検証（SQLインジェクション対策）
+    validate_table_name(collection_name)?;
+
+    let db = get_db().ok_or_else(|| invalid_query("データベースが初期化されていません".to_string()))?;
+    let conn = db.get_connection()?;
+    let schema = table_schema(&conn, collection_name)?;
+
+    // 返すカラム（id は常に含める）
+    let projection = match &query.select {
+        Some(select) if !select.is_empty() => {
+            let mut fields = vec!["id".to_string()];
+            for field in select {
+                schema.validate_column(field)?;
+                validate_visible_column(collection_name, field)?;
+                if !fields.contains(field) {
+                    fields.push(field.clone());
+                }
+            }
+            fields.iter().map(|f| format!("\"{}\"", f)).collect::<Vec<_>>().join(", ")
+        }
+        _ => "*".to_string(),
+    };
+
+    let mut builder = QueryBuilder { schema: &schema, params: Vec::new() };
+    let mut where_clauses = Vec::new();
+    if let Some(filter) = &query.filter {
+        where_clauses.push(builder.build_filter(filter, 0)?);
+    }
+    // ゴミ箱にある行は返さない
+    if is_soft_delete_table(collection_name) {
+        where_clauses.push("deletedAt IS NULL".to_string());
+    }
+
+    let mut order_clauses = Vec::new();
+    for order in &query.order_by {
+        schema.validate_column(&order.field)?;
+        validate_visible_column(collection_name, &order.field)?;
+        let direction = match order.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        order_clauses.push(format!("\"{}\" {}", order.field, direction));
+    }
+    // ページをまたいで順序が変わらないよう id で並びを確定させる
+    if !query.order_by.iter().any(|o| o.field == "id") {
+        order_clauses.push("id ASC".to_string());
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = parse_cursor(query.cursor.as_deref())?;
+
+    let mut sql = format!("SELECT {} FROM {}", projection, collection_name);
+    if !where_clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&where_clauses.join(" AND "));
+    }
+    sql.push_str(" ORDER BY ");
+    sql.push_str(&order_clauses.join(", "));
+    // 次のページの有無を判定するため1件多く取得する
+    sql.push_str(" LIMIT ? OFFSET ?");
+    let mut params = builder.params;
+    params.push(SqlValue::Integer(limit + 1));
+    params.push(SqlValue::Integer(offset));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let mut items = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| schema.row_to_map(row))?
+        .collect::<SqlResult<Vec<_>>>()?;
+
+    for item in items.iter_mut() {
+        strip_hidden_columns(collection_name, item);
+    }
+
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        Some((offset + limit).to_string())
+    } else {
+        None
+    };
+
+    Ok(CollectionPage { items, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ColumnType;
+    use serde_json::json;
+
+    fn entities_schema() -> TableSchema {
+        TableSchema {
+            table: "entities".to_string(),
+            columns: vec![
+                ("id".to_string(), ColumnType::Text),
+                ("name".to_string(), ColumnType::Text),
+                ("searchCount".to_string(), ColumnType::Integer),
+                ("metadata".to_string(), ColumnType::Json),
+                ("aliases".to_string(), ColumnType::Json),
+            ],
+        }
+    }
+
+    fn build(schema: &TableSchema, filter: Value) -> SqlResult<(String, Vec<SqlValue>)> {
+        let filter: Filter = serde_json::from_value(filter).unwrap();
+        let mut builder = QueryBuilder { schema, params: Vec::new() };
+        let sql = builder.build_filter(&filter, 0)?;
+        Ok((sql, builder.params))
+    }
+
+    #[test]
+    fn from_params_parses_order_limit_select_and_equality_conditions() {
+        let params: HashMap<String, String> = [
+            ("orderBy", "name:desc, searchCount"),
+            ("limit", "20"),
+            ("select", "name,searchCount"),
+            ("organizationId", "org1"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let query = CollectionQuery::from_params(&params).unwrap();
+        assert_eq!(query.order_by.len(), 2);
+        assert_eq!(query.order_by[0].field, "name");
+        assert_eq!(query.order_by[0].direction, SortDirection::Desc);
+        assert_eq!(query.order_by[1].field, "searchCount");
+        assert_eq!(query.order_by[1].direction, SortDirection::Asc);
+        assert_eq!(query.limit, Some(20));
+        assert_eq!(query.select, Some(vec!["name".to_string(), "searchCount".to_string()]));
+        match query.filter {
+            Some(Filter::Condition(condition)) => {
+                assert_eq!(condition.field, "organizationId");
+                assert_eq!(condition.op, FilterOp::Eq);
+                assert_eq!(condition.value, json!("org1"));
+            }
+            other => panic!("unexpected filter: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn from_params_rejects_invalid_values() {
+        let params = |key: &str, value: &str| HashMap::from([(key.to_string(), value.to_string())]);
+        assert!(CollectionQuery::from_params(&params("orderBy", "name:sideways")).is_err());
+        assert!(CollectionQuery::from_params(&params("limit", "ten")).is_err());
+        assert!(CollectionQuery::from_params(&params("where", "{\"field\":")).is_err());
+    }
+
+    #[test]
+    fn builds_nested_conditions_with_bound_parameters() {
+        let schema = entities_schema();
+        let (sql, params) = build(&schema, json!({
+            "and": [
+                { "field": "searchCount", "op": ">=", "value": 3 },
+                { "or": [
+                    { "field": "name", "op": "like", "value": "%AI%" },
+                    { "field": "id", "op": "in", "value": ["a", "b"] },
+                ]},
+                { "field": "name", "op": "eq", "value": null },
+            ]
+        })).unwrap();
+
+        assert_eq!(
+            sql,
+            "(\"searchCount\" >= ? AND (\"name\" LIKE ? OR \"id\" IN (?, ?)) AND \"name\" IS NULL)"
+        );
+        assert_eq!(params, vec![
+            SqlValue::Integer(3),
+            SqlValue::Text("%AI%".to_string()),
+            SqlValue::Text("a".to_string()),
+            SqlValue::Text("b".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn builds_json_path_conditions_and_empty_lists() {
+        let schema = entities_schema();
+        let (sql, params) = build(&schema, json!({
+            "field": "metadata", "path": "$.verified", "op": "eq", "value": true
+        })).unwrap();
+        assert_eq!(sql, "json_extract(\"metadata\", ?) = ?");
+        assert_eq!(params, vec![SqlValue::Text("$.verified".to_string()), SqlValue::Integer(1)]);
+
+        let (sql, params) = build(&schema, json!({ "field": "id", "op": "notIn", "value": [] })).unwrap();
+        assert_eq!(sql, "1");
+        assert!(params.is_empty());
+
+        let (sql, _) = build(&schema, json!({ "or": [] })).unwrap();
+        assert_eq!(sql, "0");
+    }
+
+    #[test]
+    fn rejects_invalid_conditions() {
+        let schema = entities_schema();
+        // 存在しないカラム
+        assert!(build(&schema, json!({ "field": "name; DROP TABLE entities", "op": "eq", "value": 1 })).is_err());
+        // metadata 以外のJSONパス・不正なJSONパス
+        assert!(build(&schema, json!({ "field": "aliases", "path": "$.a", "op": "eq", "value": 1 })).is_err());
+        assert!(build(&schema, json!({ "field": "metadata", "path": "$.a')--", "op": "eq", "value": 1 })).is_err());
+        // 値の形式
+        assert!(build(&schema, json!({ "field": "id", "op": "in", "value": "a" })).is_err());
+        assert!(build(&schema, json!({ "field": "name", "op": "like", "value": 1 })).is_err());
+        assert!(build(&schema, json!({ "field": "searchCount", "op": "eq", "value": "many" })).is_err());
+
+        // ネストの上限
+        let mut filter = json!({ "field": "id", "op": "eq", "value": "a" });
+        for _ in 0..=MAX_FILTER_DEPTH {
+            filter = json!({ "and": [filter] });
+        }
+        assert!(build(&schema, filter).is_err());
+    }
+
+    #[test]
+    fn rejects_hidden_columns() {
+        let schema = TableSchema {
+            table: "users".to_string(),
+            columns: vec![
+                ("id".to_string(), ColumnType::Text),
+                ("passwordHash".to_string(), ColumnType::Text),
+            ],
+        };
+        assert!(build(&schema, json!({ "field": "passwordHash", "op": "like", "value": "%" })).is_err());
+    }
+
+    #[test]
+    fn parses_cursor() {
+        assert_eq!(parse_cursor(None).unwrap(), 0);
+        assert_eq!(parse_cursor(Some("")).unwrap(), 0);
+        assert_eq!(parse_cursor(Some("200")).unwrap(), 200);
+        assert!(parse_cursor(Some("-1")).is_err());
+        assert!(parse_cursor(Some("abc")).is_err());
+    }
+}
@@ -0,0 +1,413 @@
+/**
+ * ロールベースのアクセス制御と利用者の承認ワークフロー
+ *
+ * - ロールは admin / editor / viewer（admin ⊃ editor ⊃ viewer）
+ *   - viewer: 閲覧のみ
+ *   - editor: 閲覧・作成・更新・削除
+ *   - admin: editor の権限に加えて、利用者の承認・ロール変更・APIトークン管理
+ * - 新規登録した利用者は承認待ち（viewer）となり、管理者が承認・却下する
+ */
+
+use crate::database::{get_db, get_timestamp, revoke_user_api_tokens, revoke_user_sessions, ApiScope, User};
+use rusqlite::{params, OptionalExtension, Result as SqlResult, Row};
+use serde::{Deserialize, Serialize};
+
+/// 利用者のロール
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+/// 操作に必要な権限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// データの閲覧
+    Read,
+    /// データの作成・更新
+    Write,
+    /// データの削除
+    Delete,
+    /// 利用者の承認・ロール変更・APIトークン管理などの管理操作
+    Admin,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::Read => "閲覧",
+            Permission::Write => "編集",
+            Permission::Delete => "削除",
+            Permission::Admin => "管理",
+        }
+    }
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// 文字列からロールを取得（旧ロール 'user' は editor として扱う）
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim() {
+            "viewer" => Some(Role::Viewer),
+            "editor" | "user" => Some(Role::Editor),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    /// このロールで permission の操作が許可されるか
+    pub fn allows(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::Read => true,
+            Permission::Write | Permission::Delete => *self >= Role::Editor,
+            Permission::Admin => *self == Role::Admin,
+        }
+    }
+
+    /// セッショントークンでREST APIを呼び出す場合のスコープ
+    pub fn api_scopes(&self) -> Vec<ApiScope> {
+        match self {
+            Role::Viewer => vec![ApiScope::Read],
+            Role::Editor => vec![ApiScope::Read, ApiScope::Write],
+            Role::Admin => vec![ApiScope::Read, ApiScope::Write, ApiScope::Admin],
+        }
+    }
+}
+
+/// 権限不足のエラーメッセージ
+pub fn permission_denied_message(role: Role, permission: Permission) -> String {
+    format!(
+        "この操作には{}権限が必要です（現在のロール: {}）",
+        permission.as_str(),
+        role.as_str()
+    )
+}
+
+/// 承認リクエスト
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub email: String,
+    pub status: String,
+    #[serde(rename = "requestedAt")]
+    pub requested_at: String,
+    #[serde(rename = "reviewedBy")]
+    pub reviewed_by: Option<String>,
+    #[serde(rename = "reviewedAt")]
+    pub reviewed_at: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// 利用者情報（パスワードハッシュは含まない）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserAccount {
+    pub id: String,
+    pub email: String,
+    pub role: Role,
+    pub approved: bool,
+    #[serde(rename = "approvedBy")]
+    pub approved_by: Option<String>,
+    #[serde(rename = "approvedAt")]
+    pub approved_at: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+const APPROVAL_REQUEST_COLUMNS: &str =
+    "id, userId, email, COALESCE(status, 'pending'), requestedAt, reviewedBy, reviewedAt, reason";
+
+fn row_to_approval_request(row: &Row) -> SqlResult<ApprovalRequest> {
+    Ok(ApprovalRequest {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        email: row.get(2)?,
+        status: row.get(3)?,
+        requested_at: row.get(4)?,
+        reviewed_by: row.get(5)?,
+        reviewed_at: row.get(6)?,
+        reason: row.get(7)?,
+    })
+}
+
+fn row_to_user_account(row: &Row) -> SqlResult<UserAccount> {
+    let role: Option<String> = row.get(2)?;
+    Ok(UserAccount {
+        id: row.get(0)?,
+        email: row.get(1)?,
+        role: role.as_deref().and_then(Role::from_str).unwrap_or(Role::Viewer),
+        approved: row.get::<_, i32>(3)? == 1,
+        approved_by: row.get(4)?,
+        approved_at: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+fn constraint_error(message: &str) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+        Some(message.to_string()),
+    )
+}
+
+/// ユーザーのロールを取得（不明なロールは viewer として扱う）
+pub fn get_user_role(user_id: &str) -> SqlResult<Role> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    let role: Option<String> = conn.query_row(
+        "SELECT role FROM users WHERE id = ?1",
+        [user_id],
+        |row| row.get(0),
+    )?;
+    Ok(role.as_deref().and_then(Role::from_str).unwrap_or(Role::Viewer))
+}
+
+/// ユーザーに permission の操作が許可されているか確認（許可されていない場合はエラーメッセージ）
+pub fn authorize(user: &User, permission: Permission) -> Result<Role, String> {
+    let role = get_user_role(&user.uid)
+        .map_err(|e| format!("ユーザーロールの取得に失敗しました: {}", e))?;
+    if !role.allows(permission) {
+        eprintln!(
+            "⚠️ [authorize] 権限不足: user={}, role={}, permission={:?}",
+            user.email, role.as_str(), permission
+        );
+        return Err(permission_denied_message(role, permission));
+    }
+    Ok(role)
+}
+
+/// 承認リクエスト一覧を取得（status を指定しない場合はすべて）
+pub fn list_approval_requests(status: Option<&str>) -> SqlResult<Vec<ApprovalRequest>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM approvalRequests
+         WHERE ?1 IS NULL OR COALESCE(status, 'pending') = ?1
+         ORDER BY requestedAt ASC",
+        APPROVAL_REQUEST_COLUMNS
+    ))?;
+    let requests = stmt.query_map(params![status], row_to_approval_request)?;
+    requests.collect::<Result<Vec<_>, _>>()
+}
+
+/// IDで承認リクエストを取得
+pub fn get_approval_request(request_id: &str) -> SqlResult<ApprovalRequest> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    conn.query_row(
+        &format!("SELECT {} FROM approvalRequests WHERE id = ?1", APPROVAL_REQUEST_COLUMNS),
+        [request_id],
+        row_to_approval_request,
+    )
+}
+
+/// 承認リクエストを承認（role を指定しない場合は viewer）
+pub fn approve_user(admin: &User, request_id: &str, role: Option<Role>) -> SqlResult<ApprovalRequest> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let request = get_approval_request(request_id)?;
+    if request.status != "pending" {
+        return Err(constraint_error(&format!(
+            "この承認リクエストは既に処理されています（状態: {}）",
+            request.status
+        )));
+    }
+
+    let now = get_timestamp();
+    let role = role.unwrap_or(Role::Viewer);
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE users SET approved = 1, approvedBy = ?1, approvedAt = ?2, role = ?3, updatedAt = ?2
+         WHERE id = ?4",
+        params![admin.uid, now, role.as_str(), request.user_id],
+    )?;
+    tx.execute(
+        "UPDATE approvalRequests SET status = 'approved', reviewedBy = ?1, reviewedAt = ?2
+         WHERE id = ?3",
+        params![admin.uid, now, request_id],
+    )?;
+    tx.commit()?;
+
+    eprintln!("✅ [approve_user] 利用者を承認しました: email={}, role={}", request.email, role.as_str());
+    get_approval_request(request_id)
+}
+
+/// 承認リクエストを却下
+pub fn reject_user(admin: &User, request_id: &str, reason: Option<&str>) -> SqlResult<ApprovalRequest> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let request = get_approval_request(request_id)?;
+    if request.status != "pending" {
+        return Err(constraint_error(&format!(
+            "この承認リクエストは既に処理されています（状態: {}）",
+            request.status
+        )));
+    }
+    if request.user_id == admin.uid {
+        return Err(constraint_error("自分自身の承認リクエストは却下できません"));
+    }
+
+    let now = get_timestamp();
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE users SET approved = 0, approvedBy = NULL, approvedAt = NULL, updatedAt = ?1
+         WHERE id = ?2",
+        params![now, request.user_id],
+    )?;
+    tx.execute(
+        "UPDATE approvalRequests SET status = 'rejected', reviewedBy = ?1, reviewedAt = ?2, reason = ?3
+         WHERE id = ?4",
+        params![admin.uid, now, reason, request_id],
+    )?;
+    tx.commit()?;
+
+    revoke_user_sessions(&request.user_id)?;
+    revoke_user_api_tokens(&request.user_id)?;
+    eprintln!("🚫 [reject_user] 利用者の登録を却下しました: email={}", request.email);
+    get_approval_request(request_id)
+}
+
+/// 利用者一覧を取得
+pub fn list_users() -> SqlResult<Vec<UserAccount>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, email, role, COALESCE(approved, 0), approvedBy, approvedAt, createdAt
+         FROM users ORDER BY createdAt ASC",
+    )?;
+    let users = stmt.query_map([], row_to_user_account)?;
+    users.collect::<Result<Vec<_>, _>>()
+}
+
+/// 利用者のロールを変更（最後の管理者を降格することはできない）
+pub fn set_user_role(user_id: &str, role: Role) -> SqlResult<UserAccount> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let current = get_user_role(user_id)?;
+    if current == Role::Admin && role != Role::Admin {
+        let admin_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM users WHERE role = 'admin' AND approved = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        if admin_count <= 1 {
+            return Err(constraint_error("最後の管理者のロールは変更できません"));
+        }
+    }
+
+    conn.execute(
+        "UPDATE users SET role = ?1, updatedAt = ?2 WHERE id = ?3",
+        params![role.as_str(), get_timestamp(), user_id],
+    )?;
+
+    conn.query_row(
+        "SELECT id, email, role, COALESCE(approved, 0), approvedBy, approvedAt, createdAt
+         FROM users WHERE id = ?1",
+        [user_id],
+        row_to_user_account,
+    )
+}
+
+/// 承認済みの管理者が存在するか
+pub(crate) fn has_admin(conn: &rusqlite::Connection) -> SqlResult<bool> {
+    let id: Option<String> = conn.query_row(
+        "SELECT id FROM users WHERE role = 'admin' AND approved = 1 LIMIT 1",
+        [],
+        |row| row.get(0),
+    ).optional()?;
+    Ok(id.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{init_test_database, insert_test_user};
+
+    #[test]
+    fn roles_allow_permissions_by_rank() {
+        assert!(Role::Viewer.allows(Permission::Read));
+        assert!(!Role::Viewer.allows(Permission::Write));
+        assert!(!Role::Viewer.allows(Permission::Delete));
+        assert!(Role::Editor.allows(Permission::Write));
+        assert!(Role::Editor.allows(Permission::Delete));
+        assert!(!Role::Editor.allows(Permission::Admin));
+        assert!(Role::Admin.allows(Permission::Admin));
+    }
+
+    #[test]
+    fn parses_roles_and_treats_the_legacy_user_role_as_editor() {
+        assert_eq!(Role::from_str("admin"), Some(Role::Admin));
+        assert_eq!(Role::from_str(" viewer "), Some(Role::Viewer));
+        assert_eq!(Role::from_str("user"), Some(Role::Editor));
+        assert_eq!(Role::from_str("owner"), None);
+    }
+
+    #[test]
+    fn authorize_checks_the_stored_role() {
+        let viewer = insert_test_user(Role::Viewer, true);
+        assert_eq!(authorize(&viewer, Permission::Read), Ok(Role::Viewer));
+        assert_eq!(
+            authorize(&viewer, Permission::Write),
+            Err(permission_denied_message(Role::Viewer, Permission::Write))
+        );
+
+        let editor = insert_test_user(Role::Editor, true);
+        assert_eq!(authorize(&editor, Permission::Delete), Ok(Role::Editor));
+        assert!(authorize(&editor, Permission::Admin).is_err());
+
+        let admin = insert_test_user(Role::Admin, true);
+        assert_eq!(authorize(&admin, Permission::Admin), Ok(Role::Admin));
+    }
+
+    #[test]
+    fn authorize_follows_role_changes_and_rejects_unknown_users() {
+        let user = insert_test_user(Role::Admin, true);
+        let conn = init_test_database().get_connection().unwrap();
+        conn.execute("UPDATE users SET role = 'viewer' WHERE id = ?1", [&user.uid]).unwrap();
+        assert!(authorize(&user, Permission::Write).is_err());
+
+        // ロールが不明な場合は viewer として扱う
+        conn.execute("UPDATE users SET role = 'unknown' WHERE id = ?1", [&user.uid]).unwrap();
+        assert_eq!(authorize(&user, Permission::Read), Ok(Role::Viewer));
+        assert!(authorize(&user, Permission::Write).is_err());
+
+        let missing = User { uid: "missing".to_string(), email: "missing@example.com".to_string(), email_verified: true };
+        assert!(authorize(&missing, Permission::Read).is_err());
+    }
+}
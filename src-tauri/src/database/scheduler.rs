@@ -0,0 +1,708 @@
+/**
+ * スケジューラー
+ * タスク・タスクチェーンを、cron式・指定日時・データの変更をきっかけに自動で実行する
+ *
+ * - スケジュールは schedules テーブルに保存し、実行するたびに nextRunAt / lastRunAt を更新する
+ * - タスクはタスク実行エンジンの実行キューに追加し（taskExecutions の pending 行を作成）、タスクチェーンは chain_runner で実行を開始する
+ * - cron（triggerType = "cron"）: cron式（cron.rs、ローカルタイムゾーン）で次回の実行時刻を計算する
+ * - 指定日時（"once"）: runAt に1回だけ実行し、実行後は nextRunAt を空にする
+ * - データの変更（"event"）: 監査ログ（変更フィードと同じ）から eventTable の変更を読み取り、eventOperation・eventFilter（変更後の行の値）に一致したら実行する
+ * - アプリの停止中などで実行時刻を過ぎた場合（1分以上の遅れ）は catchUpPolicy に従う
+ *   - skip: 実行せずに次回の実行時刻へ進む
+ *   - run_once: 遅れた分をまとめて1回だけ実行する
+ *   - run_all: 遅れた回数分実行する（最大10回）
+ * - 実行の記録は scheduleRuns テーブルに保存し、Tauriイベント "schedule-triggered" で通知する
+ */
+
+use crate::database::cron::CronSchedule;
+use crate::database::{
+    enqueue_task_execution, get_db, get_task, get_task_chain, get_timestamp, latest_change_seq,
+    start_task_chain_run, subscribe_changes, Timestamp,
+};
+use crate::db::WriteQueueState;
+use rusqlite::{params, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+/// 実行通知用のTauriイベント名
+pub const SCHEDULE_EVENT: &str = "schedule-triggered";
+
+/// この時間以上遅れた実行は catchUpPolicy に従う
+const MISSED_GRACE_SECS: i64 = 60;
+/// run_all で遅れを取り戻す最大回数
+const MAX_CATCH_UP_RUNS: usize = 10;
+/// 1回の読み取りで取得する監査ログの最大件数
+const EVENT_PAGE_SIZE: i64 = 500;
+/// 起こされなかった場合の確認間隔
+const POLL_INTERVAL_SECS: u64 = 30;
+
+/// 実行対象の種類
+pub const TARGET_TYPES: &[&str] = &["task", "chain"];
+/// きっかけの種類
+pub const TRIGGER_TYPES: &[&str] = &["cron", "once", "event"];
+/// 遅れた実行の扱い
+pub const CATCH_UP_POLICIES: &[&str] = &["skip", "run_once", "run_all"];
+/// eventOperation に指定できる操作（監査ログの action）
+pub const EVENT_OPERATIONS: &[&str] = &["create", "update", "delete", "restore", "purge"];
+/// eventTable に指定できないテーブル（スケジュールの実行自体が変更するため、実行が連鎖する）
+const EXCLUDED_EVENT_TABLES: &[&str] = &["taskExecutions", "taskChainRuns", "taskChainRunSteps", "schedules", "scheduleRuns", "auditLog"];
+
+/// スケジュール
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// task / chain
+    #[serde(rename = "targetType")]
+    pub target_type: String,
+    #[serde(rename = "targetId")]
+    pub target_id: String,
+    /// cron / once / event
+    #[serde(rename = "triggerType")]
+    pub trigger_type: String,
+    #[serde(rename = "cronExpression", skip_serializing_if = "Option::is_none")]
+    pub cron_expression: Option<String>,
+    #[serde(rename = "runAt", skip_serializing_if = "Option::is_none")]
+    pub run_at: Option<Timestamp>,
+    #[serde(rename = "eventTable", skip_serializing_if = "Option::is_none")]
+    pub event_table: Option<String>,
+    /// 省略した場合はすべての操作
+    #[serde(rename = "eventOperation", skip_serializing_if = "Option::is_none")]
+    pub event_operation: Option<String>,
+    /// 変更後の行と比較する値（例: {"organizationId": "org-1"}）
+    #[serde(rename = "eventFilter", skip_serializing_if = "Option::is_none")]
+    pub event_filter: Option<String>, // JSON文字列
+    /// skip / run_once / run_all
+    #[serde(rename = "catchUpPolicy", default = "default_catch_up_policy")]
+    pub catch_up_policy: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(rename = "nextRunAt", skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<Timestamp>,
+    #[serde(rename = "lastRunAt", skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<Timestamp>,
+    #[serde(rename = "lastError", skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// 読み取り済みの監査ログの位置（event のみ）
+    #[serde(rename = "lastEventSeq", default)]
+    pub last_event_seq: i64,
+    #[serde(rename = "createdAt")]
+    pub created_at: Timestamp,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Timestamp,
+}
+
+fn default_catch_up_policy() -> String {
+    "run_once".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// スケジュールの実行記録
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub id: String,
+    #[serde(rename = "scheduleId")]
+    pub schedule_id: String,
+    /// schedule / catch_up / event / manual
+    pub reason: String,
+    /// triggered / skipped / failed
+    pub status: String,
+    /// 本来の実行時刻（event の場合は変更の時刻）
+    #[serde(rename = "scheduledFor", skip_serializing_if = "Option::is_none")]
+    pub scheduled_for: Option<Timestamp>,
+    #[serde(rename = "executionId", skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
+    #[serde(rename = "chainRunId", skip_serializing_if = "Option::is_none")]
+    pub chain_run_id: Option<String>,
+    /// きっかけになった変更の seq（event のみ）
+    #[serde(rename = "eventSeq", skip_serializing_if = "Option::is_none")]
+    pub event_seq: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Timestamp,
+}
+
+static TRIGGER: OnceLock<Notify> = OnceLock::new();
+
+fn trigger() -> &'static Notify {
+    TRIGGER.get_or_init(Notify::new)
+}
+
+fn not_initialized() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    )
+}
+
+const SCHEDULE_COLUMNS: &str = "id, name, description, targetType, targetId, triggerType, cronExpression, runAt,
+    eventTable, eventOperation, eventFilter, catchUpPolicy, enabled, nextRunAt, lastRunAt, lastError,
+    lastEventSeq, createdAt, updatedAt";
+
+fn row_to_schedule(row: &rusqlite::Row) -> SqlResult<Schedule> {
+    let enabled: i32 = row.get(12)?;
+    Ok(Schedule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+        target_type: row.get(3)?,
+        target_id: row.get(4)?,
+        trigger_type: row.get(5)?,
+        cron_expression: row.get(6)?,
+        run_at: row.get(7)?,
+        event_table: row.get(8)?,
+        event_operation: row.get(9)?,
+        event_filter: row.get(10)?,
+        catch_up_policy: row.get(11)?,
+        enabled: enabled != 0,
+        next_run_at: row.get(13)?,
+        last_run_at: row.get(14)?,
+        last_error: row.get(15)?,
+        last_event_seq: row.get(16)?,
+        created_at: row.get(17)?,
+        updated_at: row.get(18)?,
+    })
+}
+
+/// スケジュールを検証（保存前に呼ぶ）
+pub fn validate_schedule(schedule: &Schedule) -> Result<(), String> {
+    if schedule.name.trim().is_empty() {
+        return Err("名前を指定してください".to_string());
+    }
+    if !CATCH_UP_POLICIES.contains(&schedule.catch_up_policy.as_str()) {
+        return Err(format!("catchUpPolicy は {} のいずれかを指定してください", CATCH_UP_POLICIES.join(" / ")));
+    }
+
+    match schedule.target_type.as_str() {
+        "task" => {
+            get_task(&schedule.target_id)
+                .map_err(|e| format!("タスクの取得に失敗しました: {}", e))?
+                .ok_or_else(|| format!("タスクが見つかりません: {}", schedule.target_id))?;
+        }
+        "chain" => {
+            get_task_chain(&schedule.target_id)
+                .map_err(|e| format!("タスクチェーンの取得に失敗しました: {}", e))?
+                .ok_or_else(|| format!("タスクチェーンが見つかりません: {}", schedule.target_id))?;
+        }
+        _ => return Err(format!("targetType は {} のいずれかを指定してください", TARGET_TYPES.join(" / "))),
+    }
+
+    match schedule.trigger_type.as_str() {
+        "cron" => {
+            let expression = schedule.cron_expression.as_deref()
+                .ok_or_else(|| "cronExpression を指定してください".to_string())?;
+            let cron = CronSchedule::parse(expression)?;
+            if cron.next_after(Timestamp::now()).is_none() {
+                return Err(format!("cron式に一致する日時がありません: {}", expression));
+            }
+        }
+        "once" => {
+            if schedule.run_at.is_none() {
+                return Err("runAt を指定してください".to_string());
+            }
+        }
+        "event" => {
+            let table = schedule.event_table.as_deref()
+                .ok_or_else(|| "eventTable を指定してください".to_string())?;
+            if EXCLUDED_EVENT_TABLES.contains(&table) {
+                return Err(format!("テーブル {} の変更はきっかけに指定できません", table));
+            }
+            if !table_exists(table).map_err(|e| format!("テーブルの確認に失敗しました: {}", e))? {
+                return Err(format!("テーブルが見つかりません: {}", table));
+            }
+            if let Some(operation) = &schedule.event_operation {
+                if !EVENT_OPERATIONS.contains(&operation.as_str()) {
+                    return Err(format!("eventOperation は {} のいずれかを指定してください", EVENT_OPERATIONS.join(" / ")));
+                }
+            }
+            if let Some(filter) = &schedule.event_filter {
+                let filter: Value = serde_json::from_str(filter)
+                    .map_err(|e| format!("eventFilter を解析できません: {}", e))?;
+                if !filter.is_object() {
+                    return Err("eventFilter はオブジェクトで指定してください".to_string());
+                }
+            }
+        }
+        _ => return Err(format!("triggerType は {} のいずれかを指定してください", TRIGGER_TYPES.join(" / "))),
+    }
+    Ok(())
+}
+
+fn table_exists(table: &str) -> SqlResult<bool> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        params![table],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// 保存時の次回の実行時刻
+fn initial_next_run_at(schedule: &Schedule, now: Timestamp) -> Option<Timestamp> {
+    match schedule.trigger_type.as_str() {
+        "cron" => schedule.cron_expression.as_deref()
+            .and_then(|expression| CronSchedule::parse(expression).ok())
+            .and_then(|cron| cron.next_after(now)),
+        "once" => schedule.run_at,
+        _ => None,
+    }
+}
+
+/// スケジュールを保存（nextRunAt は保存時に計算し直し、lastRunAt などの実行状態は引き継ぐ）
+pub fn save_schedule(schedule: &Schedule) -> SqlResult<Schedule> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let existing = get_schedule(&schedule.id)?;
+    let now = Timestamp::now();
+    let next_run_at = if schedule.enabled { initial_next_run_at(schedule, now) } else { None };
+    // event は保存した時点より後の変更から対象にする（条件を変えた場合も同じ）
+    let last_event_seq = match &existing {
+        Some(existing) if existing.trigger_type == "event"
+            && existing.event_table == schedule.event_table
+            && existing.enabled => existing.last_event_seq,
+        _ => latest_change_seq()?,
+    };
+
+    conn.execute(
+        "INSERT OR REPLACE INTO schedules (id, name, description, targetType, targetId, triggerType, cronExpression, runAt,
+            eventTable, eventOperation, eventFilter, catchUpPolicy, enabled, nextRunAt, lastRunAt, lastError,
+            lastEventSeq, createdAt, updatedAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+        params![
+            schedule.id,
+            schedule.name,
+            schedule.description,
+            schedule.target_type,
+            schedule.target_id,
+            schedule.trigger_type,
+            schedule.cron_expression,
+            schedule.run_at,
+            schedule.event_table,
+            schedule.event_operation,
+            schedule.event_filter,
+            schedule.catch_up_policy,
+            if schedule.enabled { 1 } else { 0 },
+            next_run_at,
+            existing.as_ref().and_then(|s| s.last_run_at),
+            existing.as_ref().and_then(|s| s.last_error.clone()),
+            last_event_seq,
+            existing.as_ref().map(|s| s.created_at).unwrap_or(now),
+            now,
+        ],
+    )?;
+    trigger().notify_one();
+
+    get_schedule(&schedule.id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+/// スケジュールを取得
+pub fn get_schedule(id: &str) -> SqlResult<Option<Schedule>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    conn.query_row(
+        &format!("SELECT {} FROM schedules WHERE id = ?1", SCHEDULE_COLUMNS),
+        params![id],
+        row_to_schedule,
+    ).optional()
+}
+
+/// すべてのスケジュールを取得
+pub fn get_all_schedules() -> SqlResult<Vec<Schedule>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM schedules ORDER BY createdAt DESC", SCHEDULE_COLUMNS))?;
+    let schedules = stmt.query_map([], row_to_schedule)?;
+    schedules.collect()
+}
+
+/// スケジュールを削除（実行記録も削除される）
+pub fn delete_schedule(id: &str) -> SqlResult<()> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.execute("DELETE FROM schedules WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// スケジュールの実行記録を取得（新しい順）
+pub fn get_schedule_runs(schedule_id: &str, limit: i64) -> SqlResult<Vec<ScheduleRun>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, scheduleId, reason, status, scheduledFor, executionId, chainRunId, eventSeq, error, createdAt
+         FROM scheduleRuns WHERE scheduleId = ?1 ORDER BY createdAt DESC, rowid DESC LIMIT ?2",
+    )?;
+    let runs = stmt.query_map(params![schedule_id, limit.clamp(1, 1000)], |row| {
+        Ok(ScheduleRun {
+            id: row.get(0)?,
+            schedule_id: row.get(1)?,
+            reason: row.get(2)?,
+            status: row.get(3)?,
+            scheduled_for: row.get(4)?,
+            execution_id: row.get(5)?,
+            chain_run_id: row.get(6)?,
+            event_seq: row.get(7)?,
+            error: row.get(8)?,
+            created_at: row.get(9)?,
+        })
+    })?;
+    runs.collect()
+}
+
+/// スケジュールを今すぐ実行（次回の実行時刻は変えない）
+pub async fn run_schedule_now(app: &AppHandle, write_queue: &WriteQueueState, id: &str) -> Result<ScheduleRun, String> {
+    let schedule = get_schedule(id)
+        .map_err(|e| format!("スケジュールの取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("スケジュールが見つかりません: {}", id))?;
+    let run = fire(app, write_queue, &schedule, "manual", None, None).await;
+    update_after_run(&schedule.id, schedule.next_run_at, Some(run.created_at), run.error.as_deref(), None)
+        .map_err(|e| format!("スケジュールの更新に失敗しました: {}", e))?;
+    Ok(run)
+}
+
+/// スケジューラーを起動（停止中に過ぎた実行は catchUpPolicy に従って処理する）
+pub fn start_scheduler(app: AppHandle, write_queue: WriteQueueState) {
+    tauri::async_runtime::spawn(async move {
+        let mut changes = subscribe_changes();
+        loop {
+            if let Err(e) = run_due_schedules(&app, &write_queue).await {
+                eprintln!("[Scheduler] ⚠️ スケジュールの実行に失敗しました: {}", e);
+            }
+            if let Err(e) = run_event_schedules(&app, &write_queue).await {
+                eprintln!("[Scheduler] ⚠️ 変更による実行に失敗しました: {}", e);
+            }
+
+            let mut wait = Duration::from_secs(POLL_INTERVAL_SECS);
+            if let Ok(Some(next_run_at)) = earliest_next_run_at() {
+                let millis = next_run_at.unix_millis() - Timestamp::now().unix_millis();
+                wait = wait.min(Duration::from_millis(millis.max(0) as u64));
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = trigger().notified() => {}
+                changed = changes.changed() => {
+                    if changed.is_err() {
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// 実行時刻を過ぎた cron / once のスケジュールを実行
+async fn run_due_schedules(app: &AppHandle, write_queue: &WriteQueueState) -> SqlResult<()> {
+    let now = Timestamp::now();
+    let due = {
+        let db = get_db().ok_or_else(not_initialized)?;
+        let conn = db.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM schedules
+             WHERE enabled = 1 AND triggerType IN ('cron', 'once') AND nextRunAt IS NOT NULL AND nextRunAt <= ?1
+             ORDER BY nextRunAt ASC",
+            SCHEDULE_COLUMNS
+        ))?;
+        let schedules = stmt.query_map(params![now], row_to_schedule)?;
+        schedules.collect::<SqlResult<Vec<_>>>()?
+    };
+
+    for schedule in due {
+        let Some(scheduled_for) = schedule.next_run_at else { continue };
+        let cron = schedule.cron_expression.as_deref().and_then(|e| CronSchedule::parse(e).ok());
+
+        // 過ぎた実行時刻（cron は now までのすべて、once は runAt のみ）
+        let mut occurrences = vec![scheduled_for];
+        if let Some(cron) = &cron {
+            while occurrences.len() <= MAX_CATCH_UP_RUNS {
+                match cron.next_after(*occurrences.last().unwrap_or(&scheduled_for)) {
+                    Some(next) if next <= now => occurrences.push(next),
+                    _ => break,
+                }
+            }
+        }
+        let missed = now.unix_millis() - scheduled_for.unix_millis() > MISSED_GRACE_SECS * 1000;
+
+        let mut last_run: Option<ScheduleRun> = None;
+        if !missed {
+            last_run = Some(fire(app, write_queue, &schedule, "schedule", Some(scheduled_for), None).await);
+        } else {
+            match schedule.catch_up_policy.as_str() {
+                "skip" => {
+                    let run = record_run(&schedule.id, "catch_up", "skipped", Some(scheduled_for), None, None, None, None)?;
+                    emit_run(app, &run);
+                }
+                "run_all" => {
+                    for occurrence in occurrences.iter().take(MAX_CATCH_UP_RUNS) {
+                        last_run = Some(fire(app, write_queue, &schedule, "catch_up", Some(*occurrence), None).await);
+                    }
+                }
+                _ => {
+                    last_run = Some(fire(app, write_queue, &schedule, "catch_up", Some(scheduled_for), None).await);
+                }
+            }
+        }
+
+        // once は1回で終わり
+        let next_run_at = cron.and_then(|cron| cron.next_after(now));
+        update_after_run(
+            &schedule.id,
+            next_run_at,
+            last_run.as_ref().map(|r| r.created_at),
+            last_run.as_ref().and_then(|r| r.error.as_deref()),
+            None,
+        )?;
+    }
+    Ok(())
+}
+
+/// 監査ログの変更に一致する event のスケジュールを実行
+async fn run_event_schedules(app: &AppHandle, write_queue: &WriteQueueState) -> SqlResult<()> {
+    let schedules = {
+        let db = get_db().ok_or_else(not_initialized)?;
+        let conn = db.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM schedules WHERE enabled = 1 AND triggerType = 'event'",
+            SCHEDULE_COLUMNS
+        ))?;
+        let schedules = stmt.query_map([], row_to_schedule)?;
+        schedules.collect::<SqlResult<Vec<_>>>()?
+    };
+
+    for schedule in schedules {
+        let Some(table) = schedule.event_table.as_deref() else { continue };
+        let filter: Option<serde_json::Map<String, Value>> = schedule.event_filter.as_deref()
+            .and_then(|f| serde_json::from_str(f).ok());
+        let now = Timestamp::now();
+
+        let mut last_seq = schedule.last_event_seq;
+        let mut last_run: Option<ScheduleRun> = None;
+        // run_once で遅れた変更をまとめる場合の最後の変更
+        let mut coalesced: Option<(i64, Timestamp)> = None;
+        let mut caught_up = 0;
+        loop {
+            let changes = list_table_changes(table, last_seq)?;
+            let count = changes.len() as i64;
+            for change in changes {
+                last_seq = change.seq;
+                if !change.matches(schedule.event_operation.as_deref(), filter.as_ref()) {
+                    continue;
+                }
+                let missed = now.unix_millis() - change.timestamp.unix_millis() > MISSED_GRACE_SECS * 1000;
+                if !missed {
+                    last_run = Some(fire(app, write_queue, &schedule, "event", Some(change.timestamp), Some(change.seq)).await);
+                    continue;
+                }
+                match schedule.catch_up_policy.as_str() {
+                    "skip" => {}
+                    "run_all" if caught_up < MAX_CATCH_UP_RUNS => {
+                        caught_up += 1;
+                        last_run = Some(fire(app, write_queue, &schedule, "catch_up", Some(change.timestamp), Some(change.seq)).await);
+                    }
+                    "run_all" => {}
+                    _ => coalesced = Some((change.seq, change.timestamp)),
+                }
+            }
+            if count < EVENT_PAGE_SIZE {
+                break;
+            }
+        }
+        if let Some((seq, timestamp)) = coalesced {
+            last_run = Some(fire(app, write_queue, &schedule, "catch_up", Some(timestamp), Some(seq)).await);
+        }
+
+        if last_seq != schedule.last_event_seq || last_run.is_some() {
+            update_after_run(
+                &schedule.id,
+                None,
+                last_run.as_ref().map(|r| r.created_at).or(schedule.last_run_at),
+                last_run.as_ref().map_or(schedule.last_error.as_deref(), |r| r.error.as_deref()),
+                Some(last_seq),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// 監査ログから読み取った変更
+struct TableChange {
+    seq: i64,
+    action: String,
+    data: Option<Value>,
+    timestamp: Timestamp,
+}
+
+impl TableChange {
+    fn matches(&self, operation: Option<&str>, filter: Option<&serde_json::Map<String, Value>>) -> bool {
+        if operation.is_some_and(|operation| operation != self.action) {
+            return false;
+        }
+        let Some(filter) = filter else { return true };
+        let Some(data) = &self.data else { return filter.is_empty() };
+        filter.iter().all(|(key, expected)| data.get(key) == Some(expected))
+    }
+}
+
+/// after より後の table の変更（削除の場合は変更前の行を data にする）
+fn list_table_changes(table: &str, after: i64) -> SqlResult<Vec<TableChange>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT seq, action, COALESCE(afterData, beforeData), createdAt FROM auditLog
+         WHERE seq > ?1 AND tableName = ?2 ORDER BY seq ASC LIMIT ?3",
+    )?;
+    let changes = stmt.query_map(params![after, table, EVENT_PAGE_SIZE], |row| {
+        let data: Option<String> = row.get(2)?;
+        Ok(TableChange {
+            seq: row.get(0)?,
+            action: row.get(1)?,
+            data: data.and_then(|d| serde_json::from_str(&d).ok()),
+            timestamp: row.get(3)?,
+        })
+    })?;
+    changes.collect()
+}
+
+/// 実行対象を開始して実行記録を保存
+async fn fire(
+    app: &AppHandle,
+    write_queue: &WriteQueueState,
+    schedule: &Schedule,
+    reason: &str,
+    scheduled_for: Option<Timestamp>,
+    event_seq: Option<i64>,
+) -> ScheduleRun {
+    let (execution_id, chain_run_id, error) = match schedule.target_type.as_str() {
+        // 実行者は system（actor: None）
+        "task" => match enqueue_task_execution(write_queue, &schedule.target_id, None).await {
+            Ok(execution) => (Some(execution.id), None, None),
+            Err(e) => (None, None, Some(e)),
+        },
+        "chain" => match start_task_chain_run(&schedule.target_id).await {
+            Ok(run) => (None, Some(run.id), None),
+            Err(e) => (None, None, Some(e)),
+        },
+        other => (None, None, Some(format!("targetType {} には対応していません", other))),
+    };
+    if let Some(error) = &error {
+        eprintln!("[Scheduler] ⚠️ スケジュール {} の実行に失敗しました: {}", schedule.name, error);
+    }
+
+    let status = if error.is_some() { "failed" } else { "triggered" };
+    let run = match record_run(&schedule.id, reason, status, scheduled_for, execution_id.clone(), chain_run_id.clone(), event_seq, error.clone()) {
+        Ok(run) => run,
+        Err(e) => {
+            eprintln!("[Scheduler] ⚠️ 実行記録の保存に失敗しました: {}", e);
+            ScheduleRun {
+                id: String::new(),
+                schedule_id: schedule.id.clone(),
+                reason: reason.to_string(),
+                status: status.to_string(),
+                scheduled_for,
+                execution_id,
+                chain_run_id,
+                event_seq,
+                error,
+                created_at: Timestamp::now(),
+            }
+        }
+    };
+    emit_run(app, &run);
+    run
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_run(
+    schedule_id: &str,
+    reason: &str,
+    status: &str,
+    scheduled_for: Option<Timestamp>,
+    execution_id: Option<String>,
+    chain_run_id: Option<String>,
+    event_seq: Option<i64>,
+    error: Option<String>,
+) -> SqlResult<ScheduleRun> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let run = ScheduleRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        schedule_id: schedule_id.to_string(),
+        reason: reason.to_string(),
+        status: status.to_string(),
+        scheduled_for,
+        execution_id,
+        chain_run_id,
+        event_seq,
+        error,
+        created_at: Timestamp::now(),
+    };
+    conn.execute(
+        "INSERT INTO scheduleRuns (id, scheduleId, reason, status, scheduledFor, executionId, chainRunId, eventSeq, error, createdAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            run.id,
+            run.schedule_id,
+            run.reason,
+            run.status,
+            run.scheduled_for,
+            run.execution_id,
+            run.chain_run_id,
+            run.event_seq,
+            run.error,
+            run.created_at,
+        ],
+    )?;
+    Ok(run)
+}
+
+/// 実行後の状態を保存（event_seq は event のみ）
+fn update_after_run(
+    id: &str,
+    next_run_at: Option<Timestamp>,
+    last_run_at: Option<Timestamp>,
+    last_error: Option<&str>,
+    event_seq: Option<i64>,
+) -> SqlResult<()> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.execute(
+        "UPDATE schedules
+         SET nextRunAt = ?1, lastRunAt = COALESCE(?2, lastRunAt), lastError = ?3,
+             lastEventSeq = COALESCE(?4, lastEventSeq), updatedAt = ?5
+         WHERE id = ?6",
+        params![next_run_at, last_run_at, last_error, event_seq, get_timestamp(), id],
+    )?;
+    Ok(())
+}
+
+/// 最も早い次回の実行時刻
+fn earliest_next_run_at() -> SqlResult<Option<Timestamp>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.query_row(
+        "SELECT MIN(nextRunAt) FROM schedules WHERE enabled = 1 AND nextRunAt IS NOT NULL",
+        [],
+        |row| row.get(0),
+    )
+}
+
+fn emit_run(app: &AppHandle, run: &ScheduleRun) {
+    if let Err(e) = app.emit(SCHEDULE_EVENT, run) {
+        eprintln!("[Scheduler] ⚠️ 実行イベントの送信に失敗しました: {}", e);
+    }
+}
@@ -0,0 +1,721 @@
+/**
+ * ハイブリッド検索モジュール
+ * FTS5（BM25）によるキーワード検索とベクトル類似度検索をRRF（Reciprocal Rank Fusion）で統合
+ *
+ * - entities / relations / topics の searchableText を FTS5 仮想テーブルに同期（トリガー）
+ *   トリガーは行の置き換え（INSERT OR REPLACE）による削除では実行されないため、書き込みは UPSERT で行う
+ * - 日本語の製品名などを部分一致で拾えるよう trigram トークナイザーを使用
+ * - 検索でヒットした行の searchCount / lastSearchDate は書き込みキュー経由で更新
+ */
+
+use rusqlite::{params, Connection, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::database::embedding::generate_embedding;
+use crate::database::vector_store::get_vector_store;
+use crate::database::{get_current_user, get_db, get_timestamp};
+use crate::db::{write_queue, WriteJob};
+
+// RRFの定数（一般的な値）
+const RRF_K: f64 = 60.0;
+
+// trigramトークナイザーの最小文字数（これ未満のキーワードはLIKE検索にフォールバック）
+const TRIGRAM_MIN_CHARS: usize = 3;
+
+/// 検索対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchTarget {
+    Entity,
+    Relation,
+    Topic,
+}
+
+impl SearchTarget {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "entity" | "entities" => Some(SearchTarget::Entity),
+            "relation" | "relations" => Some(SearchTarget::Relation),
+            "topic" | "topics" => Some(SearchTarget::Topic),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            SearchTarget::Entity => "entity",
+            SearchTarget::Relation => "relation",
+            SearchTarget::Topic => "topic",
+        }
+    }
+
+    pub(crate) fn table(&self) -> &'static str {
+        match self {
+            SearchTarget::Entity => "entities",
+            SearchTarget::Relation => "relations",
+            SearchTarget::Topic => "topics",
+        }
+    }
+
+    fn fts_table(&self) -> &'static str {
+        match self {
+            SearchTarget::Entity => "entities_fts",
+            SearchTarget::Relation => "relations_fts",
+            SearchTarget::Topic => "topics_fts",
+        }
+    }
+
+    pub(crate) fn all() -> [SearchTarget; 3] {
+        [SearchTarget::Entity, SearchTarget::Relation, SearchTarget::Topic]
+    }
+}
+
+/// ハイブリッド検索の結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HybridSearchResult {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub target: SearchTarget,
+    pub score: f64, // RRFスコア
+    #[serde(rename = "keywordRank")]
+    pub keyword_rank: Option<usize>,
+    #[serde(rename = "vectorRank")]
+    pub vector_rank: Option<usize>,
+    #[serde(rename = "bm25Score")]
+    pub bm25_score: Option<f64>,
+    pub similarity: Option<f32>,
+    #[serde(rename = "organizationId")]
+    pub organization_id: Option<String>,
+    #[serde(rename = "searchableText")]
+    pub searchable_text: Option<String>,
+}
+
+/**
+ * FTS5仮想テーブルと同期トリガーを作成（マイグレーション0007で使用）
+ * FTSのrowidは元テーブルのrowidと一致させる（0021でエンティティIDで対応付ける形式に移行する）
+ */
+pub fn init_fts_tables(conn: &Connection) -> SqlResult<()> {
+    for target in SearchTarget::all() {
+        let table = target.table();
+        let fts = target.fts_table();
+        conn.execute_batch(&format!(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS {fts} USING fts5(searchableText, tokenize = 'trigram');
+
+            CREATE TRIGGER IF NOT EXISTS {fts}_after_insert
+            AFTER INSERT ON {table}
+            BEGIN
+                INSERT INTO {fts}(rowid, searchableText) VALUES (NEW.rowid, COALESCE(NEW.searchableText, ''));
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS {fts}_after_update
+            AFTER UPDATE OF searchableText ON {table}
+            BEGIN
+                DELETE FROM {fts} WHERE rowid = OLD.rowid;
+                INSERT INTO {fts}(rowid, searchableText) VALUES (NEW.rowid, COALESCE(NEW.searchableText, ''));
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS {fts}_after_delete
+            AFTER DELETE ON {table}
+            BEGIN
+                DELETE FROM {fts} WHERE rowid = OLD.rowid;
+            END;
+
+            DELETE FROM {fts};
+            INSERT INTO {fts}(rowid, searchableText) SELECT rowid, COALESCE(searchableText, '') FROM {table};
+            "#
+        ))?;
+    }
+    Ok(())
+}
+
+/**
+ * FTS5仮想テーブルを元テーブルのIDで対応付ける形式に作り直す（マイグレーション0021で使用）
+ * rowidは VACUUM や行の置き換えで変わるため、UNINDEXED の id カラムに元テーブルのIDを持たせる
+ */
+pub fn migrate_fts_to_entity_ids(conn: &Connection) -> SqlResult<()> {
+    for target in SearchTarget::all() {
+        drop_fts_objects(conn, target)?;
+        create_fts_objects(conn, target)?;
+        fill_fts_table(conn, target)?;
+    }
+    Ok(())
+}
+
+/**
+ * FTS5仮想テーブルと同期トリガーの整合性を確認（起動時にマイグレーションの後で実行）
+ *
+ * - テーブルの再作成などでトリガーが消えている場合は作り直す
+ * - id カラムのない旧形式のFTSテーブルが残っている場合は作り直す
+ * - 件数のずれ、元テーブルにないID、searchableText の食い違い（FTS導入前のデータ、トリガーを通らない変更など）があれば再構築する
+ */
+pub fn ensure_search_index(conn: &Connection) -> SqlResult<()> {
+    for target in SearchTarget::all() {
+        let table = target.table();
+        let fts = target.fts_table();
+
+        let has_id_column: bool = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = 'id'",
+            params![fts],
+            |row| Ok(row.get::<_, i64>(0)? > 0),
+        )?;
+        if !has_id_column {
+            drop_fts_objects(conn, target)?;
+        }
+
+        let triggers_exist: bool = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'trigger' AND name IN (?1, ?2, ?3)",
+            params![
+                format!("{fts}_after_insert"),
+                format!("{fts}_after_update"),
+                format!("{fts}_after_delete"),
+            ],
+            |row| Ok(row.get::<_, i64>(0)? == 3),
+        )?;
+        if !triggers_exist {
+            create_fts_objects(conn, target)?;
+        }
+
+        let base_count: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| row.get(0))?;
+        let fts_count: i64 = conn.query_row(&format!("SELECT COUNT(*) FROM {fts}"), [], |row| row.get(0))?;
+        let misaligned_count = count_misaligned_rows(conn, target)?;
+        if !triggers_exist || base_count != fts_count || misaligned_count > 0 {
+            eprintln!(
+                "📝 {}を再構築します（{}件 → {}件、不整合な行: {}件）",
+                fts, fts_count, base_count, misaligned_count
+            );
+            rebuild_fts_table(conn, target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// FTSと元テーブルで (id, searchableText) が一致しない行数（どちらか一方にしかない行を数える）
+fn count_misaligned_rows(conn: &Connection, target: SearchTarget) -> SqlResult<i64> {
+    let table = target.table();
+    let fts = target.fts_table();
+    conn.query_row(
+        &format!(
+            "SELECT
+                (SELECT COUNT(*) FROM (
+                    SELECT id, searchableText FROM {fts}
+                    EXCEPT SELECT id, COALESCE(searchableText, '') FROM {table}
+                ))
+              + (SELECT COUNT(*) FROM (
+                    SELECT id, COALESCE(searchableText, '') FROM {table}
+                    EXCEPT SELECT id, searchableText FROM {fts}
+                ))"
+        ),
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// FTS5仮想テーブルと同期トリガーを作成（既にある場合は何もしない）
+fn create_fts_objects(conn: &Connection, target: SearchTarget) -> SqlResult<()> {
+    let table = target.table();
+    let fts = target.fts_table();
+    conn.execute_batch(&format!(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS {fts} USING fts5(id UNINDEXED, searchableText, tokenize = 'trigram');
+
+        CREATE TRIGGER IF NOT EXISTS {fts}_after_insert
+        AFTER INSERT ON {table}
+        BEGIN
+            INSERT INTO {fts}(id, searchableText) VALUES (NEW.id, COALESCE(NEW.searchableText, ''));
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS {fts}_after_update
+        AFTER UPDATE OF id, searchableText ON {table}
+        BEGIN
+            DELETE FROM {fts} WHERE id = OLD.id;
+            INSERT INTO {fts}(id, searchableText) VALUES (NEW.id, COALESCE(NEW.searchableText, ''));
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS {fts}_after_delete
+        AFTER DELETE ON {table}
+        BEGIN
+            DELETE FROM {fts} WHERE id = OLD.id;
+        END;
+        "#
+    ))
+}
+
+/// FTS5仮想テーブルと同期トリガーを削除
+fn drop_fts_objects(conn: &Connection, target: SearchTarget) -> SqlResult<()> {
+    let fts = target.fts_table();
+    conn.execute_batch(&format!(
+        r#"
+        DROP TRIGGER IF EXISTS {fts}_after_insert;
+        DROP TRIGGER IF EXISTS {fts}_after_update;
+        DROP TRIGGER IF EXISTS {fts}_after_delete;
+        DROP TABLE IF EXISTS {fts};
+        "#
+    ))
+}
+
+fn rebuild_fts_table(conn: &Connection, target: SearchTarget) -> SqlResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    fill_fts_table(&tx, target)?;
+    tx.commit()
+}
+
+/// FTSの内容を元テーブルから入れ直す（呼び出し側のトランザクション内で実行）
+fn fill_fts_table(conn: &Connection, target: SearchTarget) -> SqlResult<()> {
+    let table = target.table();
+    let fts = target.fts_table();
+    conn.execute(&format!("DELETE FROM {fts}"), [])?;
+    conn.execute(
+        &format!(
+            "INSERT INTO {fts}(id, searchableText) SELECT id, COALESCE(searchableText, '') FROM {table}"
+        ),
+        [],
+    )?;
+    Ok(())
+}
+
+/// 全文検索インデックスを再構築
+pub fn rebuild_search_index() -> SqlResult<()> {
+    let db = get_db().ok_or_else(|| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+            Some("データベースが初期化されていません".to_string()),
+        )
+    })?;
+    let conn = db.get_connection()?;
+
+    for target in SearchTarget::all() {
+        rebuild_fts_table(&conn, target)?;
+    }
+    Ok(())
+}
+
+/// FTS5のMATCH式を構築（各キーワードをフレーズとしてエスケープし、ORで結合）
+fn build_match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .filter(|t| t.chars().count() >= TRIGRAM_MIN_CHARS)
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" OR "))
+    }
+}
+
+/**
+ * キーワード検索（BM25）
+ * 戻り値: (ID, BM25スコア) のリスト（関連度の高い順）
+ * trigramで扱えない短いキーワードのみの場合はLIKE検索（スコアは0）
+ */
+pub fn keyword_search(
+    target: SearchTarget,
+    query: &str,
+    limit: usize,
+    organization_id: Option<&str>,
+) -> SqlResult<Vec<(String, f64)>> {
+    let db = get_db().ok_or_else(|| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+            Some("データベースが初期化されていません".to_string()),
+        )
+    })?;
+    let conn = db.get_connection()?;
+    let table = target.table();
+    let fts = target.fts_table();
+    let org_id = organization_id.filter(|id| !id.is_empty());
+
+    let mut results = Vec::new();
+    if let Some(match_expr) = build_match_expression(query) {
+        // bm25()は値が小さいほど関連度が高い（負の値）ため、符号を反転して返す
+        let mut stmt = conn.prepare(&format!(
+            "SELECT b.id, bm25(f) AS score
+             FROM {fts} AS f
+             JOIN {table} b ON b.id = f.id
+             WHERE f MATCH ?1
+               AND b.deletedAt IS NULL
+               AND (?2 IS NULL OR b.organizationId = ?2 OR b.companyId = ?2)
+             ORDER BY score
+             LIMIT ?3"
+        ))?;
+        let rows = stmt.query_map(params![match_expr, org_id, limit as i64], |row| {
+            Ok((row.get::<_, String>(0)?, -row.get::<_, f64>(1)?))
+        })?;
+        for row in rows {
+            results.push(row?);
+        }
+    } else {
+        let keyword = query.trim();
+        if keyword.is_empty() {
+            return Ok(results);
+        }
+        let pattern = format!("%{}%", keyword.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id FROM {table}
+             WHERE searchableText LIKE ?1 ESCAPE '\\'
+               AND deletedAt IS NULL
+               AND (?2 IS NULL OR organizationId = ?2 OR companyId = ?2)
+             ORDER BY searchCount DESC
+             LIMIT ?3"
+        ))?;
+        let rows = stmt.query_map(params![pattern, org_id, limit as i64], |row| {
+            Ok((row.get::<_, String>(0)?, 0.0))
+        })?;
+        for row in rows {
+            results.push(row?);
+        }
+    }
+
+    Ok(results)
+}
+
+/// ヒットした行の検索回数と最終検索日時を更新（書き込みワーカーから呼び出し側のトランザクション内で実行）
+pub(crate) fn record_search_hits_in(conn: &Connection, target: SearchTarget, ids: &[String]) -> SqlResult<()> {
+    let now = get_timestamp();
+    let mut stmt = conn.prepare(&format!(
+        "UPDATE {} SET searchCount = COALESCE(searchCount, 0) + 1, lastSearchDate = ?1 WHERE id = ?2",
+        target.table()
+    ))?;
+    for id in ids {
+        stmt.execute(params![now, id])?;
+    }
+    Ok(())
+}
+
+/// 検索結果の補足情報（organizationId, searchableText）を取得（ゴミ箱にある行は含めない）
+fn load_result_details(
+    target: SearchTarget,
+    ids: &[String],
+) -> SqlResult<HashMap<String, (Option<String>, Option<String>)>> {
+    let mut details = HashMap::new();
+    if ids.is_empty() {
+        return Ok(details);
+    }
+    let db = get_db().ok_or_else(|| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+            Some("データベースが初期化されていません".to_string()),
+        )
+    })?;
+    let conn = db.get_connection()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT COALESCE(organizationId, companyId), searchableText FROM {} WHERE id = ?1 AND deletedAt IS NULL",
+        target.table()
+    ))?;
+    for id in ids {
+        match stmt.query_row(params![id], |row| {
+            Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?))
+        }) {
+            Ok(detail) => {
+                details.insert(id.clone(), detail);
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(details)
+}
+
+/**
+ * キーワード検索とベクトル検索の結果をRRFで統合し、スコアの高い順に上位limit件を返す
+ * 各リストは関連度の高い順（順位は1始まり）
+ */
+fn fuse_rrf(
+    target: SearchTarget,
+    keyword_hits: Vec<(String, f64)>,
+    vector_hits: Vec<(String, f32)>,
+    limit: usize,
+) -> Vec<HybridSearchResult> {
+    let mut fused: HashMap<String, HybridSearchResult> = HashMap::new();
+    for (rank, (id, bm25)) in keyword_hits.into_iter().enumerate() {
+        let entry = fused.entry(id.clone()).or_insert_with(|| HybridSearchResult {
+            id,
+            target,
+            score: 0.0,
+            keyword_rank: None,
+            vector_rank: None,
+            bm25_score: None,
+            similarity: None,
+            organization_id: None,
+            searchable_text: None,
+        });
+        entry.score += 1.0 / (RRF_K + (rank + 1) as f64);
+        entry.keyword_rank = Some(rank + 1);
+        entry.bm25_score = Some(bm25);
+    }
+    for (rank, (id, similarity)) in vector_hits.into_iter().enumerate() {
+        let entry = fused.entry(id.clone()).or_insert_with(|| HybridSearchResult {
+            id,
+            target,
+            score: 0.0,
+            keyword_rank: None,
+            vector_rank: None,
+            bm25_score: None,
+            similarity: None,
+            organization_id: None,
+            searchable_text: None,
+        });
+        entry.score += 1.0 / (RRF_K + (rank + 1) as f64);
+        entry.vector_rank = Some(rank + 1);
+        entry.similarity = Some(similarity);
+    }
+
+    let mut results: Vec<HybridSearchResult> = fused.into_values().collect();
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    results.truncate(limit);
+    results
+}
+
+/**
+ * ハイブリッド検索（BM25 + ベクトル類似度をRRFで統合）
+ *
+ * @param query_text 検索キーワード
+ * @param query_embedding クエリの埋め込み（Noneの場合はキーワード検索のみ）
+ */
+pub async fn hybrid_search(
+    target: SearchTarget,
+    query_text: String,
+    query_embedding: Option<Vec<f32>>,
+    limit: usize,
+    organization_id: Option<String>,
+) -> Result<Vec<HybridSearchResult>, String> {
+    // 各手法で多めに候補を取得してから統合する
+    let candidate_limit = (limit * 3).max(20);
+
+    let keyword_hits = keyword_search(target, &query_text, candidate_limit, organization_id.as_deref())
+        .map_err(|e| format!("キーワード検索に失敗しました: {}", e))?;
+
+    // 埋め込みが渡されなかった場合はRust側で生成する（失敗時はキーワード検索のみ）
+    let query_embedding = match query_embedding {
+        Some(embedding) => Some(embedding),
+        None => match generate_embedding(&query_text).await {
+            Ok(embedding) => Some(embedding),
+            Err(e) => {
+                eprintln!("[hybrid_search] ⚠️ クエリの埋め込み生成に失敗しました（キーワード検索のみで続行）: {}", e);
+                None
+            }
+        },
+    };
+
+    let vector_hits: Vec<(String, f32)> = match query_embedding {
+        Some(embedding) if !embedding.is_empty() => {
+            let store = get_vector_store()?;
+            let result = match target {
+                SearchTarget::Entity => {
+                    store.find_similar_entities(embedding, candidate_limit, organization_id.clone()).await
+                }
+                SearchTarget::Relation => {
+                    store.find_similar_relations(embedding, candidate_limit, organization_id.clone()).await
+                }
+                SearchTarget::Topic => store
+                    .find_similar_topics(embedding, candidate_limit, organization_id.clone())
+                    .await
+                    .map(|topics| topics.into_iter().map(|t| (t.topic_id, t.similarity)).collect()),
+            };
+            match result {
+                Ok(hits) => hits,
+                Err(e) => {
+                    // ベクトル検索が使えない場合もキーワード検索の結果は返す
+                    eprintln!("[hybrid_search] ⚠️ ベクトル検索に失敗しました（キーワード検索のみで続行）: {}", e);
+                    Vec::new()
+                }
+            }
+        }
+        _ => Vec::new(),
+    };
+
+    // RRFでスコアを統合
+    let mut results = fuse_rrf(target, keyword_hits, vector_hits, limit);
+
+    let ids: Vec<String> = results.iter().map(|r| r.id.clone()).collect();
+
+    // 補足情報を付与（SQLiteに存在しない孤立した埋め込み・ゴミ箱にある行は除外）
+    let details = load_result_details(target, &ids)
+        .map_err(|e| format!("検索結果の取得に失敗しました: {}", e))?;
+    results.retain(|r| details.contains_key(&r.id));
+    for result in results.iter_mut() {
+        if let Some((org_id, searchable_text)) = details.get(&result.id) {
+            result.organization_id = org_id.clone();
+            result.searchable_text = searchable_text.clone();
+        }
+    }
+
+    // 検索回数の更新は書き込みキューに任せる（結果は待たない）
+    let hit_ids: Vec<String> = results.iter().map(|r| r.id.clone()).collect();
+    if !hit_ids.is_empty() {
+        let job = WriteJob::RecordSearchHits { target, ids: hit_ids };
+        let enqueued = match write_queue() {
+            Ok(queue) => queue.enqueue(job, get_current_user()).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = enqueued {
+            eprintln!("[hybrid_search] ⚠️ 検索回数の更新に失敗しました: {}", e);
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::init_test_database;
+    use crate::database::migrations::run_migrations;
+    use rusqlite::OptionalExtension;
+    use uuid::Uuid;
+
+    fn migrated() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn insert_entity(conn: &Connection, id: &str, name: &str) {
+        let org_id = format!("org-{}", id);
+        conn.execute(
+            "INSERT INTO organizations (id, name, level, levelName, createdAt, updatedAt)
+             VALUES (?1, '本部', 1, '本部', '2024-01-01T00:00:00.000Z', '2024-01-01T00:00:00.000Z')",
+            params![org_id],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO entities (id, name, type, organizationId, createdAt, updatedAt)
+             VALUES (?1, ?2, 'product', ?3, '2024-01-01T00:00:00.000Z', '2024-01-01T00:00:00.000Z')",
+            params![id, name, org_id],
+        ).unwrap();
+    }
+
+    fn fts_text(conn: &Connection, id: &str) -> Option<String> {
+        conn.query_row("SELECT searchableText FROM entities_fts WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .unwrap()
+    }
+
+    fn base_text(conn: &Connection, id: &str) -> String {
+        conn.query_row(
+            "SELECT COALESCE(searchableText, '') FROM entities WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        ).unwrap()
+    }
+
+    #[test]
+    fn triggers_keep_fts_rows_keyed_by_entity_id() {
+        let conn = migrated();
+        insert_entity(&conn, "e1", "製品アルファ");
+        assert_eq!(fts_text(&conn, "e1"), Some(base_text(&conn, "e1")));
+
+        conn.execute("UPDATE entities SET name = '製品ベータ' WHERE id = 'e1'", []).unwrap();
+        assert_eq!(fts_text(&conn, "e1"), Some(base_text(&conn, "e1")));
+        assert_eq!(count_misaligned_rows(&conn, SearchTarget::Entity).unwrap(), 0);
+
+        // rowid が変わってもIDで対応付けられる
+        conn.execute_batch("VACUUM").unwrap();
+        conn.execute("DELETE FROM entities WHERE id = 'e1'", []).unwrap();
+        assert_eq!(fts_text(&conn, "e1"), None);
+        let fts_rows: i64 = conn.query_row("SELECT COUNT(*) FROM entities_fts", [], |row| row.get(0)).unwrap();
+        assert_eq!(fts_rows, 0);
+    }
+
+    #[test]
+    fn ensure_search_index_rebuilds_misaligned_rows() {
+        let conn = migrated();
+        insert_entity(&conn, "e1", "製品アルファ");
+        insert_entity(&conn, "e2", "製品ベータ");
+
+        // 件数は同じでも内容やIDが食い違っている
+        conn.execute("UPDATE entities_fts SET searchableText = '古い内容' WHERE id = 'e1'", []).unwrap();
+        conn.execute("UPDATE entities_fts SET id = 'ghost' WHERE id = 'e2'", []).unwrap();
+        assert_eq!(count_misaligned_rows(&conn, SearchTarget::Entity).unwrap(), 4);
+
+        ensure_search_index(&conn).unwrap();
+        assert_eq!(count_misaligned_rows(&conn, SearchTarget::Entity).unwrap(), 0);
+        assert_eq!(fts_text(&conn, "e1"), Some(base_text(&conn, "e1")));
+        assert_eq!(fts_text(&conn, "e2"), Some(base_text(&conn, "e2")));
+        assert_eq!(fts_text(&conn, "ghost"), None);
+    }
+
+    #[test]
+    fn ensure_search_index_replaces_the_rowid_layout() {
+        let conn = migrated();
+        insert_entity(&conn, "e1", "製品アルファ");
+        for target in SearchTarget::all() {
+            drop_fts_objects(&conn, target).unwrap();
+        }
+        init_fts_tables(&conn).unwrap();
+
+        ensure_search_index(&conn).unwrap();
+        let has_id_column: bool = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('entities_fts') WHERE name = 'id'",
+            [],
+            |row| Ok(row.get::<_, i64>(0)? > 0),
+        ).unwrap();
+        assert!(has_id_column);
+        assert_eq!(fts_text(&conn, "e1"), Some(base_text(&conn, "e1")));
+    }
+
+    #[test]
+    fn keyword_search_joins_fts_rows_on_entity_ids() {
+        let conn = init_test_database().get_connection().unwrap();
+        let id = Uuid::new_v4().to_string();
+        let keyword = format!("製品{}", Uuid::new_v4().simple());
+        insert_entity(&conn, &id, &keyword);
+
+        let hits = keyword_search(SearchTarget::Entity, &keyword, 10, None).unwrap();
+        assert_eq!(hits.iter().map(|(hit, _)| hit.as_str()).collect::<Vec<_>>(), vec![id.as_str()]);
+
+        conn.execute("UPDATE entities SET deletedAt = ?1 WHERE id = ?2", params![get_timestamp(), id]).unwrap();
+        assert!(keyword_search(SearchTarget::Entity, &keyword, 10, None).unwrap().is_empty());
+    }
+
+    fn hits<T: Copy>(ids: &[&str], score: T) -> Vec<(String, T)> {
+        ids.iter().map(|id| (id.to_string(), score)).collect()
+    }
+
+    #[test]
+    fn fuse_rrf_ranks_documents_found_by_both_methods_first() {
+        let keyword_hits = hits(&["a", "b", "c"], 1.5);
+        let vector_hits = hits(&["c"], 0.8_f32);
+
+        let results = fuse_rrf(SearchTarget::Entity, keyword_hits, vector_hits, 10);
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a", "b"]);
+
+        let c = &results[0];
+        assert_eq!(c.keyword_rank, Some(3));
+        assert_eq!(c.vector_rank, Some(1));
+        assert_eq!(c.bm25_score, Some(1.5));
+        assert_eq!(c.similarity, Some(0.8));
+        assert!((c.score - (1.0 / (RRF_K + 3.0) + 1.0 / (RRF_K + 1.0))).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fuse_rrf_keeps_vector_only_hits() {
+        let results = fuse_rrf(SearchTarget::Relation, Vec::new(), hits(&["d", "e"], 0.5_f32), 10);
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["d", "e"]);
+        assert_eq!(results[0].keyword_rank, None);
+        assert_eq!(results[0].bm25_score, None);
+        assert_eq!(results[1].vector_rank, Some(2));
+        assert_eq!(results[1].target, SearchTarget::Relation);
+    }
+
+    #[test]
+    fn fuse_rrf_truncates_to_limit() {
+        let results = fuse_rrf(SearchTarget::Topic, hits(&["a", "b", "c"], 0.0), Vec::new(), 2);
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert!(fuse_rrf(SearchTarget::Topic, Vec::new(), Vec::new(), 5).is_empty());
+    }
+
+    #[test]
+    fn build_match_expression_quotes_terms_and_skips_short_ones() {
+        assert_eq!(
+            build_match_expression("製品名 ab say\"hi\""),
+            Some("\"製品名\" OR \"say\"\"hi\"\"\"".to_string())
+        );
+        assert_eq!(build_match_expression("ab c"), None);
+    }
+}
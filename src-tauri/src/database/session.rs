@@ -0,0 +1,290 @@
+/**
+ * セッション管理
+ * サインイン時にセッショントークンを発行し、sessions テーブルに有効期限付きで保存する
+ *
+ * - トークンそのものは保存せず、SHA-256ハッシュのみを保存する
+ * - Tauri側は SessionState（アプリの状態）でトークンを保持する
+ * - REST API側は `Authorization: Bearer <token>` ヘッダーでトークンを受け取る
+ * - リクエスト処理中のユーザーは with_user で設定し、get_current_user で参照する（タスクローカル）
+ */
+
+use crate::database::{authorize, get_db, Permission, Timestamp, User};
+use rusqlite::{params, OptionalExtension, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// デフォルトのセッション有効期間（秒）: 7日
+const DEFAULT_SESSION_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// SESSION_TTL_HOURS で指定できる有効期間の上限（秒）: 1年
+const MAX_SESSION_TTL_SECS: i64 = 365 * 24 * 60 * 60;
+
+/// lastUsedAt を更新する間隔（秒）。検証のたびに書き込まないようにする
+const LAST_USED_UPDATE_INTERVAL_SECS: i64 = 60;
+
+tokio::task_local! {
+    static REQUEST_USER: User;
+}
+
+/// 発行したセッション（トークンは発行時にのみ返す）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub token: String,
+    pub user: User,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Timestamp,
+}
+
+/// Tauri側のセッション状態（サインイン中のトークン）
+#[derive(Default)]
+pub struct SessionState {
+    token: Mutex<Option<String>>,
+}
+
+impl SessionState {
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.lock().unwrap_or_else(|e| e.into_inner()) = token;
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 保持しているトークンからユーザーを取得（期限切れ・失効済みの場合は None）
+    pub fn current_user(&self) -> Option<User> {
+        let token = self.token()?;
+        match validate_session(&token) {
+            Ok(user) => user,
+            Err(e) => {
+                eprintln!("⚠️ セッションの検証に失敗しました: {}", e);
+                None
+            }
+        }
+    }
+
+    /// サインイン中のユーザーを取得（サインインしていない場合はエラー）
+    pub fn require_user(&self) -> Result<User, String> {
+        self.current_user()
+            .ok_or_else(|| "ユーザーがログインしていません".to_string())
+    }
+
+    /// サインイン中のユーザーを取得し、permission の操作が許可されているか確認
+    pub fn require_permission(&self, permission: Permission) -> Result<User, String> {
+        let user = self.require_user()?;
+        authorize(&user, permission)?;
+        Ok(user)
+    }
+}
+
+/// セッションの有効期間（環境変数 SESSION_TTL_HOURS で変更可能）
+fn session_ttl_secs() -> i64 {
+    parse_session_ttl(std::env::var("SESSION_TTL_HOURS").ok().as_deref())
+}
+
+/// SESSION_TTL_HOURS の値を秒に変換（不正な値・オーバーフローする値はデフォルト、上限を超える値は上限にする）
+fn parse_session_ttl(hours: Option<&str>) -> i64 {
+    hours
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .filter(|h| *h > 0)
+        .and_then(|h| h.checked_mul(60 * 60))
+        .map(|secs| secs.min(MAX_SESSION_TTL_SECS))
+        .unwrap_or(DEFAULT_SESSION_TTL_SECS)
+}
+
+/// トークンのハッシュを計算（SHA-256）
+pub(crate) fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// セッションを作成してトークンを発行
+pub fn create_session(user: &User) -> SqlResult<Session> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let now = Timestamp::now();
+    let expires_at = now.add_seconds(session_ttl_secs());
+
+    conn.execute(
+        "INSERT INTO sessions (id, tokenHash, userId, createdAt, lastUsedAt, expiresAt)
+         VALUES (?1, ?2, ?3, ?4, ?4, ?5)",
+        params![Uuid::new_v4().to_string(), hash_token(&token), user.uid, now, expires_at],
+    )?;
+
+    // 期限切れのセッションを掃除
+    conn.execute("DELETE FROM sessions WHERE expiresAt <= ?1", params![now])?;
+
+    Ok(Session {
+        token,
+        user: user.clone(),
+        expires_at,
+    })
+}
+
+/// トークンを検証してユーザーを取得（期限切れ・失効済みの場合は None）
+pub fn validate_session(token: &str) -> SqlResult<Option<User>> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    let now = Timestamp::now();
+    let token_hash = hash_token(token);
+
+    let found = conn.query_row(
+        "SELECT u.id, u.email, s.lastUsedAt FROM sessions s
+         JOIN users u ON u.id = s.userId
+         WHERE s.tokenHash = ?1 AND s.expiresAt > ?2 AND u.approved = 1",
+        params![token_hash, now],
+        |row| Ok((
+            User {
+                uid: row.get(0)?,
+                email: row.get(1)?,
+                email_verified: true,
+            },
+            row.get::<_, Timestamp>(2).ok(),
+        )),
+    ).optional()?;
+
+    let Some((user, last_used_at)) = found else {
+        return Ok(None);
+    };
+
+    // lastUsedAt は前回の更新から一定時間が経過した場合のみ更新する
+    let stale = last_used_at
+        .map_or(true, |t| t <= now.sub_seconds(LAST_USED_UPDATE_INTERVAL_SECS));
+    if stale {
+        conn.execute(
+            "UPDATE sessions SET lastUsedAt = ?1 WHERE tokenHash = ?2",
+            params![now, token_hash],
+        )?;
+    }
+
+    Ok(Some(user))
+}
+
+/// セッションを失効
+pub fn revoke_session(token: &str) -> SqlResult<()> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    conn.execute("DELETE FROM sessions WHERE tokenHash = ?1", params![hash_token(token)])?;
+    Ok(())
+}
+
+/// ユーザーの全セッションを失効
+pub fn revoke_user_sessions(user_id: &str) -> SqlResult<usize> {
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string())
+    ))?;
+    let conn = db.get_connection()?;
+    conn.execute("DELETE FROM sessions WHERE userId = ?1", params![user_id])
+}
+
+/// リクエスト処理中のユーザーを設定して処理を実行
+pub async fn with_user<F: Future>(user: User, f: F) -> F::Output {
+    REQUEST_USER.scope(user, f).await
+}
+
+/// ユーザーを設定して同期処理を実行（Tauriコマンドから監査対象の関数を呼び出す場合に使用）
+pub fn with_user_sync<R>(user: User, f: impl FnOnce() -> R) -> R {
+    REQUEST_USER.sync_scope(user, f)
+}
+
+/// リクエスト処理中のユーザーを取得（with_user の外では None）
+pub fn get_current_user() -> Option<User> {
+    REQUEST_USER.try_with(|user| user.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{init_test_database, insert_test_user, Role};
+
+    fn set_session_column(token: &str, column: &str, value: &Timestamp) {
+        let conn = init_test_database().get_connection().unwrap();
+        conn.execute(
+            &format!("UPDATE sessions SET {} = ?1 WHERE tokenHash = ?2", column),
+            params![value, hash_token(token)],
+        ).unwrap();
+    }
+
+    fn last_used_at(token: &str) -> Timestamp {
+        let conn = init_test_database().get_connection().unwrap();
+        conn.query_row(
+            "SELECT lastUsedAt FROM sessions WHERE tokenHash = ?1",
+            [hash_token(token)],
+            |row| row.get(0),
+        ).unwrap()
+    }
+
+    #[test]
+    fn parses_the_session_ttl() {
+        assert_eq!(parse_session_ttl(None), DEFAULT_SESSION_TTL_SECS);
+        assert_eq!(parse_session_ttl(Some("12")), 12 * 60 * 60);
+        assert_eq!(parse_session_ttl(Some("0")), DEFAULT_SESSION_TTL_SECS);
+        assert_eq!(parse_session_ttl(Some("-1")), DEFAULT_SESSION_TTL_SECS);
+        assert_eq!(parse_session_ttl(Some("abc")), DEFAULT_SESSION_TTL_SECS);
+        assert_eq!(parse_session_ttl(Some("1000000")), MAX_SESSION_TTL_SECS);
+        // 秒に変換するとオーバーフローする値
+        assert_eq!(parse_session_ttl(Some(&i64::MAX.to_string())), DEFAULT_SESSION_TTL_SECS);
+    }
+
+    #[test]
+    fn validates_sessions_until_they_expire() {
+        let user = insert_test_user(Role::Viewer, true);
+        let session = create_session(&user).unwrap();
+        assert!(session.expires_at > Timestamp::now());
+        assert_eq!(validate_session(&session.token).unwrap().unwrap().uid, user.uid);
+
+        set_session_column(&session.token, "expiresAt", &Timestamp::now().sub_seconds(1));
+        assert!(validate_session(&session.token).unwrap().is_none());
+        assert!(validate_session("unknown-token").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_sessions_of_revoked_or_unapproved_users() {
+        let user = insert_test_user(Role::Editor, true);
+        let first = create_session(&user).unwrap();
+        let second = create_session(&user).unwrap();
+
+        revoke_session(&first.token).unwrap();
+        assert!(validate_session(&first.token).unwrap().is_none());
+        assert!(validate_session(&second.token).unwrap().is_some());
+
+        let conn = init_test_database().get_connection().unwrap();
+        conn.execute("UPDATE users SET approved = 0 WHERE id = ?1", [&user.uid]).unwrap();
+        assert!(validate_session(&second.token).unwrap().is_none());
+
+        conn.execute("UPDATE users SET approved = 1 WHERE id = ?1", [&user.uid]).unwrap();
+        assert_eq!(revoke_user_sessions(&user.uid).unwrap(), 1);
+        assert!(validate_session(&second.token).unwrap().is_none());
+    }
+
+    #[test]
+    fn throttles_last_used_at_updates() {
+        let user = insert_test_user(Role::Viewer, true);
+        let session = create_session(&user).unwrap();
+
+        let recent = Timestamp::now().sub_seconds(LAST_USED_UPDATE_INTERVAL_SECS / 2);
+        set_session_column(&session.token, "lastUsedAt", &recent);
+        validate_session(&session.token).unwrap();
+        assert_eq!(last_used_at(&session.token), recent);
+
+        let stale = Timestamp::now().sub_seconds(LAST_USED_UPDATE_INTERVAL_SECS * 2);
+        set_session_column(&session.token, "lastUsedAt", &stale);
+        validate_session(&session.token).unwrap();
+        assert!(last_used_at(&session.token) > stale);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::database::{get_db, get_timestamp, to_firestore_timestamp, get_current_user};
-use rusqlite::Result as SqlResult;
+use crate::database::{get_db, get_timestamp, get_current_user, table_schema, snapshot_row, audit_change, AuditAction,
+                      is_soft_delete_table, trash_item, trash_meeting_note};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, Result as SqlResult};
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -25,8 +27,20 @@ const ALLOWED_TABLES: &[&str] = &[
     "designDocSectionRelations", // システム設計ドキュメントセクション関係
 ];
 
+// 汎用ストアからは読み取りのみ許可するテーブル
+// users.role / approved は権限の判定に使うため、ロールと承認は rbac.rs の関数でのみ変更する
+const READ_ONLY_TABLES: &[&str] = &[
+    "users",
+    "approvalRequests",
+];
+
+// 汎用ストアから返さない・条件に使えないカラム
+const HIDDEN_COLUMNS: &[(&str, &str)] = &[
+    ("users", "passwordHash"),
+];
+
 // テーブル名の検証関数
-fn validate_table_name(table_name: &str) -> SqlResult<()> {
+pub(super) fn validate_table_name(table_name: &str) -> SqlResult<()> {
     if ALLOWED_TABLES.contains(&table_name) {
         Ok(())
     } else {
@@ -37,6 +51,38 @@ fn validate_table_name(table_name: &str) -> SqlResult<()> {
     }
 }
 
+// 書き込み先のテーブル名の検証関数（読み取り専用のテーブルは拒否する）
+fn validate_writable_table_name(table_name: &str) -> SqlResult<()> {
+    validate_table_name(table_name)?;
+    if READ_ONLY_TABLES.contains(&table_name) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_PERM),
+            Some(format!("テーブル '{}' は汎用ストアから変更できません", table_name))
+        ));
+    }
+    Ok(())
+}
+
+// 返さないカラムを条件・並び順に使っていないことを確認
+pub(super) fn validate_visible_column(table_name: &str, column: &str) -> SqlResult<()> {
+    if HIDDEN_COLUMNS.contains(&(table_name, column)) {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_PERM),
+            Some(format!("カラム '{}.{}' は使用できません", table_name, column))
+        ));
+    }
+    Ok(())
+}
+
+// 読み取った行から返さないカラムを除く
+pub(super) fn strip_hidden_columns(table_name: &str, row: &mut HashMap<String, Value>) {
+    for (table, column) in HIDDEN_COLUMNS {
+        if *table == table_name {
+            row.remove(*column);
+        }
+    }
+}
+
 pub fn get_doc(collection_name: &str, doc_id: &str) -> SqlResult<HashMap<String, Value>> {
     // テーブル名の検証（SQLインジェクション対策）
     validate_table_name(collection_name)?;
@@ -45,122 +91,29 @@ pub fn get_doc(collection_name: &str, doc_id: &str) -> SqlResult<HashMap<String,
         Some("データベースが初期化されていません".to_string())
     ))?;
     let conn = db.get_connection()?;
-    
-    let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE id = ?1", collection_name))?;
-    let mut row = stmt.query_row([doc_id], |row| {
-        let mut map = HashMap::new();
-        for i in 0..row.as_ref().column_count() {
-            let col_name = row.as_ref().column_name(i)
-                .map_err(|_| rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                    Some(format!("カラム名の取得に失敗しました: インデックス {}", i))
-                ))?;
-            let value: Value = match row.get::<_, Option<String>>(i) {
-                Ok(Some(s)) => {
-                    // 空文字列の場合はNullとして扱う
-                    if s.is_empty() {
-                        Value::Null
-                    } else {
-                        // JSONフィールドをパース
-                        if col_name.contains("Embedding") || 
-                           col_name == "pagesBySubMenu" || 
-                           col_name == "pageOrderBySubMenu" ||
-                           col_name == "visibleSubMenuIds" ||
-                           col_name == "customSubMenuLabels" ||
-                           col_name == "contentStructure" ||
-                           col_name == "formatPattern" ||
-                           col_name == "pageRelations" ||
-                           col_name == "linkedPlanIds" ||
-                           col_name == "initiativeIds" {
-                            // JSON文字列をパース、失敗した場合は空のオブジェクト/配列を返す
-                            match serde_json::from_str::<Value>(&s) {
-                                Ok(v) => v,
-                                Err(_) => {
-                                    eprintln!("⚠️ [get_doc] JSONパースエラー: field={}, value={}", col_name, s.chars().take(100).collect::<String>());
-                                    json!([])
-                                }
-                            }
-                        } else {
-                            json!(s)
-                        }
-                    }
-                }
-                Ok(None) => Value::Null,
-                Err(_) => {
-                    // 数値やNULLの処理
-                    match row.get::<_, Option<i64>>(i) {
-                        Ok(Some(v)) => json!(v),
-                        Ok(None) => Value::Null,
-                        Err(_) => Value::Null,
-                    }
-                }
-            };
-            map.insert(col_name.to_string(), value);
-        }
-        Ok(map)
-    })?;
-    
-    // タイムスタンプを変換（文字列または数値の両方に対応）
-    if let Some(created_at_value) = row.get("createdAt") {
-        if let Some(timestamp_str) = created_at_value.as_str().map(|s| s.to_string())
-            .or_else(|| created_at_value.as_i64().map(|n| n.to_string())) {
-            row.insert("createdAt".to_string(), json!(to_firestore_timestamp(&timestamp_str)));
-        }
-    }
-    if let Some(updated_at_value) = row.get("updatedAt") {
-        if let Some(timestamp_str) = updated_at_value.as_str().map(|s| s.to_string())
-            .or_else(|| updated_at_value.as_i64().map(|n| n.to_string())) {
-            row.insert("updatedAt".to_string(), json!(to_firestore_timestamp(&timestamp_str)));
-        }
-    }
-    
-    Ok(row)
+    let schema = table_schema(&conn, collection_name)?;
+    
+    // ゴミ箱にある行は存在しないものとして扱う
+    let live_only = if is_soft_delete_table(collection_name) { " AND deletedAt IS NULL" } else { "" };
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE id = ?1{}", collection_name, live_only))?;
+    let mut doc = stmt.query_row([doc_id], |row| schema.row_to_map(row))?;
+    strip_hidden_columns(collection_name, &mut doc);
+    Ok(doc)
 }
 
-// 値をSQLite用の文字列に変換するヘルパー関数
-// Value::Nullの場合はNoneを返し、それ以外の場合はSome(String)を返す
-fn value_to_sql_string(value: &Value, is_json_field: bool) -> Option<String> {
-    if is_json_field {
-        // JSONフィールドの場合は、既に文字列の場合はそのまま、そうでなければJSON文字列化
-        if let Some(s) = value.as_str() {
-            // 既に文字列の場合は、それがJSON文字列かどうかをチェック
-            // 有効なJSON文字列の場合はそのまま使用
-            if serde_json::from_str::<Value>(s).is_ok() {
-                return Some(s.to_string());
-            }
-        }
-        // JSONオブジェクトや配列の場合は文字列化
-        Some(serde_json::to_string(value).unwrap_or_default())
-    } else {
-        match value {
-            Value::String(s) => Some(s.clone()),
-            Value::Number(n) => Some(n.to_string()),
-            Value::Bool(b) => Some(b.to_string()),
-            Value::Null => None, // NULL値の場合はNoneを返す
-            Value::Array(_) | Value::Object(_) => Some(serde_json::to_string(value).unwrap_or_default()),
-        }
-    }
-}
+/// 古い外部キー制約が残っている可能性があるため、書き込み時に外部キー制約を無効化するテーブル
+/// （PRAGMA foreign_keys はトランザクション内では変更できないため、トランザクションの開始前に切り替える）
+const RELAXED_FOREIGN_KEY_TABLES: &[&str] = &["meetingNotes", "focusInitiatives", "topics", "entities", "relations"];
 
-// テーブルのカラム情報を取得するヘルパー関数
-fn get_table_columns(conn: &rusqlite::Connection, table_name: &str) -> SqlResult<Vec<String>> {
-    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
-    let rows = stmt.query_map([], |row| {
-        Ok(row.get::<_, String>(1)?) // カラム名は2番目のカラム（インデックス1）
-    })?;
-    
-    let mut columns = Vec::new();
-    for row in rows {
-        columns.push(row?);
-    }
-    Ok(columns)
+pub(crate) fn relaxes_foreign_keys(collection_name: &str) -> bool {
+    RELAXED_FOREIGN_KEY_TABLES.contains(&collection_name)
 }
 
 pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>) -> SqlResult<()> {
     eprintln!("🔍 [set_doc] 開始: collection_name={}, doc_id={}", collection_name, doc_id);
     
     // テーブル名の検証（SQLインジェクション対策）
-    validate_table_name(collection_name)?;
+    validate_writable_table_name(collection_name)?;
     
     let db = get_db().ok_or_else(|| {
         eprintln!("❌ [set_doc] データベースが初期化されていません");
@@ -172,26 +125,44 @@ pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>
     
     let conn = db.get_connection()?;
     
-    // テーブルが存在するか確認
-    let table_exists: bool = conn.query_row(
-        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=?1",
-        [collection_name],
-        |row| row.get(0),
-    ).unwrap_or(false);
+    // meetingNotes、focusInitiatives、topics、entities、relationsテーブルの場合、外部キー制約を一時的に無効化
+    let relax_foreign_keys = relaxes_foreign_keys(collection_name);
+    if relax_foreign_keys {
+        if let Err(e) = conn.execute("PRAGMA foreign_keys = OFF", []) {
+            eprintln!("⚠️ [set_doc] 外部キー制約の無効化に失敗しました（続行します）: {}", e);
+        } else {
+            eprintln!("✅ [set_doc] 外部キー制約を無効化しました（{}用）", collection_name);
+        }
+    }
     
-    if !table_exists {
-        eprintln!("❌ [set_doc] テーブル '{}' が存在しません", collection_name);
-        return Err(rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-            Some(format!("テーブル '{}' が存在しません", collection_name))
-        ));
+    // トランザクションを開始（データベースロックを最小化）
+    let result = conn.unchecked_transaction().and_then(|tx| {
+        set_doc_in(&tx, collection_name, doc_id, data)?;
+        tx.commit()
+    });
+    
+    // 外部キー制約を再度有効化（エラー時も）
+    if relax_foreign_keys {
+        if let Err(e) = conn.execute("PRAGMA foreign_keys = ON", []) {
+            eprintln!("⚠️ [set_doc] 外部キー制約の再有効化に失敗しました（続行します）: {}", e);
+        } else {
+            eprintln!("✅ [set_doc] 外部キー制約を再有効化しました（{}用）", collection_name);
+        }
     }
     
-    // テーブルのカラム一覧を取得
-    let table_columns = match get_table_columns(&conn, collection_name) {
-        Ok(cols) => {
-            eprintln!("✅ [set_doc] テーブル '{}' のカラム数: {}", collection_name, cols.len());
-            cols
+    result
+}
+
+/// ドキュメントを呼び出し側のトランザクション内で保存（書き込みワーカー用）
+/// 外部キー制約の切り替えは呼び出し側で行う（relaxes_foreign_keys）
+pub(crate) fn set_doc_in(conn: &Connection, collection_name: &str, doc_id: &str, data: HashMap<String, Value>) -> SqlResult<()> {
+    validate_writable_table_name(collection_name)?;
+    
+    // テーブルのカラム型を取得（テーブルが存在しない場合はエラー）
+    let schema = match table_schema(conn, collection_name) {
+        Ok(schema) => {
+            eprintln!("✅ [set_doc] テーブル '{}' のカラム数: {}", collection_name, schema.columns.len());
+            schema
         },
         Err(e) => {
             eprintln!("❌ [set_doc] カラム情報の取得に失敗: {}", e);
@@ -200,7 +171,7 @@ pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>
     };
     
     let now = get_timestamp();
-    let mut row_data = data.clone();
+    let mut row_data = data;
     
     // userIdが必須のテーブルで、userIdが提供されていない場合は現在のユーザーIDを設定
     let user_id_required_tables: Vec<&str> = vec![];
@@ -230,46 +201,10 @@ pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>
         row_data.insert("updatedAt".to_string(), json!(now));
     }
     
-    // JSONフィールドのリスト
-    let json_fields = vec![
-        "pagesBySubMenu", "pageOrderBySubMenu", "visibleSubMenuIds",
-        "customSubMenuLabels",
-        "contentStructure", "formatPattern", "pageRelations",
-        "combinedEmbedding", "titleEmbedding", "contentEmbedding",
-        "linkedPlanIds",
-        "initiativeIds", // テーマの注力施策IDリスト
-        "themeIds", // 注力施策のテーマIDリスト
-        "topicIds", // 注力施策のトピックIDリスト
-        "levels", // テーマ階層設定のレベル配列
-    ];
-    
-    // INTEGER型のフィールドのリスト
-    let integer_fields = vec![
-        "isFavorite", "approved", "isDeleted", "isFixed",
-        "titleBorderEnabled", "keyVisualHeight", "keyVisualScale",
-        "keyVisualLogoSize", "titlePositionX", "titlePositionY",
-        "titleFontSize", "fileSize", "sequenceNumber",
-        "position",
-    ];
-    
-    // JSONフィールドを文字列化（既に文字列の場合はそのまま）
-    for field in &json_fields {
-        if let Some(value) = row_data.get(*field) {
-            if let Some(json_str) = value_to_sql_string(value, true) {
-                row_data.insert(field.to_string(), json!(json_str));
-                eprintln!("📝 [set_doc] JSONフィールド '{}' を処理: {} bytes", field, json_str.len());
-            } else {
-                // Value::Nullの場合はnullを設定
-                row_data.insert(field.to_string(), json!(null));
-                eprintln!("📝 [set_doc] JSONフィールド '{}' をNULLに設定", field);
-            }
-        }
-    }
-    
     // テーブルに存在するカラムのみをフィルタリング
     let mut valid_fields: Vec<String> = Vec::new();
     for field in row_data.keys() {
-        if table_columns.contains(field) {
+        if schema.contains(field) {
             valid_fields.push(field.clone());
         } else {
             eprintln!("⚠️ [set_doc] カラム '{}' はテーブル '{}' に存在しないためスキップします", field, collection_name);
@@ -278,20 +213,16 @@ pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>
     
     eprintln!("✅ [set_doc] 有効なフィールド数: {} / {}", valid_fields.len(), row_data.len());
     
-    // meetingNotes、focusInitiatives、topics、entities、relationsテーブルの場合、外部キー制約を一時的に無効化（古い外部キー制約が残っている可能性があるため）
-    if collection_name == "meetingNotes" || collection_name == "focusInitiatives" || collection_name == "topics" || collection_name == "entities" || collection_name == "relations" {
-        if let Err(e) = conn.execute("PRAGMA foreign_keys = OFF", []) {
-            eprintln!("⚠️ [set_doc] 外部キー制約の無効化に失敗しました（続行します）: {}", e);
-        } else {
-            eprintln!("✅ [set_doc] 外部キー制約を無効化しました（{}用）", collection_name);
+    // カラムの型に従って値を変換（変換できない値がある場合は書き込まずにエラーを返す）
+    let mut sql_values: HashMap<String, SqlValue> = HashMap::new();
+    for field in &valid_fields {
+        if let Some(v) = row_data.get(field) {
+            sql_values.insert(field.clone(), schema.to_sql_value(field, v)?);
         }
     }
     
-    // トランザクションを開始（データベースロックを最小化）
-    let tx = conn.unchecked_transaction()?;
-    
     // 既存レコードをチェック
-    let exists: bool = tx.query_row(
+    let exists: bool = conn.query_row(
         &format!("SELECT COUNT(*) FROM {} WHERE id = ?1", collection_name),
         [doc_id],
         |row| row.get(0),
@@ -307,7 +238,6 @@ pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>
         
         if update_fields.is_empty() {
             eprintln!("⚠️ [set_doc] 更新するフィールドがありません");
-            tx.commit()?;
             return Ok(());
         }
         
@@ -318,79 +248,35 @@ pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>
         
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
         for field in &update_fields {
-            if let Some(v) = row_data.get(field) {
-                let is_json = json_fields.contains(&field.as_str());
-                let is_integer = integer_fields.contains(&field.as_str());
-                
-                if is_integer {
-                    // INTEGER型のフィールドは数値として直接使用
-                    if let Some(n) = v.as_i64() {
-                        params.push(Box::new(n));
-                    } else if let Some(n) = v.as_u64() {
-                        params.push(Box::new(n as i64));
-                    } else if let Some(b) = v.as_bool() {
-                        params.push(Box::new(if b { 1i64 } else { 0i64 }));
-                    } else {
-                        if let Some(sql_value) = value_to_sql_string(v, false) {
-                            params.push(Box::new(sql_value));
-                        } else {
-                            // NULL値の場合はOption<String>としてNoneを追加
-                            params.push(Box::new(None::<String>));
-                        }
-                    }
-                } else {
-                    if let Some(sql_value) = value_to_sql_string(v, is_json) {
-                        params.push(Box::new(sql_value));
-                    } else {
-                        // NULL値の場合はOption<String>としてNoneを追加
-                        params.push(Box::new(None::<String>));
-                    }
-                }
+            if let Some(v) = sql_values.get(field) {
+                params.push(Box::new(v.clone()));
             }
         }
         let doc_id_param = doc_id.to_string();
         params.push(Box::new(doc_id_param));
         
         let query = format!("UPDATE {} SET {} WHERE id = ?", collection_name, set_clause);
+        let before = snapshot_row(conn, collection_name, doc_id)?;
         eprintln!("📝 [set_doc] UPDATEクエリ: {}", query);
         eprintln!("📝 [set_doc] パラメータ数: {}", params.len());
         
         let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-        match tx.execute(&query, params_refs.as_slice()) {
+        match conn.execute(&query, params_refs.as_slice()) {
             Ok(rows_affected) => {
                 eprintln!("✅ [set_doc] 更新成功: {}行更新", rows_affected);
-                tx.commit()?;
-                
-                // meetingNotes、focusInitiatives、topics、entities、relationsテーブルの場合、外部キー制約を再度有効化
-                if collection_name == "meetingNotes" || collection_name == "focusInitiatives" || collection_name == "topics" || collection_name == "entities" || collection_name == "relations" {
-                    if let Err(e) = conn.execute("PRAGMA foreign_keys = ON", []) {
-                        eprintln!("⚠️ [set_doc] 外部キー制約の再有効化に失敗しました（続行します）: {}", e);
-                    } else {
-                        eprintln!("✅ [set_doc] 外部キー制約を再有効化しました（{}用）", collection_name);
-                    }
-                }
-                
+                audit_change(conn, AuditAction::Update, collection_name, doc_id, before)?;
                 Ok(())
             },
             Err(e) => {
                 eprintln!("❌ [set_doc] UPDATEエラー: {}", e);
                 eprintln!("❌ [set_doc] クエリ: {}", query);
-                
-                // エラー時も外部キー制約を再有効化
-                if collection_name == "meetingNotes" || collection_name == "focusInitiatives" || collection_name == "topics" || collection_name == "entities" || collection_name == "relations" {
-                    let _ = conn.execute("PRAGMA foreign_keys = ON", []);
-                }
-                
                 Err(e)
             }
         }
     } else {
         // 挿入
         eprintln!("➕ [set_doc] 新規レコードを挿入します");
-        let insert_fields: Vec<String> = valid_fields.iter()
-            .filter(|k| table_columns.contains(*k))
-            .cloned()
-            .collect();
+        let insert_fields: Vec<String> = valid_fields.clone();
         
         if insert_fields.is_empty() {
             eprintln!("❌ [set_doc] 挿入するフィールドがありません");
@@ -406,37 +292,11 @@ pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>
         for field in &insert_fields {
             if field == "id" {
                 params.push(Box::new(doc_id.to_string()));
-            } else if let Some(v) = row_data.get(field) {
-                let is_json = json_fields.contains(&field.as_str());
-                let is_integer = integer_fields.contains(&field.as_str());
-                
-                if is_integer {
-                    // INTEGER型のフィールドは数値として直接使用
-                    if let Some(n) = v.as_i64() {
-                        params.push(Box::new(n));
-                    } else if let Some(n) = v.as_u64() {
-                        params.push(Box::new(n as i64));
-                    } else if let Some(b) = v.as_bool() {
-                        params.push(Box::new(if b { 1i64 } else { 0i64 }));
-                    } else {
-                        if let Some(sql_value) = value_to_sql_string(v, false) {
-                            params.push(Box::new(sql_value));
-                        } else {
-                            // NULL値の場合はOption<String>としてNoneを追加
-                            params.push(Box::new(None::<String>));
-                        }
-                    }
-                } else {
-                    if let Some(sql_value) = value_to_sql_string(v, is_json) {
-                        params.push(Box::new(sql_value));
-                    } else {
-                        // NULL値の場合はOption<String>としてNoneを追加
-                        if field == "organizationId" || field == "companyId" {
-                            eprintln!("📝 [set_doc] INSERT: {} フィールドをNULLとして設定します", field);
-                        }
-                        params.push(Box::new(None::<String>));
-                    }
+            } else if let Some(v) = sql_values.get(field) {
+                if *v == SqlValue::Null && (field == "organizationId" || field == "companyId") {
+                    eprintln!("📝 [set_doc] INSERT: {} フィールドをNULLとして設定します", field);
                 }
+                params.push(Box::new(v.clone()));
             }
         }
         
@@ -449,31 +309,15 @@ pub fn set_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Value>
         eprintln!("📝 [set_doc] パラメータ数: {}", params.len());
         
         let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-        match tx.execute(&query, params_refs.as_slice()) {
+        match conn.execute(&query, params_refs.as_slice()) {
             Ok(rows_affected) => {
                 eprintln!("✅ [set_doc] 挿入成功: {}行挿入", rows_affected);
-                tx.commit()?;
-                
-                // meetingNotes、focusInitiatives、topics、entities、relationsテーブルの場合、外部キー制約を再度有効化
-                if collection_name == "meetingNotes" || collection_name == "focusInitiatives" || collection_name == "topics" || collection_name == "entities" || collection_name == "relations" {
-                    if let Err(e) = conn.execute("PRAGMA foreign_keys = ON", []) {
-                        eprintln!("⚠️ [set_doc] 外部キー制約の再有効化に失敗しました（続行します）: {}", e);
-                    } else {
-                        eprintln!("✅ [set_doc] 外部キー制約を再有効化しました（{}用）", collection_name);
-                    }
-                }
-                
+                audit_change(conn, AuditAction::Create, collection_name, doc_id, None)?;
                 Ok(())
             },
             Err(e) => {
                 eprintln!("❌ [set_doc] INSERTエラー: {}", e);
                 eprintln!("❌ [set_doc] クエリ: {}", query);
-                
-                // エラー時も外部キー制約を再有効化
-                if collection_name == "meetingNotes" || collection_name == "focusInitiatives" || collection_name == "topics" || collection_name == "entities" || collection_name == "relations" {
-                    let _ = conn.execute("PRAGMA foreign_keys = ON", []);
-                }
-                
                 Err(e)
             }
         }
@@ -484,7 +328,7 @@ pub fn update_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Val
     eprintln!("🔧 [update_doc] 開始: collection_name={}, doc_id={}", collection_name, doc_id);
     
     // テーブル名の検証（SQLインジェクション対策）
-    validate_table_name(collection_name)?;
+    validate_writable_table_name(collection_name)?;
     
     let db = get_db().ok_or_else(|| {
         eprintln!("❌ [update_doc] データベースが初期化されていません");
@@ -494,27 +338,20 @@ pub fn update_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Val
         )
     })?;
     let conn = db.get_connection()?;
-    
-    // テーブルが存在するか確認
-    let table_exists: bool = conn.query_row(
-        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=?1",
-        [collection_name],
-        |row| row.get(0),
-    ).unwrap_or(false);
-    
-    if !table_exists {
-        eprintln!("❌ [update_doc] テーブル '{}' が存在しません", collection_name);
-        return Err(rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-            Some(format!("テーブル '{}' が存在しません", collection_name))
-        ));
-    }
-    
-    // テーブルのカラム一覧を取得
-    let table_columns = match get_table_columns(&conn, collection_name) {
-        Ok(cols) => {
-            eprintln!("✅ [update_doc] テーブル '{}' のカラム数: {}", collection_name, cols.len());
-            cols
+    let tx = conn.unchecked_transaction()?;
+    update_doc_in(&tx, collection_name, doc_id, data)?;
+    tx.commit()
+}
+
+/// ドキュメントの指定したフィールドのみ呼び出し側のトランザクション内で更新（書き込みワーカー用）
+pub(crate) fn update_doc_in(conn: &Connection, collection_name: &str, doc_id: &str, data: HashMap<String, Value>) -> SqlResult<()> {
+    validate_writable_table_name(collection_name)?;
+    
+    // テーブルのカラム型を取得（テーブルが存在しない場合はエラー）
+    let schema = match table_schema(conn, collection_name) {
+        Ok(schema) => {
+            eprintln!("✅ [update_doc] テーブル '{}' のカラム数: {}", collection_name, schema.columns.len());
+            schema
         },
         Err(e) => {
             eprintln!("❌ [update_doc] カラム情報の取得に失敗: {}", e);
@@ -523,56 +360,28 @@ pub fn update_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Val
     };
     
     let now = get_timestamp();
-    let mut row_data = data.clone();
+    let mut row_data = data;
     
     row_data.insert("updatedAt".to_string(), json!(now));
     
-    // JSONフィールドのリスト
-    let json_fields = vec![
-        "pagesBySubMenu", "pageOrderBySubMenu", "visibleSubMenuIds",
-        "customSubMenuLabels",
-        "contentStructure", "formatPattern", "pageRelations",
-        "combinedEmbedding", "titleEmbedding", "contentEmbedding",
-        "linkedPlanIds",
-        "initiativeIds", // テーマの注力施策IDリスト
-        "themeIds", // 注力施策のテーマIDリスト
-        "topicIds", // 注力施策のトピックIDリスト
-        "levels", // テーマ階層設定のレベル配列
-    ];
-    
-    // INTEGER型のフィールドのリスト
-    let integer_fields = vec![
-        "isFavorite", "approved", "isDeleted", "isFixed",
-        "titleBorderEnabled", "keyVisualHeight", "keyVisualScale",
-        "keyVisualLogoSize", "titlePositionX", "titlePositionY",
-        "titleFontSize", "fileSize", "sequenceNumber",
-        "position",
-    ];
-    
-    // JSONフィールドを文字列化（既に文字列の場合はそのまま）
-    for field in &json_fields {
-        if let Some(value) = row_data.get(*field) {
-            if let Some(json_str) = value_to_sql_string(value, true) {
-                row_data.insert(field.to_string(), json!(json_str));
-                eprintln!("📝 [update_doc] JSONフィールド '{}' を処理: {} bytes", field, json_str.len());
-            } else {
-                // Value::Nullの場合はnullを設定
-                row_data.insert(field.to_string(), json!(null));
-                eprintln!("📝 [update_doc] JSONフィールド '{}' をNULLに設定", field);
-            }
-        }
-    }
-    
     // テーブルに存在するカラムのみをフィルタリング
     let mut valid_fields: Vec<String> = Vec::new();
     for field in row_data.keys() {
-        if table_columns.contains(field) {
+        if schema.contains(field) {
             valid_fields.push(field.clone());
         } else {
             eprintln!("⚠️ [update_doc] カラム '{}' はテーブル '{}' に存在しないためスキップします", field, collection_name);
         }
     }
     
+    // カラムの型に従って値を変換（変換できない値がある場合は書き込まずにエラーを返す）
+    let mut sql_values: HashMap<String, SqlValue> = HashMap::new();
+    for field in &valid_fields {
+        if let Some(v) = row_data.get(field) {
+            sql_values.insert(field.clone(), schema.to_sql_value(field, v)?);
+        }
+    }
+    
     // idとcreatedAtは更新しない
     let update_fields: Vec<String> = valid_fields.iter()
         .filter(|k| **k != "id" && **k != "createdAt")
@@ -586,9 +395,6 @@ pub fn update_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Val
     
     eprintln!("✅ [update_doc] 有効な更新フィールド数: {} / {}", update_fields.len(), row_data.len());
     
-    // トランザクションを開始（データベースロックを最小化）
-    let tx = conn.unchecked_transaction()?;
-    
     let set_clause = update_fields.iter()
         .map(|f| format!("{} = ?", f))
         .collect::<Vec<_>>()
@@ -596,51 +402,27 @@ pub fn update_doc(collection_name: &str, doc_id: &str, data: HashMap<String, Val
     
     let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
     for field in &update_fields {
-        if let Some(v) = row_data.get(field) {
-            let is_json = json_fields.contains(&field.as_str());
-            let is_integer = integer_fields.contains(&field.as_str());
-            
-            if is_integer {
-                // INTEGER型のフィールドは数値として直接使用
-                if let Some(n) = v.as_i64() {
-                    params.push(Box::new(n));
-                } else if let Some(n) = v.as_u64() {
-                    params.push(Box::new(n as i64));
-                } else if let Some(b) = v.as_bool() {
-                    params.push(Box::new(if b { 1i64 } else { 0i64 }));
-                } else {
-                    if let Some(sql_value) = value_to_sql_string(v, false) {
-                        params.push(Box::new(sql_value));
-                    } else {
-                        // NULL値の場合はOption<String>としてNoneを追加
-                        params.push(Box::new(None::<String>));
-                    }
-                }
-            } else {
-                if let Some(sql_value) = value_to_sql_string(v, is_json) {
-                    params.push(Box::new(sql_value));
-                } else {
-                    // NULL値の場合はOption<String>としてNoneを追加
-                    params.push(Box::new(None::<String>));
-                }
-            }
+        if let Some(v) = sql_values.get(field) {
+            params.push(Box::new(v.clone()));
         }
     }
     let doc_id_param = doc_id.to_string();
     params.push(Box::new(doc_id_param));
     
     let query = format!("UPDATE {} SET {} WHERE id = ?", collection_name, set_clause);
+    let before = snapshot_row(conn, collection_name, doc_id)?;
     eprintln!("📝 [update_doc] SQLクエリ: {}", query);
     eprintln!("📝 [update_doc] パラメータ数: {}", params.len());
     
     let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-    match tx.execute(&query, params_refs.as_slice()) {
+    match conn.execute(&query, params_refs.as_slice()) {
         Ok(rows_affected) => {
             eprintln!("✅ [update_doc] 成功: {}行更新", rows_affected);
             if rows_affected == 0 {
                 eprintln!("⚠️ [update_doc] 警告: 更新された行が0行です。doc_id={} が存在しない可能性があります。", doc_id);
+            } else {
+                audit_change(conn, AuditAction::Update, collection_name, doc_id, before)?;
             }
-            tx.commit()?;
             Ok(())
         }
         Err(e) => {
@@ -655,7 +437,7 @@ pub fn delete_doc(collection_name: &str, doc_id: &str) -> SqlResult<()> {
     eprintln!("🗑️ [delete_doc] 削除開始: collection_name={}, doc_id={}", collection_name, doc_id);
     
     // テーブル名の検証（SQLインジェクション対策）
-    validate_table_name(collection_name)?;
+    validate_writable_table_name(collection_name)?;
     
     // organizationsテーブルの場合は、専用の削除関数を使用（関連データも一緒に削除）
     if collection_name == "organizations" {
@@ -670,6 +452,23 @@ pub fn delete_doc(collection_name: &str, doc_id: &str) -> SqlResult<()> {
         return delete_meeting_note_with_relations(doc_id);
     }
     
+    // 論理削除に対応したテーブルの場合は、ゴミ箱に移動
+    if is_soft_delete_table(collection_name) {
+        return match trash_item(collection_name, doc_id)? {
+            Some(item) => {
+                eprintln!("✅ [delete_doc] ゴミ箱に移動しました: collection_name={}, doc_id={}, trashId={}", collection_name, doc_id, item.id);
+                Ok(())
+            }
+            None => {
+                eprintln!("⚠️ [delete_doc] レコードが存在しません: doc_id={}", doc_id);
+                Err(rusqlite::Error::SqliteFailure(
+                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTFOUND),
+                    Some(format!("レコード '{}' が存在しません", doc_id))
+                ))
+            }
+        };
+    }
+    
     let db = get_db().ok_or_else(|| {
         eprintln!("❌ [delete_doc] データベースが初期化されていません");
         rusqlite::Error::SqliteFailure(
@@ -679,15 +478,6 @@ pub fn delete_doc(collection_name: &str, doc_id: &str) -> SqlResult<()> {
     })?;
     let conn = db.get_connection()?;
     
-    // focusInitiativesテーブルの場合、外部キー制約を一時的に無効化（古い外部キー制約が残っている可能性があるため）
-    if collection_name == "focusInitiatives" {
-        if let Err(e) = conn.execute("PRAGMA foreign_keys = OFF", []) {
-            eprintln!("⚠️ [delete_doc] 外部キー制約の無効化に失敗しました（続行します）: {}", e);
-        } else {
-            eprintln!("✅ [delete_doc] 外部キー制約を無効化しました（{}用）", collection_name);
-        }
-    }
-    
     // テーブルが存在するか確認
     let table_exists: bool = conn.query_row(
         "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=?1",
@@ -697,10 +487,6 @@ pub fn delete_doc(collection_name: &str, doc_id: &str) -> SqlResult<()> {
     
     if !table_exists {
         eprintln!("❌ [delete_doc] テーブル '{}' が存在しません", collection_name);
-        // focusInitiativesの場合、外部キー制約を再有効化
-        if collection_name == "focusInitiatives" {
-            let _ = conn.execute("PRAGMA foreign_keys = ON", []);
-        }
         return Err(rusqlite::Error::SqliteFailure(
             rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
             Some(format!("テーブル '{}' が存在しません", collection_name))
@@ -719,10 +505,6 @@ pub fn delete_doc(collection_name: &str, doc_id: &str) -> SqlResult<()> {
     
     if !exists {
         eprintln!("⚠️ [delete_doc] レコードが存在しません: doc_id={}", doc_id);
-        // focusInitiativesの場合、外部キー制約を再有効化
-        if collection_name == "focusInitiatives" {
-            let _ = conn.execute("PRAGMA foreign_keys = ON", []);
-        }
         return Err(rusqlite::Error::SqliteFailure(
             rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTFOUND),
             Some(format!("レコード '{}' が存在しません", doc_id))
@@ -732,6 +514,7 @@ pub fn delete_doc(collection_name: &str, doc_id: &str) -> SqlResult<()> {
     eprintln!("✅ [delete_doc] レコードが存在することを確認: doc_id={}", doc_id);
     
     let query = format!("DELETE FROM {} WHERE id = ?1", collection_name);
+    let before = snapshot_row(&tx, collection_name, doc_id)?;
     eprintln!("📝 [delete_doc] 実行するSQL: {}", query);
     
     match tx.execute(&query, [doc_id]) {
@@ -740,27 +523,16 @@ pub fn delete_doc(collection_name: &str, doc_id: &str) -> SqlResult<()> {
             
             if rows_affected == 0 {
                 eprintln!("⚠️ [delete_doc] 警告: 0行が削除されました（レコードが存在しない可能性があります）");
+            } else {
+                audit_change(&tx, AuditAction::Delete, collection_name, doc_id, before)?;
             }
             
             tx.commit()?;
             
-            // focusInitiativesテーブルの場合、外部キー制約を再度有効化
-            if collection_name == "focusInitiatives" {
-                if let Err(e) = conn.execute("PRAGMA foreign_keys = ON", []) {
-                    eprintln!("⚠️ [delete_doc] 外部キー制約の再有効化に失敗しました（続行します）: {}", e);
-                } else {
-                    eprintln!("✅ [delete_doc] 外部キー制約を再有効化しました（{}用）", collection_name);
-                }
-            }
-            
             Ok(())
         }
         Err(e) => {
             eprintln!("❌ [delete_doc] 削除エラー: {}", e);
-            // focusInitiativesの場合、外部キー制約を再有効化
-            if collection_name == "focusInitiatives" {
-                let _ = conn.execute("PRAGMA foreign_keys = ON", []);
-            }
             Err(e)
         }
     }
@@ -781,10 +553,13 @@ pub fn get_collection(collection_name: &str, conditions: Option<HashMap<String,
         Some("データベースが初期化されていません".to_string())
     ))?;
     let conn = db.get_connection()?;
+    let schema = table_schema(&conn, collection_name)?;
     
     let mut query = format!("SELECT * FROM {}", collection_name);
-    let mut param_values: Vec<String> = Vec::new();
+    let mut param_values: Vec<SqlValue> = Vec::new();
     let mut where_clauses: Vec<String> = Vec::new();
+    // ゴミ箱にある行は返さない
+    let live_only = is_soft_delete_table(collection_name);
     
     if let Some(conds) = conditions {
         // 新しい形式: { field: value } の形式をサポート
@@ -796,23 +571,16 @@ pub fn get_collection(collection_name: &str, conditions: Option<HashMap<String,
             }
             
             // フィールド名の検証（SQLインジェクション対策）
-            if !field.chars().all(|c| c.is_alphanumeric() || c == '_') {
-                return Err(rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                    Some(format!("無効なフィールド名: {}", field))
-                ));
-            }
+            schema.validate_column(field)?;
+            validate_visible_column(collection_name, field)?;
             
+            if value.is_null() {
+                where_clauses.push(format!("{} IS NULL", field));
+                continue;
+            }
             where_clauses.push(format!("{} = ?", field));
-            // valueを文字列に変換（ライフタイムの問題を回避）
-            let param_str = match value {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Bool(b) => b.to_string(),
-                Value::Null => "NULL".to_string(),
-                _ => value.to_string(),
-            };
-            param_values.push(param_str);
+            // カラムの型に従って変換
+            param_values.push(schema.to_sql_value(field, value)?);
         }
         
         // 後方互換性のため、古い形式（field, operator, value）もサポート
@@ -820,6 +588,8 @@ pub fn get_collection(collection_name: &str, conditions: Option<HashMap<String,
             if let Some(field) = conds.get("field").and_then(|v| v.as_str()) {
                 if let Some(operator) = conds.get("operator").and_then(|v| v.as_str()) {
                     if let Some(value) = conds.get("value") {
+                        schema.validate_column(field)?;
+                        validate_visible_column(collection_name, field)?;
                         let sql_op = match operator {
                             "==" => "=",
                             "!=" => "!=",
@@ -830,20 +600,17 @@ pub fn get_collection(collection_name: &str, conditions: Option<HashMap<String,
                             _ => "=",
                         };
                         where_clauses.push(format!("{} {} ?", field, sql_op));
-                        // valueを文字列に変換（ライフタイムの問題を回避）
-                        let param_str = match value {
-                            Value::String(s) => s.clone(),
-                            Value::Number(n) => n.to_string(),
-                            Value::Bool(b) => b.to_string(),
-                            _ => value.to_string(),
-                        };
-                        param_values.push(param_str);
+                        // カラムの型に従って変換
+                        param_values.push(schema.to_sql_value(field, value)?);
                     }
                 }
             }
         }
         
         // WHERE句を追加
+        if live_only {
+            where_clauses.push("deletedAt IS NULL".to_string());
+        }
         if !where_clauses.is_empty() {
             query.push_str(" WHERE ");
             query.push_str(&where_clauses.join(" AND "));
@@ -851,6 +618,8 @@ pub fn get_collection(collection_name: &str, conditions: Option<HashMap<String,
         
         // ORDER BY句を追加
         if let Some(order_by) = conds.get("orderBy").and_then(|v| v.as_str()) {
+            schema.validate_column(order_by)?;
+            validate_visible_column(collection_name, order_by)?;
             let direction = if conds.get("orderDirection")
                 .and_then(|v| v.as_str())
                 .map(|d| d == "desc")
@@ -861,178 +630,34 @@ pub fn get_collection(collection_name: &str, conditions: Option<HashMap<String,
             };
             query.push_str(&format!(" ORDER BY {} {}", order_by, direction));
         }
+    } else if live_only {
+        query.push_str(" WHERE deletedAt IS NULL");
     }
     
-    // paramsを構築（param_valuesのライフタイムを保持）
-    let params: Vec<&dyn rusqlite::ToSql> = param_values.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
-    
     let mut stmt = conn.prepare(&query)?;
-    let rows = stmt.query_map(params.as_slice(), |row| {
-        let mut map = HashMap::new();
-        for i in 0..row.as_ref().column_count() {
-            let col_name = row.as_ref().column_name(i)
-                .map_err(|_| rusqlite::Error::SqliteFailure(
-                    rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                    Some(format!("カラム名の取得に失敗しました: インデックス {}", i))
-                ))?;
-            let value: Value = match row.get::<_, Option<String>>(i) {
-                Ok(Some(s)) => {
-                    // 空文字列の場合はNullとして扱う
-                    if s.is_empty() {
-                        Value::Null
-                    } else {
-                        if col_name.contains("Embedding") || 
-                           col_name == "pagesBySubMenu" || 
-                           col_name == "pageOrderBySubMenu" ||
-                           col_name == "visibleSubMenuIds" ||
-                           col_name == "customSubMenuLabels" ||
-                           col_name == "contentStructure" ||
-                           col_name == "formatPattern" ||
-                           col_name == "pageRelations" ||
-                           col_name == "linkedPlanIds" ||
-                           col_name == "initiativeIds" {
-                            // JSON文字列をパース、失敗した場合は空のオブジェクト/配列を返す
-                            match serde_json::from_str::<Value>(&s) {
-                                Ok(v) => v,
-                                Err(_) => {
-                                    eprintln!("⚠️ [get_collection] JSONパースエラー: field={}, value={}", col_name, s.chars().take(100).collect::<String>());
-                                    json!([])
-                                }
-                            }
-                        } else {
-                            json!(s)
-                        }
-                    }
-                }
-                Ok(None) => Value::Null,
-                Err(_) => {
-                    // 数値やNULLの処理（INTEGER型のフィールドに対応）
-                    match row.get::<_, Option<i64>>(i) {
-                        Ok(Some(v)) => json!(v),
-                        Ok(None) => Value::Null,
-                        Err(_) => Value::Null,
-                    }
-                }
-            };
-            map.insert(col_name.to_string(), value);
-        }
-        Ok(map)
-    })?;
-    
-    let mut results = Vec::new();
-    for row in rows {
-        let mut row = row?;
-        
-        // タイムスタンプを変換
-        if let Some(created_at) = row.get("createdAt").and_then(|v| v.as_str()) {
-            row.insert("createdAt".to_string(), json!(to_firestore_timestamp(created_at)));
-        }
-        if let Some(updated_at) = row.get("updatedAt").and_then(|v| v.as_str()) {
-            row.insert("updatedAt".to_string(), json!(to_firestore_timestamp(updated_at)));
-        }
-        
-        results.push(row);
-    }
-    
-    Ok(results)
+    let rows = stmt.query_map(rusqlite::params_from_iter(param_values.iter()), |row| schema.row_to_map(row))?;
+    rows.map(|row| row.map(|mut row| {
+        strip_hidden_columns(collection_name, &mut row);
+        row
+    })).collect()
 }
 
-/// 議事録と関連データを一括削除（バッチ削除）
-/// 1つのトランザクション内でtopicRelations、topicEmbeddings、meetingNotesを削除
+/// 議事録と関連データ（トピック・リレーション）をまとめてゴミ箱に移動
 pub fn delete_meeting_note_with_relations(note_id: &str) -> SqlResult<()> {
     eprintln!("🗑️ [delete_meeting_note_with_relations] 開始: note_id={}", note_id);
     
-    let db = get_db().ok_or_else(|| {
-        eprintln!("❌ [delete_meeting_note_with_relations] データベースが初期化されていません");
-        rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-            Some("データベースが初期化されていません".to_string())
-        )
-    })?;
-    
-    let conn = db.get_connection()?;
-    
-    // 外部キー制約を一時的に無効化（古い外部キー制約が残っている可能性があるため）
-    if let Err(e) = conn.execute("PRAGMA foreign_keys = OFF", []) {
-        eprintln!("⚠️ [delete_meeting_note_with_relations] 外部キー制約の無効化に失敗しました（続行します）: {}", e);
-    } else {
-        eprintln!("✅ [delete_meeting_note_with_relations] 外部キー制約を無効化しました");
-    }
-    
-    // 1つのトランザクション内で全ての削除を実行
-    let tx = conn.unchecked_transaction()?;
-    
-    // 1. 関連するrelationsを取得（削除用、topicRelationsからリネーム済み）
-    eprintln!("📊 [delete_meeting_note_with_relations] 関連するrelationsを取得中...");
-    let relation_ids: Vec<String> = {
-        let query = "SELECT id FROM relations WHERE topicId IN (
-            SELECT id FROM topics WHERE meetingNoteId = ?1
-        )";
-        let mut stmt = tx.prepare(query)?;
-        let rows = stmt.query_map([note_id], |row| {
-            Ok(row.get::<_, String>(0)?)
-        })?;
-        rows.collect::<Result<Vec<_>, _>>().unwrap_or_else(|e| {
-            eprintln!("⚠️ [delete_meeting_note_with_relations] relations取得エラー: {}", e);
-            Vec::new()
-        })
-    };
-    
-    eprintln!("📊 [delete_meeting_note_with_relations] 関連するrelations: {}件", relation_ids.len());
-    
-    // 2. relationsを削除
-    if !relation_ids.is_empty() {
-        // ループで個別削除（rusqliteのIN句は可変長パラメータを直接サポートしていないため）
-        let mut deleted_count = 0;
-        for id in &relation_ids {
-            deleted_count += tx.execute(
-                "DELETE FROM relations WHERE id = ?1",
-                [id.as_str()]
-            )?;
+    match trash_meeting_note(note_id)? {
+        Some(item) => {
+            eprintln!("✅ [delete_meeting_note_with_relations] ゴミ箱に移動しました: note_id={}, trashId={}, 対象: {}件",
+                note_id, item.id, item.item_count);
+            Ok(())
+        }
+        None => {
+            eprintln!("⚠️ [delete_meeting_note_with_relations] meetingNotesが存在しません: note_id={}", note_id);
+            Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTFOUND),
+                Some(format!("議事録 '{}' が存在しません", note_id))
+            ))
         }
-        eprintln!("✅ [delete_meeting_note_with_relations] relations削除: {}件", deleted_count);
-    }
-    
-    // 3. topicsを削除（topicEmbeddingsから統合済み）
-    eprintln!("📊 [delete_meeting_note_with_relations] topicsを削除中...");
-    let deleted_topics = tx.execute(
-        "DELETE FROM topics WHERE meetingNoteId = ?1",
-        [note_id]
-    )?;
-    eprintln!("✅ [delete_meeting_note_with_relations] topics削除: {}件", deleted_topics);
-    
-    // 4. meetingNotesを削除
-    eprintln!("📊 [delete_meeting_note_with_relations] meetingNotesを削除中...");
-    let deleted_notes = tx.execute(
-        "DELETE FROM meetingNotes WHERE id = ?1",
-        [note_id]
-    )?;
-    
-    if deleted_notes == 0 {
-        eprintln!("⚠️ [delete_meeting_note_with_relations] meetingNotesが存在しません: note_id={}", note_id);
-        tx.rollback()?;
-        return Err(rusqlite::Error::SqliteFailure(
-            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTFOUND),
-            Some(format!("議事録 '{}' が存在しません", note_id))
-        ));
-    }
-    
-    eprintln!("✅ [delete_meeting_note_with_relations] meetingNotes削除: {}件", deleted_notes);
-    
-    // トランザクションをコミット
-    tx.commit()?;
-    
-    // 外部キー制約を再度有効化
-    if let Err(e) = conn.execute("PRAGMA foreign_keys = ON", []) {
-        eprintln!("⚠️ [delete_meeting_note_with_relations] 外部キー制約の再有効化に失敗しました（続行します）: {}", e);
-    } else {
-        eprintln!("✅ [delete_meeting_note_with_relations] 外部キー制約を再有効化しました");
     }
-    
-    eprintln!("✅ [delete_meeting_note_with_relations] 全ての削除が完了しました (リレーション: {}件, トピック: {}件, 議事録: {}件)", 
-        relation_ids.len(), deleted_topics, deleted_notes);
-    Ok(())
 }
-
-
-
@@ -0,0 +1,602 @@
+/**
+ * タスクチェーンのノード定義と検証
+ * taskChains.nodes（JSON文字列）を型付きの ChainNode として解釈し、実行前にグラフを検証する
+ *
+ * - ノードの種類: task / condition / parallel（分岐）/ join（合流）/ loop / wait
+ * - JSONの形式はフロントエンドの ChainNode と同じ（"type" で種類を区別し、フィールド名は camelCase）
+ * - 検証では、開始ノード・参照先ノードの存在、到達できないノード、出口のない循環、
+ *   tasks テーブルにないタスクID、parallel の各分岐が join に到達するかを確認する
+ */
+
+use crate::database::{get_db, TaskChain};
+use rusqlite::{params, Result as SqlResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// 条件の比較方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOp {
+    Equals,
+    NotEquals,
+    GreaterThan,
+    LessThan,
+    Contains,
+    Exists,
+}
+
+/// 条件分岐・ループ継続の条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainCondition {
+    #[serde(rename = "type")]
+    pub op: ConditionOp,
+    /// 直前のタスクの実行結果から値を取り出すパス（例: "status", "result.output.score"）
+    pub field: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+impl ChainCondition {
+    /// 直前のタスクの実行結果（{ status, result, error }）に対して評価
+    pub fn evaluate(&self, context: Option<&Value>) -> bool {
+        let Some(mut value) = context else {
+            return false;
+        };
+        for part in self.field.split('.').filter(|p| !p.is_empty()) {
+            match value.get(part) {
+                Some(v) => value = v,
+                None => return false,
+            }
+        }
+
+        match self.op {
+            ConditionOp::Equals => *value == self.value,
+            ConditionOp::NotEquals => *value != self.value,
+            ConditionOp::GreaterThan => matches!((value.as_f64(), self.value.as_f64()), (Some(a), Some(b)) if a > b),
+            ConditionOp::LessThan => matches!((value.as_f64(), self.value.as_f64()), (Some(a), Some(b)) if a < b),
+            ConditionOp::Contains => match (value.as_str(), self.value.as_str()) {
+                (Some(s), Some(needle)) => s.contains(needle),
+                _ => false,
+            },
+            ConditionOp::Exists => !value.is_null(),
+        }
+    }
+}
+
+/// ノードの種類ごとの内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChainNodeKind {
+    /// タスクを実行して次のノードへ進む（タスク未指定のノードは開始・終了の目印として何もしない）
+    Task {
+        #[serde(rename = "taskId", default, skip_serializing_if = "Option::is_none")]
+        task_id: Option<String>,
+        /// フロントエンドが埋め込むタスク定義（id のみ使用する）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        task: Option<Value>,
+        #[serde(rename = "nextNodeId", default, skip_serializing_if = "Option::is_none")]
+        next_node_id: Option<String>,
+    },
+    /// 直前のタスクの結果で分岐（分岐先が未指定の場合はその経路を終了する）
+    Condition {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        condition: Option<ChainCondition>,
+        #[serde(rename = "trueBranch", default, skip_serializing_if = "Option::is_none")]
+        true_branch: Option<String>,
+        #[serde(rename = "falseBranch", default, skip_serializing_if = "Option::is_none")]
+        false_branch: Option<String>,
+    },
+    /// 各分岐を並列に実行し、すべての分岐が joinNodeId に到達したら合流する
+    Parallel {
+        #[serde(default)]
+        branches: Vec<String>,
+        #[serde(rename = "joinNodeId", default, skip_serializing_if = "Option::is_none")]
+        join_node_id: Option<String>,
+    },
+    /// parallel の分岐の合流点
+    Join {
+        #[serde(rename = "nextNodeId", default, skip_serializing_if = "Option::is_none")]
+        next_node_id: Option<String>,
+    },
+    /// loopCount 回まで（loopCondition を指定した場合は条件を満たす間）本体を繰り返す
+    /// 本体はタスク（taskId / task）か、このノードに戻ってくる経路の先頭（bodyNodeId）
+    Loop {
+        #[serde(rename = "loopCount", default, skip_serializing_if = "Option::is_none")]
+        loop_count: Option<u32>,
+        #[serde(rename = "loopCondition", default, skip_serializing_if = "Option::is_none")]
+        loop_condition: Option<ChainCondition>,
+        #[serde(rename = "taskId", default, skip_serializing_if = "Option::is_none")]
+        task_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        task: Option<Value>,
+        #[serde(rename = "bodyNodeId", default, skip_serializing_if = "Option::is_none")]
+        body_node_id: Option<String>,
+        #[serde(rename = "nextNodeId", default, skip_serializing_if = "Option::is_none")]
+        next_node_id: Option<String>,
+    },
+    /// durationMs だけ待ってから次のノードへ進む
+    Wait {
+        #[serde(rename = "durationMs", default, skip_serializing_if = "Option::is_none")]
+        duration_ms: Option<u64>,
+        #[serde(rename = "nextNodeId", default, skip_serializing_if = "Option::is_none")]
+        next_node_id: Option<String>,
+    },
+}
+
+/// チェーンのノード
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainNode {
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: ChainNodeKind,
+}
+
+/// taskId または埋め込みのタスク定義の id
+fn referenced_task_id<'a>(task_id: &'a Option<String>, task: &'a Option<Value>) -> Option<&'a str> {
+    task_id.as_deref()
+        .or_else(|| task.as_ref().and_then(|t| t.get("id")).and_then(|id| id.as_str()))
+        .filter(|id| !id.is_empty())
+}
+
+impl ChainNode {
+    pub fn type_name(&self) -> &'static str {
+        match self.kind {
+            ChainNodeKind::Task { .. } => "task",
+            ChainNodeKind::Condition { .. } => "condition",
+            ChainNodeKind::Parallel { .. } => "parallel",
+            ChainNodeKind::Join { .. } => "join",
+            ChainNodeKind::Loop { .. } => "loop",
+            ChainNodeKind::Wait { .. } => "wait",
+        }
+    }
+
+    /// 実行するタスクのID（task / loop ノードのみ）
+    pub fn task_id(&self) -> Option<&str> {
+        match &self.kind {
+            ChainNodeKind::Task { task_id, task, .. } | ChainNodeKind::Loop { task_id, task, .. } => {
+                referenced_task_id(task_id, task)
+            }
+            _ => None,
+        }
+    }
+
+    /// 次に進む可能性のあるノード（None はその経路の終了）
+    /// parallel の joinNodeId は分岐の先で到達するため含めない
+    pub fn successors(&self) -> Vec<Option<&str>> {
+        match &self.kind {
+            ChainNodeKind::Task { next_node_id, .. }
+            | ChainNodeKind::Join { next_node_id }
+            | ChainNodeKind::Wait { next_node_id, .. } => vec![next_node_id.as_deref()],
+            ChainNodeKind::Condition { true_branch, false_branch, .. } => {
+                vec![true_branch.as_deref(), false_branch.as_deref()]
+            }
+            ChainNodeKind::Parallel { branches, .. } => branches.iter().map(|b| Some(b.as_str())).collect(),
+            ChainNodeKind::Loop { body_node_id, next_node_id, .. } => {
+                let mut successors = vec![next_node_id.as_deref()];
+                if body_node_id.is_some() {
+                    successors.push(body_node_id.as_deref());
+                }
+                successors
+            }
+        }
+    }
+
+    /// 参照しているノードID（存在確認用。joinNodeId を含む）
+    fn referenced_node_ids(&self) -> Vec<(&'static str, &str)> {
+        let mut refs = Vec::new();
+        match &self.kind {
+            ChainNodeKind::Task { next_node_id, .. }
+            | ChainNodeKind::Join { next_node_id }
+            | ChainNodeKind::Wait { next_node_id, .. } => {
+                refs.extend(next_node_id.as_deref().map(|id| ("nextNodeId", id)));
+            }
+            ChainNodeKind::Condition { true_branch, false_branch, .. } => {
+                refs.extend(true_branch.as_deref().map(|id| ("trueBranch", id)));
+                refs.extend(false_branch.as_deref().map(|id| ("falseBranch", id)));
+            }
+            ChainNodeKind::Parallel { branches, join_node_id } => {
+                refs.extend(branches.iter().map(|id| ("branches", id.as_str())));
+                refs.extend(join_node_id.as_deref().map(|id| ("joinNodeId", id)));
+            }
+            ChainNodeKind::Loop { body_node_id, next_node_id, .. } => {
+                refs.extend(body_node_id.as_deref().map(|id| ("bodyNodeId", id)));
+                refs.extend(next_node_id.as_deref().map(|id| ("nextNodeId", id)));
+            }
+        }
+        refs
+    }
+}
+
+/// 検証で見つかった問題
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainIssue {
+    #[serde(rename = "nodeId")]
+    pub node_id: Option<String>,
+    pub message: String,
+}
+
+/// 検証結果（errors がある場合は実行できない）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChainValidation {
+    pub valid: bool,
+    pub errors: Vec<ChainIssue>,
+    pub warnings: Vec<ChainIssue>,
+}
+
+impl ChainValidation {
+    fn error(&mut self, node_id: Option<&str>, message: impl Into<String>) {
+        self.errors.push(ChainIssue { node_id: node_id.map(|s| s.to_string()), message: message.into() });
+    }
+
+    fn warning(&mut self, node_id: Option<&str>, message: impl Into<String>) {
+        self.warnings.push(ChainIssue { node_id: node_id.map(|s| s.to_string()), message: message.into() });
+    }
+}
+
+/// nodes（JSON文字列）を解析（ノードIDの順に並べる）
+pub fn parse_chain_nodes(nodes: &str) -> Result<BTreeMap<String, ChainNode>, String> {
+    let raw: BTreeMap<String, Value> = serde_json::from_str(nodes)
+        .map_err(|e| format!("nodes を解析できません: {}", e))?;
+    raw.into_iter()
+        .map(|(key, value)| {
+            serde_json::from_value::<ChainNode>(value)
+                .map(|node| (key.clone(), node))
+                .map_err(|e| format!("ノード {} を解析できません: {}", key, e))
+        })
+        .collect()
+}
+
+/// 存在するタスクIDを取得
+fn existing_task_ids(ids: &HashSet<&str>) -> SqlResult<HashSet<String>> {
+    if ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let db = get_db().ok_or_else(|| rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    ))?;
+    let conn = db.get_connection()?;
+    let mut stmt = conn.prepare("SELECT COUNT(*) FROM tasks WHERE id = ?1")?;
+
+    let mut existing = HashSet::new();
+    for id in ids {
+        let count: i64 = stmt.query_row(params![id], |row| row.get(0))?;
+        if count > 0 {
+            existing.insert(id.to_string());
+        }
+    }
+    Ok(existing)
+}
+
+/// タスクチェーンを検証
+pub fn validate_task_chain(chain: &TaskChain) -> SqlResult<ChainValidation> {
+    let mut validation = ChainValidation::default();
+
+    // ノードごとに解析し、解析できたノードだけでグラフを検証する
+    let raw: BTreeMap<String, Value> = match serde_json::from_str(&chain.nodes) {
+        Ok(raw) => raw,
+        Err(e) => {
+            validation.error(None, format!("nodes を解析できません: {}", e));
+            return Ok(validation);
+        }
+    };
+    let mut nodes: BTreeMap<String, ChainNode> = BTreeMap::new();
+    for (key, value) in raw {
+        match serde_json::from_value::<ChainNode>(value) {
+            Ok(node) => {
+                if node.id != key {
+                    validation.error(Some(key.as_str()), format!("ノードIDがキーと一致しません: {}", node.id));
+                }
+                nodes.insert(key, node);
+            }
+            Err(e) => validation.error(Some(key.as_str()), format!("ノードを解析できません: {}", e)),
+        }
+    }
+
+    if !nodes.contains_key(&chain.start_node_id) {
+        validation.error(None, format!("開始ノード {} が見つかりません", chain.start_node_id));
+    }
+
+    // ノードごとの検証
+    for (id, node) in &nodes {
+        let id = id.as_str();
+        for (field, target) in node.referenced_node_ids() {
+            if !nodes.contains_key(target) {
+                validation.error(Some(id), format!("{} の参照先ノード {} が見つかりません", field, target));
+            }
+        }
+
+        match &node.kind {
+            ChainNodeKind::Task { .. } => {
+                if node.task_id().is_none() {
+                    validation.warning(Some(id), "タスクが指定されていません（実行時は何もせずに次へ進みます）");
+                }
+            }
+            ChainNodeKind::Condition { condition, true_branch, false_branch } => {
+                if condition.is_none() {
+                    validation.error(Some(id), "条件が指定されていません");
+                }
+                if true_branch.is_none() && false_branch.is_none() {
+                    validation.warning(Some(id), "分岐先が指定されていません");
+                }
+            }
+            ChainNodeKind::Parallel { branches, join_node_id } => {
+                if branches.is_empty() {
+                    validation.error(Some(id), "分岐が指定されていません");
+                }
+                match join_node_id.as_deref().and_then(|join| nodes.get(join).map(|n| (join, n))) {
+                    Some((_, join)) if !matches!(join.kind, ChainNodeKind::Join { .. }) => {
+                        validation.error(Some(id), "joinNodeId には join ノードを指定してください");
+                    }
+                    Some((join, _)) => {
+                        for branch in branches {
+                            if !reaches(&nodes, branch, join) {
+                                validation.error(Some(id), format!("分岐 {} が合流ノード {} に到達しません", branch, join));
+                            }
+                        }
+                    }
+                    None if join_node_id.is_none() => validation.error(Some(id), "joinNodeId が指定されていません"),
+                    None => {}
+                }
+            }
+            ChainNodeKind::Join { .. } => {}
+            ChainNodeKind::Loop { loop_count, loop_condition, body_node_id, .. } => {
+                if loop_count.is_none() && loop_condition.is_none() {
+                    validation.error(Some(id), "loopCount または loopCondition を指定してください");
+                }
+                if node.task_id().is_none() && body_node_id.is_none() {
+                    validation.error(Some(id), "ループ本体（taskId / task / bodyNodeId）が指定されていません");
+                }
+                if node.task_id().is_some() && body_node_id.is_some() {
+                    validation.error(Some(id), "ループ本体にはタスクか bodyNodeId のどちらか一方を指定してください");
+                }
+                if let Some(body) = body_node_id.as_deref() {
+                    if nodes.contains_key(body) && !reaches(&nodes, body, id) {
+                        validation.error(Some(id), format!("ループ本体 {} がループノードに戻りません", body));
+                    }
+                }
+            }
+            ChainNodeKind::Wait { duration_ms, .. } => {
+                if duration_ms.is_none() {
+                    validation.error(Some(id), "durationMs が指定されていません");
+                }
+            }
+        }
+    }
+
+    // tasks テーブルにないタスク
+    let task_ids: HashSet<&str> = nodes.values().filter_map(|n| n.task_id()).collect();
+    let existing = existing_task_ids(&task_ids)?;
+    for (id, node) in &nodes {
+        if let Some(task_id) = node.task_id() {
+            if !existing.contains(task_id) {
+                validation.error(Some(id.as_str()), format!("タスク {} が見つかりません（タスクを保存してください）", task_id));
+            }
+        }
+    }
+
+    // 開始ノードから到達できないノード
+    if nodes.contains_key(&chain.start_node_id) {
+        let reachable = reachable_from(&nodes, &chain.start_node_id);
+        for id in nodes.keys() {
+            if !reachable.contains(id.as_str()) {
+                validation.warning(Some(id.as_str()), "開始ノードから到達できません");
+            }
+        }
+    }
+
+    // 出口のない循環
+    for component in strongly_connected_components(&nodes) {
+        let is_cycle = component.len() > 1
+            || nodes[component[0]].successors().contains(&Some(component[0]));
+        if !is_cycle {
+            continue;
+        }
+        let members: HashSet<&str> = component.iter().copied().collect();
+        let has_exit = component.iter().any(|id| {
+            nodes[*id].successors().iter().any(|next| match next {
+                Some(next) => !members.contains(next),
+                None => true,
+            })
+        });
+        if !has_exit {
+            let mut ids = component.clone();
+            ids.sort();
+            validation.error(Some(ids[0]), format!("出口のない循環があります: {}", ids.join(" → ")));
+        }
+    }
+
+    validation.valid = validation.errors.is_empty();
+    Ok(validation)
+}
+
+/// from から到達できるノード（from を含む）
+fn reachable_from<'a>(nodes: &'a BTreeMap<String, ChainNode>, from: &'a str) -> HashSet<&'a str> {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(id) = queue.pop_front() {
+        if !visited.insert(id) {
+            continue;
+        }
+        if let Some(node) = nodes.get(id) {
+            queue.extend(node.successors().into_iter().flatten());
+        }
+    }
+    visited
+}
+
+/// from から target に到達できるか
+fn reaches(nodes: &BTreeMap<String, ChainNode>, from: &str, target: &str) -> bool {
+    from == target || nodes.get(from).is_some_and(|node| {
+        node.successors().into_iter().flatten().any(|next| reachable_from(nodes, next).contains(target))
+    })
+}
+
+/// 強連結成分（Tarjan のアルゴリズム）
+fn strongly_connected_components(nodes: &BTreeMap<String, ChainNode>) -> Vec<Vec<&str>> {
+    struct State<'a> {
+        index: usize,
+        indices: HashMap<&'a str, usize>,
+        lowlinks: HashMap<&'a str, usize>,
+        stack: Vec<&'a str>,
+        on_stack: HashSet<&'a str>,
+        components: Vec<Vec<&'a str>>,
+    }
+
+    fn visit<'a>(nodes: &'a BTreeMap<String, ChainNode>, id: &'a str, state: &mut State<'a>) {
+        state.indices.insert(id, state.index);
+        state.lowlinks.insert(id, state.index);
+        state.index += 1;
+        state.stack.push(id);
+        state.on_stack.insert(id);
+
+        let successors: Vec<&str> = nodes[id].successors().into_iter().flatten()
+            .filter(|next| nodes.contains_key(*next))
+            .collect();
+        for next in successors {
+            if !state.indices.contains_key(next) {
+                visit(nodes, next, state);
+                let low = state.lowlinks[id].min(state.lowlinks[next]);
+                state.lowlinks.insert(id, low);
+            } else if state.on_stack.contains(next) {
+                let low = state.lowlinks[id].min(state.indices[next]);
+                state.lowlinks.insert(id, low);
+            }
+        }
+
+        if state.lowlinks[id] == state.indices[id] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(member);
+                component.push(member);
+                if member == id {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        index: 0,
+        indices: HashMap::new(),
+        lowlinks: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        components: Vec::new(),
+    };
+    for id in nodes.keys() {
+        if !state.indices.contains_key(id.as_str()) {
+            visit(nodes, id, &mut state);
+        }
+    }
+    state.components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Timestamp;
+    use serde_json::json;
+
+    fn chain(start_node_id: &str, nodes: Value) -> TaskChain {
+        TaskChain {
+            id: "chain1".to_string(),
+            name: "テスト".to_string(),
+            description: String::new(),
+            start_node_id: start_node_id.to_string(),
+            nodes: nodes.to_string(),
+            created_at: Timestamp::now(),
+            updated_at: Timestamp::now(),
+        }
+    }
+
+    fn wait(id: &str, next: Option<&str>) -> Value {
+        json!({ "id": id, "type": "wait", "durationMs": 10, "nextNodeId": next })
+    }
+
+    fn issue_ids(issues: &[ChainIssue]) -> Vec<&str> {
+        issues.iter().filter_map(|i| i.node_id.as_deref()).collect()
+    }
+
+    #[test]
+    fn warns_about_unreachable_nodes() {
+        let validation = validate_task_chain(&chain("a", json!({
+            "a": wait("a", Some("b")),
+            "b": wait("b", None),
+            "orphan": wait("orphan", Some("b")),
+        }))).unwrap();
+
+        assert!(validation.valid);
+        assert_eq!(issue_ids(&validation.warnings), vec!["orphan"]);
+    }
+
+    #[test]
+    fn rejects_cycles_without_exit() {
+        let validation = validate_task_chain(&chain("a", json!({
+            "a": wait("a", Some("b")),
+            "b": wait("b", Some("c")),
+            "c": wait("c", Some("b")),
+        }))).unwrap();
+
+        assert!(!validation.valid);
+        assert_eq!(validation.errors.len(), 1);
+        assert_eq!(validation.errors[0].node_id.as_deref(), Some("b"));
+        assert!(validation.errors[0].message.contains("b → c"));
+    }
+
+    #[test]
+    fn rejects_self_loops_without_exit() {
+        let validation = validate_task_chain(&chain("a", json!({
+            "a": wait("a", Some("a")),
+        }))).unwrap();
+
+        assert!(!validation.valid);
+        assert_eq!(issue_ids(&validation.errors), vec!["a"]);
+    }
+
+    #[test]
+    fn accepts_cycles_with_a_condition_exit() {
+        let validation = validate_task_chain(&chain("check", json!({
+            "check": {
+                "id": "check",
+                "type": "condition",
+                "condition": { "type": "equals", "field": "status", "value": "failed" },
+                "trueBranch": "retry",
+            },
+            "retry": wait("retry", Some("check")),
+        }))).unwrap();
+
+        assert!(validation.valid, "{:?}", validation.errors);
+    }
+
+    #[test]
+    fn reports_missing_references_and_branches_that_never_join() {
+        let validation = validate_task_chain(&chain("split", json!({
+            "split": { "id": "split", "type": "parallel", "branches": ["left", "right"], "joinNodeId": "join" },
+            "left": wait("left", Some("join")),
+            "right": wait("right", Some("missing")),
+            "join": { "id": "join", "type": "join" },
+        }))).unwrap();
+
+        assert!(!validation.valid);
+        let messages: Vec<&str> = validation.errors.iter().map(|e| e.message.as_str()).collect();
+        assert!(messages.iter().any(|m| m.contains("参照先ノード missing")));
+        assert!(messages.iter().any(|m| m.contains("分岐 right")));
+        assert!(!messages.iter().any(|m| m.contains("分岐 left")));
+    }
+
+    #[test]
+    fn reports_missing_start_node_and_unparseable_nodes() {
+        let validation = validate_task_chain(&chain("start", json!({
+            "a": { "id": "a", "type": "unknown" },
+        }))).unwrap();
+
+        assert!(!validation.valid);
+        assert_eq!(validation.errors.len(), 2);
+
+        let validation = validate_task_chain(&chain("a", json!([]))).unwrap();
+        assert!(!validation.valid);
+    }
+}
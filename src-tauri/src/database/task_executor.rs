@@ -0,0 +1,559 @@
+/**
+ * タスク実行エンジン
+ * tasks / taskExecutions をRust側（tokio）で実行する。ウィンドウを閉じたり画面を再読み込みしても実行は続く
+ *
+ * - 実行待ちのキューは taskExecutions の status = 'pending' の行。enqueue_task_execution で追加する
+ * - 優先度（priority の大きい順）→ 登録順に取り出し、同時に MAX_CONCURRENT_EXECUTIONS 件まで実行する
+ * - dependencies のタスクは最新の実行が completed になるまで待つ。failed / cancelled の場合や一度も実行されていない場合は失敗にする
+ * - Agent（task.agentId）のシステムプロンプトと、タスク → Agent → デフォルトの順で決めたモデルでLLMを呼び出す
+ * - timeout（ミリ秒、デフォルト60秒）を超えたら失敗にし、retryCount 回まで Agent の retryPolicy に従って再試行する
+ * - 状態遷移（pending → running → completed / failed / cancelled）とログは書き込みキュー経由で保存し、Tauriイベントで通知する
+ * - 起動時に running のまま残っている実行（前回の終了で中断されたもの）は pending に戻して再実行する
+ */
+
+use crate::database::llm::{LlmClient, DEFAULT_MODEL, DEFAULT_MODEL_TYPE};
+use crate::database::{get_agent, get_agent_prompt_version, get_db, get_task, get_task_execution, get_timestamp, Agent, Task, TaskExecution, Timestamp, User};
+use crate::db::{WriteJob, WriteQueueState};
+use rusqlite::{params, Result as SqlResult};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{Mutex, Notify, OwnedSemaphorePermit, Semaphore};
+
+/// 進捗通知用のTauriイベント名
+pub const TASK_EXECUTION_EVENT: &str = "task-execution-progress";
+
+/// 同時に実行する最大件数（フロントエンドの Agent の maxConcurrentTasks のデフォルトと同じ）
+const MAX_CONCURRENT_EXECUTIONS: usize = 3;
+/// タイムアウトのデフォルト（ミリ秒）
+const DEFAULT_TIMEOUT_MS: i64 = 60_000;
+/// リトライ間隔のデフォルト（ミリ秒）
+const DEFAULT_RETRY_DELAY_MS: u64 = 1_000;
+/// リトライ間隔の倍率のデフォルト
+const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
+/// 起こされなかった場合にキューを確認する間隔
+const POLL_INTERVAL_SECS: u64 = 5;
+/// Agent未指定のタスクを実行するAgent（フロントエンドの汎用Agentと同じID）
+const DEFAULT_AGENT_ID: &str = "general-agent";
+/// Agent未指定のタスクで使用するシステムプロンプト
+const DEFAULT_SYSTEM_PROMPT: &str = "あなたは汎用AIエージェントです。\nユーザーからのタスクを実行し、適切な結果を返してください。";
+
+/// 実行状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl ExecutionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExecutionStatus::Pending => "pending",
+            ExecutionStatus::Running => "running",
+            ExecutionStatus::Completed => "completed",
+            ExecutionStatus::Failed => "failed",
+            ExecutionStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// 実行の進捗（Tauriイベントで送る）
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskExecutionProgress {
+    #[serde(rename = "executionId")]
+    pub execution_id: String,
+    #[serde(rename = "taskId")]
+    pub task_id: String,
+    pub status: String,
+    /// 試行回数（1回目の実行は1）
+    pub attempt: u32,
+    pub message: Option<String>,
+    pub timestamp: Timestamp,
+}
+
+/// Agent設定のリトライポリシー
+struct RetryPolicy {
+    retry_delay_ms: u64,
+    backoff_multiplier: f64,
+}
+
+impl RetryPolicy {
+    /// Agent の config（JSON文字列）の retryPolicy から取得
+    fn from_agent(agent: Option<&Agent>) -> Self {
+        let policy = agent
+            .and_then(|a| serde_json::from_str::<Value>(&a.config).ok())
+            .and_then(|config| config.get("retryPolicy").cloned())
+            .unwrap_or(Value::Null);
+        Self {
+            retry_delay_ms: policy.get("retryDelay").and_then(|v| v.as_u64()).unwrap_or(DEFAULT_RETRY_DELAY_MS),
+            backoff_multiplier: policy.get("backoffMultiplier").and_then(|v| v.as_f64())
+                .filter(|m| m.is_finite() && *m >= 1.0)
+                .unwrap_or(DEFAULT_BACKOFF_MULTIPLIER),
+        }
+    }
+
+    /// attempt 回目の失敗後に待つ時間
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.backoff_multiplier.powi(attempt.saturating_sub(1) as i32);
+        Duration::from_millis((self.retry_delay_ms as f64 * factor) as u64)
+    }
+}
+
+/// 依存タスクの状態
+enum DependencyState {
+    Ready,
+    Waiting,
+    Failed(String),
+}
+
+/// 実行中の実行ID → キャンセル通知
+static RUNNING: OnceLock<Mutex<HashMap<String, Arc<Notify>>>> = OnceLock::new();
+static TRIGGER: OnceLock<Notify> = OnceLock::new();
+
+fn running() -> &'static Mutex<HashMap<String, Arc<Notify>>> {
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn trigger() -> &'static Notify {
+    TRIGGER.get_or_init(Notify::new)
+}
+
+fn not_initialized() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    )
+}
+
+/// 保存・通知中の実行（ログはJSON文字列ではなく配列で保持する）
+struct ExecutionRun {
+    execution: TaskExecution,
+    logs: Vec<Value>,
+    attempt: u32,
+}
+
+impl ExecutionRun {
+    fn new(execution: TaskExecution) -> Self {
+        let logs = serde_json::from_str::<Vec<Value>>(&execution.logs).unwrap_or_default();
+        Self { execution, logs, attempt: 0 }
+    }
+
+    /// ログを追加（フロントエンドの ExecutionLog と同じ形式）
+    fn log(&mut self, level: &str, message: impl Into<String>) {
+        self.logs.push(json!({
+            "timestamp": Timestamp::now().unix_millis(),
+            "level": level,
+            "message": message.into(),
+        }));
+    }
+
+    fn last_message(&self) -> Option<String> {
+        self.logs.last()
+            .and_then(|log| log.get("message"))
+            .and_then(|m| m.as_str())
+            .map(|s| s.to_string())
+    }
+
+    /// 終了状態にする
+    fn finish(&mut self, status: ExecutionStatus, result: Option<Value>, error: Option<String>) {
+        self.execution.status = status.as_str().to_string();
+        self.execution.completed_at = Some(get_timestamp());
+        self.execution.result = result.map(|r| r.to_string());
+        if let Some(error) = &error {
+            let level = if status == ExecutionStatus::Cancelled { "warn" } else { "error" };
+            self.log(level, error.clone());
+        }
+        self.execution.error = error;
+    }
+
+    /// 書き込みキュー経由で保存し、進捗をTauriイベントで通知
+    async fn save(&mut self, app: &AppHandle, write_queue: &WriteQueueState) {
+        self.execution.logs = serde_json::to_string(&self.logs).unwrap_or_else(|_| "[]".to_string());
+        // バックグラウンドの実行のため実行者は system（actor: None）
+        let job = WriteJob::SaveTaskExecution { execution: self.execution.clone() };
+        if let Err(e) = write_queue.send(job, None).await {
+            eprintln!("[TaskExecutor] ⚠️ 実行履歴の保存に失敗しました: id={}, error={}", self.execution.id, e);
+        }
+
+        let progress = TaskExecutionProgress {
+            execution_id: self.execution.id.clone(),
+            task_id: self.execution.task_id.clone(),
+            status: self.execution.status.clone(),
+            attempt: self.attempt,
+            message: self.last_message(),
+            timestamp: Timestamp::now(),
+        };
+        if let Err(e) = app.emit(TASK_EXECUTION_EVENT, &progress) {
+            eprintln!("[TaskExecutor] ⚠️ 進捗イベントの送信に失敗しました: {}", e);
+        }
+    }
+}
+
+/// タスクを実行キューに追加（pending の実行を作成し、実行エンジンを起こす）
+pub async fn enqueue_task_execution(
+    write_queue: &WriteQueueState,
+    task_id: &str,
+    actor: Option<User>,
+) -> Result<TaskExecution, String> {
+    let task = get_task(task_id)
+        .map_err(|e| format!("タスクの取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("タスクが見つかりません: {}", task_id))?;
+
+    let now = get_timestamp();
+    let logs = json!([{
+        "timestamp": Timestamp::now().unix_millis(),
+        "level": "info",
+        "message": "実行キューに追加しました",
+    }]);
+    let execution = TaskExecution {
+        id: uuid::Uuid::new_v4().to_string(),
+        task_id: task.id.clone(),
+        agent_id: task.agent_id.clone().unwrap_or_else(|| DEFAULT_AGENT_ID.to_string()),
+        status: ExecutionStatus::Pending.as_str().to_string(),
+        started_at: now.clone(),
+        completed_at: None,
+        result: None,
+        error: None,
+        logs: logs.to_string(),
+        prompt_version: None,
+        created_at: now.clone(),
+        updated_at: now,
+    };
+    let execution_id = execution.id.clone();
+    write_queue.send(WriteJob::SaveTaskExecution { execution }, actor).await?;
+    trigger().notify_one();
+
+    get_task_execution(&execution_id)
+        .map_err(|e| format!("タスク実行の取得に失敗しました: {}", e))?
+        .ok_or_else(|| "タスク実行の保存後に取得に失敗しました".to_string())
+}
+
+/// 実行をキャンセル（実行待ちはその場で、実行中はLLMの呼び出しを中断して cancelled にする）
+pub async fn cancel_task_execution(
+    write_queue: &WriteQueueState,
+    execution_id: &str,
+    actor: Option<User>,
+) -> Result<(), String> {
+    // 実行エンジンが同時に取り出さないよう、実行中の一覧をロックしたまま更新する
+    let running = running().lock().await;
+    if let Some(cancel) = running.get(execution_id) {
+        cancel.notify_one();
+        return Ok(());
+    }
+
+    let mut execution = get_task_execution(execution_id)
+        .map_err(|e| format!("タスク実行の取得に失敗しました: {}", e))?
+        .ok_or_else(|| format!("タスク実行が見つかりません: {}", execution_id))?;
+    if execution.status != ExecutionStatus::Pending.as_str() {
+        return Err(format!("タスク実行は既に終了しています: status={}", execution.status));
+    }
+
+    let mut logs = serde_json::from_str::<Vec<Value>>(&execution.logs).unwrap_or_default();
+    logs.push(json!({
+        "timestamp": Timestamp::now().unix_millis(),
+        "level": "warn",
+        "message": "タスクがキャンセルされました",
+    }));
+    execution.status = ExecutionStatus::Cancelled.as_str().to_string();
+    execution.completed_at = Some(get_timestamp());
+    execution.error = Some("タスクがキャンセルされました".to_string());
+    execution.logs = serde_json::to_string(&logs).unwrap_or_else(|_| "[]".to_string());
+    write_queue.send(WriteJob::SaveTaskExecution { execution }, actor).await
+}
+
+/// タスク実行エンジンを起動
+pub fn start_task_executor(app: AppHandle, write_queue: WriteQueueState) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = requeue_interrupted(&app, &write_queue).await {
+            eprintln!("[TaskExecutor] ⚠️ 中断された実行の再登録に失敗しました: {}", e);
+        }
+
+        let slots = Arc::new(Semaphore::new(MAX_CONCURRENT_EXECUTIONS));
+        loop {
+            if let Err(e) = dispatch_pending(&app, &write_queue, &slots).await {
+                eprintln!("[TaskExecutor] ⚠️ 実行待ちのタスクの取得に失敗しました: {}", e);
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+                _ = trigger().notified() => {}
+            }
+        }
+    });
+}
+
+/// 前回の終了で running のまま残った実行を pending に戻す
+async fn requeue_interrupted(app: &AppHandle, write_queue: &WriteQueueState) -> Result<(), String> {
+    let ids = load_execution_ids(ExecutionStatus::Running)
+        .map_err(|e| format!("実行中のタスクの取得に失敗しました: {}", e))?;
+    for id in ids {
+        let Some(execution) = get_task_execution(&id).map_err(|e| e.to_string())? else {
+            continue;
+        };
+        let mut run = ExecutionRun::new(execution);
+        run.execution.status = ExecutionStatus::Pending.as_str().to_string();
+        run.log("warn", "アプリの終了で中断されたため、再実行します");
+        run.save(app, write_queue).await;
+    }
+    Ok(())
+}
+
+/// 実行待ちの実行を優先度順に取り出し、空きがある分だけ実行を始める
+async fn dispatch_pending(
+    app: &AppHandle,
+    write_queue: &WriteQueueState,
+    slots: &Arc<Semaphore>,
+) -> Result<(), String> {
+    let mut running = running().lock().await;
+    if slots.available_permits() == 0 {
+        return Ok(());
+    }
+
+    let ids = load_execution_ids(ExecutionStatus::Pending).map_err(|e| e.to_string())?;
+    for id in ids {
+        if running.contains_key(&id) {
+            continue;
+        }
+        let Some(execution) = get_task_execution(&id).map_err(|e| e.to_string())? else {
+            continue;
+        };
+        let Some(task) = get_task(&execution.task_id).map_err(|e| e.to_string())? else {
+            continue;
+        };
+
+        match check_dependencies(&task).map_err(|e| e.to_string())? {
+            DependencyState::Waiting => continue,
+            DependencyState::Failed(reason) => {
+                let mut run = ExecutionRun::new(execution);
+                run.finish(ExecutionStatus::Failed, None, Some(reason));
+                run.save(app, write_queue).await;
+                continue;
+            }
+            DependencyState::Ready => {}
+        }
+
+        let Ok(permit) = slots.clone().try_acquire_owned() else {
+            break;
+        };
+        let cancel = Arc::new(Notify::new());
+        running.insert(id, cancel.clone());
+
+        let app = app.clone();
+        let write_queue = write_queue.clone();
+        tauri::async_runtime::spawn(async move {
+            let execution_id = execution.id.clone();
+            run_execution(&app, &write_queue, ExecutionRun::new(execution), task, cancel, permit).await;
+            running().lock().await.remove(&execution_id);
+            // 依存していたタスクが実行できるようになった可能性があるため、キューを確認し直す
+            trigger().notify_one();
+        });
+    }
+    Ok(())
+}
+
+/// 指定した状態の実行IDを取得（タスクの優先度の高い順 → 登録順）
+fn load_execution_ids(status: ExecutionStatus) -> SqlResult<Vec<String>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT e.id FROM taskExecutions e
+         JOIN tasks t ON t.id = e.taskId
+         WHERE e.status = ?1
+         ORDER BY t.priority DESC, e.createdAt ASC",
+    )?;
+    let ids = stmt.query_map(params![status.as_str()], |row| row.get(0))?;
+    ids.collect()
+}
+
+/// 依存タスクの最新の実行状態を確認
+fn check_dependencies(task: &Task) -> SqlResult<DependencyState> {
+    let dependencies: Vec<String> = match task.dependencies.as_deref().filter(|d| !d.trim().is_empty()) {
+        Some(json) => match serde_json::from_str(json) {
+            Ok(dependencies) => dependencies,
+            Err(e) => return Ok(DependencyState::Failed(format!("dependencies を解析できません: {}", e))),
+        },
+        None => return Ok(DependencyState::Ready),
+    };
+    if dependencies.is_empty() {
+        return Ok(DependencyState::Ready);
+    }
+
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let mut stmt = conn.prepare(
+        "SELECT status FROM taskExecutions WHERE taskId = ?1 ORDER BY createdAt DESC LIMIT 1",
+    )?;
+
+    let mut waiting = false;
+    for dependency in &dependencies {
+        let status: Option<String> = match stmt.query_row(params![dependency], |row| row.get(0)) {
+            Ok(status) => Some(status),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e),
+        };
+        match status.as_deref() {
+            Some("completed") => {}
+            Some("pending") | Some("running") => waiting = true,
+            Some(status) => {
+                return Ok(DependencyState::Failed(format!("依存タスク {} の実行が完了していません: status={}", dependency, status)));
+            }
+            None => {
+                return Ok(DependencyState::Failed(format!("依存タスク {} が実行されていません", dependency)));
+            }
+        }
+    }
+    Ok(if waiting { DependencyState::Waiting } else { DependencyState::Ready })
+}
+
+/// タスクの内容からLLMへのプロンプトを作成
+fn build_prompt(task: &Task) -> String {
+    let mut prompt = format!("タスク: {}\n種類: {}\n", task.name, task.task_type);
+    if !task.description.trim().is_empty() {
+        prompt.push_str(&format!("\n説明:\n{}\n", task.description));
+    }
+    let parameters = serde_json::from_str::<Value>(&task.parameters).unwrap_or(Value::Null);
+    if parameters.as_object().is_some_and(|p| !p.is_empty()) {
+        let parameters = serde_json::to_string_pretty(&parameters).unwrap_or_default();
+        prompt.push_str(&format!("\nパラメータ:\n{}\n", parameters));
+    }
+    prompt
+}
+
+/// 1件の実行（リトライを含む）
+async fn run_execution(
+    app: &AppHandle,
+    write_queue: &WriteQueueState,
+    mut run: ExecutionRun,
+    task: Task,
+    cancel: Arc<Notify>,
+    _permit: OwnedSemaphorePermit,
+) {
+    let agent = match task.agent_id.as_deref() {
+        Some(agent_id) => match get_agent(agent_id) {
+            Ok(Some(agent)) => Some(agent),
+            Ok(None) => {
+                run.finish(ExecutionStatus::Failed, None, Some(format!("Agent {} が見つかりません", agent_id)));
+                run.save(app, write_queue).await;
+                return;
+            }
+            Err(e) => {
+                run.finish(ExecutionStatus::Failed, None, Some(format!("Agentの取得に失敗しました: {}", e)));
+                run.save(app, write_queue).await;
+                return;
+            }
+        },
+        None => None,
+    };
+
+    // モデルはタスクの指定を優先し、なければAgentの設定を使用（フロントエンドの getModelInfo と同じ）
+    let model_type = task.model_type.clone().filter(|s| !s.is_empty())
+        .or_else(|| agent.as_ref().map(|a| a.model_type.clone()).filter(|s| !s.is_empty()))
+        .unwrap_or_else(|| DEFAULT_MODEL_TYPE.to_string());
+    let model = task.selected_model.clone().filter(|s| !s.is_empty())
+        .or_else(|| agent.as_ref().and_then(|a| a.selected_model.clone()).filter(|s| !s.is_empty()))
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    // システムプロンプトはタスクで固定したバージョンを優先し、なければAgentの現在のバージョンを使用
+    let (system_prompt, prompt_version) = match (agent.as_ref(), task.prompt_version) {
+        (Some(agent), Some(version)) => match get_agent_prompt_version(&agent.id, version) {
+            Ok(Some(pinned)) => (pinned.system_prompt, Some(version)),
+            Ok(None) => {
+                run.finish(ExecutionStatus::Failed, None, Some(format!("Agent {} のプロンプトのバージョン {} が見つかりません", agent.id, version)));
+                run.save(app, write_queue).await;
+                return;
+            }
+            Err(e) => {
+                run.finish(ExecutionStatus::Failed, None, Some(format!("プロンプトのバージョンの取得に失敗しました: {}", e)));
+                run.save(app, write_queue).await;
+                return;
+            }
+        },
+        (Some(agent), None) => (agent.system_prompt.clone(), agent.prompt_version),
+        (None, _) => (String::new(), None),
+    };
+    let system_prompt = Some(system_prompt)
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string());
+    let prompt = build_prompt(&task);
+    let retry_policy = RetryPolicy::from_agent(agent.as_ref());
+    let max_attempts = 1 + task.retry_count.unwrap_or(0).max(0) as u32;
+    let timeout_ms = task.timeout.filter(|t| *t > 0).unwrap_or(DEFAULT_TIMEOUT_MS);
+
+    run.execution.status = ExecutionStatus::Running.as_str().to_string();
+    run.execution.started_at = get_timestamp();
+    run.execution.completed_at = None;
+    run.execution.error = None;
+    run.execution.prompt_version = prompt_version;
+    match prompt_version {
+        Some(version) => run.log("info", format!("タスクを開始しました（モデル: {} / {}、プロンプト: v{}）", model_type, model, version)),
+        None => run.log("info", format!("タスクを開始しました（モデル: {} / {}）", model_type, model)),
+    }
+    run.save(app, write_queue).await;
+
+    let client = match LlmClient::for_model(&model_type, &model) {
+        Ok(client) => client,
+        Err(e) => {
+            run.finish(ExecutionStatus::Failed, None, Some(e));
+            run.save(app, write_queue).await;
+            return;
+        }
+    };
+
+    for attempt in 1..=max_attempts {
+        run.attempt = attempt;
+        let outcome = tokio::select! {
+            result = tokio::time::timeout(
+                Duration::from_millis(timeout_ms as u64),
+                client.complete(&system_prompt, &prompt),
+            ) => match result {
+                Ok(result) => result,
+                Err(_) => Err(format!("タスクがタイムアウトしました（{}ms）", timeout_ms)),
+            },
+            _ = cancel.notified() => {
+                run.finish(ExecutionStatus::Cancelled, None, Some("タスクがキャンセルされました".to_string()));
+                run.save(app, write_queue).await;
+                return;
+            }
+        };
+
+        match outcome {
+            Ok(output) => {
+                let result = json!({
+                    "output": output,
+                    "provider": client.provider(),
+                    "model": client.model(),
+                    "attempts": attempt,
+                });
+                run.log("info", "タスクが完了しました");
+                run.finish(ExecutionStatus::Completed, Some(result), None);
+                run.save(app, write_queue).await;
+                return;
+            }
+            Err(e) if attempt < max_attempts => {
+                let delay = retry_policy.delay(attempt);
+                run.log("warn", format!("実行に失敗しました（{}回目）。{}ms後に再試行します: {}", attempt, delay.as_millis(), e));
+                run.save(app, write_queue).await;
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = cancel.notified() => {
+                        run.finish(ExecutionStatus::Cancelled, None, Some("タスクがキャンセルされました".to_string()));
+                        run.save(app, write_queue).await;
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                run.finish(ExecutionStatus::Failed, None, Some(format!("タスク実行エラー: {}", e)));
+                run.save(app, write_queue).await;
+                return;
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use rusqlite::{params, Result as SqlResult};
+use rusqlite::{params, Connection, Result as SqlResult};
 use serde::{Deserialize, Serialize};
-use crate::database::{get_db, get_timestamp};
+use crate::database::{get_db, get_timestamp, snapshot_row, audit_change, AuditAction};
 use serde_json;
 use uuid::Uuid;
 
@@ -168,6 +168,23 @@ pub fn save_theme(theme: &Theme) -> SqlResult<Theme> {
     })?;
 
     let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    save_theme_in(&tx, theme)?;
+    tx.commit()?;
+
+    // 更新後のテーマを取得
+    get_theme_by_id(&theme.id)
+        .and_then(|opt| opt.ok_or_else(|| {
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+                Some("テーマの保存後に取得に失敗しました".to_string()),
+            )
+        }))
+}
+
+/// テーマを呼び出し側のトランザクション内で作成または更新（書き込みワーカー用）
+pub(crate) fn save_theme_in(conn: &Connection, theme: &Theme) -> SqlResult<()> {
+    let before = snapshot_row(conn, "themes", &theme.id)?;
     let now = get_timestamp();
 
     // initiativeIdsをJSON文字列に変換
@@ -235,18 +252,19 @@ pub fn save_theme(theme: &Theme) -> SqlResult<Theme> {
         )?;
     }
 
-    // 更新後のテーマを取得
-    get_theme_by_id(&theme.id)
-        .and_then(|opt| opt.ok_or_else(|| {
-            rusqlite::Error::SqliteFailure(
-                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
-                Some("テーマの保存後に取得に失敗しました".to_string()),
-            )
-        }))
+    let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+    audit_change(conn, action, "themes", &theme.id, before)?;
+
+    Ok(())
 }
 
 /// テーマを作成（IDは自動生成）
 pub fn create_theme(title: String, description: Option<String>) -> SqlResult<Theme> {
+    save_theme(&new_theme(title, description))
+}
+
+/// 新規作成するテーマを組み立てる（IDを生成。書き込みキュー経由で保存する場合に使用）
+pub fn new_theme(title: String, description: Option<String>) -> Theme {
     use std::time::{SystemTime, UNIX_EPOCH};
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -257,7 +275,7 @@ pub fn create_theme(title: String, description: Option<String>) -> SqlResult<The
         Uuid::new_v4().to_string().replace("-", "").chars().take(8).collect::<String>()
     );
 
-    let theme = Theme {
+    Theme {
         id: theme_id,
         title,
         description,
@@ -265,9 +283,7 @@ pub fn create_theme(title: String, description: Option<String>) -> SqlResult<The
         position: None, // 新規作成時はpositionを自動設定（save_theme内で処理）
         created_at: None,
         updated_at: None,
-    };
-
-    save_theme(&theme)
+    }
 }
 
 /// テーマを削除
@@ -280,12 +296,24 @@ pub fn delete_theme(id: &str) -> SqlResult<()> {
     })?;
 
     let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    delete_theme_in(&tx, id)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// テーマを呼び出し側のトランザクション内で削除（書き込みワーカー用）
+pub(crate) fn delete_theme_in(conn: &Connection, id: &str) -> SqlResult<()> {
+    let before = snapshot_row(conn, "themes", id)?;
 
     conn.execute(
         "DELETE FROM themes WHERE id = ?1",
         params![id],
     )?;
 
+    audit_change(conn, AuditAction::Delete, "themes", id, before)?;
+
     Ok(())
 }
 
@@ -300,21 +328,7 @@ pub fn update_theme_positions(updates: &[(String, i32)]) -> SqlResult<()> {
 
     let conn = db.get_connection()?;
     let tx = conn.unchecked_transaction()?;
-    let now = get_timestamp();
-
-    println!("🔄 [update_theme_positions] 更新開始: {}件", updates.len());
-    
-    // 各テーマのpositionを更新
-    // フロントエンドから送られてきた順序をそのまま使用（既に1から始まる連番）
-    for (theme_id, position) in updates {
-        println!("  📝 テーマID: {}, position: {} に更新", theme_id, position);
-        let rows_affected = tx.execute(
-            "UPDATE themes SET position = ?1, updatedAt = ?2 WHERE id = ?3",
-            params![position, now, theme_id],
-        )?;
-        println!("  ✅ {}行が更新されました", rows_affected);
-    }
-
+    update_theme_positions_in(&tx, updates)?;
     tx.commit()?;
     println!("✅ [update_theme_positions] コミット完了");
     
@@ -331,3 +345,27 @@ pub fn update_theme_positions(updates: &[(String, i32)]) -> SqlResult<()> {
 
     Ok(())
 }
+
+/// 複数のテーマのpositionを呼び出し側のトランザクション内で更新（書き込みワーカー用）
+pub(crate) fn update_theme_positions_in(conn: &Connection, updates: &[(String, i32)]) -> SqlResult<()> {
+    let now = get_timestamp();
+
+    println!("🔄 [update_theme_positions] 更新開始: {}件", updates.len());
+    
+    // 各テーマのpositionを更新
+    // フロントエンドから送られてきた順序をそのまま使用（既に1から始まる連番）
+    for (theme_id, position) in updates {
+        println!("  📝 テーマID: {}, position: {} に更新", theme_id, position);
+        let before = snapshot_row(conn, "themes", theme_id)?;
+        let rows_affected = conn.execute(
+            "UPDATE themes SET position = ?1, updatedAt = ?2 WHERE id = ?3",
+            params![position, now, theme_id],
+        )?;
+        if rows_affected > 0 {
+            audit_change(conn, AuditAction::Update, "themes", theme_id, before)?;
+        }
+        println!("  ✅ {}行が更新されました", rows_affected);
+    }
+
+    Ok(())
+}
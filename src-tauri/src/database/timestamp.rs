@@ -0,0 +1,260 @@
+/**
+ * タイムスタンプ型
+ *
+ * - 保存形式は RFC 3339（UTC・ミリ秒精度、例: 2024-01-01T00:00:00.000Z）に統一する
+ * - 固定長の文字列なので、SQLite上でも文字列比較で時刻の大小を比較できる
+ * - 読み込み時は旧形式（Unix秒・Unixミリ秒・"YYYY-MM-DD HH:MM:SS" など）も解釈する
+ * - 解釈できない値は現在時刻で置き換えず、エラーまたは None として扱う
+ */
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, SecondsFormat, SubsecRound, TimeZone, Utc};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// これ以上の絶対値を持つ整数はUnixミリ秒とみなす（秒なら西暦5138年以降に相当）
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// UTC・ミリ秒精度のタイムスタンプ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(DateTime<Utc>);
+
+impl Timestamp {
+    /// 現在時刻（ミリ秒未満は切り捨て）
+    pub fn now() -> Self {
+        Self::from_date_time(Utc::now())
+    }
+
+    /// Unixミリ秒から生成（表現できる範囲外の場合は None）
+    pub fn from_unix_millis(millis: i64) -> Option<Self> {
+        Utc.timestamp_millis_opt(millis).single().map(Timestamp)
+    }
+
+    /// Unix秒またはUnixミリ秒（旧形式）から生成（表現できる範囲外の場合は None）
+    pub fn from_unix_number(value: i64) -> Option<Self> {
+        if value.unsigned_abs() >= MILLIS_THRESHOLD as u64 {
+            Self::from_unix_millis(value)
+        } else {
+            Self::from_unix_millis(value.checked_mul(1000)?)
+        }
+    }
+
+    /// 文字列をパース（RFC 3339 と旧形式を受け付ける）
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        if let Ok(number) = value.parse::<i64>() {
+            return Self::from_unix_number(number);
+        }
+        if let Ok(seconds) = value.parse::<f64>() {
+            return Self::from_unix_seconds_f64(seconds);
+        }
+        if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+            return Some(Self::from_date_time(date_time.with_timezone(&Utc)));
+        }
+        // タイムゾーンなしの日時はUTCとみなす
+        for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+            if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+                return Some(Self::from_date_time(naive.and_utc()));
+            }
+        }
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|naive| Self::from_date_time(naive.and_utc()))
+    }
+
+    /// JSONの値から生成（文字列・数値・Firestore形式 { seconds, nanoseconds } を受け付ける）
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Self::parse(s),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Self::from_unix_number(i),
+                None => n.as_f64().and_then(Self::from_unix_seconds_f64),
+            },
+            Value::Object(map) => {
+                let seconds = map.get("seconds").and_then(|s| s.as_i64())?;
+                let nanoseconds = map.get("nanoseconds").and_then(|n| n.as_i64()).unwrap_or(0);
+                Self::from_unix_millis(seconds.checked_mul(1000)?.checked_add(nanoseconds / 1_000_000)?)
+            }
+            _ => None,
+        }
+    }
+
+    /// 小数のUnix秒から生成（NaN・無限大・範囲外の場合は None）
+    fn from_unix_seconds_f64(seconds: f64) -> Option<Self> {
+        let millis = (seconds * 1000.0).round();
+        if !millis.is_finite() || millis < i64::MIN as f64 || millis > i64::MAX as f64 {
+            return None;
+        }
+        Self::from_unix_millis(millis as i64)
+    }
+
+    /// ミリ秒未満を切り捨てて生成
+    fn from_date_time(date_time: DateTime<Utc>) -> Self {
+        Timestamp(date_time.trunc_subsecs(3))
+    }
+
+    /// 保存形式（RFC 3339・UTC・ミリ秒精度）の文字列
+    pub fn to_rfc3339(&self) -> String {
+        self.0.to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+
+    pub fn unix_seconds(&self) -> i64 {
+        self.0.timestamp()
+    }
+
+    pub fn unix_millis(&self) -> i64 {
+        self.0.timestamp_millis()
+    }
+
+    /// Firestore形式（{ seconds, nanoseconds }）
+    pub fn to_firestore(&self) -> HashMap<String, i64> {
+        let mut ts = HashMap::new();
+        ts.insert("seconds".to_string(), self.0.timestamp());
+        ts.insert("nanoseconds".to_string(), self.0.timestamp_subsec_nanos() as i64);
+        ts
+    }
+
+    pub fn add_seconds(&self, seconds: i64) -> Self {
+        Timestamp(self.0 + Duration::seconds(seconds))
+    }
+
+    pub fn sub_seconds(&self, seconds: i64) -> Self {
+        Timestamp(self.0 - Duration::seconds(seconds))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_rfc3339())
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_rfc3339())
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Self::from_json(&value).ok_or_else(|| {
+            serde::de::Error::custom(format!("タイムスタンプとして解釈できません: {}", value))
+        })
+    }
+}
+
+impl ToSql for Timestamp {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_rfc3339()))
+    }
+}
+
+impl FromSql for Timestamp {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(i) => Self::from_unix_number(i).ok_or(FromSqlError::OutOfRange(i)),
+            ValueRef::Real(f) => Self::from_unix_seconds_f64(f).ok_or_else(|| {
+                FromSqlError::Other(format!("タイムスタンプとして解釈できません: {}", f).into())
+            }),
+            ValueRef::Text(bytes) => {
+                let text = std::str::from_utf8(bytes).map_err(|e| FromSqlError::Other(Box::new(e)))?;
+                Self::parse(text).ok_or_else(|| {
+                    FromSqlError::Other(format!("タイムスタンプとして解釈できません: {}", text).into())
+                })
+            }
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NEW_YEAR_2024: &str = "2024-01-01T00:00:00.000Z";
+
+    #[test]
+    fn parses_unix_seconds_and_milliseconds() {
+        assert_eq!(Timestamp::parse("1704067200").unwrap().to_rfc3339(), NEW_YEAR_2024);
+        assert_eq!(Timestamp::parse("1704067200000").unwrap().to_rfc3339(), NEW_YEAR_2024);
+        assert_eq!(Timestamp::parse("1704067200.5").unwrap().to_rfc3339(), "2024-01-01T00:00:00.500Z");
+        assert_eq!(Timestamp::from_unix_number(1_704_067_200).unwrap().unix_millis(), 1_704_067_200_000);
+        assert_eq!(Timestamp::from_unix_number(1_704_067_200_123).unwrap().unix_millis(), 1_704_067_200_123);
+    }
+
+    #[test]
+    fn parses_iso_strings() {
+        assert_eq!(Timestamp::parse(NEW_YEAR_2024).unwrap().to_rfc3339(), NEW_YEAR_2024);
+        assert_eq!(Timestamp::parse("2024-01-01T09:00:00+09:00").unwrap().to_rfc3339(), NEW_YEAR_2024);
+        assert_eq!(Timestamp::parse("2024-01-01 00:00:00").unwrap().to_rfc3339(), NEW_YEAR_2024);
+        assert_eq!(Timestamp::parse("2024-01-01T00:00:00.250").unwrap().to_rfc3339(), "2024-01-01T00:00:00.250Z");
+        assert_eq!(Timestamp::parse(" 2024-01-01 ").unwrap().to_rfc3339(), NEW_YEAR_2024);
+    }
+
+    #[test]
+    fn rejects_unparseable_values() {
+        assert!(Timestamp::parse("").is_none());
+        assert!(Timestamp::parse("yesterday").is_none());
+        assert!(Timestamp::parse("NaN").is_none());
+        assert!(Timestamp::parse("2024-13-01").is_none());
+        assert!(Timestamp::from_json(&json!(true)).is_none());
+    }
+
+    #[test]
+    fn rejects_out_of_range_values_instead_of_using_the_epoch() {
+        assert!(Timestamp::from_unix_millis(i64::MAX).is_none());
+        assert!(Timestamp::from_unix_number(i64::MIN).is_none());
+        assert!(Timestamp::parse("9223372036854775807").is_none());
+        assert!(Timestamp::parse("1e300").is_none());
+        assert!(Timestamp::from_json(&json!({ "seconds": i64::MAX, "nanoseconds": 0 })).is_none());
+
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let out_of_range: rusqlite::Result<Timestamp> =
+            conn.query_row("SELECT 9223372036854775807", [], |row| row.get(0));
+        assert!(out_of_range.is_err());
+    }
+
+    #[test]
+    fn reads_json_and_firestore_values() {
+        let expected = Timestamp::parse(NEW_YEAR_2024).unwrap();
+        assert_eq!(Timestamp::from_json(&json!(1_704_067_200)), Some(expected));
+        assert_eq!(Timestamp::from_json(&json!(NEW_YEAR_2024)), Some(expected));
+        assert_eq!(
+            Timestamp::from_json(&json!({ "seconds": 1_704_067_200, "nanoseconds": 500_000_000 })).unwrap().to_rfc3339(),
+            "2024-01-01T00:00:00.500Z"
+        );
+        let firestore = expected.to_firestore();
+        assert_eq!(firestore.get("seconds"), Some(&1_704_067_200));
+        assert_eq!(firestore.get("nanoseconds"), Some(&0));
+    }
+
+    #[test]
+    fn orders_like_the_stored_strings() {
+        let earlier = Timestamp::parse("2024-01-01T00:00:00Z").unwrap();
+        let later = earlier.add_seconds(90);
+        assert!(earlier < later);
+        assert!(earlier.to_rfc3339() < later.to_rfc3339());
+        assert_eq!(later.sub_seconds(90), earlier);
+    }
+
+    #[test]
+    fn round_trips_through_sqlite() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        let ts = Timestamp::parse(NEW_YEAR_2024).unwrap();
+        let stored: String = conn.query_row("SELECT ?1", [ts], |row| row.get(0)).unwrap();
+        assert_eq!(stored, NEW_YEAR_2024);
+
+        let from_integer: Timestamp = conn.query_row("SELECT 1704067200", [], |row| row.get(0)).unwrap();
+        assert_eq!(from_integer, ts);
+        let invalid: rusqlite::Result<Timestamp> = conn.query_row("SELECT 'not a time'", [], |row| row.get(0));
+        assert!(invalid.is_err());
+    }
+}
@@ -0,0 +1,719 @@
+/**
+ * ゴミ箱（論理削除・復元・完全削除）
+ * 組織・議事録・ナレッジ項目の削除は行を消さずに deletedAt を設定し、trash テーブルに1件として記録する
+ *
+ * - 1回の削除で論理削除した行には同じ deletionId を付与し、復元時はその単位でまとめて戻す
+ * - 通常の取得処理は deletedAt IS NULL の行のみを対象にする
+ * - 復元した行は chromaSynced = 0 に戻し、再同期ワーカーでベクトルを作り直す
+ * - 保持期間（環境変数 TRASH_RETENTION_DAYS、デフォルト30日）を過ぎた項目は定期的に完全削除する
+ */
+
+use crate::database::search::SearchTarget;
+use crate::database::vector_store::get_vector_store;
+use crate::database::{get_current_user, get_db, trigger_chroma_sync, record_audit, snapshot_row, AuditAction, Timestamp};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult, Row, ToSql};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// 論理削除に対応したテーブル
+pub(crate) const SOFT_DELETE_TABLES: &[&str] = &[
+    "organizations",
+    "organizationMembers",
+    "organizationContents",
+    "companyContents",
+    "focusInitiatives",
+    "meetingNotes",
+    "entities",
+    "relations",
+    "topics",
+    "organizationCompanyDisplay",
+];
+
+/// 1件ずつゴミ箱に移動できるテーブル（組織・議事録は関連データもまとめて移動する）
+const SINGLE_ITEM_TABLES: &[&str] = &[
+    "organizationMembers",
+    "organizationContents",
+    "companyContents",
+    "focusInitiatives",
+    "entities",
+    "relations",
+    "topics",
+];
+
+/// 保持期間のデフォルト（日）
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+/// 起動直後の待機時間
+const PURGE_STARTUP_DELAY_SECS: u64 = 120;
+/// 期限切れ項目の完全削除を実行する間隔
+const PURGE_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+/// ゴミ箱の項目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    pub id: String,
+    /// organization / meetingNote / 単体で削除した行のテーブル名
+    #[serde(rename = "itemType")]
+    pub item_type: String,
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    pub name: Option<String>,
+    #[serde(rename = "organizationId")]
+    pub organization_id: Option<String>,
+    #[serde(rename = "deletedBy")]
+    pub deleted_by: Option<String>,
+    #[serde(rename = "deletedByEmail")]
+    pub deleted_by_email: Option<String>,
+    /// 削除日時
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: Timestamp,
+    /// まとめて論理削除した行数（子組織・メンバー・議事録なども含む）
+    #[serde(rename = "itemCount")]
+    pub item_count: i64,
+    /// 完全削除される日時
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Timestamp,
+}
+
+/// 完全削除した行に対応する埋め込み（ベクトルストアから削除する）
+#[derive(Debug, Default)]
+pub struct PurgedEmbeddings {
+    /// コレクションごと削除する組織
+    pub organization_ids: Vec<String>,
+    /// 個別に削除する行（対象, ID, 組織ID）
+    pub items: Vec<(SearchTarget, String, String)>,
+}
+
+impl PurgedEmbeddings {
+    fn extend(&mut self, other: PurgedEmbeddings) {
+        self.organization_ids.extend(other.organization_ids);
+        self.items.extend(other.items);
+    }
+}
+
+fn not_initialized() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    )
+}
+
+fn constraint_error(message: &str) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+        Some(message.to_string()),
+    )
+}
+
+/// 論理削除に対応したテーブルか
+pub(crate) fn is_soft_delete_table(table: &str) -> bool {
+    SOFT_DELETE_TABLES.contains(&table)
+}
+
+/// ゴミ箱の保持期間（日）を取得（環境変数 TRASH_RETENTION_DAYS）
+pub fn trash_retention_days() -> i64 {
+    std::env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+fn row_to_trash_item(row: &Row) -> SqlResult<TrashItem> {
+    let deleted_at: Timestamp = row.get(7)?;
+    Ok(TrashItem {
+        id: row.get(0)?,
+        item_type: row.get(1)?,
+        item_id: row.get(2)?,
+        name: row.get(3)?,
+        organization_id: row.get(4)?,
+        deleted_by: row.get(5)?,
+        deleted_by_email: row.get(6)?,
+        deleted_at,
+        item_count: row.get(8)?,
+        expires_at: deleted_at.add_seconds(trash_retention_days() * 24 * 60 * 60),
+    })
+}
+
+const TRASH_COLUMNS: &str =
+    "id, itemType, itemId, name, organizationId, deletedBy, deletedByEmail, deletedAt, itemCount";
+
+/// 行のJSONから表示名・所属組織を取り出す
+fn describe_row(row: &Value) -> (Option<String>, Option<String>) {
+    let text = |key: &str| row.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(|s| s.to_string());
+    (
+        text("name").or_else(|| text("title")),
+        text("organizationId").or_else(|| text("companyId")),
+    )
+}
+
+/// 1回の削除操作（同じ deletionId を付与した行をまとめてゴミ箱の1項目にする）
+struct Deletion<'a> {
+    conn: &'a Connection,
+    id: String,
+    deleted_at: Timestamp,
+    count: i64,
+}
+
+impl<'a> Deletion<'a> {
+    fn new(conn: &'a Connection) -> Self {
+        Self { conn, id: Uuid::new_v4().to_string(), deleted_at: Timestamp::now(), count: 0 }
+    }
+
+    /// 条件に一致する未削除の行を論理削除し、監査ログに記録
+    /// table と condition は呼び出し側で固定したテーブル名・条件であること
+    fn mark(&mut self, table: &str, condition: &str, params: &[&dyn ToSql]) -> SqlResult<usize> {
+        let ids: Vec<String> = {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT id FROM {} WHERE ({}) AND deletedAt IS NULL",
+                table, condition
+            ))?;
+            let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+            rows.collect::<SqlResult<Vec<_>>>()?
+        };
+
+        for id in &ids {
+            let before = snapshot_row(self.conn, table, id)?;
+            self.conn.execute(
+                &format!("UPDATE {} SET deletedAt = ?1, deletionId = ?2 WHERE id = ?3", table),
+                params![self.deleted_at, self.id, id],
+            )?;
+            record_audit(self.conn, AuditAction::Delete, table, id, before.as_ref(), None)?;
+        }
+        self.count += ids.len() as i64;
+        Ok(ids.len())
+    }
+
+    /// ゴミ箱に項目を追加
+    fn finish(self, item_type: &str, item_id: &str, name: Option<String>, organization_id: Option<String>) -> SqlResult<TrashItem> {
+        let actor = get_current_user();
+        self.conn.execute(
+            "INSERT INTO trash (id, itemType, itemId, name, organizationId, deletedBy, deletedByEmail, deletedAt, itemCount)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                self.id,
+                item_type,
+                item_id,
+                name,
+                organization_id,
+                actor.as_ref().map(|u| u.uid.as_str()),
+                actor.as_ref().map(|u| u.email.as_str()),
+                self.deleted_at,
+                self.count,
+            ],
+        )?;
+        Ok(TrashItem {
+            id: self.id,
+            item_type: item_type.to_string(),
+            item_id: item_id.to_string(),
+            name,
+            organization_id,
+            deleted_by: actor.as_ref().map(|u| u.uid.clone()),
+            deleted_by_email: actor.as_ref().map(|u| u.email.clone()),
+            deleted_at: self.deleted_at,
+            item_count: self.count,
+            expires_at: self.deleted_at.add_seconds(trash_retention_days() * 24 * 60 * 60),
+        })
+    }
+}
+
+/// 組織をゴミ箱に移動（子組織・メンバー・コンテンツ・注力施策・議事録・ナレッジ項目・表示関係もまとめて論理削除）
+/// 組織が存在しないか既に削除されている場合は None
+pub fn trash_organization(id: &str) -> SqlResult<Option<TrashItem>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    let item = trash_organization_in(&tx, id)?;
+    tx.commit()?;
+    Ok(item)
+}
+
+/// 組織を呼び出し側のトランザクション内でゴミ箱に移動（書き込みワーカー用）
+pub(crate) fn trash_organization_in(conn: &Connection, id: &str) -> SqlResult<Option<TrashItem>> {
+    let org: Option<(String, Option<String>)> = conn.query_row(
+        "SELECT name, parentId FROM organizations WHERE id = ?1 AND deletedAt IS NULL",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional()?;
+    let (name, parent_id) = match org {
+        Some(org) => org,
+        None => return Ok(None),
+    };
+
+    // 子組織を再帰的に取得（既に削除済みの子組織は別の項目としてゴミ箱に残す）
+    let org_ids: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT ?1
+                UNION ALL
+                SELECT o.id FROM organizations o JOIN subtree s ON o.parentId = s.id
+                WHERE o.deletedAt IS NULL
+             )
+             SELECT id FROM subtree",
+        )?;
+        let rows = stmt.query_map(params![id], |row| row.get::<_, String>(0))?;
+        rows.collect::<SqlResult<Vec<_>>>()?
+    };
+
+    let mut deletion = Deletion::new(conn);
+    for org_id in &org_ids {
+        let p: &[&dyn ToSql] = &[org_id];
+        deletion.mark("organizationMembers", "organizationId = ?1", p)?;
+        deletion.mark("organizationContents", "organizationId = ?1", p)?;
+        deletion.mark("companyContents", "companyId = ?1", p)?;
+        deletion.mark("focusInitiatives", "organizationId = ?1 OR companyId = ?1", p)?;
+        deletion.mark("relations", "organizationId = ?1 OR companyId = ?1", p)?;
+        deletion.mark("topics", "organizationId = ?1 OR companyId = ?1", p)?;
+        deletion.mark("entities", "organizationId = ?1 OR companyId = ?1", p)?;
+        deletion.mark("meetingNotes", "organizationId = ?1 OR companyId = ?1", p)?;
+        deletion.mark("organizationCompanyDisplay", "organizationId = ?1 OR companyId = ?1", p)?;
+        deletion.mark("organizations", "id = ?1", p)?;
+    }
+
+    let item = deletion.finish("organization", id, Some(name), parent_id)?;
+    Ok(Some(item))
+}
+
+/// 議事録をゴミ箱に移動（議事録のトピックとリレーションもまとめて論理削除）
+/// 議事録が存在しないか既に削除されている場合は None
+pub fn trash_meeting_note(note_id: &str) -> SqlResult<Option<TrashItem>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    let item = trash_meeting_note_in(&tx, note_id)?;
+    tx.commit()?;
+    Ok(item)
+}
+
+/// 議事録を呼び出し側のトランザクション内でゴミ箱に移動（書き込みワーカー用）
+pub(crate) fn trash_meeting_note_in(conn: &Connection, note_id: &str) -> SqlResult<Option<TrashItem>> {
+    let note: Option<(Option<String>, Option<String>)> = conn.query_row(
+        "SELECT title, COALESCE(organizationId, companyId) FROM meetingNotes WHERE id = ?1 AND deletedAt IS NULL",
+        params![note_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional()?;
+    let (title, organization_id) = match note {
+        Some(note) => note,
+        None => return Ok(None),
+    };
+
+    let mut deletion = Deletion::new(conn);
+    let p: &[&dyn ToSql] = &[&note_id];
+    deletion.mark("relations", "topicId IN (SELECT id FROM topics WHERE meetingNoteId = ?1)", p)?;
+    deletion.mark("topics", "meetingNoteId = ?1", p)?;
+    deletion.mark("meetingNotes", "id = ?1", p)?;
+
+    let item = deletion.finish("meetingNote", note_id, title, organization_id)?;
+    Ok(Some(item))
+}
+
+/// 1件の行をゴミ箱に移動（メンバー・コンテンツ・注力施策・エンティティ・リレーション・トピック）
+/// 行が存在しないか既に削除されている場合は None
+pub fn trash_item(table: &str, id: &str) -> SqlResult<Option<TrashItem>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+    let item = trash_item_in(&tx, table, id)?;
+    tx.commit()?;
+    Ok(item)
+}
+
+/// 1件の行を呼び出し側のトランザクション内でゴミ箱に移動（書き込みワーカー用）
+pub(crate) fn trash_item_in(conn: &Connection, table: &str, id: &str) -> SqlResult<Option<TrashItem>> {
+    match table {
+        "organizations" => return trash_organization_in(conn, id),
+        "meetingNotes" => return trash_meeting_note_in(conn, id),
+        _ if !SINGLE_ITEM_TABLES.contains(&table) => {
+            return Err(constraint_error(&format!("ゴミ箱に移動できないテーブルです: {}", table)));
+        }
+        _ => {}
+    }
+
+    let row = match snapshot_row(conn, table, id)? {
+        Some(row) if row.get("deletedAt").map_or(true, |v| v.is_null()) => row,
+        _ => return Ok(None),
+    };
+    let (name, organization_id) = describe_row(&row);
+
+    let mut deletion = Deletion::new(conn);
+    deletion.mark(table, "id = ?1", &[&id])?;
+
+    let item = deletion.finish(table, id, name, organization_id)?;
+    Ok(Some(item))
+}
+
+/// ゴミ箱の項目一覧を取得（新しい順、item_type で絞り込み可能）
+pub fn list_trash(item_type: Option<&str>) -> SqlResult<Vec<TrashItem>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM trash WHERE (?1 IS NULL OR itemType = ?1) ORDER BY deletedAt DESC, rowid DESC",
+        TRASH_COLUMNS
+    ))?;
+    let items = stmt.query_map(params![item_type], row_to_trash_item)?;
+    items.collect::<Result<Vec<_>, _>>()
+}
+
+/// ゴミ箱の項目を取得
+pub fn get_trash_item(trash_id: &str) -> SqlResult<TrashItem> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    conn.query_row(
+        &format!("SELECT {} FROM trash WHERE id = ?1", TRASH_COLUMNS),
+        params![trash_id],
+        row_to_trash_item,
+    )
+}
+
+/// 復元先の組織が削除されていないことを確認
+fn ensure_parent_available(conn: &Connection, item: &TrashItem) -> SqlResult<()> {
+    let parent_id = match item.organization_id.as_deref() {
+        Some(id) if !id.is_empty() => id,
+        _ => return Ok(()),
+    };
+    let parent_deleted: Option<bool> = conn.query_row(
+        "SELECT deletedAt IS NOT NULL FROM organizations WHERE id = ?1",
+        params![parent_id],
+        |row| row.get(0),
+    ).optional()?;
+    match parent_deleted {
+        Some(true) => Err(constraint_error(&format!(
+            "所属する組織（{}）がゴミ箱にあるため復元できません。先に組織を復元してください",
+            parent_id
+        ))),
+        None if item.item_type == "organization" => Err(constraint_error(&format!(
+            "親組織（{}）が存在しないため復元できません",
+            parent_id
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// ゴミ箱の項目を復元（同じ削除操作で論理削除した行を1つのトランザクションで戻す）
+/// 復元したエンティティ・リレーション・トピックは再同期ワーカーでベクトルを作り直す
+pub fn restore_from_trash(trash_id: &str) -> SqlResult<TrashItem> {
+    let item = get_trash_item(trash_id)?;
+
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+
+    ensure_parent_available(&tx, &item)?;
+
+    let mut resync = false;
+    for table in SOFT_DELETE_TABLES {
+        let ids: Vec<String> = {
+            let mut stmt = tx.prepare(&format!("SELECT id FROM {} WHERE deletionId = ?1", table))?;
+            let rows = stmt.query_map(params![trash_id], |row| row.get::<_, String>(0))?;
+            rows.collect::<SqlResult<Vec<_>>>()?
+        };
+        if ids.is_empty() {
+            continue;
+        }
+
+        let reset_sync = matches!(*table, "entities" | "relations" | "topics");
+        let sql = if reset_sync {
+            format!("UPDATE {} SET deletedAt = NULL, deletionId = NULL, chromaSynced = 0 WHERE id = ?1", table)
+        } else {
+            format!("UPDATE {} SET deletedAt = NULL, deletionId = NULL WHERE id = ?1", table)
+        };
+        for id in &ids {
+            tx.execute(&sql, params![id])?;
+            let after = snapshot_row(&tx, table, id)?;
+            record_audit(&tx, AuditAction::Restore, table, id, None, after.as_ref())?;
+        }
+        resync |= reset_sync;
+    }
+
+    tx.execute("DELETE FROM trash WHERE id = ?1", params![trash_id])?;
+    tx.commit()?;
+
+    if resync {
+        trigger_chroma_sync();
+    }
+    Ok(item)
+}
+
+/// 1項目分の行を完全削除（トランザクション内で呼び出す）
+fn purge_in(conn: &Connection, item: &TrashItem) -> SqlResult<PurgedEmbeddings> {
+    let mut purged = PurgedEmbeddings::default();
+
+    // 子テーブルから順に削除する（SOFT_DELETE_TABLES の逆順）
+    for table in SOFT_DELETE_TABLES.iter().rev() {
+        let ids: Vec<String> = {
+            let mut stmt = conn.prepare(&format!("SELECT id FROM {} WHERE deletionId = ?1", table))?;
+            let rows = stmt.query_map(params![item.id], |row| row.get::<_, String>(0))?;
+            rows.collect::<SqlResult<Vec<_>>>()?
+        };
+
+        for id in &ids {
+            let before = snapshot_row(conn, table, id)?;
+            conn.execute(&format!("DELETE FROM {} WHERE id = ?1", table), params![id])?;
+            record_audit(conn, AuditAction::Purge, table, id, before.as_ref(), None)?;
+
+            let target = match *table {
+                "entities" => Some(SearchTarget::Entity),
+                "relations" => Some(SearchTarget::Relation),
+                "topics" => Some(SearchTarget::Topic),
+                _ => None,
+            };
+            if let (Some(target), Some(org_id)) = (target, before.as_ref().and_then(|b| describe_row(b).1)) {
+                purged.items.push((target, id.clone(), org_id));
+            }
+            if *table == "organizations" {
+                purged.organization_ids.push(id.clone());
+            }
+        }
+    }
+
+    conn.execute("DELETE FROM trash WHERE id = ?1", params![item.id])?;
+    Ok(purged)
+}
+
+/// ゴミ箱の項目を完全削除（戻り値はベクトルストアから削除する埋め込み）
+pub fn purge_trash_item(trash_id: &str) -> SqlResult<PurgedEmbeddings> {
+    let item = get_trash_item(trash_id)?;
+
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+
+    // 外部キー制約を一時的に無効化（古い外部キー制約が残っている可能性があるため）
+    conn.execute("PRAGMA foreign_keys = OFF", [])?;
+    let result = (|| {
+        let tx = conn.unchecked_transaction()?;
+        let purged = purge_in(&tx, &item)?;
+        tx.commit()?;
+        Ok(purged)
+    })();
+    conn.execute("PRAGMA foreign_keys = ON", [])?;
+    result
+}
+
+/// 保持期間を過ぎたゴミ箱の項目をすべて完全削除（戻り値は削除した項目数と埋め込み）
+pub fn purge_expired_trash(retention_days: i64) -> SqlResult<(usize, PurgedEmbeddings)> {
+    let cutoff = Timestamp::now().sub_seconds(retention_days.max(0) * 24 * 60 * 60);
+    let expired: Vec<String> = {
+        let db = get_db().ok_or_else(not_initialized)?;
+        let conn = db.get_connection()?;
+        let mut stmt = conn.prepare("SELECT id FROM trash WHERE deletedAt < ?1 ORDER BY deletedAt ASC")?;
+        let rows = stmt.query_map(params![cutoff], |row| row.get::<_, String>(0))?;
+        rows.collect::<SqlResult<Vec<_>>>()?
+    };
+
+    let mut purged = PurgedEmbeddings::default();
+    for trash_id in &expired {
+        purged.extend(purge_trash_item(trash_id)?);
+    }
+    Ok((expired.len(), purged))
+}
+
+/// 完全削除した行の埋め込みをベクトルストアから削除（失敗しても処理は続行）
+pub async fn remove_purged_embeddings(purged: PurgedEmbeddings) {
+    if purged.organization_ids.is_empty() && purged.items.is_empty() {
+        return;
+    }
+    let store = match get_vector_store() {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("[Trash] ⚠️ ベクトルストアを取得できないため埋め込みを削除できません: {}", e);
+            return;
+        }
+    };
+
+    for org_id in purged.organization_ids {
+        if let Err(e) = store.delete_organization_collections(org_id.clone()).await {
+            eprintln!("[Trash] ⚠️ 組織コレクションの削除に失敗しました: organizationId={}, error={}", org_id, e);
+        }
+    }
+    for (target, id, org_id) in purged.items {
+        let result = match target {
+            SearchTarget::Entity => store.delete_entity_embedding(id.clone(), org_id).await,
+            SearchTarget::Relation => store.delete_relation_embedding(id.clone(), org_id).await,
+            SearchTarget::Topic => store.delete_topic_embedding(id.clone(), org_id).await,
+        };
+        if let Err(e) = result {
+            eprintln!("[Trash] ⚠️ {}埋め込みの削除に失敗しました: id={}, error={}", target.as_str(), id, e);
+        }
+    }
+}
+
+/// 保持期間を過ぎたゴミ箱の項目を定期的に完全削除するワーカーを起動
+pub fn start_trash_purge_worker() {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(PURGE_STARTUP_DELAY_SECS)).await;
+
+        loop {
+            let retention_days = trash_retention_days();
+            match purge_expired_trash(retention_days) {
+                Ok((0, _)) => {}
+                Ok((count, purged)) => {
+                    eprintln!("[Trash] ✅ 保持期間（{}日）を過ぎた{}件を完全削除しました", retention_days, count);
+                    remove_purged_embeddings(purged).await;
+                }
+                Err(e) => eprintln!("[Trash] ⚠️ 期限切れ項目の完全削除に失敗しました: {}", e),
+            }
+
+            tokio::time::sleep(Duration::from_secs(PURGE_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::init_test_database;
+
+    fn insert_organization(conn: &Connection) -> String {
+        let id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO organizations (id, name, level, levelName, createdAt, updatedAt)
+             VALUES (?1, 'テスト組織', 0, '本部', ?2, ?2)",
+            params![id, Timestamp::now()],
+        ).unwrap();
+        id
+    }
+
+    fn insert_meeting_note(conn: &Connection, org_id: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO meetingNotes (id, organizationId, title, createdAt, updatedAt)
+             VALUES (?1, ?2, '定例会議', ?3, ?3)",
+            params![id, org_id, Timestamp::now()],
+        ).unwrap();
+        id
+    }
+
+    fn insert_entity(conn: &Connection, org_id: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO entities (id, name, type, organizationId, chromaSynced, createdAt, updatedAt)
+             VALUES (?1, 'テストエンティティ', 'person', ?2, 1, ?3, ?3)",
+            params![id, org_id, Timestamp::now()],
+        ).unwrap();
+        id
+    }
+
+    /// (論理削除されているか, deletionId)
+    fn deletion_state(conn: &Connection, table: &str, id: &str) -> (bool, Option<String>) {
+        conn.query_row(
+            &format!("SELECT deletedAt IS NOT NULL, deletionId FROM {} WHERE id = ?1", table),
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap()
+    }
+
+    fn audit_count(conn: &Connection, action: AuditAction, id: &str) -> i64 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM auditLog WHERE action = ?1 AND recordId = ?2",
+            params![action.as_str(), id],
+            |row| row.get(0),
+        ).unwrap()
+    }
+
+    fn row_exists(conn: &Connection, table: &str, id: &str) -> bool {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {} WHERE id = ?1", table), params![id], |row| row.get::<_, i64>(0))
+            .unwrap() > 0
+    }
+
+    #[test]
+    fn trashes_and_restores_an_organization_with_its_rows() {
+        let conn = init_test_database().get_connection().unwrap();
+        let org_id = insert_organization(&conn);
+        let note_id = insert_meeting_note(&conn, &org_id);
+        let entity_id = insert_entity(&conn, &org_id);
+
+        let item = trash_organization(&org_id).unwrap().expect("組織がゴミ箱に移動されていません");
+        assert_eq!(item.item_type, "organization");
+        assert_eq!(item.item_count, 3);
+        for (table, id) in [("organizations", &org_id), ("meetingNotes", &note_id), ("entities", &entity_id)] {
+            assert_eq!(deletion_state(&conn, table, id), (true, Some(item.id.clone())), "{}", table);
+        }
+        assert!(list_trash(Some("organization")).unwrap().iter().any(|t| t.id == item.id));
+        // 既に削除済みの組織は再度ゴミ箱に移動しない
+        assert!(trash_organization(&org_id).unwrap().is_none());
+
+        restore_from_trash(&item.id).unwrap();
+        for (table, id) in [("organizations", &org_id), ("meetingNotes", &note_id), ("entities", &entity_id)] {
+            assert_eq!(deletion_state(&conn, table, id), (false, None), "{}", table);
+            assert_eq!(audit_count(&conn, AuditAction::Restore, id), 1, "{}", table);
+        }
+        let synced: i64 = conn
+            .query_row("SELECT chromaSynced FROM entities WHERE id = ?1", params![entity_id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(synced, 0);
+        assert!(get_trash_item(&item.id).is_err());
+    }
+
+    #[test]
+    fn refuses_to_restore_an_item_while_its_organization_is_trashed() {
+        let conn = init_test_database().get_connection().unwrap();
+        let org_id = insert_organization(&conn);
+        let entity_id = insert_entity(&conn, &org_id);
+
+        let entity_item = trash_item("entities", &entity_id).unwrap().unwrap();
+        assert_eq!(entity_item.organization_id.as_deref(), Some(org_id.as_str()));
+        let org_item = trash_organization(&org_id).unwrap().unwrap();
+        // 先に削除したエンティティは組織の削除に含まれない
+        assert_eq!(org_item.item_count, 1);
+
+        assert!(restore_from_trash(&entity_item.id).is_err());
+        assert_eq!(deletion_state(&conn, "entities", &entity_id), (true, Some(entity_item.id.clone())));
+        assert!(get_trash_item(&entity_item.id).is_ok());
+
+        restore_from_trash(&org_item.id).unwrap();
+        restore_from_trash(&entity_item.id).unwrap();
+        assert_eq!(deletion_state(&conn, "entities", &entity_id), (false, None));
+    }
+
+    #[test]
+    fn rejects_tables_without_soft_delete() {
+        init_test_database();
+        assert!(trash_item("users", "u1").is_err());
+        assert!(trash_item("entities", &Uuid::new_v4().to_string()).unwrap().is_none());
+    }
+
+    #[test]
+    fn purges_rows_and_reports_embeddings_to_remove() {
+        let conn = init_test_database().get_connection().unwrap();
+        let org_id = insert_organization(&conn);
+        let entity_id = insert_entity(&conn, &org_id);
+
+        let item = trash_organization(&org_id).unwrap().unwrap();
+        let purged = purge_trash_item(&item.id).unwrap();
+
+        assert_eq!(purged.organization_ids, vec![org_id.clone()]);
+        assert_eq!(purged.items.len(), 1);
+        assert_eq!(purged.items[0], (SearchTarget::Entity, entity_id.clone(), org_id.clone()));
+
+        assert!(!row_exists(&conn, "organizations", &org_id));
+        assert!(!row_exists(&conn, "entities", &entity_id));
+        assert_eq!(audit_count(&conn, AuditAction::Purge, &entity_id), 1);
+        assert!(get_trash_item(&item.id).is_err());
+    }
+
+    #[test]
+    fn purges_only_items_past_the_retention_period() {
+        let conn = init_test_database().get_connection().unwrap();
+        let org_id = insert_organization(&conn);
+        let old_entity = insert_entity(&conn, &org_id);
+        let recent_entity = insert_entity(&conn, &org_id);
+
+        let old_item = trash_item("entities", &old_entity).unwrap().unwrap();
+        let recent_item = trash_item("entities", &recent_entity).unwrap().unwrap();
+        conn.execute(
+            "UPDATE trash SET deletedAt = ?1 WHERE id = ?2",
+            params![Timestamp::now().sub_seconds(40 * 24 * 60 * 60), old_item.id],
+        ).unwrap();
+
+        let (count, purged) = purge_expired_trash(30).unwrap();
+        assert!(count >= 1);
+        assert!(purged.items.iter().any(|(_, id, _)| id == &old_entity));
+        assert!(!purged.items.iter().any(|(_, id, _)| id == &recent_entity));
+        assert!(!row_exists(&conn, "entities", &old_entity));
+        assert!(row_exists(&conn, "entities", &recent_entity));
+        assert!(get_trash_item(&recent_item.id).is_ok());
+    }
+}
@@ -1,38 +1,44 @@
 /**
  * ベクトル検索モジュール
  * RustネイティブのHNSWアルゴリズムを使用した高速ベクトル検索機能を提供
- * 
+ *
  * 使用ライブラリ: hnsw_rs
  * - Rustネイティブで動作（サーバー不要）
  * - ローカルファイルに保存可能（シリアライゼーション対応）
  * - 高速な近似最近傍検索（HNSWアルゴリズム）
  * - コサイン類似度、ユークリッド距離など複数の距離指標をサポート
+ *
+ * 保存形式:
+ * - 組織×コレクションごとに1つのインデックス（例: entities_{orgId}）
+ * - chromadb/hnsw/{コレクション名}.{ハッシュ}.jsonl に追記型ログとして保存（1行目のヘッダーに元のコレクション名を記録）
+ * - 起動時（初回アクセス時）にログを再生してHNSWグラフを再構築
+ * - ファイルの読み書きとインデックスのロックはブロッキング処理のため spawn_blocking で実行する
+ *
+ * バックエンドの切り替え:
+ * - 環境変数 VECTOR_BACKEND=local でこのモジュールを使用（Python不要）
+ * - 未設定または VECTOR_BACKEND=chromadb の場合は従来どおりChromaDBを使用
+ * - 呼び出し側は vector_store::VectorStore トレイト経由で利用する
  */
 
-// 注意: 現在はChromaDBを使用するため、hnsw_rsは使用されていません
-// use hnsw_rs::dist::DistCosine;
-// use hnsw_rs::hnsw::Hnsw;
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use hnsw_rs::prelude::{DistCosine, Hnsw};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-// ベクトル検索インデックスのラッパー
-// 注意: 現在はChromaDBを使用するため、この実装は使用されていません
-#[allow(dead_code)]
-struct VectorIndex {
-    // hnsw: Hnsw<f32, DistCosine>, // コメントアウト
-    id_to_index: HashMap<String, usize>, // エンティティID -> インデックス番号
-    index_to_id: HashMap<usize, String>, // インデックス番号 -> エンティティID
-    metadata: HashMap<String, HashMap<String, Value>>, // エンティティID -> メタデータ
-    next_index: usize,
-}
+use super::vector_store::{TopicSearchResult, VectorBackend, VectorStore};
 
 // コレクション名の定義
 pub const COLLECTION_ENTITIES: &str = "entities";
 pub const COLLECTION_RELATIONS: &str = "relations";
 pub const COLLECTION_TOPICS: &str = "topics";
 pub const COLLECTION_PAGES: &str = "pages";
+pub const COLLECTION_DESIGN_DOCS: &str = "design_docs";
 
 // 埋め込み次元数（text-embedding-3-smallの場合）
 pub const EMBEDDING_DIMENSION: usize = 1536;
@@ -42,125 +48,1116 @@ const MAX_NB_CONNECTION: usize = 16; // Mパラメータ（各ノードの最大
 const NB_LAYERS: usize = 12; // レイヤー数
 const EF_CONSTRUCTION: usize = 200; // 構築時の動的リストサイズ
 const EF_SEARCH: usize = 50; // 検索時の動的リストサイズ
+const INITIAL_CAPACITY: usize = 10_000; // インデックス作成時の想定要素数
 
-// インデックスのストレージ
-// 注意: 現在はChromaDBを使用するため、この実装は使用されていません
-#[allow(dead_code)]
-static VECTOR_INDICES: Mutex<Option<HashMap<String, Arc<Mutex<VectorIndex>>>>> = Mutex::new(None);
+// 削除・上書きで孤立したノードがこの件数を超え、かつ有効件数を上回ったら再構築する
+const REBUILD_THRESHOLD: usize = 256;
 
-/**
- * ベクトル検索インデックスを初期化
- * 
- * @param collection_name コレクション名
- * @param data_dir データディレクトリパス
- */
-// 注意: 現在はChromaDBを使用するため、この実装は使用されていません
-// コンパイルエラーを回避するための空実装
-#[allow(dead_code)]
-pub async fn init_vector_index(
-    _collection_name: &str,
-    _data_dir: PathBuf,
-) -> Result<(), String> {
-    // ChromaDBを使用するため、この実装は使用されていません
-    Ok(())
+// 保存ディレクトリ名（chromadbデータディレクトリ配下）
+const LOCAL_INDEX_DIR_NAME: &str = "hnsw";
+
+// 追記型ログの1レコード
+#[derive(Debug, Serialize, Deserialize)]
+struct LogRecord {
+    op: String, // "header" | "upsert" | "delete"
+    #[serde(default)]
+    id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    embedding: Vec<f32>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    metadata: HashMap<String, Value>,
+    // ヘッダーのみ: 元のコレクション名（ファイル名はパス区切り文字などを置換しているため）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    collection: Option<String>,
 }
 
-/**
- * エンティティ埋め込みを保存
- * 注意: 現在はChromaDBを使用するため、この実装は使用されていません
- */
-#[allow(dead_code)]
-pub async fn save_entity_embedding(
-    _entity_id: String,
-    _organization_id: String,
-    _combined_embedding: Vec<f32>,
-    _metadata: HashMap<String, Value>,
-) -> Result<(), String> {
-    // ChromaDBを使用するため、この実装は使用されていません
-    Ok(())
+impl LogRecord {
+    fn header(collection: &str) -> Self {
+        LogRecord {
+            op: "header".to_string(),
+            id: String::new(),
+            embedding: Vec::new(),
+            metadata: HashMap::new(),
+            collection: Some(collection.to_string()),
+        }
+    }
 }
 
-/**
- * エンティティ埋め込みを取得
- * 注意: 現在はChromaDBを使用するため、この実装は使用されていません
- */
-#[allow(dead_code)]
-pub async fn get_entity_embedding(
-    _entity_id: String,
-) -> Result<Option<(Vec<f32>, HashMap<String, Value>)>, String> {
-    // ChromaDBを使用するため、この実装は使用されていません
-    Ok(None)
+// ベクトル検索インデックスのラッパー
+struct VectorIndex {
+    name: String, // コレクション名（ログのヘッダーに記録する）
+    hnsw: Hnsw<'static, f32, DistCosine>,
+    id_to_index: HashMap<String, usize>, // ドキュメントID -> インデックス番号
+    index_to_id: HashMap<usize, String>, // インデックス番号 -> ドキュメントID
+    embeddings: HashMap<String, Vec<f32>>, // ドキュメントID -> 埋め込み（再構築・取得用）
+    metadata: HashMap<String, HashMap<String, Value>>, // ドキュメントID -> メタデータ
+    next_index: usize,
+    dimension: Option<usize>,
+    log_path: Option<PathBuf>, // Noneの場合はメモリ上のみ（永続化しない）
+    log_records: usize, // ログファイル内のレコード数（圧縮判定用）
 }
 
-/**
- * 類似エンティティを検索
- * 注意: 現在はChromaDBを使用するため、この実装は使用されていません
- */
-#[allow(dead_code)]
-pub async fn find_similar_entities(
-    _query_embedding: Vec<f32>,
-    _limit: usize,
-    _organization_id: Option<String>,
-) -> Result<Vec<(String, f32)>, String> {
-    // ChromaDBを使用するため、この実装は使用されていません
-    Ok(vec![])
+fn new_hnsw(capacity: usize) -> Hnsw<'static, f32, DistCosine> {
+    Hnsw::<f32, DistCosine>::new(
+        MAX_NB_CONNECTION,
+        capacity.max(INITIAL_CAPACITY),
+        NB_LAYERS,
+        EF_CONSTRUCTION,
+        DistCosine {},
+    )
 }
 
-/**
- * リレーション埋め込みを保存
- * 注意: 現在はChromaDBを使用するため、この実装は使用されていません
- */
-#[allow(dead_code)]
-pub async fn save_relation_embedding(
-    _relation_id: String,
-    _organization_id: String,
-    _combined_embedding: Vec<f32>,
-    _metadata: HashMap<String, Value>,
-) -> Result<(), String> {
-    // ChromaDBを使用するため、この実装は使用されていません
-    Ok(())
+impl VectorIndex {
+    /// ログファイルからインデックスを読み込む（存在しない場合は空のインデックス）
+    fn load(name: &str, log_path: Option<PathBuf>) -> Result<Self, String> {
+        let mut embeddings: HashMap<String, Vec<f32>> = HashMap::new();
+        let mut metadata: HashMap<String, HashMap<String, Value>> = HashMap::new();
+        let mut log_records = 0;
+
+        if let Some(log_path) = log_path.as_ref().filter(|p| p.exists()) {
+            let file = File::open(log_path)
+                .map_err(|e| format!("ベクトルインデックスの読み込みに失敗しました: {} ({})", e, log_path.display()))?;
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(|e| format!("ベクトルインデックスの読み込みに失敗しました: {}", e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                // 書き込み途中で終了した最終行などは読み飛ばす
+                let record: LogRecord = match serde_json::from_str(&line) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        eprintln!("⚠️ [vector_search] 破損したレコードをスキップします: {} ({})", e, log_path.display());
+                        continue;
+                    }
+                };
+                if record.op == "header" {
+                    // ファイル名が衝突した別のコレクションのログは読み込まない
+                    if let Some(collection) = record.collection.as_deref().filter(|c| *c != name) {
+                        return Err(format!(
+                            "ベクトルインデックスのコレクション名が一致しません: {} (ファイルのコレクション名: {}, {})",
+                            name, collection, log_path.display()
+                        ));
+                    }
+                    continue;
+                }
+                log_records += 1;
+                match record.op.as_str() {
+                    "upsert" => {
+                        embeddings.insert(record.id.clone(), record.embedding);
+                        metadata.insert(record.id, record.metadata);
+                    }
+                    "delete" => {
+                        embeddings.remove(&record.id);
+                        metadata.remove(&record.id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut index = VectorIndex {
+            name: name.to_string(),
+            hnsw: new_hnsw(embeddings.len() * 2),
+            id_to_index: HashMap::new(),
+            index_to_id: HashMap::new(),
+            embeddings,
+            metadata,
+            next_index: 0,
+            dimension: None,
+            log_path,
+            log_records,
+        };
+        index.rebuild();
+        Ok(index)
+    }
+
+    /// 保持している埋め込みからHNSWグラフを再構築
+    fn rebuild(&mut self) {
+        self.hnsw = new_hnsw(self.embeddings.len() * 2);
+        self.id_to_index.clear();
+        self.index_to_id.clear();
+        self.next_index = 0;
+        self.dimension = self.embeddings.values().next().map(|v| v.len());
+
+        for (id, embedding) in &self.embeddings {
+            self.hnsw.insert_slice((embedding.as_slice(), self.next_index));
+            self.id_to_index.insert(id.clone(), self.next_index);
+            self.index_to_id.insert(self.next_index, id.clone());
+            self.next_index += 1;
+        }
+    }
+
+    /// 削除・上書きで孤立したノード数
+    fn orphan_count(&self) -> usize {
+        self.next_index - self.id_to_index.len()
+    }
+
+    fn len(&self) -> usize {
+        self.id_to_index.len()
+    }
+
+    fn append_log(&mut self, record: &LogRecord) -> Result<(), String> {
+        let log_path = match &self.log_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("ベクトルインデックスディレクトリの作成に失敗しました: {}", e))?;
+        }
+        // 新しいファイルには先頭にヘッダーを書き込む
+        let needs_header = fs::metadata(log_path).map_or(true, |m| m.len() == 0);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)
+            .map_err(|e| format!("ベクトルインデックスの保存に失敗しました: {}", e))?;
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| format!("ベクトルインデックスのシリアライズに失敗しました: {}", e))?;
+        if needs_header {
+            let header = serde_json::to_string(&LogRecord::header(&self.name))
+                .map_err(|e| format!("ベクトルインデックスのシリアライズに失敗しました: {}", e))?;
+            line = format!("{}\n{}", header, line);
+        }
+        writeln!(file, "{}", line)
+            .map_err(|e| format!("ベクトルインデックスの保存に失敗しました: {}", e))?;
+        self.log_records += 1;
+        Ok(())
+    }
+
+    /// 有効なレコードのみでログファイルを書き直す
+    fn compact(&mut self) -> Result<(), String> {
+        let log_path = match &self.log_path {
+            Some(path) => path.clone(),
+            None => return Ok(()),
+        };
+        let tmp_path = log_path.with_extension("jsonl.tmp");
+        {
+            let file = File::create(&tmp_path)
+                .map_err(|e| format!("ベクトルインデックスの圧縮に失敗しました: {}", e))?;
+            let mut writer = BufWriter::new(file);
+            let header = serde_json::to_string(&LogRecord::header(&self.name))
+                .map_err(|e| format!("ベクトルインデックスのシリアライズに失敗しました: {}", e))?;
+            writeln!(writer, "{}", header)
+                .map_err(|e| format!("ベクトルインデックスの圧縮に失敗しました: {}", e))?;
+            for (id, embedding) in &self.embeddings {
+                let record = LogRecord {
+                    op: "upsert".to_string(),
+                    id: id.clone(),
+                    embedding: embedding.clone(),
+                    metadata: self.metadata.get(id).cloned().unwrap_or_default(),
+                    collection: None,
+                };
+                let line = serde_json::to_string(&record)
+                    .map_err(|e| format!("ベクトルインデックスのシリアライズに失敗しました: {}", e))?;
+                writeln!(writer, "{}", line)
+                    .map_err(|e| format!("ベクトルインデックスの圧縮に失敗しました: {}", e))?;
+            }
+            writer.flush()
+                .map_err(|e| format!("ベクトルインデックスの圧縮に失敗しました: {}", e))?;
+        }
+        fs::rename(&tmp_path, &log_path)
+            .map_err(|e| format!("ベクトルインデックスの圧縮に失敗しました: {}", e))?;
+        self.log_records = self.embeddings.len();
+        Ok(())
+    }
+
+    /// 孤立ノードやログが肥大化していれば再構築・圧縮する
+    fn maintain(&mut self) -> Result<(), String> {
+        let live = self.len();
+        if self.orphan_count() > REBUILD_THRESHOLD && self.orphan_count() > live {
+            self.rebuild();
+        }
+        if self.log_path.is_some() && self.log_records > REBUILD_THRESHOLD && self.log_records > live * 2 {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    fn upsert(&mut self, id: &str, embedding: Vec<f32>, metadata: HashMap<String, Value>) -> Result<(), String> {
+        if embedding.is_empty() {
+            return Err("埋め込みベクトルが空です".to_string());
+        }
+        if let Some(dim) = self.dimension {
+            if dim != embedding.len() {
+                return Err(format!(
+                    "埋め込みベクトルの次元数が一致しません: 期待値={}, 実際={}",
+                    dim,
+                    embedding.len()
+                ));
+            }
+        }
+
+        self.append_log(&LogRecord {
+            op: "upsert".to_string(),
+            id: id.to_string(),
+            embedding: embedding.clone(),
+            metadata: metadata.clone(),
+            collection: None,
+        })?;
+
+        // HNSWは削除に対応していないため、上書き時は旧ノードを孤立させて新しいノードを追加
+        if let Some(old_index) = self.id_to_index.remove(id) {
+            self.index_to_id.remove(&old_index);
+        }
+        let index = self.next_index;
+        self.hnsw.insert_slice((embedding.as_slice(), index));
+        self.next_index += 1;
+        self.id_to_index.insert(id.to_string(), index);
+        self.index_to_id.insert(index, id.to_string());
+        self.dimension = Some(embedding.len());
+        self.embeddings.insert(id.to_string(), embedding);
+        self.metadata.insert(id.to_string(), metadata);
+
+        self.maintain()
+    }
+
+    fn remove(&mut self, id: &str) -> Result<(), String> {
+        if !self.embeddings.contains_key(id) {
+            return Ok(());
+        }
+        self.append_log(&LogRecord {
+            op: "delete".to_string(),
+            id: id.to_string(),
+            embedding: Vec::new(),
+            metadata: HashMap::new(),
+            collection: None,
+        })?;
+        if let Some(old_index) = self.id_to_index.remove(id) {
+            self.index_to_id.remove(&old_index);
+        }
+        self.embeddings.remove(id);
+        self.metadata.remove(id);
+        if self.embeddings.is_empty() {
+            self.dimension = None;
+        }
+
+        self.maintain()
+    }
+
+    /// 類似ドキュメントを検索（ドキュメントID, 類似度）
+    fn search(&self, query: &[f32], limit: usize) -> Result<Vec<(String, f32)>, String> {
+        if self.len() == 0 || limit == 0 {
+            return Ok(Vec::new());
+        }
+        if let Some(dim) = self.dimension {
+            if dim != query.len() {
+                return Err(format!(
+                    "クエリベクトルの次元数が一致しません: 期待値={}, 実際={}",
+                    dim,
+                    query.len()
+                ));
+            }
+        }
+
+        // 孤立ノードがヒットする分だけ多めに取得する
+        let knbn = (limit + self.orphan_count()).min(self.next_index);
+        let neighbours = self.hnsw.search(query, knbn, EF_SEARCH.max(knbn));
+
+        let mut seen = HashSet::new();
+        let mut results = Vec::new();
+        for neighbour in neighbours {
+            if let Some(id) = self.index_to_id.get(&neighbour.d_id) {
+                if seen.insert(id.clone()) {
+                    // 距離を類似度に変換（ChromaDBバックエンドと同じ 1 - distance）
+                    let similarity = (1.0_f32 - neighbour.distance).max(0.0_f32);
+                    results.push((id.clone(), similarity));
+                }
+            }
+            if results.len() >= limit {
+                break;
+            }
+        }
+        Ok(results)
+    }
 }
 
-/**
- * 類似リレーションを検索
- * 注意: 現在はChromaDBを使用するため、この実装は使用されていません
- */
-#[allow(dead_code)]
-pub async fn find_similar_relations(
-    _query_embedding: Vec<f32>,
-    _limit: usize,
-    _organization_id: Option<String>,
-) -> Result<Vec<(String, f32)>, String> {
-    // ChromaDBを使用するため、この実装は使用されていません
-    Ok(vec![])
+/// コレクション名を構築（organizationIdが空の場合は"{prefix}_all"）
+fn collection_name(prefix: &str, organization_id: &str) -> String {
+    if organization_id.is_empty() {
+        format!("{}_all", prefix)
+    } else {
+        format!("{}_{}", prefix, organization_id)
+    }
 }
 
-/**
- * トピック埋め込みを保存
- * 注意: 現在はChromaDBを使用するため、この実装は使用されていません
- */
-#[allow(dead_code)]
-pub async fn save_topic_embedding(
-    _topic_id: String,
-    _meeting_note_id: String,
-    _organization_id: String,
-    _combined_embedding: Vec<f32>,
-    _metadata: HashMap<String, Value>,
-) -> Result<(), String> {
-    // ChromaDBを使用するため、この実装は使用されていません
-    Ok(())
+/// ファイル名に使えない文字（パス区切り文字など）を置換
+fn sanitize_collection_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// コレクション名からファイル名を構築
+/// 置換後の名前が別のコレクションと衝突しないよう（例: "a.b" と "a_b"）、元の名前のハッシュを付ける
+fn collection_file_name(name: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(name.as_bytes()));
+    format!("{}.{}.jsonl", sanitize_collection_name(name), &digest[..12])
+}
+
+/// ハッシュを付けていない旧形式のファイル名
+fn legacy_collection_file_name(name: &str) -> String {
+    format!("{}.jsonl", sanitize_collection_name(name))
+}
+
+/// ログファイルのヘッダーから元のコレクション名を読み込む（ヘッダーのない古いファイルは None）
+fn read_collection_name(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let first_line = BufReader::new(file).lines().next()?.ok()?;
+    let record: LogRecord = serde_json::from_str(&first_line).ok()?;
+    if record.op == "header" {
+        record.collection
+    } else {
+        None
+    }
+}
+
+/// メタデータのtagsに指定タグのいずれかが含まれるか（JSON文字列・配列の両方に対応）
+fn metadata_has_any_tag(metadata: Option<&HashMap<String, Value>>, tags: &[String]) -> bool {
+    let stored: Vec<String> = match metadata.and_then(|m| m.get("tags")) {
+        Some(Value::Array(values)) => values.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect(),
+        Some(Value::String(s)) => serde_json::from_str::<Vec<String>>(s).unwrap_or_else(|_| vec![s.clone()]),
+        _ => Vec::new(),
+    };
+    tags.iter().any(|tag| stored.iter().any(|s| s == tag))
 }
 
 /**
- * 類似トピックを検索
- * 注意: 現在はChromaDBを使用するため、この実装は使用されていません
+ * ローカル（hnsw_rs）ベクトルストア
+ * index_dirがNoneの場合はメモリ上のみで動作する（テスト・一時利用向け）
+ * インデックスの操作はファイルI/Oと std::sync::Mutex を伴うため、非同期メソッドからは spawn_blocking で実行する
  */
-#[allow(dead_code)]
-pub async fn find_similar_topics(
-    _query_embedding: Vec<f32>,
-    _limit: usize,
-    _organization_id: Option<String>,
-) -> Result<Vec<(String, String, f32)>, String> {
-    // ChromaDBを使用するため、この実装は使用されていません
-    Ok(vec![])
+pub struct LocalVectorStore {
+    inner: Arc<LocalIndices>,
+}
+
+// 読み込み済みのインデックス（spawn_blocking のタスクと共有する）
+struct LocalIndices {
+    index_dir: Option<PathBuf>,
+    indices: Mutex<HashMap<String, Arc<Mutex<VectorIndex>>>>,
+}
+
+impl LocalVectorStore {
+    /**
+     * 永続化するローカルベクトルストアを作成
+     *
+     * @param data_dir ChromaDBデータディレクトリ（app.dbと同じ階層のchromadb/）
+     */
+    pub fn open(data_dir: PathBuf) -> Result<Self, String> {
+        let index_dir = data_dir.join(LOCAL_INDEX_DIR_NAME);
+        fs::create_dir_all(&index_dir)
+            .map_err(|e| format!("ベクトルインデックスディレクトリの作成に失敗しました: {}", e))?;
+        eprintln!("✅ ローカルベクトルインデックスを初期化しました: {}", index_dir.display());
+
+        Ok(Self {
+            inner: Arc::new(LocalIndices {
+                index_dir: Some(index_dir),
+                indices: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    /// メモリ上のみのローカルベクトルストアを作成
+    pub fn in_memory() -> Self {
+        Self {
+            inner: Arc::new(LocalIndices {
+                index_dir: None,
+                indices: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// インデックスの操作をブロッキング処理用のスレッドで実行
+    async fn run_blocking<T, F>(&self, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&LocalIndices) -> Result<T, String> + Send + 'static,
+    {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || f(&inner))
+            .await
+            .map_err(|e| format!("ベクトルインデックスの処理に失敗しました: {}", e))?
+    }
+}
+
+impl LocalIndices {
+    fn collection_path(&self, name: &str) -> Option<PathBuf> {
+        self.index_dir.as_ref().map(|dir| dir.join(collection_file_name(name)))
+    }
+
+    /// 旧形式のファイル名で保存されたこのコレクションのログ（別のコレクションのログは対象外）
+    fn legacy_collection_path(&self, name: &str) -> Option<PathBuf> {
+        let path = self.index_dir.as_ref()?.join(legacy_collection_file_name(name));
+        if !path.exists() {
+            return None;
+        }
+        let owned = match read_collection_name(&path) {
+            Some(collection) => collection == name,
+            // ヘッダーのないファイルは、置換する文字を含まない名前の場合のみ同じコレクションとみなす
+            None => sanitize_collection_name(name) == name,
+        };
+        owned.then_some(path)
+    }
+
+    /// コレクションのインデックスを取得（未読み込みの場合はファイルから読み込む）
+    fn get_or_load_index(&self, name: &str) -> Result<Arc<Mutex<VectorIndex>>, String> {
+        let mut indices = self.indices.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+
+        if let Some(index) = indices.get(name) {
+            return Ok(index.clone());
+        }
+
+        let path = self.collection_path(name);
+        if let (Some(path), Some(legacy_path)) = (&path, self.legacy_collection_path(name)) {
+            if !path.exists() {
+                fs::rename(&legacy_path, path)
+                    .map_err(|e| format!("インデックスファイルの移動に失敗しました: {} ({})", e, legacy_path.display()))?;
+                eprintln!("✅ [vector_search] 旧形式のインデックスファイルを移動しました: {} -> {}", legacy_path.display(), path.display());
+            }
+        }
+
+        let index = Arc::new(Mutex::new(VectorIndex::load(name, path)?));
+        indices.insert(name.to_string(), index.clone());
+        Ok(index)
+    }
+
+    /// 指定プレフィックスを持つコレクション名を列挙（ディスク上のファイルと読み込み済みのもの）
+    /// ファイルのコレクション名はヘッダーから読み込む（ヘッダーのない古いファイルはファイル名を使う）
+    fn list_collections(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let prefix = format!("{}_", prefix);
+        let mut names = HashSet::new();
+
+        if let Some(index_dir) = &self.index_dir {
+            if let Ok(entries) = fs::read_dir(index_dir) {
+                for entry in entries.flatten() {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    if let Some(stem) = file_name.strip_suffix(".jsonl") {
+                        let name = read_collection_name(&entry.path()).unwrap_or_else(|| stem.to_string());
+                        if name.starts_with(&prefix) {
+                            names.insert(name);
+                        }
+                    }
+                }
+            }
+        }
+
+        let indices = self.indices.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+        for name in indices.keys() {
+            if name.starts_with(&prefix) {
+                names.insert(name.clone());
+            }
+        }
+
+        let mut names: Vec<String> = names.into_iter().collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// 対象コレクションを決定（organizationId未指定の場合は組織横断）
+    fn target_collections(&self, prefix: &str, organization_id: Option<String>) -> Result<Vec<String>, String> {
+        match organization_id {
+            Some(id) if !id.is_empty() => Ok(vec![collection_name(prefix, &id)]),
+            _ => self.list_collections(prefix),
+        }
+    }
+
+    fn upsert_document(
+        &self,
+        name: &str,
+        id: &str,
+        embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        let index = self.get_or_load_index(name)?;
+        let mut index_guard = index.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+        index_guard.upsert(id, embedding, metadata)
+    }
+
+    fn delete_document(&self, name: &str, id: &str) -> Result<(), String> {
+        let index = self.get_or_load_index(name)?;
+        let mut index_guard = index.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+        index_guard.remove(id)
+    }
+
+    /// 埋め込みとメタデータを取得（ChromaDBバックエンドと同じ形式: combinedEmbedding + メタデータ）
+    fn get_document(&self, name: &str, id: &str) -> Result<Option<HashMap<String, Value>>, String> {
+        let index = self.get_or_load_index(name)?;
+        let index_guard = index.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+
+        let embedding = match index_guard.embeddings.get(id) {
+            Some(embedding) => embedding,
+            None => return Ok(None),
+        };
+
+        let mut result_data = index_guard.metadata.get(id).cloned().unwrap_or_default();
+        result_data.insert("combinedEmbedding".to_string(), Value::Array(
+            embedding.iter()
+                .filter_map(|&v| serde_json::Number::from_f64(v as f64).map(Value::Number))
+                .collect()
+        ));
+        Ok(Some(result_data))
+    }
+
+    /// コレクションのドキュメント数
+    fn count_documents(&self, name: &str) -> Result<usize, String> {
+        let index = self.get_or_load_index(name)?;
+        let index_guard = index.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+        Ok(index_guard.len())
+    }
+
+    /// 複数コレクションを検索し、類似度順に上位limit件を返す
+    fn search_collections(
+        &self,
+        prefix: &str,
+        query_embedding: &[f32],
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<(String, f32)>, String> {
+        let mut all_results = Vec::new();
+        for name in self.target_collections(prefix, organization_id)? {
+            let index = self.get_or_load_index(&name)?;
+            let index_guard = index.lock()
+                .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+            match index_guard.search(query_embedding, limit) {
+                Ok(results) => all_results.extend(results),
+                Err(e) => {
+                    eprintln!("[vector_search] ⚠️ コレクション '{}' の検索エラー: {}", name, e);
+                }
+            }
+        }
+
+        all_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        all_results.truncate(limit);
+        Ok(all_results)
+    }
+
+    /// トピックを検索し、メタデータから議事録ID・タイトルなどを補う
+    fn find_similar_topics(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<TopicSearchResult>, String> {
+        let mut all_results = Vec::new();
+
+        for name in self.target_collections(COLLECTION_TOPICS, organization_id)? {
+            let index = self.get_or_load_index(&name)?;
+            let index_guard = index.lock()
+                .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+
+            let hits = match index_guard.search(query_embedding, limit) {
+                Ok(hits) => hits,
+                Err(e) => {
+                    eprintln!("[find_similar_topics] ⚠️ コレクション '{}' の検索エラー: {}", name, e);
+                    continue;
+                }
+            };
+
+            for (topic_id, similarity) in hits {
+                let metadata = index_guard.metadata.get(&topic_id);
+                let get_str = |key: &str| {
+                    metadata
+                        .and_then(|m| m.get(key))
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string())
+                };
+                all_results.push(TopicSearchResult {
+                    meeting_note_id: get_str("meetingNoteId").unwrap_or_default(),
+                    title: get_str("title").unwrap_or_default(),
+                    content_summary: get_str("contentSummary").unwrap_or_default(),
+                    organization_id: get_str("organizationId"),
+                    topic_id,
+                    similarity,
+                });
+            }
+        }
+
+        all_results.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+        all_results.truncate(limit);
+        Ok(all_results)
+    }
+
+    /// システム設計ドキュメントを検索（セクションID・タグで絞り込み可能）
+    fn find_similar_design_docs(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        section_id: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> Result<Vec<(String, f32)>, String> {
+        let index = self.get_or_load_index(COLLECTION_DESIGN_DOCS)?;
+        let index_guard = index.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+
+        let tags = tags.filter(|t| !t.is_empty());
+        // フィルターがある場合は全件から絞り込む
+        let search_limit = if section_id.is_some() || tags.is_some() {
+            index_guard.len()
+        } else {
+            limit
+        };
+
+        let hits = index_guard.search(query_embedding, search_limit)?;
+
+        let results = hits
+            .into_iter()
+            .filter(|(id, _)| section_id.as_ref().map_or(true, |sid| sid == id))
+            .filter(|(id, _)| {
+                tags.as_ref()
+                    .map_or(true, |t| metadata_has_any_tag(index_guard.metadata.get(id), t))
+            })
+            .take(limit)
+            .collect();
+
+        Ok(results)
+    }
+
+    fn design_doc_metadata(&self, section_id: &str) -> Result<HashMap<String, Value>, String> {
+        let index = self.get_or_load_index(COLLECTION_DESIGN_DOCS)?;
+        let index_guard = index.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+
+        index_guard.metadata
+            .get(section_id)
+            .cloned()
+            .ok_or_else(|| "メタデータが見つかりませんでした".to_string())
+    }
+
+    fn design_doc_section_ids(&self) -> Result<Vec<String>, String> {
+        let index = self.get_or_load_index(COLLECTION_DESIGN_DOCS)?;
+        let index_guard = index.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+
+        let mut section_ids: Vec<String> = index_guard.embeddings.keys().cloned().collect();
+        section_ids.sort();
+        Ok(section_ids)
+    }
+
+    /// コレクションを削除（読み込み済みのインデックスとファイル）
+    fn delete_collections(&self, names: &[String]) -> Result<(), String> {
+        let mut indices = self.indices.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+        for name in names {
+            indices.remove(name);
+            let paths = self.collection_path(name).filter(|p| p.exists())
+                .into_iter()
+                .chain(self.legacy_collection_path(name));
+            for path in paths {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("インデックスファイルの削除に失敗しました: {} ({})", e, path.display()))?;
+                eprintln!("✅ [delete_organization_collections] インデックス削除成功: {}", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// すべてのインデックスを削除
+    fn clear(&self) -> Result<(), String> {
+        let mut indices = self.indices.lock()
+            .map_err(|e| format!("ベクトルインデックスのロック取得に失敗しました: {}", e))?;
+        indices.clear();
+
+        if let Some(index_dir) = &self.index_dir {
+            eprintln!("🗑️ ローカルベクトルインデックスをクリアします: {}", index_dir.display());
+            if index_dir.exists() {
+                fs::remove_dir_all(index_dir)
+                    .map_err(|e| format!("ローカルベクトルインデックスの削除に失敗しました: {}", e))?;
+            }
+            fs::create_dir_all(index_dir)
+                .map_err(|e| format!("ベクトルインデックスディレクトリの作成に失敗しました: {}", e))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VectorStore for LocalVectorStore {
+    fn backend(&self) -> VectorBackend {
+        VectorBackend::Local
+    }
+
+    async fn save_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        let mut embedding_metadata = metadata;
+        embedding_metadata.insert("entityId".to_string(), Value::String(entity_id.clone()));
+        embedding_metadata.insert("organizationId".to_string(), Value::String(organization_id.clone()));
+
+        let name = collection_name(COLLECTION_ENTITIES, &organization_id);
+        self.run_blocking(move |store| store.upsert_document(&name, &entity_id, combined_embedding, embedding_metadata))
+            .await
+            .map_err(|e| format!("エンティティ埋め込みの保存に失敗しました: {}", e))
+    }
+
+    async fn get_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String> {
+        let name = collection_name(COLLECTION_ENTITIES, &organization_id);
+        self.run_blocking(move |store| store.get_document(&name, &entity_id))
+            .await
+            .map_err(|e| format!("エンティティ埋め込みの取得に失敗しました: {}", e))
+    }
+
+    async fn find_similar_entities(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<(String, f32)>, String> {
+        self.run_blocking(move |store| {
+            store.search_collections(COLLECTION_ENTITIES, &query_embedding, limit, organization_id)
+        })
+        .await
+        .map_err(|e| format!("類似エンティティの検索に失敗しました: {}", e))
+    }
+
+    async fn count_entities(&self, organization_id: Option<String>) -> Result<usize, String> {
+        let org_id = match organization_id {
+            Some(id) if !id.is_empty() => id,
+            _ => return Err("organizationIdが指定されていません".to_string()),
+        };
+
+        let name = collection_name(COLLECTION_ENTITIES, &org_id);
+        self.run_blocking(move |store| store.count_documents(&name)).await
+    }
+
+    async fn save_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        let mut embedding_metadata = metadata;
+        embedding_metadata.insert("relationId".to_string(), Value::String(relation_id.clone()));
+        embedding_metadata.insert("organizationId".to_string(), Value::String(organization_id.clone()));
+
+        let name = collection_name(COLLECTION_RELATIONS, &organization_id);
+        self.run_blocking(move |store| store.upsert_document(&name, &relation_id, combined_embedding, embedding_metadata))
+            .await
+            .map_err(|e| format!("リレーション埋め込みの保存に失敗しました: {}", e))
+    }
+
+    async fn get_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String> {
+        let name = collection_name(COLLECTION_RELATIONS, &organization_id);
+        self.run_blocking(move |store| store.get_document(&name, &relation_id))
+            .await
+            .map_err(|e| format!("リレーション埋め込みの取得に失敗しました: {}", e))
+    }
+
+    async fn find_similar_relations(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<(String, f32)>, String> {
+        self.run_blocking(move |store| {
+            store.search_collections(COLLECTION_RELATIONS, &query_embedding, limit, organization_id)
+        })
+        .await
+        .map_err(|e| format!("類似リレーションの検索に失敗しました: {}", e))
+    }
+
+    async fn save_topic_embedding(
+        &self,
+        topic_id: String,
+        meeting_note_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        let mut embedding_metadata = metadata;
+        embedding_metadata.insert("topicId".to_string(), Value::String(topic_id.clone()));
+        embedding_metadata.insert("meetingNoteId".to_string(), Value::String(meeting_note_id));
+        embedding_metadata.insert("organizationId".to_string(), Value::String(organization_id.clone()));
+
+        let name = collection_name(COLLECTION_TOPICS, &organization_id);
+        self.run_blocking(move |store| store.upsert_document(&name, &topic_id, combined_embedding, embedding_metadata))
+            .await
+            .map_err(|e| format!("トピック埋め込みの保存に失敗しました: {}", e))
+    }
+
+    async fn get_topic_embedding(
+        &self,
+        topic_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String> {
+        let name = collection_name(COLLECTION_TOPICS, &organization_id);
+        self.run_blocking(move |store| store.get_document(&name, &topic_id))
+            .await
+            .map_err(|e| format!("トピック埋め込みの取得に失敗しました: {}", e))
+    }
+
+    async fn find_similar_topics(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<TopicSearchResult>, String> {
+        self.run_blocking(move |store| store.find_similar_topics(&query_embedding, limit, organization_id))
+            .await
+    }
+
+    async fn save_design_doc_embedding(
+        &self,
+        section_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        let mut embedding_metadata = metadata;
+        embedding_metadata.insert("sectionId".to_string(), Value::String(section_id.clone()));
+
+        self.run_blocking(move |store| {
+            store.upsert_document(COLLECTION_DESIGN_DOCS, &section_id, combined_embedding, embedding_metadata)
+        })
+        .await
+        .map_err(|e| format!("システム設計ドキュメント埋め込みの保存に失敗しました: {}", e))
+    }
+
+    async fn find_similar_design_docs(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        section_id: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> Result<Vec<(String, f32)>, String> {
+        self.run_blocking(move |store| store.find_similar_design_docs(&query_embedding, limit, section_id, tags))
+            .await
+            .map_err(|e| format!("類似システム設計ドキュメントの検索に失敗しました: {}", e))
+    }
+
+    async fn get_design_doc_metadata(
+        &self,
+        section_id: String,
+    ) -> Result<HashMap<String, Value>, String> {
+        self.run_blocking(move |store| store.design_doc_metadata(&section_id)).await
+    }
+
+    async fn list_design_doc_section_ids(&self) -> Result<Vec<String>, String> {
+        self.run_blocking(|store| store.design_doc_section_ids()).await
+    }
+
+    async fn delete_topic_embedding(
+        &self,
+        topic_id: String,
+        organization_id: String,
+    ) -> Result<(), String> {
+        let name = collection_name(COLLECTION_TOPICS, &organization_id);
+        self.run_blocking(move |store| store.delete_document(&name, &topic_id))
+            .await
+            .map_err(|e| format!("トピック埋め込みの削除に失敗しました: {}", e))
+    }
+
+    async fn delete_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+    ) -> Result<(), String> {
+        let name = collection_name(COLLECTION_ENTITIES, &organization_id);
+        self.run_blocking(move |store| store.delete_document(&name, &entity_id))
+            .await
+            .map_err(|e| format!("エンティティ埋め込みの削除に失敗しました: {}", e))
+    }
+
+    async fn delete_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+    ) -> Result<(), String> {
+        let name = collection_name(COLLECTION_RELATIONS, &organization_id);
+        self.run_blocking(move |store| store.delete_document(&name, &relation_id))
+            .await
+            .map_err(|e| format!("リレーション埋め込みの削除に失敗しました: {}", e))
+    }
+
+    async fn delete_organization_collections(
+        &self,
+        organization_id: String,
+    ) -> Result<(), String> {
+        let names = vec![
+            collection_name(COLLECTION_TOPICS, &organization_id),
+            collection_name(COLLECTION_ENTITIES, &organization_id),
+            collection_name(COLLECTION_RELATIONS, &organization_id),
+        ];
+        self.run_blocking(move |store| store.delete_collections(&names)).await
+    }
+
+    async fn clear_data_dir(&self) -> Result<(), String> {
+        self.run_blocking(|store| store.clear()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embedding(values: &[f32]) -> Vec<f32> {
+        values.to_vec()
+    }
+
+    fn temp_data_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("vector_search_test_{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn in_memory_upsert_query_delete() {
+        let store = LocalVectorStore::in_memory();
+        store.save_entity_embedding("e1".into(), "org1".into(), embedding(&[1.0, 0.0, 0.0]), HashMap::new()).await.unwrap();
+        store.save_entity_embedding("e2".into(), "org1".into(), embedding(&[0.0, 1.0, 0.0]), HashMap::new()).await.unwrap();
+
+        let results = store.find_similar_entities(embedding(&[0.9, 0.1, 0.0]), 2, Some("org1".into())).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "e1");
+        assert!(results[0].1 > results[1].1);
+        assert_eq!(store.count_entities(Some("org1".into())).await.unwrap(), 2);
+
+        let saved = store.get_entity_embedding("e1".into(), "org1".into()).await.unwrap().unwrap();
+        assert_eq!(saved.get("entityId"), Some(&Value::String("e1".into())));
+        assert!(saved.contains_key("combinedEmbedding"));
+
+        store.delete_entity_embedding("e1".into(), "org1".into()).await.unwrap();
+        let results = store.find_similar_entities(embedding(&[0.9, 0.1, 0.0]), 2, Some("org1".into())).await.unwrap();
+        assert_eq!(results.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["e2"]);
+        assert!(store.get_entity_embedding("e1".into(), "org1".into()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn in_memory_upsert_overwrites_and_rejects_dimension_mismatch() {
+        let store = LocalVectorStore::in_memory();
+        store.save_entity_embedding("e1".into(), "org1".into(), embedding(&[1.0, 0.0]), HashMap::new()).await.unwrap();
+        store.save_entity_embedding("e1".into(), "org1".into(), embedding(&[0.0, 1.0]), HashMap::new()).await.unwrap();
+
+        let results = store.find_similar_entities(embedding(&[0.0, 1.0]), 5, Some("org1".into())).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1 > 0.99);
+
+        let err = store.save_entity_embedding("e2".into(), "org1".into(), embedding(&[1.0, 0.0, 0.0]), HashMap::new()).await;
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn rebuild_drops_orphaned_nodes() {
+        let mut index = VectorIndex::load("entities_org1", None).unwrap();
+        for i in 0..5 {
+            index.upsert("e1", embedding(&[1.0, i as f32]), HashMap::new()).unwrap();
+        }
+        index.upsert("e2", embedding(&[0.0, 1.0]), HashMap::new()).unwrap();
+        assert_eq!(index.orphan_count(), 4);
+
+        index.rebuild();
+        assert_eq!(index.orphan_count(), 0);
+        assert_eq!(index.len(), 2);
+        let results = index.search(&[1.0, 4.0], 2).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "e1");
+    }
+
+    #[tokio::test]
+    async fn persisted_collections_keep_their_original_names() {
+        let data_dir = temp_data_dir();
+        {
+            let store = LocalVectorStore::open(data_dir.clone()).unwrap();
+            store.save_entity_embedding("e1".into(), "org/1".into(), embedding(&[1.0, 0.0]), HashMap::new()).await.unwrap();
+            store.save_entity_embedding("e2".into(), "org2".into(), embedding(&[0.0, 1.0]), HashMap::new()).await.unwrap();
+            store.delete_entity_embedding("e2".into(), "org2".into()).await.unwrap();
+        }
+
+        let store = LocalVectorStore::open(data_dir.clone()).unwrap();
+        let names = store.inner.list_collections(COLLECTION_ENTITIES).unwrap();
+        assert_eq!(names, vec!["entities_org/1".to_string(), "entities_org2".to_string()]);
+
+        let results = store.find_similar_entities(embedding(&[1.0, 0.0]), 5, None).await.unwrap();
+        assert_eq!(results.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["e1"]);
+
+        store.clear_data_dir().await.unwrap();
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    fn upsert_record(id: &str, values: &[f32]) -> LogRecord {
+        LogRecord {
+            op: "upsert".to_string(),
+            id: id.to_string(),
+            embedding: embedding(values),
+            metadata: HashMap::new(),
+            collection: None,
+        }
+    }
+
+    fn write_log(path: &Path, records: &[LogRecord]) {
+        let lines: Vec<String> = records.iter().map(|r| serde_json::to_string(r).unwrap()).collect();
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    #[tokio::test]
+    async fn names_that_sanitize_to_the_same_file_name_do_not_collide() {
+        assert_ne!(collection_file_name("entities_a.b"), collection_file_name("entities_a_b"));
+
+        let data_dir = temp_data_dir();
+        {
+            let store = LocalVectorStore::open(data_dir.clone()).unwrap();
+            store.save_entity_embedding("e1".into(), "a.b".into(), embedding(&[1.0, 0.0]), HashMap::new()).await.unwrap();
+            store.save_entity_embedding("e2".into(), "a_b".into(), embedding(&[0.0, 1.0]), HashMap::new()).await.unwrap();
+        }
+
+        let store = LocalVectorStore::open(data_dir.clone()).unwrap();
+        let results = store.find_similar_entities(embedding(&[1.0, 0.0]), 5, Some("a.b".into())).await.unwrap();
+        assert_eq!(results.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["e1"]);
+        let results = store.find_similar_entities(embedding(&[1.0, 0.0]), 5, Some("a_b".into())).await.unwrap();
+        assert_eq!(results.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["e2"]);
+
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_log_whose_header_names_another_collection() {
+        let data_dir = temp_data_dir();
+        let store = LocalVectorStore::open(data_dir.clone()).unwrap();
+        let path = store.inner.collection_path("entities_org1").unwrap();
+        write_log(&path, &[LogRecord::header("entities_org2"), upsert_record("e1", &[1.0, 0.0])]);
+
+        assert!(store.inner.get_or_load_index("entities_org1").is_err());
+
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn moves_logs_saved_under_the_legacy_file_name() {
+        let data_dir = temp_data_dir();
+        let store = LocalVectorStore::open(data_dir.clone()).unwrap();
+        let index_dir = data_dir.join(LOCAL_INDEX_DIR_NAME);
+        // ヘッダーのない旧形式のログ
+        let legacy_path = index_dir.join(legacy_collection_file_name("entities_org1"));
+        write_log(&legacy_path, &[upsert_record("e1", &[1.0, 0.0])]);
+        // 置換後の名前が同じ別のコレクションのログは移動しない
+        let other_path = index_dir.join(legacy_collection_file_name("entities_org.2"));
+        write_log(&other_path, &[LogRecord::header("entities_org_2"), upsert_record("e2", &[0.0, 1.0])]);
+
+        let results = store.find_similar_entities(embedding(&[1.0, 0.0]), 5, Some("org1".into())).await.unwrap();
+        assert_eq!(results.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["e1"]);
+        assert!(!legacy_path.exists());
+        assert!(store.inner.collection_path("entities_org1").unwrap().exists());
+
+        let results = store.find_similar_entities(embedding(&[0.0, 1.0]), 5, Some("org.2".into())).await.unwrap();
+        assert!(results.is_empty());
+        assert!(other_path.exists());
+
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
 }
@@ -0,0 +1,387 @@
+/**
+ * ベクトルストア抽象化モジュール
+ * ChromaDB（Python Server）とローカル（hnsw_rs）のバックエンドを共通のトレイトで扱う
+ *
+ * - Tauriコマンドと書き込みワーカーは get_vector_store() 経由で利用する
+ * - バックエンドは起動時に環境変数 VECTOR_BACKEND で選択（chromadb / local）
+ * - set_vector_store() で任意の実装（メモリ上のストアなど）に差し替え可能
+ */
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use super::chromadb;
+use super::vector_search::LocalVectorStore;
+
+pub use super::chromadb::TopicSearchResult;
+
+/// ベクトルストアのバックエンド
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorBackend {
+    ChromaDB,
+    Local,
+}
+
+impl VectorBackend {
+    pub fn from_str(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "local" | "hnsw" | "native" => VectorBackend::Local,
+            _ => VectorBackend::ChromaDB,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            VectorBackend::ChromaDB => "chromadb",
+            VectorBackend::Local => "local",
+        }
+    }
+}
+
+/// 起動時に選択されたベクトルストアのバックエンドを取得（環境変数 VECTOR_BACKEND）
+pub fn get_vector_backend() -> VectorBackend {
+    std::env::var("VECTOR_BACKEND")
+        .map(|s| VectorBackend::from_str(&s))
+        .unwrap_or(VectorBackend::ChromaDB)
+}
+
+/// ベクトルストアの共通インターフェース
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// バックエンドの種類
+    fn backend(&self) -> VectorBackend;
+
+    /// エンティティ埋め込みを保存
+    async fn save_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String>;
+
+    /// エンティティ埋め込みを取得
+    async fn get_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String>;
+
+    /// 類似エンティティを検索（組織横断検索対応）
+    async fn find_similar_entities(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<(String, f32)>, String>;
+
+    /// エンティティコレクションの件数を取得
+    async fn count_entities(&self, organization_id: Option<String>) -> Result<usize, String>;
+
+    /// リレーション埋め込みを保存
+    async fn save_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String>;
+
+    /// リレーション埋め込みを取得
+    async fn get_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String>;
+
+    /// 類似リレーションを検索（組織横断検索対応）
+    async fn find_similar_relations(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<(String, f32)>, String>;
+
+    /// トピック埋め込みを保存
+    async fn save_topic_embedding(
+        &self,
+        topic_id: String,
+        meeting_note_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String>;
+
+    /// トピック埋め込みを取得
+    async fn get_topic_embedding(
+        &self,
+        topic_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String>;
+
+    /// 類似トピックを検索（組織横断検索対応）
+    async fn find_similar_topics(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<TopicSearchResult>, String>;
+
+    /// システム設計ドキュメントの埋め込みを保存
+    async fn save_design_doc_embedding(
+        &self,
+        section_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String>;
+
+    /// 類似システム設計ドキュメントを検索
+    async fn find_similar_design_docs(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        section_id: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> Result<Vec<(String, f32)>, String>;
+
+    /// システム設計ドキュメントのメタデータを取得
+    async fn get_design_doc_metadata(
+        &self,
+        section_id: String,
+    ) -> Result<HashMap<String, Value>, String>;
+
+    /// システム設計ドキュメントコレクション内の全セクションIDを取得
+    async fn list_design_doc_section_ids(&self) -> Result<Vec<String>, String>;
+
+    /// トピック埋め込みを削除
+    async fn delete_topic_embedding(
+        &self,
+        topic_id: String,
+        organization_id: String,
+    ) -> Result<(), String>;
+
+    /// エンティティ埋め込みを削除
+    async fn delete_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+    ) -> Result<(), String>;
+
+    /// リレーション埋め込みを削除
+    async fn delete_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+    ) -> Result<(), String>;
+
+    /// 組織に関連するコレクションを削除
+    async fn delete_organization_collections(
+        &self,
+        organization_id: String,
+    ) -> Result<(), String>;
+
+    /// データディレクトリをクリア（破損したデータを修復するため）
+    async fn clear_data_dir(&self) -> Result<(), String>;
+}
+
+/// ChromaDB Serverを使用するベクトルストア（database::chromadbへ委譲）
+pub struct ChromaVectorStore;
+
+#[async_trait]
+impl VectorStore for ChromaVectorStore {
+    fn backend(&self) -> VectorBackend {
+        VectorBackend::ChromaDB
+    }
+
+    async fn save_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        chromadb::save_entity_embedding(entity_id, organization_id, combined_embedding, metadata).await
+    }
+
+    async fn get_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String> {
+        chromadb::get_entity_embedding(entity_id, organization_id).await
+    }
+
+    async fn find_similar_entities(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<(String, f32)>, String> {
+        chromadb::find_similar_entities(query_embedding, limit, organization_id).await
+    }
+
+    async fn count_entities(&self, organization_id: Option<String>) -> Result<usize, String> {
+        chromadb::count_entities(organization_id).await
+    }
+
+    async fn save_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        chromadb::save_relation_embedding(relation_id, organization_id, combined_embedding, metadata).await
+    }
+
+    async fn get_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String> {
+        chromadb::get_relation_embedding(relation_id, organization_id).await
+    }
+
+    async fn find_similar_relations(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<(String, f32)>, String> {
+        chromadb::find_similar_relations(query_embedding, limit, organization_id).await
+    }
+
+    async fn save_topic_embedding(
+        &self,
+        topic_id: String,
+        meeting_note_id: String,
+        organization_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        chromadb::save_topic_embedding(topic_id, meeting_note_id, organization_id, combined_embedding, metadata).await
+    }
+
+    async fn get_topic_embedding(
+        &self,
+        topic_id: String,
+        organization_id: String,
+    ) -> Result<Option<HashMap<String, Value>>, String> {
+        chromadb::get_topic_embedding(topic_id, organization_id).await
+    }
+
+    async fn find_similar_topics(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        organization_id: Option<String>,
+    ) -> Result<Vec<TopicSearchResult>, String> {
+        chromadb::find_similar_topics(query_embedding, limit, organization_id).await
+    }
+
+    async fn save_design_doc_embedding(
+        &self,
+        section_id: String,
+        combined_embedding: Vec<f32>,
+        metadata: HashMap<String, Value>,
+    ) -> Result<(), String> {
+        chromadb::save_design_doc_embedding(section_id, combined_embedding, metadata).await
+    }
+
+    async fn find_similar_design_docs(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: usize,
+        section_id: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> Result<Vec<(String, f32)>, String> {
+        chromadb::find_similar_design_docs(query_embedding, limit, section_id, tags).await
+    }
+
+    async fn get_design_doc_metadata(
+        &self,
+        section_id: String,
+    ) -> Result<HashMap<String, Value>, String> {
+        chromadb::get_design_doc_metadata(section_id).await
+    }
+
+    async fn list_design_doc_section_ids(&self) -> Result<Vec<String>, String> {
+        chromadb::list_design_doc_section_ids().await
+    }
+
+    async fn delete_topic_embedding(
+        &self,
+        topic_id: String,
+        organization_id: String,
+    ) -> Result<(), String> {
+        chromadb::delete_topic_embedding(topic_id, organization_id).await
+    }
+
+    async fn delete_entity_embedding(
+        &self,
+        entity_id: String,
+        organization_id: String,
+    ) -> Result<(), String> {
+        chromadb::delete_entity_embedding(entity_id, organization_id).await
+    }
+
+    async fn delete_relation_embedding(
+        &self,
+        relation_id: String,
+        organization_id: String,
+    ) -> Result<(), String> {
+        chromadb::delete_relation_embedding(relation_id, organization_id).await
+    }
+
+    async fn delete_organization_collections(
+        &self,
+        organization_id: String,
+    ) -> Result<(), String> {
+        chromadb::delete_organization_collections(organization_id).await
+    }
+
+    async fn clear_data_dir(&self) -> Result<(), String> {
+        chromadb::clear_chromadb_data_dir().await
+    }
+}
+
+// 現在有効なベクトルストア
+static VECTOR_STORE: OnceLock<Arc<dyn VectorStore>> = OnceLock::new();
+
+/**
+ * 起動時に選択されたバックエンドでベクトルストアを初期化
+ *
+ * @param data_dir ChromaDBデータディレクトリ（app.dbと同じ階層のchromadb/）
+ */
+pub fn init_vector_store(data_dir: PathBuf) -> Result<(), String> {
+    // データベースの再初期化時などは既存のストアをそのまま使用
+    if VECTOR_STORE.get().is_some() {
+        return Ok(());
+    }
+    let store: Arc<dyn VectorStore> = match get_vector_backend() {
+        VectorBackend::Local => Arc::new(LocalVectorStore::open(data_dir)?),
+        VectorBackend::ChromaDB => Arc::new(ChromaVectorStore),
+    };
+    set_vector_store(store)
+}
+
+/// ベクトルストアを設定（初期化済みの場合はエラー）
+pub fn set_vector_store(store: Arc<dyn VectorStore>) -> Result<(), String> {
+    let backend = store.backend();
+    VECTOR_STORE
+        .set(store)
+        .map_err(|_| "ベクトルストアは既に初期化されています".to_string())?;
+    eprintln!("✅ ベクトルストアを初期化しました: {}", backend.as_str());
+    Ok(())
+}
+
+/// 現在のベクトルストアを取得
+pub fn get_vector_store() -> Result<Arc<dyn VectorStore>, String> {
+    VECTOR_STORE
+        .get()
+        .cloned()
+        .ok_or_else(|| "ベクトルストアが初期化されていません".to_string())
+}
@@ -0,0 +1,127 @@
+/**
+ * 書き込みキューのメトリクス
+ * キューの滞留件数・処理件数・合流件数・バッチの大きさと、直近のスループットを集計する
+ */
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// スループットを計算する期間
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(60);
+
+/// メトリクスのスナップショット
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteQueueMetricsSnapshot {
+    /// キューに滞留しているジョブ数（送信済みで書き込みワーカーが未取得）
+    pub queue_depth: u64,
+    pub enqueued: u64,
+    pub processed: u64,
+    pub failed: u64,
+    /// 同じ行への書き込みとして合流させたジョブ数
+    pub coalesced: u64,
+    pub batches: u64,
+    pub last_batch_size: u64,
+    pub last_batch_ms: u64,
+    /// 直近60秒の処理件数（件/秒）
+    pub jobs_per_second: f64,
+    pub uptime_secs: u64,
+}
+
+pub struct WriteQueueMetrics {
+    started_at: Instant,
+    enqueued: AtomicU64,
+    dequeued: AtomicU64,
+    processed: AtomicU64,
+    failed: AtomicU64,
+    coalesced: AtomicU64,
+    batches: AtomicU64,
+    last_batch_size: AtomicU64,
+    last_batch_ms: AtomicU64,
+    /// 直近のバッチの完了時刻と処理件数
+    recent: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+/// 書き込みキューのメトリクス（プロセスで1つ）
+pub fn write_queue_metrics() -> &'static WriteQueueMetrics {
+    static METRICS: OnceLock<WriteQueueMetrics> = OnceLock::new();
+    METRICS.get_or_init(|| WriteQueueMetrics {
+        started_at: Instant::now(),
+        enqueued: AtomicU64::new(0),
+        dequeued: AtomicU64::new(0),
+        processed: AtomicU64::new(0),
+        failed: AtomicU64::new(0),
+        coalesced: AtomicU64::new(0),
+        batches: AtomicU64::new(0),
+        last_batch_size: AtomicU64::new(0),
+        last_batch_ms: AtomicU64::new(0),
+        recent: Mutex::new(VecDeque::new()),
+    })
+}
+
+impl WriteQueueMetrics {
+    pub(crate) fn record_enqueued(&self) {
+        self.enqueued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dequeued(&self, count: usize) {
+        self.dequeued.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_coalesced(&self) {
+        self.coalesced.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_failed(&self, count: usize) {
+        self.failed.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    /// バッチの処理完了を記録（processed は成功したジョブ数）
+    pub(crate) fn record_batch(&self, size: usize, processed: usize, elapsed: Duration) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.processed.fetch_add(processed as u64, Ordering::Relaxed);
+        self.last_batch_size.store(size as u64, Ordering::Relaxed);
+        self.last_batch_ms.store(elapsed.as_millis() as u64, Ordering::Relaxed);
+
+        if let Ok(mut recent) = self.recent.lock() {
+            let now = Instant::now();
+            recent.push_back((now, processed as u64));
+            while recent.front().map_or(false, |(at, _)| now.duration_since(*at) > THROUGHPUT_WINDOW) {
+                recent.pop_front();
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> WriteQueueMetricsSnapshot {
+        let now = Instant::now();
+        let uptime = now.duration_since(self.started_at);
+        let recent_jobs: u64 = self.recent.lock()
+            .map(|recent| {
+                recent.iter()
+                    .filter(|(at, _)| now.duration_since(*at) <= THROUGHPUT_WINDOW)
+                    .map(|(_, count)| count)
+                    .sum()
+            })
+            .unwrap_or(0);
+        // 起動直後は経過時間で割る
+        let window_secs = uptime.min(THROUGHPUT_WINDOW).as_secs_f64().max(1.0);
+
+        let enqueued = self.enqueued.load(Ordering::Relaxed);
+        let dequeued = self.dequeued.load(Ordering::Relaxed);
+        WriteQueueMetricsSnapshot {
+            queue_depth: enqueued.saturating_sub(dequeued),
+            enqueued,
+            processed: self.processed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+            batches: self.batches.load(Ordering::Relaxed),
+            last_batch_size: self.last_batch_size.load(Ordering::Relaxed),
+            last_batch_ms: self.last_batch_ms.load(Ordering::Relaxed),
+            jobs_per_second: recent_jobs as f64 / window_secs,
+            uptime_secs: uptime.as_secs(),
+        }
+    }
+}
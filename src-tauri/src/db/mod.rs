@@ -1,14 +1,120 @@
+pub mod metrics;
+pub mod outbox;
 pub mod write_job;
 pub mod write_worker;
 
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use async_channel::Sender;
+use tokio::sync::oneshot;
+use crate::database::User;
 
-pub use write_job::WriteJob;
-pub use write_worker::WriteWorker;
+pub use metrics::{write_queue_metrics, WriteQueueMetricsSnapshot};
+pub use outbox::{list_dead_letter_jobs, count_dead_letter_jobs, discard_dead_letter_job, DeadLetterJob};
+pub use write_job::{QueuedWriteJob, WriteJob, WriteJobResult};
+pub use write_worker::{WriteBatchConfig, WriteWorker};
 
 // 書き込みキュー状態
 #[derive(Clone)]
 pub struct WriteQueueState {
-    pub tx: Arc<Sender<WriteJob>>,
+    pub tx: Arc<Sender<QueuedWriteJob>>,
+}
+
+static WRITE_QUEUE: OnceLock<WriteQueueState> = OnceLock::new();
+
+/// Tauriの状態を参照できない処理（APIサーバーなど）から使う書き込みキューを登録（起動時に1回）
+pub fn register_write_queue(state: WriteQueueState) {
+    let _ = WRITE_QUEUE.set(state);
+}
+
+/// 登録済みの書き込みキューを取得
+pub fn write_queue() -> Result<&'static WriteQueueState, String> {
+    WRITE_QUEUE.get().ok_or_else(|| "書き込みキューが初期化されていません".to_string())
+}
+
+impl WriteQueueState {
+    /// ジョブを書き込みキューに送信し、処理結果を待つ（actor は監査ログに実行者として記録される）
+    pub async fn send(&self, job: WriteJob, actor: Option<User>) -> Result<(), String> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.dispatch(QueuedWriteJob { job, actor, outbox_id: None, reply: Some(reply_tx) }).await?;
+
+        reply_rx.await
+            .map_err(|_| "書き込みワーカーから応答がありませんでした".to_string())?
+    }
+
+    /// ジョブを書き込みキューに送信し、処理結果を待たずに戻る
+    /// （書き込みワーカーが取り出すまではメモリ上のキューにあり、その前にアプリが終了した場合は失われる）
+    pub async fn enqueue(&self, job: WriteJob, actor: Option<User>) -> Result<(), String> {
+        self.dispatch(QueuedWriteJob { job, actor, outbox_id: None, reply: None }).await
+    }
+
+    /// 複数のジョブをまとめて送信し、すべての処理結果を待つ（一括取り込み用。書き込みワーカーでバッチ処理される）
+    /// 失敗したジョブがあった場合は、件数と最初のエラーを返す
+    pub async fn send_all(&self, jobs: Vec<WriteJob>, actor: Option<User>) -> Result<(), String> {
+        if jobs.is_empty() {
+            return Ok(());
+        }
+        let mut replies = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            self.dispatch(QueuedWriteJob { job, actor: actor.clone(), outbox_id: None, reply: Some(reply_tx) }).await?;
+            replies.push(reply_rx);
+        }
+
+        let total = replies.len();
+        let mut failed = 0;
+        let mut first_error = None;
+        for reply_rx in replies {
+            let result = reply_rx.await
+                .unwrap_or_else(|_| Err("書き込みワーカーから応答がありませんでした".to_string()));
+            if let Err(e) = result {
+                failed += 1;
+                first_error.get_or_insert(e);
+            }
+        }
+        match first_error {
+            None => Ok(()),
+            Some(e) => Err(format!("{}件中{}件の書き込みに失敗しました: {}", total, failed, e)),
+        }
+    }
+
+    /// 前回の起動で処理されなかったジョブをアウトボックスから再投入（戻り値は再投入した件数）
+    pub fn replay_outbox(&self) -> Result<usize, String> {
+        let entries = outbox::load_outbox()
+            .map_err(|e| format!("アウトボックスの読み込みに失敗しました: {}", e))?;
+        let count = entries.len();
+        for entry in entries {
+            write_queue_metrics().record_enqueued();
+            self.tx.try_send(QueuedWriteJob {
+                job: entry.job,
+                actor: entry.actor,
+                outbox_id: Some(entry.id),
+                reply: None,
+            }).map_err(|e| format!("書き込みキューへの送信に失敗しました: {}", e))?;
+        }
+        Ok(count)
+    }
+
+    /// デッドレターのジョブを再実行し、処理結果を待つ（実行者は元のジョブを依頼したユーザー）
+    pub async fn retry_dead_letter(&self, id: &str) -> Result<(), String> {
+        let entry = outbox::requeue_dead_letter_job(id)
+            .map_err(|e| format!("デッドレターの再投入に失敗しました: {}", e))?
+            .ok_or_else(|| format!("デッドレターが見つかりません: {}", id))?;
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.dispatch(QueuedWriteJob {
+            job: entry.job,
+            actor: entry.actor,
+            outbox_id: Some(entry.id),
+            reply: Some(reply_tx),
+        }).await?;
+
+        reply_rx.await
+            .map_err(|_| "書き込みワーカーから応答がありませんでした".to_string())?
+    }
+
+    async fn dispatch(&self, queued: QueuedWriteJob) -> Result<(), String> {
+        // ワーカーが先に取り出しても滞留件数が負にならないよう、送信前に数える
+        write_queue_metrics().record_enqueued();
+        self.tx.send(queued).await
+            .map_err(|e| format!("書き込みキューへの送信に失敗しました: {}", e))
+    }
 }
@@ -0,0 +1,230 @@
+/**
+ * 書き込みキューの永続化（アウトボックス・デッドレター）
+ *
+ * - キューに送信されたジョブは、書き込みワーカーが取り出した時点で writeOutbox テーブルに保存し、処理が完了したら削除する
+ *   （書き込みはワーカーの接続だけで行い、呼び出し側の接続から書き込まない）
+ * - アプリ終了などで処理されなかったジョブは、次回起動時に writeOutbox から再投入する（少なくとも1回は実行される）
+ * - リトライしても失敗したジョブは writeDeadLetters テーブルに移し、コマンドから確認・再実行できる
+ */
+
+use crate::database::{get_db, Timestamp, User};
+use crate::db::write_job::WriteJob;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// デッドレター一覧で返す件数のデフォルト・上限
+const DEFAULT_LIST_LIMIT: i64 = 100;
+const MAX_LIST_LIMIT: i64 = 1000;
+
+/// アウトボックスに保存されたジョブ
+pub struct OutboxEntry {
+    pub id: String,
+    pub job: WriteJob,
+    pub actor: Option<User>,
+}
+
+/// デッドレター（リトライしても失敗したジョブ）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterJob {
+    pub id: String,
+    #[serde(rename = "jobType")]
+    pub job_type: String,
+    pub job: Value,
+    #[serde(rename = "actorId")]
+    pub actor_id: Option<String>,
+    #[serde(rename = "actorEmail")]
+    pub actor_email: Option<String>,
+    pub attempts: i64,
+    pub error: String,
+    #[serde(rename = "enqueuedAt")]
+    pub enqueued_at: Timestamp,
+    #[serde(rename = "failedAt")]
+    pub failed_at: Timestamp,
+}
+
+fn not_initialized() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+        Some("データベースが初期化されていません".to_string()),
+    )
+}
+
+fn now() -> Timestamp {
+    Timestamp::now()
+}
+
+fn encode_job(job: &WriteJob) -> SqlResult<String> {
+    serde_json::to_string(job).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn decode_job(payload: &str) -> SqlResult<WriteJob> {
+    serde_json::from_str(payload).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn actor_from_columns(actor_id: Option<String>, actor_email: Option<String>) -> Option<User> {
+    actor_id.map(|uid| User {
+        uid,
+        email: actor_email.unwrap_or_default(),
+        email_verified: true,
+    })
+}
+
+/// 新しいジョブを呼び出し側のトランザクション内でアウトボックスに保存（書き込みワーカーから呼び出す。戻り値はアウトボックスID）
+pub(crate) fn save_to_outbox_in(
+    conn: &Connection,
+    job: &WriteJob,
+    actor: Option<&User>,
+    enqueued_at: Timestamp,
+) -> SqlResult<String> {
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO writeOutbox (id, jobType, payload, actorId, actorEmail, enqueuedAt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            id,
+            job.job_type(),
+            encode_job(job)?,
+            actor.map(|u| u.uid.as_str()),
+            actor.map(|u| u.email.as_str()),
+            enqueued_at,
+        ],
+    )?;
+    Ok(id)
+}
+
+/// 未処理のジョブを保存順に取得（起動時の再投入用）
+pub fn load_outbox() -> SqlResult<Vec<OutboxEntry>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let mut stmt = conn.prepare(
+        "SELECT id, payload, actorId, actorEmail FROM writeOutbox ORDER BY enqueuedAt, rowid",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+        ))
+    })?.collect::<SqlResult<Vec<_>>>()?;
+
+    let mut entries = Vec::new();
+    for (id, payload, actor_id, actor_email) in rows {
+        match decode_job(&payload) {
+            Ok(job) => entries.push(OutboxEntry {
+                id,
+                job,
+                actor: actor_from_columns(actor_id, actor_email),
+            }),
+            // 読み込めないジョブは再投入せずデッドレターに移す
+            Err(e) => {
+                eprintln!("[DB-WRITER] ⚠️ アウトボックスのジョブを読み込めませんでした: id={}, error={}", id, e);
+                move_to_dead_letters(&conn, &id, 0, &format!("ジョブの読み込みに失敗しました: {}", e))?;
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// 完了したジョブをアウトボックスから削除
+pub(crate) fn complete_outbox_entry(conn: &Connection, id: &str) -> SqlResult<()> {
+    conn.execute("DELETE FROM writeOutbox WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+/// 失敗したジョブをアウトボックスからデッドレターに移動
+pub(crate) fn move_to_dead_letters(conn: &Connection, id: &str, attempts: i64, error: &str) -> SqlResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT OR REPLACE INTO writeDeadLetters (id, jobType, payload, actorId, actorEmail, attempts, error, enqueuedAt, failedAt)
+         SELECT id, jobType, payload, actorId, actorEmail, ?2, ?3, enqueuedAt, ?4 FROM writeOutbox WHERE id = ?1",
+        params![id, attempts, error, now()],
+    )?;
+    tx.execute("DELETE FROM writeOutbox WHERE id = ?1", params![id])?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn dead_letter_from_row(row: &Row) -> SqlResult<DeadLetterJob> {
+    let payload: String = row.get(2)?;
+    Ok(DeadLetterJob {
+        id: row.get(0)?,
+        job_type: row.get(1)?,
+        job: serde_json::from_str(&payload).unwrap_or(Value::String(payload)),
+        actor_id: row.get(3)?,
+        actor_email: row.get(4)?,
+        attempts: row.get(5)?,
+        error: row.get(6)?,
+        enqueued_at: row.get(7)?,
+        failed_at: row.get(8)?,
+    })
+}
+
+/// デッドレターを新しい順に取得
+pub fn list_dead_letter_jobs(limit: Option<i64>) -> SqlResult<Vec<DeadLetterJob>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+
+    let mut stmt = conn.prepare(
+        "SELECT id, jobType, payload, actorId, actorEmail, attempts, error, enqueuedAt, failedAt
+         FROM writeDeadLetters ORDER BY failedAt DESC, rowid DESC LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(params![limit], dead_letter_from_row)?;
+    rows.collect()
+}
+
+/// デッドレターの件数を取得
+pub fn count_dead_letter_jobs() -> SqlResult<i64> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    conn.query_row("SELECT COUNT(*) FROM writeDeadLetters", [], |row| row.get(0))
+}
+
+/// デッドレターをアウトボックスに戻す（再実行用。存在しない場合は None）
+pub fn requeue_dead_letter_job(id: &str) -> SqlResult<Option<OutboxEntry>> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let tx = conn.unchecked_transaction()?;
+
+    let row = tx.query_row(
+        "SELECT payload, actorId, actorEmail FROM writeDeadLetters WHERE id = ?1",
+        params![id],
+        |row| Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, Option<String>>(2)?,
+        )),
+    ).optional()?;
+    let (payload, actor_id, actor_email) = match row {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+    let job = decode_job(&payload)?;
+
+    tx.execute(
+        "INSERT OR REPLACE INTO writeOutbox (id, jobType, payload, actorId, actorEmail, enqueuedAt)
+         SELECT id, jobType, payload, actorId, actorEmail, ?2 FROM writeDeadLetters WHERE id = ?1",
+        params![id, now()],
+    )?;
+    tx.execute("DELETE FROM writeDeadLetters WHERE id = ?1", params![id])?;
+    tx.commit()?;
+
+    Ok(Some(OutboxEntry {
+        id: id.to_string(),
+        job,
+        actor: actor_from_columns(actor_id, actor_email),
+    }))
+}
+
+/// デッドレターを破棄（存在しなかった場合は false）
+pub fn discard_dead_letter_job(id: &str) -> SqlResult<bool> {
+    let db = get_db().ok_or_else(not_initialized)?;
+    let conn = db.get_connection()?;
+    let deleted = conn.execute("DELETE FROM writeDeadLetters WHERE id = ?1", params![id])?;
+    Ok(deleted > 0)
+}
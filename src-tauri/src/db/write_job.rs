@@ -3,10 +3,15 @@
  * データベースへの書き込み操作を表すenum
  */
 
+use crate::database::{Agent, SearchTarget, Task, TaskExecution, Theme, User};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use tokio::sync::oneshot;
 
-#[derive(Debug, Clone)]
+/// 書き込みジョブ（アウトボックスに保存するため JSON にシリアライズできる）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum WriteJob {
     // エンティティ操作
     UpsertEntity {
@@ -52,11 +57,74 @@ pub enum WriteJob {
     },
     
     // 議事録操作
+    UpsertMeetingNote {
+        meeting_note_id: String,
+        payload: HashMap<String, Value>,
+    },
+    // 指定したフィールドのみ更新
+    UpdateMeetingNote {
+        meeting_note_id: String,
+        payload: HashMap<String, Value>,
+    },
     DeleteMeetingNote {
         meeting_note_id: String,
         organization_id: String,
     },
     
+    // 注力施策操作
+    UpsertFocusInitiative {
+        focus_initiative_id: String,
+        payload: HashMap<String, Value>,
+    },
+    // 指定したフィールドのみ更新
+    UpdateFocusInitiative {
+        focus_initiative_id: String,
+        payload: HashMap<String, Value>,
+    },
+    DeleteFocusInitiative {
+        focus_initiative_id: String,
+    },
+    
+    // テーマ操作
+    SaveTheme {
+        theme: Theme,
+    },
+    DeleteTheme {
+        theme_id: String,
+    },
+    UpdateThemePositions {
+        updates: Vec<(String, i32)>,
+    },
+    
+    // タスク操作
+    SaveTask {
+        task: Task,
+    },
+    DeleteTask {
+        task_id: String,
+    },
+    SaveTaskExecution {
+        execution: TaskExecution,
+    },
+    // タスクをシステムプロンプトのバージョンに固定（None の場合は固定を解除）
+    SetTaskPromptVersion {
+        task_id: String,
+        version: Option<i64>,
+    },
+    
+    // Agent操作
+    SaveAgent {
+        agent: Agent,
+    },
+    DeleteAgent {
+        agent_id: String,
+    },
+    // システムプロンプトを指定したバージョンに戻す（新しいバージョンとして保存）
+    RollbackAgentPrompt {
+        agent_id: String,
+        version: i64,
+    },
+    
     // ChromaDB同期状態の更新
     UpdateChromaSyncStatus {
         entity_type: String, // "entity", "relation", "topic", "meetingNote"
@@ -64,4 +132,97 @@ pub enum WriteJob {
         synced: bool, // true: 同期成功, false: 同期失敗
         error: Option<String>, // エラーメッセージ（失敗時）
     },
+    
+    // 検索でヒットした行の searchCount / lastSearchDate を更新
+    RecordSearchHits {
+        target: SearchTarget,
+        ids: Vec<String>,
+    },
+}
+
+impl WriteJob {
+    /// ジョブの種別名（アウトボックス・デッドレターに記録する）
+    pub fn job_type(&self) -> &'static str {
+        match self {
+            WriteJob::UpsertEntity { .. } => "UpsertEntity",
+            WriteJob::DeleteEntities { .. } => "DeleteEntities",
+            WriteJob::UpsertRelation { .. } => "UpsertRelation",
+            WriteJob::DeleteRelations { .. } => "DeleteRelations",
+            WriteJob::UpsertTopic { .. } => "UpsertTopic",
+            WriteJob::DeleteTopics { .. } => "DeleteTopics",
+            WriteJob::UpsertOrganization { .. } => "UpsertOrganization",
+            WriteJob::DeleteOrganization { .. } => "DeleteOrganization",
+            WriteJob::UpsertMeetingNote { .. } => "UpsertMeetingNote",
+            WriteJob::UpdateMeetingNote { .. } => "UpdateMeetingNote",
+            WriteJob::DeleteMeetingNote { .. } => "DeleteMeetingNote",
+            WriteJob::UpsertFocusInitiative { .. } => "UpsertFocusInitiative",
+            WriteJob::UpdateFocusInitiative { .. } => "UpdateFocusInitiative",
+            WriteJob::DeleteFocusInitiative { .. } => "DeleteFocusInitiative",
+            WriteJob::SaveTheme { .. } => "SaveTheme",
+            WriteJob::DeleteTheme { .. } => "DeleteTheme",
+            WriteJob::UpdateThemePositions { .. } => "UpdateThemePositions",
+            WriteJob::SaveTask { .. } => "SaveTask",
+            WriteJob::DeleteTask { .. } => "DeleteTask",
+            WriteJob::SaveTaskExecution { .. } => "SaveTaskExecution",
+            WriteJob::SetTaskPromptVersion { .. } => "SetTaskPromptVersion",
+            WriteJob::SaveAgent { .. } => "SaveAgent",
+            WriteJob::DeleteAgent { .. } => "DeleteAgent",
+            WriteJob::RollbackAgentPrompt { .. } => "RollbackAgentPrompt",
+            WriteJob::UpdateChromaSyncStatus { .. } => "UpdateChromaSyncStatus",
+            WriteJob::RecordSearchHits { .. } => "RecordSearchHits",
+        }
+    }
+
+    /// 削除系のジョブか（実行には削除権限が必要）
+    pub fn is_delete(&self) -> bool {
+        matches!(
+            self,
+            WriteJob::DeleteEntities { .. }
+                | WriteJob::DeleteRelations { .. }
+                | WriteJob::DeleteTopics { .. }
+                | WriteJob::DeleteOrganization { .. }
+                | WriteJob::DeleteMeetingNote { .. }
+                | WriteJob::DeleteFocusInitiative { .. }
+                | WriteJob::DeleteTheme { .. }
+                | WriteJob::DeleteTask { .. }
+                | WriteJob::DeleteAgent { .. }
+        )
+    }
+
+    /// 他のジョブとまとめて1つのトランザクションで処理できるか
+    /// （エンティティ・リレーション・トピック・組織の追加・更新とChromaDB同期状態の更新のみ。それ以外は単独で処理する）
+    pub fn is_batchable(&self) -> bool {
+        self.coalesce_key().is_some()
+    }
+
+    /// 同じ行への書き込みを合流させるためのキー（後のジョブの内容で上書きして1回だけ書き込む）
+    pub fn coalesce_key(&self) -> Option<String> {
+        let id = match self {
+            WriteJob::UpsertEntity { entity_id, .. } => entity_id,
+            WriteJob::UpsertRelation { relation_id, .. } => relation_id,
+            WriteJob::UpsertTopic { topic_id, .. } => topic_id,
+            WriteJob::UpsertOrganization { organization_id, .. } => organization_id,
+            WriteJob::UpdateChromaSyncStatus { entity_type, entity_id, .. } => {
+                return Some(format!("{}:{}:{}", self.job_type(), entity_type, entity_id));
+            }
+            _ => return None,
+        };
+        Some(format!("{}:{}", self.job_type(), id))
+    }
+}
+
+/// ジョブの処理結果（失敗時はエラーメッセージ）
+pub type WriteJobResult = Result<(), String>;
+
+/// 書き込みキューに送信するジョブ（実行者は監査ログに記録される）
+#[derive(Debug)]
+pub struct QueuedWriteJob {
+    pub job: WriteJob,
+    /// ジョブを依頼したユーザー（バックグラウンド処理の場合は None = system）
+    pub actor: Option<User>,
+    /// アウトボックス（writeOutbox）上のID。処理が完了したら削除される
+    /// 新しいジョブは None で、書き込みワーカーが取り出した時点で保存する
+    pub outbox_id: Option<String>,
+    /// 処理結果の返信先（結果を待たない場合は None）
+    pub reply: Option<oneshot::Sender<WriteJobResult>>,
 }
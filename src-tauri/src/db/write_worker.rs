@@ -1,86 +1,752 @@
 /**
  * 書き込み専用ワーカー
  * すべてのデータベース書き込み操作を1本の通路に集約
+ * 削除系ジョブは行をゴミ箱に移動し、コミット後にVectorStore経由で埋め込みも削除する（復元時に再同期される）
+ * 追加・更新系ジョブは再同期ワーカーを起こし、Rust側で埋め込みを生成して索引する
+ * データの変更は同じトランザクション内で監査ログに記録する（実行者はジョブを依頼したユーザー、ChromaDB同期状態の更新は除く）
+ * 取り出した新しいジョブはワーカーの接続でアウトボックスに保存する（書き込みはこのワーカーからのみ行う）
+ * ジョブの書き込みとアウトボックスからの削除は同じトランザクションでコミットする（コミット済みのジョブは再投入しない）
+ * 失敗したジョブは待ち時間をおいて一定回数リトライし（待っている間も後続のジョブは処理する）、
+ * それでも失敗した場合はデッドレターに移す。処理結果は依頼元に返信する
+ *
+ * キューに溜まっているジョブはまとめて取り出し、追加・更新系は同じ行への書き込みを合流させたうえで
+ * 1つのトランザクションで書き込む（削除系ジョブと、既存の関数で書き込む議事録・注力施策・テーマ・タスク・Agentのジョブは
+ * その前後で単独で処理し、順序は保つ）
  */
 
 use async_channel::Receiver;
 use crate::database::pool::DatabasePool;
-use crate::db::write_job::WriteJob;
+use crate::database::{trigger_chroma_sync, with_user_sync, snapshot_row, audit_change, AuditAction, Timestamp, User};
+use crate::database::{
+    trash_item_in, trash_organization_in, trash_meeting_note_in, set_doc_in, update_doc_in, relaxes_foreign_keys,
+    save_theme_in, delete_theme_in, update_theme_positions_in, save_task_in, delete_task_in, save_task_execution_in,
+    save_agent_in, delete_agent_in, record_search_hits_in, set_task_prompt_version_in, rollback_agent_prompt_in,
+};
+use crate::database::vector_store::VectorStore;
+use crate::db::metrics::write_queue_metrics;
+use crate::db::outbox::{complete_outbox_entry, move_to_dead_letters, save_to_outbox_in};
+use crate::db::write_job::{QueuedWriteJob, WriteJob, WriteJobResult};
 use anyhow::{Context, Result};
 use rusqlite::params;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use serde_json::Value;
+use tokio::sync::oneshot;
+
+/// 1つのジョブを試行する最大回数（データベースのロック競合などの一時的な失敗に備える）
+const MAX_ATTEMPTS: u32 = 3;
+/// リトライまでの待ち時間（試行回数に比例して延ばす。待っている間も他のジョブは処理する）
+const RETRY_DELAY_MS: u64 = 500;
+
+/// バッチの大きさ・待ち時間のデフォルト
+const DEFAULT_MAX_BATCH_SIZE: usize = 200;
+const DEFAULT_MAX_BATCH_LATENCY_MS: u64 = 10;
+
+/// バッチ書き込みの設定
+#[derive(Debug, Clone, Copy)]
+pub struct WriteBatchConfig {
+    /// 1回のトランザクションで処理する最大ジョブ数
+    pub max_batch_size: usize,
+    /// 後続のジョブを待つ最大時間（追加・更新系ジョブのみ待つ）
+    pub max_latency: Duration,
+}
+
+impl Default for WriteBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            max_latency: Duration::from_millis(DEFAULT_MAX_BATCH_LATENCY_MS),
+        }
+    }
+}
+
+impl WriteBatchConfig {
+    /// 環境変数（WRITE_BATCH_SIZE / WRITE_BATCH_MAX_LATENCY_MS）から読み込む
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let max_batch_size = std::env::var("WRITE_BATCH_SIZE")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(default.max_batch_size);
+        let max_latency = std::env::var("WRITE_BATCH_MAX_LATENCY_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(default.max_latency);
+        Self { max_batch_size, max_latency }
+    }
+}
+
+/// SQLiteから削除した行に対応する、ベクトルストアから削除すべき埋め込み
+#[derive(Default)]
+struct EmbeddingCleanup {
+    organization_id: String,
+    entity_ids: Vec<String>,
+    relation_ids: Vec<String>,
+    topic_ids: Vec<String>,
+    whole_organization: bool,
+}
+
+/// 処理待ちのジョブ（合流したジョブのアウトボックスIDと返信先をまとめて持つ）
+struct PendingJob {
+    job: WriteJob,
+    actor: Option<User>,
+    outbox_ids: Vec<String>,
+    replies: Vec<oneshot::Sender<WriteJobResult>>,
+    /// 合流したジョブの件数
+    count: usize,
+}
+
+impl PendingJob {
+    fn new(queued: QueuedWriteJob) -> Self {
+        Self {
+            job: queued.job,
+            actor: queued.actor,
+            outbox_ids: queued.outbox_id.into_iter().collect(),
+            replies: queued.reply.into_iter().collect(),
+            count: 1,
+        }
+    }
+
+    /// 後続のジョブを合流させられるか（監査ログの実行者が変わらないよう同じ実行者のみ）
+    fn can_merge(&self, queued: &QueuedWriteJob) -> bool {
+        self.actor.as_ref().map(|u| &u.uid) == queued.actor.as_ref().map(|u| &u.uid)
+    }
+
+    /// 同じ行への後続のジョブを合流させる（内容は後のジョブで上書きする）
+    fn merge(&mut self, queued: QueuedWriteJob) {
+        self.job = queued.job;
+        self.outbox_ids.extend(queued.outbox_id);
+        self.replies.extend(queued.reply);
+        self.count += 1;
+    }
+
+    /// 合流したジョブの件数
+    fn len(&self) -> usize {
+        self.count
+    }
+
+    fn respond(self, result: &WriteJobResult) {
+        for reply in self.replies {
+            // 依頼元が結果を待たずに終了している場合は返信先がないため無視する
+            let _ = reply.send(result.clone());
+        }
+    }
+}
+
+/// 失敗してリトライを待っているジョブ
+struct RetryJob {
+    pending: PendingJob,
+    /// 次の試行が何回目か
+    attempt: u32,
+    due: Instant,
+}
+
+/// 順序を保ったまま区切った処理単位
+enum Segment {
+    /// 1つのトランザクションでまとめて書き込む追加・更新系ジョブ
+    Batch(Vec<PendingJob>),
+    /// 単独で処理するジョブ
+    Single(PendingJob),
+}
+
+/// 取り出したジョブを、同じ行への追加・更新を合流させながら処理単位に区切る
+fn coalesce(jobs: Vec<QueuedWriteJob>) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut batch: Vec<PendingJob> = Vec::new();
+    let mut index_by_key: HashMap<String, usize> = HashMap::new();
+
+    for queued in jobs {
+        let key = match queued.job.coalesce_key() {
+            Some(key) => key,
+            None => {
+                // 単独で処理するジョブの前後で合流させると順序が変わるため、ここでバッチを区切る
+                if !batch.is_empty() {
+                    segments.push(Segment::Batch(std::mem::take(&mut batch)));
+                }
+                index_by_key.clear();
+                segments.push(Segment::Single(PendingJob::new(queued)));
+                continue;
+            }
+        };
+
+        if let Some(&index) = index_by_key.get(&key) {
+            if batch[index].can_merge(&queued) {
+                batch[index].merge(queued);
+                write_queue_metrics().record_coalesced();
+                continue;
+            }
+        }
+        index_by_key.insert(key, batch.len());
+        batch.push(PendingJob::new(queued));
+    }
+
+    if !batch.is_empty() {
+        segments.push(Segment::Batch(batch));
+    }
+    segments
+}
+
+/// 追加・更新した行（chromaSynced = 0）を再同期ワーカーで索引する必要があるか
+fn needs_chroma_sync(job: &WriteJob) -> bool {
+    matches!(
+        job,
+        WriteJob::UpsertEntity { .. } | WriteJob::UpsertRelation { .. } | WriteJob::UpsertTopic { .. }
+    )
+}
 
 pub struct WriteWorker {
     pool: DatabasePool,
+    vector_store: Option<Arc<dyn VectorStore>>,
+    batch_config: WriteBatchConfig,
 }
 
 impl WriteWorker {
     pub fn new(pool: DatabasePool) -> Self {
-        Self { pool }
+        Self { pool, vector_store: None, batch_config: WriteBatchConfig::default() }
     }
 
-    pub async fn run(&self, rx: Receiver<WriteJob>) {
-        eprintln!("[DB-WRITER] 書き込みワーカーを起動しました");
+    /// 削除時に埋め込みを同期するベクトルストアを設定
+    pub fn with_vector_store(mut self, vector_store: Arc<dyn VectorStore>) -> Self {
+        self.vector_store = Some(vector_store);
+        self
+    }
+
+    /// バッチ書き込みの設定を変更
+    pub fn with_batch_config(mut self, batch_config: WriteBatchConfig) -> Self {
+        self.batch_config = batch_config;
+        self
+    }
+
+    pub async fn run(&self, rx: Receiver<QueuedWriteJob>) {
+        eprintln!(
+            "[DB-WRITER] 書き込みワーカーを起動しました（バッチ: 最大{}件, 待ち時間{}ms）",
+            self.batch_config.max_batch_size,
+            self.batch_config.max_latency.as_millis()
+        );
         
-        while let Ok(job) = rx.recv().await {
-            if let Err(e) = self.handle_job(&job).await {
-                eprintln!("[DB-WRITER] ジョブ処理エラー: {e:#}");
-                eprintln!("[DB-WRITER] 失敗したジョブ: {:?}", job);
-                // TODO: エラーログ・監視への通知
+        let mut retries: Vec<RetryJob> = Vec::new();
+        let mut closed = false;
+        loop {
+            // 新しいジョブか、リトライの期限のどちらか早いほうを待つ
+            let next_due = retries.iter().map(|r| r.due).min();
+            let first = match (closed, next_due) {
+                (true, None) => break,
+                (true, Some(due)) => {
+                    tokio::time::sleep_until(tokio::time::Instant::from_std(due)).await;
+                    None
+                }
+                (false, Some(due)) => tokio::select! {
+                    received = rx.recv() => {
+                        closed = received.is_err();
+                        received.ok()
+                    }
+                    _ = tokio::time::sleep_until(tokio::time::Instant::from_std(due)) => None,
+                },
+                (false, None) => {
+                    let received = rx.recv().await;
+                    closed = received.is_err();
+                    received.ok()
+                }
+            };
+
+            let jobs = match first {
+                Some(first) => self.collect_batch(first, &rx).await,
+                None => Vec::new(),
+            };
+            let size = jobs.len();
+            write_queue_metrics().record_dequeued(size);
+            let jobs = self.save_new_jobs(jobs);
+
+            // 期限が来たリトライと、新しいジョブと同じ行に書き込むリトライを先に処理する（同じ行への書き込みの順序を保つ）
+            let keys: HashSet<String> = jobs.iter().filter_map(|q| q.job.coalesce_key()).collect();
+            let now = Instant::now();
+            let (ready, waiting): (Vec<RetryJob>, Vec<RetryJob>) = std::mem::take(&mut retries)
+                .into_iter()
+                .partition(|r| r.due <= now || r.pending.job.coalesce_key().is_some_and(|key| keys.contains(&key)));
+            retries = waiting;
+            if jobs.is_empty() && ready.is_empty() {
+                continue;
+            }
+
+            let started_at = Instant::now();
+            let mut processed = 0;
+            for retry in ready {
+                let (count, retry) = self.process_single(retry.pending, retry.attempt).await;
+                processed += count;
+                retries.extend(retry);
             }
+            for segment in coalesce(jobs) {
+                let (count, retry) = match segment {
+                    Segment::Batch(pending) => self.process_batch(pending).await,
+                    Segment::Single(pending) => {
+                        let (count, retry) = self.process_single(pending, 1).await;
+                        (count, retry.into_iter().collect())
+                    }
+                };
+                processed += count;
+                retries.extend(retry);
+            }
+            write_queue_metrics().record_batch(size, processed, started_at.elapsed());
         }
         
         eprintln!("[DB-WRITER] 書き込みワーカーを停止しました");
     }
 
-    async fn handle_job(&self, job: &WriteJob) -> Result<()> {
+    /// 新しいジョブを1つのトランザクションでアウトボックスに保存する（戻り値は処理を続けるジョブ）
+    /// 保存できなかったジョブは依頼元にエラーを返して処理しない
+    fn save_new_jobs(&self, jobs: Vec<QueuedWriteJob>) -> Vec<QueuedWriteJob> {
+        let new_count = jobs.iter().filter(|q| q.outbox_id.is_none()).count();
+        if new_count == 0 {
+            return jobs;
+        }
+
+        let save = || -> Result<Vec<String>> {
+            let conn = self.pool.get_connection()
+                .context("Failed to get database connection")?;
+            let tx = conn.unchecked_transaction()?;
+            let enqueued_at = Timestamp::now();
+            let mut ids = Vec::with_capacity(new_count);
+            for queued in jobs.iter().filter(|q| q.outbox_id.is_none()) {
+                ids.push(save_to_outbox_in(&tx, &queued.job, queued.actor.as_ref(), enqueued_at)?);
+            }
+            tx.commit()?;
+            Ok(ids)
+        };
+
+        match save() {
+            Ok(ids) => {
+                let mut ids = ids.into_iter();
+                jobs.into_iter()
+                    .map(|mut queued| {
+                        if queued.outbox_id.is_none() {
+                            queued.outbox_id = ids.next();
+                        }
+                        queued
+                    })
+                    .collect()
+            }
+            Err(e) => {
+                let error = format!("書き込みジョブの保存に失敗しました: {e:#}");
+                eprintln!("[DB-WRITER] ❌ {}（{}件）", error, new_count);
+                write_queue_metrics().record_failed(new_count);
+                let (new_jobs, saved): (Vec<_>, Vec<_>) = jobs.into_iter().partition(|q| q.outbox_id.is_none());
+                for queued in new_jobs {
+                    if let Some(reply) = queued.reply {
+                        let _ = reply.send(Err(error.clone()));
+                    }
+                }
+                saved
+            }
+        }
+    }
+
+    /// キューに溜まっているジョブを最大件数まで取り出す（追加・更新系の場合は待ち時間の上限まで後続を待つ）
+    async fn collect_batch(&self, first: QueuedWriteJob, rx: &Receiver<QueuedWriteJob>) -> Vec<QueuedWriteJob> {
+        let wait = first.job.is_batchable();
+        let deadline = Instant::now() + self.batch_config.max_latency;
+        let mut jobs = vec![first];
+
+        while jobs.len() < self.batch_config.max_batch_size {
+            if let Ok(queued) = rx.try_recv() {
+                jobs.push(queued);
+                continue;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !wait || remaining.is_zero() {
+                break;
+            }
+            match tokio::time::timeout(remaining, rx.recv()).await {
+                Ok(Ok(queued)) => jobs.push(queued),
+                _ => break,
+            }
+        }
+        jobs
+    }
+
+    /// 追加・更新系ジョブを1つのトランザクションで書き込む（戻り値は成功したジョブ数とリトライを待つジョブ）
+    /// 失敗した場合は1件ずつ処理し直し、原因のジョブだけをリトライ・デッドレターの対象にする
+    async fn process_batch(&self, pending: Vec<PendingJob>) -> (usize, Vec<RetryJob>) {
+        if pending.len() == 1 {
+            let single = pending.into_iter().next().unwrap();
+            let (processed, retry) = self.process_single(single, 1).await;
+            return (processed, retry.into_iter().collect());
+        }
+
+        match self.apply_batch(&pending) {
+            Ok(()) => {
+                if pending.iter().any(|p| needs_chroma_sync(&p.job)) {
+                    trigger_chroma_sync();
+                }
+                let processed = pending.iter().map(|p| p.len()).sum();
+                let ok: WriteJobResult = Ok(());
+                for p in pending {
+                    p.respond(&ok);
+                }
+                (processed, Vec::new())
+            }
+            Err(e) => {
+                eprintln!("[DB-WRITER] ⚠️ バッチ書き込みに失敗したため1件ずつ処理します（{}件）: {e:#}", pending.len());
+                let mut processed = 0;
+                let mut retries = Vec::new();
+                for p in pending {
+                    let (count, retry) = self.process_single(p, 1).await;
+                    processed += count;
+                    retries.extend(retry);
+                }
+                (processed, retries)
+            }
+        }
+    }
+
+    /// バッチを1つのトランザクションで書き込み、同じトランザクションでアウトボックスから削除する
+    fn apply_batch(&self, pending: &[PendingJob]) -> Result<()> {
         let conn = self.pool.get_connection()
             .context("Failed to get database connection")?;
+        let tx = conn.unchecked_transaction()?;
 
-        match job {
-            WriteJob::UpsertEntity { entity_id, organization_id, payload } => {
-                self.upsert_entity(&conn, entity_id, organization_id, payload)?;
+        for p in pending {
+            // 依頼したユーザーを実行者として処理する（監査ログ用）
+            match p.actor.clone() {
+                Some(user) => with_user_sync(user, || self.apply_batchable(&tx, &p.job))?,
+                None => self.apply_batchable(&tx, &p.job)?,
             }
-            
+            for outbox_id in &p.outbox_ids {
+                complete_outbox_entry(&tx, outbox_id)?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// ジョブを処理し、アウトボックスの状態を更新する（戻り値は成功したジョブ数と、失敗した場合のリトライ）
+    /// attempt は何回目の試行か。上限に達するまではスリープせずリトライを予約し、上限に達したらデッドレターに移す
+    async fn process_single(&self, pending: PendingJob, attempt: u32) -> (usize, Option<RetryJob>) {
+        let error = match self.handle_job(&pending).await {
+            Ok(()) => {
+                let processed = pending.len();
+                pending.respond(&Ok(()));
+                return (processed, None);
+            }
+            Err(e) if attempt < MAX_ATTEMPTS => {
+                let delay = Duration::from_millis(RETRY_DELAY_MS * attempt as u64);
+                eprintln!(
+                    "[DB-WRITER] ⚠️ ジョブ処理エラー（{}回目、{}ms後にリトライします）: {e:#}",
+                    attempt,
+                    delay.as_millis()
+                );
+                return (0, Some(RetryJob { pending, attempt: attempt + 1, due: Instant::now() + delay }));
+            }
+            Err(e) => format!("{e:#}"),
+        };
+
+        eprintln!("[DB-WRITER] ❌ ジョブ処理エラー（デッドレターに移動します）: {}", error);
+        eprintln!("[DB-WRITER] 失敗したジョブ: {:?}", pending.job);
+        for outbox_id in &pending.outbox_ids {
+            self.finish_outbox_entry(outbox_id, |conn| move_to_dead_letters(conn, outbox_id, attempt as i64, &error));
+        }
+        write_queue_metrics().record_failed(pending.len());
+        pending.respond(&Err(error));
+        (0, None)
+    }
+
+    /// アウトボックスを更新（失敗した場合は次回起動時に再投入される）
+    fn finish_outbox_entry(&self, outbox_id: &str, update: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<()>) {
+        let result = self.pool.get_connection()
+            .map_err(|e| e.to_string())
+            .and_then(|conn| update(&conn).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            eprintln!("[DB-WRITER] ⚠️ アウトボックスの更新に失敗しました: id={}, error={}", outbox_id, e);
+        }
+    }
+
+    async fn handle_job(&self, pending: &PendingJob) -> Result<()> {
+        let job = &pending.job;
+
+        // SQLiteへの書き込み（接続は.awaitの前に解放する）
+        let cleanup = self.commit_job(pending)?;
+        
+        // ベクトルストアから対応する埋め込みを削除
+        if let Some(cleanup) = cleanup {
+            self.cleanup_embeddings(cleanup).await;
+        }
+        
+        // 追加・更新した行（chromaSynced = 0）を再同期ワーカーで索引する
+        if needs_chroma_sync(job) {
+            trigger_chroma_sync();
+        }
+        
+        Ok(())
+    }
+
+    /// ジョブを1つのトランザクションで書き込み、同じトランザクションでアウトボックスから削除する
+    /// （コミット前に終了した場合は次回起動時に再投入され、コミット後に終了した場合は再実行されない）
+    fn commit_job(&self, pending: &PendingJob) -> Result<Option<EmbeddingCleanup>> {
+        let conn = self.pool.get_connection()
+            .context("Failed to get database connection")?;
+
+        // set_doc と同じく、議事録・注力施策の保存時は外部キー制約を無効化する（トランザクションの開始前に切り替える）
+        let relax_foreign_keys = match &pending.job {
+            WriteJob::UpsertMeetingNote { .. } => relaxes_foreign_keys("meetingNotes"),
+            WriteJob::UpsertFocusInitiative { .. } => relaxes_foreign_keys("focusInitiatives"),
+            _ => false,
+        };
+        if relax_foreign_keys {
+            conn.execute("PRAGMA foreign_keys = OFF", [])?;
+        }
+
+        let apply = || -> Result<Option<EmbeddingCleanup>> {
+            let tx = conn.unchecked_transaction()?;
+            // 依頼したユーザーを実行者として処理する（監査ログ用）
+            let cleanup = match pending.actor.clone() {
+                Some(user) => with_user_sync(user, || self.apply_job(&tx, &pending.job))?,
+                None => self.apply_job(&tx, &pending.job)?,
+            };
+            for outbox_id in &pending.outbox_ids {
+                complete_outbox_entry(&tx, outbox_id)?;
+            }
+            tx.commit()?;
+            Ok(cleanup)
+        };
+        let result = apply();
+
+        if relax_foreign_keys {
+            if let Err(e) = conn.execute("PRAGMA foreign_keys = ON", []) {
+                eprintln!("[DB-WRITER] ⚠️ 外部キー制約の再有効化に失敗しました: {}", e);
+            }
+        }
+        result
+    }
+
+    /// ジョブを呼び出し側のトランザクション内で書き込む（戻り値はコミット後に削除する埋め込み）
+    fn apply_job(&self, conn: &rusqlite::Connection, job: &WriteJob) -> Result<Option<EmbeddingCleanup>> {
+        let cleanup = match job {
             WriteJob::DeleteEntities { entity_ids, organization_id } => {
-                self.delete_entities(&conn, entity_ids, organization_id)?;
+                self.delete_entities(conn, entity_ids, organization_id)?;
+                Some(EmbeddingCleanup {
+                    organization_id: organization_id.clone(),
+                    entity_ids: entity_ids.clone(),
+                    ..Default::default()
+                })
             }
             
-            WriteJob::UpsertRelation { relation_id, organization_id, payload } => {
-                self.upsert_relation(&conn, relation_id, organization_id, payload)?;
+            WriteJob::DeleteRelations { relation_ids, organization_id } => {
+                self.delete_relations(conn, relation_ids, organization_id)?;
+                Some(EmbeddingCleanup {
+                    organization_id: organization_id.clone(),
+                    relation_ids: relation_ids.clone(),
+                    ..Default::default()
+                })
             }
             
-            WriteJob::DeleteRelations { relation_ids, organization_id } => {
-                self.delete_relations(&conn, relation_ids, organization_id)?;
+            WriteJob::DeleteTopics { topic_ids, organization_id } => {
+                self.delete_topics(conn, topic_ids, organization_id)?;
+                Some(EmbeddingCleanup {
+                    organization_id: organization_id.clone(),
+                    topic_ids: topic_ids.clone(),
+                    ..Default::default()
+                })
             }
             
-            WriteJob::UpsertTopic { topic_id, meeting_note_id, organization_id, payload } => {
-                self.upsert_topic(&conn, topic_id, meeting_note_id, organization_id, payload)?;
+            WriteJob::DeleteOrganization { organization_id } => {
+                self.delete_organization(conn, organization_id)?;
+                Some(EmbeddingCleanup {
+                    organization_id: organization_id.clone(),
+                    whole_organization: true,
+                    ..Default::default()
+                })
             }
             
-            WriteJob::DeleteTopics { topic_ids, organization_id } => {
-                self.delete_topics(&conn, topic_ids, organization_id)?;
+            WriteJob::DeleteMeetingNote { meeting_note_id, organization_id } => {
+                // 削除前に関連するトピック・リレーションのIDを取得しておく
+                let (topic_ids, relation_ids) = self.collect_meeting_note_children(conn, meeting_note_id)?;
+                self.delete_meeting_note(conn, meeting_note_id, organization_id)?;
+                Some(EmbeddingCleanup {
+                    organization_id: organization_id.clone(),
+                    topic_ids,
+                    relation_ids,
+                    ..Default::default()
+                })
             }
             
-            WriteJob::UpsertOrganization { organization_id, payload } => {
-                self.upsert_organization(&conn, organization_id, payload)?;
+            WriteJob::UpsertMeetingNote { meeting_note_id, payload } => {
+                self.set_document(conn, "meetingNotes", meeting_note_id, payload)?;
+                None
             }
             
-            WriteJob::DeleteOrganization { organization_id } => {
-                self.delete_organization(&conn, organization_id)?;
+            WriteJob::UpdateMeetingNote { meeting_note_id, payload } => {
+                self.update_document(conn, "meetingNotes", meeting_note_id, payload)?;
+                None
             }
             
-            WriteJob::DeleteMeetingNote { meeting_note_id, organization_id } => {
-                self.delete_meeting_note(&conn, meeting_note_id, organization_id)?;
+            WriteJob::UpsertFocusInitiative { focus_initiative_id, payload } => {
+                self.set_document(conn, "focusInitiatives", focus_initiative_id, payload)?;
+                None
+            }
+            
+            WriteJob::UpdateFocusInitiative { focus_initiative_id, payload } => {
+                self.update_document(conn, "focusInitiatives", focus_initiative_id, payload)?;
+                None
+            }
+            
+            WriteJob::DeleteFocusInitiative { focus_initiative_id } => {
+                trash_item_in(conn, "focusInitiatives", focus_initiative_id)
+                    .map_err(|e| anyhow::anyhow!("Failed to move focus initiative to trash: {}", e))?;
+                None
+            }
+            
+            WriteJob::SaveTheme { theme } => {
+                save_theme_in(conn, theme)
+                    .map_err(|e| anyhow::anyhow!("Failed to save theme: {}", e))?;
+                None
+            }
+            
+            WriteJob::DeleteTheme { theme_id } => {
+                delete_theme_in(conn, theme_id)
+                    .map_err(|e| anyhow::anyhow!("Failed to delete theme: {}", e))?;
+                None
+            }
+            
+            WriteJob::UpdateThemePositions { updates } => {
+                update_theme_positions_in(conn, updates)
+                    .map_err(|e| anyhow::anyhow!("Failed to update theme positions: {}", e))?;
+                None
+            }
+            
+            WriteJob::SaveTask { task } => {
+                save_task_in(conn, task)
+                    .map_err(|e| anyhow::anyhow!("Failed to save task: {}", e))?;
+                None
+            }
+            
+            WriteJob::DeleteTask { task_id } => {
+                delete_task_in(conn, task_id)
+                    .map_err(|e| anyhow::anyhow!("Failed to delete task: {}", e))?;
+                None
+            }
+            
+            WriteJob::SaveTaskExecution { execution } => {
+                save_task_execution_in(conn, execution)
+                    .map_err(|e| anyhow::anyhow!("Failed to save task execution: {}", e))?;
+                None
+            }
+            
+            WriteJob::SetTaskPromptVersion { task_id, version } => {
+                set_task_prompt_version_in(conn, task_id, *version)
+                    .map_err(|e| anyhow::anyhow!("Failed to set task prompt version: {}", e))?;
+                None
+            }
+            
+            WriteJob::SaveAgent { agent } => {
+                save_agent_in(conn, agent, None)
+                    .map_err(|e| anyhow::anyhow!("Failed to save agent: {}", e))?;
+                None
+            }
+            
+            WriteJob::DeleteAgent { agent_id } => {
+                delete_agent_in(conn, agent_id)
+                    .map_err(|e| anyhow::anyhow!("Failed to delete agent: {}", e))?;
+                None
+            }
+            
+            WriteJob::RollbackAgentPrompt { agent_id, version } => {
+                rollback_agent_prompt_in(conn, agent_id, *version)
+                    .map_err(|e| anyhow::anyhow!("Failed to roll back agent prompt: {}", e))?;
+                None
+            }
+            
+            WriteJob::RecordSearchHits { target, ids } => {
+                record_search_hits_in(conn, *target, ids)
+                    .map_err(|e| anyhow::anyhow!("Failed to record search hits: {}", e))?;
+                None
             }
             
+            // 追加・更新系はバッチと同じ処理
+            _ => {
+                self.apply_batchable(conn, job)?;
+                None
+            }
+        };
+        
+        Ok(cleanup)
+    }
+
+    /// 追加・更新系ジョブを呼び出し側のトランザクション内で書き込む
+    fn apply_batchable(&self, conn: &rusqlite::Connection, job: &WriteJob) -> Result<()> {
+        match job {
+            WriteJob::UpsertEntity { entity_id, organization_id, payload } => {
+                self.upsert_entity(conn, entity_id, organization_id, payload)
+            }
+            WriteJob::UpsertRelation { relation_id, organization_id, payload } => {
+                self.upsert_relation(conn, relation_id, organization_id, payload)
+            }
+            WriteJob::UpsertTopic { topic_id, meeting_note_id, organization_id, payload } => {
+                self.upsert_topic(conn, topic_id, meeting_note_id, organization_id, payload)
+            }
+            WriteJob::UpsertOrganization { organization_id, payload } => {
+                self.upsert_organization(conn, organization_id, payload)
+            }
             WriteJob::UpdateChromaSyncStatus { entity_type, entity_id, synced, error } => {
-                self.update_chroma_sync_status(&conn, &entity_type, &entity_id, *synced, error.as_deref())?;
+                self.update_chroma_sync_status(conn, entity_type, entity_id, *synced, error.as_deref())
             }
+            _ => Err(anyhow::anyhow!("Job cannot be batched: {}", job.job_type())),
         }
-        
-        Ok(())
+    }
+
+    /// 削除した行に対応する埋め込みをベクトルストアから削除（失敗してもジョブは成功扱い）
+    async fn cleanup_embeddings(&self, cleanup: EmbeddingCleanup) {
+        let store = match &self.vector_store {
+            Some(store) => store,
+            None => return,
+        };
+        let org_id = cleanup.organization_id;
+
+        if cleanup.whole_organization {
+            if let Err(e) = store.delete_organization_collections(org_id.clone()).await {
+                eprintln!("[DB-WRITER] ⚠️ 組織コレクションの削除に失敗しました: organizationId={}, error={}", org_id, e);
+            }
+            return;
+        }
+
+        for id in cleanup.entity_ids {
+            if let Err(e) = store.delete_entity_embedding(id.clone(), org_id.clone()).await {
+                eprintln!("[DB-WRITER] ⚠️ エンティティ埋め込みの削除に失敗しました: id={}, error={}", id, e);
+            }
+        }
+        for id in cleanup.relation_ids {
+            if let Err(e) = store.delete_relation_embedding(id.clone(), org_id.clone()).await {
+                eprintln!("[DB-WRITER] ⚠️ リレーション埋め込みの削除に失敗しました: id={}, error={}", id, e);
+            }
+        }
+        for id in cleanup.topic_ids {
+            if let Err(e) = store.delete_topic_embedding(id.clone(), org_id.clone()).await {
+                eprintln!("[DB-WRITER] ⚠️ トピック埋め込みの削除に失敗しました: id={}, error={}", id, e);
+            }
+        }
+    }
+
+    /// 議事録に紐づくトピックIDとリレーションIDを取得
+    fn collect_meeting_note_children(
+        &self,
+        conn: &rusqlite::Connection,
+        meeting_note_id: &str,
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        let topic_ids = {
+            let mut stmt = conn.prepare("SELECT id FROM topics WHERE meetingNoteId = ?1")?;
+            let rows = stmt.query_map(params![meeting_note_id], |row| row.get::<_, String>(0))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        let relation_ids = {
+            let mut stmt = conn.prepare(
+                "SELECT id FROM relations WHERE topicId IN (SELECT id FROM topics WHERE meetingNoteId = ?1)"
+            )?;
+            let rows = stmt.query_map(params![meeting_note_id], |row| row.get::<_, String>(0))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        Ok((topic_ids, relation_ids))
     }
 
     fn upsert_entity(
@@ -90,8 +756,6 @@ impl WriteWorker {
         organization_id: &str,
         payload: &HashMap<String, Value>,
     ) -> Result<()> {
-        let tx = conn.unchecked_transaction()?;
-        
         let name = payload.get("name").and_then(|v| v.as_str()).unwrap_or("");
         let entity_type = payload.get("type").and_then(|v| v.as_str()).unwrap_or("");
         let aliases_json = payload.get("aliases")
@@ -108,9 +772,10 @@ impl WriteWorker {
         let org_id = if company_id.is_some() { None } else { Some(organization_id) };
         
         // entitiesテーブルに挿入/更新（ChromaDB同期状態を0に設定）
-        tx.execute(
+        let before = snapshot_row(conn, "entities", entity_id)?;
+        conn.execute(
             r#"INSERT INTO entities (id, name, type, aliases, metadata, organizationId, companyId, chromaSynced, createdAt, updatedAt)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, datetime('now'), datetime('now'))
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
                ON CONFLICT(id) DO UPDATE SET
                    name = excluded.name,
                    type = excluded.type,
@@ -119,36 +784,46 @@ impl WriteWorker {
                    organizationId = excluded.organizationId,
                    companyId = excluded.companyId,
                    chromaSynced = 0,
-                   updatedAt = datetime('now')"#,
+                   updatedAt = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')"#,
             params![entity_id, name, entity_type, aliases_json, metadata_json, org_id, company_id],
         )?;
+        let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+        audit_change(conn, action, "entities", entity_id, before)?;
 
-        tx.commit()?;
         Ok(())
     }
 
-    fn delete_entities(
+    /// 組織に属する行を1件ずつゴミ箱に移動（他の組織の行は対象外）
+    fn trash_rows(
         &self,
         conn: &rusqlite::Connection,
-        entity_ids: &[String],
+        table: &str,
+        ids: &[String],
         organization_id: &str,
     ) -> Result<()> {
-        let tx = conn.unchecked_transaction()?;
-        
-        {
-            let mut stmt = tx.prepare(
-                r#"DELETE FROM entities WHERE id = ?1 AND organizationId = ?2"#
+        for id in ids {
+            let belongs: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE id = ?1 AND organizationId = ?2", table),
+                params![id, organization_id],
+                |row| row.get(0),
             )?;
-            
-            for id in entity_ids {
-                stmt.execute(params![id, organization_id])?;
+            if belongs > 0 {
+                trash_item_in(conn, table, id)
+                    .map_err(|e| anyhow::anyhow!("Failed to move {} to trash: {}", table, e))?;
             }
-        } // stmtのスコープを終了
-        
-        tx.commit()?;
+        }
         Ok(())
     }
 
+    fn delete_entities(
+        &self,
+        conn: &rusqlite::Connection,
+        entity_ids: &[String],
+        organization_id: &str,
+    ) -> Result<()> {
+        self.trash_rows(conn, "entities", entity_ids, organization_id)
+    }
+
     fn upsert_relation(
         &self,
         conn: &rusqlite::Connection,
@@ -156,8 +831,6 @@ impl WriteWorker {
         organization_id: &str,
         payload: &HashMap<String, Value>,
     ) -> Result<()> {
-        let tx = conn.unchecked_transaction()?;
-        
         let topic_id = payload.get("topicId").and_then(|v| v.as_str()).unwrap_or("");
         let source_entity_id = payload.get("sourceEntityId").and_then(|v| v.as_str());
         let target_entity_id = payload.get("targetEntityId").and_then(|v| v.as_str());
@@ -175,9 +848,10 @@ impl WriteWorker {
         let org_id = if company_id.is_some() { None } else { Some(organization_id) };
         
         // relationsテーブルに挿入/更新（topicRelationsからリネーム済み、ChromaDB同期状態を0に設定）
-        tx.execute(
+        let before = snapshot_row(conn, "relations", relation_id)?;
+        conn.execute(
             r#"INSERT INTO relations (id, topicId, sourceEntityId, targetEntityId, relationType, description, confidence, metadata, organizationId, companyId, chromaSynced, createdAt, updatedAt)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 0, datetime('now'), datetime('now'))
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 0, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
                ON CONFLICT(id) DO UPDATE SET
                    topicId = excluded.topicId,
                    sourceEntityId = excluded.sourceEntityId,
@@ -189,11 +863,12 @@ impl WriteWorker {
                    organizationId = excluded.organizationId,
                    companyId = excluded.companyId,
                    chromaSynced = 0,
-                   updatedAt = datetime('now')"#,
+                   updatedAt = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')"#,
             params![relation_id, topic_id, source_entity_id, target_entity_id, relation_type, description, confidence, metadata_json, org_id, company_id],
         )?;
+        let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+        audit_change(conn, action, "relations", relation_id, before)?;
 
-        tx.commit()?;
         Ok(())
     }
 
@@ -203,20 +878,7 @@ impl WriteWorker {
         relation_ids: &[String],
         organization_id: &str,
     ) -> Result<()> {
-        let tx = conn.unchecked_transaction()?;
-        
-        {
-            let mut stmt = tx.prepare(
-                r#"DELETE FROM relations WHERE id = ?1 AND organizationId = ?2"#
-            )?;
-            
-            for id in relation_ids {
-                stmt.execute(params![id, organization_id])?;
-            }
-        } // stmtのスコープを終了
-        
-        tx.commit()?;
-        Ok(())
+        self.trash_rows(conn, "relations", relation_ids, organization_id)
     }
 
     fn upsert_topic(
@@ -227,8 +889,6 @@ impl WriteWorker {
         organization_id: &str,
         payload: &HashMap<String, Value>,
     ) -> Result<()> {
-        let tx = conn.unchecked_transaction()?;
-        
         // topicsテーブルに挿入/更新（topicEmbeddingsから統合済み、ChromaDB同期状態を0に設定）
         let title = payload.get("title").and_then(|v| v.as_str()).unwrap_or("");
         let description = payload.get("description").and_then(|v| v.as_str());
@@ -247,9 +907,10 @@ impl WriteWorker {
         // organizationIdとcompanyIdのどちらか一方が設定されていることを確認
         let org_id = if company_id.is_some() { None } else { Some(organization_id) };
         
-        tx.execute(
+        let before = snapshot_row(conn, "topics", topic_id)?;
+        conn.execute(
             r#"INSERT INTO topics (id, topicId, meetingNoteId, organizationId, companyId, title, description, content, semanticCategory, keywords, tags, chromaSynced, createdAt, updatedAt)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 0, datetime('now'), datetime('now'))
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 0, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
                ON CONFLICT(id) DO UPDATE SET
                    title = excluded.title,
                    description = excluded.description,
@@ -260,11 +921,12 @@ impl WriteWorker {
                    organizationId = excluded.organizationId,
                    companyId = excluded.companyId,
                    chromaSynced = 0,
-                   updatedAt = datetime('now')"#,
+                   updatedAt = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')"#,
             params![topic_id, topic_id, meeting_note_id, org_id, company_id, title, description, content, semantic_category, keywords_json, tags_json],
         )?;
-        
-        tx.commit()?;
+        let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+        audit_change(conn, action, "topics", topic_id, before)?;
+
         Ok(())
     }
 
@@ -274,20 +936,7 @@ impl WriteWorker {
         topic_ids: &[String],
         organization_id: &str,
     ) -> Result<()> {
-        let tx = conn.unchecked_transaction()?;
-        
-        {
-            let mut stmt = tx.prepare(
-                r#"DELETE FROM topics WHERE id = ?1 AND organizationId = ?2"#
-            )?;
-            
-            for id in topic_ids {
-                stmt.execute(params![id, organization_id])?;
-            }
-        } // stmtのスコープを終了
-        
-        tx.commit()?;
-        Ok(())
+        self.trash_rows(conn, "topics", topic_ids, organization_id)
     }
 
     fn upsert_organization(
@@ -296,8 +945,6 @@ impl WriteWorker {
         organization_id: &str,
         payload: &HashMap<String, Value>,
     ) -> Result<()> {
-        let tx = conn.unchecked_transaction()?;
-        
         let name = payload.get("name").and_then(|v| v.as_str()).unwrap_or("");
         let parent_id = payload.get("parentId").and_then(|v| v.as_str());
         let title = payload.get("title").and_then(|v| v.as_str());
@@ -306,9 +953,10 @@ impl WriteWorker {
         let level_name = payload.get("levelName").and_then(|v| v.as_str()).unwrap_or("");
         let position = payload.get("position").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
         
-        tx.execute(
+        let before = snapshot_row(conn, "organizations", organization_id)?;
+        conn.execute(
             r#"INSERT INTO organizations (id, parentId, name, title, description, level, levelName, position, createdAt, updatedAt)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, datetime('now'), datetime('now'))
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
                ON CONFLICT(id) DO UPDATE SET
                    parentId = excluded.parentId,
                    name = excluded.name,
@@ -317,39 +965,55 @@ impl WriteWorker {
                    level = excluded.level,
                    levelName = excluded.levelName,
                    position = excluded.position,
-                   updatedAt = datetime('now')"#,
+                   updatedAt = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')"#,
             params![organization_id, parent_id, name, title, description, level, level_name, position],
         )?;
+        let action = if before.is_some() { AuditAction::Update } else { AuditAction::Create };
+        audit_change(conn, action, "organizations", organization_id, before)?;
 
-        tx.commit()?;
         Ok(())
     }
 
     fn delete_organization(
         &self,
-        _conn: &rusqlite::Connection,
+        conn: &rusqlite::Connection,
         organization_id: &str,
     ) -> Result<()> {
-        // 専用のdelete_organization関数を使用（関連データも一緒に削除）
-        use crate::database::delete_organization;
-        delete_organization(organization_id)
-            .map_err(|e| anyhow::anyhow!("Failed to delete organization: {}", e))?;
+        // 関連データも一緒にゴミ箱に移動（既に削除されている場合は成功として扱う）
+        if trash_organization_in(conn, organization_id)
+            .map_err(|e| anyhow::anyhow!("Failed to delete organization: {}", e))?
+            .is_none()
+        {
+            eprintln!("[DB-WRITER] ⚠️ 組織が存在しません: id={}", organization_id);
+        }
         Ok(())
     }
 
     fn delete_meeting_note(
         &self,
-        _conn: &rusqlite::Connection,
+        conn: &rusqlite::Connection,
         meeting_note_id: &str,
         _organization_id: &str,
     ) -> Result<()> {
-        // 既存のdelete_meeting_note_with_relations関数を呼び出す
-        use crate::database::delete_meeting_note_with_relations;
-        delete_meeting_note_with_relations(meeting_note_id)
-            .map_err(|e| anyhow::anyhow!("Failed to delete meeting note: {}", e))?;
+        // 関連するトピック・リレーションも一緒にゴミ箱に移動
+        trash_meeting_note_in(conn, meeting_note_id)
+            .map_err(|e| anyhow::anyhow!("Failed to delete meeting note: {}", e))?
+            .ok_or_else(|| anyhow::anyhow!("Failed to delete meeting note: 議事録 '{}' が存在しません", meeting_note_id))?;
         Ok(())
     }
 
+    /// ドキュメントを保存（set_docと同じ処理。カラムの変換もset_docに合わせる）
+    fn set_document(&self, conn: &rusqlite::Connection, table: &str, id: &str, payload: &HashMap<String, Value>) -> Result<()> {
+        set_doc_in(conn, table, id, payload.clone())
+            .map_err(|e| anyhow::anyhow!("Failed to save {}: {}", table, e))
+    }
+
+    /// ドキュメントの指定したフィールドのみ更新（update_docと同じ処理）
+    fn update_document(&self, conn: &rusqlite::Connection, table: &str, id: &str, payload: &HashMap<String, Value>) -> Result<()> {
+        update_doc_in(conn, table, id, payload.clone())
+            .map_err(|e| anyhow::anyhow!("Failed to update {}: {}", table, e))
+    }
+
     fn update_chroma_sync_status(
         &self,
         conn: &rusqlite::Connection,
@@ -358,13 +1022,12 @@ impl WriteWorker {
         synced: bool,
         error: Option<&str>,
     ) -> Result<()> {
-        let tx = conn.unchecked_transaction()?;
         use crate::database::get_timestamp;
         let now = get_timestamp();
         
         match entity_type {
             "entity" => {
-                tx.execute(
+                conn.execute(
                     r#"UPDATE entities 
                        SET chromaSynced = ?1, 
                            chromaSyncError = ?2, 
@@ -375,7 +1038,7 @@ impl WriteWorker {
                 )?;
             }
             "relation" => {
-                tx.execute(
+                conn.execute(
                     r#"UPDATE relations 
                        SET chromaSynced = ?1, 
                            chromaSyncError = ?2, 
@@ -386,7 +1049,7 @@ impl WriteWorker {
                 )?;
             }
             "topic" => {
-                tx.execute(
+                conn.execute(
                     r#"UPDATE topics 
                        SET chromaSynced = ?1, 
                            chromaSyncError = ?2, 
@@ -397,7 +1060,7 @@ impl WriteWorker {
                 )?;
             }
             "meetingNote" => {
-                tx.execute(
+                conn.execute(
                     r#"UPDATE meetingNotes 
                        SET chromaSynced = ?1, 
                            chromaSyncError = ?2, 
@@ -411,8 +1074,216 @@ impl WriteWorker {
                 return Err(anyhow::anyhow!("Unknown entity type: {}", entity_type));
             }
         }
-        
-        tx.commit()?;
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::init_test_database;
+    use crate::db::outbox::discard_dead_letter_job;
+    use crate::db::WriteQueueState;
+    use rusqlite::OptionalExtension;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn user(uid: &str) -> User {
+        User { uid: uid.to_string(), email: format!("{}@example.com", uid), email_verified: true }
+    }
+
+    fn upsert_entity(outbox_id: &str, entity_id: &str, name: &str, actor: Option<User>) -> QueuedWriteJob {
+        QueuedWriteJob {
+            job: WriteJob::UpsertEntity {
+                entity_id: entity_id.to_string(),
+                organization_id: "org1".to_string(),
+                payload: HashMap::from([("name".to_string(), json!(name))]),
+            },
+            actor,
+            outbox_id: Some(outbox_id.to_string()),
+            reply: None,
+        }
+    }
+
+    fn delete_entities(outbox_id: &str, entity_id: &str) -> QueuedWriteJob {
+        QueuedWriteJob {
+            job: WriteJob::DeleteEntities {
+                entity_ids: vec![entity_id.to_string()],
+                organization_id: "org1".to_string(),
+            },
+            actor: None,
+            outbox_id: Some(outbox_id.to_string()),
+            reply: None,
+        }
+    }
+
+    fn outbox_ids(job: &PendingJob) -> Vec<&str> {
+        job.outbox_ids.iter().map(String::as_str).collect()
+    }
+
+    fn payload_name(job: &PendingJob) -> Option<&Value> {
+        match &job.job {
+            WriteJob::UpsertEntity { payload, .. } => payload.get("name"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn merges_writes_to_the_same_row_with_the_last_payload() {
+        let (reply_tx, _reply_rx) = oneshot::channel();
+        let mut second = upsert_entity("o2", "e1", "v2", None);
+        second.reply = Some(reply_tx);
+
+        let segments = coalesce(vec![
+            upsert_entity("o1", "e1", "v1", None),
+            upsert_entity("o3", "e2", "other", None),
+            second,
+        ]);
+
+        assert_eq!(segments.len(), 1);
+        let Segment::Batch(batch) = &segments[0] else { panic!("expected a batch") };
+        assert_eq!(batch.len(), 2);
+        assert_eq!(outbox_ids(&batch[0]), vec!["o1", "o2"]);
+        assert_eq!(batch[0].len(), 2);
+        assert_eq!(batch[0].replies.len(), 1);
+        assert_eq!(payload_name(&batch[0]), Some(&json!("v2")));
+        assert_eq!(outbox_ids(&batch[1]), vec!["o3"]);
+    }
+
+    #[test]
+    fn does_not_merge_across_single_jobs() {
+        let segments = coalesce(vec![
+            upsert_entity("o1", "e1", "v1", None),
+            delete_entities("o2", "e1"),
+            upsert_entity("o3", "e1", "v2", None),
+            upsert_entity("o4", "e1", "v3", None),
+        ]);
+
+        assert_eq!(segments.len(), 3);
+        match &segments[..] {
+            [Segment::Batch(before), Segment::Single(single), Segment::Batch(after)] => {
+                assert_eq!(before.len(), 1);
+                assert_eq!(outbox_ids(&before[0]), vec!["o1"]);
+                assert_eq!(payload_name(&before[0]), Some(&json!("v1")));
+                assert_eq!(outbox_ids(single), vec!["o2"]);
+                assert_eq!(after.len(), 1);
+                assert_eq!(outbox_ids(&after[0]), vec!["o3", "o4"]);
+                assert_eq!(payload_name(&after[0]), Some(&json!("v3")));
+            }
+            _ => panic!("unexpected segments"),
+        }
+    }
+
+    #[test]
+    fn keeps_consecutive_single_jobs_in_order() {
+        let segments = coalesce(vec![delete_entities("o1", "e1"), delete_entities("o2", "e2")]);
+        let ids: Vec<Vec<&str>> = segments.iter().map(|segment| match segment {
+            Segment::Single(job) => outbox_ids(job),
+            Segment::Batch(_) => panic!("expected single jobs"),
+        }).collect();
+        assert_eq!(ids, vec![vec!["o1"], vec!["o2"]]);
+    }
+
+    #[test]
+    fn does_not_merge_jobs_from_different_actors() {
+        let segments = coalesce(vec![
+            upsert_entity("o1", "e1", "v1", Some(user("alice"))),
+            upsert_entity("o2", "e1", "v2", Some(user("bob"))),
+            upsert_entity("o3", "e1", "v3", Some(user("bob"))),
+        ]);
+
+        let Segment::Batch(batch) = &segments[0] else { panic!("expected a batch") };
+        assert_eq!(segments.len(), 1);
+        assert_eq!(batch.len(), 2);
+        assert_eq!(outbox_ids(&batch[0]), vec!["o1"]);
+        assert_eq!(outbox_ids(&batch[1]), vec!["o2", "o3"]);
+    }
+
+    fn sync_status(entity_type: &str, entity_id: &str) -> WriteJob {
+        WriteJob::UpdateChromaSyncStatus {
+            entity_type: entity_type.to_string(),
+            entity_id: entity_id.to_string(),
+            synced: true,
+            error: None,
+        }
+    }
+
+    /// payload に marker を含むアウトボックス・デッドレターの件数
+    fn count_rows(table: &str, marker: &str) -> i64 {
+        let conn = init_test_database().get_connection().unwrap();
+        conn.query_row(
+            &format!("SELECT COUNT(*) FROM {} WHERE payload LIKE ?1", table),
+            [format!("%{}%", marker)],
+            |row| row.get(0),
+        ).unwrap()
+    }
+
+    fn dead_letter_id(marker: &str) -> Option<(String, i64)> {
+        let conn = init_test_database().get_connection().unwrap();
+        conn.query_row(
+            "SELECT id, attempts FROM writeDeadLetters WHERE payload LIKE ?1",
+            [format!("%{}%", marker)],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional().unwrap()
+    }
+
+    // アウトボックスを読み書きするテストは並行して実行すると再投入で干渉するため、1つのテストにまとめる
+    #[tokio::test]
+    async fn saves_replays_and_dead_letters_jobs() {
+        let db = init_test_database();
+        let (tx, rx) = async_channel::unbounded::<QueuedWriteJob>();
+        let queue = WriteQueueState { tx: Arc::new(tx) };
+        let worker = WriteWorker::new(db.get_pool());
+        let handle = tokio::spawn(async move { worker.run(rx).await });
+
+        // 処理が完了したジョブはアウトボックスに残らない
+        let done = Uuid::new_v4().to_string();
+        queue.send(sync_status("entity", &done), None).await.unwrap();
+        assert_eq!(count_rows("writeOutbox", &done), 0);
+
+        // 前回の起動で処理されなかったジョブを再投入する
+        let left_over = Uuid::new_v4().to_string();
+        {
+            let conn = db.get_connection().unwrap();
+            save_to_outbox_in(&conn, &sync_status("entity", &left_over), None, Timestamp::now()).unwrap();
+        }
+        assert_eq!(queue.replay_outbox().unwrap(), 1);
+        for _ in 0..50 {
+            if count_rows("writeOutbox", &left_over) == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(count_rows("writeOutbox", &left_over), 0);
+
+        // 失敗し続けるジョブはリトライの後デッドレターに移る。リトライを待っている間も後続のジョブは処理される
+        let failing = Uuid::new_v4().to_string();
+        let failing_send = tokio::spawn({
+            let queue = queue.clone();
+            let job = sync_status("unknown", &failing);
+            async move { queue.send(job, None).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let next = Uuid::new_v4().to_string();
+        tokio::time::timeout(
+            Duration::from_millis(RETRY_DELAY_MS / 2),
+            queue.send(sync_status("entity", &next), None),
+        ).await.expect("後続のジョブがリトライの待ち時間に止められました").unwrap();
+
+        assert!(failing_send.await.unwrap().is_err());
+        assert_eq!(count_rows("writeOutbox", &failing), 0);
+        let (dead_letter, attempts) = dead_letter_id(&failing).unwrap();
+        assert_eq!(attempts, MAX_ATTEMPTS as i64);
+
+        // デッドレターを再実行しても失敗した場合はデッドレターに戻り、破棄すると削除される
+        assert!(queue.retry_dead_letter(&dead_letter).await.is_err());
+        assert_eq!(dead_letter_id(&failing).map(|(id, _)| id), Some(dead_letter.clone()));
+        assert!(discard_dead_letter_job(&dead_letter).unwrap());
+        assert!(dead_letter_id(&failing).is_none());
+        assert!(queue.retry_dead_letter(&dead_letter).await.is_err());
+
+        drop(queue);
+        handle.await.unwrap();
+    }
+}
@@ -8,7 +8,7 @@ mod db;
 
 use std::net::SocketAddr;
 use tauri::Manager;
-use db::{WriteJob, WriteWorker, WriteQueueState};
+use db::{QueuedWriteJob, WriteBatchConfig, WriteWorker, WriteQueueState};
 
 fn main() {
     // ログシステムの初期化（リリースビルドではINFOレベル）
@@ -22,6 +22,8 @@ fn main() {
         .init();
     
     tauri::Builder::default()
+        // サインイン中のセッショントークン（ユーザーはsessionsテーブルから解決する）
+        .manage(database::SessionState::default())
         .setup(|app| {
             // 開発環境でのみ環境変数ファイルを読み込む
             #[cfg(debug_assertions)]
@@ -54,19 +56,54 @@ fn main() {
                     // 書き込みワーカーを起動
                     if let Some(db) = database::get_db() {
                         let pool = db.get_pool();
-                        let (write_tx, write_rx) = async_channel::unbounded::<WriteJob>();
+                        let (write_tx, write_rx) = async_channel::unbounded::<QueuedWriteJob>();
                         let write_tx_arc = std::sync::Arc::new(write_tx);
-                        let write_worker = WriteWorker::new(pool);
+                        let mut write_worker = WriteWorker::new(pool)
+                            .with_batch_config(WriteBatchConfig::from_env());
+                        if let Ok(vector_store) = database::vector_store::get_vector_store() {
+                            write_worker = write_worker.with_vector_store(vector_store);
+                        }
                         
                         // 書き込みワーカーを起動
                         tauri::async_runtime::spawn(async move {
                             write_worker.run(write_rx).await;
                         });
                         
-                        // 書き込みキューをアプリの状態として保存
-                        app.manage(WriteQueueState {
+                        let write_queue = WriteQueueState {
                             tx: write_tx_arc,
-                        });
+                        };
+                        
+                        // 前回の起動で処理されなかった書き込みジョブを再投入
+                        match write_queue.replay_outbox() {
+                            Ok(0) => {}
+                            Ok(count) => eprintln!("🔁 未処理の書き込みジョブを再投入しました: {}件", count),
+                            Err(e) => eprintln!("⚠️  未処理の書き込みジョブの再投入に失敗しました: {}", e),
+                        }
+                        
+                        // 未同期の埋め込みを再同期するワーカーを起動
+                        database::start_chroma_sync_worker(app.handle().clone(), write_queue.clone());
+                        
+                        // 保持期間を過ぎたゴミ箱の項目を完全削除するワーカーを起動
+                        database::start_trash_purge_worker();
+                        
+                        // 変更をTauriイベント・REST APIで通知する変更フィードを起動
+                        database::start_change_feed_worker(app.handle().clone());
+                        
+                        // タスクをRust側で実行するタスク実行エンジンを起動
+                        database::start_task_executor(app.handle().clone(), write_queue.clone());
+                        
+                        // タスクチェーンをRust側で実行する実行エンジンを起動（中断した実行は再開する）
+                        database::start_task_chain_runner(app.handle().clone(), write_queue.clone());
+                        
+                        // cron式・指定日時・データの変更でタスクを実行するスケジューラーを起動
+                        database::start_scheduler(app.handle().clone(), write_queue.clone());
+                        
+                        // A2Aメッセージの返信期限を確認するワーカーを起動
+                        database::start_a2a_timeout_worker(app.handle().clone());
+                        
+                        // 書き込みキューをアプリの状態として保存（APIサーバーからも使えるよう登録）
+                        db::register_write_queue(write_queue.clone());
+                        app.manage(write_queue);
                         
                         #[cfg(debug_assertions)]
                         eprintln!("✅ 書き込みワーカーを起動しました");
@@ -98,6 +135,7 @@ fn main() {
                         eprintln!("   2. ChromaDBがインストールされているか確認してください: pip3 install chromadb");
                         eprintln!("   3. ポート8000が使用可能か確認してください");
                         eprintln!("   4. アプリケーションを再起動してください");
+                        eprintln!("   5. Pythonを使用しない場合は環境変数 VECTOR_BACKEND=local を設定してください");
                     }
                 }
             });
@@ -148,6 +186,7 @@ fn main() {
             commands::db::collection_get,
             // クエリ操作コマンド
             commands::db::query_get,
+            commands::db::changes_since,
             // データエクスポート/インポートコマンド
             commands::db::export_database_data,
             commands::db::import_database_data,
@@ -162,6 +201,7 @@ fn main() {
             commands::app::list_tables,
             commands::app::diagnose_database,
             commands::app::get_table_schema,
+            commands::app::get_schema_version,
             commands::app::update_chroma_sync_status,
             // 組織管理コマンド
             commands::organization::create_org,
@@ -180,24 +220,26 @@ fn main() {
             commands::organization::update_theme_positions_cmd,
             commands::organization::get_themes_cmd,
             commands::organization::get_deletion_targets_cmd,
-            // 事業会社管理コマンドは削除（事業会社ページ削除のため）
-            // commands::companies::create_company_cmd,
-            // commands::companies::update_company_cmd,
-            // commands::companies::get_company,
-            // commands::companies::get_company_by_code_cmd,
-            // commands::companies::get_companies_by_org,
-            // commands::companies::get_all_companies_cmd,
-            // commands::companies::delete_company_cmd,
-            // 組織と事業会社の表示関係管理コマンドは削除（事業会社ページ削除のため）
-            // commands::organization_company_display::create_org_company_display,
-            // commands::organization_company_display::get_companies_by_org_display,
-            // commands::organization_company_display::get_organizations_by_company_display_cmd,
-            // commands::organization_company_display::get_all_org_company_displays,
-            // commands::organization_company_display::update_org_company_display_order,
-            // commands::organization_company_display::delete_org_company_display,
-            // commands::organization_company_display::delete_org_company_display_by_ids,
-            // commands::organization_company_display::delete_all_org_company_displays_by_org,
-            // commands::organization_company_display::delete_all_org_company_displays_by_company,
+            // 事業会社管理コマンド（organizationsテーブルのtype='company'）
+            commands::companies::create_company_cmd,
+            commands::companies::update_company_cmd,
+            commands::companies::get_company,
+            commands::companies::get_company_by_code_cmd,
+            commands::companies::get_companies_by_org,
+            commands::companies::get_all_companies_cmd,
+            commands::companies::update_company_positions_cmd,
+            commands::companies::delete_company_cmd,
+            commands::companies::export_companies_csv,
+            // 組織と事業会社の表示関係管理コマンド
+            commands::organization_company_display::create_org_company_display,
+            commands::organization_company_display::get_companies_by_org_display,
+            commands::organization_company_display::get_organizations_by_company_display_cmd,
+            commands::organization_company_display::get_all_org_company_displays,
+            commands::organization_company_display::update_org_company_display_order,
+            commands::organization_company_display::delete_org_company_display,
+            commands::organization_company_display::delete_org_company_display_by_ids,
+            commands::organization_company_display::delete_all_org_company_displays_by_org,
+            commands::organization_company_display::delete_all_org_company_displays_by_company,
             // ChromaDBコマンド
             commands::chromadb::chromadb_save_entity_embedding,
             commands::chromadb::chromadb_get_entity_embedding,
@@ -218,6 +260,13 @@ fn main() {
             commands::chromadb::chromadb_delete_relation_embedding,
             commands::chromadb::chromadb_clear_data_dir,
             commands::chromadb::chromadb_delete_organization_collections,
+            commands::chromadb::chromadb_get_sync_status,
+            commands::chromadb::chromadb_trigger_sync,
+            // ハイブリッド検索コマンド
+            commands::search::hybrid_search,
+            commands::search::rebuild_search_index,
+            commands::embedding::generate_embedding,
+            commands::embedding::check_embedding_model,
             // システム設計ドキュメントセクション管理コマンド
             commands::design_doc::create_design_doc_section_cmd,
             commands::design_doc::update_design_doc_section_cmd,
@@ -232,6 +281,36 @@ fn main() {
             commands::design_doc::get_design_doc_section_relations_by_section_cmd,
             commands::design_doc::get_all_design_doc_section_relations_cmd,
             commands::design_doc::delete_design_doc_section_relation_cmd,
+            // APIトークン管理コマンド
+            commands::api_tokens::create_api_token_cmd,
+            commands::api_tokens::list_api_tokens_cmd,
+            commands::api_tokens::revoke_api_token_cmd,
+            commands::users::get_current_user_role,
+            commands::users::list_approval_requests_cmd,
+            commands::users::approve_user_cmd,
+            commands::users::reject_user_cmd,
+            commands::users::list_users_cmd,
+            commands::users::update_user_role_cmd,
+            commands::audit_log::query_audit_log_cmd,
+            commands::audit_log::export_audit_log_csv,
+            commands::trash::list_trash_cmd,
+            commands::trash::restore_trash_item_cmd,
+            commands::trash::purge_trash_item_cmd,
+            commands::write_queue::submit_write_jobs_cmd,
+            commands::write_queue::get_write_queue_metrics_cmd,
+            commands::write_queue::list_dead_letter_jobs_cmd,
+            commands::write_queue::retry_dead_letter_job_cmd,
+            commands::write_queue::discard_dead_letter_job_cmd,
+            // 事業計画ファイル管理コマンド
+            commands::business_plan::attach_business_plan_file_cmd,
+            commands::business_plan::get_business_plan_file_cmd,
+            commands::business_plan::get_business_plan_files_cmd,
+            commands::business_plan::delete_business_plan_file_cmd,
+            commands::business_plan::permanently_delete_business_plan_file_cmd,
+            commands::business_plan::register_business_plan_id_cmd,
+            commands::business_plan::get_business_plan_id_info_cmd,
+            commands::business_plan::record_business_plan_history_cmd,
+            commands::business_plan::get_business_plan_history_cmd,
             // ファイル操作コマンド
             commands::fs::read_file,
             commands::fs::write_file,
@@ -249,14 +328,40 @@ fn main() {
             commands::agent_system::get_task_execution_command,
             commands::agent_system::get_task_executions_command,
             commands::agent_system::get_all_task_executions_command,
+            commands::agent_system::enqueue_task_execution_command,
+            commands::agent_system::cancel_task_execution_command,
             commands::agent_system::save_task_chain_command,
             commands::agent_system::get_task_chain_command,
             commands::agent_system::get_all_task_chains_command,
             commands::agent_system::delete_task_chain_command,
+            commands::agent_system::validate_task_chain_command,
+            commands::agent_system::run_task_chain_command,
+            commands::agent_system::cancel_task_chain_run_command,
+            commands::agent_system::get_task_chain_run_command,
+            commands::agent_system::get_task_chain_runs_command,
+            commands::agent_system::get_task_chain_run_steps_command,
+            commands::agent_system::save_schedule_command,
+            commands::agent_system::get_schedule_command,
+            commands::agent_system::get_all_schedules_command,
+            commands::agent_system::delete_schedule_command,
+            commands::agent_system::get_schedule_runs_command,
+            commands::agent_system::run_schedule_now_command,
+            commands::agent_system::send_a2a_message_command,
+            commands::agent_system::reply_a2a_message_command,
+            commands::agent_system::get_a2a_message_command,
+            commands::agent_system::get_a2a_inbox_command,
+            commands::agent_system::receive_a2a_messages_command,
+            commands::agent_system::wait_for_a2a_response_command,
+            commands::agent_system::get_a2a_history_command,
             commands::agent_system::save_agent_command,
             commands::agent_system::get_agent_command,
             commands::agent_system::get_all_agents_command,
             commands::agent_system::delete_agent_command,
+            commands::agent_system::get_agent_prompt_versions_command,
+            commands::agent_system::get_agent_prompt_version_command,
+            commands::agent_system::diff_agent_prompt_versions_command,
+            commands::agent_system::rollback_agent_prompt_command,
+            commands::agent_system::set_task_prompt_version_command,
             commands::agent_system::save_mcp_tool_command,
             commands::agent_system::get_mcp_tool_command,
             commands::agent_system::get_all_mcp_tools_command,